@@ -0,0 +1,130 @@
+//! Benchmarks for the parse and compute paths over a synthetic year of
+//! five-minute data. Run with `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use energy_analysis::{
+    compute::Compute,
+    config::WriteOptions,
+    convert::{self, CsvFormat, OnError, TotalCheck},
+    store::Store,
+    testdata::SyntheticData,
+};
+use std::path::PathBuf;
+
+struct Dataset {
+    dir: PathBuf,
+}
+
+impl Dataset {
+    fn year() -> Self {
+        let dir =
+            std::env::temp_dir().join(format!("energy_analysis_bench_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data = SyntheticData {
+            days: 365,
+            ..Default::default()
+        };
+        let dataset = Self { dir };
+        data.write_price_csv(&dataset.path("price_raw.csv"))
+            .unwrap();
+        data.write_gen_csv(&dataset.path("gen_raw.csv")).unwrap();
+        convert::convert_energy_price_csv(
+            &[dataset.path("price_raw.csv")],
+            &dataset.path("prices.csv"),
+            CsvFormat::default(),
+            OnError::Abort,
+            WriteOptions::default(),
+        )
+        .unwrap();
+        convert::convert_energy_gen_csv(
+            &[dataset.path("gen_raw.csv")],
+            &dataset.path("gen.csv"),
+            CsvFormat::default(),
+            TotalCheck::Report,
+            OnError::Abort,
+            WriteOptions::default(),
+        )
+        .unwrap();
+        dataset
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.dir.join(file)
+    }
+}
+
+impl Drop for Dataset {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn parse(c: &mut Criterion) {
+    let dataset = Dataset::year();
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(20);
+    group.bench_function("price", |b| {
+        b.iter(|| {
+            convert::convert_energy_price_csv(
+                &[dataset.path("price_raw.csv")],
+                &dataset.path("price_out.csv"),
+                CsvFormat::default(),
+                OnError::Abort,
+                WriteOptions::default(),
+            )
+            .unwrap()
+        })
+    });
+    group.bench_function("gen", |b| {
+        b.iter(|| {
+            convert::convert_energy_gen_csv(
+                &[dataset.path("gen_raw.csv")],
+                &dataset.path("gen_out.csv"),
+                CsvFormat::default(),
+                TotalCheck::Report,
+                OnError::Abort,
+                WriteOptions::default(),
+            )
+            .unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn compute(c: &mut Criterion) {
+    let dataset = Dataset::year();
+    let (prices, gen) = (dataset.path("prices.csv"), dataset.path("gen.csv"));
+    let mut group = c.benchmark_group("compute");
+    group.sample_size(20);
+    group.bench_function("average_price_5min", |b| {
+        b.iter(|| Compute::new(&prices).average_price_5min().unwrap())
+    });
+    group.bench_function("average_gen_5min", |b| {
+        b.iter(|| Compute::new(&gen).average_gen_5min().unwrap())
+    });
+    group.bench_function("average_value_5min", |b| {
+        b.iter(|| Compute::new(&prices).average_value_5min(&gen).unwrap())
+    });
+    group.finish();
+}
+
+fn store(c: &mut Criterion) {
+    let dataset = Dataset::year();
+    let (prices, gen) = (dataset.path("prices.bin"), dataset.path("gen.bin"));
+    Store::write_prices(
+        &dataset.path("prices.csv"),
+        &prices,
+        WriteOptions::default(),
+    )
+    .unwrap();
+    Store::write_gen(&dataset.path("gen.csv"), &gen, WriteOptions::default()).unwrap();
+    let mut group = c.benchmark_group("store");
+    group.sample_size(20);
+    group.bench_function("average_value_5min", |b| {
+        b.iter(|| Compute::new(&prices).average_value_5min(&gen).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse, compute, store);
+criterion_main!(benches);
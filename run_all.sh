@@ -2,7 +2,7 @@
 
 set -e
 
-cargo run --release parse-price-csv \
+cargo run --release parse-price-csv --overwrite \
   --caiso-csv \
   data/caiso_lmp_rt_5min_zones_2023Q4.csv \
   data/caiso_lmp_rt_5min_zones_2024Q1.csv \
@@ -10,7 +10,7 @@ cargo run --release parse-price-csv \
   data/caiso_lmp_rt_5min_zones_2024Q3.csv \
   --output-csv data/prices.csv
 
-cargo run --release parse-gen-csv \
+cargo run --release parse-gen-csv --overwrite \
   --caiso-csv \
   data/caiso_gen_all_5min_2023Q4.csv \
   data/caiso_gen_all_5min_2024Q1.csv \
@@ -18,22 +18,73 @@ cargo run --release parse-gen-csv \
   data/caiso_gen_all_5min_2024Q3.csv \
   --output-csv data/gen.csv
 
-cargo run --release write-price-minutes data/prices.csv results/prices_avg.csv
+cargo run --release analyze --overwrite price-minutes data/prices.csv --out-csv results/prices_avg.csv --out-png results/prices.png
 
-cargo run --release write-gen-minutes data/gen.csv results/gen_avg.csv
+cargo run --release analyze --overwrite price-components data/prices.csv --out-csv results/price_components.csv --out-png results/price_components.png
 
-cargo run --release write-gen-solar-battery data/gen.csv results/gen_solar_battery.csv
+cargo run --release analyze --overwrite gen-minutes data/gen.csv --out-csv results/gen_avg.csv --out-png results/gen.png
 
-cargo run --release write-value-minutes data/prices.csv data/gen.csv results/values_avg.csv
+cargo run --release analyze --overwrite gen-solar-battery data/gen.csv --out-csv results/gen_solar_battery.csv --out-png results/gen_solar_battery.png
 
-cargo run --release write-value-solar-battery data/prices.csv data/gen.csv results/values_solar_battery.csv
+cargo run --release analyze --overwrite value-minutes data/prices.csv data/gen.csv --out-csv results/values_avg.csv --out-png results/values.png
 
-cargo run --release graph-price-minutes data/prices.csv results/prices.png
+cargo run --release analyze --overwrite value-solar-battery data/prices.csv data/gen.csv --out-csv results/values_solar_battery.csv --out-png results/solar_battery.png
 
-cargo run --release graph-gen-minutes data/gen.csv results/gen.png
+cargo run --release analyze --overwrite storage-sweep data/prices.csv data/gen.csv --out-csv results/storage_sweep.csv --out-png results/storage_sweep.png
 
-cargo run --release graph-gen-solar-battery data/gen.csv results/gen_solar_battery.png
+cargo run --release analyze --overwrite price-impact data/prices.csv data/gen.csv --scale-source Solar --scale-factor 2 --out-csv results/price_impact.csv --out-png results/price_impact.png
 
-cargo run --release graph-value-minutes data/prices.csv data/gen.csv results/values.png
+cargo run --release graph-diff --overwrite scaled data/prices.csv data/gen.csv results/scaled_diff.png --scale-source Solar --scale-factor 2
 
-cargo run --release graph-value-solar-battery data/prices.csv data/gen.csv results/solar_battery.png
+cargo run --release analyze --overwrite price-spikes data/prices.csv --z-score 4 --out-csv results/price_spikes.csv --out-png results/price_spikes.png
+
+cargo run --release analyze --overwrite correlation data/prices.csv data/gen.csv --out-csv results/correlation.csv --out-png results/correlation.png
+
+cargo run --release report --overwrite data/prices.csv data/gen.csv results/report
+
+cargo run --release analyze --overwrite capacity-factors data/gen.csv data/capacity.toml --out-csv results/capacity_factors.csv --out-png results/capacity_factors.png
+
+cargo run --release analyze --overwrite solar-split data/prices.csv data/gen.csv --out-csv results/solar_split.csv --out-png results/solar_split.png
+
+cargo run --release analyze --overwrite interchange data/prices.csv data/gen.csv --out-csv results/interchange.csv --out-png results/interchange.png
+
+cargo run --release analyze --overwrite battery-split data/prices.csv data/gen.csv --out-csv results/battery_split.csv --out-png results/battery_split.png
+
+cargo run --release analyze --overwrite arbitrage-spread data/prices.csv --out-csv results/arbitrage_spread.csv --out-png results/arbitrage_spread.png
+
+cargo run --release analyze --overwrite price-minutes data/prices.csv --profile week --out-csv results/prices_week_avg.csv --out-png results/prices_week.png
+
+cargo run --release analyze --overwrite gen-minutes data/gen.csv --band-source Solar --out-png results/gen_solar_band.png
+
+cargo run --release graph-gen-animation --overwrite data/gen.csv results/gen_animation.gif
+
+cargo run --release analyze --overwrite value-profile data/prices.csv data/gen.csv --sun --out-csv results/values_profile.csv --out-png results/values_profile.png
+
+cargo run --release analyze --overwrite gen-minutes data/gen.csv --share --out-csv results/gen_share.csv --out-png results/gen_share.png
+
+cargo run --release analyze --overwrite price-minutes data/prices.csv --facet quarter --out-png results/prices_quarters.png
+
+cargo run --release analyze --overwrite gen-minutes data/gen.csv --facet quarter --out-png results/gen_quarters.png
+
+cargo run --release graph-price-timeseries --overwrite data/prices.csv results/prices_timeseries.png --window 30
+
+cargo run --release graph-gen-timeseries --overwrite data/gen.csv results/gen_timeseries.png --window 7
+
+cargo run --release analyze --overwrite tou-summary data/prices.csv data/gen.csv data/tou.toml --out-csv results/tou_summary.csv --out-png results/tou_summary.png
+
+cargo run --release analyze --overwrite tariff-costs data/prices.csv data/household_load.csv data/tariffs.toml --out-csv results/tariff_costs.csv --out-png results/tariff_costs.png
+
+cargo run --release optimize-charging --overwrite data/prices.csv results/charging.csv --per-day --gen-csv data/gen.csv --schedule-csv results/charging_schedule.csv
+cargo run --release optimize-charging --overwrite data/prices.csv results/charging_clean.csv --per-day --gen-csv data/gen.csv --objective emissions
+
+cargo run --release analyze --overwrite hydro-years data/prices.csv data/gen.csv data/water_years.toml --out-csv results/hydro_years.csv --out-png results/hydro_years.png
+
+cargo run --release analyze --overwrite complementarity data/gen.csv --out-csv results/complementarity.csv --out-png results/complementarity.png
+
+cargo run --release analyze --overwrite net-load-peaks data/prices.csv data/gen.csv --out-csv results/net_load_peaks.csv --out-png results/net_load_peaks.png
+
+cargo run --release analyze --overwrite price-forecast data/prices.csv --out-csv results/price_forecast.csv --out-png results/price_forecast.png
+
+cargo run --release analyze --overwrite forecast-errors data/prices.csv --out-csv results/forecast_errors.csv --out-png results/forecast_errors.png
+
+cargo run --release check-gen-totals --overwrite data/gen.csv results/gen_totals.csv
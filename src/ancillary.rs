@@ -0,0 +1,326 @@
+//! ### Ancillary
+//! Ancillary service prices from CAISO OASIS (PRC_AS): what the ISO pays
+//! each hour per MW of capacity held ready for regulation up and down and
+//! for spinning and non-spinning reserves. Parsing keeps one region's
+//! prices as an hourly csv, which the battery revenue comparison joins
+//! onto hourly energy prices.
+
+use crate::compute::{Aggs, Compute, Period};
+use crate::convert::{parse_timestamp, read_rows, CsvRow, Schema, TIMESTAMP_FMT};
+use crate::error::{self, bail, Locate};
+use crate::fetch::pacific_from_utc;
+use crate::output::WriteOptions;
+use crate::scenario::{BatteryScenario, Dispatch, HourPrices, Revenue};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Timelike};
+use csv::{ByteRecord, Position};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Layouts of the OASIS ancillary service price extract. Columns are the
+/// interval start in UTC, the service, its region and its price.
+pub const AS_SCHEMAS: [Schema<4>; 1] = [Schema {
+    name: "OASIS ancillary service prices (PRC_AS)",
+    columns: ["INTERVALSTARTTIME_GMT", "ANC_TYPE", "ANC_REGION", "MW"],
+}];
+
+/// The region covering all of CAISO's balancing area, where system-wide
+/// requirements clear.
+pub const DEFAULT_REGION: &str = "AS_CAISO_EXP";
+
+/// The services kept, by `ANC_TYPE`, in `AncillaryCsvRow::prices` order.
+pub const SERVICES: [&str; 4] = ["RU", "RD", "SR", "NR"];
+
+/// One hour of ancillary service prices, in $/MW, as parse-ancillary-csv writes
+/// them. A service with no price that hour is NaN.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AncillaryCsvRow {
+    /// Pacific local start of the hour.
+    pub timestamp: String,
+    pub reg_up: f64,
+    pub reg_down: f64,
+    pub spin: f64,
+    pub non_spin: f64,
+}
+
+impl CsvRow for AncillaryCsvRow {
+    const TIMESTAMPS: &'static [&'static str] = &["timestamp"];
+}
+
+impl AncillaryCsvRow {
+    /// Each service's price, in `SERVICES` order.
+    pub fn prices(&self) -> [f64; 4] {
+        [self.reg_up, self.reg_down, self.spin, self.non_spin]
+    }
+}
+
+/// Reads OASIS ancillary service extracts and writes `region`'s hourly
+/// prices. Markets that clear more often than hourly are averaged over
+/// the hour. Returns how many hours were written.
+pub fn convert_ancillary_csv(
+    inputs: &[impl AsRef<Path>],
+    output: &Path,
+    region: &str,
+    options: WriteOptions,
+) -> error::Result<usize> {
+    let mut hours: BTreeMap<NaiveDateTime, [(f64, usize); 4]> = BTreeMap::new();
+    let mut regions = BTreeSet::new();
+    let mut record = ByteRecord::new();
+    for input in inputs {
+        let input = input.as_ref();
+        let mut reader = csv::Reader::from_path(input)?;
+        let header = reader.headers()?.clone();
+        let (schema, columns) = Schema::detect(&AS_SCHEMAS, &header, input)?;
+        println!("{input:?} matches {}", schema.name);
+        while reader.read_byte_record(&mut record)? {
+            let line = record.position().map_or(0, Position::line);
+            let field = |col: usize| {
+                let bytes = record.get(columns[col]).unwrap_or_default();
+                std::str::from_utf8(bytes).locate(
+                    input,
+                    line,
+                    schema.columns[col],
+                    &String::from_utf8_lossy(bytes),
+                )
+            };
+            let row_region = field(2)?;
+            if row_region != region {
+                if !regions.contains(row_region) {
+                    regions.insert(row_region.to_string());
+                }
+                continue;
+            }
+            let service = field(1)?;
+            let Some(idx) = SERVICES.iter().position(|known| *known == service) else {
+                continue;
+            };
+            let utc = field(0)?;
+            let start = DateTime::parse_from_rfc3339(utc)
+                .locate(input, line, schema.columns[0], utc)?
+                .naive_utc();
+            let price = field(3)?;
+            let price: f64 = price
+                .trim()
+                .parse()
+                .locate(input, line, schema.columns[3], price)?;
+            let local = pacific_from_utc(start);
+            let hour = local
+                .date()
+                .and_hms_opt(local.hour(), 0, 0)
+                .expect("valid time");
+            let (sum, count) = &mut hours.entry(hour).or_default()[idx];
+            *sum += price;
+            *count += 1;
+        }
+    }
+    if hours.is_empty() {
+        bail!(
+            NoData,
+            "No {region} prices in the inputs. They have {regions:?}. Pick one with --region"
+        );
+    }
+
+    let mut csv = options.csv(output)?;
+    for (hour, services) in &hours {
+        let [reg_up, reg_down, spin, non_spin] = services.map(|(sum, count)| match count {
+            0 => f64::NAN,
+            count => sum / count as f64,
+        });
+        csv.serialize(AncillaryCsvRow {
+            timestamp: hour.format(TIMESTAMP_FMT).to_string(),
+            reg_up,
+            reg_down,
+            spin,
+            non_spin,
+        })?;
+    }
+    Ok(hours.len())
+}
+
+/// Hourly ancillary service prices, in $/MW.
+#[derive(Clone, Debug, Default)]
+pub struct AncillaryPrices {
+    by_hour: BTreeMap<NaiveDateTime, [f64; 4]>,
+}
+
+impl AncillaryPrices {
+    /// Reads a csv written by parse-ancillary-csv.
+    pub fn load(path: &Path) -> error::Result<Self> {
+        let mut by_hour = BTreeMap::new();
+        for row in read_rows::<AncillaryCsvRow>(path)? {
+            let row = row?;
+            by_hour.insert(parse_timestamp(&row.timestamp)?, row.prices());
+        }
+        if by_hour.is_empty() {
+            bail!(NoData, "No ancillary service prices in {path:?}");
+        }
+        Ok(Self { by_hour })
+    }
+
+    /// Each service's price over the hour `time` falls in, in `SERVICES`
+    /// order.
+    pub fn at(&self, time: NaiveDateTime) -> Option<[f64; 4]> {
+        let hour = time.date().and_hms_opt(time.hour(), 0, 0)?;
+        self.by_hour.get(&hour).copied()
+    }
+}
+
+/// Hourly energy and regulation prices grouped by day, ready to dispatch
+/// a battery against.
+#[derive(Clone, Debug, Default)]
+pub struct BatteryDays {
+    /// The hours of each day that have both kinds of price.
+    pub days: Vec<(NaiveDate, Vec<HourPrices>)>,
+    /// Hours with an energy price but no regulation prices.
+    pub skipped_hours: usize,
+}
+
+/// Joins hourly averages of `prices` onto `ancillary` by hour.
+fn battery_days(prices: &Compute, ancillary: &AncillaryPrices) -> error::Result<BatteryDays> {
+    let hourly = prices.downsample_prices(60, &Aggs::default())?;
+    let mut days: Vec<(NaiveDate, Vec<HourPrices>)> = Vec::new();
+    let mut skipped_hours = 0;
+    for row in hourly {
+        let time = parse_timestamp(&row.timestamp)?;
+        let Some([reg_up, reg_down, spin, _]) = ancillary.at(time) else {
+            skipped_hours += 1;
+            continue;
+        };
+        if reg_up.is_nan() || reg_down.is_nan() {
+            skipped_hours += 1;
+            continue;
+        }
+        let hour = HourPrices {
+            energy: row.lmp_avg,
+            reg_up,
+            reg_down,
+            // A region without a spinning reserve market pays nothing for it.
+            spin: if spin.is_nan() { 0. } else { spin },
+        };
+        match days.last_mut() {
+            Some((date, hours)) if *date == time.date() => hours.push(hour),
+            _ => days.push((time.date(), vec![hour])),
+        }
+    }
+    if days.is_empty() {
+        bail!(
+            NoData,
+            "No hour of {:?} has ancillary service prices to join it to",
+            prices.path()
+        );
+    }
+    Ok(BatteryDays {
+        days,
+        skipped_hours,
+    })
+}
+
+/// A battery's revenue over a dataset sold each way it can be dispatched.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StorageRevenue {
+    pub power_mw: f64,
+    pub revenues: Vec<(Dispatch, Revenue)>,
+    /// Days with both energy and regulation prices.
+    pub days: usize,
+    /// Hours with an energy price but no regulation prices.
+    pub skipped_hours: usize,
+}
+
+impl StorageRevenue {
+    /// Revenue in $/kW-year, the unit storage projects are compared in.
+    pub fn per_kw_year(&self, dollars: f64) -> f64 {
+        dollars / (self.power_mw * 1000.) * 365. / self.days as f64
+    }
+}
+
+/// `battery`'s revenue from arbitrage alone, regulation alone, and both
+/// co-optimized, over every day with both kinds of price.
+pub fn storage_revenue(
+    prices: &Compute,
+    ancillary: &AncillaryPrices,
+    battery: BatteryScenario,
+) -> error::Result<StorageRevenue> {
+    let BatteryDays {
+        days,
+        skipped_hours,
+    } = battery_days(prices, ancillary)?;
+    let revenues = Dispatch::ALL
+        .into_iter()
+        .map(|dispatch| {
+            let mut revenue = Revenue::default();
+            for (_, hours) in &days {
+                revenue += battery.daily_revenue(hours, dispatch);
+            }
+            (dispatch, revenue)
+        })
+        .collect();
+    Ok(StorageRevenue {
+        power_mw: battery.power_mw(),
+        revenues,
+        days: days.len(),
+        skipped_hours,
+    })
+}
+
+/// A battery's revenue over one month, by source.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MonthRevenue {
+    /// The month, as `2024-01`.
+    pub month: String,
+    /// Days of the month with both energy and regulation prices.
+    pub days: usize,
+    pub revenue: Revenue,
+}
+
+/// Where a battery's revenue came from in each month of a dataset.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ValueStack {
+    pub power_mw: f64,
+    pub dispatch: Dispatch,
+    pub months: Vec<MonthRevenue>,
+    /// Hours with an energy price but no regulation prices.
+    pub skipped_hours: usize,
+}
+
+impl ValueStack {
+    /// Revenue in $/kW of the battery's rated power.
+    pub fn per_kw(&self, dollars: f64) -> f64 {
+        dollars / (self.power_mw * 1000.)
+    }
+}
+
+/// `battery`'s revenue in each month with both kinds of price, dispatched
+/// as `dispatch` says.
+pub fn value_stack(
+    prices: &Compute,
+    ancillary: &AncillaryPrices,
+    battery: BatteryScenario,
+    dispatch: Dispatch,
+) -> error::Result<ValueStack> {
+    let BatteryDays {
+        days,
+        skipped_hours,
+    } = battery_days(prices, ancillary)?;
+    let mut months: Vec<MonthRevenue> = Vec::new();
+    for (date, hours) in &days {
+        let month = Period::Month.label(*date);
+        let revenue = battery.daily_revenue(hours, dispatch);
+        match months.last_mut() {
+            Some(last) if last.month == month => {
+                last.days += 1;
+                last.revenue += revenue;
+            }
+            _ => months.push(MonthRevenue {
+                month,
+                days: 1,
+                revenue,
+            }),
+        }
+    }
+    Ok(ValueStack {
+        power_mw: battery.power_mw(),
+        dispatch,
+        months,
+        skipped_hours,
+    })
+}
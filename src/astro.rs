@@ -0,0 +1,86 @@
+//! ### Astro
+//! Sunrise and sunset times from NOAA's approximate solar position
+//! equations, accurate to a minute or two, which is plenty next to
+//! five-minute data.
+
+use chrono::{Datelike, NaiveDate, Weekday};
+
+/// A location and the clock its data is recorded in.
+#[derive(Clone, Copy, Debug)]
+pub struct Site {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Hours from UTC outside of daylight saving time.
+    pub standard_offset: f64,
+    /// Whether local time follows the US daylight saving rules.
+    pub us_dst: bool,
+}
+
+/// Sunrise and sunset in minutes after local midnight.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SunTimes {
+    pub sunrise: f64,
+    pub sunset: f64,
+}
+
+impl Site {
+    /// Sun times on `date`, or None during polar day or night.
+    pub fn sun_times(&self, date: NaiveDate) -> Option<SunTimes> {
+        let gamma = 2. * std::f64::consts::PI / 365. * (date.ordinal0() as f64);
+        let eqtime = 229.18
+            * (0.000075 + 0.001868 * gamma.cos()
+                - 0.032077 * gamma.sin()
+                - 0.014615 * (2. * gamma).cos()
+                - 0.040849 * (2. * gamma).sin());
+        let decl = 0.006918 - 0.399912 * gamma.cos() + 0.070257 * gamma.sin()
+            - 0.006758 * (2. * gamma).cos()
+            + 0.000907 * (2. * gamma).sin()
+            - 0.002697 * (3. * gamma).cos()
+            + 0.00148 * (3. * gamma).sin();
+
+        // 90.833° accounts for refraction and the radius of the sun's disc.
+        let lat = self.latitude.to_radians();
+        let cos_ha =
+            90.833f64.to_radians().cos() / (lat.cos() * decl.cos()) - lat.tan() * decl.tan();
+        if !(-1. ..=1.).contains(&cos_ha) {
+            return None;
+        }
+        let ha = cos_ha.acos().to_degrees();
+
+        let offset = 60. * (self.standard_offset + if self.is_dst(date) { 1. } else { 0. });
+        Some(SunTimes {
+            sunrise: 720. - 4. * (self.longitude + ha) - eqtime + offset,
+            sunset: 720. - 4. * (self.longitude - ha) - eqtime + offset,
+        })
+    }
+
+    /// Average sun times over `dates`, skipping any without a sunrise.
+    pub fn average_sun_times(&self, dates: &[NaiveDate]) -> Option<SunTimes> {
+        let times: Vec<_> = dates
+            .iter()
+            .filter_map(|date| self.sun_times(*date))
+            .collect();
+        if times.is_empty() {
+            return None;
+        }
+        let count = times.len() as f64;
+        Some(SunTimes {
+            sunrise: times.iter().map(|t| t.sunrise).sum::<f64>() / count,
+            sunset: times.iter().map(|t| t.sunset).sum::<f64>() / count,
+        })
+    }
+
+    /// Daylight saving runs from the second Sunday in March to the first
+    /// Sunday in November. The 2am switch is ignored at daily resolution.
+    fn is_dst(&self, date: NaiveDate) -> bool {
+        if !self.us_dst {
+            return false;
+        }
+        let sunday =
+            |month, n| NaiveDate::from_weekday_of_month_opt(date.year(), month, Weekday::Sun, n);
+        match (sunday(3, 2), sunday(11, 1)) {
+            (Some(start), Some(end)) => start <= date && date < end,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! ### Cache
+//! Remembers compute results between runs, keyed by the content of their
+//! input files and the parameters they were computed with, so re-rendering
+//! a chart on unchanged data skips the csv scan.
+//!
+//! Caching is off for library callers. The CLI turns it on unless it's
+//! given `--no-cache`.
+
+use crate::error;
+use crate::manifest::Manifest;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+#[cfg(feature = "native")]
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "native")]
+use std::{fmt::Debug, fs, path::PathBuf, sync::RwLock};
+
+#[cfg(feature = "native")]
+/// Where the CLI keeps cache entries, relative to the working directory.
+pub const DEFAULT_DIR: &str = ".energy-analysis-cache";
+
+#[cfg(feature = "native")]
+static DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+#[cfg(feature = "native")]
+/// Caches results under `dir` for the rest of the process.
+pub fn enable(dir: &Path) {
+    if let Ok(mut guard) = DIR.write() {
+        *guard = Some(dir.to_path_buf());
+    }
+}
+
+#[cfg(feature = "native")]
+/// Returns the result `compute` gave the last time it ran as `operation`
+/// with the same `params` over inputs with the same content, or runs it and
+/// remembers the result. Unreadable entries are recomputed, and failing to
+/// write one only costs the next run a scan. A result over prices depends
+/// on the node they're read at, so that belongs in `params`.
+pub fn cached<T: Serialize + DeserializeOwned>(
+    operation: &str,
+    params: &impl Debug,
+    inputs: &[&Path],
+    compute: impl FnOnce() -> error::Result<T>,
+) -> error::Result<T> {
+    let dir = DIR.read().ok().and_then(|guard| guard.clone());
+    let Some(dir) = dir else {
+        return compute();
+    };
+
+    let mut hasher = Sha256::new();
+    // Each field ends in a NUL so one can't run into the next.
+    for field in [operation.to_string(), format!("{params:?}")] {
+        hasher.update(field);
+        hasher.update([0]);
+    }
+    for input in inputs {
+        hash_file(input, &mut hasher)?;
+    }
+    let entry = dir.join(format!("{operation}-{:x}.bin", hasher.finalize()));
+
+    if let Some(hit) = fs::read(&entry)
+        .ok()
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+    {
+        return Ok(hit);
+    }
+    let result = compute()?;
+    if let Ok(bytes) = bincode::serialize(&result) {
+        // Written aside and renamed so a concurrent reader never sees half an entry.
+        let partial = entry.with_extension(format!("{}.tmp", std::process::id()));
+        let written = fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&partial, bytes))
+            .and_then(|_| fs::rename(&partial, &entry));
+        if written.is_err() {
+            fs::remove_file(&partial).ok();
+        }
+    }
+    Ok(result)
+}
+
+/// Feeds a file's content to a SHA-256, and for a `Manifest` the content of
+/// every month it lists first.
+pub(crate) fn hash_file(path: &Path, hasher: &mut Sha256) -> error::Result<()> {
+    if Manifest::is_manifest(path) {
+        for month in Manifest::open(path)?.files() {
+            hash_file(month, hasher)?;
+        }
+    }
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let read = reader.read(&mut buf)?;
+        if read == 0 {
+            return Ok(());
+        }
+        hasher.update(&buf[..read]);
+    }
+}
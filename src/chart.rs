@@ -0,0 +1,122 @@
+//! ### Chart
+//! How charts are styled and what they mark, apart from drawing them, so
+//! config and the csv writers can name these in builds without the `graph`
+//! feature.
+
+use serde::{Deserialize, Serialize};
+
+/// The palette charts draw grouped and paired series with.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Default,
+    /// The Okabe-Ito palette, which stays distinguishable under the
+    /// common forms of color blindness.
+    Colorblind,
+}
+
+/// How values map onto the y axis of the price charts: `daily_price`,
+/// `price_spikes`, `daily_spreads`, `price_timeseries`, `day` and the
+/// scatter plots.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum YScale {
+    #[default]
+    Linear,
+    /// Base 10. A log axis never reaches zero, so values below
+    /// `YScale::LOG_FLOOR` sit on its floor.
+    Log,
+    /// Linear within `YScale::LOG_FLOOR` of zero and base 10 beyond it in
+    /// either direction, so negative prices and spikes fit one axis.
+    Symlog,
+}
+
+impl YScale {
+    pub const LOG_FLOOR: f64 = 1.;
+
+    /// Where `value` lands on the axis.
+    pub fn forward(self, value: f64) -> f64 {
+        match self {
+            YScale::Linear => value,
+            YScale::Log => value.max(Self::LOG_FLOOR).log10(),
+            YScale::Symlog => value.signum() * (1. + value.abs() / Self::LOG_FLOOR).log10(),
+        }
+    }
+
+    /// The value at a point on the axis, undoing `forward`.
+    pub fn inverse(self, pos: f64) -> f64 {
+        match self {
+            YScale::Linear => pos,
+            YScale::Log => 10f64.powf(pos),
+            YScale::Symlog => pos.signum() * Self::LOG_FLOOR * (10f64.powf(pos.abs()) - 1.),
+        }
+    }
+}
+
+/// The unit charts of generation draw output in.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, clap::ValueEnum)]
+pub enum PowerUnit {
+    #[default]
+    #[value(name = "MW")]
+    MW,
+    #[value(name = "GW")]
+    GW,
+}
+
+impl PowerUnit {
+    /// `mw` in this unit.
+    pub fn of(self, mw: f64) -> f64 {
+        match self {
+            PowerUnit::MW => mw,
+            PowerUnit::GW => mw / 1000.,
+        }
+    }
+}
+
+/// One value a chart draws, as `Graphing::with_also_csv` writes it.
+#[derive(Debug, Serialize)]
+pub struct PlotPoint {
+    pub series: String,
+    pub x: String,
+    pub y: f64,
+}
+
+/// A labeled vertical line drawn across a time-of-day chart.
+#[derive(Clone, Debug)]
+pub struct Marker {
+    pub slot: usize,
+    pub label: String,
+}
+
+/// A labeled horizontal line across a chart, at `value` in the units its
+/// y axis is labeled in, such as $0/MWh or a peak price.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ReferenceLine {
+    pub value: f64,
+    pub label: String,
+}
+
+/// The order the line charts of generation draw and list their sources in.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SourceOrder {
+    /// The order of the gen csv's columns.
+    #[default]
+    Schema,
+    /// Largest average output first.
+    Magnitude,
+    /// These sources first, by their index in
+    /// `EnergyGenCsvRow::source_keys`, then the rest in schema order.
+    Given(Vec<usize>),
+}
+
+/// Where the line charts of generation put their legend.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LegendPosition {
+    /// The upper right corner of the plot.
+    #[default]
+    Inside,
+    /// A column right of the plot, where it can't cover the evening peak.
+    Outside,
+}
@@ -0,0 +1,7160 @@
+//! ### Cli
+//! The `energy_analysis` command line. It lives in the library so it can
+//! reach the modules that only it uses, like `output`, `cache` and
+//! `watch`, without making them part of the library's API.
+
+use crate::{
+    ancillary::{self, AncillaryPrices},
+    cache,
+    chart::{LegendPosition, PowerUnit, ReferenceLine, SourceOrder, YScale},
+    compute::{
+        Agg, Aggs, Compute, DatasetStats, DayPolicy, FewSamples, PeakTiming, Period, PriceNode,
+        Profile, ProfileAgg, SampleCheck, SpikeThreshold,
+    },
+    config::{InstalledCapacity, Settings, TimeMark, TouSchedule, WaterYears, WriteOptions},
+    convert::{
+        self, ConvertSummary, CsvFormat, EnergyGenCsvRow, Inspection, OnError, RawKind, TotalCheck,
+    },
+    error,
+    fetch::{self, eia::EiaApi, oasis},
+    forecast::Baseline,
+    gas::{self, GasPrices},
+    manifest::Manifest,
+    monitor::{Hook, Monitor, Thresholds},
+    nodal::{self, NodeIndex},
+    output::{self, StampedDir},
+    pipeline::Pipeline,
+    provenance::{self, Provenance},
+    scenario::{
+        BatteryScenario, ChargingObjective, ChargingScenario, Dispatch, ScenarioWindow,
+        SourceScaling,
+    },
+    series::{self, SeriesLayout},
+    serve::Service,
+    stats::{Clip, ClipBounds},
+    store::Store,
+    tariff::{self, LoadProfile, Tariff},
+    watch::Watcher,
+    weather::{self, WeatherDay, WeatherFits},
+    xlsx,
+};
+use anyhow::{anyhow, bail};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use clap::{CommandFactory, Parser, ValueEnum};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Debug;
+use std::fs;
+use std::io::Write;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+#[cfg(feature = "testdata")]
+use crate::testdata::SyntheticData;
+
+#[cfg(feature = "graph")]
+use crate::{
+    chart::Marker,
+    compute::{histogram, profile_delta, rolling_mean, value_delta, GroupSummary},
+    forecast,
+    graph::{ChartOptions, Graphing, SourceBand},
+    report::{Report, ReportFormat},
+};
+
+/// Parses CAISO price and generation data, then analyzes, charts and
+/// reports on it
+#[derive(clap::Parser, Debug)]
+struct Cli {
+    /// A config of default paths and options. Defaults to
+    /// energy-analysis.toml in the working directory, if there is one
+    #[clap(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Replace output files that already exist instead of refusing to
+    #[clap(long, global = true)]
+    overwrite: bool,
+
+    /// Writes every output into this directory instead, with the time of
+    /// the run added to each file name
+    #[clap(long = "output-dir", global = true)]
+    stamped_dir: Option<PathBuf>,
+
+    /// Recomputes every result instead of reusing ones cached in
+    /// .energy-analysis-cache/ from earlier runs on the same data
+    #[clap(long, global = true)]
+    no_cache: bool,
+
+    /// Skips writing a .meta.json beside each output recording the inputs,
+    /// options and version that produced it
+    #[clap(long, global = true)]
+    no_meta: bool,
+
+    /// Which LMP every price analysis reads: the average of the three
+    /// areas, or one area's own zone or trading hub price
+    #[clap(long, global = true, value_enum, default_value_t)]
+    price_node: PriceNode,
+
+    /// Decimals kept in every number written to a csv. Trailing zeros are
+    /// dropped, so 23.5 stays 23.5
+    #[clap(long, global = true, default_value_t = output::DEFAULT_PRECISION)]
+    precision: usize,
+
+    #[clap(flatten)]
+    chart: ChartArgs,
+
+    /// Keeps running a graph command, re-rendering its chart whenever one
+    /// of its inputs or the config changes
+    #[clap(long, global = true)]
+    watch: bool,
+
+    #[clap(subcommand)]
+    command: Args,
+}
+
+#[derive(clap::Subcommand, Clone, Debug)]
+enum Args {
+    /// Takes a raw 5-min zone or trading hub price data CSV from
+    /// https://www.eia.gov/electricity/wholesalemarkets/data.php?rto=caiso
+    /// and simplifies it into a form more suitable for processing: the
+    /// areas' average LMP, along with each area's own LMP and its congestion
+    /// and loss components. Zone and hub files are parsed separately.
+    /*
+    cargo run parse-price-csv \
+        --caiso-csv \
+        data/caiso_lmp_rt_5min_zones_2023Q4.csv \
+        data/caiso_lmp_rt_5min_zones_2024Q1.csv \
+        data/caiso_lmp_rt_5min_zones_2024Q2.csv \
+        data/caiso_lmp_rt_5min_zones_2024Q3.csv \
+        --output-csv data/prices.csv
+    */
+    ParsePriceCsv {
+        /// A list of input CSV files to aggregate into a single output.
+        /// Expected file format is that of `caiso_lmp_rt_5min_zones_202*Q*.csv`
+        /// or `caiso_lmp_rt_5min_hubs_202*Q*.csv`, or an .xlsx workbook with
+        /// the same columns in one of its sheets
+        #[clap(short, long, num_args = 1.., value_delimiter = ' ')]
+        caiso_csv: Vec<PathBuf>,
+
+        /// An output file that the simplified inputs are written to
+        #[clap(short, long)]
+        output_csv: PathBuf,
+
+        /// Also writes the simplified rows to this binary, day-indexed store.
+        /// Every command that takes a parsed csv accepts the store instead
+        /// and loads it much faster.
+        #[clap(long)]
+        store: Option<PathBuf>,
+
+        /// Also splits the simplified rows into one csv per month beside
+        /// this toml manifest, named after it: data/prices.toml lists
+        /// data/prices-2024-01.csv and so on. Every command that takes a
+        /// parsed csv accepts the manifest instead.
+        #[clap(long)]
+        months: Option<PathBuf>,
+
+        #[clap(flatten)]
+        format: CsvFormatArgs,
+
+        /// What to do with rows that don't parse. skip and log leave them
+        /// out, log printing each, and abort stops at the first
+        #[clap(long, value_enum, default_value_t = OnError::Skip)]
+        on_error: OnError,
+    },
+
+    /// Takes a raw 5-min energy generation source data CSV from
+    /// https://www.eia.gov/electricity/wholesalemarkets/data.php?rto=caiso
+    /// and simplifies it into a form more suitable for processing.
+    /*
+    cargo run parse-gen-csv \
+        --caiso-csv \
+        data/caiso_gen_all_5min_2023Q4.csv \
+        data/caiso_gen_all_5min_2024Q1.csv \
+        data/caiso_gen_all_5min_2024Q2.csv \
+        data/caiso_gen_all_5min_2024Q3.csv \
+        --output-csv data/gen.csv
+        */
+    ParseGenCsv {
+        /// A list of input CSV files to aggregate into a single output.
+        /// Expected file format is that of `caiso_gen_all_5min_202*Q*.csv`,
+        /// or an .xlsx workbook with the same columns in one of its sheets
+        #[clap(short, long, num_args = 1.., value_delimiter = ' ')]
+        caiso_csv: Vec<PathBuf>,
+
+        /// An output file that the simplified inputs are written to
+        #[clap(short, long)]
+        output_csv: PathBuf,
+
+        /// Also writes the simplified rows to this binary, day-indexed store.
+        /// Every command that takes a parsed csv accepts the store instead
+        /// and loads it much faster.
+        #[clap(long)]
+        store: Option<PathBuf>,
+
+        /// Also splits the simplified rows into one csv per month beside
+        /// this toml manifest, named after it: data/prices.toml lists
+        /// data/prices-2024-01.csv and so on. Every command that takes a
+        /// parsed csv accepts the manifest instead.
+        #[clap(long)]
+        months: Option<PathBuf>,
+
+        #[clap(flatten)]
+        format: CsvFormatArgs,
+
+        /// What to do with rows whose Total doesn't match the sum of their
+        /// sources. Either way, they're counted
+        #[clap(long, value_enum, default_value_t = TotalCheck::Report)]
+        totals: TotalCheck,
+
+        /// What to do with rows that don't parse. skip and log leave them
+        /// out, log printing each, and abort stops at the first
+        #[clap(long, value_enum, default_value_t = OnError::Skip)]
+        on_error: OnError,
+    },
+
+    /// Takes raw nodal LMP extracts from CAISO OASIS (PRC_INTVL_LMP or
+    /// PRC_LMP, as csv) and streams them into a narrow csv of each node's
+    /// price components, with a node index beside it that lets a node's
+    /// rows be read without scanning the rest.
+    /*
+    cargo run parse-nodal-csv \
+        --caiso-csv data/oasis_intvl_lmp_2024Q1.csv data/oasis_intvl_lmp_2024Q2.csv \
+        --output-csv data/nodal.csv
+    */
+    ParseNodalCsv {
+        /// A list of OASIS nodal extracts to aggregate into a single output
+        #[clap(short, long, num_args = 1.., value_delimiter = ' ')]
+        caiso_csv: Vec<PathBuf>,
+
+        /// An output file that the simplified inputs are written to. The
+        /// node index is written beside it, e.g. data/nodal.nodes.csv for
+        /// data/nodal.csv
+        #[clap(short, long)]
+        output_csv: PathBuf,
+    },
+
+    /// Takes raw ancillary service price extracts from CAISO OASIS (PRC_AS,
+    /// as csv) and writes one region's hourly regulation and reserve prices.
+    /*
+    cargo run parse-ancillary-csv \
+        --caiso-csv data/oasis_as_2024Q1.csv data/oasis_as_2024Q2.csv \
+        --output-csv data/ancillary.csv
+    */
+    ParseAncillaryCsv {
+        /// A list of OASIS ancillary service extracts to aggregate into a
+        /// single output
+        #[clap(short, long, num_args = 1.., value_delimiter = ' ')]
+        caiso_csv: Vec<PathBuf>,
+
+        /// An output file that the hourly prices are written to
+        #[clap(short, long)]
+        output_csv: PathBuf,
+
+        /// The ANC_REGION whose prices are kept
+        #[clap(long, default_value = ancillary::DEFAULT_REGION)]
+        region: String,
+    },
+
+    /// Combines a parsed price or gen csv into longer intervals, such as
+    /// 15 minutes or an hour, writing the same columns so it stays readable
+    /// by every command. Much smaller files for sharing and quick charts.
+    // cargo run downsample data/gen.csv data/gen_hourly.csv --minutes 60 --agg Solar=sum
+    Downsample {
+        /// A csv, store or manifest output by parse-price-csv or parse-gen-csv
+        csv_in: PathBuf,
+
+        /// Where the downsampled csv will be written
+        csv_out: PathBuf,
+
+        /// The length of each output interval, a multiple of five minutes
+        /// that divides the day
+        #[clap(long, default_value_t = 60)]
+        minutes: usize,
+
+        /// How each interval's values are combined, unless --agg says
+        /// otherwise for a column
+        #[clap(long, value_enum, default_value_t = Agg::Mean)]
+        default_agg: Agg,
+
+        /// How to combine one column, e.g. Solar=sum. Columns are Price, or
+        /// sources named as in the other gen commands
+        #[clap(long, value_parser = parse_agg)]
+        agg: Vec<(String, Agg)>,
+    },
+
+    /// Interpolates a third-party time series, such as hourly temperatures,
+    /// onto the five-minute grid so it can be joined with the price and gen
+    /// data. Writes a timestamp column followed by each value column.
+    /*
+    cargo run align-series data/temperature.csv data/temperature_5min.csv \
+        --timestamp-column DATE --timestamp-format "%m/%d/%Y %H:%M" \
+        --column TMP --grid data/prices.csv
+    */
+    AlignSeries {
+        /// A csv with a timestamp column and one or more numeric columns
+        series_csv: PathBuf,
+
+        /// Where the aligned csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        layout: SeriesArgs,
+
+        /// Columns to align. Defaults to every column but the timestamp
+        #[clap(long, num_args = 1.., value_delimiter = ',')]
+        column: Vec<String>,
+
+        /// Aligns onto the intervals of this parsed price or gen csv,
+        /// store or manifest instead of every five minutes the series spans
+        #[clap(long)]
+        grid: Option<PathBuf>,
+
+        /// Leaves out intervals between values more than this many minutes
+        /// apart rather than interpolating across the gap
+        #[clap(long)]
+        max_gap_mins: Option<u32>,
+    },
+
+    /// Reads the header and first rows of raw price or gen files and
+    /// reports which layout each matches, roughly how many rows and which
+    /// dates it covers, and what parsing it would do, without writing
+    /// anything.
+    // cargo run inspect --caiso-csv data/caiso_gen_all_5min_2024Q3.csv
+    Inspect {
+        /// Raw price or gen exports, csv or xlsx
+        #[clap(short, long, num_args = 1.., value_delimiter = ' ')]
+        caiso_csv: Vec<PathBuf>,
+
+        /// How many rows of each file to try parsing
+        #[clap(long, default_value_t = 1000, value_parser = clap::value_parser!(u64).range(1..))]
+        rows: u64,
+
+        #[clap(flatten)]
+        format: CsvFormatArgs,
+    },
+
+    /// Downloads real-time prices from CAISO's OASIS API into the same
+    /// form parse-price-csv writes, and optionally solar, wind and load
+    /// into the form parse-gen-csv writes, for dates the EIA files don't
+    /// reach yet. Requests are spaced out to stay under OASIS's rate limit.
+    // cargo run fetch-oasis --start 2024-10-01 --end 2024-10-07 --output-csv data/prices_oasis.csv
+    // cargo run fetch-oasis --append --output-csv data/prices_oasis.csv --gen-csv data/gen_oasis.csv
+    FetchOasis {
+        /// The first Pacific date to fetch. With --append, defaults to the
+        /// date of the output's latest interval
+        #[clap(long, required_unless_present_any = ["zip", "append"])]
+        start: Option<NaiveDate>,
+
+        /// The last Pacific date to fetch. With --append, defaults to today
+        #[clap(long, required_unless_present_any = ["zip", "append"])]
+        end: Option<NaiveDate>,
+
+        /// Pricing nodes averaged into each interval's price. Defaults to
+        /// the NP15, SP15 and ZP26 trading hubs the EIA files average
+        #[clap(long, num_args = 1.., value_delimiter = ',')]
+        node: Vec<String>,
+
+        /// Reads OASIS zips that were already downloaded instead of
+        /// fetching anything
+        #[clap(long, num_args = 1.., conflicts_with_all = ["start", "end", "node"])]
+        zip: Vec<PathBuf>,
+
+        /// An output file that the prices are written to
+        #[clap(short, long)]
+        output_csv: PathBuf,
+
+        /// Also writes real-time solar and wind output here as a parsed gen
+        /// csv, with the load forecast as the Total and the rest of the
+        /// load as Other
+        #[clap(long)]
+        gen_csv: Option<PathBuf>,
+
+        /// Adds only the intervals after each output's latest one to its
+        /// end, rather than replacing it, so it can be kept up to date for
+        /// serve and monitor
+        #[clap(long)]
+        append: bool,
+    },
+
+    /// Downloads hourly net generation by fuel from EIA's API into the same
+    /// form parse-gen-csv writes, optionally with hourly demand and
+    /// interchange by neighboring balancing authority. Needs an API key in
+    /// EIA_API_KEY or the config's eia_api_key.
+    // cargo run fetch-eia --start 2024-10-01 --end 2024-10-07 --output-csv data/gen_eia.csv
+    FetchEia {
+        /// The first Pacific date to fetch. With --append, defaults to the
+        /// date of the output's latest interval
+        #[clap(long, required_unless_present = "append")]
+        start: Option<NaiveDate>,
+
+        /// The last Pacific date to fetch. With --append, defaults to today
+        #[clap(long, required_unless_present = "append")]
+        end: Option<NaiveDate>,
+
+        /// An output file that generation is written to, repeating each
+        /// hour over its five-minute intervals
+        #[clap(short, long)]
+        output_csv: PathBuf,
+
+        /// Also writes hourly demand here
+        #[clap(long)]
+        demand_csv: Option<PathBuf>,
+
+        /// Also writes hourly imports from each neighboring balancing
+        /// authority here
+        #[clap(long)]
+        interchange_csv: Option<PathBuf>,
+
+        /// Adds only the hours after the output's latest interval to the
+        /// end of each csv, rather than replacing them
+        #[clap(long)]
+        append: bool,
+    },
+
+    /// Prints a parsed dataset's date coverage and row counts, and its
+    /// overall price spread or each source's total energy, as a quick check
+    /// before analyzing it.
+    // cargo run stats data/prices.csv --out-json results/price_stats.json
+    Stats {
+        /// A csv of the form output by parse-price-csv or parse-gen-csv
+        csv_in: PathBuf,
+
+        /// Where to also write the stats as a stat,value csv
+        #[clap(long)]
+        out_csv: Option<PathBuf>,
+
+        /// Where to also write the stats as json
+        #[clap(long)]
+        out_json: Option<PathBuf>,
+    },
+
+    /// Lists every row of a parsed generation csv whose Total is off from
+    /// the sum of its sources by more than a megawatt.
+    // cargo run check-gen-totals data/gen.csv results/gen_totals.csv
+    CheckGenTotals {
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Where the mismatched rows will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Runs an analysis once and writes its results to a csv, a chart, or
+    /// both, e.g. `analyze value-minutes prices.csv gen.csv --out-csv
+    /// values.csv --out-png values.png`.
+    // cargo run analyze price-minutes data/prices.csv --out-png results/prices.png
+    Analyze {
+        #[clap(subcommand)]
+        analysis: Analysis,
+    },
+
+    /// Takes the output of parse-price-csv and records the price
+    /// five-minute averages into the output csv. The same data
+    /// is charted in the graph-price-minutes function.
+    // cargo run analyze price-minutes data/prices.csv --out-csv results/prices_avg.csv
+    #[clap(hide = true)]
+    WritePriceMinutes {
+        /// A csv of the form output by parse-price-csv
+        csv_in: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        agg: AggArgs,
+
+        #[clap(flatten)]
+        clip: ClipArgs,
+
+        #[clap(flatten)]
+        samples: SampleArgs,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Takes the output of parse-gen-csv and records the generation
+    /// distribution five-minute averages into the output csv. The
+    /// same data is charted in the graph-gen-minutes function.
+    // cargo run analyze gen-minutes data/gen.csv --out-csv results/gen_avg.csv
+    #[clap(hide = true)]
+    WriteGenMinutes {
+        /// A csv of the form output by parse-gen-csv
+        csv_in: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        agg: AggArgs,
+
+        #[clap(flatten)]
+        samples: SampleArgs,
+
+        /// Records each source's percent share of generation instead of MW
+        #[clap(long)]
+        share: bool,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Same as write-gen-minutes but merges solar and battery columns.
+    // cargo run analyze gen-solar-battery data/gen.csv --out-csv results/gen_solar_battery.csv
+    #[clap(hide = true)]
+    WriteGenSolarBattery {
+        /// A csv of the form output by parse-gen-csv
+        csv_in: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        agg: AggArgs,
+
+        #[clap(flatten)]
+        samples: SampleArgs,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Reports the days at the start and end of a parsed csv that the data
+    /// only partly covers, and writes how many whole days land in each
+    /// slot of the profile, after any days the options leave out.
+    // cargo run write-day-coverage data/prices.csv results/day_coverage.csv
+    WriteDayCoverage {
+        /// A csv of the form output by parse-price-csv or parse-gen-csv
+        csv_in: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Writes the values from graph-value-minutes into a CSV.
+    // cargo run analyze value-minutes data/prices.csv data/gen.csv --out-csv results/values_avg.csv
+    #[clap(hide = true)]
+    WriteValueMinutes {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        scale: ScaleArgs,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Writes value-minutes under the hypothetical of merged solar + battery.
+    // cargo run analyze value-solar-battery data/prices.csv data/gen.csv \
+    //     --out-csv results/values_solar_battery.csv
+    #[clap(hide = true)]
+    WriteValueSolarBattery {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Takes the output of parse-price-csv and renders it as a png at
+    /// the given output_png location.
+    // cargo run analyze price-minutes data/prices.csv --out-png results/prices.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphPriceMinutes {
+        /// A csv of the form output by ParsePriceCsv
+        price_csv: PathBuf,
+
+        /// Where the output PNG file will be written.
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        agg: AggArgs,
+
+        #[clap(flatten)]
+        clip: ClipArgs,
+
+        #[clap(flatten)]
+        sun: SunArgs,
+    },
+
+    /// Takes the output of parse-price-csv and renders it as a png at
+    /// the given output_png location.
+    // cargo run analyze gen-minutes data/gen.csv --out-png results/gen.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphGenMinutes {
+        gen_csv: PathBuf,
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        agg: AggArgs,
+
+        #[clap(flatten)]
+        sun: SunArgs,
+
+        /// Shades this source's 10th-90th percentile output behind its
+        /// line, e.g. "Solar"
+        #[clap(long)]
+        band_source: Option<String>,
+
+        /// Stacks each source's percent share of generation instead
+        #[clap(long, conflicts_with = "band_source")]
+        share: bool,
+    },
+
+    /// graph-gen-minutes but merges the solar and battery columns
+    // cargo run analyze gen-solar-battery data/gen.csv --out-png results/gen_solar_battery.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphGenSolarBattery {
+        gen_csv: PathBuf,
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        agg: AggArgs,
+
+        #[clap(flatten)]
+        sun: SunArgs,
+    },
+
+    /// Animates the daily generation profile through the year as a gif,
+    /// one frame per month or week.
+    // cargo run graph-gen-animation data/gen.csv results/gen_animation.gif
+    #[cfg(feature = "graph")]
+    GraphGenAnimation {
+        gen_csv: PathBuf,
+        output_gif: PathBuf,
+
+        /// The stretch of data each frame averages over
+        #[clap(long, value_enum, default_value = "month")]
+        frame: Period,
+
+        /// How long each frame is shown, in milliseconds
+        #[clap(long, default_value_t = 750)]
+        frame_ms: u32,
+    },
+
+    /// Takes the output of both parse-price-csv and parse-gen-csv and
+    /// writes a graph displaying the average dollar value of each type
+    /// of electricity.
+    // cargo run analyze value-minutes data/prices.csv data/gen.csv --out-png results/values.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphValueMinutes {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        scale: ScaleArgs,
+    },
+
+    /// Graphs value-minutes but adds solar + battery output into a
+    /// single column.
+    // cargo run analyze value-solar-battery data/prices.csv data/gen.csv --out-png results/solar-battery.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphValueSolarBattery {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+    },
+
+    /// Records each source's realized $/MWh in every five-minute window of
+    /// the day, rather than one average for the whole day.
+    // cargo run analyze value-profile data/prices.csv data/gen.csv --out-csv results/values_profile.csv
+    #[clap(hide = true)]
+    WriteValueProfile {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs write-value-profile as a line per source.
+    // cargo run analyze value-profile data/prices.csv data/gen.csv --out-png results/values_profile.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphValueProfile {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        sun: SunArgs,
+    },
+
+    /// Graphs the average generation mix through the day as stacked areas,
+    /// with the average price drawn over it against a second axis.
+    // cargo run graph-price-gen-overlay data/prices.csv data/gen.csv results/price_gen_overlay.png
+    #[cfg(feature = "graph")]
+    GraphPriceGenOverlay {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        sun: SunArgs,
+    },
+
+    /// Adds hypothetical solar-charged batteries of increasing size and
+    /// records the resulting solar + battery value and arbitrage revenue.
+    // cargo run analyze storage-sweep data/prices.csv data/gen.csv --out-csv results/storage_sweep.csv
+    #[clap(hide = true)]
+    WriteStorageSweep {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        /// The largest battery capacity simulated, in GWh
+        #[clap(long, default_value_t = 40.)]
+        max_gwh: f64,
+
+        /// The capacity increment between simulated batteries, in GWh
+        #[clap(long, default_value_t = 2.)]
+        step_gwh: f64,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs solar + battery value against added storage capacity.
+    // cargo run analyze storage-sweep data/prices.csv data/gen.csv --out-png results/storage_sweep.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphStorageSweep {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        /// The largest battery capacity simulated, in GWh
+        #[clap(long, default_value_t = 40.)]
+        max_gwh: f64,
+
+        /// The capacity increment between simulated batteries, in GWh
+        #[clap(long, default_value_t = 2.)]
+        step_gwh: f64,
+    },
+
+    /// Scales a source's output (e.g. twice the solar) and records the
+    /// historical and scenario average price of each five-minute window.
+    // cargo run analyze price-impact data/prices.csv data/gen.csv --out-csv results/price_impact.csv \
+    //     --scale-source Solar --scale-factor 2
+    #[clap(hide = true)]
+    WritePriceImpact {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        scale: ScaleArgs,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs the historical and scenario price profiles from write-price-impact.
+    // cargo run analyze price-impact data/prices.csv data/gen.csv --out-png results/price_impact.png \
+    //     --scale-source Solar --scale-factor 2
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphPriceImpact {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        scale: ScaleArgs,
+    },
+
+    /// Finds runs of intervals where price exceeds a threshold and
+    /// records each as an event with its start, end, duration, and peak.
+    // cargo run analyze price-spikes data/prices.csv --out-csv results/price_spikes.csv --z-score 4
+    #[clap(hide = true)]
+    WritePriceSpikes {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        threshold: SpikeArgs,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs the full price time series with spikes marked.
+    // cargo run analyze price-spikes data/prices.csv --out-png results/price_spikes.png --z-score 4
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphPriceSpikes {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        threshold: SpikeArgs,
+    },
+
+    /// Graphs how often five-minute prices land in each price range, with
+    /// negative prices in their own color.
+    // cargo run graph-price-histogram data/prices.csv results/price_histogram.png --log-scale
+    #[cfg(feature = "graph")]
+    GraphPriceHistogram {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        /// The width of each bin in $/MWh
+        #[clap(long, default_value_t = 5.)]
+        bin_width: f64,
+
+        /// Draws counts on a log scale so rare spikes stay visible
+        #[clap(long)]
+        log_scale: bool,
+    },
+
+    /// Graphs how far one scenario moved from a base scenario, with a zero
+    /// line for reference.
+    // cargo run graph-diff price-profile data/prices_2023.csv data/prices_2024.csv results/price_diff.png
+    #[cfg(feature = "graph")]
+    GraphDiff {
+        #[clap(subcommand)]
+        diff: Diff,
+    },
+
+    /// Plots a single calendar day's raw five-minute prices and generation
+    /// rather than the averaged daily profile.
+    // cargo run graph-day data/prices.csv data/gen.csv 2024-01-16 results/day.png
+    #[cfg(feature = "graph")]
+    GraphDay {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// The day to plot, as YYYY-MM-DD
+        date: NaiveDate,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+    },
+
+    /// Charts each of a few event days' raw five-minute prices and
+    /// generation against the average of the days around it, and writes how
+    /// far each strayed to case_study.csv, e.g. for the October 14, 2023
+    /// eclipse.
+    /*
+    cargo run case-study data/prices.csv data/gen.csv results/eclipse --dates 2023-10-14
+    */
+    CaseStudy {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A directory where the summary and a chart per day will be written
+        output_dir: PathBuf,
+
+        /// The days to study, as YYYY-MM-DD
+        #[clap(long, required = true, num_args = 1.., value_delimiter = ',')]
+        dates: Vec<NaiveDate>,
+
+        /// How many days either side of each date make up its baseline
+        #[clap(long, default_value_t = 14)]
+        baseline_days: u32,
+    },
+
+    /// Prints the highest-price, lowest-price, and highest-solar days,
+    /// which make good candidates for graph-day.
+    // cargo run find-extremes data/prices.csv data/gen.csv
+    FindExtremes {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        #[clap(flatten)]
+        threads: ThreadArgs,
+    },
+
+    /// Records the Pearson correlation of each generation source with
+    /// price, across the whole dataset and within each hour of the day.
+    // cargo run analyze correlation data/prices.csv data/gen.csv --out-csv results/correlation.csv
+    #[clap(hide = true)]
+    WriteCorrelation {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        threads: ThreadArgs,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs the values from write-correlation as a heatmap.
+    // cargo run analyze correlation data/prices.csv data/gen.csv --out-png results/correlation.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphCorrelation {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        threads: ThreadArgs,
+    },
+
+    /// Plots every five-minute interval's output from one source against
+    /// its price, with a fitted line through them.
+    // cargo run graph-scatter data/prices.csv data/gen.csv results/solar_scatter.png --source Solar
+    #[cfg(feature = "graph")]
+    GraphScatter {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        /// The source whose output goes on the x axis, e.g. "Solar"
+        #[clap(long, default_value = "Solar")]
+        source: String,
+
+        /// Colors each point by its hour of the day
+        #[clap(long)]
+        by_hour: bool,
+    },
+
+    /// Runs the standard analyses and writes a report with their charts
+    /// and monthly and quarterly summary tables into the output directory.
+    // cargo run report data/prices.csv data/gen.csv results/report --format html
+    #[cfg(feature = "graph")]
+    Report {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A directory where the report and its charts will be written.
+        output_dir: PathBuf,
+
+        #[clap(long, value_enum, default_value_t = ReportFormat::Markdown)]
+        format: ReportFormat,
+    },
+
+    /// Converts average output into capacity factors using a TOML file of
+    /// installed capacity by source, per five-minute window or per month.
+    // cargo run analyze capacity-factors data/gen.csv data/capacity.toml --out-csv results/capacity_factors.csv
+    #[clap(hide = true)]
+    WriteCapacityFactors {
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A TOML file mapping source names to installed MW
+        capacity_toml: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        /// Computes one capacity factor per month instead of per time of day
+        #[clap(long)]
+        by_month: bool,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs the capacity factors from write-capacity-factors.
+    // cargo run analyze capacity-factors data/gen.csv data/capacity.toml --out-png results/capacity_factors.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphCapacityFactors {
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A TOML file mapping source names to installed MW
+        capacity_toml: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        /// Computes one capacity factor per month instead of per time of day
+        #[clap(long)]
+        by_month: bool,
+    },
+
+    /// Splits intervals into solar hours (solar output above a threshold) and
+    /// non-solar hours, recording average price, generation share, and value
+    /// by source for each.
+    // cargo run analyze solar-split data/prices.csv data/gen.csv --out-csv results/solar_split.csv
+    #[clap(hide = true)]
+    WriteSolarSplit {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        /// Solar output in MW above which an interval counts as a solar hour
+        #[clap(long, default_value_t = 500.)]
+        solar_threshold: f64,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs value by source during solar and non-solar hours side by side.
+    // cargo run analyze solar-split data/prices.csv data/gen.csv --out-png results/solar_split.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphSolarSplit {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        /// Solar output in MW above which an interval counts as a solar hour
+        #[clap(long, default_value_t = 500.)]
+        solar_threshold: f64,
+    },
+
+    /// Groups intervals into the time-of-use periods of a TOML schedule,
+    /// like a 4-9pm peak, recording average price, generation share, and
+    /// value by source for each.
+    // cargo run analyze tou-summary data/prices.csv data/gen.csv data/tou.toml --out-csv results/tou_summary.csv
+    #[clap(hide = true)]
+    WriteTouSummary {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A TOML file defining the time-of-use periods
+        tou_toml: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs value by source in each time-of-use period side by side.
+    // cargo run analyze tou-summary data/prices.csv data/gen.csv data/tou.toml --out-png results/tou_summary.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphTouSummary {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A TOML file defining the time-of-use periods
+        tou_toml: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+    },
+
+    /// Groups intervals by California water year, labeled wet through
+    /// critical from a TOML file, recording average price, generation share,
+    /// and value by source for each.
+    // cargo run analyze hydro-years data/prices.csv data/gen.csv data/water_years.toml --out-csv results/hydro_years.csv
+    #[clap(hide = true)]
+    WriteHydroYears {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A TOML file mapping water years to their type
+        water_years_toml: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs the average day of large + small hydro output in each water
+    /// year, labeled with the year's type and average price.
+    // cargo run analyze hydro-years data/prices.csv data/gen.csv data/water_years.toml --out-png results/hydro_years.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphHydroYears {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A TOML file mapping water years to their type
+        water_years_toml: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+    },
+
+    /// Records how wind and solar output vary across days in each
+    /// five-minute window, alone and combined, and how they correlate.
+    // cargo run analyze complementarity data/gen.csv --out-csv results/complementarity.csv
+    #[clap(hide = true)]
+    WriteComplementarity {
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs the average day of wind, solar, and their sum.
+    // cargo run analyze complementarity data/gen.csv --out-png results/complementarity.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphComplementarity {
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+    },
+
+    /// Records each day's high temperature from a weather csv beside its
+    /// peak price and evening ramp (the rise in net load from its midday
+    /// low to its evening high), and prints how well temperature predicts
+    /// each.
+    /*
+    cargo run analyze weather-correlation data/prices.csv data/gen.csv \
+        data/temperature_5min.csv --out-csv results/weather_days.csv
+    */
+    #[clap(hide = true)]
+    WriteWeatherCorrelation {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Temperatures, such as a csv output by align-series
+        weather_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        weather: WeatherArgs,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Plots each day's peak price and evening ramp against its high
+    /// temperature, with a fitted line through each.
+    /*
+    cargo run analyze weather-correlation data/prices.csv data/gen.csv \
+        data/temperature_5min.csv --out-png results/weather.png
+    */
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphWeatherCorrelation {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Temperatures, such as a csv output by align-series
+        weather_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        weather: WeatherArgs,
+    },
+
+    /// Finds the hours with the highest net load (total generation less
+    /// wind and solar) and records the price, generation mix, and value by
+    /// source in each, then across all of them and across every other hour.
+    // cargo run analyze net-load-peaks data/prices.csv data/gen.csv --out-csv results/net_load_peaks.csv
+    #[clap(hide = true)]
+    WriteNetLoadPeaks {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        /// How many of the highest net-load hours to report
+        #[clap(long, default_value_t = 100)]
+        top: usize,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs the generation mix during the highest net-load hours next to
+    /// the mix in every other hour.
+    // cargo run analyze net-load-peaks data/prices.csv data/gen.csv --out-png results/net_load_peaks.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphNetLoadPeaks {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        /// How many of the highest net-load hours to compare
+        #[clap(long, default_value_t = 100)]
+        top: usize,
+    },
+
+    /// Forecasts prices for each five-minute interval of the day after the
+    /// dataset ends with a naive baseline, for models to be measured against.
+    // cargo run analyze price-forecast data/prices.csv --out-csv results/price_forecast.csv
+    #[clap(hide = true)]
+    WritePriceForecast {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(long, value_enum, default_value_t = Baseline::Yesterday)]
+        baseline: Baseline,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Backtests every naive baseline over the dataset and records the mean
+    /// absolute error and mean absolute percentage error of each.
+    // cargo run analyze forecast-errors data/prices.csv --out-csv results/forecast_errors.csv
+    #[clap(hide = true)]
+    WriteForecastErrors {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs one day of actual prices against a baseline's forecast.
+    // cargo run analyze price-forecast data/prices.csv --out-png results/price_forecast.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphPriceForecast {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        #[clap(long, value_enum, default_value_t = Baseline::Yesterday)]
+        baseline: Baseline,
+
+        /// The day to graph. Defaults to the last day in the dataset
+        #[clap(long)]
+        date: Option<NaiveDate>,
+    },
+
+    /// Bills a household's typical daily load under each retail tariff in a
+    /// TOML file, including real-time plans that pass wholesale prices through.
+    // cargo run analyze tariff-costs data/prices.csv data/household_load.csv data/tariffs.toml
+    //     --out-csv results/tariff_costs.csv
+    #[clap(hide = true)]
+    WriteTariffCosts {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of `time,kw` rows giving the household's demand through the day
+        load_csv: PathBuf,
+
+        /// A TOML file defining the tariffs
+        tariffs_toml: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Finds the cheapest five-minute slots to charge an electric vehicle in
+    /// overnight, and what that saves over charging as soon as it's plugged in.
+    // cargo run optimize-charging data/prices.csv results/charging.csv --per-day
+    OptimizeCharging {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        /// Energy the vehicle needs by the end of the window, in kWh
+        #[clap(long, default_value_t = 40.)]
+        kwh: f64,
+
+        /// The charger's power, in kW
+        #[clap(long, default_value_t = 7.2)]
+        power_kw: f64,
+
+        /// When the vehicle is plugged in
+        #[clap(long, default_value = "18:00")]
+        window_start: NaiveTime,
+
+        /// When the vehicle leaves, the next morning if it's before the start
+        #[clap(long, default_value = "07:00")]
+        window_end: NaiveTime,
+
+        /// Plans every day's session instead of one against the average day
+        #[clap(long)]
+        per_day: bool,
+
+        /// Whether to fill the cheapest or the cleanest slots first
+        #[clap(long, value_enum, default_value_t)]
+        objective: ChargingObjective,
+
+        /// A csv of the form output by parse-gen-csv, to weigh each slot by
+        /// the grid's carbon intensity. Needed by --objective emissions
+        #[clap(long)]
+        gen_csv: Option<PathBuf>,
+
+        /// Also writes each plan's charging slots here, one row per slot
+        #[clap(long)]
+        schedule_csv: Option<PathBuf>,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+    /// Records import and export energy and the average price in each
+    /// direction by hour of day. Negative values in the Imports column are
+    /// treated as exports.
+    // cargo run analyze interchange data/prices.csv data/gen.csv --out-csv results/interchange.csv
+    #[clap(hide = true)]
+    WriteInterchange {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs the values from write-interchange as a diverging bar chart.
+    // cargo run analyze interchange data/prices.csv data/gen.csv --out-png results/interchange.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphInterchange {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+    },
+
+    /// Records the average MW flowing in from each neighboring balancing
+    /// authority by hour of day. Exports are negative.
+    // cargo run analyze ba-interchange data/interchange.csv --out-csv results/ba_interchange.csv
+    #[clap(hide = true)]
+    WriteBaInterchange {
+        /// A csv of the form output by fetch-eia --interchange-csv
+        interchange_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs the values from write-ba-interchange as bars stacked by
+    /// neighbor, imports above zero and exports below.
+    // cargo run analyze ba-interchange data/interchange.csv --out-png results/ba_interchange.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphBaInterchange {
+        /// A csv output by fetch-eia --interchange-csv
+        interchange_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+    },
+
+    /// Records battery discharge and charge energy and the average price of
+    /// each by hour of day, instead of netting them in one signed column.
+    // cargo run analyze battery-split data/prices.csv data/gen.csv --out-csv results/battery_split.csv
+    #[clap(hide = true)]
+    WriteBatterySplit {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs the values from write-battery-split with discharge above the
+    /// axis and charge below it.
+    // cargo run analyze battery-split data/prices.csv data/gen.csv --out-png results/battery_split.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphBatterySplit {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+    },
+
+    /// Records each day's spread between the average of its N most expensive
+    /// and N cheapest five-minute prices, approximating the arbitrage
+    /// opportunity available to storage that day.
+    // cargo run analyze arbitrage-spread data/prices.csv --out-csv results/arbitrage_spread.csv
+    #[clap(hide = true)]
+    WriteArbitrageSpread {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// Where the output csv will be written
+        csv_out: PathBuf,
+
+        #[clap(flatten)]
+        spread: SpreadArgs,
+
+        #[clap(flatten)]
+        table: TableArgs,
+    },
+
+    /// Graphs the values from write-arbitrage-spread across the year.
+    // cargo run analyze arbitrage-spread data/prices.csv --out-png results/arbitrage_spread.png
+    #[clap(hide = true)]
+    #[cfg(feature = "graph")]
+    GraphArbitrageSpread {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        spread: SpreadArgs,
+    },
+
+    /// Graphs daily average price across the whole dataset with a rolling
+    /// average over it.
+    // cargo run graph-price-timeseries data/prices.csv results/prices_timeseries.png
+    #[cfg(feature = "graph")]
+    GraphPriceTimeseries {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        rolling: RollingArgs,
+    },
+
+    /// Graphs the rolling daily average output of each source across the
+    /// whole dataset.
+    // cargo run graph-gen-timeseries data/gen.csv results/gen_timeseries.png
+    #[cfg(feature = "graph")]
+    GraphGenTimeseries {
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        rolling: RollingArgs,
+    },
+
+    /// Answers price and generation queries over HTTP for a dashboard,
+    /// with JSON profiles at /price-profile and /gen-profile, charts at
+    /// /charts/price.png and /charts/gen.png, and Prometheus gauges of the
+    /// latest data at /metrics. See the `serve` module for the query
+    /// parameters.
+    // cargo run serve data/prices.csv data/gen.csv --addr 127.0.0.1:8080
+    Serve {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// The address to listen on
+        #[clap(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+
+    /// Watches the latest price and generation for thresholds being
+    /// crossed, printing each crossing and passing it to a command or
+    /// webhook. Keeps checking as the csvs are updated.
+    // cargo run monitor data/prices.csv data/gen.csv --price-above 100 --command 'notify-send "$ENERGY_ALERT_MESSAGE"'
+    Monitor {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Alerts when the price rises above this many $/MWh
+        #[clap(long)]
+        price_above: Option<f64>,
+
+        /// Alerts when the price falls below this many $/MWh
+        #[clap(long)]
+        price_below: Option<f64>,
+
+        /// Alerts when solar rises above this percent of generation
+        #[clap(long)]
+        solar_share_above: Option<f64>,
+
+        /// A shell command run on each alert, which gets the alert in
+        /// ENERGY_ALERT_KIND, _TIME, _VALUE, _THRESHOLD and _MESSAGE
+        #[clap(long)]
+        command: Option<String>,
+
+        /// An http:// or https:// URL each alert is POSTed to as JSON, given
+        /// ten seconds to answer
+        #[clap(long)]
+        webhook: Option<String>,
+
+        /// Checks the data once and exits instead of watching it
+        #[clap(long)]
+        once: bool,
+    },
+
+    /// Runs every step of a pipeline TOML in order, so a whole analysis can
+    /// be reproduced with one command. Each step is a subcommand and its
+    /// arguments, which may reference the pipeline's named artifacts.
+    // cargo run pipeline pipeline.toml
+    Pipeline {
+        /// A TOML file of `[artifacts]` and `[[steps]]`
+        pipeline_toml: PathBuf,
+    },
+
+    /// Prints a shell completion script for every command and option.
+    // cargo run completions bash > ~/.local/share/bash-completion/completions/energy_analysis
+    Completions {
+        /// The shell to generate completions for
+        shell: clap_complete::Shell,
+    },
+
+    /// Writes manpages for the tool and each of its commands.
+    // cargo run man target/man
+    Man {
+        /// A directory the .1 files are written into
+        output_dir: PathBuf,
+    },
+
+    /// Writes synthetic raw price and gen CSVs for tests and benchmarks.
+    // cargo run --features testdata gen-testdata /tmp/price_raw.csv /tmp/gen_raw.csv --days 365
+    #[clap(hide = true)]
+    #[cfg(feature = "testdata")]
+    GenTestdata {
+        /// Where the raw price csv will be written
+        price_csv: PathBuf,
+
+        /// Where the raw gen csv will be written
+        gen_csv: PathBuf,
+
+        /// Number of days of five-minute data to generate
+        #[clap(long, default_value_t = 7)]
+        days: usize,
+
+        /// Seed for the noise, so runs are reproducible
+        #[clap(long, default_value_t = 0)]
+        seed: u64,
+
+        /// Noise as a fraction of each value
+        #[clap(long, default_value_t = 0.05)]
+        noise: f64,
+
+        /// Drops every nth price interval
+        #[clap(long)]
+        price_gap_every: Option<usize>,
+
+        /// Drops every nth gen interval
+        #[clap(long)]
+        gen_gap_every: Option<usize>,
+
+        /// Also writes a raw OASIS nodal extract here
+        #[clap(long)]
+        nodal_csv: Option<PathBuf>,
+
+        /// How many nodes the nodal extract prices
+        #[clap(long, default_value_t = 20)]
+        nodes: usize,
+
+        /// Also writes a raw OASIS ancillary service extract here
+        #[clap(long)]
+        ancillary_csv: Option<PathBuf>,
+    },
+}
+
+/// The analyses that can be both written and graphed. Each computes once,
+/// then writes a csv, draws a chart, or both.
+#[derive(clap::Subcommand, Clone, Debug)]
+enum Analysis {
+    /// The price five-minute averages.
+    // cargo run analyze price-minutes data/prices.csv --out-csv results/prices_avg.csv
+    //     --out-png results/prices.png
+    // cargo run analyze price-minutes data/prices.csv --facet quarter --out-png results/prices_quarters.png
+    PriceMinutes {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        agg: AggArgs,
+
+        #[clap(flatten)]
+        clip: ClipArgs,
+
+        #[clap(flatten)]
+        samples: SampleArgs,
+
+        #[clap(flatten)]
+        sun: SunArgs,
+
+        /// Charts a panel per period in a grid, e.g. one per quarter or
+        /// month, instead of a single average day
+        #[clap(long, value_enum, conflicts_with = "sun")]
+        facet: Option<Period>,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// The generation distribution five-minute averages.
+    // cargo run analyze gen-minutes data/gen.csv --out-csv results/gen_avg.csv
+    //     --out-png results/gen.png
+    GenMinutes {
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        agg: AggArgs,
+
+        #[clap(flatten)]
+        samples: SampleArgs,
+
+        #[clap(flatten)]
+        sun: SunArgs,
+
+        /// Charts a panel per period in a grid, e.g. one per quarter or
+        /// month, instead of a single average day
+        #[clap(long, value_enum, conflicts_with_all = ["sun", "band_source", "share"])]
+        facet: Option<Period>,
+
+        /// Shades this source's 10th-90th percentile output behind its
+        /// line on the chart, e.g. "Solar"
+        #[clap(long)]
+        band_source: Option<String>,
+
+        /// Each source's percent share of generation instead of MW
+        #[clap(long, conflicts_with = "band_source")]
+        share: bool,
+
+        #[clap(flatten)]
+        legend: LegendArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// gen-minutes but merges the solar and battery columns.
+    // cargo run analyze gen-solar-battery data/gen.csv --out-png results/gen_solar_battery.png
+    GenSolarBattery {
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        agg: AggArgs,
+
+        #[clap(flatten)]
+        samples: SampleArgs,
+
+        #[clap(flatten)]
+        sun: SunArgs,
+
+        #[clap(flatten)]
+        legend: LegendArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// The average dollar value of each type of electricity.
+    // cargo run analyze value-minutes data/prices.csv data/gen.csv --out-png results/values.png
+    ValueMinutes {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        #[clap(flatten)]
+        scale: ScaleArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// value-minutes with solar + battery output in a single column.
+    // cargo run analyze value-solar-battery data/prices.csv data/gen.csv
+    //     --out-png results/solar_battery.png
+    ValueSolarBattery {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// value-minutes and value-solar-battery side by side, showing what
+    /// pairing batteries with solar adds to solar's value.
+    // cargo run analyze value-comparison data/prices.csv data/gen.csv
+    //     --out-csv results/value_comparison.csv --out-png results/value_comparison.png
+    ValueComparison {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Each source's revenue on every day of the dataset, charted with a
+    /// rolling average.
+    // cargo run analyze daily-revenue data/prices.csv data/gen.csv
+    //     --out-csv results/daily_revenue.csv --out-png results/daily_revenue.png
+    DailyRevenue {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        #[clap(flatten)]
+        rolling: RollingArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Each source's realized $/MWh in every five-minute window of the day.
+    // cargo run analyze value-profile data/prices.csv data/gen.csv
+    //     --out-png results/values_profile.png
+    ValueProfile {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        sun: SunArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Solar + battery value and arbitrage revenue as hypothetical
+    /// solar-charged batteries of increasing size are added.
+    // cargo run analyze storage-sweep data/prices.csv data/gen.csv
+    //     --out-csv results/storage_sweep.csv --out-png results/storage_sweep.png
+    StorageSweep {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// The largest battery capacity simulated, in GWh
+        #[clap(long, default_value_t = 40.)]
+        max_gwh: f64,
+
+        /// The capacity increment between simulated batteries, in GWh
+        #[clap(long, default_value_t = 2.)]
+        step_gwh: f64,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// The historical and scenario average price of each five-minute
+    /// window when a source's output is scaled.
+    // cargo run analyze price-impact data/prices.csv data/gen.csv --scale-source Solar
+    //     --scale-factor 2 --out-png results/price_impact.png
+    PriceImpact {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        #[clap(flatten)]
+        scale: ScaleArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Net load and price over the day with part of solar's output lost
+    /// during a window, as on a smoky day or during an eclipse, averaged
+    /// over the days the window covers.
+    /*
+    cargo run analyze solar-loss data/prices.csv data/gen.csv --loss 0.6 --window-start 09:00 \
+        --window-end 11:30 --start 2024-04-08 --end 2024-04-08 --out-png results/solar_loss.png
+    */
+    SolarLoss {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// The fraction of solar output lost inside the window, from 0 to 1
+        #[clap(long, default_value_t = 0.5, value_parser = parse_fraction)]
+        loss: f64,
+
+        #[clap(flatten)]
+        window: WindowArgs,
+
+        /// How strongly price responds to the change in net load
+        #[clap(long, default_value_t = SourceScaling::DEFAULT_ELASTICITY)]
+        elasticity: f64,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Runs of intervals where price exceeds a threshold, each with its
+    /// start, end, duration, and peak.
+    // cargo run analyze price-spikes data/prices.csv --z-score 4
+    //     --out-csv results/price_spikes.csv --out-png results/price_spikes.png
+    PriceSpikes {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        #[clap(flatten)]
+        threshold: SpikeArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// The chance price exceeds each of a few thresholds in each slot of
+    /// the day or week, for judging when demand response or backup
+    /// generation would pay.
+    /*
+    cargo run analyze price-exceedance data/prices.csv --thresholds 100,200,500 \
+        --out-csv results/price_exceedance.csv --out-png results/price_exceedance.png
+    */
+    PriceExceedance {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// Prices in $/MWh to count intervals above
+        #[clap(long, num_args = 1.., value_delimiter = ',', default_values_t = [100., 200., 500.])]
+        thresholds: Vec<f64>,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        samples: SampleArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// How far each slot's average price, or a source's average output,
+    /// moved from one period to another, such as before and after a jump in
+    /// battery capacity, with a 95% confidence interval and p-value for each.
+    /*
+    cargo run analyze period-diff data/prices.csv --before 2023-04-01..2023-06-30 \
+        --after 2024-04-01..2024-06-30 --out-csv results/period_diff.csv \
+        --out-png results/period_diff.png
+    */
+    PeriodDiff {
+        /// A csv of the form output by parse-price-csv or parse-gen-csv
+        csv_in: PathBuf,
+
+        /// The column compared: Price, or a source like Solar
+        #[clap(long, default_value = "Price")]
+        column: String,
+
+        /// The base period, as START..END with both days included
+        #[clap(long, value_parser = parse_date_range)]
+        before: RangeInclusive<NaiveDate>,
+
+        /// The period compared with it, as START..END
+        #[clap(long, value_parser = parse_date_range)]
+        after: RangeInclusive<NaiveDate>,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// The Pearson correlation of each generation source with price, across
+    /// the whole dataset and within each hour of the day.
+    // cargo run analyze correlation data/prices.csv data/gen.csv --out-png results/correlation.png
+    Correlation {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        #[clap(flatten)]
+        threads: ThreadArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Average output as capacity factors, using a TOML file of installed
+    /// capacity by source.
+    // cargo run analyze capacity-factors data/gen.csv data/capacity.toml
+    //     --out-png results/capacity_factors.png
+    CapacityFactors {
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A TOML file mapping source names to installed MW
+        capacity_toml: PathBuf,
+
+        /// Computes one capacity factor per month instead of per time of day
+        #[clap(long)]
+        by_month: bool,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Average price at each level of implied reserve margin: the capacity
+    /// that could have been called on beyond demand, from a TOML file of
+    /// installed capacity by source and the hourly demand fetch-eia writes.
+    // cargo run analyze reserve-margin data/prices.csv data/gen.csv data/demand.csv
+    //     data/capacity.toml --out-png results/reserve_margin.png
+    ReserveMargin {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A csv of hourly demand, as fetch-eia --demand-csv writes
+        demand_csv: PathBuf,
+
+        /// A TOML file mapping source names to installed MW
+        capacity_toml: PathBuf,
+
+        /// The width of each margin band, as a fraction of demand
+        #[clap(long, default_value_t = 0.05)]
+        bin_width: f64,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Average price, generation share, and value by source in solar and
+    /// non-solar hours.
+    // cargo run analyze solar-split data/prices.csv data/gen.csv --out-png results/solar_split.png
+    SolarSplit {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Solar output in MW above which an interval counts as a solar hour
+        #[clap(long, default_value_t = 500.)]
+        solar_threshold: f64,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Average price, generation share, and value by source in each
+    /// time-of-use period of a TOML schedule.
+    // cargo run analyze tou-summary data/prices.csv data/gen.csv data/tou.toml
+    //     --out-png results/tou_summary.png
+    TouSummary {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A TOML file defining the time-of-use periods
+        tou_toml: PathBuf,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Average price, generation share, and value by source in each
+    /// California water year, with the year's hydro output charted. Further
+    /// datasets from --dataset are pooled in, so wet and dry years parsed
+    /// separately compare on one chart.
+    // cargo run analyze hydro-years data/prices.csv data/gen.csv data/water_years.toml
+    //     --dataset data/prices_2024.csv data/gen_2024.csv --out-png results/hydro_years.png
+    HydroYears {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A TOML file mapping water years to their type
+        water_years_toml: PathBuf,
+
+        /// Another price and gen csv to pool in, such as another year's.
+        /// Repeatable, in time order
+        #[clap(long, num_args = 2, value_names = ["PRICE_CSV", "GEN_CSV"])]
+        dataset: Vec<PathBuf>,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// How wind and solar output vary across days in each five-minute
+    /// window, alone and combined.
+    // cargo run analyze complementarity data/gen.csv --out-png results/complementarity.png
+    Complementarity {
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Each day's high temperature beside its peak price and evening ramp,
+    /// with how well temperature predicts each.
+    /*
+    cargo run analyze weather-correlation data/prices.csv data/gen.csv \
+        data/temperature_5min.csv --out-png results/weather.png
+    */
+    WeatherCorrelation {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// Temperatures, such as a csv output by align-series
+        weather_csv: PathBuf,
+
+        #[clap(flatten)]
+        weather: WeatherArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// The price, generation mix, and value by source in the hours with the
+    /// highest net load, against every other hour.
+    // cargo run analyze net-load-peaks data/prices.csv data/gen.csv
+    //     --out-png results/net_load_peaks.png
+    NetLoadPeaks {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// How many of the highest net-load hours to report
+        #[clap(long, default_value_t = 100)]
+        top: usize,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// When in the day price and net load peak, by month, and the days
+    /// either peak lastingly shifted to a new time of day.
+    // cargo run analyze peak-timing data/prices.csv data/gen.csv
+    //     --out-csv results/peak_timing.csv --out-png results/peak_timing.png
+    PeakTiming {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// The fewest days either side of a reported shift
+        #[clap(long, default_value_t = 14)]
+        min_days: usize,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Import and export energy and the average price in each direction by
+    /// hour of day.
+    // cargo run analyze interchange data/prices.csv data/gen.csv --out-png results/interchange.png
+    Interchange {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// The average MW flowing in from each neighboring balancing authority
+    /// by hour of day.
+    // cargo run analyze ba-interchange data/interchange.csv --out-png results/ba_interchange.png
+    BaInterchange {
+        /// A csv of the form output by fetch-eia --interchange-csv
+        interchange_csv: PathBuf,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Battery discharge and charge energy and the average price of each by
+    /// hour of day.
+    // cargo run analyze battery-split data/prices.csv data/gen.csv
+    //     --out-png results/battery_split.png
+    BatterySplit {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of the form output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Each day's spread between the average of its N most expensive and N
+    /// cheapest five-minute prices.
+    // cargo run analyze arbitrage-spread data/prices.csv --out-png results/arbitrage_spread.png
+    ArbitrageSpread {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        #[clap(flatten)]
+        spread: SpreadArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Each zone's congestion and loss components of its LMP, averaged
+    /// over the day or week.
+    /*
+    cargo run analyze price-components data/prices.csv \
+        --out-csv results/price_components.csv --out-png results/price_components.png
+    */
+    PriceComponents {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        agg: AggArgs,
+
+        #[clap(flatten)]
+        samples: SampleArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Each area's zone LMP beside its trading hub's, averaged over the day
+    /// or week.
+    /*
+    cargo run analyze hub-zone data/prices.csv data/hub_prices.csv \
+        --out-csv results/hub_zone.csv --out-png results/hub_zone.png
+    */
+    HubZone {
+        /// A csv output by parse-price-csv from zone files
+        zone_csv: PathBuf,
+
+        /// A csv output by parse-price-csv from trading hub files
+        hub_csv: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        days: DayArgs,
+
+        #[clap(flatten)]
+        agg: AggArgs,
+
+        #[clap(flatten)]
+        samples: SampleArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// The nodes with the highest average congestion component, found in
+    /// one streaming pass over a parsed nodal csv.
+    /*
+    cargo run analyze congested-nodes data/nodal.csv --top 20 \
+        --out-csv results/congested_nodes.csv --out-png results/congested_nodes.png
+    */
+    CongestedNodes {
+        /// A csv output by parse-nodal-csv
+        nodal_csv: PathBuf,
+
+        /// How many nodes to keep
+        #[clap(long, default_value_t = 10)]
+        top: usize,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Each chosen node's LMP and congestion component averaged over the
+    /// day or week, read through the node index.
+    /*
+    cargo run analyze node-profile data/nodal.csv --node TH_NP15_GEN-APND --node TH_SP15_GEN-APND \
+        --out-csv results/node_profile.csv --out-png results/node_profile.png
+    */
+    NodeProfile {
+        /// A csv output by parse-nodal-csv
+        nodal_csv: PathBuf,
+
+        /// The ID of a node to profile. Repeat it to compare nodes
+        #[clap(long = "node", required = true)]
+        nodes: Vec<String>,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// The spark spread, price less the fuel cost of a gas plant at a
+    /// given heat rate, averaged over the day or week.
+    /*
+    cargo run analyze spark-spread data/prices.csv data/gas.csv --heat-rate 7.5 \
+        --out-csv results/spark_spread.csv --out-png results/spark_spread.png
+    */
+    SparkSpread {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// Daily natural gas hub prices in $/MMBtu, one row per trading day
+        gas_csv: PathBuf,
+
+        #[clap(flatten)]
+        gas: GasArgs,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// A battery's revenue from energy arbitrage alone, regulation alone,
+    /// and the two co-optimized, in $/kW-year.
+    /*
+    cargo run analyze storage-revenue data/prices.csv data/ancillary.csv --power-mw 100 \
+        --out-csv results/storage_revenue.csv --out-png results/storage_revenue.png
+    */
+    StorageRevenue {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-ancillary-csv
+        ancillary_csv: PathBuf,
+
+        #[clap(flatten)]
+        battery: BatteryArgs,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// A battery's revenue in each month, stacked by source: energy
+    /// arbitrage, regulation up and down, and spinning reserve.
+    /*
+    cargo run analyze value-stack data/prices.csv data/ancillary.csv \
+        --out-csv results/value_stack.csv --out-png results/value_stack.png
+    */
+    ValueStack {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-ancillary-csv
+        ancillary_csv: PathBuf,
+
+        #[clap(flatten)]
+        battery: BatteryArgs,
+
+        /// What the battery's hours are sold for
+        #[clap(long, value_enum, default_value_t = Dispatch::CoOptimized)]
+        dispatch: Dispatch,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Prices for each five-minute interval of the day after the dataset
+    /// ends from a naive baseline, for models to be measured against. The
+    /// chart is the baseline's backtest of one day against actual prices.
+    /*
+    cargo run analyze price-forecast data/prices.csv \
+        --out-csv results/price_forecast.csv --out-png results/price_forecast.png
+    */
+    PriceForecast {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        #[clap(long, value_enum, default_value_t = Baseline::Yesterday)]
+        baseline: Baseline,
+
+        /// The day to chart. Defaults to the last day in the dataset
+        #[clap(long)]
+        date: Option<NaiveDate>,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// Every naive baseline backtested over the dataset, with the mean
+    /// absolute error and mean absolute percentage error of each. The chart
+    /// is each baseline's forecast of one day against actual prices.
+    /*
+    cargo run analyze forecast-errors data/prices.csv \
+        --out-csv results/forecast_errors.csv --out-png results/forecast_errors.png
+    */
+    ForecastErrors {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// The day to chart. Defaults to the last day in the dataset
+        #[clap(long)]
+        date: Option<NaiveDate>,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+
+    /// A household's typical daily load billed under each retail tariff in a
+    /// TOML file, including real-time plans that pass wholesale prices
+    /// through.
+    /*
+    cargo run analyze tariff-costs data/prices.csv data/household_load.csv data/tariffs.toml \
+        --out-csv results/tariff_costs.csv --out-png results/tariff_costs.png
+    */
+    TariffCosts {
+        /// A csv of the form output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv of `time,kw` rows giving the household's demand through the day
+        load_csv: PathBuf,
+
+        /// A TOML file defining the tariffs
+        tariffs_toml: PathBuf,
+
+        #[clap(flatten)]
+        out: OutArgs,
+    },
+}
+
+/// The scenarios graph-diff compares, each as a base and another scenario.
+#[cfg(feature = "graph")]
+#[derive(clap::Subcommand, Clone, Debug)]
+enum Diff {
+    /// The price profile of one price csv less that of another, such as
+    /// 2024's less 2023's.
+    // cargo run graph-diff price-profile data/prices_2023.csv data/prices_2024.csv results/price_diff.png
+    PriceProfile {
+        /// A csv output by parse-price-csv, the base scenario
+        base_csv: PathBuf,
+
+        /// A csv output by parse-price-csv, compared against the base
+        other_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+    },
+
+    /// Value by source with solar and battery output merged, less the value
+    /// of each as recorded.
+    // cargo run graph-diff solar-battery data/prices.csv data/gen.csv results/solar_battery_diff.png
+    SolarBattery {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+    },
+
+    /// Value by source with one source's output scaled, less the value of
+    /// each as recorded.
+    // cargo run graph-diff scaled data/prices.csv data/gen.csv results/scaled_diff.png \
+    //     --scale-source Solar --scale-factor 2
+    Scaled {
+        /// A csv output by parse-price-csv
+        price_csv: PathBuf,
+
+        /// A csv output by parse-gen-csv
+        gen_csv: PathBuf,
+
+        /// A png file where the graph should be written, or an svg file for
+        /// a vector image.
+        output_png: PathBuf,
+
+        #[clap(flatten)]
+        scale: ScaleArgs,
+    },
+}
+
+/// Where an analysis writes what it computes. Either or both may be given.
+#[derive(clap::Args, Clone, Debug)]
+struct OutArgs {
+    /// Where the output csv will be written
+    #[cfg_attr(feature = "graph", clap(long, required_unless_present = "out_png"))]
+    #[cfg_attr(not(feature = "graph"), clap(long, required = true))]
+    out_csv: Option<PathBuf>,
+
+    /// A png file where the graph should be written, or an svg file for
+    /// a vector image.
+    #[cfg(feature = "graph")]
+    #[clap(long)]
+    out_png: Option<PathBuf>,
+
+    #[clap(flatten)]
+    table: TableArgs,
+}
+
+impl OutArgs {
+    /// The output of an old write- command.
+    fn csv(csv_out: PathBuf, table: TableArgs) -> Self {
+        OutArgs {
+            out_csv: Some(csv_out),
+            #[cfg(feature = "graph")]
+            out_png: None,
+            table,
+        }
+    }
+
+    /// The output of an old graph- command.
+    #[cfg(feature = "graph")]
+    fn png(output_png: PathBuf) -> Self {
+        OutArgs {
+            out_csv: None,
+            out_png: Some(output_png),
+            table: TableArgs::default(),
+        }
+    }
+
+    /// Runs `write` against the csv, if one was asked for.
+    fn write_csv(
+        &self,
+        options: WriteOptions,
+        write: impl FnOnce(&Path) -> error::Result<()>,
+    ) -> anyhow::Result<()> {
+        match &self.out_csv {
+            Some(out) => self.table.write(out, options, write),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether a chart was asked for.
+    #[cfg(feature = "graph")]
+    fn is_graph(&self) -> bool {
+        self.out_png.is_some()
+    }
+
+    #[cfg(not(feature = "graph"))]
+    fn is_graph(&self) -> bool {
+        false
+    }
+}
+
+impl Analysis {
+    fn out(&self) -> &OutArgs {
+        match self {
+            Analysis::PriceMinutes { out, .. }
+            | Analysis::GenMinutes { out, .. }
+            | Analysis::GenSolarBattery { out, .. }
+            | Analysis::ValueMinutes { out, .. }
+            | Analysis::ValueSolarBattery { out, .. }
+            | Analysis::ValueComparison { out, .. }
+            | Analysis::DailyRevenue { out, .. }
+            | Analysis::ValueProfile { out, .. }
+            | Analysis::StorageSweep { out, .. }
+            | Analysis::PriceImpact { out, .. }
+            | Analysis::SolarLoss { out, .. }
+            | Analysis::PriceSpikes { out, .. }
+            | Analysis::PriceExceedance { out, .. }
+            | Analysis::PeriodDiff { out, .. }
+            | Analysis::Correlation { out, .. }
+            | Analysis::CapacityFactors { out, .. }
+            | Analysis::ReserveMargin { out, .. }
+            | Analysis::SolarSplit { out, .. }
+            | Analysis::TouSummary { out, .. }
+            | Analysis::HydroYears { out, .. }
+            | Analysis::Complementarity { out, .. }
+            | Analysis::WeatherCorrelation { out, .. }
+            | Analysis::NetLoadPeaks { out, .. }
+            | Analysis::PeakTiming { out, .. }
+            | Analysis::Interchange { out, .. }
+            | Analysis::BaInterchange { out, .. }
+            | Analysis::BatterySplit { out, .. }
+            | Analysis::ArbitrageSpread { out, .. }
+            | Analysis::PriceComponents { out, .. }
+            | Analysis::HubZone { out, .. }
+            | Analysis::CongestedNodes { out, .. }
+            | Analysis::NodeProfile { out, .. }
+            | Analysis::SparkSpread { out, .. }
+            | Analysis::StorageRevenue { out, .. }
+            | Analysis::ValueStack { out, .. }
+            | Analysis::PriceForecast { out, .. }
+            | Analysis::ForecastErrors { out, .. }
+            | Analysis::TariffCosts { out, .. } => out,
+        }
+    }
+
+    /// The files the analysis reads, and where it writes.
+    fn paths(&mut self) -> (Vec<&mut PathBuf>, &mut OutArgs) {
+        match self {
+            Analysis::PriceMinutes { price_csv, out, .. }
+            | Analysis::PriceSpikes { price_csv, out, .. }
+            | Analysis::PriceExceedance { price_csv, out, .. }
+            | Analysis::PeriodDiff {
+                csv_in: price_csv,
+                out,
+                ..
+            }
+            | Analysis::ArbitrageSpread { price_csv, out, .. }
+            | Analysis::PriceComponents { price_csv, out, .. }
+            | Analysis::PriceForecast { price_csv, out, .. }
+            | Analysis::ForecastErrors { price_csv, out, .. } => (vec![price_csv], out),
+            Analysis::GenMinutes { gen_csv, out, .. }
+            | Analysis::GenSolarBattery { gen_csv, out, .. }
+            | Analysis::Complementarity { gen_csv, out } => (vec![gen_csv], out),
+            Analysis::BaInterchange {
+                interchange_csv,
+                out,
+            } => (vec![interchange_csv], out),
+            Analysis::ValueMinutes {
+                price_csv,
+                gen_csv,
+                out,
+                ..
+            }
+            | Analysis::ValueSolarBattery {
+                price_csv,
+                gen_csv,
+                out,
+            }
+            | Analysis::ValueComparison {
+                price_csv,
+                gen_csv,
+                out,
+            }
+            | Analysis::DailyRevenue {
+                price_csv,
+                gen_csv,
+                out,
+                ..
+            }
+            | Analysis::ValueProfile {
+                price_csv,
+                gen_csv,
+                out,
+                ..
+            }
+            | Analysis::StorageSweep {
+                price_csv,
+                gen_csv,
+                out,
+                ..
+            }
+            | Analysis::PriceImpact {
+                price_csv,
+                gen_csv,
+                out,
+                ..
+            }
+            | Analysis::SolarLoss {
+                price_csv,
+                gen_csv,
+                out,
+                ..
+            }
+            | Analysis::Correlation {
+                price_csv,
+                gen_csv,
+                out,
+                ..
+            }
+            | Analysis::SolarSplit {
+                price_csv,
+                gen_csv,
+                out,
+                ..
+            }
+            | Analysis::NetLoadPeaks {
+                price_csv,
+                gen_csv,
+                out,
+                ..
+            }
+            | Analysis::PeakTiming {
+                price_csv,
+                gen_csv,
+                out,
+                ..
+            }
+            | Analysis::Interchange {
+                price_csv,
+                gen_csv,
+                out,
+            }
+            | Analysis::BatterySplit {
+                price_csv,
+                gen_csv,
+                out,
+            } => (vec![price_csv, gen_csv], out),
+            Analysis::CapacityFactors {
+                gen_csv,
+                capacity_toml,
+                out,
+                ..
+            } => (vec![gen_csv, capacity_toml], out),
+            Analysis::ReserveMargin {
+                price_csv,
+                gen_csv,
+                demand_csv,
+                capacity_toml,
+                out,
+                ..
+            } => (vec![price_csv, gen_csv, demand_csv, capacity_toml], out),
+            Analysis::TariffCosts {
+                price_csv,
+                load_csv,
+                tariffs_toml,
+                out,
+            } => (vec![price_csv, load_csv, tariffs_toml], out),
+            Analysis::TouSummary {
+                price_csv,
+                gen_csv,
+                tou_toml: extra,
+                out,
+            }
+            | Analysis::WeatherCorrelation {
+                price_csv,
+                gen_csv,
+                weather_csv: extra,
+                out,
+                ..
+            } => (vec![price_csv, gen_csv, extra], out),
+            Analysis::HydroYears {
+                price_csv,
+                gen_csv,
+                water_years_toml,
+                dataset,
+                out,
+            } => {
+                let mut inputs = vec![price_csv, gen_csv, water_years_toml];
+                inputs.extend(dataset);
+                (inputs, out)
+            }
+            Analysis::SparkSpread {
+                price_csv,
+                gas_csv,
+                out,
+                ..
+            } => (vec![price_csv, gas_csv], out),
+            Analysis::HubZone {
+                zone_csv,
+                hub_csv,
+                out,
+                ..
+            } => (vec![zone_csv, hub_csv], out),
+            Analysis::CongestedNodes { nodal_csv, out, .. }
+            | Analysis::NodeProfile { nodal_csv, out, .. } => (vec![nodal_csv], out),
+            Analysis::StorageRevenue {
+                price_csv,
+                ancillary_csv,
+                out,
+                ..
+            }
+            | Analysis::ValueStack {
+                price_csv,
+                ancillary_csv,
+                out,
+                ..
+            } => (vec![price_csv, ancillary_csv], out),
+        }
+    }
+}
+
+/// Options for what counts as a price spike.
+#[derive(clap::Args, Clone, Debug)]
+#[group(required = true, multiple = false)]
+struct SpikeArgs {
+    /// A fixed $/MWh price above which intervals are spikes
+    #[clap(long)]
+    threshold: Option<f64>,
+
+    /// Standard deviations above the mean price above which intervals are spikes
+    #[clap(long)]
+    z_score: Option<f64>,
+}
+
+impl SpikeArgs {
+    fn threshold(&self) -> SpikeThreshold {
+        match (self.threshold, self.z_score) {
+            (Some(price), _) => SpikeThreshold::Absolute(price),
+            (None, Some(z)) => SpikeThreshold::ZScore(z),
+            (None, None) => unreachable!("clap requires one spike threshold"),
+        }
+    }
+}
+
+/// Which repeating cycle an average profile covers.
+#[derive(clap::Args, Clone, Debug)]
+struct ProfileArgs {
+    /// Average over each five-minute window of the day, or each hour of the week
+    #[clap(long, value_enum, default_value_t = Profile::Day)]
+    profile: Profile,
+}
+
+/// Which days count toward an average profile.
+#[derive(clap::Args, Clone, Debug)]
+struct DayArgs {
+    /// Leaves out the first and last days when the data starts or ends
+    /// partway through them
+    #[clap(long)]
+    trim_partial_days: bool,
+
+    /// Leaves out February 29
+    #[clap(long)]
+    skip_leap_days: bool,
+}
+
+impl DayArgs {
+    fn policy(&self) -> DayPolicy {
+        DayPolicy {
+            trim_partial: self.trim_partial_days,
+            skip_leap_days: self.skip_leap_days,
+        }
+    }
+}
+
+/// How each slot of a price or generation profile summarizes its intervals.
+#[derive(clap::Args, Clone, Debug)]
+struct AggArgs {
+    /// mean, median, or trimmed-mean(p), which averages what's left after
+    /// dropping fraction p of each slot's intervals from each end
+    #[clap(long, default_value = "mean", value_parser = parse_profile_agg)]
+    agg: ProfileAgg,
+}
+
+/// Whether to winsorize prices before profiling them.
+#[derive(clap::Args, Clone, Debug)]
+struct ClipArgs {
+    /// Holds prices between two percentiles before averaging, e.g. "1,99",
+    /// so a few extreme intervals don't swamp the profile
+    #[clap(long, value_parser = parse_clip)]
+    clip_percentile: Option<Clip>,
+}
+
+impl ClipArgs {
+    /// Says what the clip held prices to, if there was one.
+    fn report(&self, clipped: Option<ClipBounds>, settings: &Settings) {
+        let (Some(clip), Some(bounds)) = (self.clip_percentile, clipped) else {
+            return;
+        };
+        let clipped = format!(
+            "{:.2} to {:.2} {}, percentiles {} to {}",
+            bounds.low, bounds.high, settings.labels.units.price, clip.low, clip.high
+        );
+        println!("Clipped prices to {clipped}");
+        provenance::note("clipped_prices", clipped);
+    }
+}
+
+/// How many intervals each slot of a profile needs.
+#[derive(clap::Args, Clone, Debug, Default)]
+struct SampleArgs {
+    /// The fewest intervals any slot may average. By default a slot may
+    /// trail the fullest slot by twelve days' worth
+    #[clap(long)]
+    min_samples: Option<usize>,
+
+    /// Whether a slot with too few intervals fails the command or only warns
+    #[clap(long, value_enum, default_value_t = FewSamples::Error)]
+    few_samples: FewSamples,
+}
+
+impl SampleArgs {
+    fn check(&self) -> SampleCheck {
+        SampleCheck {
+            min_samples: self.min_samples,
+            on_few: self.few_samples,
+        }
+    }
+}
+
+impl ProfileArgs {
+    #[cfg(feature = "graph")]
+    fn adjective(&self) -> &'static str {
+        match self.profile {
+            Profile::Day => "Daily",
+            Profile::Week => "Weekly",
+        }
+    }
+}
+
+/// Options for marking sunrise and sunset on time-of-day charts.
+#[derive(clap::Args, Clone, Debug, Default)]
+struct SunArgs {
+    /// Marks the average sunrise and sunset over the dates in the data
+    #[clap(long)]
+    sun: bool,
+
+    /// Where the sun times are computed for. Defaults to the config's
+    /// latitude, then a central point in the RTO
+    #[clap(long, requires = "sun", allow_negative_numbers = true)]
+    latitude: Option<f64>,
+
+    /// Defaults to the config's longitude, then a central point in the RTO
+    #[clap(long, requires = "sun", allow_negative_numbers = true)]
+    longitude: Option<f64>,
+}
+
+impl SunArgs {
+    /// Sunrise and sunset markers, or none without `--sun`. Only reads the
+    /// data's dates when they're needed.
+    #[cfg(feature = "graph")]
+    fn markers(
+        &self,
+        settings: &Settings,
+        profile: Profile,
+        dates: impl FnOnce() -> error::Result<Vec<NaiveDate>>,
+    ) -> anyhow::Result<Vec<Marker>> {
+        if !self.sun {
+            return Ok(Vec::new());
+        }
+        if profile != Profile::Day {
+            bail!("--sun only applies to --profile day");
+        }
+        let mut site = settings.site();
+        site.latitude = self.latitude.unwrap_or(site.latitude);
+        site.longitude = self.longitude.unwrap_or(site.longitude);
+        let Some(times) = site.average_sun_times(&dates()?) else {
+            bail!("The sun never rises or sets at {site:?} on these dates");
+        };
+        let marker = |label: &str, minutes: f64| {
+            let slot = (minutes / Compute::MINS_INCR as f64).round() as usize;
+            let slot = slot.min(Compute::slots_per_day() - 1);
+            let (hour, minute) = Compute::idx_5min_to_time(slot);
+            Marker {
+                slot,
+                label: format!("{label} {hour:02}:{minute:02}"),
+            }
+        };
+        Ok(vec![
+            marker("Sunrise", times.sunrise),
+            marker("Sunset", times.sunset),
+        ])
+    }
+}
+
+/// How the line charts of generation order and style their sources and
+/// place their legend.
+#[derive(clap::Args, Clone, Debug, Default)]
+struct LegendArgs {
+    /// The order sources are drawn and listed in: "schema" for the gen
+    /// csv's, "magnitude" for largest average output first, or sources to
+    /// put first, e.g. "Solar,Wind"
+    #[clap(long, default_value = "schema", value_parser = parse_source_order)]
+    source_order: SourceOrder,
+
+    /// Where the legend goes. Outside keeps it off the evening peak
+    #[clap(long, value_enum, default_value_t)]
+    legend: LegendPosition,
+
+    /// Draws this source in its color and the rest in grey, e.g. "Solar"
+    #[clap(long, value_parser = parse_source)]
+    highlight: Option<usize>,
+}
+
+impl LegendArgs {
+    #[cfg(feature = "graph")]
+    fn apply<'a>(&self, graphing: Graphing<'a>) -> Graphing<'a> {
+        graphing
+            .with_source_order(self.source_order.clone())
+            .with_legend(self.legend)
+            .with_highlight(self.highlight)
+    }
+}
+
+/// Options for the daily arbitrage spread.
+#[derive(clap::Args, Clone, Debug)]
+struct SpreadArgs {
+    /// How many of each day's cheapest and most expensive five-minute
+    /// intervals to average. Defaults to the config's window_intervals,
+    /// or 48 (four hours)
+    #[clap(long)]
+    intervals: Option<usize>,
+}
+
+impl SpreadArgs {
+    const DEFAULT_INTERVALS: usize = 48;
+
+    fn intervals(&self, settings: &Settings) -> usize {
+        self.intervals
+            .or(settings.window_intervals)
+            .unwrap_or(Self::DEFAULT_INTERVALS)
+    }
+}
+
+/// Smoothing for calendar-time charts.
+#[derive(clap::Args, Clone, Debug)]
+struct RollingArgs {
+    /// How many days the rolling average spans, e.g. 7 or 30
+    #[clap(long, default_value_t = 7, value_parser = clap::value_parser!(u16).range(1..))]
+    window: u16,
+}
+
+// How every chart of a run is drawn, over what the config says. A `//`
+// comment, since clap would show a doc comment as the program's help.
+#[derive(clap::Args, Clone, Debug, Default, PartialEq)]
+struct ChartArgs {
+    /// Writes a footer on every chart naming the span of its data, the
+    /// version that drew it and today's date
+    #[clap(long, global = true)]
+    footer: bool,
+
+    /// How the y axis of price charts is scaled, in place of the config's
+    /// y_scale
+    #[clap(long, global = true, value_enum)]
+    y_scale: Option<YScale>,
+
+    /// Fixes the bottom of the y axis, in the units the chart is labeled
+    /// in, so charts of different data can be compared side by side
+    #[clap(long, global = true, allow_negative_numbers = true)]
+    y_min: Option<f64>,
+
+    /// Fixes the top of the y axis, like --y-min
+    #[clap(long, global = true, allow_negative_numbers = true)]
+    y_max: Option<f64>,
+
+    /// Draws generation in MW or GW, in place of the config's unit
+    #[clap(long, global = true, value_enum, ignore_case = true)]
+    unit: Option<PowerUnit>,
+
+    /// Also writes the values each chart draws to a csv beside it, e.g.
+    /// results/prices.csv for results/prices.png
+    #[clap(long, global = true)]
+    also_csv: bool,
+
+    /// Titles the chart in place of the title it picks for itself
+    #[clap(long, global = true)]
+    title: Option<String>,
+
+    /// Labels the chart's x axis in place of its own label
+    #[clap(long, global = true)]
+    x_label: Option<String>,
+
+    /// Labels the chart's y axis in place of its own label
+    #[clap(long, global = true)]
+    y_label: Option<String>,
+
+    /// Draws the chart at this many pixels, as WIDTHxHEIGHT, e.g. 1600x900
+    #[clap(long, global = true, value_parser = parse_size)]
+    size: Option<(u32, u32)>,
+
+    /// Draws a labeled horizontal line across each chart with a y axis of
+    /// values, as VALUE=LABEL in the axis' units, e.g. "0=$0/MWh". May be
+    /// repeated, and adds to the config's reference_lines
+    #[clap(long, global = true, allow_hyphen_values = true, value_parser = parse_reference_line)]
+    reference_line: Vec<ReferenceLine>,
+
+    /// Marks a labeled time on time-of-day charts, as HH:MM=LABEL, e.g.
+    /// "16:00=Peak start". May be repeated, and adds to the config's
+    /// time_marks
+    #[clap(long, global = true, value_parser = parse_time_mark)]
+    time_mark: Vec<TimeMark>,
+}
+
+impl ChartArgs {
+    fn check(&self) -> anyhow::Result<()> {
+        if let (Some(min), Some(max)) = (self.y_min, self.y_max) {
+            if min >= max {
+                bail!("--y-min {min} must be below --y-max {max}");
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&self, settings: &mut Settings) {
+        settings.footer |= self.footer;
+        settings.y_scale = self.y_scale.unwrap_or(settings.y_scale);
+        settings.y_min = self.y_min.or(settings.y_min);
+        settings.y_max = self.y_max.or(settings.y_max);
+        settings.unit = self.unit.unwrap_or(settings.unit);
+        settings.also_csv = self.also_csv;
+        settings.title.clone_from(&self.title);
+        settings.x_label.clone_from(&self.x_label);
+        settings.y_label.clone_from(&self.y_label);
+        settings.size = self.size;
+        settings
+            .reference_lines
+            .extend(self.reference_line.iter().cloned());
+        settings.time_marks.extend(self.time_mark.iter().cloned());
+    }
+}
+
+/// Parallelism for analyses that compute over day partitions.
+#[derive(clap::Args, Clone, Debug)]
+struct ThreadArgs {
+    /// Worker threads for per-day computation. 0 uses one per core
+    #[clap(long, default_value_t = 1)]
+    threads: usize,
+}
+
+impl ThreadArgs {
+    /// Sizes rayon's global pool. The pool can only be built once per
+    /// process, so later steps of a pipeline must agree with the first.
+    fn init(&self) -> anyhow::Result<()> {
+        static THREADS: OnceLock<usize> = OnceLock::new();
+        if let Some(threads) = THREADS.get() {
+            if *threads != self.threads {
+                bail!("--threads was already set to {threads} by an earlier step");
+            }
+            return Ok(());
+        }
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.threads)
+            .build_global()?;
+        THREADS.get_or_init(|| self.threads);
+        Ok(())
+    }
+}
+
+/// Options for the hypothetical capacity scaling scenario.
+#[derive(clap::Args, Clone, Debug)]
+struct ScaleArgs {
+    /// Scales this generation source's output before analysis, e.g. "Solar"
+    #[clap(long)]
+    scale_source: Option<String>,
+
+    /// The factor the scaled source's output is multiplied by
+    #[clap(long, default_value_t = 2.)]
+    scale_factor: f64,
+
+    /// How strongly price responds to the change in net load
+    #[clap(long, default_value_t = SourceScaling::DEFAULT_ELASTICITY)]
+    elasticity: f64,
+}
+
+impl ScaleArgs {
+    fn scaling(&self) -> error::Result<Option<SourceScaling>> {
+        self.scale_source
+            .as_deref()
+            .map(|source| SourceScaling::new(source, self.scale_factor, self.elasticity))
+            .transpose()
+    }
+
+    fn required_scaling(&self) -> anyhow::Result<SourceScaling> {
+        self.scaling()?
+            .ok_or_else(|| anyhow!("This command requires --scale-source"))
+    }
+}
+
+/// How the raw files given to the parse commands are written.
+#[derive(clap::Args, Clone, Debug)]
+struct CsvFormatArgs {
+    /// The character separating fields, e.g. ";"
+    #[clap(long, default_value_t = ',')]
+    delimiter: char,
+
+    /// Reads numbers written with a decimal comma, like 1.234,5
+    #[clap(long)]
+    decimal_comma: bool,
+}
+
+impl CsvFormatArgs {
+    fn format(&self) -> anyhow::Result<CsvFormat> {
+        if !self.delimiter.is_ascii() {
+            bail!("--delimiter must be a single ASCII character");
+        }
+        Ok(CsvFormat {
+            delimiter: self.delimiter as u8,
+            decimal_comma: self.decimal_comma,
+        })
+    }
+}
+
+/// Where the temperatures are in a weather csv.
+#[derive(clap::Args, Clone, Debug)]
+struct WeatherArgs {
+    /// The column holding temperatures, in whatever unit they were recorded
+    #[clap(long, default_value = "temperature")]
+    temperature_column: String,
+
+    #[clap(flatten)]
+    layout: SeriesArgs,
+}
+
+impl WeatherArgs {
+    /// Each day of the joined data with the fits through them.
+    fn days(
+        &self,
+        prices: &Compute,
+        gen_csv: &Path,
+        weather_csv: &Path,
+    ) -> anyhow::Result<(Vec<WeatherDay>, WeatherFits)> {
+        let column = [self.temperature_column.clone()];
+        let weather = series::load(weather_csv, &self.layout.layout()?, &column)?;
+        let days = weather::weather_days(prices, gen_csv, &weather, &column[0])?;
+        let fits = WeatherFits::new(&days);
+        Ok((days, fits))
+    }
+}
+
+/// Where the prices are in a daily gas hub csv, and the plant burning it.
+#[derive(clap::Args, Clone, Debug)]
+struct GasArgs {
+    /// The plant's heat rate, in MMBtu of gas burned per MWh generated
+    #[clap(long, default_value_t = gas::DEFAULT_HEAT_RATE)]
+    heat_rate: f64,
+
+    /// The column holding each day's hub price, in $/MMBtu
+    #[clap(long, default_value = "price")]
+    gas_price_column: String,
+
+    /// The header of the column holding each price's date
+    #[clap(long, default_value = "date")]
+    gas_date_column: String,
+
+    /// How the dates are written, in chrono's strftime syntax
+    #[clap(long, default_value = "%Y-%m-%d")]
+    gas_date_format: String,
+
+    #[clap(flatten)]
+    format: CsvFormatArgs,
+}
+
+impl GasArgs {
+    fn prices(&self, gas_csv: &Path) -> anyhow::Result<GasPrices> {
+        let layout = SeriesLayout {
+            timestamp_column: self.gas_date_column.clone(),
+            timestamp_format: self.gas_date_format.clone(),
+            csv: self.format.format()?,
+        };
+        Ok(GasPrices::load(gas_csv, &layout, &self.gas_price_column)?)
+    }
+}
+
+/// When a scenario applies.
+#[derive(clap::Args, Clone, Debug)]
+struct WindowArgs {
+    /// When the scenario starts each day
+    #[clap(long, default_value = "09:00")]
+    window_start: NaiveTime,
+
+    /// When it ends each day, the next day if it's before the start
+    #[clap(long, default_value = "17:00")]
+    window_end: NaiveTime,
+
+    /// The first day the scenario applies. Defaults to the first in the data
+    #[clap(long)]
+    start: Option<NaiveDate>,
+
+    /// The last day the scenario applies. Defaults to the last in the data
+    #[clap(long)]
+    end: Option<NaiveDate>,
+}
+
+impl WindowArgs {
+    fn window(&self) -> anyhow::Result<ScenarioWindow> {
+        let dates = match (self.start, self.end) {
+            (None, None) => None,
+            (start, end) => {
+                let start = start.unwrap_or(NaiveDate::MIN);
+                let end = end.unwrap_or(NaiveDate::MAX);
+                if start > end {
+                    bail!("--start {start} is after --end {end}");
+                }
+                Some(start..=end)
+            }
+        };
+        Ok(ScenarioWindow {
+            start: self.window_start,
+            end: self.window_end,
+            dates,
+        })
+    }
+}
+
+/// The battery being dispatched.
+#[derive(clap::Args, Clone, Debug)]
+struct BatteryArgs {
+    /// Rated charge and discharge power, in MW
+    #[clap(long, default_value_t = 100.)]
+    power_mw: f64,
+
+    /// Hours needed to fully charge or discharge at rated power
+    #[clap(long, default_value_t = 4.)]
+    duration_hours: f64,
+
+    /// The fraction of energy charged that comes back out
+    #[clap(long, default_value_t = 0.85)]
+    round_trip_efficiency: f64,
+}
+
+impl BatteryArgs {
+    fn scenario(&self) -> BatteryScenario {
+        BatteryScenario {
+            capacity_mwh: self.power_mw * self.duration_hours,
+            duration_hours: self.duration_hours,
+            round_trip_efficiency: self.round_trip_efficiency,
+        }
+    }
+}
+
+/// How a third-party time series csv is laid out.
+#[derive(clap::Args, Clone, Debug)]
+struct SeriesArgs {
+    /// The header of the column holding each row's local time
+    #[clap(long, default_value = "timestamp")]
+    timestamp_column: String,
+
+    /// How the timestamps are written, in chrono's strftime syntax
+    #[clap(long, default_value = convert::TIMESTAMP_FMT)]
+    timestamp_format: String,
+
+    #[clap(flatten)]
+    format: CsvFormatArgs,
+}
+
+impl SeriesArgs {
+    fn layout(&self) -> anyhow::Result<SeriesLayout> {
+        Ok(SeriesLayout {
+            timestamp_column: self.timestamp_column.clone(),
+            timestamp_format: self.timestamp_format.clone(),
+            csv: self.format.format()?,
+        })
+    }
+}
+
+/// The file type a write command produces.
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+enum TableFormat {
+    #[default]
+    Csv,
+    Xlsx,
+}
+
+#[derive(clap::Args, Clone, Debug, Default)]
+struct TableArgs {
+    /// xlsx writes an Excel workbook with a chart instead, changing the
+    /// output's extension to .xlsx
+    #[clap(long, value_enum, default_value = "csv")]
+    format: TableFormat,
+}
+
+impl TableArgs {
+    /// Runs `write` against `out`, or for xlsx against a scratch csv that's
+    /// then copied into a workbook next to `out`.
+    fn write(
+        &self,
+        out: &Path,
+        options: WriteOptions,
+        write: impl FnOnce(&Path) -> error::Result<()>,
+    ) -> anyhow::Result<()> {
+        match self.format {
+            TableFormat::Csv => Ok(write(out)?),
+            TableFormat::Xlsx => {
+                let xlsx = out.with_extension("xlsx");
+                options.check(&xlsx)?;
+                let name = out.file_stem().unwrap_or_default().to_string_lossy();
+                let scratch = std::env::temp_dir()
+                    .join(format!("energy-analysis-{}-{name}.csv", std::process::id()));
+                let written = write(&scratch)
+                    .and_then(|()| xlsx::write_tables(&xlsx, &[(&name, &scratch)], options));
+                fs::remove_file(&scratch).ok();
+                Ok(written?)
+            }
+        }
+    }
+}
+
+/// A chart writer styled by the config's theme and labels.
+#[cfg(feature = "graph")]
+fn graphing<'a>(path: &'a Path, settings: &Settings) -> Graphing<'a> {
+    Graphing::new(path)
+        .with_options(ChartOptions {
+            title: settings.title.clone(),
+            x_label: settings.x_label.clone(),
+            y_label: settings.y_label.clone(),
+            size: settings.size,
+            theme: settings.theme,
+        })
+        .with_labels(settings.labels.clone())
+        .with_references(settings.reference_lines.clone())
+        .with_markers(settings.time_marks.iter().map(TimeMark::marker).collect())
+        .with_footer(settings.footer_line.clone())
+        .with_y_scale(settings.y_scale)
+        .with_y_limits(settings.y_min, settings.y_max)
+        .with_unit(settings.unit)
+        .with_also_csv(settings.also_csv)
+        .with_write_options(settings.write)
+}
+
+/// Average value by source, optionally under a scaling scenario.
+fn value_5min(
+    prices: &Compute,
+    gen_csv: &Path,
+    scale: &ScaleArgs,
+) -> error::Result<([f64; 14], [f64; 14])> {
+    match scale.scaling()? {
+        Some(scaling) => prices.average_value_scaled(gen_csv, &scaling),
+        None => prices.average_value_5min(gen_csv),
+    }
+}
+
+impl Args {
+    /// Applies the config's data and results directories, and any
+    /// `--output-dir`, to every path this command reads or writes. Returns
+    /// the resolved inputs.
+    fn resolve_paths(&mut self, settings: &Settings, stamped: Option<&StampedDir>) -> Vec<PathBuf> {
+        let mut inputs: Vec<&mut PathBuf> = Vec::new();
+        let mut outputs: Vec<&mut PathBuf> = Vec::new();
+        let mut data_outputs: Vec<&mut PathBuf> = Vec::new();
+        match self {
+            Args::ParsePriceCsv {
+                caiso_csv,
+                output_csv,
+                store,
+                months,
+                ..
+            }
+            | Args::ParseGenCsv {
+                caiso_csv,
+                output_csv,
+                store,
+                months,
+                ..
+            } => {
+                inputs.extend(caiso_csv);
+                data_outputs.push(output_csv);
+                data_outputs.extend(store);
+                data_outputs.extend(months);
+            }
+            Args::ParseNodalCsv {
+                caiso_csv,
+                output_csv,
+            }
+            | Args::ParseAncillaryCsv {
+                caiso_csv,
+                output_csv,
+                ..
+            } => {
+                inputs.extend(caiso_csv);
+                data_outputs.push(output_csv);
+            }
+            Args::CheckGenTotals {
+                gen_csv,
+                csv_out: out,
+                ..
+            } => {
+                inputs.push(gen_csv);
+                outputs.push(out);
+            }
+            Args::WritePriceMinutes {
+                csv_in, csv_out, ..
+            }
+            | Args::WriteGenMinutes {
+                csv_in, csv_out, ..
+            }
+            | Args::WriteGenSolarBattery {
+                csv_in, csv_out, ..
+            }
+            | Args::WriteDayCoverage {
+                csv_in, csv_out, ..
+            }
+            | Args::WritePriceSpikes {
+                price_csv: csv_in,
+                csv_out,
+                ..
+            }
+            | Args::WriteBaInterchange {
+                interchange_csv: csv_in,
+                csv_out,
+                ..
+            }
+            | Args::WriteArbitrageSpread {
+                price_csv: csv_in,
+                csv_out,
+                ..
+            } => {
+                inputs.push(csv_in);
+                outputs.push(csv_out);
+            }
+            #[cfg(feature = "graph")]
+            Args::GraphBaInterchange {
+                interchange_csv: csv_in,
+                output_png: csv_out,
+            }
+            | Args::GraphPriceMinutes {
+                price_csv: csv_in,
+                output_png: csv_out,
+                ..
+            }
+            | Args::GraphGenMinutes {
+                gen_csv: csv_in,
+                output_png: csv_out,
+                ..
+            }
+            | Args::GraphGenSolarBattery {
+                gen_csv: csv_in,
+                output_png: csv_out,
+                ..
+            }
+            | Args::GraphGenAnimation {
+                gen_csv: csv_in,
+                output_gif: csv_out,
+                ..
+            }
+            | Args::GraphPriceSpikes {
+                price_csv: csv_in,
+                output_png: csv_out,
+                ..
+            }
+            | Args::GraphPriceHistogram {
+                price_csv: csv_in,
+                output_png: csv_out,
+                ..
+            }
+            | Args::GraphArbitrageSpread {
+                price_csv: csv_in,
+                output_png: csv_out,
+                ..
+            }
+            | Args::GraphPriceTimeseries {
+                price_csv: csv_in,
+                output_png: csv_out,
+                ..
+            }
+            | Args::GraphGenTimeseries {
+                gen_csv: csv_in,
+                output_png: csv_out,
+                ..
+            } => {
+                inputs.push(csv_in);
+                outputs.push(csv_out);
+            }
+            Args::WriteValueMinutes {
+                price_csv,
+                gen_csv,
+                csv_out: out,
+                ..
+            }
+            | Args::WriteValueSolarBattery {
+                price_csv,
+                gen_csv,
+                csv_out: out,
+                ..
+            }
+            | Args::WriteValueProfile {
+                price_csv,
+                gen_csv,
+                csv_out: out,
+                ..
+            }
+            | Args::WriteStorageSweep {
+                price_csv,
+                gen_csv,
+                csv_out: out,
+                ..
+            }
+            | Args::WritePriceImpact {
+                price_csv,
+                gen_csv,
+                csv_out: out,
+                ..
+            }
+            | Args::WriteCorrelation {
+                price_csv,
+                gen_csv,
+                csv_out: out,
+                ..
+            }
+            | Args::CaseStudy {
+                price_csv,
+                gen_csv,
+                output_dir: out,
+                ..
+            }
+            | Args::WriteSolarSplit {
+                price_csv,
+                gen_csv,
+                csv_out: out,
+                ..
+            }
+            | Args::WriteInterchange {
+                price_csv,
+                gen_csv,
+                csv_out: out,
+                ..
+            }
+            | Args::WriteBatterySplit {
+                price_csv,
+                gen_csv,
+                csv_out: out,
+                ..
+            } => {
+                inputs.extend([price_csv, gen_csv]);
+                outputs.push(out);
+            }
+            #[cfg(feature = "graph")]
+            Args::GraphValueMinutes {
+                price_csv,
+                gen_csv,
+                output_png: out,
+                ..
+            }
+            | Args::GraphValueSolarBattery {
+                price_csv,
+                gen_csv,
+                output_png: out,
+            }
+            | Args::GraphValueProfile {
+                price_csv,
+                gen_csv,
+                output_png: out,
+                ..
+            }
+            | Args::GraphPriceGenOverlay {
+                price_csv,
+                gen_csv,
+                output_png: out,
+                ..
+            }
+            | Args::GraphStorageSweep {
+                price_csv,
+                gen_csv,
+                output_png: out,
+                ..
+            }
+            | Args::GraphPriceImpact {
+                price_csv,
+                gen_csv,
+                output_png: out,
+                ..
+            }
+            | Args::GraphDay {
+                price_csv,
+                gen_csv,
+                output_png: out,
+                ..
+            }
+            | Args::GraphCorrelation {
+                price_csv,
+                gen_csv,
+                output_png: out,
+                ..
+            }
+            | Args::GraphScatter {
+                price_csv,
+                gen_csv,
+                output_png: out,
+                ..
+            }
+            | Args::Report {
+                price_csv,
+                gen_csv,
+                output_dir: out,
+                ..
+            }
+            | Args::GraphSolarSplit {
+                price_csv,
+                gen_csv,
+                output_png: out,
+                ..
+            }
+            | Args::GraphInterchange {
+                price_csv,
+                gen_csv,
+                output_png: out,
+            }
+            | Args::GraphBatterySplit {
+                price_csv,
+                gen_csv,
+                output_png: out,
+            } => {
+                inputs.extend([price_csv, gen_csv]);
+                outputs.push(out);
+            }
+            Args::WriteCapacityFactors {
+                gen_csv,
+                capacity_toml,
+                csv_out: out,
+                ..
+            } => {
+                inputs.extend([gen_csv, capacity_toml]);
+                outputs.push(out);
+            }
+            #[cfg(feature = "graph")]
+            Args::GraphCapacityFactors {
+                gen_csv,
+                capacity_toml,
+                output_png: out,
+                ..
+            } => {
+                inputs.extend([gen_csv, capacity_toml]);
+                outputs.push(out);
+            }
+            Args::WriteTouSummary {
+                price_csv,
+                gen_csv,
+                tou_toml,
+                csv_out: out,
+                ..
+            } => {
+                inputs.extend([price_csv, gen_csv, tou_toml]);
+                outputs.push(out);
+            }
+            #[cfg(feature = "graph")]
+            Args::GraphTouSummary {
+                price_csv,
+                gen_csv,
+                tou_toml,
+                output_png: out,
+            } => {
+                inputs.extend([price_csv, gen_csv, tou_toml]);
+                outputs.push(out);
+            }
+            Args::WriteHydroYears {
+                price_csv,
+                gen_csv,
+                water_years_toml,
+                csv_out: out,
+                ..
+            } => {
+                inputs.extend([price_csv, gen_csv, water_years_toml]);
+                outputs.push(out);
+            }
+            #[cfg(feature = "graph")]
+            Args::GraphHydroYears {
+                price_csv,
+                gen_csv,
+                water_years_toml,
+                output_png: out,
+            } => {
+                inputs.extend([price_csv, gen_csv, water_years_toml]);
+                outputs.push(out);
+            }
+            Args::WriteComplementarity {
+                gen_csv,
+                csv_out: out,
+                ..
+            } => {
+                inputs.push(gen_csv);
+                outputs.push(out);
+            }
+            #[cfg(feature = "graph")]
+            Args::GraphComplementarity {
+                gen_csv,
+                output_png: out,
+            } => {
+                inputs.push(gen_csv);
+                outputs.push(out);
+            }
+            Args::WriteNetLoadPeaks {
+                price_csv,
+                gen_csv,
+                csv_out: out,
+                ..
+            } => {
+                inputs.extend([price_csv, gen_csv]);
+                outputs.push(out);
+            }
+            #[cfg(feature = "graph")]
+            Args::GraphNetLoadPeaks {
+                price_csv,
+                gen_csv,
+                output_png: out,
+                ..
+            } => {
+                inputs.extend([price_csv, gen_csv]);
+                outputs.push(out);
+            }
+            Args::WriteWeatherCorrelation {
+                price_csv,
+                gen_csv,
+                weather_csv,
+                csv_out: out,
+                ..
+            } => {
+                inputs.extend([price_csv, gen_csv, weather_csv]);
+                outputs.push(out);
+            }
+            #[cfg(feature = "graph")]
+            Args::GraphWeatherCorrelation {
+                price_csv,
+                gen_csv,
+                weather_csv,
+                output_png: out,
+                ..
+            } => {
+                inputs.extend([price_csv, gen_csv, weather_csv]);
+                outputs.push(out);
+            }
+            Args::WritePriceForecast {
+                price_csv,
+                csv_out: out,
+                ..
+            }
+            | Args::WriteForecastErrors {
+                price_csv,
+                csv_out: out,
+                ..
+            } => {
+                inputs.push(price_csv);
+                outputs.push(out);
+            }
+            #[cfg(feature = "graph")]
+            Args::GraphPriceForecast {
+                price_csv,
+                output_png: out,
+                ..
+            } => {
+                inputs.push(price_csv);
+                outputs.push(out);
+            }
+            Args::WriteTariffCosts {
+                price_csv,
+                load_csv,
+                tariffs_toml,
+                csv_out: out,
+                ..
+            } => {
+                inputs.extend([price_csv, load_csv, tariffs_toml]);
+                outputs.push(out);
+            }
+            Args::OptimizeCharging {
+                price_csv,
+                csv_out: out,
+                gen_csv,
+                schedule_csv,
+                ..
+            } => {
+                inputs.push(price_csv);
+                inputs.extend(gen_csv);
+                outputs.push(out);
+                outputs.extend(schedule_csv);
+            }
+            #[cfg(feature = "graph")]
+            Args::GraphDiff {
+                diff:
+                    Diff::PriceProfile {
+                        base_csv: price_csv,
+                        other_csv: gen_csv,
+                        output_png,
+                        ..
+                    }
+                    | Diff::SolarBattery {
+                        price_csv,
+                        gen_csv,
+                        output_png,
+                    }
+                    | Diff::Scaled {
+                        price_csv,
+                        gen_csv,
+                        output_png,
+                        ..
+                    },
+            } => {
+                inputs.extend([price_csv, gen_csv]);
+                outputs.push(output_png);
+            }
+            Args::Analyze { analysis } => {
+                let (analysis_inputs, out) = analysis.paths();
+                inputs.extend(analysis_inputs);
+                outputs.extend(out.out_csv.as_mut());
+                #[cfg(feature = "graph")]
+                outputs.extend(out.out_png.as_mut());
+            }
+            Args::Stats {
+                csv_in,
+                out_csv,
+                out_json,
+            } => {
+                inputs.push(csv_in);
+                outputs.extend(out_csv);
+                outputs.extend(out_json);
+            }
+            Args::FindExtremes {
+                price_csv, gen_csv, ..
+            } => inputs.extend([price_csv, gen_csv]),
+            Args::FetchEia {
+                output_csv,
+                demand_csv,
+                interchange_csv,
+                ..
+            } => {
+                data_outputs.push(output_csv);
+                data_outputs.extend(demand_csv);
+                data_outputs.extend(interchange_csv);
+            }
+            Args::Inspect { caiso_csv, .. } => inputs.extend(caiso_csv),
+            Args::Downsample {
+                csv_in, csv_out, ..
+            } => {
+                inputs.push(csv_in);
+                data_outputs.push(csv_out);
+            }
+            Args::AlignSeries {
+                series_csv,
+                csv_out,
+                grid,
+                ..
+            } => {
+                inputs.push(series_csv);
+                inputs.extend(grid);
+                data_outputs.push(csv_out);
+            }
+            Args::FetchOasis {
+                zip,
+                output_csv,
+                gen_csv,
+                ..
+            } => {
+                inputs.extend(zip);
+                data_outputs.push(output_csv);
+                data_outputs.extend(gen_csv);
+            }
+            Args::Serve {
+                price_csv, gen_csv, ..
+            }
+            | Args::Monitor {
+                price_csv, gen_csv, ..
+            } => inputs.extend([price_csv, gen_csv]),
+            Args::Pipeline { pipeline_toml } => inputs.push(pipeline_toml),
+            Args::Completions { .. } | Args::Man { .. } => (),
+            #[cfg(feature = "testdata")]
+            Args::GenTestdata {
+                price_csv,
+                gen_csv,
+                nodal_csv,
+                ..
+            } => {
+                data_outputs.extend([price_csv, gen_csv]);
+                data_outputs.extend(nodal_csv);
+            }
+        }
+        let mut resolved = Vec::new();
+        for path in inputs {
+            *path = settings.input(path);
+            resolved.push(path.clone());
+        }
+        for path in outputs {
+            *path = match stamped {
+                Some(stamped) => stamped.place(path),
+                None => settings.output(path),
+            };
+        }
+        for path in data_outputs {
+            *path = settings.data_output(path);
+        }
+        resolved
+    }
+
+    fn is_graph(&self) -> bool {
+        match self {
+            Args::Analyze { analysis } => analysis.out().is_graph(),
+            #[cfg(feature = "graph")]
+            Args::GraphPriceMinutes { .. }
+            | Args::GraphGenMinutes { .. }
+            | Args::GraphGenSolarBattery { .. }
+            | Args::GraphGenAnimation { .. }
+            | Args::GraphValueMinutes { .. }
+            | Args::GraphValueSolarBattery { .. }
+            | Args::GraphValueProfile { .. }
+            | Args::GraphPriceGenOverlay { .. }
+            | Args::GraphStorageSweep { .. }
+            | Args::GraphPriceImpact { .. }
+            | Args::GraphPriceSpikes { .. }
+            | Args::GraphPriceHistogram { .. }
+            | Args::GraphDay { .. }
+            | Args::GraphDiff { .. }
+            | Args::GraphCorrelation { .. }
+            | Args::GraphScatter { .. }
+            | Args::GraphCapacityFactors { .. }
+            | Args::GraphSolarSplit { .. }
+            | Args::GraphTouSummary { .. }
+            | Args::GraphHydroYears { .. }
+            | Args::GraphComplementarity { .. }
+            | Args::GraphNetLoadPeaks { .. }
+            | Args::GraphWeatherCorrelation { .. }
+            | Args::GraphPriceForecast { .. }
+            | Args::GraphInterchange { .. }
+            | Args::GraphBaInterchange { .. }
+            | Args::GraphBatterySplit { .. }
+            | Args::GraphArbitrageSpread { .. }
+            | Args::GraphPriceTimeseries { .. }
+            | Args::GraphGenTimeseries { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// Runs the command line on the process's arguments.
+pub fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    cli.chart.check()?;
+    let write = WriteOptions {
+        overwrite: cli.overwrite,
+        precision: cli.precision,
+    };
+    if cli.precision != output::DEFAULT_PRECISION {
+        provenance::note("precision", cli.precision.to_string());
+    }
+    if cli.price_node != PriceNode::Average {
+        provenance::note("price_node", cli.price_node.name());
+    }
+    if !cli.no_cache {
+        cache::enable(Path::new(cache::DEFAULT_DIR));
+    }
+    if !cli.no_meta {
+        output::track_written();
+    }
+    let stamped = cli.stamped_dir.as_deref().map(StampedDir::new);
+    if let Some(stamped) = &stamped {
+        fs::create_dir_all(stamped.dir())?;
+    }
+    if cli.watch {
+        return watch(
+            cli.command,
+            cli.config.as_deref(),
+            &cli.chart,
+            cli.price_node,
+            write,
+            stamped.as_ref(),
+        );
+    }
+    let mut settings = Settings::discover(cli.config.as_deref())?;
+    cli.chart.apply(&mut settings);
+    settings.price_node = cli.price_node;
+    settings.write = write;
+    run(cli.command, &settings, stamped.as_ref())
+}
+
+/// Renders a graph, then renders it again each time its inputs, the config,
+/// or the config's labels change. Errors are reported and the watch goes
+/// on, since a file caught halfway through an edit often won't parse.
+fn watch(
+    args: Args,
+    config: Option<&Path>,
+    chart: &ChartArgs,
+    price_node: PriceNode,
+    mut write: WriteOptions,
+    stamped: Option<&StampedDir>,
+) -> anyhow::Result<()> {
+    if !args.is_graph() {
+        bail!("--watch only works with graph commands");
+    }
+    let config_path = config.unwrap_or(Path::new(Settings::FILE_NAME));
+    loop {
+        let settings = Settings::discover(config).map(|mut settings| {
+            chart.apply(&mut settings);
+            settings.price_node = price_node;
+            settings.write = write;
+            settings
+        });
+        let mut paths = vec![config_path.to_path_buf()];
+        if let Ok(settings) = &settings {
+            paths.extend(settings.labels_file.clone());
+            paths.extend(args.clone().resolve_paths(settings, None));
+        }
+        // Taken before rendering so edits made during a slow render count.
+        let mut watcher = Watcher::new(paths);
+        match settings
+            .map_err(anyhow::Error::from)
+            .and_then(|settings| run(args.clone(), &settings, stamped))
+        {
+            Ok(()) => {
+                println!("Rendered at {}", chrono::Local::now().format("%H:%M:%S"));
+                // Later renders replace the chart this one wrote.
+                write.overwrite = true;
+            }
+            Err(e) => eprintln!("{e:#}"),
+        }
+        println!(
+            "Watching {} files for changes (Ctrl-C to stop)",
+            watcher.paths().len()
+        );
+        watcher.wait();
+    }
+}
+
+/// Parses a `Column=agg` argument of downsample.
+fn parse_agg(arg: &str) -> Result<(String, Agg), String> {
+    let (column, agg) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected Column=agg, got {arg:?}"))?;
+    Ok((column.trim().to_string(), Agg::from_str(agg.trim(), true)?))
+}
+
+/// Parses a `START..END` range of days, both included.
+fn parse_date_range(arg: &str) -> Result<RangeInclusive<NaiveDate>, String> {
+    let (start, end) = arg
+        .split_once("..")
+        .ok_or_else(|| format!("expected START..END, got {arg:?}"))?;
+    let date = |value: &str| -> Result<NaiveDate, String> {
+        value
+            .trim()
+            .parse()
+            .map_err(|e| format!("bad date {value:?}: {e}"))
+    };
+    let (start, end) = (date(start)?, date(end)?);
+    if start > end {
+        return Err(format!("{start} is after {end}"));
+    }
+    Ok(start..=end)
+}
+
+/// Parses a fraction from 0 to 1.
+fn parse_fraction(arg: &str) -> Result<f64, String> {
+    let fraction: f64 = arg
+        .trim()
+        .parse()
+        .map_err(|e| format!("bad fraction {arg:?}: {e}"))?;
+    if !(0. ..=1.).contains(&fraction) {
+        return Err(format!("expected a fraction from 0 to 1, got {fraction}"));
+    }
+    Ok(fraction)
+}
+
+/// Parses a `low,high` pair of percentiles.
+fn parse_clip(arg: &str) -> Result<Clip, String> {
+    let (low, high) = arg
+        .split_once(',')
+        .ok_or_else(|| format!("expected low,high percentiles, got {arg:?}"))?;
+    let percent = |value: &str| -> Result<f64, String> {
+        value
+            .trim()
+            .parse()
+            .map_err(|e| format!("bad percentile {value:?}: {e}"))
+    };
+    let (low, high) = (percent(low)?, percent(high)?);
+    if !(0. <= low && low < high && high <= 100.) {
+        return Err(format!(
+            "percentiles must satisfy 0 <= low < high <= 100, got {low},{high}"
+        ));
+    }
+    Ok(Clip { low, high })
+}
+
+/// Parses a `--source-order`: "schema", "magnitude" or a comma-separated
+/// list of sources.
+fn parse_source_order(arg: &str) -> Result<SourceOrder, String> {
+    match arg.trim().to_ascii_lowercase().as_str() {
+        "schema" => Ok(SourceOrder::Schema),
+        "magnitude" => Ok(SourceOrder::Magnitude),
+        _ => arg
+            .split(',')
+            .map(parse_source)
+            .collect::<Result<_, _>>()
+            .map(SourceOrder::Given),
+    }
+}
+
+/// Parses a source drawn as a line, by name, into its index.
+fn parse_source(arg: &str) -> Result<usize, String> {
+    match EnergyGenCsvRow::source_idx(arg.trim()).map_err(|e| e.to_string())? {
+        0 => Err("the total isn't drawn as a line of its own".to_string()),
+        idx => Ok(idx),
+    }
+}
+
+/// Parses a `VALUE=LABEL` reference line.
+fn parse_reference_line(arg: &str) -> Result<ReferenceLine, String> {
+    let (value, label) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected VALUE=LABEL, got {arg:?}"))?;
+    let value = value
+        .trim()
+        .parse()
+        .map_err(|e| format!("bad value {value:?}: {e}"))?;
+    Ok(ReferenceLine {
+        value,
+        label: label.to_string(),
+    })
+}
+
+/// Parses an `HH:MM=LABEL` time mark.
+fn parse_time_mark(arg: &str) -> Result<TimeMark, String> {
+    let (time, label) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected HH:MM=LABEL, got {arg:?}"))?;
+    let time = NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .map_err(|e| format!("bad time {time:?}: {e}"))?;
+    Ok(TimeMark {
+        time,
+        label: label.to_string(),
+    })
+}
+
+/// Parses a `WIDTHxHEIGHT` chart size in pixels.
+fn parse_size(arg: &str) -> Result<(u32, u32), String> {
+    let (width, height) = arg
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, got {arg:?}"))?;
+    let pixels = |value: &str| -> Result<u32, String> {
+        match value.trim().parse() {
+            Ok(0) => Err(format!("a chart can't be 0 pixels across in {arg:?}")),
+            Ok(pixels) => Ok(pixels),
+            Err(e) => Err(format!("bad pixel count {value:?}: {e}")),
+        }
+    };
+    Ok((pixels(width)?, pixels(height)?))
+}
+
+/// Parses the `--agg` of a profile command.
+fn parse_profile_agg(arg: &str) -> Result<ProfileAgg, String> {
+    let arg = arg.trim().to_ascii_lowercase();
+    match arg.as_str() {
+        "mean" => return Ok(ProfileAgg::Mean),
+        "median" => return Ok(ProfileAgg::Median),
+        _ => {}
+    }
+    let fraction = arg
+        .strip_prefix("trimmed-mean(")
+        .and_then(|rest| rest.strip_suffix(')'))
+        .ok_or_else(|| format!("expected mean, median or trimmed-mean(p), got {arg:?}"))?;
+    let fraction: f64 = fraction
+        .trim()
+        .parse()
+        .map_err(|e| format!("bad trimmed-mean fraction {fraction:?}: {e}"))?;
+    if !(0. ..0.5).contains(&fraction) {
+        return Err(format!(
+            "trimmed-mean drops a fraction from each end, so it must be in [0, 0.5), got {fraction}"
+        ));
+    }
+    Ok(ProfileAgg::TrimmedMean(fraction))
+}
+
+/// Describes what parsing an inspected raw file would do.
+fn print_summary(output: &Path, summary: &ConvertSummary) {
+    println!(
+        "Wrote {} of {} rows to {output:?}, skipping {}",
+        summary.written(),
+        summary.rows,
+        summary.skipped
+    );
+}
+
+fn print_stats(input: &Path, stats: &DatasetStats) {
+    let kind = match stats.kind {
+        RawKind::Price => "price",
+        RawKind::Gen => "gen",
+    };
+    println!(
+        "{input:?} has {} {kind} rows from {} to {}",
+        stats.rows, stats.first, stats.last
+    );
+    println!(
+        "  covering {} days, {} of them partial",
+        stats.days, stats.partial_days
+    );
+    if let Some(price) = &stats.price {
+        println!(
+            "  Price mean ${:.2}, median ${:.2}, min ${:.2}, max ${:.2}",
+            price.mean, price.median, price.min, price.max
+        );
+        println!(
+            "  {:.1}% of intervals priced below zero",
+            price.negative_share * 100.
+        );
+    }
+    for source in &stats.sources {
+        println!(
+            "  {:<12} {:>14.0} MWh {:>6.1}%",
+            source.source,
+            source.mwh,
+            source.share * 100.
+        );
+    }
+}
+
+fn print_inspection(input: &Path, inspection: &Inspection) {
+    let (command, kind) = match inspection.kind {
+        RawKind::Price => ("parse-price-csv", "price"),
+        RawKind::Gen => ("parse-gen-csv", "gen"),
+    };
+    println!(
+        "{input:?} is a {kind} export matching {}",
+        inspection.schema
+    );
+    let about = if inspection.rows_exact { "" } else { "about " };
+    match (inspection.first, inspection.last) {
+        (Some(first), Some(last)) => {
+            println!("  {about}{} rows from {first} to {last}", inspection.rows)
+        }
+        _ => println!("  {about}{} rows", inspection.rows),
+    }
+    match inspection.kind {
+        RawKind::Price => println!(
+            "  {command} would average {:?} into lmp_avg at each {:?}",
+            &inspection.columns[1..],
+            inspection.columns[0]
+        ),
+        RawKind::Gen => println!(
+            "  {command} would keep {:?} and the sources {:?}",
+            &inspection.columns[..5],
+            &inspection.columns[5..]
+        ),
+    }
+    if !inspection.ignored.is_empty() {
+        println!("  and ignore {:?}", inspection.ignored);
+    }
+    println!("  Of the first {} rows:", inspection.sampled);
+    if let Some(e) = &inspection.first_skipped {
+        println!(
+            "    {} would be skipped, the first: {e}",
+            inspection.skipped
+        );
+    }
+    if inspection.mismatched_totals > 0 {
+        println!(
+            "    {} have a Total that doesn't match the sum of sources",
+            inspection.mismatched_totals
+        );
+    }
+    match &inspection.fatal {
+        Some(e) => println!("    {command} would stop at {e}"),
+        None if inspection.skipped == 0 => println!("    every one parses"),
+        None => (),
+    }
+}
+
+/// The Pacific dates a fetch covers. When appending, they default to the
+/// date of the output's latest interval through today.
+fn fetch_dates(
+    start: Option<NaiveDate>,
+    end: Option<NaiveDate>,
+    after: Option<NaiveDateTime>,
+    output: &Path,
+) -> anyhow::Result<(NaiveDate, NaiveDate)> {
+    let Some(start) = start.or(after.map(|after| after.date())) else {
+        bail!("{output:?} has no data yet, so --append needs a --start");
+    };
+    let today = fetch::pacific_from_utc(Utc::now().naive_utc()).date();
+    Ok((start, end.unwrap_or(today)))
+}
+
+/// Says what a fetch wrote, and warns when appended data doesn't pick up
+/// where the output left off.
+fn report_fetched(
+    output: &Path,
+    what: &str,
+    count: usize,
+    after: Option<NaiveDateTime>,
+    first: Option<NaiveDateTime>,
+) {
+    match after {
+        Some(after) => println!("Appended {count} {what} after {after} to {output:?}"),
+        None => println!("Wrote {count} {what} to {output:?}"),
+    }
+    if let (Some(after), Some(first)) = (after, first) {
+        if first - after > TimeDelta::minutes(5) {
+            eprintln!("Warning: {output:?} now has no data between {after} and {first}");
+        }
+    }
+}
+
+/// Runs a command, then records the provenance of every file it wrote
+/// beside it, unless `--no-meta` turned that off.
+fn run(mut args: Args, settings: &Settings, stamped: Option<&StampedDir>) -> anyhow::Result<()> {
+    let inputs = args.resolve_paths(settings, stamped);
+    let options = format!("{args:?}");
+    // Whatever an earlier failed run left behind isn't this one's.
+    output::take_written();
+    provenance::take_notes();
+    let record = || Provenance::new(std::env::args().collect(), options.clone(), &inputs);
+
+    let mut recorded = None;
+    let footed;
+    let settings = match settings.footer && args.is_graph() {
+        true => {
+            let provenance = record()?;
+            footed = Settings {
+                footer_line: Some(provenance.footer(settings.rto)),
+                ..settings.clone()
+            };
+            recorded = Some(provenance);
+            &footed
+        }
+        false => settings,
+    };
+    execute(args, settings, stamped)?;
+
+    let written: Vec<PathBuf> = output::take_written()
+        .into_iter()
+        .filter(|path| path.is_file())
+        .collect();
+    if !written.is_empty() {
+        let mut provenance = match recorded {
+            Some(provenance) => provenance,
+            None => record()?,
+        };
+        provenance.notes.extend(provenance::take_notes());
+        for path in written {
+            provenance.write(&path, settings.write)?;
+        }
+        // The sidecars are outputs of no command, so a pipeline running
+        // this one doesn't record sidecars of them in turn.
+        output::take_written();
+    }
+    Ok(())
+}
+
+fn execute(args: Args, settings: &Settings, stamped: Option<&StampedDir>) -> anyhow::Result<()> {
+    let write = settings.write;
+    match args {
+        Args::ParsePriceCsv {
+            caiso_csv: input,
+            output_csv: output,
+            store,
+            months,
+            format,
+            on_error,
+        } => {
+            let summary = convert::convert_energy_price_csv(
+                &input,
+                &output,
+                format.format()?,
+                on_error,
+                write,
+            )?;
+            print_summary(&output, &summary);
+            if let Some(store) = store {
+                Store::write_prices(&output, &store, write)?;
+            }
+            if let Some(months) = months {
+                Manifest::split(&output, RawKind::Price, &months, write)?;
+            }
+        }
+        Args::ParseNodalCsv {
+            caiso_csv,
+            output_csv,
+        } => {
+            let index = nodal::convert_nodal_csv(&caiso_csv, &output_csv, write)?;
+            println!(
+                "Indexed {} nodes in {:?}",
+                index.nodes().count(),
+                NodeIndex::path(&output_csv)
+            );
+        }
+        Args::ParseAncillaryCsv {
+            caiso_csv,
+            output_csv,
+            region,
+        } => {
+            let hours = ancillary::convert_ancillary_csv(&caiso_csv, &output_csv, &region, write)?;
+            println!("Wrote {hours} hours of {region} prices");
+        }
+        Args::ParseGenCsv {
+            caiso_csv,
+            output_csv,
+            store,
+            months,
+            format,
+            totals,
+            on_error,
+        } => {
+            let summary = convert::convert_energy_gen_csv(
+                &caiso_csv,
+                &output_csv,
+                format.format()?,
+                totals,
+                on_error,
+                write,
+            )?;
+            print_summary(&output_csv, &summary);
+            if let Some(store) = store {
+                Store::write_gen(&output_csv, &store, write)?;
+            }
+            if let Some(months) = months {
+                Manifest::split(&output_csv, RawKind::Gen, &months, write)?;
+            }
+        }
+        Args::Downsample {
+            csv_in,
+            csv_out,
+            minutes,
+            default_agg,
+            agg,
+        } => {
+            let compute = settings.compute(&csv_in);
+            let aggs = Aggs {
+                default: default_agg,
+                columns: agg,
+            };
+            match compute.kind()? {
+                RawKind::Price => {
+                    let rows = compute.downsample_prices(minutes, &aggs)?;
+                    convert::write_parsed(&csv_out, &rows, write)?;
+                }
+                RawKind::Gen => {
+                    let rows = compute.downsample_gen(minutes, &aggs)?;
+                    convert::write_parsed(&csv_out, &rows, write)?;
+                }
+            }
+        }
+        Args::AlignSeries {
+            series_csv,
+            csv_out,
+            layout,
+            column,
+            grid,
+            max_gap_mins,
+        } => {
+            let loaded = series::load(&series_csv, &layout.layout()?, &column)?;
+            let grid = match &grid {
+                Some(grid) => Some(settings.compute(grid).frame()?.times().to_vec()),
+                None => None,
+            };
+            let max_gap = max_gap_mins.map(|mins| TimeDelta::minutes(mins.into()));
+            let aligned = series::align(&loaded, grid.as_deref(), max_gap)?;
+            convert::write_frame(&csv_out, &aligned, write)?;
+            println!(
+                "Aligned {} rows of {series_csv:?} onto {} intervals",
+                loaded.len(),
+                aligned.len()
+            );
+        }
+        Args::Inspect {
+            caiso_csv,
+            rows,
+            format,
+        } => {
+            for input in caiso_csv {
+                let inspection = convert::inspect_raw(&input, format.format()?, rows as usize)?;
+                print_inspection(&input, &inspection);
+            }
+        }
+        Args::Stats {
+            csv_in,
+            out_csv,
+            out_json,
+        } => {
+            let stats = settings.compute(&csv_in).dataset_stats()?;
+            print_stats(&csv_in, &stats);
+            if let Some(out) = out_csv {
+                convert::write_dataset_stats(&out, &stats, write)?;
+            }
+            if let Some(out) = out_json {
+                let json = serde_json::to_string_pretty(&stats)?;
+                writeln!(write.create(&out)?, "{json}")?;
+            }
+        }
+        Args::CheckGenTotals {
+            gen_csv,
+            csv_out,
+            table,
+        } => {
+            let mismatches = settings.compute(&gen_csv).total_mismatches()?;
+            println!(
+                "{gen_csv:?} has {} rows whose Total doesn't match the sum of sources",
+                mismatches.len()
+            );
+            table.write(&csv_out, write, |out| {
+                convert::write_total_mismatches(out, &mismatches, write)
+            })?;
+        }
+        Args::Analyze { analysis } => analyze(analysis, settings)?,
+        // The write- and graph- commands kept for older scripts and
+        // pipelines run the analysis with just the one output.
+        Args::WritePriceMinutes {
+            csv_in,
+            csv_out,
+            profile,
+            days,
+            agg,
+            clip,
+            samples,
+            table,
+        } => analyze(
+            Analysis::PriceMinutes {
+                price_csv: csv_in,
+                profile,
+                days,
+                agg,
+                clip,
+                samples,
+                sun: SunArgs::default(),
+                facet: None,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphPriceMinutes {
+            price_csv,
+            output_png,
+            profile,
+            days,
+            agg,
+            clip,
+            sun,
+        } => analyze(
+            Analysis::PriceMinutes {
+                price_csv,
+                profile,
+                days,
+                agg,
+                clip,
+                samples: SampleArgs::default(),
+                sun,
+                facet: None,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteGenMinutes {
+            csv_in,
+            csv_out,
+            profile,
+            days,
+            agg,
+            samples,
+            share,
+            table,
+        } => analyze(
+            Analysis::GenMinutes {
+                gen_csv: csv_in,
+                profile,
+                days,
+                agg,
+                samples,
+                sun: SunArgs::default(),
+                facet: None,
+                band_source: None,
+                share,
+                legend: LegendArgs::default(),
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphGenMinutes {
+            gen_csv,
+            output_png,
+            profile,
+            days,
+            agg,
+            sun,
+            band_source,
+            share,
+        } => analyze(
+            Analysis::GenMinutes {
+                gen_csv,
+                profile,
+                days,
+                agg,
+                samples: SampleArgs::default(),
+                sun,
+                facet: None,
+                band_source,
+                share,
+                legend: LegendArgs::default(),
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteGenSolarBattery {
+            csv_in,
+            csv_out,
+            profile,
+            days,
+            agg,
+            samples,
+            table,
+        } => analyze(
+            Analysis::GenSolarBattery {
+                gen_csv: csv_in,
+                profile,
+                days,
+                agg,
+                samples,
+                sun: SunArgs::default(),
+                legend: LegendArgs::default(),
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphGenSolarBattery {
+            gen_csv,
+            output_png,
+            profile,
+            days,
+            agg,
+            sun,
+        } => analyze(
+            Analysis::GenSolarBattery {
+                gen_csv,
+                profile,
+                days,
+                agg,
+                samples: SampleArgs::default(),
+                sun,
+                legend: LegendArgs::default(),
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteValueMinutes {
+            price_csv,
+            gen_csv,
+            csv_out,
+            scale,
+            table,
+        } => analyze(
+            Analysis::ValueMinutes {
+                price_csv,
+                gen_csv,
+                scale,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphValueMinutes {
+            price_csv,
+            gen_csv,
+            output_png,
+            scale,
+        } => analyze(
+            Analysis::ValueMinutes {
+                price_csv,
+                gen_csv,
+                scale,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteValueSolarBattery {
+            price_csv,
+            gen_csv,
+            csv_out,
+            table,
+        } => analyze(
+            Analysis::ValueSolarBattery {
+                price_csv,
+                gen_csv,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphValueSolarBattery {
+            price_csv,
+            gen_csv,
+            output_png,
+        } => analyze(
+            Analysis::ValueSolarBattery {
+                price_csv,
+                gen_csv,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteValueProfile {
+            price_csv,
+            gen_csv,
+            csv_out,
+            profile,
+            table,
+        } => analyze(
+            Analysis::ValueProfile {
+                price_csv,
+                gen_csv,
+                profile,
+                sun: SunArgs::default(),
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphValueProfile {
+            price_csv,
+            gen_csv,
+            output_png,
+            profile,
+            sun,
+        } => analyze(
+            Analysis::ValueProfile {
+                price_csv,
+                gen_csv,
+                profile,
+                sun,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteStorageSweep {
+            price_csv,
+            gen_csv,
+            csv_out,
+            max_gwh,
+            step_gwh,
+            table,
+        } => analyze(
+            Analysis::StorageSweep {
+                price_csv,
+                gen_csv,
+                max_gwh,
+                step_gwh,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphStorageSweep {
+            price_csv,
+            gen_csv,
+            output_png,
+            max_gwh,
+            step_gwh,
+        } => analyze(
+            Analysis::StorageSweep {
+                price_csv,
+                gen_csv,
+                max_gwh,
+                step_gwh,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WritePriceImpact {
+            price_csv,
+            gen_csv,
+            csv_out,
+            scale,
+            table,
+        } => analyze(
+            Analysis::PriceImpact {
+                price_csv,
+                gen_csv,
+                scale,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphPriceImpact {
+            price_csv,
+            gen_csv,
+            output_png,
+            scale,
+        } => analyze(
+            Analysis::PriceImpact {
+                price_csv,
+                gen_csv,
+                scale,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WritePriceSpikes {
+            price_csv,
+            csv_out,
+            threshold,
+            table,
+        } => analyze(
+            Analysis::PriceSpikes {
+                price_csv,
+                threshold,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphPriceSpikes {
+            price_csv,
+            output_png,
+            threshold,
+        } => analyze(
+            Analysis::PriceSpikes {
+                price_csv,
+                threshold,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteCorrelation {
+            price_csv,
+            gen_csv,
+            csv_out,
+            threads,
+            table,
+        } => analyze(
+            Analysis::Correlation {
+                price_csv,
+                gen_csv,
+                threads,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphCorrelation {
+            price_csv,
+            gen_csv,
+            output_png,
+            threads,
+        } => analyze(
+            Analysis::Correlation {
+                price_csv,
+                gen_csv,
+                threads,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteCapacityFactors {
+            gen_csv,
+            capacity_toml,
+            csv_out,
+            by_month,
+            table,
+        } => analyze(
+            Analysis::CapacityFactors {
+                gen_csv,
+                capacity_toml,
+                by_month,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphCapacityFactors {
+            gen_csv,
+            capacity_toml,
+            output_png,
+            by_month,
+        } => analyze(
+            Analysis::CapacityFactors {
+                gen_csv,
+                capacity_toml,
+                by_month,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteSolarSplit {
+            price_csv,
+            gen_csv,
+            csv_out,
+            solar_threshold,
+            table,
+        } => analyze(
+            Analysis::SolarSplit {
+                price_csv,
+                gen_csv,
+                solar_threshold,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphSolarSplit {
+            price_csv,
+            gen_csv,
+            output_png,
+            solar_threshold,
+        } => analyze(
+            Analysis::SolarSplit {
+                price_csv,
+                gen_csv,
+                solar_threshold,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteTouSummary {
+            price_csv,
+            gen_csv,
+            tou_toml,
+            csv_out,
+            table,
+        } => analyze(
+            Analysis::TouSummary {
+                price_csv,
+                gen_csv,
+                tou_toml,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphTouSummary {
+            price_csv,
+            gen_csv,
+            tou_toml,
+            output_png,
+        } => analyze(
+            Analysis::TouSummary {
+                price_csv,
+                gen_csv,
+                tou_toml,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteHydroYears {
+            price_csv,
+            gen_csv,
+            water_years_toml,
+            csv_out,
+            table,
+        } => analyze(
+            Analysis::HydroYears {
+                price_csv,
+                gen_csv,
+                water_years_toml,
+                dataset: Vec::new(),
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphHydroYears {
+            price_csv,
+            gen_csv,
+            water_years_toml,
+            output_png,
+        } => analyze(
+            Analysis::HydroYears {
+                price_csv,
+                gen_csv,
+                water_years_toml,
+                dataset: Vec::new(),
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteComplementarity {
+            gen_csv,
+            csv_out,
+            table,
+        } => analyze(
+            Analysis::Complementarity {
+                gen_csv,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphComplementarity {
+            gen_csv,
+            output_png,
+        } => analyze(
+            Analysis::Complementarity {
+                gen_csv,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteWeatherCorrelation {
+            price_csv,
+            gen_csv,
+            weather_csv,
+            csv_out,
+            weather,
+            table,
+        } => analyze(
+            Analysis::WeatherCorrelation {
+                price_csv,
+                gen_csv,
+                weather_csv,
+                weather,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphWeatherCorrelation {
+            price_csv,
+            gen_csv,
+            weather_csv,
+            output_png,
+            weather,
+        } => analyze(
+            Analysis::WeatherCorrelation {
+                price_csv,
+                gen_csv,
+                weather_csv,
+                weather,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteNetLoadPeaks {
+            price_csv,
+            gen_csv,
+            csv_out,
+            top,
+            table,
+        } => analyze(
+            Analysis::NetLoadPeaks {
+                price_csv,
+                gen_csv,
+                top,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphNetLoadPeaks {
+            price_csv,
+            gen_csv,
+            output_png,
+            top,
+        } => analyze(
+            Analysis::NetLoadPeaks {
+                price_csv,
+                gen_csv,
+                top,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteInterchange {
+            price_csv,
+            gen_csv,
+            csv_out,
+            table,
+        } => analyze(
+            Analysis::Interchange {
+                price_csv,
+                gen_csv,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphInterchange {
+            price_csv,
+            gen_csv,
+            output_png,
+        } => analyze(
+            Analysis::Interchange {
+                price_csv,
+                gen_csv,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteBaInterchange {
+            interchange_csv,
+            csv_out,
+            table,
+        } => analyze(
+            Analysis::BaInterchange {
+                interchange_csv,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphBaInterchange {
+            interchange_csv,
+            output_png,
+        } => analyze(
+            Analysis::BaInterchange {
+                interchange_csv,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteBatterySplit {
+            price_csv,
+            gen_csv,
+            csv_out,
+            table,
+        } => analyze(
+            Analysis::BatterySplit {
+                price_csv,
+                gen_csv,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphBatterySplit {
+            price_csv,
+            gen_csv,
+            output_png,
+        } => analyze(
+            Analysis::BatterySplit {
+                price_csv,
+                gen_csv,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteArbitrageSpread {
+            price_csv,
+            csv_out,
+            spread,
+            table,
+        } => analyze(
+            Analysis::ArbitrageSpread {
+                price_csv,
+                spread,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphArbitrageSpread {
+            price_csv,
+            output_png,
+            spread,
+        } => analyze(
+            Analysis::ArbitrageSpread {
+                price_csv,
+                spread,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteDayCoverage {
+            csv_in,
+            csv_out,
+            profile,
+            days,
+            table,
+        } => {
+            let coverage = settings
+                .compute(&csv_in)
+                .with_days(days.policy())
+                .day_coverage(profile.profile)?;
+            for (date, intervals) in &coverage.partial {
+                println!(
+                    "{date} is a partial day with {intervals} of {} intervals",
+                    Compute::slots_per_day()
+                );
+            }
+            let fewest = coverage.full_days.iter().min().copied().unwrap_or_default();
+            let most = coverage.full_days.iter().max().copied().unwrap_or_default();
+            println!("Each slot has {fewest} to {most} full days");
+            table.write(&csv_out, write, |out| {
+                convert::write_day_coverage(out, &coverage.full_days, profile.profile, write)
+            })?;
+        }
+        #[cfg(feature = "graph")]
+        Args::GraphGenAnimation {
+            gen_csv,
+            output_gif,
+            frame,
+            frame_ms,
+        } => {
+            let frames = settings
+                .compute(&gen_csv)
+                .average_gen_5min_by_period(frame)?;
+            graphing(&output_gif, settings).gen_animation(
+                &frames,
+                "Average generation by source",
+                frame_ms,
+            )?;
+        }
+        #[cfg(feature = "graph")]
+        Args::GraphPriceGenOverlay {
+            price_csv,
+            gen_csv,
+            output_png,
+            profile,
+            sun,
+        } => {
+            let (prices, gen) = cached(
+                settings,
+                "price_gen_profile",
+                &profile.profile,
+                &[&price_csv, &gen_csv],
+                || {
+                    settings
+                        .compute(&price_csv)
+                        .price_gen_profile(&gen_csv, profile.profile)
+                },
+            )?;
+            let markers = sun.markers(settings, profile.profile, || {
+                cached(settings, "price_dates", &(), &[&price_csv], || {
+                    settings.compute(&price_csv).price_dates()
+                })
+            })?;
+            let title = format!("{} generation mix and price", profile.adjective());
+            graphing(&output_png, settings)
+                .with_markers(markers)
+                .price_gen_overlay(&prices, &gen, profile.profile, &title)?;
+        }
+        #[cfg(feature = "graph")]
+        Args::GraphPriceHistogram {
+            price_csv,
+            output_png,
+            bin_width,
+            log_scale,
+        } => {
+            let series = settings.compute(&price_csv).price_series()?;
+            let bins = histogram(series.iter().map(|(_, price)| *price), bin_width)?;
+            graphing(&output_png, settings).price_histogram(&bins, bin_width, log_scale)?;
+        }
+        #[cfg(feature = "graph")]
+        Args::GraphDay {
+            price_csv,
+            gen_csv,
+            date,
+            output_png,
+        } => {
+            let day = settings.compute(&price_csv).day_profile(&gen_csv, date)?;
+            graphing(&output_png, settings).day(&day)?;
+        }
+        #[cfg(feature = "graph")]
+        Args::GraphDiff { diff } => match diff {
+            Diff::PriceProfile {
+                base_csv,
+                other_csv,
+                output_png,
+                profile,
+            } => {
+                let prices = |csv: &Path| {
+                    settings
+                        .compute(csv)
+                        .sampled_price_profile(profile.profile)
+                        .map(|profiled| profiled.prices)
+                };
+                let delta = profile_delta(&prices(&base_csv)?, &prices(&other_csv)?)?;
+                let name = |csv: &Path| {
+                    csv.file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string()
+                };
+                let title = format!(
+                    "{} price, {} less {}",
+                    profile.adjective(),
+                    name(&other_csv),
+                    name(&base_csv)
+                );
+                graphing(&output_png, settings).profile_delta(&delta, profile.profile, &title)?;
+            }
+            Diff::SolarBattery {
+                price_csv,
+                gen_csv,
+                output_png,
+            } => {
+                let base = settings.compute(&price_csv).average_value_5min(&gen_csv)?;
+                let merged = settings
+                    .compute(&price_csv)
+                    .average_value_solar_battery(&gen_csv)?;
+                graphing(&output_png, settings).value_delta(
+                    &value_delta(&base, &merged),
+                    "Change in price/MWh with Solar + Battery merged",
+                )?;
+            }
+            Diff::Scaled {
+                price_csv,
+                gen_csv,
+                output_png,
+                scale,
+            } => {
+                let scaling = scale.required_scaling()?;
+                let base = settings.compute(&price_csv).average_value_5min(&gen_csv)?;
+                let scaled = settings
+                    .compute(&price_csv)
+                    .average_value_scaled(&gen_csv, &scaling)?;
+                let title = format!(
+                    "Change in price/MWh with {}x {}",
+                    scaling.factor,
+                    scaling.source_name()
+                );
+                graphing(&output_png, settings)
+                    .value_delta(&value_delta(&base, &scaled), &title)?;
+            }
+        },
+        Args::FindExtremes {
+            price_csv,
+            gen_csv,
+            threads,
+        } => {
+            threads.init()?;
+            for day in settings.compute(&price_csv).find_extreme_days(&gen_csv)? {
+                println!("{}: {} ({:.2})", day.label, day.date, day.value);
+            }
+        }
+        #[cfg(feature = "graph")]
+        Args::GraphScatter {
+            price_csv,
+            gen_csv,
+            output_png,
+            source,
+            by_hour,
+        } => {
+            let source_idx = EnergyGenCsvRow::source_idx(&source)?;
+            let (points, fit) = settings
+                .compute(&price_csv)
+                .price_points(&gen_csv, source_idx)?;
+            println!(
+                "Price = {:.4} * {source} MW + {:.2} (R² {:.2}, {} intervals)",
+                fit.slope, fit.intercept, fit.r_squared, fit.count
+            );
+            graphing(&output_png, settings).price_scatter(&points, &fit, &source, by_hour)?;
+        }
+        Args::CaseStudy {
+            price_csv,
+            gen_csv,
+            output_dir,
+            dates,
+            baseline_days,
+        } => {
+            fs::create_dir_all(&output_dir)?;
+            let events =
+                settings
+                    .compute(&price_csv)
+                    .event_days(&gen_csv, &dates, baseline_days)?;
+            let mut deviations = Vec::new();
+            for event in &events {
+                #[cfg(feature = "graph")]
+                graphing(
+                    &output_dir.join(format!("{}.png", event.day.date)),
+                    settings,
+                )
+                .event_day(event)?;
+                let day = event.deviations();
+                if let Some(price) = day.iter().find(|deviation| deviation.series == "Price") {
+                    println!(
+                        "{}: price averaged ${:.2} against ${:.2} over the {} days around \
+                         it, with the widest gap {:+.2} at {}",
+                        price.date,
+                        price.actual,
+                        price.baseline,
+                        event.baseline_days,
+                        price.largest_gap,
+                        price.largest_gap_at
+                    );
+                }
+                deviations.extend(day);
+            }
+            convert::write_event_deviations(
+                &output_dir.join("case_study.csv"),
+                &deviations,
+                write,
+            )?;
+        }
+        #[cfg(feature = "graph")]
+        Args::Report {
+            price_csv,
+            gen_csv,
+            output_dir,
+            format,
+        } => {
+            let path = Report::generate(
+                &settings.compute(&price_csv),
+                &gen_csv,
+                &output_dir,
+                format,
+                write,
+            )?;
+            println!("Wrote report to {path:?}");
+        }
+        Args::WritePriceForecast {
+            price_csv,
+            csv_out,
+            baseline,
+            table,
+        } => analyze(
+            Analysis::PriceForecast {
+                price_csv,
+                baseline,
+                date: None,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        Args::WriteForecastErrors {
+            price_csv,
+            csv_out,
+            table,
+        } => analyze(
+            Analysis::ForecastErrors {
+                price_csv,
+                date: None,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        #[cfg(feature = "graph")]
+        Args::GraphPriceForecast {
+            price_csv,
+            output_png,
+            baseline,
+            date,
+        } => analyze(
+            Analysis::PriceForecast {
+                price_csv,
+                baseline,
+                date,
+                out: OutArgs::png(output_png),
+            },
+            settings,
+        )?,
+        Args::WriteTariffCosts {
+            price_csv,
+            load_csv,
+            tariffs_toml,
+            csv_out,
+            table,
+        } => analyze(
+            Analysis::TariffCosts {
+                price_csv,
+                load_csv,
+                tariffs_toml,
+                out: OutArgs::csv(csv_out, table),
+            },
+            settings,
+        )?,
+        Args::OptimizeCharging {
+            price_csv,
+            csv_out,
+            kwh,
+            power_kw,
+            window_start,
+            window_end,
+            per_day,
+            objective,
+            gen_csv,
+            schedule_csv,
+            table,
+        } => {
+            if kwh <= 0. || power_kw <= 0. {
+                bail!("--kwh and --power-kw must be positive");
+            }
+            let scenario = ChargingScenario {
+                kwh,
+                power_kw,
+                start: window_start,
+                end: window_end,
+                objective,
+            };
+            let plans = settings.compute(&price_csv).charging_plans(
+                &scenario,
+                per_day,
+                gen_csv.as_deref(),
+            )?;
+            table.write(&csv_out, write, |out| {
+                convert::write_charging_plans(out, &plans, write)
+            })?;
+            if let Some(schedule_csv) = schedule_csv {
+                convert::write_charging_schedule(&schedule_csv, &plans, write)?;
+            }
+        }
+        #[cfg(feature = "graph")]
+        Args::GraphPriceTimeseries {
+            price_csv,
+            output_png,
+            rolling,
+        } => {
+            let daily = cached(settings, "daily_average_prices", &(), &[&price_csv], || {
+                settings.compute(&price_csv).daily_average_prices()
+            })?;
+            let smoothed = rolling_mean(&daily, rolling.window.into());
+            graphing(&output_png, settings).price_timeseries(
+                &daily,
+                &smoothed,
+                &format!("Daily average price/MWh ({}-day rolling)", rolling.window),
+            )?;
+        }
+        #[cfg(feature = "graph")]
+        Args::GraphGenTimeseries {
+            gen_csv,
+            output_png,
+            rolling,
+        } => {
+            let daily = cached(settings, "daily_average_gen", &(), &[&gen_csv], || {
+                settings.compute(&gen_csv).daily_average_gen()
+            })?;
+            graphing(&output_png, settings).gen_timeseries(
+                &rolling_mean(&daily, rolling.window.into()),
+                &format!(
+                    "Average generation by source ({}-day rolling)",
+                    rolling.window
+                ),
+            )?;
+        }
+        #[cfg(feature = "testdata")]
+        Args::GenTestdata {
+            price_csv,
+            gen_csv,
+            days,
+            seed,
+            noise,
+            price_gap_every,
+            gen_gap_every,
+            nodal_csv,
+            nodes,
+            ancillary_csv,
+        } => {
+            let data = SyntheticData {
+                days,
+                seed,
+                noise,
+                price_gap_every,
+                gen_gap_every,
+                ..Default::default()
+            };
+            data.write_price_csv(&price_csv)?;
+            data.write_gen_csv(&gen_csv)?;
+            if let Some(nodal_csv) = nodal_csv {
+                data.write_nodal_csv(&nodal_csv, nodes)?;
+            }
+            if let Some(ancillary_csv) = ancillary_csv {
+                data.write_ancillary_csv(&ancillary_csv)?;
+            }
+        }
+        Args::Completions { shell } => {
+            let mut cmd = Cli::command();
+            let name = cmd.get_name().to_string();
+            clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+        }
+        Args::Man { output_dir } => {
+            fs::create_dir_all(&output_dir)?;
+            let cmd = Cli::command();
+            let name = cmd.get_name().to_string();
+            let mut pages = vec![(name.clone(), cmd.clone())];
+            for sub in cmd.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+                let page = format!("{name}-{}", sub.get_name());
+                let sub = sub
+                    .clone()
+                    .display_name(&page)
+                    .bin_name(format!("{name} {}", sub.get_name()));
+                pages.push((page, sub));
+            }
+            for (page, cmd) in pages {
+                let mut buf = Vec::new();
+                clap_mangen::Man::new(cmd).render(&mut buf)?;
+                fs::write(output_dir.join(format!("{page}.1")), buf)?;
+            }
+            println!("Wrote manpages to {output_dir:?}");
+        }
+        Args::FetchOasis {
+            start,
+            end,
+            node,
+            zip,
+            output_csv,
+            gen_csv,
+            append,
+        } => {
+            let resume = |path: &Path, kind| match append {
+                true => fetch::resume_after(path, kind),
+                false => Ok(None),
+            };
+            let after = resume(&output_csv, RawKind::Price)?;
+            let gen_after = gen_csv
+                .as_deref()
+                .map(|gen_csv| resume(gen_csv, RawKind::Gen))
+                .transpose()?
+                .flatten();
+            let (mut lmps, mut mix) = if zip.is_empty() {
+                // Appending starts from whichever output is further behind.
+                let behind = match gen_csv {
+                    Some(_) => after.min(gen_after),
+                    None => after,
+                };
+                let (start, end) = fetch_dates(start, end, behind, &output_csv)?;
+                let nodes = match node.is_empty() {
+                    true => oasis::HUBS.map(String::from).to_vec(),
+                    false => node,
+                };
+                let mut client = fetch::Client::new(oasis::REQUEST_GAP);
+                let lmps = oasis::fetch_lmps(&mut client, start, end, &nodes)?;
+                let mix = match gen_csv {
+                    Some(_) => oasis::fetch_mix(&mut client, start, end)?,
+                    None => Vec::new(),
+                };
+                (lmps, mix)
+            } else {
+                let (mut lmps, mut mix) = (Vec::new(), Vec::new());
+                for zip in &zip {
+                    let bytes = fs::read(zip)?;
+                    lmps.extend(oasis::parse_zip(&bytes)?);
+                    mix.extend(oasis::parse_mix_zip(&bytes)?);
+                }
+                (lmps, mix)
+            };
+            if let Some(after) = after {
+                lmps.retain(|lmp| fetch::pacific_from_utc(lmp.start) > after);
+            }
+            let count = oasis::write_prices(&output_csv, &lmps, append, write)?;
+            let first = lmps
+                .iter()
+                .map(|lmp| fetch::pacific_from_utc(lmp.start))
+                .min();
+            report_fetched(&output_csv, "prices", count, after, first);
+
+            if let Some(gen_csv) = gen_csv {
+                if let Some(after) = gen_after {
+                    mix.retain(|item| fetch::pacific_from_utc(item.start) > after);
+                }
+                let count = oasis::write_gen(&gen_csv, &mix, append, write)?;
+                let first = mix
+                    .iter()
+                    .map(|item| fetch::pacific_from_utc(item.start))
+                    .min();
+                report_fetched(&gen_csv, "intervals of generation", count, gen_after, first);
+            }
+        }
+        Args::FetchEia {
+            start,
+            end,
+            output_csv,
+            demand_csv,
+            interchange_csv,
+            append,
+        } => {
+            let after = match append {
+                true => fetch::resume_after(&output_csv, RawKind::Gen)?,
+                false => None,
+            };
+            let (start, end) = fetch_dates(start, end, after, &output_csv)?;
+            let mut api = EiaApi::new(
+                settings.eia_api_key()?,
+                settings.rto.eia_respondent().to_string(),
+            );
+            let mut hourly = api.hourly(start, end)?;
+            if let Some(after) = after {
+                hourly.retain_after(after);
+            }
+            hourly.write_gen(&output_csv, append, write)?;
+            let first = hourly
+                .gen
+                .keys()
+                .next()
+                .map(|hour_end| fetch::pacific_from_utc(*hour_end - TimeDelta::hours(1)));
+            report_fetched(
+                &output_csv,
+                "hours of generation",
+                hourly.gen.len(),
+                after,
+                first,
+            );
+            if let Some(demand_csv) = demand_csv {
+                hourly.write_demand(&demand_csv, append, write)?;
+            }
+            if let Some(interchange_csv) = interchange_csv {
+                hourly.write_interchange(&interchange_csv, append, write)?;
+            }
+        }
+        Args::Serve {
+            price_csv,
+            gen_csv,
+            addr,
+        } => {
+            Service::new(price_csv, gen_csv)
+                .with_style(settings.theme, settings.labels.clone())
+                .with_price_node(settings.price_node)
+                .serve(&addr)?;
+        }
+        Args::Monitor {
+            price_csv,
+            gen_csv,
+            price_above,
+            price_below,
+            solar_share_above,
+            command,
+            webhook,
+            once,
+        } => {
+            let thresholds = Thresholds {
+                price_above,
+                price_below,
+                solar_share_above,
+            };
+            if price_above.is_none() && price_below.is_none() && solar_share_above.is_none() {
+                bail!("Give at least one threshold to monitor");
+            }
+            let hooks: Vec<Hook> = command
+                .map(Hook::Command)
+                .into_iter()
+                .chain(webhook.map(Hook::Webhook))
+                .collect();
+            let mut monitor = Monitor::new(thresholds);
+            let mut watcher = Watcher::new(vec![price_csv.clone(), gen_csv.clone()]);
+            loop {
+                for alert in monitor.check_files(&settings.compute(&price_csv), &gen_csv)? {
+                    println!("{}", alert.message());
+                    for hook in &hooks {
+                        // A hook that's down shouldn't stop the monitor.
+                        if let Err(e) = hook.fire(&alert) {
+                            eprintln!("{e:#}");
+                        }
+                    }
+                }
+                if once {
+                    break;
+                }
+                watcher.wait();
+            }
+        }
+        Args::Pipeline { pipeline_toml } => {
+            let lines = Pipeline::load(&pipeline_toml)?.command_lines()?;
+            // Parse every step before running any so a bad argument late in
+            // the file doesn't leave a half-finished run behind.
+            let steps = lines
+                .iter()
+                .map(|line| {
+                    let bin = std::iter::once(env!("CARGO_PKG_NAME"));
+                    match Cli::try_parse_from(bin.chain(line.iter().map(String::as_str))) {
+                        Ok(Cli {
+                            command: Args::Pipeline { .. },
+                            ..
+                        }) => bail!("Pipelines cannot run other pipelines"),
+                        Ok(cli)
+                            if cli.config.is_some()
+                                || cli.overwrite
+                                || cli.stamped_dir.is_some()
+                                || cli.no_cache
+                                || cli.no_meta
+                                || cli.chart != ChartArgs::default()
+                                || cli.watch =>
+                        {
+                            bail!("Global options go on the pipeline command, not its steps")
+                        }
+                        Ok(cli) => Ok(cli.command),
+                        Err(e) => Err(anyhow!("{}: {e}", line.join(" "))),
+                    }
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            for (idx, (step, line)) in steps.into_iter().zip(&lines).enumerate() {
+                println!("[{}/{}] {}", idx + 1, lines.len(), line.join(" "));
+                run(step, settings, stamped)
+                    .map_err(|e| anyhow!("Step {} ({}) failed: {e}", idx + 1, line[0]))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `cache::cached`, keyed also on the price node the run reads prices at.
+fn cached<T: Serialize + DeserializeOwned>(
+    settings: &Settings,
+    operation: &str,
+    params: &impl Debug,
+    inputs: &[&Path],
+    compute: impl FnOnce() -> error::Result<T>,
+) -> error::Result<T> {
+    cache::cached(operation, &(settings.price_node, params), inputs, compute)
+}
+
+/// Computes an analysis once, then writes whichever of its csv and chart
+/// were asked for.
+fn analyze(analysis: Analysis, settings: &Settings) -> anyhow::Result<()> {
+    let write = settings.write;
+    match analysis {
+        Analysis::PriceMinutes {
+            price_csv,
+            profile,
+            days,
+            agg,
+            clip,
+            samples,
+            #[cfg(feature = "graph")]
+            sun,
+            #[cfg(feature = "graph")]
+            facet,
+            out,
+            ..
+        } => {
+            let compute = settings
+                .compute(&price_csv)
+                .with_days(days.policy())
+                .with_samples(samples.check())
+                .with_agg(agg.agg)
+                .with_clip(clip.clip_percentile);
+            let prices = cached(
+                settings,
+                "sampled_price_profile",
+                &(
+                    profile.profile,
+                    days.policy(),
+                    samples.check(),
+                    agg.agg,
+                    clip.clip_percentile,
+                ),
+                &[&price_csv],
+                || compute.sampled_price_profile(profile.profile),
+            )?;
+            clip.report(prices.clipped, settings);
+            out.write_csv(write, |csv| {
+                convert::write_energy_price_averages(csv, &prices.prices, &prices.samples, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                if let Some(period) = facet {
+                    if profile.profile != Profile::Day {
+                        bail!("--facet only applies to --profile day");
+                    }
+                    let facets = cached(
+                        settings,
+                        "price_profile_by_period",
+                        &(
+                            period,
+                            days.policy(),
+                            samples.check(),
+                            agg.agg,
+                            clip.clip_percentile,
+                        ),
+                        &[&price_csv],
+                        || compute.average_price_5min_by_period(period),
+                    )?;
+                    let title = format!("Daily average price/MWh by {}", period.noun());
+                    graphing(png, settings).facet_price(&facets, &title)?;
+                    return Ok(());
+                }
+                let markers = sun.markers(settings, profile.profile, || {
+                    cached(settings, "price_dates", &(), &[&price_csv], || {
+                        compute.price_dates()
+                    })
+                })?;
+                graphing(png, settings)
+                    .with_markers(markers)
+                    .daily_price(&prices.prices, profile.profile)?;
+            }
+        }
+        Analysis::GenMinutes {
+            gen_csv,
+            profile,
+            days,
+            agg,
+            samples,
+            #[cfg(feature = "graph")]
+            sun,
+            #[cfg(feature = "graph")]
+            facet,
+            #[cfg(feature = "graph")]
+            band_source,
+            share,
+            #[cfg(feature = "graph")]
+            legend,
+            out,
+            ..
+        } => {
+            let compute = settings
+                .compute(&gen_csv)
+                .with_days(days.policy())
+                .with_samples(samples.check())
+                .with_agg(agg.agg);
+            let (mut gen, counts) = cached(
+                settings,
+                "sampled_gen_profile",
+                &(profile.profile, days.policy(), samples.check(), agg.agg),
+                &[&gen_csv],
+                || compute.sampled_gen_profile(profile.profile),
+            )?;
+            if share {
+                gen = Compute::gen_shares(&gen);
+            }
+            out.write_csv(write, |csv| {
+                convert::write_energy_gen_averages(csv, &gen, &counts, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                if let Some(period) = facet {
+                    if profile.profile != Profile::Day {
+                        bail!("--facet only applies to --profile day");
+                    }
+                    let facets = cached(
+                        settings,
+                        "gen_profile_by_period",
+                        &(period, days.policy(), samples.check(), agg.agg),
+                        &[&gen_csv],
+                        || compute.average_gen_5min_by_period(period),
+                    )?;
+                    let title = format!("Daily average generation by source by {}", period.noun());
+                    graphing(png, settings).facet_gen(&facets, &title)?;
+                    return Ok(());
+                }
+                let markers = sun.markers(settings, profile.profile, || {
+                    cached(settings, "gen_dates", &(), &[&gen_csv], || {
+                        compute.gen_dates()
+                    })
+                })?;
+                if share {
+                    let title = format!("{} share of generation by source", profile.adjective());
+                    graphing(png, settings).with_markers(markers).gen_share(
+                        &gen,
+                        profile.profile,
+                        &title,
+                    )?;
+                    return Ok(());
+                }
+                let bands = match &band_source {
+                    Some(source) => Some((
+                        EnergyGenCsvRow::source_idx(source)?,
+                        compute.gen_percentile_bands(profile.profile)?,
+                    )),
+                    None => None,
+                };
+                let title = format!("{} average generation by source", profile.adjective());
+                legend
+                    .apply(graphing(png, settings))
+                    .with_markers(markers)
+                    .daily_gen(
+                        &gen,
+                        profile.profile,
+                        &title,
+                        bands.as_ref().map(|(source, bands)| SourceBand {
+                            source: *source,
+                            bands,
+                        }),
+                    )?;
+            }
+        }
+        Analysis::GenSolarBattery {
+            gen_csv,
+            profile,
+            days,
+            agg,
+            samples,
+            #[cfg(feature = "graph")]
+            sun,
+            #[cfg(feature = "graph")]
+            legend,
+            out,
+            ..
+        } => {
+            let compute = settings
+                .compute(&gen_csv)
+                .with_days(days.policy())
+                .with_samples(samples.check())
+                .with_agg(agg.agg);
+            let (gen, counts) = cached(
+                settings,
+                "sampled_gen_solar_battery",
+                &(profile.profile, days.policy(), samples.check(), agg.agg),
+                &[&gen_csv],
+                || compute.sampled_gen_solar_battery(profile.profile),
+            )?;
+            out.write_csv(write, |csv| {
+                convert::write_energy_gen_averages(csv, &gen, &counts, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let markers = sun.markers(settings, profile.profile, || {
+                    cached(settings, "gen_dates", &(), &[&gen_csv], || {
+                        compute.gen_dates()
+                    })
+                })?;
+                let title = format!("{} average Solar + Battery", profile.adjective());
+                legend
+                    .apply(graphing(png, settings))
+                    .with_markers(markers)
+                    .daily_gen(&gen, profile.profile, &title, None)?;
+            }
+        }
+        Analysis::ValueMinutes {
+            price_csv,
+            gen_csv,
+            scale,
+            out,
+        } => {
+            let (values, qtys) = cached(
+                settings,
+                "value_5min",
+                &scale,
+                &[&price_csv, &gen_csv],
+                || value_5min(&settings.compute(&price_csv), &gen_csv, &scale),
+            )?;
+            out.write_csv(write, |csv| {
+                convert::write_energy_value_averages(csv, &values, &qtys, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).avg_value(&values, "Average value by source")?;
+            }
+        }
+        Analysis::ValueSolarBattery {
+            price_csv,
+            gen_csv,
+            out,
+        } => {
+            let (values, qtys) = settings
+                .compute(&price_csv)
+                .average_value_solar_battery(&gen_csv)?;
+            out.write_csv(write, |csv| {
+                convert::write_energy_value_averages(csv, &values, &qtys, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).avg_value(&values, "Average value of Solar + Battery")?;
+            }
+        }
+        Analysis::ValueComparison {
+            price_csv,
+            gen_csv,
+            out,
+        } => {
+            let (base, _) = settings.compute(&price_csv).average_value_5min(&gen_csv)?;
+            let (paired, _) = settings
+                .compute(&price_csv)
+                .average_value_solar_battery(&gen_csv)?;
+            let scenarios = [
+                ("base".to_string(), base),
+                ("solar_battery".to_string(), paired),
+            ];
+            out.write_csv(write, |csv| {
+                convert::write_value_scenarios(csv, &scenarios, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let scenarios = [
+                    ("Base".to_string(), base),
+                    ("Solar + Battery".to_string(), paired),
+                ];
+                graphing(png, settings)
+                    .value_scenarios(&scenarios, "Average value, base vs Solar + Battery")?;
+            }
+        }
+        Analysis::DailyRevenue {
+            price_csv,
+            gen_csv,
+            #[cfg(feature = "graph")]
+            rolling,
+            out,
+            ..
+        } => {
+            let daily = settings.compute(&price_csv).daily_revenue(&gen_csv)?;
+            out.write_csv(write, |csv| {
+                let rows: Vec<_> = daily
+                    .iter()
+                    .map(|(date, sources)| (date.to_string(), *sources))
+                    .collect();
+                convert::write_labeled_source_values(csv, "date", &rows, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).revenue_timeseries(
+                    &rolling_mean(&daily, rolling.window.into()),
+                    &format!("Revenue by source ({}-day rolling)", rolling.window),
+                )?;
+            }
+        }
+        Analysis::ValueProfile {
+            price_csv,
+            gen_csv,
+            profile,
+            #[cfg(feature = "graph")]
+            sun,
+            out,
+            ..
+        } => {
+            let values = cached(
+                settings,
+                "value_profile",
+                &profile.profile,
+                &[&price_csv, &gen_csv],
+                || {
+                    settings
+                        .compute(&price_csv)
+                        .value_profile(&gen_csv, profile.profile)
+                },
+            )?;
+            out.write_csv(write, |csv| {
+                let labeled: Vec<_> = values
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, row)| (profile.profile.label(idx), *row))
+                    .collect();
+                convert::write_labeled_source_values(csv, "time", &labeled, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let markers = sun.markers(settings, profile.profile, || {
+                    cached(settings, "price_dates", &(), &[&price_csv], || {
+                        settings.compute(&price_csv).price_dates()
+                    })
+                })?;
+                let title = format!("{} realized price by source", profile.adjective());
+                graphing(png, settings).with_markers(markers).daily_value(
+                    &values,
+                    profile.profile,
+                    &title,
+                )?;
+            }
+        }
+        Analysis::StorageSweep {
+            price_csv,
+            gen_csv,
+            max_gwh,
+            step_gwh,
+            out,
+        } => {
+            let sweep = settings
+                .compute(&price_csv)
+                .storage_sweep(&gen_csv, max_gwh, step_gwh)?;
+            out.write_csv(write, |csv| {
+                convert::write_storage_sweep(csv, &sweep, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings)
+                    .storage_sweep(&sweep, "Solar + Battery value by added storage")?;
+            }
+        }
+        Analysis::PriceImpact {
+            price_csv,
+            gen_csv,
+            scale,
+            out,
+        } => {
+            let scaling = scale.required_scaling()?;
+            let prices = settings
+                .compute(&price_csv)
+                .price_impact_5min(&gen_csv, &scaling)?;
+            out.write_csv(write, |csv| {
+                convert::write_price_impact(csv, &prices, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let title = format!(
+                    "Daily price with {}x {}",
+                    scaling.factor,
+                    scaling.source_name()
+                );
+                graphing(png, settings).price_impact(&prices, &title)?;
+            }
+        }
+        Analysis::SolarLoss {
+            price_csv,
+            gen_csv,
+            loss,
+            window,
+            elasticity,
+            out,
+        } => {
+            let scaling =
+                SourceScaling::new("Solar", 1. - loss, elasticity)?.with_window(window.window()?);
+            let slots = settings
+                .compute(&price_csv)
+                .net_load_impact_5min(&gen_csv, &scaling)?;
+            out.write_csv(write, |csv| {
+                convert::write_scenario_slots(csv, &slots, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let title = format!(
+                    "{:.0}% of solar lost {} to {}",
+                    loss * 100.,
+                    window.window_start.format("%H:%M"),
+                    window.window_end.format("%H:%M")
+                );
+                graphing(png, settings).scenario_impact(&slots, &title)?;
+            }
+        }
+        Analysis::PriceSpikes {
+            price_csv,
+            threshold,
+            out,
+        } => {
+            let series = cached(settings, "price_series", &(), &[&price_csv], || {
+                settings.compute(&price_csv).price_series()
+            })?;
+            let spike_price = Compute::spike_price(&series, threshold.threshold());
+            let spikes = Compute::find_price_spikes(&series, spike_price);
+            out.write_csv(write, |csv| {
+                convert::write_price_spikes(csv, &spikes, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).price_spikes(&series, spike_price, &spikes)?;
+            }
+        }
+        Analysis::Correlation {
+            price_csv,
+            gen_csv,
+            threads,
+            out,
+        } => {
+            threads.init()?;
+            let corr = settings
+                .compute(&price_csv)
+                .source_price_correlation(&gen_csv)?;
+            out.write_csv(write, |csv| {
+                convert::write_source_price_correlation(csv, &corr, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).correlation_matrix(&corr)?;
+            }
+        }
+        Analysis::CapacityFactors {
+            gen_csv,
+            capacity_toml,
+            by_month,
+            out,
+        } => {
+            let capacity = InstalledCapacity::load(&capacity_toml)?;
+            let compute = settings.compute(&gen_csv);
+            let (factors, column) = match by_month {
+                true => (compute.capacity_factors_monthly(&capacity)?, "month"),
+                false => (compute.capacity_factors_5min(&capacity)?, "time"),
+            };
+            out.write_csv(write, |csv| {
+                convert::write_labeled_source_values(csv, column, &factors, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let (title, x_desc) = match by_month {
+                    true => ("Monthly capacity factor", "Month"),
+                    false => ("Daily capacity factor", "Time of day"),
+                };
+                graphing(png, settings).capacity_factors(&factors, title, x_desc)?;
+            }
+        }
+        Analysis::ReserveMargin {
+            price_csv,
+            gen_csv,
+            demand_csv,
+            capacity_toml,
+            bin_width,
+            out,
+        } => {
+            let capacity = InstalledCapacity::load(&capacity_toml)?;
+            let margin = settings.compute(&price_csv).reserve_margin_prices(
+                &gen_csv,
+                &demand_csv,
+                &capacity,
+                bin_width,
+            )?;
+            println!(
+                "Joined {} price and generation intervals; {} prices and {} generation rows had no partner",
+                margin.price_gen.matched,
+                margin.price_gen.left_unmatched,
+                margin.price_gen.right_unmatched
+            );
+            println!(
+                "Joined {} intervals to demand; {} had no demand and {} hours of demand went unused",
+                margin.join.matched, margin.join.left_unmatched, margin.join.right_unmatched
+            );
+            out.write_csv(write, |csv| {
+                convert::write_margin_bins(csv, &margin.bins, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings)
+                    .reserve_margin(&margin.bins, "Price by implied reserve margin")?;
+            }
+        }
+        Analysis::SolarSplit {
+            price_csv,
+            gen_csv,
+            solar_threshold,
+            out,
+        } => {
+            let summaries = settings
+                .compute(&price_csv)
+                .solar_regime_summaries(&gen_csv, solar_threshold)?;
+            out.write_csv(write, |csv| {
+                convert::write_group_summaries(csv, &summaries, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).grouped_values(
+                    &summary_groups(summaries),
+                    "Value in solar vs non-solar hours",
+                )?;
+            }
+        }
+        Analysis::TouSummary {
+            price_csv,
+            gen_csv,
+            tou_toml,
+            out,
+        } => {
+            let schedule = TouSchedule::load(&tou_toml)?;
+            let summaries = settings
+                .compute(&price_csv)
+                .tou_summaries(&gen_csv, &schedule)?;
+            out.write_csv(write, |csv| {
+                convert::write_group_summaries(csv, &summaries, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings)
+                    .grouped_values(&summary_groups(summaries), "Value by time-of-use period")?;
+            }
+        }
+        Analysis::HydroYears {
+            price_csv,
+            gen_csv,
+            water_years_toml,
+            dataset,
+            out,
+        } => {
+            let years = WaterYears::load(&water_years_toml)?;
+            let others: Vec<_> = dataset
+                .chunks(2)
+                .map(|pair| (pair[0].clone(), pair[1].clone()))
+                .collect();
+            let summaries = settings
+                .compute(&price_csv)
+                .water_year_summaries(&gen_csv, &others, &years)?;
+            out.write_csv(write, |csv| {
+                convert::write_group_summaries(csv, &summaries, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let gen_others: Vec<_> = others.into_iter().map(|(_, gen)| gen).collect();
+                let profiles = settings
+                    .compute(&gen_csv)
+                    .average_gen_5min_by_water_year(&gen_others, &years)?;
+                let hydro = [
+                    EnergyGenCsvRow::source_idx("Large Hydro")?,
+                    EnergyGenCsvRow::source_idx("Small Hydro")?,
+                ];
+                let groups: Vec<_> = profiles
+                    .into_iter()
+                    .map(|(label, gen)| {
+                        let output = gen
+                            .iter()
+                            .map(|row| hydro.iter().map(|&idx| row[idx]).sum());
+                        let label = match summaries.iter().find(|s| s.label == label) {
+                            Some(summary) => format!("{label}, avg ${:.2}", summary.avg_price),
+                            None => label,
+                        };
+                        (label, output.collect())
+                    })
+                    .collect();
+                graphing(png, settings).grouped_profiles(
+                    &groups,
+                    "Hydro output by water year",
+                    &settings.labels.units.power,
+                )?;
+            }
+        }
+        Analysis::Complementarity { gen_csv, out } => {
+            let slots = settings.compute(&gen_csv).wind_solar_complementarity()?;
+            out.write_csv(write, |csv| {
+                convert::write_complementarity(csv, &slots, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let means: Vec<_> = slots
+                    .iter()
+                    .map(|slot| [slot.wind_mw, slot.solar_mw, slot.combined_mw])
+                    .collect();
+                let names = ["Wind", "Solar", "Wind + Solar"];
+                let groups: Vec<_> = names
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, name)| (name.to_string(), means.iter().map(|m| m[idx]).collect()))
+                    .collect();
+                graphing(png, settings).grouped_profiles(
+                    &groups,
+                    "Wind and solar output",
+                    &settings.labels.units.power,
+                )?;
+            }
+        }
+        Analysis::WeatherCorrelation {
+            price_csv,
+            gen_csv,
+            weather_csv,
+            weather,
+            out,
+        } => {
+            let (days, fits) =
+                weather.days(&settings.compute(&price_csv), &gen_csv, &weather_csv)?;
+            for (name, fit) in [
+                ("Peak price", fits.peak_price),
+                ("Evening ramp", fits.evening_ramp),
+            ] {
+                println!(
+                    "{name} = {:.2} * high temperature + {:.2} (R² {:.2}, {} days)",
+                    fit.slope, fit.intercept, fit.r_squared, fit.count
+                );
+            }
+            out.write_csv(write, |csv| convert::write_weather_days(csv, &days, write))?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let temp_desc = format!("Daily high {}", weather.temperature_column);
+                graphing(png, settings).weather_scatter(&days, &fits, &temp_desc)?;
+            }
+        }
+        Analysis::NetLoadPeaks {
+            price_csv,
+            gen_csv,
+            top,
+            out,
+        } => {
+            let peaks = settings.compute(&price_csv).net_load_peaks(&gen_csv, top)?;
+            out.write_csv(write, |csv| {
+                convert::write_net_load_peaks(csv, &peaks, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let groups: Vec<_> = [peaks.peak, peaks.rest]
+                    .into_iter()
+                    .map(|(summary, net_load)| {
+                        let label = format!(
+                            "{} ({:.0} MW net load, avg ${:.2})",
+                            summary.label, net_load, summary.avg_price
+                        );
+                        (label, summary.shares)
+                    })
+                    .collect();
+                graphing(png, settings)
+                    .grouped_shares(&groups, "Generation mix at net-load peaks")?;
+            }
+        }
+        Analysis::PeakTiming {
+            price_csv,
+            gen_csv,
+            min_days,
+            out,
+        } => {
+            let timing = settings
+                .compute(&price_csv)
+                .peak_timing(&gen_csv, min_days)?;
+            for shift in &timing.shifts {
+                println!(
+                    "{} peak moved from {} to {} on {} (p = {:.4})",
+                    shift.series,
+                    PeakTiming::clock(shift.before),
+                    PeakTiming::clock(shift.after),
+                    shift.date,
+                    shift.p_value
+                );
+            }
+            if timing.shifts.is_empty() {
+                println!("No lasting shift in either peak's timing");
+            }
+            out.write_csv(write, |csv| {
+                convert::write_month_peaks(csv, &timing.months, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).peak_timing(&timing, "Timing of daily peaks")?;
+            }
+        }
+        Analysis::Interchange {
+            price_csv,
+            gen_csv,
+            out,
+        } => {
+            let hours = settings.compute(&price_csv).interchange_by_hour(&gen_csv)?;
+            out.write_csv(write, |csv| {
+                convert::write_directions(csv, &hours, ["import", "export"], write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).directions(
+                    &hours,
+                    ["Imports", "Exports"],
+                    "Imports vs exports by hour",
+                )?;
+            }
+        }
+        Analysis::BaInterchange {
+            interchange_csv,
+            out,
+        } => {
+            let bas = settings.compute(&interchange_csv).interchange_by_ba()?;
+            out.write_csv(write, |csv| convert::write_ba_interchange(csv, &bas, write))?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings)
+                    .ba_interchange(&bas, "Imports by neighboring balancing authority")?;
+            }
+        }
+        Analysis::BatterySplit {
+            price_csv,
+            gen_csv,
+            out,
+        } => {
+            let hours = settings.compute(&price_csv).battery_by_hour(&gen_csv)?;
+            out.write_csv(write, |csv| {
+                convert::write_directions(csv, &hours, ["discharge", "charge"], write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).directions(
+                    &hours,
+                    ["Discharge", "Charge"],
+                    "Battery discharge vs charge by hour",
+                )?;
+            }
+        }
+        Analysis::ArbitrageSpread {
+            price_csv,
+            spread,
+            out,
+        } => {
+            let intervals = spread.intervals(settings);
+            let spreads = settings
+                .compute(&price_csv)
+                .daily_arbitrage_spread(intervals)?;
+            out.write_csv(write, |csv| {
+                convert::write_daily_spreads(csv, &spreads, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let hours = intervals * Compute::MINS_INCR / 60;
+                graphing(png, settings).daily_spreads(
+                    &spreads,
+                    &format!("Daily arbitrage spread ({hours}h most vs least expensive)"),
+                )?;
+            }
+        }
+        Analysis::PriceExceedance {
+            price_csv,
+            thresholds,
+            profile,
+            days,
+            samples,
+            out,
+        } => {
+            let compute = settings
+                .compute(&price_csv)
+                .with_days(days.policy())
+                .with_samples(samples.check());
+            let exceedance = cached(
+                settings,
+                "price_exceedance",
+                &(&thresholds, profile.profile, days.policy(), samples.check()),
+                &[&price_csv],
+                || compute.price_exceedance(profile.profile, &thresholds),
+            )?;
+            out.write_csv(write, |csv| {
+                convert::write_price_exceedance(csv, &exceedance, profile.profile, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).price_exceedance(&exceedance, profile.profile)?;
+            }
+        }
+        Analysis::PeriodDiff {
+            csv_in,
+            column,
+            before,
+            after,
+            profile,
+            days,
+            out,
+        } => {
+            let compute = settings.compute(&csv_in).with_days(days.policy());
+            let diffs = compute.period_difference(
+                &column,
+                profile.profile,
+                before.clone(),
+                after.clone(),
+            )?;
+            let significant = diffs.iter().filter(|diff| diff.p_value < 0.05).count();
+            println!(
+                "{column} moved significantly (p < 0.05) in {significant} of {} slots",
+                diffs.len()
+            );
+            out.write_csv(write, |csv| {
+                convert::write_period_difference(csv, &diffs, profile.profile, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let unit = match compute.kind()? {
+                    RawKind::Price => &settings.labels.units.price,
+                    RawKind::Gen => &settings.labels.units.power,
+                };
+                let title = format!(
+                    "{column}, {}..{} against {}..{}",
+                    after.start(),
+                    after.end(),
+                    before.start(),
+                    before.end()
+                );
+                graphing(png, settings).period_difference(&diffs, profile.profile, unit, &title)?;
+            }
+        }
+        Analysis::PriceComponents {
+            price_csv,
+            profile,
+            days,
+            agg,
+            samples,
+            out,
+        } => {
+            let compute = settings
+                .compute(&price_csv)
+                .with_days(days.policy())
+                .with_samples(samples.check())
+                .with_agg(agg.agg);
+            let components = cached(
+                settings,
+                "price_components",
+                &(profile.profile, days.policy(), samples.check(), agg.agg),
+                &[&price_csv],
+                || compute.price_components(profile.profile),
+            )?;
+            out.write_csv(write, |csv| {
+                convert::write_price_components(csv, &components, profile.profile, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).price_components(&components, profile.profile)?;
+            }
+        }
+        Analysis::HubZone {
+            zone_csv,
+            hub_csv,
+            profile,
+            days,
+            agg,
+            samples,
+            out,
+        } => {
+            let [zone, hub] = [&zone_csv, &hub_csv].map(|csv| {
+                let compute = settings
+                    .compute(csv)
+                    .with_days(days.policy())
+                    .with_samples(samples.check())
+                    .with_agg(agg.agg);
+                cached(
+                    settings,
+                    "area_prices",
+                    &(profile.profile, days.policy(), samples.check(), agg.agg),
+                    &[csv],
+                    || compute.area_prices(profile.profile),
+                )
+            });
+            let (zone, hub) = (zone?, hub?);
+            out.write_csv(write, |csv| {
+                convert::write_hub_zone(csv, &zone, &hub, profile.profile, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).hub_zone(&zone, &hub, profile.profile)?;
+            }
+        }
+        Analysis::CongestedNodes {
+            nodal_csv,
+            top,
+            out,
+        } => {
+            let nodes = cached(settings, "congested_nodes", &top, &[&nodal_csv], || {
+                nodal::top_congested(&nodal_csv, top)
+            })?;
+            out.write_csv(write, |csv| {
+                convert::write_congested_nodes(csv, &nodes, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let title = format!("{} most congested nodes", nodes.len());
+                graphing(png, settings).congested_nodes(&nodes, &title)?;
+            }
+        }
+        Analysis::NodeProfile {
+            nodal_csv,
+            nodes,
+            profile,
+            out,
+        } => {
+            let index = NodeIndex::open(&nodal_csv)?;
+            let profiles = nodes
+                .iter()
+                .map(|node| nodal::node_profile(&nodal_csv, &index, node, profile.profile))
+                .collect::<error::Result<Vec<_>>>()?;
+            out.write_csv(write, |csv| {
+                convert::write_node_profiles(csv, &profiles, profile.profile, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).node_profiles(&profiles, profile.profile)?;
+            }
+        }
+        Analysis::SparkSpread {
+            price_csv,
+            gas_csv,
+            gas,
+            profile,
+            out,
+        } => {
+            let prices = gas.prices(&gas_csv)?;
+            let spread = gas::spark_spread(
+                &settings.compute(&price_csv),
+                &prices,
+                gas.heat_rate,
+                profile.profile,
+            )?;
+            if spread.skipped_days > 0 {
+                println!("Left out {} days without a gas price", spread.skipped_days);
+                provenance::note("days_without_gas", spread.skipped_days.to_string());
+            }
+            out.write_csv(write, |csv| {
+                convert::write_spark_spread(csv, &spread, profile.profile, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let title = format!(
+                    "{} average spark spread at {} MMBtu/MWh",
+                    profile.adjective(),
+                    gas.heat_rate
+                );
+                graphing(png, settings).spark_spread(&spread, profile.profile, &title)?;
+            }
+        }
+        Analysis::StorageRevenue {
+            price_csv,
+            ancillary_csv,
+            battery,
+            out,
+        } => {
+            let prices = AncillaryPrices::load(&ancillary_csv)?;
+            let revenue = ancillary::storage_revenue(
+                &settings.compute(&price_csv),
+                &prices,
+                battery.scenario(),
+            )?;
+            if revenue.skipped_hours > 0 {
+                println!(
+                    "Left out {} hours without regulation prices",
+                    revenue.skipped_hours
+                );
+                provenance::note(
+                    "hours_without_regulation",
+                    revenue.skipped_hours.to_string(),
+                );
+            }
+            out.write_csv(write, |csv| {
+                convert::write_storage_revenue(csv, &revenue, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let title = format!(
+                    "Revenue of a {} MW, {}-hour battery",
+                    battery.power_mw, battery.duration_hours
+                );
+                graphing(png, settings).storage_revenue(&revenue, &title)?;
+            }
+        }
+        Analysis::ValueStack {
+            price_csv,
+            ancillary_csv,
+            battery,
+            dispatch,
+            out,
+        } => {
+            let prices = AncillaryPrices::load(&ancillary_csv)?;
+            let stack = ancillary::value_stack(
+                &settings.compute(&price_csv),
+                &prices,
+                battery.scenario(),
+                dispatch,
+            )?;
+            if stack.skipped_hours > 0 {
+                println!(
+                    "Left out {} hours without regulation prices",
+                    stack.skipped_hours
+                );
+                provenance::note("hours_without_regulation", stack.skipped_hours.to_string());
+            }
+            out.write_csv(write, |csv| convert::write_value_stack(csv, &stack, write))?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let title = format!(
+                    "Monthly value stack of a {} MW, {}-hour battery ({})",
+                    battery.power_mw,
+                    battery.duration_hours,
+                    dispatch.name().to_lowercase()
+                );
+                graphing(png, settings).value_stack(&stack, &title)?;
+            }
+        }
+        Analysis::PriceForecast {
+            price_csv,
+            baseline,
+            #[cfg(feature = "graph")]
+            date,
+            out,
+            ..
+        } => {
+            let series = settings.compute(&price_csv).price_series()?;
+            out.write_csv(write, |csv| {
+                convert::write_price_forecast(csv, &baseline.next_day(&series)?, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let date = forecast_date(&series, date, &price_csv)?;
+                let day = forecast::day_of(&baseline.backtest(&series), date)?;
+                let error = baseline.errors(&day)?;
+                let groups = [
+                    ("Actual".to_string(), day.iter().map(|f| f.actual).collect()),
+                    (
+                        format!("Forecast ({}), MAE ${:.2}", baseline.name(), error.mae),
+                        day.iter().map(|f| f.forecast).collect(),
+                    ),
+                ];
+                graphing(png, settings).grouped_profiles(
+                    &groups,
+                    &format!("Price forecast for {date}"),
+                    &settings.labels.units.price,
+                )?;
+            }
+        }
+        Analysis::ForecastErrors {
+            price_csv,
+            #[cfg(feature = "graph")]
+            date,
+            out,
+            ..
+        } => {
+            let series = settings.compute(&price_csv).price_series()?;
+            let errors = Baseline::value_variants()
+                .iter()
+                .map(|baseline| baseline.errors(&baseline.backtest(&series)))
+                .collect::<error::Result<Vec<_>>>()?;
+            out.write_csv(write, |csv| {
+                convert::write_forecast_errors(csv, &errors, write)
+            })?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                let date = forecast_date(&series, date, &price_csv)?;
+                let mut groups = Vec::new();
+                for baseline in Baseline::value_variants() {
+                    let day = forecast::day_of(&baseline.backtest(&series), date)?;
+                    if groups.is_empty() {
+                        groups.push(("Actual".to_string(), day.iter().map(|f| f.actual).collect()));
+                    }
+                    let error = baseline.errors(&day)?;
+                    groups.push((
+                        format!("{}, MAE ${:.2}", baseline.name(), error.mae),
+                        day.iter().map(|f| f.forecast).collect(),
+                    ));
+                }
+                graphing(png, settings).grouped_profiles(
+                    &groups,
+                    &format!("Baseline forecasts for {date}"),
+                    &settings.labels.units.price,
+                )?;
+            }
+        }
+        Analysis::TariffCosts {
+            price_csv,
+            load_csv,
+            tariffs_toml,
+            out,
+        } => {
+            let tariffs = Tariff::load_all(&tariffs_toml)?;
+            let load = LoadProfile::load(&load_csv)?;
+            let prices = settings.compute(&price_csv).price_series()?;
+            let costs = tariff::household_costs(&prices, &load, &tariffs)?;
+            out.write_csv(write, |csv| convert::write_tariff_costs(csv, &costs, write))?;
+            #[cfg(feature = "graph")]
+            if let Some(png) = &out.out_png {
+                graphing(png, settings).tariff_costs(&costs, "Household cost by tariff")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The day a forecast chart shows: `date` if given, else the last day of
+/// the series.
+#[cfg(feature = "graph")]
+fn forecast_date(
+    series: &[(NaiveDateTime, f64)],
+    date: Option<NaiveDate>,
+    price_csv: &Path,
+) -> anyhow::Result<NaiveDate> {
+    match date {
+        Some(date) => Ok(date),
+        None => series
+            .last()
+            .map(|(time, _)| time.date())
+            .ok_or_else(|| anyhow!("No prices in {price_csv:?}")),
+    }
+}
+
+/// Group summaries as bar groups labeled with their average price.
+#[cfg(feature = "graph")]
+fn summary_groups(summaries: Vec<GroupSummary>) -> Vec<(String, [f64; 14])> {
+    summaries
+        .into_iter()
+        .map(|summary| {
+            let label = format!("{} (avg ${:.2})", summary.label, summary.avg_price);
+            (label, summary.values)
+        })
+        .collect()
+}
@@ -2,145 +2,3349 @@
 //! Calculations on energy price and production caiso data
 //! preprocessed through the `convert` module.
 
-use crate::convert::{EnergyGenCsvRow, EnergyPriceCsvRow};
-use anyhow::bail;
-use chrono::NaiveDateTime;
-use csv::DeserializeRecordsIntoIter;
-use std::{array, cmp::Ordering, fs::File, iter::Peekable, path::Path};
+use crate::config::{InstalledCapacity, TouSchedule, WaterYears};
+use crate::convert::{
+    parse_timestamp, read_rows, read_rows_from, CsvRow, DemandCsvRow, EnergyGenCsvRow,
+    EnergyPriceCsvRow, InterchangeCsvRow, RawKind, TIMESTAMP_FMT, TOTAL_TOLERANCE_MW,
+};
+use crate::dataset::Dataset;
+use crate::error::{self, bail, err, EnergyAnalysisError};
+use crate::frame::Frame;
+use crate::join::{self, JoinMode, JoinStats, TimeAlignedJoin};
+use crate::manifest::Manifest;
+use crate::scenario::{
+    carbon_intensity, BatteryScenario, ChargingPlan, ChargingScenario, SourceScaling,
+    StorageSweepRow,
+};
+use crate::stats::{
+    changepoints, percentile, Clip, ClipBounds, Correlation, LinearFit, MeanDifference,
+};
+use crate::store::Store;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike};
+#[cfg(feature = "native")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::{
+    array,
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    iter,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
 
+#[derive(Clone)]
 pub struct Compute<'a> {
     path: &'a Path,
+    days: DayPolicy,
+    samples: SampleCheck,
+    agg: ProfileAgg,
+    clip: Option<Clip>,
+    node: PriceNode,
+    datasets: Vec<&'a Dataset>,
 }
 
-struct PriceGenIter {
-    prices: Peekable<DeserializeRecordsIntoIter<File, EnergyPriceCsvRow>>,
-    gen: Peekable<DeserializeRecordsIntoIter<File, EnergyGenCsvRow>>,
+/// Which days of a dataset count toward its average profiles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DayPolicy {
+    /// Leaves out the first and last days when they don't cover every
+    /// five-minute interval (see `Frame::partial_days`).
+    pub trim_partial: bool,
+    /// Leaves out February 29, so a leap year's profile weighs its days
+    /// the same as any other year's.
+    pub skip_leap_days: bool,
 }
 
+impl DayPolicy {
+    /// The rows of `frame` on the days this policy keeps.
+    pub fn apply(self, frame: Frame) -> Frame {
+        let mut dropped: Vec<NaiveDate> = Vec::new();
+        if self.trim_partial {
+            dropped.extend(frame.partial_days().into_iter().map(|(date, _)| date));
+        }
+        if self.skip_leap_days {
+            for time in frame.times() {
+                let date = time.date();
+                if date.month() == 2 && date.day() == 29 && !dropped.contains(&date) {
+                    dropped.push(date);
+                }
+            }
+        }
+        match dropped.is_empty() {
+            true => frame,
+            false => frame.without(&dropped),
+        }
+    }
+}
+
+/// How each slot of a profile summarizes the intervals that land in it.
+/// Spikes drag a mean far more than a median or trimmed mean.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ProfileAgg {
+    #[default]
+    Mean,
+    Median,
+    /// The mean of what's left after dropping this fraction of the
+    /// intervals from each end.
+    TrimmedMean(f64),
+}
+
+impl ProfileAgg {
+    /// Summarizes values sorted in ascending order. NaN when empty.
+    pub fn of_sorted(self, sorted: &[f64]) -> f64 {
+        match self {
+            ProfileAgg::Mean => sorted.iter().sum::<f64>() / sorted.len() as f64,
+            ProfileAgg::Median => percentile(sorted, 0.5),
+            ProfileAgg::TrimmedMean(p) => {
+                // Always keep at least the middle value.
+                let cut = ((sorted.len() as f64 * p).floor() as usize)
+                    .min(sorted.len().saturating_sub(1) / 2);
+                ProfileAgg::Mean.of_sorted(&sorted[cut..sorted.len() - cut])
+            }
+        }
+    }
+}
+
+/// What a profile does with slots that have too few samples.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum FewSamples {
+    /// Fails the profile.
+    #[default]
+    Error,
+    /// Prints a warning and averages the slot anyway.
+    Warn,
+}
+
+/// How many samples every slot of a profile needs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SampleCheck {
+    /// The fewest samples a slot may have. Without one, a slot may trail
+    /// the fullest slot by `MAX_WINDOW_MISS` cycles' worth of intervals.
+    pub min_samples: Option<usize>,
+    pub on_few: FewSamples,
+}
+
+impl SampleCheck {
+    /// Errors or warns, per `on_few`, if any slot has too few samples.
+    pub fn check(self, counts: &[usize], profile: Profile) -> error::Result<()> {
+        let Some(&most) = counts.iter().max() else {
+            return Ok(());
+        };
+        let min = self.min_samples.unwrap_or_else(|| {
+            most.saturating_sub(Compute::MAX_WINDOW_MISS * profile.intervals_per_slot())
+        });
+        let short: Vec<usize> = (0..counts.len()).filter(|idx| counts[*idx] < min).collect();
+        let Some(&first) = short.first() else {
+            return Ok(());
+        };
+        let problem = format!(
+            "{} of {} slots have fewer than {min} samples, the first being {} with {}",
+            short.len(),
+            counts.len(),
+            profile.label(first),
+            counts[first]
+        );
+        match self.on_few {
+            FewSamples::Error => bail!(TimeGap, "{problem}"),
+            FewSamples::Warn => eprintln!("Warning: {problem}"),
+        }
+        Ok(())
+    }
+}
+
+/// A price profile and what went into each of its slots.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PriceProfile {
+    pub prices: Vec<f64>,
+    /// How many intervals each slot summarizes.
+    pub samples: Vec<usize>,
+    /// What prices were held to before averaging, if they were clipped.
+    pub clipped: Option<ClipBounds>,
+}
+
+/// How often price exceeds each of a set of thresholds in each slot of a
+/// profile.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PriceExceedance {
+    /// In $/MWh, ascending.
+    pub thresholds: Vec<f64>,
+    /// The fraction of each slot's intervals priced above each threshold,
+    /// in `thresholds` order.
+    pub probabilities: Vec<Vec<f64>>,
+    /// How many intervals each slot summarizes.
+    pub samples: Vec<usize>,
+}
+
+/// The congestion and loss components of each zone's LMP in each slot of
+/// a profile, in `EnergyPriceCsvRow::ZONES` order.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PriceComponents {
+    pub congestion: Vec<[f64; 3]>,
+    pub loss: Vec<[f64; 3]>,
+    /// How many intervals each slot summarizes.
+    pub samples: Vec<usize>,
+}
+
+/// Each area's LMP in each slot of a profile, in `EnergyPriceCsvRow::ZONES`
+/// order.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AreaPrices {
+    pub prices: Vec<[f64; 3]>,
+    /// How many intervals each slot summarizes.
+    pub samples: Vec<usize>,
+}
+
+/// Which days a profile was averaged over.
+#[derive(Debug)]
+pub struct DayCoverage {
+    /// Days at the edges of the data that don't cover the whole day, with
+    /// how many intervals they do have, before the policy was applied.
+    pub partial: Vec<(NaiveDate, usize)>,
+    /// How many whole days the policy kept in each slot of the profile.
+    pub full_days: Vec<usize>,
+}
+
+/// Overall figures for a parsed dataset, for a quick look before any
+/// analysis.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DatasetStats {
+    pub kind: RawKind,
+    pub rows: usize,
+    /// The first and last timestamps.
+    pub first: String,
+    pub last: String,
+    /// Dates with any rows.
+    pub days: usize,
+    /// Days at the edges of the data that don't cover the whole day.
+    pub partial_days: usize,
+    /// Set for price data.
+    pub price: Option<PriceStats>,
+    /// Each source's energy, Total first, for generation data.
+    pub sources: Vec<SourceEnergy>,
+}
+
+/// The spread of every interval's price, in $/MWh.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PriceStats {
+    pub mean: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    /// The fraction of intervals priced below zero.
+    pub negative_share: f64,
+}
+
+/// A source's energy over a whole dataset.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SourceEnergy {
+    pub source: String,
+    pub mwh: f64,
+    /// The fraction of Total's energy.
+    pub share: f64,
+}
+
+/// How high a price must be to count as a spike.
+#[derive(Clone, Copy, Debug)]
+pub enum SpikeThreshold {
+    /// A fixed $/MWh price.
+    Absolute(f64),
+    /// Standard deviations above the dataset's mean price.
+    ZScore(f64),
+}
+
+/// How `Compute::downsample_prices` and `downsample_gen` combine the
+/// five-minute values within each longer interval.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Agg {
+    #[default]
+    Mean,
+    Sum,
+}
+
+/// An `Agg` for each column, named as in a `Frame` (Price, or a source
+/// like Solar), with a default for the rest.
+#[derive(Clone, Debug, Default)]
+pub struct Aggs {
+    pub default: Agg,
+    pub columns: Vec<(String, Agg)>,
+}
+
+impl Aggs {
+    /// The `Agg` of each of `names`, erroring on a column that isn't one of
+    /// them.
+    fn resolve<const N: usize>(&self, names: [&str; N]) -> error::Result<[Agg; N]> {
+        if let Some((unknown, _)) = self
+            .columns
+            .iter()
+            .find(|(col, _)| !names.iter().any(|name| name.eq_ignore_ascii_case(col)))
+        {
+            bail!(Invalid, "No column {unknown:?}, expected one of {names:?}");
+        }
+        Ok(names.map(|name| {
+            self.columns
+                .iter()
+                .rfind(|(col, _)| col.eq_ignore_ascii_case(name))
+                .map_or(self.default, |(_, agg)| *agg)
+        }))
+    }
+}
+
+/// One longer interval of downsampled rows.
+struct Downsampled<'a, T: Clone, const N: usize> {
+    start: NaiveDateTime,
+    /// The interval's last row, which ends when it does.
+    last: Cow<'a, T>,
+    values: [f64; N],
+}
+
+/// Combines consecutive rows into `minutes`-minute intervals of the local
+/// day by their `time`, applying each column's `Agg` to its `values`.
+fn downsample<T: Clone, const N: usize>(
+    rows: Rows<'_, T>,
+    minutes: usize,
+    aggs: [Agg; N],
+    time: impl Fn(&T) -> error::Result<NaiveDateTime>,
+    values: impl Fn(&T) -> [f64; N],
+) -> error::Result<Vec<Downsampled<'_, T, N>>> {
+    let per_interval = Compute::intervals_per_window(minutes)?;
+    let mut intervals: Vec<(Downsampled<T, N>, usize)> = Vec::new();
+    for row in rows {
+        let row = row?;
+        let time = time(&row)?;
+        let slot = Compute::time_to_idx_5min(time.hour(), time.minute()) / per_interval;
+        let start =
+            time.date().and_time(NaiveTime::MIN) + TimeDelta::minutes((slot * minutes) as i64);
+        let row_values = values(&row);
+        match intervals.last_mut() {
+            Some((interval, count)) if interval.start == start => {
+                for (total, value) in interval.values.iter_mut().zip(row_values) {
+                    *total += value;
+                }
+                interval.last = row;
+                *count += 1;
+            }
+            _ => intervals.push((
+                Downsampled {
+                    start,
+                    last: row,
+                    values: row_values,
+                },
+                1,
+            )),
+        }
+    }
+    Ok(intervals
+        .into_iter()
+        .map(|(mut interval, count)| {
+            for (value, agg) in interval.values.iter_mut().zip(aggs) {
+                if agg == Agg::Mean {
+                    *value /= count as f64;
+                }
+            }
+            interval
+        })
+        .collect())
+}
+
+/// A run of consecutive five-minute intervals priced above the spike threshold.
+#[derive(Serialize, Debug)]
+pub struct PriceSpike {
+    pub start: String,
+    pub end: String,
+    pub duration_mins: i64,
+    pub peak_time: String,
+    pub peak_price: f64,
+}
+
+/// The raw five-minute prices and generation of a single calendar day,
+/// keyed by their index in the day's five-minute windows.
+pub struct DayProfile {
+    pub date: NaiveDate,
+    pub prices: Vec<(usize, f64)>,
+    pub gen: Vec<(usize, [f64; 14])>,
+}
+
+/// A day studied against a baseline: the average of the days around it.
+pub struct EventDay {
+    pub day: DayProfile,
+    /// The average price in each five-minute window of the baseline days.
+    pub baseline_prices: Vec<f64>,
+    /// The average output of each source in each window of the baseline days.
+    pub baseline_gen: Vec<[f64; 14]>,
+    /// How many days the baseline averages.
+    pub baseline_days: usize,
+}
+
+/// How far one series of an event day strayed from its baseline.
+#[derive(Serialize, Debug)]
+pub struct EventDeviation {
+    pub date: NaiveDate,
+    /// Price, or a source.
+    pub series: String,
+    /// The day's average price in $/MWh, or a source's energy in MWh.
+    pub actual: f64,
+    pub baseline: f64,
+    pub change: f64,
+    /// The widest gap between the day and its baseline in any one
+    /// interval, in $/MWh or MW, and when it was.
+    pub largest_gap: f64,
+    pub largest_gap_at: String,
+}
+
+impl EventDay {
+    /// The day's price, then each source's output, compared with the
+    /// baseline over the intervals the day has.
+    pub fn deviations(&self) -> Vec<EventDeviation> {
+        let hours = Compute::MINS_INCR as f64 / 60.;
+        let mut deviations = Vec::new();
+        let mut push = |series: &str, pairs: Vec<(usize, f64, f64)>, per_interval: f64| {
+            let Some(&(widest, day, base)) = pairs
+                .iter()
+                .max_by(|a, b| (a.1 - a.2).abs().total_cmp(&(b.1 - b.2).abs()))
+            else {
+                return;
+            };
+            let (actual, baseline) =
+                pairs
+                    .iter()
+                    .fold((0., 0.), |(actual, baseline), (_, a, b)| {
+                        (actual + a * per_interval, baseline + b * per_interval)
+                    });
+            deviations.push(EventDeviation {
+                date: self.day.date,
+                series: series.to_string(),
+                actual,
+                baseline,
+                change: actual - baseline,
+                largest_gap: day - base,
+                largest_gap_at: Profile::Day.label(widest),
+            });
+        };
+        let prices: Vec<_> = self
+            .day
+            .prices
+            .iter()
+            .map(|&(idx, price)| (idx, price, self.baseline_prices[idx]))
+            .collect();
+        let per_price = 1. / prices.len().max(1) as f64;
+        push("Price", prices, per_price);
+        for (src_idx, (source, _)) in EnergyGenCsvRow::source_keys().enumerate() {
+            let gen = self
+                .day
+                .gen
+                .iter()
+                .map(|(idx, sources)| (*idx, sources[src_idx], self.baseline_gen[*idx][src_idx]))
+                .collect();
+            push(source, gen, hours);
+        }
+        deviations
+    }
+}
+
+/// How far apart a day's cheapest and most expensive intervals were priced,
+/// a rough measure of what storage could have earned that day.
+#[derive(Serialize, Debug)]
+pub struct DailySpread {
+    pub date: NaiveDate,
+    /// Average price of the day's N cheapest intervals.
+    pub cheap_avg: f64,
+    /// Average price of the day's N most expensive intervals.
+    pub expensive_avg: f64,
+    pub spread: f64,
+}
+
+/// When in the day price and net load peaked, in minutes after midnight.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct DailyPeak {
+    pub date: NaiveDate,
+    pub price_peak: f64,
+    pub net_load_peak: f64,
+}
+
+/// The median peak times of one month's days, in minutes after midnight.
+#[derive(Serialize, Debug)]
+pub struct MonthPeak {
+    /// The month, as `2024-01`.
+    pub month: String,
+    pub days: usize,
+    pub price_peak: f64,
+    pub net_load_peak: f64,
+}
+
+/// A lasting shift in when a series peaks, from the first day peaking at
+/// the new time on average.
+#[derive(Serialize, Debug)]
+pub struct PeakShift {
+    /// "price" or "net_load".
+    pub series: &'static str,
+    pub date: NaiveDate,
+    /// Mean peak times either side of the shift, in minutes after midnight.
+    pub before: f64,
+    pub after: f64,
+    pub p_value: f64,
+}
+
+/// How the daily price and net load peaks moved across a dataset.
+#[derive(Debug)]
+pub struct PeakTiming {
+    pub days: Vec<DailyPeak>,
+    pub months: Vec<MonthPeak>,
+    pub shifts: Vec<PeakShift>,
+}
+
+impl PeakTiming {
+    /// Significance a shift needs to be reported. Strict, since every
+    /// candidate split of the series is tried.
+    pub const ALPHA: f64 = 0.01;
+
+    /// A peak time, in minutes after midnight, as `HH:MM`.
+    pub fn clock(mins: f64) -> String {
+        let mins = mins.round() as u32;
+        format!("{:02}:{:02}", mins / 60, mins % 60)
+    }
+}
+
+/// The average price over the intervals in one band of implied reserve
+/// margin.
+#[derive(Serialize, Debug)]
+pub struct MarginBin {
+    /// The band's lower edge, as a fraction of demand.
+    pub margin: f64,
+    pub intervals: usize,
+    pub avg_price: f64,
+    pub avg_demand_mw: f64,
+}
+
+/// How price tracked the implied reserve margin across a dataset.
+#[derive(Debug)]
+pub struct ReserveMargin {
+    pub bins: Vec<MarginBin>,
+    /// How the price and generation intervals met each other.
+    pub price_gen: JoinStats,
+    /// How the joined price and generation intervals met demand.
+    pub join: JoinStats,
+}
+
+/// A day that stands out from the rest of the dataset.
+#[derive(Debug)]
+pub struct ExtremeDay {
+    pub label: &'static str,
+    pub date: NaiveDate,
+    pub value: f64,
+}
+
+/// Pearson correlation of each generation source with price, across the whole
+/// dataset and within each hour of the day. Undefined correlations are NaN.
+pub struct SourcePriceCorrelation {
+    pub overall: [f64; 14],
+    pub hourly: [[f64; 14]; 24],
+}
+
+/// A calendar grouping used to break analyses down over the dataset.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum Period {
+    Week,
+    Month,
+    Quarter,
+}
+
+impl Period {
+    pub fn label(&self, date: NaiveDate) -> String {
+        match self {
+            Period::Week => date.format("%G-W%V").to_string(),
+            Period::Month => date.format("%Y-%m").to_string(),
+            Period::Quarter => format!("{}Q{}", date.year(), date.month0() / 3 + 1),
+        }
+    }
+
+    pub fn noun(&self) -> &'static str {
+        match self {
+            Period::Week => "week",
+            Period::Month => "month",
+            Period::Quarter => "quarter",
+        }
+    }
+}
+
+/// Which LMP price analyses read as each interval's price.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum PriceNode {
+    /// The average of the three areas, which every parsed csv has.
+    #[default]
+    Average,
+    Np15,
+    Sp15,
+    Zp26,
+}
+
+impl PriceNode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PriceNode::Average => "average",
+            PriceNode::Np15 => EnergyPriceCsvRow::ZONES[0],
+            PriceNode::Sp15 => EnergyPriceCsvRow::ZONES[1],
+            PriceNode::Zp26 => EnergyPriceCsvRow::ZONES[2],
+        }
+    }
+
+    /// `row` with its `lmp_avg` replaced by this node's LMP.
+    fn select(self, mut row: EnergyPriceCsvRow, path: &Path) -> error::Result<EnergyPriceCsvRow> {
+        if self == PriceNode::Average {
+            return Ok(row);
+        }
+        let lmp = row.lmp(self);
+        if lmp.is_nan() {
+            bail!(
+                NoData,
+                "{path:?} has no {} LMP at {}. Parse it again to keep each area's price",
+                self.name(),
+                row.timestamp
+            );
+        }
+        row.lmp_avg = lmp;
+        Ok(row)
+    }
+}
+
+/// The spread of one source's output within one slot of a profile.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Band {
+    pub p10: f64,
+    pub p50: f64,
+    pub p90: f64,
+}
+
+/// The repeating cycle an average profile is bucketed over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Profile {
+    /// Each five-minute window of the day.
+    Day,
+    /// Each hour of the week, starting Monday at midnight.
+    Week,
+}
+
+impl Profile {
+    pub fn slots(self) -> usize {
+        match self {
+            Profile::Day => Compute::slots_per_day(),
+            Profile::Week => 7 * 24,
+        }
+    }
+
+    /// How many five-minute intervals of one cycle land in each slot.
+    fn intervals_per_slot(self) -> usize {
+        match self {
+            Profile::Day => 1,
+            Profile::Week => 60 / Compute::MINS_INCR,
+        }
+    }
+
+    /// Returns the slot of an interval. `timestamp` only needs to start with
+    /// its `%Y-%m-%d` date, which is only read for weekly profiles.
+    pub fn slot(self, timestamp: &str, hour: u32, minute: u32) -> error::Result<usize> {
+        match self {
+            Profile::Day => Ok(Compute::time_to_idx_5min(hour, minute)),
+            Profile::Week => {
+                let date = timestamp
+                    .get(..10)
+                    .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+                    .ok_or_else(|| err!(Invalid, "No date at the start of {timestamp:?}"))?;
+                Ok(date.weekday().num_days_from_monday() as usize * 24 + hour as usize)
+            }
+        }
+    }
+
+    /// Like `slot`, for an already parsed time.
+    pub(crate) fn slot_of(self, time: NaiveDateTime) -> usize {
+        match self {
+            Profile::Day => Compute::time_to_idx_5min(time.hour(), time.minute()),
+            Profile::Week => {
+                time.weekday().num_days_from_monday() as usize * 24 + time.hour() as usize
+            }
+        }
+    }
+
+    pub fn label(self, idx: usize) -> String {
+        match self {
+            Profile::Day => {
+                let (hour, minute) = Compute::idx_5min_to_time(idx);
+                format!("{hour:02}:{minute:02}")
+            }
+            Profile::Week => {
+                const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+                format!("{} {:02}:00", DAYS[idx / 24 % 7], idx % 24)
+            }
+        }
+    }
+}
+
+/// Headline numbers for one group (such as a month) of joined price and generation data.
+pub struct GroupSummary {
+    pub label: String,
+    pub avg_price: f64,
+    /// Each source's average output as a fraction of average total generation.
+    pub shares: [f64; 14],
+    /// Average $/MWh realized by each source.
+    pub values: [f64; 14],
+}
+
+/// Folds the values that land in one bucket of a grouping into a result.
+pub trait Reducer: Default {
+    type Item;
+    type Output;
+
+    fn push(&mut self, item: Self::Item);
+    /// Folds in a reducer that saw a different partition of the same bucket.
+    fn merge(&mut self, other: Self);
+    fn finish(self) -> Self::Output;
+}
+
+/// Collects a bucket's items as-is, e.g. to partition rows by day.
+impl<T> Reducer for Vec<T> {
+    type Item = T;
+    type Output = Vec<T>;
+
+    fn push(&mut self, item: T) {
+        Vec::push(self, item);
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.extend(other);
+    }
+
+    fn finish(self) -> Vec<T> {
+        self
+    }
+}
+
+/// Values that can be summed and then divided by a count.
+pub trait Averageable: Copy + Default {
+    fn add(&mut self, other: &Self);
+    fn div(self, count: f64) -> Self;
+}
+
+impl Averageable for f64 {
+    fn add(&mut self, other: &Self) {
+        *self += other;
+    }
+    fn div(self, count: f64) -> Self {
+        self / count
+    }
+}
+
+impl<const N: usize> Averageable for [f64; N]
+where
+    [f64; N]: Default,
+{
+    fn add(&mut self, other: &Self) {
+        for (total, val) in self.iter_mut().zip(other) {
+            *total += val;
+        }
+    }
+    fn div(self, count: f64) -> Self {
+        self.map(|total| total / count)
+    }
+}
+
+impl<A: Averageable, B: Averageable> Averageable for (A, B) {
+    fn add(&mut self, other: &Self) {
+        self.0.add(&other.0);
+        self.1.add(&other.1);
+    }
+    fn div(self, count: f64) -> Self {
+        (self.0.div(count), self.1.div(count))
+    }
+}
+
+/// Reduces each half of a pair with its own reducer, e.g. to summarize a
+/// bucket and average something else about it in one pass.
+impl<A: Reducer, B: Reducer> Reducer for (A, B) {
+    type Item = (A::Item, B::Item);
+    type Output = (A::Output, B::Output);
+
+    fn push(&mut self, (a, b): Self::Item) {
+        self.0.push(a);
+        self.1.push(b);
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.0.merge(other.0);
+        self.1.merge(other.1);
+    }
+
+    fn finish(self) -> Self::Output {
+        (self.0.finish(), self.1.finish())
+    }
+}
+
+/// The arithmetic mean of everything pushed. An empty bucket averages to NaN.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Mean<V> {
+    total: V,
+    count: usize,
+}
+
+impl<V> Mean<V> {
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl<V: Averageable> Reducer for Mean<V> {
+    type Item = V;
+    type Output = V;
+
+    fn push(&mut self, item: V) {
+        self.total.add(&item);
+        self.count += 1;
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.total.add(&other.total);
+        self.count += other.count;
+    }
+
+    fn finish(self) -> V {
+        self.total.div(self.count as f64)
+    }
+}
+
+/// Accumulates the price, generation mix, and value by source of joined rows.
+#[derive(Default, Clone)]
+pub struct SummaryReducer {
+    price_total: f64,
+    count: usize,
+    gen: [f64; 14],
+    value: [f64; 14],
+    qtys: [f64; 14],
+}
+
+impl Reducer for SummaryReducer {
+    type Item = (f64, [f64; 14]);
+    type Output = (f64, [f64; 14], [f64; 14]);
+
+    fn push(&mut self, (price, sources): Self::Item) {
+        self.price_total += price;
+        self.count += 1;
+        for (idx, qty) in sources.iter().copied().enumerate() {
+            self.gen[idx] += qty;
+            self.qtys[idx] += qty.abs();
+            self.value[idx] += qty * price;
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.price_total += other.price_total;
+        self.count += other.count;
+        for idx in 0..self.gen.len() {
+            self.gen[idx] += other.gen[idx];
+            self.value[idx] += other.value[idx];
+            self.qtys[idx] += other.qtys[idx];
+        }
+    }
+
+    /// Returns (average price, share of total by source, value by source).
+    fn finish(self) -> Self::Output {
+        let total = self.gen[0];
+        (
+            self.price_total / self.count as f64,
+            self.gen
+                .map(|qty| if total != 0. { qty / total } else { 0. }),
+            array::from_fn(|idx| {
+                if self.qtys[idx] != 0. {
+                    self.value[idx] / self.qtys[idx]
+                } else {
+                    0.
+                }
+            }),
+        )
+    }
+}
+
+/// Accumulates the correlation of each source's output with price.
+#[derive(Default, Clone, Copy)]
+pub struct CorrelationReducer([Correlation; 14]);
+
+impl Reducer for CorrelationReducer {
+    /// (generation by source, price)
+    type Item = ([f64; 14], f64);
+    type Output = [f64; 14];
+
+    fn push(&mut self, (sources, price): Self::Item) {
+        for (corr, qty) in self.0.iter_mut().zip(sources) {
+            corr.push(qty, price);
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        for (corr, other) in self.0.iter_mut().zip(&other.0) {
+            corr.merge(other);
+        }
+    }
+
+    fn finish(self) -> [f64; 14] {
+        self.0.map(|corr| corr.pearson())
+    }
+}
+
+/// A generation row whose Total doesn't add up.
+#[derive(Serialize, Debug)]
+pub struct TotalMismatch {
+    pub timestamp: String,
+    pub total: f64,
+    pub sum_of_sources: f64,
+    pub difference: f64,
+}
+
+/// The hours of highest net load (total generation less wind and solar),
+/// when the rest of the fleet, mostly gas, has to ramp up to cover demand.
+pub struct NetLoadPeaks {
+    /// Each peak hour, highest net load first, with its net load in MW.
+    pub hours: Vec<(GroupSummary, f64)>,
+    /// All of the peak hours together.
+    pub peak: (GroupSummary, f64),
+    /// Every other hour.
+    pub rest: (GroupSummary, f64),
+}
+
+/// Net load (total generation less wind and solar) and price in one slot
+/// of the day, historically and under a scenario.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ScenarioSlot {
+    pub net_load: f64,
+    pub scenario_net_load: f64,
+    pub price: f64,
+    pub scenario_price: f64,
+}
+
+/// Pairs of values whose correlation and joint spread are wanted, like two
+/// sources' output in one slot of the day.
+impl Reducer for Correlation {
+    type Item = (f64, f64);
+    type Output = Correlation;
+
+    fn push(&mut self, (x, y): (f64, f64)) {
+        Correlation::push(self, x, y);
+    }
+
+    fn merge(&mut self, other: Self) {
+        Correlation::merge(self, &other);
+    }
+
+    fn finish(self) -> Correlation {
+        self
+    }
+}
+
+/// How wind and solar output vary, alone and together, within one
+/// five-minute window of the day. A coefficient of variation is the
+/// standard deviation across days over the mean, NaN when the mean is
+/// under 1 MW, as solar's is overnight.
+#[derive(Serialize, Debug)]
+pub struct Complementarity {
+    pub time: String,
+    pub wind_mw: f64,
+    pub solar_mw: f64,
+    pub combined_mw: f64,
+    pub wind_cv: f64,
+    pub solar_cv: f64,
+    pub combined_cv: f64,
+    pub correlation: f64,
+}
+
+/// Energy moving in one direction and the prices it moved at.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Flow {
+    pub mwh: f64,
+    pub intervals: usize,
+    dollars: f64,
+}
+
+impl Flow {
+    fn push(&mut self, mwh: f64, price: f64) {
+        self.mwh += mwh;
+        self.intervals += 1;
+        self.dollars += mwh * price;
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        self.mwh += other.mwh;
+        self.intervals += other.intervals;
+        self.dollars += other.dollars;
+    }
+
+    /// Energy-weighted average $/MWh, or NaN if nothing flowed.
+    pub fn avg_price(&self) -> f64 {
+        self.dollars / self.mwh
+    }
+}
+
+/// Energy flowing each way through a signed source within one hour of the
+/// day. CAISO reports net interchange in the Imports column (negative means
+/// exporting) and net battery output in Batteries (negative means charging).
+#[derive(Debug)]
+pub struct DirectionalHour {
+    pub hour: usize,
+    pub positive: Flow,
+    /// Energy in the negative direction, as a positive quantity.
+    pub negative: Flow,
+}
+
+/// One five-minute interval's output from a single source against its
+/// price, for scatter plots.
+#[derive(Clone, Copy, Debug)]
+pub struct PricePoint {
+    pub mw: f64,
+    pub lmp: f64,
+    pub hour: u32,
+}
+
+/// The average MW flowing into CAISO from one neighboring balancing
+/// authority in each hour of the day. Negative hours are net exports.
+#[derive(Debug)]
+pub struct BaInterchange {
+    pub ba: String,
+    pub hours: [f64; 24],
+}
+
+impl BaInterchange {
+    /// Average flow over the whole day, used to rank neighbors.
+    fn daily_mw(&self) -> f64 {
+        self.hours.iter().sum::<f64>() / 24.
+    }
+}
+
+/// Splits a signed source's intervals by direction.
+#[derive(Default)]
+pub struct DirectionReducer {
+    positive: Flow,
+    negative: Flow,
+}
+
+impl Reducer for DirectionReducer {
+    /// (signed output in MW, price)
+    type Item = (f64, f64);
+    /// (positive, negative)
+    type Output = (Flow, Flow);
+
+    fn push(&mut self, (mw, price): Self::Item) {
+        let mwh = mw * Compute::MINS_INCR as f64 / 60.;
+        if mwh >= 0. {
+            self.positive.push(mwh, price);
+        } else {
+            self.negative.push(-mwh, price);
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.positive.merge(&other.positive);
+        self.negative.merge(&other.negative);
+    }
+
+    fn finish(self) -> Self::Output {
+        (self.positive, self.negative)
+    }
+}
+
+/// Sends each item to the bucket named by `bucket` and reduces it there.
+/// Buckets are returned in order of first appearance and don't need to be
+/// contiguous in the input.
+pub fn group_by<T, K: PartialEq, R: Reducer>(
+    items: impl IntoIterator<Item = error::Result<T>>,
+    mut bucket: impl FnMut(T) -> error::Result<(K, R::Item)>,
+) -> error::Result<Vec<(K, R)>> {
+    let mut groups: Vec<(K, R)> = Vec::new();
+    for item in items {
+        let (key, val) = bucket(item?)?;
+        // Most groupings are contiguous, so search from the most recent bucket.
+        match groups.iter_mut().rev().find(|(k, _)| *k == key) {
+            Some((_, reducer)) => reducer.push(val),
+            None => {
+                let mut reducer = R::default();
+                reducer.push(val);
+                groups.push((key, reducer));
+            }
+        }
+    }
+    Ok(groups)
+}
+
+/// Like `group_by`, but for a fixed number of buckets addressed by index,
+/// such as the five-minute windows of a day.
+pub fn group_by_index<T, R: Reducer>(
+    items: impl IntoIterator<Item = error::Result<T>>,
+    num_buckets: usize,
+    mut bucket: impl FnMut(T) -> error::Result<(usize, R::Item)>,
+) -> error::Result<Vec<R>> {
+    let mut groups: Vec<R> = (0..num_buckets).map(|_| R::default()).collect();
+    for item in items {
+        let (idx, val) = bucket(item?)?;
+        let Some(reducer) = groups.get_mut(idx) else {
+            bail!(
+                Invalid,
+                "Bucket {idx} is out of range for {num_buckets} buckets"
+            );
+        };
+        reducer.push(val);
+    }
+    Ok(groups)
+}
+
+/// Rows read from a parsed csv or a binary store, or borrowed from a
+/// `Dataset` held in memory.
+type Rows<'a, T> = Box<dyn Iterator<Item = error::Result<Cow<'a, T>>> + 'a>;
+
+/// Like `group_by_index`, but reduces each partition (typically a day of
+/// rows, see `partition_by_day`) on the rayon pool and merges the partial
+/// buckets. Reducers must give the same result however items are split.
+/// Without the `native` feature the partitions are reduced in turn.
+pub fn par_group_by_index<T: Send, R: Reducer + Send>(
+    partitions: Vec<Vec<T>>,
+    num_buckets: usize,
+    bucket: impl Fn(T) -> error::Result<(usize, R::Item)> + Sync,
+) -> error::Result<Vec<R>> {
+    let empty = || (0..num_buckets).map(|_| R::default()).collect();
+    let merge = |mut acc: Vec<R>, partial: Vec<R>| {
+        for (reducer, other) in acc.iter_mut().zip(partial) {
+            reducer.merge(other);
+        }
+        Ok(acc)
+    };
+    let partial =
+        |partition: Vec<T>| group_by_index(partition.into_iter().map(Ok), num_buckets, &bucket);
+    #[cfg(feature = "native")]
+    let merged = partitions
+        .into_par_iter()
+        .map(partial)
+        .try_reduce(empty, merge);
+    #[cfg(not(feature = "native"))]
+    let merged = partitions
+        .into_iter()
+        .map(partial)
+        .try_fold(empty(), |acc, partial| merge(acc, partial?));
+    merged
+}
+
+/// Splits time-ordered `(date, item)` pairs into one partition per calendar
+/// day, for handing to `par_group_by_index` or a parallel map. Keep items
+/// small: every partition is held in memory at once.
+pub fn partition_by_day<T>(
+    rows: impl IntoIterator<Item = error::Result<(NaiveDate, T)>>,
+) -> error::Result<Vec<(NaiveDate, Vec<T>)>> {
+    group_by(rows, Ok)
+}
+
+/// Trailing `days`-day average of a daily series at each of its dates.
+/// Windows are measured in calendar days, so missing days shrink the window
+/// rather than stretching it, and the first few dates average what's there.
+pub fn rolling_mean<V: Averageable>(series: &[(NaiveDate, V)], days: usize) -> Vec<(NaiveDate, V)> {
+    let days = days.max(1) as i64;
+    let mut start = 0;
+    series
+        .iter()
+        .enumerate()
+        .map(|(end, &(date, _))| {
+            while (date - series[start].0).num_days() >= days {
+                start += 1;
+            }
+            let mut mean = Mean::default();
+            for &(_, val) in &series[start..=end] {
+                mean.push(val);
+            }
+            (date, mean.finish())
+        })
+        .collect()
+}
+
+/// How many values landed in one bin of a histogram, which covers
+/// `low..low + width`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bin {
+    pub low: f64,
+    pub count: usize,
+}
+
+/// Counts `values` into bins `width` wide, aligned to multiples of `width`
+/// so zero always falls on an edge. Every bin from the lowest value to the
+/// highest is included, even when empty. NaN values are skipped.
+pub fn histogram(values: impl IntoIterator<Item = f64>, width: f64) -> error::Result<Vec<Bin>> {
+    const MAX_BINS: usize = 10_000;
+    if !(width > 0. && width.is_finite()) {
+        bail!(Invalid, "Bin width must be positive, got {width}");
+    }
+    let idxs: Vec<i64> = values
+        .into_iter()
+        .filter(|val| !val.is_nan())
+        .map(|val| (val / width).floor() as i64)
+        .collect();
+    let (Some(&first), Some(&last)) = (idxs.iter().min(), idxs.iter().max()) else {
+        bail!(NoData, "No values to bin");
+    };
+    let len = (last - first) as usize + 1;
+    if len > MAX_BINS {
+        bail!(
+            Invalid,
+            "A bin width of {width} makes {len} bins, more than {MAX_BINS}"
+        );
+    }
+    let mut bins: Vec<Bin> = (first..=last)
+        .map(|idx| Bin {
+            low: idx as f64 * width,
+            count: 0,
+        })
+        .collect();
+    for idx in idxs {
+        bins[(idx - first) as usize].count += 1;
+    }
+    Ok(bins)
+}
+
+/// How far `other` moved from `base` in each slot of a profile. Both must
+/// be profiles of the same cycle.
+pub fn profile_delta(base: &[f64], other: &[f64]) -> error::Result<Vec<f64>> {
+    if base.len() != other.len() {
+        bail!(
+            Invalid,
+            "Can't compare a profile of {} slots with one of {}",
+            base.len(),
+            other.len()
+        );
+    }
+    Ok(other
+        .iter()
+        .zip(base)
+        .map(|(other, base)| other - base)
+        .collect())
+}
+
+/// How far each source's average value moved from `base` to `other`, both
+/// values and quantities as the `average_value_*` functions return them. A
+/// source without output in either scenario has no value to compare and is
+/// NaN.
+pub fn value_delta(
+    (base, base_qtys): &([f64; 14], [f64; 14]),
+    (other, other_qtys): &([f64; 14], [f64; 14]),
+) -> [f64; 14] {
+    std::array::from_fn(|idx| match base_qtys[idx] == 0. || other_qtys[idx] == 0. {
+        true => f64::NAN,
+        false => other[idx] - base[idx],
+    })
+}
+
+/// The last `count` rows of a csv with a header, read backwards from the
+/// end of the file instead of scanning it from the start. A bad row fails
+/// with a `Parse` error like `read_rows`.
+fn tail_csv<T: CsvRow>(path: &Path, count: usize) -> error::Result<Vec<T>> {
+    const CHUNK: u64 = 1 << 16;
+    let mut file = File::open(path)?;
+    let mut header = String::new();
+    BufReader::new(&file).read_line(&mut header)?;
+    let body_start = header.len() as u64;
+
+    let mut tail: Vec<u8> = Vec::new();
+    let mut newlines = 0;
+    let mut pos = file.metadata()?.len();
+    // One newline more than rows, so the first row kept is whole.
+    while pos > body_start && newlines <= count {
+        let start = pos.saturating_sub(CHUNK).max(body_start);
+        let mut chunk = vec![0; (pos - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        newlines += chunk.iter().filter(|&&byte| byte == b'\n').count();
+        chunk.extend(tail);
+        tail = chunk;
+        pos = start;
+    }
+
+    let tail = String::from_utf8(tail)?;
+    let mut lines: Vec<&str> = tail.lines().filter(|line| !line.is_empty()).collect();
+    if pos > body_start && !lines.is_empty() {
+        lines.remove(0);
+    }
+    let kept = lines.len().min(count) as u64;
+    let rows = lines[lines.len().saturating_sub(count)..].join("\n");
+    let text = format!("{header}{rows}");
+    let rows = read_rows_from(path, text.as_bytes())?
+        .map(|row| {
+            row.map_err(|e| match e {
+                // Lines are numbered within the tail, so the file is read
+                // whole to place the bad one, which only failing reads pay.
+                EnergyAnalysisError::Parse {
+                    file,
+                    line,
+                    field,
+                    value,
+                    message,
+                } => EnergyAnalysisError::Parse {
+                    line: (line_count(&file).unwrap_or(0) + line).saturating_sub(kept + 1),
+                    file,
+                    field,
+                    value,
+                    message,
+                },
+                e => e,
+            })
+        })
+        .collect();
+    rows
+}
+
+/// How many lines a file has, counting a last one without a newline.
+fn line_count(path: &Path) -> error::Result<u64> {
+    let bytes = std::fs::read(path)?;
+    let newlines = bytes.iter().filter(|&&byte| byte == b'\n').count() as u64;
+    Ok(newlines + u64::from(bytes.last().is_some_and(|&byte| byte != b'\n')))
+}
+
+/// The rows of every month of a manifest, in order.
+fn manifest_rows<'a, T: CsvRow + Clone + 'static>(manifest: &Manifest) -> Rows<'a, T> {
+    let files: Vec<PathBuf> = manifest.files().map(Path::to_path_buf).collect();
+    Box::new(files.into_iter().flat_map(|file| -> Rows<T> {
+        match read_rows(&file) {
+            Ok(rows) => owned(rows),
+            Err(e) => Box::new(iter::once(Err(e))),
+        }
+    }))
+}
+
+/// Rows read from disk, owned by the iterator that reads them.
+fn owned<'a, T: Clone + 'static>(
+    rows: impl Iterator<Item = error::Result<T>> + 'static,
+) -> Rows<'a, T> {
+    Box::new(rows.map(|row| row.map(Cow::Owned)))
+}
+
+/// The rows a `Dataset` holds, if they're of the kind asked for.
+fn held_rows<'a, T>(rows: Option<&'a [T]>, dataset: &Dataset) -> error::Result<&'a [T]> {
+    rows.ok_or_else(|| {
+        err!(
+            Invalid,
+            "{:?} holds {:?} rows",
+            dataset.path(),
+            dataset.kind()
+        )
+    })
+}
+
+/// Rows read in place from a `Dataset`.
+fn borrowed<'a, T: Clone>(rows: Option<&'a [T]>, dataset: &Dataset) -> error::Result<Rows<'a, T>> {
+    Ok(Box::new(
+        held_rows(rows, dataset)?
+            .iter()
+            .map(|row| Ok(Cow::Borrowed(row))),
+    ))
+}
+
+/// The last `count` rows of a manifest, read from the end of its last
+/// months.
+fn manifest_tail<T: CsvRow>(manifest: &Manifest, count: usize) -> error::Result<Vec<T>> {
+    let mut rows = Vec::new();
+    for file in manifest.files().rev() {
+        if rows.len() >= count {
+            break;
+        }
+        let mut earlier = tail_csv(file, count - rows.len())?;
+        earlier.append(&mut rows);
+        rows = earlier;
+    }
+    Ok(rows)
+}
+
+type PriceGenJoin<'a> = TimeAlignedJoin<'a, Cow<'a, EnergyPriceCsvRow>, Cow<'a, EnergyGenCsvRow>>;
+type PriceGenIter<'a> = join::Results<'a, Cow<'a, EnergyPriceCsvRow>, Cow<'a, EnergyGenCsvRow>>;
+
+/// Price and generation intervals, each with the demand of its hour.
+/// Rows of a `Dataset` are borrowed from it.
+pub type PriceGenDemandIter<'a> =
+    TimeAlignedJoin<'a, (Cow<'a, EnergyPriceCsvRow>, Cow<'a, EnergyGenCsvRow>), DemandCsvRow>;
+
 impl<'a> Compute<'a> {
-    const MINS_PER_DAY: usize = 24 * 60;
-    const MINS_INCR: usize = 5;
+    pub const MINS_PER_DAY: usize = 24 * 60;
+    pub const MINS_INCR: usize = 5;
+
+    // I assume every timeslot has an equal number of data points. Unless
+    // told otherwise, allow up to this many missed times before warning
+    // that the data doesn't look how I think it does.
+    const MAX_WINDOW_MISS: usize = 12;
+
+    pub fn new(path: &'a Path) -> Self {
+        Self {
+            path,
+            days: DayPolicy::default(),
+            samples: SampleCheck::default(),
+            agg: ProfileAgg::default(),
+            clip: None,
+            node: PriceNode::default(),
+            datasets: Vec::new(),
+        }
+    }
+
+    /// Computes over `dataset`, reading its rows in place.
+    pub fn from_dataset(dataset: &'a Dataset) -> Self {
+        Self::new(dataset.path()).with_dataset(dataset)
+    }
+
+    /// Reads `dataset`'s rows in place wherever its path is read, such as
+    /// the generation side of a joined analysis. Any other path is read
+    /// from disk as before.
+    pub fn with_dataset(mut self, dataset: &'a Dataset) -> Self {
+        self.datasets.push(dataset);
+        self
+    }
+
+    /// Averages profiles over only the days `days` keeps.
+    pub fn with_days(mut self, days: DayPolicy) -> Self {
+        self.days = days;
+        self
+    }
+
+    /// Holds each slot of a profile to `samples` instead of the default
+    /// check.
+    pub fn with_samples(mut self, samples: SampleCheck) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Summarizes each slot of a profile with `agg` rather than its mean.
+    pub fn with_agg(mut self, agg: ProfileAgg) -> Self {
+        self.agg = agg;
+        self
+    }
+
+    /// Winsorizes prices by `clip` before profiling them.
+    pub fn with_clip(mut self, clip: Option<Clip>) -> Self {
+        self.clip = clip;
+        self
+    }
+
+    /// Reads `node`'s LMP as each interval's price instead of the average
+    /// of the three areas.
+    pub fn with_price_node(mut self, node: PriceNode) -> Self {
+        self.node = node;
+        self
+    }
+
+    /// These same options over another dataset.
+    fn with_path<'b>(&self, path: &'b Path) -> Compute<'b>
+    where
+        'a: 'b,
+    {
+        Compute {
+            path,
+            days: self.days,
+            samples: self.samples,
+            agg: self.agg,
+            clip: self.clip,
+            node: self.node,
+            datasets: self.datasets.clone(),
+        }
+    }
+
+    /// Default options over another dataset, still reading the datasets
+    /// this one was handed in place.
+    fn over<'b>(&self, path: &'b Path) -> Compute<'b>
+    where
+        'a: 'b,
+    {
+        Compute {
+            datasets: self.datasets.clone(),
+            ..Compute::new(path)
+        }
+    }
+
+    /// The dataset handed over for this path, if there is one.
+    fn held(&self) -> Option<&'a Dataset> {
+        self.datasets
+            .iter()
+            .copied()
+            .find(|dataset| dataset.path() == self.path)
+    }
+
+    /// The node whose LMP is read as the price.
+    pub fn price_node(&self) -> PriceNode {
+        self.node
+    }
+
+    /// The dataset computed over.
+    pub fn path(&self) -> &'a Path {
+        self.path
+    }
+
+    /// Returns the index in a 24-hour block of five-minute windows that this time should fill.
+    pub fn time_to_idx_5min(hour: u32, minute: u32) -> usize {
+        ((hour * 60) + minute) as usize / Self::MINS_INCR
+    }
+
+    pub fn idx_5min_to_time(idx: usize) -> (u32, u32) {
+        ((idx as u32 * 5) / 60, (idx as u32 * 5) % 60)
+    }
+
+    pub fn average_gen_5min(&self) -> error::Result<Vec<[f64; 14]>> {
+        self.average_gen_profile(Profile::Day)
+    }
+
+    pub fn average_gen_profile(&self, profile: Profile) -> error::Result<Vec<[f64; 14]>> {
+        Ok(self.sampled_gen_profile(profile)?.0)
+    }
+
+    pub fn average_gen_solar_battery(&self, profile: Profile) -> error::Result<Vec<[f64; 14]>> {
+        Ok(self.sampled_gen_solar_battery(profile)?.0)
+    }
+
+    /// `average_gen_profile` along with how many intervals each slot averages.
+    pub fn sampled_gen_profile(
+        &self,
+        profile: Profile,
+    ) -> error::Result<(Vec<[f64; 14]>, Vec<usize>)> {
+        self.average_gen_custom(profile, |_| ())
+    }
+
+    /// `average_gen_solar_battery` along with how many intervals each slot
+    /// averages.
+    pub fn sampled_gen_solar_battery(
+        &self,
+        profile: Profile,
+    ) -> error::Result<(Vec<[f64; 14]>, Vec<usize>)> {
+        let battery_idx = Self::battery_idx();
+        let solar_idx = Self::solar_idx();
+
+        self.average_gen_custom(profile, |row| {
+            row[solar_idx] += row[battery_idx];
+            row[battery_idx] = 0.;
+        })
+    }
+
+    /// `gen_mod` is applied to each slot's averages, so it should be linear
+    /// (like moving one source's output into another).
+    fn average_gen_custom(
+        &self,
+        profile: Profile,
+        gen_mod: impl Fn(&mut [f64; 14]),
+    ) -> error::Result<(Vec<[f64; 14]>, Vec<usize>)> {
+        let (slots, samples) =
+            self.days
+                .apply(self.gen_frame()?)
+                .profile(profile, self.samples, self.agg)?;
+        let gen = slots
+            .into_iter()
+            .map(|slot| {
+                let mut sources = Self::to_sources(slot);
+                gen_mod(&mut sources);
+                sources
+            })
+            .collect();
+        Ok((gen, samples))
+    }
+
+    /// Converts each slot of an average profile into each source's percent
+    /// of that slot's generation. Negative output, like batteries charging,
+    /// counts as zero so that the sources stack to exactly 100%. The total
+    /// column is always 100.
+    pub fn gen_shares(gen: &[[f64; 14]]) -> Vec<[f64; 14]> {
+        gen.iter()
+            .map(|slot| {
+                let total: f64 = slot.iter().skip(1).map(|qty| qty.max(0.)).sum();
+                array::from_fn(|idx| match idx {
+                    0 => 100.,
+                    _ if total > 0. => 100. * slot[idx].max(0.) / total,
+                    _ => 0.,
+                })
+            })
+            .collect()
+    }
+
+    /// Every row whose Total is off from the sum of its sources by more
+    /// than `TOTAL_TOLERANCE_MW`, in order.
+    pub fn total_mismatches(&self) -> error::Result<Vec<TotalMismatch>> {
+        let mut mismatches = Vec::new();
+        for line in self.gen_rows()? {
+            let line = line?;
+            let difference = line.total_discrepancy();
+            if difference.abs() > TOTAL_TOLERANCE_MW {
+                mismatches.push(TotalMismatch {
+                    total: line.total,
+                    sum_of_sources: line.sum_of_sources(),
+                    difference,
+                    timestamp: line.into_owned().local_timestamp_start,
+                });
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// Correlates wind and solar output across days in each five-minute
+    /// window, and compares how much each varies alone with how much their
+    /// sum does. Combined variability below either source's shows them
+    /// covering for each other.
+    pub fn wind_solar_complementarity(&self) -> error::Result<Vec<Complementarity>> {
+        let wind = EnergyGenCsvRow::source_idx("Wind")?;
+        let solar = Self::solar_idx();
+        let slots: Vec<Correlation> =
+            group_by_index(self.gen_rows()?, Profile::Day.slots(), |line| {
+                let slot = Profile::Day.slot(&line.local_date, line.hour, line.minute)?;
+                let sources = line.sources();
+                Ok((slot, (sources[wind], sources[solar])))
+            })?;
+        if slots.iter().all(|slot| slot.count() == 0) {
+            bail!(NoData, "No generation in {:?}", self.path);
+        }
+
+        let cv = |var: f64, mean: f64| {
+            if mean < 1. {
+                f64::NAN
+            } else {
+                var.sqrt() / mean
+            }
+        };
+        Ok(slots
+            .iter()
+            .enumerate()
+            .map(|(idx, corr)| {
+                let (wind_mw, solar_mw) = corr.means();
+                let (wind_var, solar_var, cov) = corr.variances();
+                let combined_mw = wind_mw + solar_mw;
+                Complementarity {
+                    time: Profile::Day.label(idx),
+                    wind_mw,
+                    solar_mw,
+                    combined_mw,
+                    wind_cv: cv(wind_var, wind_mw),
+                    solar_cv: cv(solar_var, solar_mw),
+                    combined_cv: cv(wind_var + solar_var + 2. * cov, combined_mw),
+                    correlation: corr.pearson(),
+                }
+            })
+            .collect())
+    }
+
+    /// The 10th, 50th and 90th percentile of each source's output in each
+    /// slot of the profile, showing how much a source varies around its
+    /// average at that time.
+    pub fn gen_percentile_bands(&self, profile: Profile) -> error::Result<Vec<[Band; 14]>> {
+        let slots: Vec<Vec<[f64; 14]>> =
+            group_by_index(self.gen_rows()?, profile.slots(), |line| {
+                let slot = profile.slot(&line.local_date, line.hour, line.minute)?;
+                Ok((slot, line.sources()))
+            })?;
+        Ok(slots
+            .into_iter()
+            .map(|rows| {
+                array::from_fn(|src| {
+                    let mut vals: Vec<f64> = rows.iter().map(|row| row[src]).collect();
+                    vals.sort_unstable_by(f64::total_cmp);
+                    Band {
+                        p10: percentile(&vals, 0.1),
+                        p50: percentile(&vals, 0.5),
+                        p90: percentile(&vals, 0.9),
+                    }
+                })
+            })
+            .collect())
+    }
+
+    /// Returns the average output of each source within each period, in order.
+    pub fn average_gen_by_period(&self, period: Period) -> error::Result<Vec<(String, [f64; 14])>> {
+        let periods = group_by(self.gen_rows()?, |line| {
+            let date = NaiveDate::parse_from_str(&line.local_date, "%Y-%m-%d")?;
+            Ok((period.label(date), line.sources()))
+        })?;
+        Ok(periods
+            .into_iter()
+            .map(|(label, mean): (_, Mean<_>)| (label, mean.finish()))
+            .collect())
+    }
+
+    /// The average day of generation within each period, in order, for
+    /// watching the daily curve change over the dataset.
+    pub fn average_gen_5min_by_period(
+        &self,
+        period: Period,
+    ) -> error::Result<Vec<(String, Vec<[f64; 14]>)>> {
+        self.average_gen_5min_by(self.days.apply(self.gen_frame()?), |date| {
+            period.label(date)
+        })
+    }
+
+    /// The average day of generation within each water year, in order,
+    /// pooling this dataset with `others`, such as one parsed gen csv per
+    /// year. The datasets go in time order.
+    pub fn average_gen_5min_by_water_year(
+        &self,
+        others: &[PathBuf],
+        years: &WaterYears,
+    ) -> error::Result<Vec<(String, Vec<[f64; 14]>)>> {
+        let mut frame = self.days.apply(self.gen_frame()?);
+        for path in others {
+            frame = frame.append(self.days.apply(self.with_path(path).gen_frame()?))?;
+        }
+        self.average_gen_5min_by(frame, |date| years.label(date))
+    }
+
+    fn average_gen_5min_by(
+        &self,
+        frame: Frame,
+        key: impl Fn(NaiveDate) -> String,
+    ) -> error::Result<Vec<(String, Vec<[f64; 14]>)>> {
+        frame
+            .split_by(key)
+            .into_iter()
+            .map(|(label, frame)| {
+                let (slots, _) = frame.profile(Profile::Day, self.samples, self.agg)?;
+                Ok((label, slots.into_iter().map(Self::to_sources).collect()))
+            })
+            .collect()
+    }
+
+    /// Capacity factor of each source in each five-minute window of the day,
+    /// labeled by time of day.
+    pub fn capacity_factors_5min(
+        &self,
+        capacity: &InstalledCapacity,
+    ) -> error::Result<Vec<(String, [f64; 14])>> {
+        Ok(self
+            .average_gen_5min()?
+            .iter()
+            .enumerate()
+            .map(|(idx, gen)| {
+                let (hour, minute) = Self::idx_5min_to_time(idx);
+                (format!("{hour:02}:{minute:02}"), capacity.normalize(gen))
+            })
+            .collect())
+    }
+
+    /// Capacity factor of each source in each month, labeled by month.
+    pub fn capacity_factors_monthly(
+        &self,
+        capacity: &InstalledCapacity,
+    ) -> error::Result<Vec<(String, [f64; 14])>> {
+        Ok(self
+            .average_gen_by_period(Period::Month)?
+            .into_iter()
+            .map(|(label, gen)| (label, capacity.normalize(&gen)))
+            .collect())
+    }
+
+    pub fn average_price_5min(&self) -> error::Result<Vec<f64>> {
+        self.average_price_profile(Profile::Day)
+    }
+
+    pub fn average_price_profile(&self, profile: Profile) -> error::Result<Vec<f64>> {
+        Ok(self.sampled_price_profile(profile)?.prices)
+    }
+
+    /// The average day of prices within each period, in order, for
+    /// watching the daily curve change over the dataset.
+    pub fn average_price_5min_by_period(
+        &self,
+        period: Period,
+    ) -> error::Result<Vec<(String, Vec<f64>)>> {
+        let mut frame = self.days.apply(self.price_frame()?);
+        if let Some(clip) = self.clip {
+            frame = frame.winsorize("Price", clip)?.0;
+        }
+        frame
+            .split_by(|date| period.label(date))
+            .into_iter()
+            .map(|(label, frame)| {
+                let (slots, _) = frame.profile(Profile::Day, self.samples, self.agg)?;
+                Ok((label, slots.into_iter().map(|slot| slot[0]).collect()))
+            })
+            .collect()
+    }
+
+    /// `average_price_profile` along with how many intervals each slot
+    /// averages and what any clip held prices to.
+    pub fn sampled_price_profile(&self, profile: Profile) -> error::Result<PriceProfile> {
+        let mut frame = self.days.apply(self.price_frame()?);
+        let mut clipped = None;
+        if let Some(clip) = self.clip {
+            let (winsorized, bounds) = frame.winsorize("Price", clip)?;
+            frame = winsorized;
+            clipped = Some(bounds);
+        }
+        let (slots, samples) = frame.profile(profile, self.samples, self.agg)?;
+        Ok(PriceProfile {
+            prices: slots.into_iter().map(|slot| slot[0]).collect(),
+            samples,
+            clipped,
+        })
+    }
+
+    /// The fraction of intervals in each slot of `profile` priced above
+    /// each of `thresholds`.
+    pub fn price_exceedance(
+        &self,
+        profile: Profile,
+        thresholds: &[f64],
+    ) -> error::Result<PriceExceedance> {
+        if thresholds.is_empty() {
+            bail!(Invalid, "Give at least one price threshold");
+        }
+        let mut thresholds = thresholds.to_vec();
+        thresholds.sort_by(f64::total_cmp);
+        thresholds.dedup();
+        let prices = self.days.apply(self.price_frame()?);
+        let price = prices.column("Price")?;
+        let mut frame = Frame::new(prices.times().to_vec());
+        for threshold in &thresholds {
+            let above = price
+                .iter()
+                .map(|price| if price > threshold { 1. } else { 0. })
+                .collect();
+            frame = frame.with_column(&threshold.to_string(), above)?;
+        }
+        let (probabilities, samples) = frame.profile(profile, self.samples, ProfileAgg::Mean)?;
+        Ok(PriceExceedance {
+            thresholds,
+            probabilities,
+            samples,
+        })
+    }
+
+    /// Each zone's congestion and loss components summarized in every slot
+    /// of `profile`. Needs a price csv parsed with its components.
+    pub fn price_components(&self, profile: Profile) -> error::Result<PriceComponents> {
+        let mut times = Vec::new();
+        let mut columns: [Vec<f64>; 6] = array::from_fn(|_| Vec::new());
+        for line in self.price_rows()? {
+            let line = line?;
+            let (congestion, loss) = (line.congestion(), line.loss());
+            if congestion.iter().chain(&loss).all(|value| value.is_nan()) {
+                continue;
+            }
+            times.push(parse_timestamp(&line.timestamp)?);
+            for (column, value) in columns.iter_mut().zip(congestion.into_iter().chain(loss)) {
+                column.push(value);
+            }
+        }
+        if times.is_empty() {
+            bail!(
+                NoData,
+                "{:?} has no congestion or loss components. Parse it again from the \
+                 raw zone files to keep them",
+                self.path
+            );
+        }
+        let names = ["congestion", "loss"].into_iter().flat_map(|component| {
+            EnergyPriceCsvRow::ZONES.map(|zone| format!("{zone} {component}"))
+        });
+        let mut frame = Frame::new(times);
+        for (name, column) in names.zip(columns) {
+            frame = frame.with_column(&name, column)?;
+        }
+        let (slots, samples) = self
+            .days
+            .apply(frame)
+            .profile(profile, self.samples, self.agg)?;
+        Ok(PriceComponents {
+            congestion: slots
+                .iter()
+                .map(|slot| [slot[0], slot[1], slot[2]])
+                .collect(),
+            loss: slots
+                .iter()
+                .map(|slot| [slot[3], slot[4], slot[5]])
+                .collect(),
+            samples,
+        })
+    }
+
+    /// Each area's own LMP averaged in every slot of `profile`. Needs a
+    /// price csv parsed with them.
+    pub fn area_prices(&self, profile: Profile) -> error::Result<AreaPrices> {
+        let mut times = Vec::new();
+        let mut columns: [Vec<f64>; 3] = array::from_fn(|_| Vec::new());
+        for line in self.price_rows()? {
+            let line = line?;
+            let lmps = line.lmps();
+            if lmps.iter().all(|value| value.is_nan()) {
+                continue;
+            }
+            times.push(parse_timestamp(&line.timestamp)?);
+            for (column, value) in columns.iter_mut().zip(lmps) {
+                column.push(value);
+            }
+        }
+        if times.is_empty() {
+            bail!(
+                NoData,
+                "{:?} has no per-area LMPs. Parse it again from the raw zone or hub \
+                 files to keep them",
+                self.path
+            );
+        }
+        let mut frame = Frame::new(times);
+        for (zone, column) in EnergyPriceCsvRow::ZONES.iter().zip(columns) {
+            frame = frame.with_column(zone, column)?;
+        }
+        let (slots, samples) = self
+            .days
+            .apply(frame)
+            .profile(profile, self.samples, self.agg)?;
+        Ok(AreaPrices {
+            prices: slots
+                .iter()
+                .map(|slot| [slot[0], slot[1], slot[2]])
+                .collect(),
+            samples,
+        })
+    }
+
+    /// The partial days at the edges of the dataset and how many whole days
+    /// the day policy leaves in each slot of `profile`.
+    pub fn day_coverage(&self, profile: Profile) -> error::Result<DayCoverage> {
+        let frame = self.frame()?;
+        if frame.is_empty() {
+            bail!(NoData, "No rows in {:?}", self.path);
+        }
+        Ok(DayCoverage {
+            partial: frame.partial_days(),
+            full_days: self.days.apply(frame).full_days_per_slot(profile),
+        })
+    }
+
+    /// Row counts, date coverage and overall price or energy figures for
+    /// the whole dataset, before any day policy.
+    pub fn dataset_stats(&self) -> error::Result<DatasetStats> {
+        let kind = self.kind()?;
+        let frame = match kind {
+            RawKind::Price => self.price_frame()?,
+            RawKind::Gen => self.gen_frame()?,
+        };
+        let times = frame.times();
+        let (Some(first), Some(last)) = (times.first(), times.last()) else {
+            bail!(NoData, "No rows in {:?}", self.path);
+        };
+        let mut days: Vec<NaiveDate> = times.iter().map(NaiveDateTime::date).collect();
+        days.dedup();
+        let mut stats = DatasetStats {
+            kind,
+            rows: frame.len(),
+            first: first.format(TIMESTAMP_FMT).to_string(),
+            last: last.format(TIMESTAMP_FMT).to_string(),
+            days: days.len(),
+            partial_days: frame.partial_days().len(),
+            price: None,
+            sources: Vec::new(),
+        };
+        match kind {
+            RawKind::Price => {
+                let mut prices: Vec<f64> = frame
+                    .column("Price")?
+                    .iter()
+                    .copied()
+                    .filter(|price| !price.is_nan())
+                    .collect();
+                if prices.is_empty() {
+                    bail!(NoData, "No prices in {:?}", self.path);
+                }
+                prices.sort_by(f64::total_cmp);
+                let count = prices.len() as f64;
+                let negative = prices.iter().filter(|price| **price < 0.).count();
+                stats.price = Some(PriceStats {
+                    mean: prices.iter().sum::<f64>() / count,
+                    median: percentile(&prices, 0.5),
+                    min: prices[0],
+                    max: prices[prices.len() - 1],
+                    negative_share: negative as f64 / count,
+                });
+            }
+            RawKind::Gen => {
+                let hours = Self::MINS_INCR as f64 / 60.;
+                for name in frame.names() {
+                    let mw: f64 = frame.column(name)?.iter().filter(|mw| !mw.is_nan()).sum();
+                    stats.sources.push(SourceEnergy {
+                        source: name.clone(),
+                        mwh: mw * hours,
+                        share: f64::NAN,
+                    });
+                }
+                let total = stats.sources[0].mwh;
+                for source in &mut stats.sources {
+                    source.share = source.mwh / total;
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Compares `column`'s values in each slot of `profile` over the dates
+    /// in `after` with those over the dates in `before`, such as before and
+    /// after a milestone in battery capacity.
+    pub fn period_difference(
+        &self,
+        column: &str,
+        profile: Profile,
+        before: RangeInclusive<NaiveDate>,
+        after: RangeInclusive<NaiveDate>,
+    ) -> error::Result<Vec<MeanDifference>> {
+        let frame = self.days.apply(self.frame()?);
+        let by_slot = |dates: RangeInclusive<NaiveDate>| -> error::Result<Vec<Vec<f64>>> {
+            let period = frame.between(dates.clone());
+            if period.is_empty() {
+                bail!(NoData, "No rows in {:?} between {dates:?}", self.path);
+            }
+            let mut slots = vec![Vec::new(); profile.slots()];
+            for (time, value) in period.times().iter().zip(period.column(column)?) {
+                slots[profile.slot_of(*time)].push(*value);
+            }
+            Ok(slots)
+        };
+        let (before, after) = (by_slot(before)?, by_slot(after)?);
+        Ok(before
+            .iter()
+            .zip(&after)
+            .map(|(before, after)| MeanDifference::of(before, after))
+            .collect())
+    }
+
+    /// Average price in each `window_mins`-minute window of the day, over
+    /// only the dates in `dates`.
+    pub fn windowed_price_profile(
+        &self,
+        dates: RangeInclusive<NaiveDate>,
+        window_mins: usize,
+    ) -> error::Result<Vec<f64>> {
+        let frame = self.price_frame()?.between(dates.clone());
+        if frame.is_empty() {
+            bail!(NoData, "No prices between {dates:?}");
+        }
+        let slots = frame.windowed(window_mins)?;
+        Ok(slots.into_iter().map(|slot| slot[0]).collect())
+    }
+
+    /// Like `windowed_price_profile`, for each source's output.
+    pub fn windowed_gen_profile(
+        &self,
+        dates: RangeInclusive<NaiveDate>,
+        window_mins: usize,
+    ) -> error::Result<Vec<[f64; 14]>> {
+        let frame = self.gen_frame()?.between(dates.clone());
+        if frame.is_empty() {
+            bail!(NoData, "No generation between {dates:?}");
+        }
+        let slots = frame.windowed(window_mins)?;
+        Ok(slots.into_iter().map(Self::to_sources).collect())
+    }
+
+    /// How many five-minute intervals make up a window, which must evenly
+    /// divide the day.
+    pub fn intervals_per_window(window_mins: usize) -> error::Result<usize> {
+        if window_mins == 0
+            || !window_mins.is_multiple_of(Self::MINS_INCR)
+            || !Self::MINS_PER_DAY.is_multiple_of(window_mins)
+        {
+            bail!(
+                Invalid,
+                "Windows must be a multiple of {} minutes that divides the day, got {window_mins}",
+                Self::MINS_INCR
+            );
+        }
+        Ok(window_mins / Self::MINS_INCR)
+    }
+
+    /// The number of five-minute windows in a day.
+    pub fn slots_per_day() -> usize {
+        Self::MINS_PER_DAY / Self::MINS_INCR
+    }
+
+    /// Reads price rows (see `stored_price_rows`) with the price node's
+    /// LMP as each row's price.
+    fn price_rows(&self) -> error::Result<Rows<'a, EnergyPriceCsvRow>> {
+        let rows = self.stored_price_rows()?;
+        let (node, path) = (self.node, self.path.to_path_buf());
+        if node == PriceNode::Average {
+            return Ok(rows);
+        }
+        Ok(Box::new(rows.map(move |row| {
+            node.select(row?.into_owned(), &path).map(Cow::Owned)
+        })))
+    }
+
+    /// Reads price rows as they're stored, before a price node is picked:
+    /// in place from a `Dataset` this was handed for the path, or else from
+    /// a parsed csv, a binary `Store` or a monthly `Manifest`.
+    pub(crate) fn stored_price_rows(&self) -> error::Result<Rows<'a, EnergyPriceCsvRow>> {
+        if let Some(dataset) = self.held() {
+            return borrowed(dataset.price_rows(), dataset);
+        }
+        let rows: Rows<EnergyPriceCsvRow> = if Manifest::is_manifest(self.path) {
+            manifest_rows(&Manifest::open(self.path)?)
+        } else if Store::is_store(self.path)? {
+            let store = Store::open(self.path)?;
+            let all = 0..store.len();
+            owned(store.into_price_rows(all)?)
+        } else {
+            owned(read_rows(self.path)?)
+        };
+        Ok(rows)
+    }
 
-    // I assume every timeslot has an equal number of data points. Allow up to
-    // this many missed times before warning that the data doesn't look
-    // how I think it does.
-    const MAX_WINDOW_MISS: usize = 12;
+    /// Loads the dataset into a `Frame`, with a Price column for price data
+    /// or one column per source (named as in `EnergyGenCsvRow::source_idx`)
+    /// for generation.
+    pub fn frame(&self) -> error::Result<Frame> {
+        match self.kind()? {
+            RawKind::Price => self.price_frame(),
+            RawKind::Gen => self.gen_frame(),
+        }
+    }
 
-    pub fn new(path: &'a Path) -> Self {
-        Self { path }
+    /// Whether the dataset holds prices or generation.
+    pub fn kind(&self) -> error::Result<RawKind> {
+        if let Some(dataset) = self.held() {
+            return Ok(dataset.kind());
+        }
+        let holds_prices = if Manifest::is_manifest(self.path) {
+            Manifest::open(self.path)?.kind == RawKind::Price
+        } else if Store::is_store(self.path)? {
+            Store::open(self.path)?.holds_prices()
+        } else {
+            csv::Reader::from_path(self.path)?
+                .headers()?
+                .iter()
+                .any(|header| header == "lmp_avg")
+        };
+        Ok(match holds_prices {
+            true => RawKind::Price,
+            false => RawKind::Gen,
+        })
     }
 
-    /// Returns the index in a 24-hour block of five-minute windows that this time should fill.
-    pub fn time_to_idx_5min(hour: u32, minute: u32) -> usize {
-        ((hour * 60) + minute) as usize / Self::MINS_INCR
+    /// The price rows combined into `minutes`-minute intervals, each stamped
+    /// with its start. The Price column is averaged unless `aggs` says to
+    /// sum it.
+    pub fn downsample_prices(
+        &self,
+        minutes: usize,
+        aggs: &Aggs,
+    ) -> error::Result<Vec<EnergyPriceCsvRow>> {
+        // Each area's LMP and its congestion and loss components are combined
+        // the same way as the price.
+        let [agg] = aggs.resolve(["Price"])?;
+        let intervals = downsample(
+            self.price_rows()?,
+            minutes,
+            [agg; 10],
+            |row| parse_timestamp(&row.timestamp),
+            EnergyPriceCsvRow::values,
+        )?;
+        Ok(intervals
+            .into_iter()
+            .map(|interval| {
+                let mut row = EnergyPriceCsvRow::new(
+                    interval.start.format(TIMESTAMP_FMT).to_string(),
+                    interval.start.hour(),
+                    interval.start.minute(),
+                    0.,
+                );
+                row.set_values(interval.values);
+                row
+            })
+            .collect())
     }
 
-    pub fn idx_5min_to_time(idx: usize) -> (u32, u32) {
-        ((idx as u32 * 5) / 60, (idx as u32 * 5) % 60)
+    /// The gen rows combined into `minutes`-minute intervals, each running
+    /// from its local start to the end of its last row. Sources are
+    /// averaged unless `aggs` says to sum them.
+    pub fn downsample_gen(
+        &self,
+        minutes: usize,
+        aggs: &Aggs,
+    ) -> error::Result<Vec<EnergyGenCsvRow>> {
+        let names: [&str; 14] = array::from_fn(|idx| {
+            EnergyGenCsvRow::source_keys()
+                .nth(idx)
+                .expect("14 sources")
+                .0
+        });
+        let aggs = aggs.resolve(names)?;
+        let intervals = downsample(
+            self.gen_rows()?,
+            minutes,
+            aggs,
+            |row| parse_timestamp(&row.local_timestamp_start),
+            EnergyGenCsvRow::sources,
+        )?;
+        Ok(intervals
+            .into_iter()
+            .map(|interval| {
+                let mut row = interval.last.into_owned();
+                row.local_timestamp_start = interval.start.format(TIMESTAMP_FMT).to_string();
+                row.local_date = interval.start.date().to_string();
+                row.hour = interval.start.hour();
+                row.minute = interval.start.minute();
+                row.set_sources(interval.values);
+                row
+            })
+            .collect())
     }
 
-    pub fn average_gen_5min(&self) -> anyhow::Result<Vec<[f64; 14]>> {
-        self.average_gen_5min_custom(|_| ())
+    /// The named columns of the dataset, e.g. `["Solar", "Wind"]`.
+    pub fn select(&self, columns: &[&str]) -> error::Result<Frame> {
+        self.frame()?.select(columns)
     }
 
-    pub fn average_gen_solar_battery(&self) -> anyhow::Result<Vec<[f64; 14]>> {
-        let battery_idx = Self::battery_idx();
+    fn price_frame(&self) -> error::Result<Frame> {
+        let (mut times, mut prices) = (Vec::new(), Vec::new());
+        for line in self.price_rows()? {
+            let line = line?;
+            times.push(parse_timestamp(&line.timestamp)?);
+            prices.push(line.lmp_avg);
+        }
+        Frame::new(times).with_column("Price", prices)
+    }
+
+    fn gen_frame(&self) -> error::Result<Frame> {
+        let mut times = Vec::new();
+        let mut columns: [Vec<f64>; 14] = array::from_fn(|_| Vec::new());
+        for line in self.gen_rows()? {
+            let line = line?;
+            times.push(parse_timestamp(&line.local_timestamp_start)?);
+            for (column, mw) in columns.iter_mut().zip(line.sources()) {
+                column.push(mw);
+            }
+        }
+        let mut frame = Frame::new(times);
+        for ((name, _), column) in EnergyGenCsvRow::source_keys().zip(columns) {
+            frame = frame.with_column(name, column)?;
+        }
+        Ok(frame)
+    }
+
+    /// A row of a gen frame's averages as a sources array.
+    fn to_sources(row: Vec<f64>) -> [f64; 14] {
+        row.try_into().expect("gen frames have a column per source")
+    }
+
+    /// Reads gen rows in place from a `Dataset` this was handed for the
+    /// path, or else from a parsed csv, a binary `Store` or a monthly
+    /// `Manifest`.
+    pub(crate) fn gen_rows(&self) -> error::Result<Rows<'a, EnergyGenCsvRow>> {
+        if let Some(dataset) = self.held() {
+            return borrowed(dataset.gen_rows(), dataset);
+        }
+        if Manifest::is_manifest(self.path) {
+            return Ok(manifest_rows(&Manifest::open(self.path)?));
+        }
+        if Store::is_store(self.path)? {
+            let store = Store::open(self.path)?;
+            let all = 0..store.len();
+            return Ok(owned(store.into_gen_rows(all)?));
+        }
+        Ok(owned(read_rows(self.path)?))
+    }
+
+    /// Reads the gen rows of a single day. Stores seek straight to it through
+    /// their day index; manifests scan only its month, and csvs are scanned.
+    fn gen_rows_on(&self, date: NaiveDate) -> error::Result<Rows<'a, EnergyGenCsvRow>> {
+        if self.held().is_none() && Store::is_store(self.path)? {
+            let store = Store::open(self.path)?;
+            let rows = store.rows_between(date, date + TimeDelta::days(1));
+            return Ok(owned(store.into_gen_rows(rows)?));
+        }
+        let rows = match Manifest::is_manifest(self.path) && self.held().is_none() {
+            true => match Manifest::open(self.path)?.file_of(date) {
+                Some(file) => owned(read_rows(file)?),
+                None => return Ok(Box::new(iter::empty())),
+            },
+            false => self.gen_rows()?,
+        };
+        let date_str = date.format("%Y-%m-%d").to_string();
+        Ok(Box::new(rows.filter(move |row| {
+            row.as_ref().map_or(true, |row| row.local_date == date_str)
+        })))
+    }
+
+    /// Returns every price in the dataset paired with its timestamp.
+    pub fn price_series(&self) -> error::Result<Vec<(NaiveDateTime, f64)>> {
+        let mut series = Vec::new();
+        for line in self.price_rows()? {
+            let line = line?;
+            let timestamp = parse_timestamp(&line.timestamp)?;
+            series.push((timestamp, line.lmp_avg));
+        }
+        Ok(series)
+    }
+
+    /// The last `count` prices in the dataset, oldest first. Parsed csvs are
+    /// read from the end, so this stays cheap as a live file grows.
+    pub fn latest_prices(&self, count: usize) -> error::Result<Vec<(NaiveDateTime, f64)>> {
+        let rows: Vec<EnergyPriceCsvRow> = if let Some(dataset) = self.held() {
+            let rows = held_rows(dataset.price_rows(), dataset)?;
+            rows[rows.len().saturating_sub(count)..].to_vec()
+        } else if Store::is_store(self.path)? {
+            let store = Store::open(self.path)?;
+            let rows = store.len().saturating_sub(count)..store.len();
+            store.into_price_rows(rows)?.collect::<error::Result<_>>()?
+        } else if Manifest::is_manifest(self.path) {
+            manifest_tail(&Manifest::open(self.path)?, count)?
+        } else {
+            tail_csv(self.path, count)?
+        };
+        rows.into_iter()
+            .map(|row| {
+                let row = self.node.select(row, self.path)?;
+                Ok((parse_timestamp(&row.timestamp)?, row.lmp_avg))
+            })
+            .collect()
+    }
+
+    /// The last interval of generation in the dataset, if there is one.
+    pub fn latest_gen(&self) -> error::Result<Option<(NaiveDateTime, [f64; 14])>> {
+        let rows: Vec<EnergyGenCsvRow> = if let Some(dataset) = self.held() {
+            let rows = held_rows(dataset.gen_rows(), dataset)?;
+            rows.last().cloned().into_iter().collect()
+        } else if Store::is_store(self.path)? {
+            let store = Store::open(self.path)?;
+            let rows = store.len().saturating_sub(1)..store.len();
+            store.into_gen_rows(rows)?.collect::<error::Result<_>>()?
+        } else if Manifest::is_manifest(self.path) {
+            manifest_tail(&Manifest::open(self.path)?, 1)?
+        } else {
+            tail_csv(self.path, 1)?
+        };
+        rows.last()
+            .map(|row| Ok((parse_timestamp(&row.local_timestamp_start)?, row.sources())))
+            .transpose()
+    }
+
+    /// The first and last dates in the dataset, read from its two ends.
+    pub fn date_span(&self) -> error::Result<Option<(NaiveDate, NaiveDate)>> {
+        let (first, last) = match self.kind()? {
+            RawKind::Price => (
+                match self.price_rows()?.next().transpose()? {
+                    Some(row) => Some(parse_timestamp(&row.timestamp)?.date()),
+                    None => None,
+                },
+                self.latest_prices(1)?.last().map(|(time, _)| time.date()),
+            ),
+            RawKind::Gen => (
+                match self.gen_rows()?.next().transpose()? {
+                    Some(row) => Some(parse_timestamp(&row.local_timestamp_start)?.date()),
+                    None => None,
+                },
+                self.latest_gen()?.map(|(time, _)| time.date()),
+            ),
+        };
+        Ok(first.zip(last))
+    }
+
+    /// Every date with a price, in order.
+    pub fn price_dates(&self) -> error::Result<Vec<NaiveDate>> {
+        let mut dates: Vec<NaiveDate> = Vec::new();
+        for line in self.price_rows()? {
+            let date = parse_timestamp(&line?.timestamp)?.date();
+            if dates.last() != Some(&date) {
+                dates.push(date);
+            }
+        }
+        Ok(dates)
+    }
+
+    /// Every date with generation data, in order.
+    pub fn gen_dates(&self) -> error::Result<Vec<NaiveDate>> {
+        let mut dates: Vec<NaiveDate> = Vec::new();
+        for line in self.gen_rows()? {
+            let date = parse_timestamp(&line?.local_timestamp_start)?.date();
+            if dates.last() != Some(&date) {
+                dates.push(date);
+            }
+        }
+        Ok(dates)
+    }
+
+    /// Resolves a spike threshold into a $/MWh price for the given series.
+    pub fn spike_price(series: &[(NaiveDateTime, f64)], threshold: SpikeThreshold) -> f64 {
+        match threshold {
+            SpikeThreshold::Absolute(price) => price,
+            SpikeThreshold::ZScore(z) => {
+                let len = series.len().max(1) as f64;
+                let mean = series.iter().map(|(_, price)| price).sum::<f64>() / len;
+                let variance = series
+                    .iter()
+                    .map(|(_, price)| (price - mean).powi(2))
+                    .sum::<f64>()
+                    / len;
+                mean + z * variance.sqrt()
+            }
+        }
+    }
+
+    /// Groups intervals priced above `spike_price` into events. Intervals
+    /// separated by more than one five-minute step start a new event.
+    pub fn find_price_spikes(series: &[(NaiveDateTime, f64)], spike_price: f64) -> Vec<PriceSpike> {
+        let step = TimeDelta::minutes(Self::MINS_INCR as i64);
+        let mut events: Vec<(NaiveDateTime, NaiveDateTime, NaiveDateTime, f64)> = Vec::new();
+
+        for &(time, price) in series.iter().filter(|(_, price)| *price > spike_price) {
+            match events.last_mut() {
+                Some((_, end, peak_time, peak)) if time - *end <= step => {
+                    *end = time;
+                    if price > *peak {
+                        *peak = price;
+                        *peak_time = time;
+                    }
+                }
+                _ => events.push((time, time, time, price)),
+            }
+        }
+
+        events
+            .into_iter()
+            .map(|(start, end, peak_time, peak_price)| {
+                // Each timestamp marks the beginning of its interval.
+                let end = end + step;
+                PriceSpike {
+                    start: start.format(TIMESTAMP_FMT).to_string(),
+                    end: end.format(TIMESTAMP_FMT).to_string(),
+                    duration_mins: (end - start).num_minutes(),
+                    peak_time: peak_time.format(TIMESTAMP_FMT).to_string(),
+                    peak_price,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns, for each day, the spread between the average of its `intervals`
+    /// most expensive and `intervals` cheapest five-minute prices. Days with
+    /// fewer than `2 * intervals` prices are skipped so the two sets never overlap.
+    pub fn daily_arbitrage_spread(&self, intervals: usize) -> error::Result<Vec<DailySpread>> {
+        if intervals == 0 {
+            bail!(
+                Invalid,
+                "Arbitrage spread needs at least one interval per side"
+            );
+        }
+        let series = self.price_series()?;
+        let rows = series
+            .into_iter()
+            .map(|(time, price)| Ok((time.date(), price)));
+        let days = partition_by_day(rows)?;
+        #[cfg(feature = "native")]
+        let days = days.into_par_iter();
+        #[cfg(not(feature = "native"))]
+        let days = days.into_iter();
+        Ok(days
+            .filter(|(_, prices)| prices.len() >= 2 * intervals)
+            .map(|(date, mut prices)| {
+                prices.sort_by(f64::total_cmp);
+                let avg = |prices: &[f64]| prices.iter().sum::<f64>() / prices.len() as f64;
+                let cheap_avg = avg(&prices[..intervals]);
+                let expensive_avg = avg(&prices[prices.len() - intervals..]);
+                DailySpread {
+                    date,
+                    cheap_avg,
+                    expensive_avg,
+                    spread: expensive_avg - cheap_avg,
+                }
+            })
+            .collect())
+    }
+
+    /// Returns the average price of each calendar day in the dataset, in order.
+    pub fn daily_average_prices(&self) -> error::Result<Vec<(NaiveDate, f64)>> {
+        if Manifest::is_manifest(self.path) {
+            let months = Manifest::open(self.path)?.par_map(|file| {
+                Compute::new(file)
+                    .with_price_node(self.node)
+                    .daily_average_prices()
+            })?;
+            return Ok(months.concat());
+        }
+        Ok(self
+            .price_frame()?
+            .daily()
+            .into_iter()
+            .map(|(date, means)| (date, means[0]))
+            .collect())
+    }
+
+    /// Returns the average output of each source on each calendar day, in order.
+    pub fn daily_average_gen(&self) -> error::Result<Vec<(NaiveDate, [f64; 14])>> {
+        if Manifest::is_manifest(self.path) {
+            let months = Manifest::open(self.path)?
+                .par_map(|file| Compute::new(file).daily_average_gen())?;
+            return Ok(months.concat());
+        }
+        Ok(self
+            .gen_frame()?
+            .daily()
+            .into_iter()
+            .map(|(date, means)| (date, Self::to_sources(means)))
+            .collect())
+    }
+
+    /// Pulls the raw five-minute data for a single day out of both datasets.
+    pub fn day_profile(&self, gen_csv: &Path, date: NaiveDate) -> error::Result<DayProfile> {
+        let prices: Vec<_> = self
+            .price_series()?
+            .into_iter()
+            .filter(|(time, _)| time.date() == date)
+            .map(|(time, price)| (Self::time_to_idx_5min(time.hour(), time.minute()), price))
+            .collect();
+
+        let mut gen = Vec::new();
+        for line in self.over(gen_csv).gen_rows_on(date)? {
+            let line = line?;
+            gen.push((
+                Self::time_to_idx_5min(line.hour, line.minute),
+                line.sources(),
+            ));
+        }
+
+        if prices.is_empty() && gen.is_empty() {
+            bail!(NoData, "No data found for {date}");
+        }
+        Ok(DayProfile { date, prices, gen })
+    }
+
+    /// Each of `dates` with the average day of the `window_days` days either
+    /// side of it as its baseline. None of `dates` count toward a baseline.
+    pub fn event_days(
+        &self,
+        gen_csv: &Path,
+        dates: &[NaiveDate],
+        window_days: u32,
+    ) -> error::Result<Vec<EventDay>> {
+        let prices = self.price_frame()?.without(dates);
+        let gen = self.over(gen_csv).gen_frame()?.without(dates);
+        let mut events = Vec::new();
+        for &date in dates {
+            let around = date - TimeDelta::days(window_days.into())
+                ..=date + TimeDelta::days(window_days.into());
+            let (prices, gen) = (prices.between(around.clone()), gen.between(around));
+            let baseline_prices = prices.windowed(Self::MINS_INCR).map_err(|_| {
+                err!(
+                    NoData,
+                    "The {window_days} days around {date} don't have prices for every time of day"
+                )
+            })?;
+            let baseline_gen = gen.windowed(Self::MINS_INCR).map_err(|_| {
+                err!(
+                    NoData,
+                    "The {window_days} days around {date} don't have generation for every time of day"
+                )
+            })?;
+            let mut days: Vec<NaiveDate> = prices.times().iter().map(NaiveDateTime::date).collect();
+            days.dedup();
+            events.push(EventDay {
+                day: self.day_profile(gen_csv, date)?,
+                baseline_prices: baseline_prices.into_iter().map(|slot| slot[0]).collect(),
+                baseline_gen: baseline_gen.into_iter().map(Self::to_sources).collect(),
+                baseline_days: days.len(),
+            });
+        }
+        Ok(events)
+    }
+
+    /// Finds the days with the highest and lowest average price and the most solar.
+    pub fn find_extreme_days(&self, gen_csv: &Path) -> error::Result<Vec<ExtremeDay>> {
+        let prices = self.daily_average_prices()?;
+        let gen = self.over(gen_csv).daily_average_gen()?;
         let solar_idx = Self::solar_idx();
 
-        self.average_gen_5min_custom(|row| {
+        let highest_price = prices.iter().max_by(|a, b| a.1.total_cmp(&b.1));
+        let lowest_price = prices.iter().min_by(|a, b| a.1.total_cmp(&b.1));
+        let highest_solar = gen
+            .iter()
+            .map(|(date, sources)| (*date, sources[solar_idx]))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        let (Some(highest_price), Some(lowest_price), Some(highest_solar)) =
+            (highest_price, lowest_price, highest_solar)
+        else {
+            bail!(NoData, "Cannot find extreme days of an empty dataset");
+        };
+
+        Ok(vec![
+            ExtremeDay {
+                label: "Highest average price ($/MWh)",
+                date: highest_price.0,
+                value: highest_price.1,
+            },
+            ExtremeDay {
+                label: "Lowest average price ($/MWh)",
+                date: lowest_price.0,
+                value: lowest_price.1,
+            },
+            ExtremeDay {
+                label: "Highest average solar (MW)",
+                date: highest_solar.0,
+                value: highest_solar.1,
+            },
+        ])
+    }
+
+    pub fn average_value_5min(&self, gen_csv: &Path) -> error::Result<([f64; 14], [f64; 14])> {
+        self.average_value_5min_custom(gen_csv, |_, _| ())
+    }
+
+    pub fn average_value_solar_battery(
+        &self,
+        gen_csv: &Path,
+    ) -> error::Result<([f64; 14], [f64; 14])> {
+        let battery_idx = Self::battery_idx();
+        let solar_idx = Self::solar_idx();
+        self.average_value_5min_custom(gen_csv, |row, _| {
             row[solar_idx] += row[battery_idx];
             row[battery_idx] = 0.;
         })
     }
 
-    fn average_gen_5min_custom(
+    /// Average value by source after scaling one source's output and adjusting
+    /// prices with the scenario's merit-order model.
+    pub fn average_value_scaled(
         &self,
-        gen_mod: impl Fn(&mut [f64; 14]),
-    ) -> anyhow::Result<Vec<[f64; 14]>> {
-        let mut reader = csv::Reader::from_path(self.path)?;
-        let mut results: Vec<[f64; 14]> = (0..(Self::MINS_PER_DAY / Self::MINS_INCR))
-            .map(|idx| {
-                let (hour, minute) = Self::idx_5min_to_time(idx);
-                EnergyGenCsvRow {
-                    hour,
-                    minute,
-                    ..Default::default()
+        gen_csv: &Path,
+        scaling: &SourceScaling,
+    ) -> error::Result<([f64; 14], [f64; 14])> {
+        self.average_value_5min_custom(gen_csv, |row, price| {
+            *price = scaling.apply(row, *price);
+        })
+    }
+
+    /// Each source's revenue on each calendar day, in dollars: its energy in
+    /// every interval times that interval's price. Charging batteries and
+    /// exports pay rather than earn, so their days can be negative.
+    pub fn daily_revenue(&self, gen_csv: &Path) -> error::Result<Vec<(NaiveDate, [f64; 14])>> {
+        let hours = Self::MINS_INCR as f64 / 60.;
+        let mut days: Vec<(NaiveDate, [f64; 14])> = Vec::new();
+        for row in self.try_iter_price_gen(gen_csv)? {
+            let (price, gen) = row?;
+            let date = parse_timestamp(&price.timestamp)?.date();
+            let revenue = gen.sources().map(|mw| mw * hours * price.lmp_avg);
+            match days.last_mut() {
+                Some((last, totals)) if *last == date => {
+                    for (total, dollars) in totals.iter_mut().zip(revenue) {
+                        *total += dollars;
+                    }
+                }
+                _ => days.push((date, revenue)),
+            }
+        }
+        if days.is_empty() {
+            return Err(EnergyAnalysisError::JoinMismatch {
+                price: self.path.to_path_buf(),
+                gen: gen_csv.to_path_buf(),
+            });
+        }
+        Ok(days)
+    }
+
+    /// The average price and output of each source in each slot of the
+    /// profile, over only the intervals that have both, so the two can share
+    /// a chart.
+    pub fn price_gen_profile(
+        &self,
+        gen_csv: &Path,
+        profile: Profile,
+    ) -> error::Result<(Vec<f64>, Vec<[f64; 14]>)> {
+        let joined = self.try_iter_price_gen(gen_csv)?;
+        let slots: Vec<(Mean<f64>, Mean<[f64; 14]>)> =
+            group_by_index(joined, profile.slots(), |(price, gen)| {
+                let slot = profile.slot(&price.timestamp, price.hour, price.minute)?;
+                Ok((slot, (price.lmp_avg, gen.sources())))
+            })?;
+        if slots.iter().any(|(price, _)| price.count() == 0) {
+            bail!(
+                TimeGap,
+                "No joined price and generation data for some slot of the profile"
+            );
+        }
+        Ok(slots.into_iter().map(Reducer::finish).unzip())
+    }
+
+    /// Each source's realized $/MWh within each slot of the profile, the
+    /// time-resolved counterpart of `average_value_5min`. Shows when in the
+    /// day a source's output is worth the least.
+    pub fn value_profile(&self, gen_csv: &Path, profile: Profile) -> error::Result<Vec<[f64; 14]>> {
+        let joined = self.try_iter_price_gen(gen_csv)?;
+        let slots: Vec<SummaryReducer> =
+            group_by_index(joined, profile.slots(), |(price, gen)| {
+                let slot = profile.slot(&price.timestamp, price.hour, price.minute)?;
+                Ok((slot, (price.lmp_avg, gen.sources())))
+            })?;
+        if slots.iter().any(|slot| slot.count == 0) {
+            bail!(
+                TimeGap,
+                "No joined price and generation data for some slot of the profile"
+            );
+        }
+        Ok(slots
+            .into_iter()
+            .map(|slot| {
+                let (_, _, values) = slot.finish();
+                values
+            })
+            .collect())
+    }
+
+    /// Returns the (historical, scenario) average price of each five-minute
+    /// window of the day under the given source scaling.
+    pub fn price_impact_5min(
+        &self,
+        gen_csv: &Path,
+        scaling: &SourceScaling,
+    ) -> error::Result<Vec<(f64, f64)>> {
+        let joined = self.try_iter_price_gen(gen_csv)?;
+        let slots: Vec<Mean<(f64, f64)>> =
+            group_by_index(joined, Self::slots_per_day(), |(price, gen)| {
+                let mut sources = gen.sources();
+                let scenario = scaling.apply(&mut sources, price.lmp_avg);
+                Ok((
+                    Self::time_to_idx_5min(price.hour, price.minute),
+                    (price.lmp_avg, scenario),
+                ))
+            })?;
+        if slots.iter().any(|slot| slot.count() == 0) {
+            bail!(
+                TimeGap,
+                "No joined price and generation data for some time of day"
+            );
+        }
+        Ok(slots.into_iter().map(Mean::finish).collect())
+    }
+
+    /// The average historical and scenario net load and price in each
+    /// five-minute window of the day under `scaling`, over only the days its
+    /// window covers. Demand is left as it was, so whatever output the
+    /// scaling takes away is made up by the rest of the fleet.
+    pub fn net_load_impact_5min(
+        &self,
+        gen_csv: &Path,
+        scaling: &SourceScaling,
+    ) -> error::Result<Vec<ScenarioSlot>> {
+        let wind = EnergyGenCsvRow::source_idx("Wind")?;
+        let solar = Self::solar_idx();
+        let net_load = |sources: &[f64; 14]| sources[0] - sources[wind] - sources[solar];
+        let joined = self
+            .try_iter_price_gen(gen_csv)?
+            .map(|row| {
+                let (price, gen) = row?;
+                Ok((parse_timestamp(&price.timestamp)?, price, gen))
+            })
+            .filter(|row| match (row, &scaling.window) {
+                (Ok((time, _, _)), Some(window)) => window.covers(time.date()),
+                _ => true,
+            });
+        let slots: Vec<Mean<[f64; 4]>> =
+            group_by_index(joined, Self::slots_per_day(), |(time, price, gen)| {
+                let mut sources = gen.sources();
+                let before = net_load(&sources);
+                let scenario = scaling.apply_at(time, &mut sources, price.lmp_avg);
+                Ok((
+                    Self::time_to_idx_5min(price.hour, price.minute),
+                    [before, net_load(&sources), price.lmp_avg, scenario],
+                ))
+            })?;
+        if slots.iter().any(|slot| slot.count() == 0) {
+            bail!(
+                TimeGap,
+                "No joined price and generation data for some time of day in the scenario's days"
+            );
+        }
+        Ok(slots
+            .into_iter()
+            .map(|slot| {
+                let [net_load, scenario_net_load, price, scenario_price] = slot.finish();
+                ScenarioSlot {
+                    net_load,
+                    scenario_net_load,
+                    price,
+                    scenario_price,
+                }
+            })
+            .collect())
+    }
+
+    /// Finds the time of each day's highest price and highest net load
+    /// (demand less wind and solar), their medians by month, and the days
+    /// either peak shifted to a new time of day. Shifts are only looked for
+    /// between stretches of at least `min_days` days. Days missing more
+    /// than an hour of joined data are skipped, since their peak may be in
+    /// the gap.
+    pub fn peak_timing(&self, gen_csv: &Path, min_days: usize) -> error::Result<PeakTiming> {
+        let wind = EnergyGenCsvRow::source_idx("Wind")?;
+        let solar = Self::solar_idx();
+        let rows = self.try_iter_price_gen(gen_csv)?.map(|row| {
+            let (price, gen) = row?;
+            let sources = gen.sources();
+            let mins = f64::from(price.hour * 60 + price.minute);
+            let net_load = sources[0] - sources[wind] - sources[solar];
+            Ok((
+                parse_timestamp(&price.timestamp)?.date(),
+                (mins, price.lmp_avg, net_load),
+            ))
+        });
+        let min_slots = Self::slots_per_day() - 60 / Self::MINS_INCR;
+        let days = partition_by_day(rows)?;
+        #[cfg(feature = "native")]
+        let days = days.into_par_iter();
+        #[cfg(not(feature = "native"))]
+        let days = days.into_iter();
+        let days: Vec<DailyPeak> = days
+            .filter(|(_, slots)| slots.len() >= min_slots)
+            .map(|(date, slots)| {
+                let peak = |value: fn(&(f64, f64, f64)) -> f64| {
+                    slots
+                        .iter()
+                        .max_by(|a, b| value(a).total_cmp(&value(b)))
+                        .map_or(f64::NAN, |slot| slot.0)
+                };
+                DailyPeak {
+                    date,
+                    price_peak: peak(|slot| slot.1),
+                    net_load_peak: peak(|slot| slot.2),
                 }
-                .sources()
             })
             .collect();
-        let mut counts = vec![0; results.len()];
+        if days.is_empty() {
+            bail!(
+                NoData,
+                "No day of {:?} and {gen_csv:?} has joined data for the whole day",
+                self.path
+            );
+        }
 
-        for line in reader.deserialize() {
-            let line: EnergyGenCsvRow = line?;
-            let mut sources = line.sources();
-            gen_mod(&mut sources);
-            let idx = Self::time_to_idx_5min(line.hour, line.minute);
-            for (res_src, src_val) in results[idx].iter_mut().zip(sources.iter()) {
-                *res_src += src_val;
+        let mut months: Vec<(String, Vec<DailyPeak>)> = Vec::new();
+        for day in &days {
+            let month = Period::Month.label(day.date);
+            match months.last_mut() {
+                Some((last, peaks)) if *last == month => peaks.push(*day),
+                _ => months.push((month, vec![*day])),
             }
-            counts[idx] += 1;
         }
+        let median = |peaks: &[DailyPeak], value: fn(&DailyPeak) -> f64| {
+            let mut values: Vec<f64> = peaks.iter().map(value).collect();
+            values.sort_by(f64::total_cmp);
+            percentile(&values, 0.5)
+        };
+        let months = months
+            .into_iter()
+            .map(|(month, peaks)| MonthPeak {
+                month,
+                days: peaks.len(),
+                price_peak: median(&peaks, |day| day.price_peak),
+                net_load_peak: median(&peaks, |day| day.net_load_peak),
+            })
+            .collect();
 
-        for (total, ct) in results.iter_mut().zip(&counts) {
-            if ct.max(&counts[0]) - ct.min(&counts[0]) > Self::MAX_WINDOW_MISS {
-                bail!(
-                    "Distrib is not even: diff({}, {ct}) > {}",
-                    counts[0],
-                    Self::MAX_WINDOW_MISS
+        let mut shifts = Vec::new();
+        type Series = (&'static str, fn(&DailyPeak) -> f64);
+        let series: [Series; 2] = [
+            ("price", |day| day.price_peak),
+            ("net_load", |day| day.net_load_peak),
+        ];
+        for (name, value) in series {
+            let values: Vec<f64> = days.iter().map(value).collect();
+            let splits = changepoints(&values, min_days, PeakTiming::ALPHA);
+            let bounds: Vec<usize> = iter::once(0)
+                .chain(splits.iter().copied())
+                .chain(iter::once(values.len()))
+                .collect();
+            for (idx, &split) in splits.iter().enumerate() {
+                let diff = MeanDifference::of(
+                    &values[bounds[idx]..split],
+                    &values[split..bounds[idx + 2]],
                 );
+                shifts.push(PeakShift {
+                    series: name,
+                    date: days[split].date,
+                    before: diff.before,
+                    after: diff.after,
+                    p_value: diff.p_value,
+                });
+            }
+        }
+        Ok(PeakTiming {
+            days,
+            months,
+            shifts,
+        })
+    }
+
+    /// Bins every interval by its implied reserve margin and averages the
+    /// price in each band of `bin_width`. The margin is the capacity that
+    /// could have been called on beyond demand, as a fraction of demand:
+    /// each source's installed capacity, or what it produced when it has
+    /// none in `capacity`. Wind and solar always count what they produced,
+    /// since they can't be called on for more.
+    pub fn reserve_margin_prices(
+        &self,
+        gen_csv: &Path,
+        demand_csv: &Path,
+        capacity: &InstalledCapacity,
+        bin_width: f64,
+    ) -> error::Result<ReserveMargin> {
+        if bin_width <= 0. {
+            bail!(Invalid, "Reserve margin bins must be wider than 0");
+        }
+        let variable = [EnergyGenCsvRow::source_idx("Wind")?, Self::solar_idx()];
+        let mut bins: BTreeMap<i64, Mean<[f64; 2]>> = BTreeMap::new();
+        let mut joined = self.iter_price_gen_demand(gen_csv, demand_csv)?;
+        for ((price, gen), demand) in joined.by_ref() {
+            if demand.demand_mw <= 0. {
+                continue;
+            }
+            let available: f64 = gen
+                .sources()
+                .iter()
+                .zip(&capacity.capacity_mw)
+                .enumerate()
+                .skip(1)
+                .map(|(idx, (mw, cap))| match cap {
+                    Some(cap) if !variable.contains(&idx) => *cap,
+                    _ => *mw,
+                })
+                .sum();
+            let margin = (available - demand.demand_mw) / demand.demand_mw;
+            bins.entry((margin / bin_width).floor() as i64)
+                .or_default()
+                .push([price.lmp_avg, demand.demand_mw]);
+        }
+        let price_gen = joined.inner_stats().unwrap_or_default();
+        let join = joined.finish()?;
+        if bins.is_empty() {
+            bail!(
+                NoData,
+                "No interval of {:?} and {gen_csv:?} falls in an hour of {demand_csv:?}",
+                self.path
+            );
+        }
+        let bins = bins
+            .into_iter()
+            .map(|(bin, mean)| {
+                let intervals = mean.count();
+                let [avg_price, avg_demand_mw] = mean.finish();
+                MarginBin {
+                    margin: bin as f64 * bin_width,
+                    intervals,
+                    avg_price,
+                    avg_demand_mw,
+                }
+            })
+            .collect();
+        Ok(ReserveMargin {
+            bins,
+            price_gen,
+            join,
+        })
+    }
+
+    /// Adds hypothetical batteries from zero up to `max_gwh` in `step_gwh` increments
+    /// and reports how each size changes the value of solar + battery output.
+    pub fn storage_sweep(
+        &self,
+        gen_csv: &Path,
+        max_gwh: f64,
+        step_gwh: f64,
+    ) -> error::Result<Vec<StorageSweepRow>> {
+        if step_gwh <= 0. {
+            bail!(Invalid, "Sweep step must be positive, got {step_gwh}");
+        }
+        let hours_per_incr = Self::MINS_INCR as f64 / 60.;
+        let mut base_revenue = 0.;
+        let mut base_mwh = 0.;
+        let mut days: Vec<Vec<(f64, f64)>> = Vec::new();
+        let mut last_date = String::new();
+
+        for row in self.try_iter_price_gen(gen_csv)? {
+            let (price, gen) = row?;
+            let merged = gen.solar + gen.battery;
+            base_revenue += merged * price.lmp_avg * hours_per_incr;
+            base_mwh += merged.abs() * hours_per_incr;
+            if gen.local_date != last_date {
+                last_date.clone_from(&gen.local_date);
+                days.push(Vec::new());
             }
-            for val in total.iter_mut() {
-                *val /= *ct as f64;
+            if let Some(day) = days.last_mut() {
+                day.push((price.lmp_avg, gen.solar));
             }
         }
+        if base_mwh == 0. {
+            return Err(EnergyAnalysisError::JoinMismatch {
+                price: self.path.to_path_buf(),
+                gen: gen_csv.to_path_buf(),
+            });
+        }
 
-        Ok(results)
+        let steps = (max_gwh / step_gwh).round() as usize;
+        Ok((0..=steps)
+            .map(|step| {
+                let capacity_gwh = step as f64 * step_gwh;
+                let battery = BatteryScenario::with_capacity(capacity_gwh * 1000.);
+                let arbitrage_revenue: f64 =
+                    days.iter().map(|day| battery.daily_arbitrage(day)).sum();
+                StorageSweepRow {
+                    capacity_gwh,
+                    avg_value: (base_revenue + arbitrage_revenue) / base_mwh,
+                    arbitrage_revenue,
+                }
+            })
+            .collect())
     }
 
-    pub fn average_price_5min(&self) -> anyhow::Result<Vec<f64>> {
-        let mut reader = csv::Reader::from_path(self.path)?;
+    /// Plans `scenario` against the average price profile, or against every
+    /// day whose whole window has prices when `per_day` is set. With
+    /// `gen_csv`, each slot is also weighed by the grid's carbon intensity
+    /// then, and days missing any of the window's generation are skipped.
+    pub fn charging_plans(
+        &self,
+        scenario: &ChargingScenario,
+        per_day: bool,
+        gen_csv: Option<&Path>,
+    ) -> error::Result<Vec<ChargingPlan>> {
+        let slots = scenario.slots();
+        if !per_day {
+            let avg = self.average_price_5min()?;
+            let prices: Vec<f64> = slots.iter().map(|&idx| avg[idx]).collect();
+            let intensity: Option<Vec<f64>> = match gen_csv {
+                Some(gen_csv) => {
+                    let gen = self.over(gen_csv).average_gen_5min()?;
+                    Some(
+                        slots
+                            .iter()
+                            .map(|&idx| carbon_intensity(&gen[idx]))
+                            .collect(),
+                    )
+                }
+                None => None,
+            };
+            let plan = scenario.plan("average".to_string(), &prices, intensity.as_deref())?;
+            return Ok(vec![plan]);
+        }
+
+        let series: HashMap<NaiveDateTime, f64> = self.price_series()?.into_iter().collect();
+        let intensities = match gen_csv {
+            Some(gen_csv) => {
+                let mut intensities = HashMap::new();
+                for line in self.over(gen_csv).gen_rows()? {
+                    let line = line?;
+                    let date = NaiveDate::parse_from_str(&line.local_date, "%Y-%m-%d")?;
+                    let time = date
+                        .and_hms_opt(line.hour, line.minute, 0)
+                        .expect("valid time");
+                    intensities.insert(time, carbon_intensity(&line.sources()));
+                }
+                Some(intensities)
+            }
+            None => None,
+        };
+        let (hour, minute) = Self::idx_5min_to_time(slots[0]);
+        let mut plans = Vec::new();
+        for date in self.price_dates()? {
+            let plug_in = date.and_hms_opt(hour, minute, 0).expect("valid time");
+            let times: Vec<NaiveDateTime> = (0..slots.len())
+                .map(|offset| plug_in + TimeDelta::minutes((offset * Self::MINS_INCR) as i64))
+                .collect();
+            let prices: Option<Vec<f64>> =
+                times.iter().map(|time| series.get(time).copied()).collect();
+            let intensity: Option<Option<Vec<f64>>> = match &intensities {
+                Some(intensities) => times
+                    .iter()
+                    .map(|time| intensities.get(time).copied())
+                    .collect::<Option<_>>()
+                    .map(Some),
+                None => Some(None),
+            };
+            if let (Some(prices), Some(intensity)) = (prices, intensity) {
+                plans.push(scenario.plan(date.to_string(), &prices, intensity.as_deref())?);
+            }
+        }
+        if plans.is_empty() {
+            bail!(
+                TimeGap,
+                "No day in {:?} has data for the whole charging window",
+                self.path
+            );
+        }
+        Ok(plans)
+    }
 
-        // (60 mins / 5 min increments) * 24 hours
-        let mut results = vec![0.; Self::MINS_PER_DAY / Self::MINS_INCR];
-        let mut counts = vec![0; results.len()];
+    /// Correlates every generation source with price, overall and per hour.
+    pub fn source_price_correlation(
+        &self,
+        gen_csv: &Path,
+    ) -> error::Result<SourcePriceCorrelation> {
+        let rows = self.try_iter_price_gen(gen_csv)?.map(|row| {
+            let (price, gen) = row?;
+            let date = parse_timestamp(&price.timestamp)?.date();
+            Ok((date, (price.hour as usize, (gen.sources(), price.lmp_avg))))
+        });
+        let days = partition_by_day(rows)?
+            .into_iter()
+            .map(|(_, day)| day)
+            .collect();
+        let hourly: Vec<CorrelationReducer> = par_group_by_index(days, 24, Ok)?;
 
-        for line in reader.deserialize() {
-            let line: EnergyPriceCsvRow = line?;
-            let idx = Self::time_to_idx_5min(line.hour, line.minute);
-            results[idx] += line.lmp_avg;
-            counts[idx] += 1;
+        let mut overall = CorrelationReducer::default();
+        for hour in &hourly {
+            overall.merge(*hour);
         }
+        if overall.0[0].count() == 0 {
+            return Err(EnergyAnalysisError::JoinMismatch {
+                price: self.path.to_path_buf(),
+                gen: gen_csv.to_path_buf(),
+            });
+        }
+
+        Ok(SourcePriceCorrelation {
+            overall: overall.finish(),
+            hourly: array::from_fn(|hour| hourly[hour].finish()),
+        })
+    }
+
+    /// Separates import and export intervals and totals the energy and value
+    /// in each direction by hour of day. Averaging the signed Imports column
+    /// nets the two directions against each other, which hides midday exports.
+    pub fn interchange_by_hour(&self, gen_csv: &Path) -> error::Result<Vec<DirectionalHour>> {
+        let imports_idx = EnergyGenCsvRow::source_idx("Imports")?;
+        self.directions_by_hour(gen_csv, imports_idx)
+    }
 
-        for (total, ct) in results.iter_mut().zip(&counts) {
-            if ct.max(&counts[0]) - ct.min(&counts[0]) > Self::MAX_WINDOW_MISS {
-                bail!("Distrib is not even: diff({}, {ct}) > target", counts[0]);
+    /// Averages an interchange csv (see `InterchangeCsvRow`) by neighbor and
+    /// hour of day, breaking the net Imports column down by where the power
+    /// comes from. Neighbors are ordered by their absolute average flow,
+    /// largest first.
+    pub fn interchange_by_ba(&self) -> error::Result<Vec<BaInterchange>> {
+        let rows = read_rows::<InterchangeCsvRow>(self.path)?;
+        let groups: Vec<(String, Vec<(usize, f64)>)> = group_by(rows, |row| {
+            let hour = parse_timestamp(&row.local_timestamp)?.hour() as usize;
+            Ok((row.ba, (hour, row.imports_mw)))
+        })?;
+        let mut bas: Vec<BaInterchange> = groups
+            .into_iter()
+            .map(|(ba, flows)| {
+                let mut hours: [Mean<f64>; 24] = array::from_fn(|_| Mean::default());
+                for (hour, mw) in flows {
+                    hours[hour].push(mw);
+                }
+                BaInterchange {
+                    ba,
+                    hours: hours.map(|mean| match mean.count() {
+                        0 => 0.,
+                        _ => mean.finish(),
+                    }),
+                }
+            })
+            .collect();
+        bas.sort_by(|a, b| b.daily_mw().abs().total_cmp(&a.daily_mw().abs()));
+        Ok(bas)
+    }
+
+    /// Separates battery discharging (positive) from charging (negative), so
+    /// the cost of charging and revenue from discharging can be compared.
+    pub fn battery_by_hour(&self, gen_csv: &Path) -> error::Result<Vec<DirectionalHour>> {
+        self.directions_by_hour(gen_csv, Self::battery_idx())
+    }
+
+    /// Every interval with both a price and generation, as the output of
+    /// the source at `source_idx` against the price, along with the line
+    /// that best fits them.
+    pub fn price_points(
+        &self,
+        gen_csv: &Path,
+        source_idx: usize,
+    ) -> error::Result<(Vec<PricePoint>, LinearFit)> {
+        let mut corr = Correlation::default();
+        let mut points: Vec<PricePoint> = Vec::new();
+        for row in self.try_iter_price_gen(gen_csv)? {
+            let (price, gen) = row?;
+            let point = PricePoint {
+                mw: gen.sources()[source_idx],
+                lmp: price.lmp_avg,
+                hour: price.hour,
+            };
+            if !point.mw.is_nan() && !point.lmp.is_nan() {
+                corr.push(point.mw, point.lmp);
+                points.push(point);
             }
-            *total /= *ct as f64;
         }
+        if points.is_empty() {
+            bail!(NoData, "No intervals with both a price and generation");
+        }
+        Ok((points, corr.fit()))
+    }
+
+    fn directions_by_hour(
+        &self,
+        gen_csv: &Path,
+        source_idx: usize,
+    ) -> error::Result<Vec<DirectionalHour>> {
+        let hours: Vec<DirectionReducer> =
+            group_by_index(self.try_iter_price_gen(gen_csv)?, 24, |(price, gen)| {
+                let sources = gen.sources();
+                Ok((price.hour as usize, (sources[source_idx], price.lmp_avg)))
+            })?;
+        Ok(hours
+            .into_iter()
+            .enumerate()
+            .map(|(hour, reducer)| {
+                let (positive, negative) = reducer.finish();
+                DirectionalHour {
+                    hour,
+                    positive,
+                    negative,
+                }
+            })
+            .collect())
+    }
 
-        Ok(results)
+    /// Summarizes price, generation mix, and value by source for each period.
+    pub fn period_summaries(
+        &self,
+        gen_csv: &Path,
+        period: Period,
+    ) -> error::Result<Vec<GroupSummary>> {
+        self.grouped_summaries(gen_csv, |price, _| {
+            let date = parse_timestamp(&price.timestamp)?.date();
+            Ok(period.label(date))
+        })
     }
 
-    pub fn average_value_5min(
-        price_csv: &Path,
+    /// Summarizes price, generation mix, and value by source for each water
+    /// year, pooling this dataset with each price and gen csv pair in
+    /// `others`. Intervals in the same water year share a summary, whichever
+    /// dataset they come from.
+    pub fn water_year_summaries(
+        &self,
         gen_csv: &Path,
-    ) -> anyhow::Result<([f64; 14], [f64; 14])> {
-        Self::average_value_5min_custom(price_csv, gen_csv, |_| ())
+        others: &[(PathBuf, PathBuf)],
+        years: &WaterYears,
+    ) -> error::Result<Vec<GroupSummary>> {
+        let mut joined = vec![self.try_iter_price_gen(gen_csv)?];
+        for (price_csv, gen_csv) in others {
+            joined.push(self.with_path(price_csv).try_iter_price_gen(gen_csv)?);
+        }
+        Self::summarize_joined(joined.into_iter().flatten(), |price, _| {
+            let date = parse_timestamp(&price.timestamp)?.date();
+            Ok(years.label(date))
+        })
     }
 
-    pub fn average_value_solar_battery(
-        price_csv: &Path,
+    /// Splits intervals into those where solar output exceeds `solar_threshold`
+    /// MW and those where it doesn't, and summarizes each regime.
+    pub fn solar_regime_summaries(
+        &self,
         gen_csv: &Path,
-    ) -> anyhow::Result<([f64; 14], [f64; 14])> {
-        let battery_idx = Self::battery_idx();
-        let solar_idx = Self::solar_idx();
-        Self::average_value_5min_custom(price_csv, gen_csv, |row| {
-            row[solar_idx] += row[battery_idx];
-            row[battery_idx] = 0.;
+        solar_threshold: f64,
+    ) -> error::Result<Vec<GroupSummary>> {
+        let mut summaries = self.grouped_summaries(gen_csv, |_, gen| {
+            Ok(if gen.solar > solar_threshold {
+                "Solar hours".to_string()
+            } else {
+                "Non-solar hours".to_string()
+            })
+        })?;
+        summaries.sort_by(|a, b| b.label.cmp(&a.label));
+        Ok(summaries)
+    }
+
+    /// Finds the `top` hours with the highest average net load and
+    /// summarizes price, generation mix, and value during them.
+    pub fn net_load_peaks(&self, gen_csv: &Path, top: usize) -> error::Result<NetLoadPeaks> {
+        let wind = EnergyGenCsvRow::source_idx("Wind")?;
+        let solar = Self::solar_idx();
+        let joined = self.try_iter_price_gen(gen_csv)?;
+        let hours = group_by(joined, |(price, gen)| {
+            let hour = parse_timestamp(&price.timestamp)?.format("%Y-%m-%d %H:00");
+            let sources = gen.sources();
+            let net_load = sources[0] - sources[wind] - sources[solar];
+            Ok((hour.to_string(), ((price.lmp_avg, sources), net_load)))
+        })?;
+        if hours.len() <= top {
+            bail!(
+                NoData,
+                "Only {} hours of data, fewer than the top {top}",
+                hours.len()
+            );
+        }
+
+        let mut hours: Vec<(String, (SummaryReducer, Mean<f64>))> = hours;
+        hours.sort_by(|a, b| (b.1).1.finish().total_cmp(&(a.1).1.finish()));
+        let rest = hours.split_off(top);
+        let fold = |label: String, hours: &[(String, (SummaryReducer, Mean<f64>))]| {
+            let mut merged = <(SummaryReducer, Mean<f64>)>::default();
+            for (_, reducers) in hours {
+                merged.merge(reducers.clone());
+            }
+            Self::summarize(label, merged)
+        };
+        Ok(NetLoadPeaks {
+            peak: fold(format!("Top {top} net-load hours"), &hours),
+            rest: fold("Other hours".to_string(), &rest),
+            hours: hours
+                .into_iter()
+                .map(|(label, reducers)| Self::summarize(label, reducers))
+                .collect(),
         })
     }
 
+    fn summarize(
+        label: String,
+        (summary, net_load): (SummaryReducer, Mean<f64>),
+    ) -> (GroupSummary, f64) {
+        let (avg_price, shares, values) = summary.finish();
+        let summary = GroupSummary {
+            label,
+            avg_price,
+            shares,
+            values,
+        };
+        (summary, net_load.finish())
+    }
+
+    /// Summarizes each time-of-use period in `schedule`, in schedule order.
+    /// Periods no interval falls in are left out.
+    pub fn tou_summaries(
+        &self,
+        gen_csv: &Path,
+        schedule: &TouSchedule,
+    ) -> error::Result<Vec<GroupSummary>> {
+        let mut summaries = self.grouped_summaries(gen_csv, |price, _| {
+            let time = parse_timestamp(&price.timestamp)?;
+            Ok(schedule.period(time).to_string())
+        })?;
+        let names = schedule.names();
+        summaries.sort_by_key(|s| names.iter().position(|name| *name == s.label));
+        Ok(summaries)
+    }
+
+    /// Summarizes joined rows grouped by `key`, in order of each group's first
+    /// appearance.
+    fn grouped_summaries(
+        &self,
+        gen_csv: &Path,
+        key: impl FnMut(&EnergyPriceCsvRow, &EnergyGenCsvRow) -> error::Result<String>,
+    ) -> error::Result<Vec<GroupSummary>> {
+        Self::summarize_joined(self.try_iter_price_gen(gen_csv)?, key)
+    }
+
+    fn summarize_joined<'b>(
+        joined: impl Iterator<
+            Item = error::Result<(Cow<'b, EnergyPriceCsvRow>, Cow<'b, EnergyGenCsvRow>)>,
+        >,
+        mut key: impl FnMut(&EnergyPriceCsvRow, &EnergyGenCsvRow) -> error::Result<String>,
+    ) -> error::Result<Vec<GroupSummary>> {
+        let groups = group_by(joined, |(price, gen)| {
+            Ok((key(&price, &gen)?, (price.lmp_avg, gen.sources())))
+        })?;
+        Ok(groups
+            .into_iter()
+            .map(|(label, reducer): (_, SummaryReducer)| {
+                let (avg_price, shares, values) = reducer.finish();
+                GroupSummary {
+                    label,
+                    avg_price,
+                    shares,
+                    values,
+                }
+            })
+            .collect())
+    }
+
     fn battery_idx() -> usize {
         const BATTERY_IDX: usize = 1;
         let mut key_iter = EnergyGenCsvRow::source_keys();
@@ -149,7 +3353,7 @@ impl<'a> Compute<'a> {
         BATTERY_IDX
     }
 
-    fn solar_idx() -> usize {
+    pub(crate) fn solar_idx() -> usize {
         const SOLAR_IDX: usize = 12;
         let mut key_iter = EnergyGenCsvRow::source_keys();
         let keys: [&'static str; 14] = array::from_fn(|_| key_iter.next().unwrap().0);
@@ -158,19 +3362,21 @@ impl<'a> Compute<'a> {
     }
 
     fn average_value_5min_custom(
-        price_csv: &Path,
+        &self,
         gen_csv: &Path,
-        gen_mod: impl Fn(&mut [f64; 14]),
-    ) -> anyhow::Result<([f64; 14], [f64; 14])> {
+        row_mod: impl Fn(&mut [f64; 14], &mut f64),
+    ) -> error::Result<([f64; 14], [f64; 14])> {
         let mut accs = [0f64; 14];
         let mut qtys = [0f64; 14];
 
-        for (price, gen) in Self::try_iter_price_gen(price_csv, gen_csv)? {
+        for row in self.try_iter_price_gen(gen_csv)? {
+            let (price, gen) = row?;
             let mut sources = gen.sources();
-            gen_mod(&mut sources);
+            let mut lmp = price.lmp_avg;
+            row_mod(&mut sources, &mut lmp);
             for (idx, qty) in sources.iter().copied().enumerate() {
                 qtys[idx] += qty.abs();
-                accs[idx] += qty * price.lmp_avg;
+                accs[idx] += qty * lmp;
             }
         }
 
@@ -183,61 +3389,48 @@ impl<'a> Compute<'a> {
         Ok((accs, qtys))
     }
 
+    /// Joins price, generation and demand by time. A demand reading holds
+    /// for the hour after it, so the hourly demand fetch-eia writes reaches
+    /// every five-minute interval of its hour.
+    pub fn iter_price_gen_demand<'b>(
+        &self,
+        gen_csv: &'b Path,
+        demand_csv: &Path,
+    ) -> error::Result<PriceGenDemandIter<'b>>
+    where
+        'a: 'b,
+    {
+        let demand = read_rows::<DemandCsvRow>(demand_csv)?
+            .map(|row| row.and_then(|row| Ok((parse_timestamp(&row.local_timestamp)?, row))));
+        Ok(self.price_gen_join(gen_csv)?.then(
+            |price, _| parse_timestamp(&price.timestamp),
+            demand,
+            JoinMode::Interval(TimeDelta::hours(1)),
+        ))
+    }
+
     /// Creates an iterator over joined price + generation data occuring at the same
     /// timestamps. The data is spotty at places, and this ensures the timestamps
-    /// line up between the two.
-    fn try_iter_price_gen(prices_csv: &Path, gen_csv: &Path) -> anyhow::Result<PriceGenIter> {
-        Ok(PriceGenIter {
-            prices: csv::Reader::from_path(prices_csv)?
-                .into_deserialize()
-                .peekable(),
-            gen: csv::Reader::from_path(gen_csv)?
-                .into_deserialize()
-                .peekable(),
-        })
+    /// line up between the two. A read error is the last item.
+    fn try_iter_price_gen<'b>(&self, gen_csv: &'b Path) -> error::Result<PriceGenIter<'b>>
+    where
+        'a: 'b,
+    {
+        Ok(self.price_gen_join(gen_csv)?.results())
     }
-}
-
-impl Iterator for PriceGenIter {
-    type Item = (EnergyPriceCsvRow, EnergyGenCsvRow);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            let (price, gen) = match (self.prices.peek(), self.gen.peek()) {
-                (Some(Err(e)), _) | (_, Some(Err(e))) => {
-                    eprintln!("{e}");
-                    return None;
-                }
-                (None, _) | (_, None) => return None,
-                (Some(Ok(p)), Some(Ok(g))) => (p, g),
-            };
-            let price_time =
-                NaiveDateTime::parse_from_str(&price.timestamp, "%Y-%m-%d %H:%M:%S").ok()?;
-            let gen_time =
-                NaiveDateTime::parse_from_str(&gen.local_timestamp_start, "%Y-%m-%d %H:%M:%S")
-                    .ok()?;
-            match price_time.cmp(&gen_time) {
-                Ordering::Equal => break,
-                Ordering::Greater => {
-                    // println!(
-                    //     "Unequal time: price {} v. gen {}",
-                    //     &price.timestamp, &gen.local_timestamp_start
-                    // );
-                    self.gen.next();
-                }
-                Ordering::Less => {
-                    // println!(
-                    //     "Unequal time: price {} v. gen {}",
-                    //     &price.timestamp, &gen.local_timestamp_start
-                    // );
-                    self.prices.next();
-                }
-            }
-        }
-        let (Some(Ok(price)), Some(Ok(gen))) = (self.prices.next(), self.gen.next()) else {
-            return None;
-        };
-        debug_assert!(price.timestamp == gen.local_timestamp_start);
-        Some((price, gen))
+    fn price_gen_join<'b>(&self, gen_csv: &'b Path) -> error::Result<PriceGenJoin<'b>>
+    where
+        'a: 'b,
+    {
+        let prices = self.price_rows()?.map(|row| {
+            let row: Cow<'b, EnergyPriceCsvRow> = row?;
+            Ok((parse_timestamp(&row.timestamp)?, row))
+        });
+        let gen = self
+            .over(gen_csv)
+            .gen_rows()?
+            .map(|row| row.and_then(|row| Ok((parse_timestamp(&row.local_timestamp_start)?, row))));
+        Ok(TimeAlignedJoin::new(prices, gen, JoinMode::Exact))
     }
 }
@@ -0,0 +1,520 @@
+//! ### Config
+//! Loaders for the user-supplied TOML files that parameterize analyses.
+
+use crate::astro::Site;
+use crate::chart::{Marker, PowerUnit, ReferenceLine, Theme, YScale};
+use crate::compute::{Compute, PriceNode};
+use crate::convert::EnergyGenCsvRow;
+use crate::error::{self, bail, err};
+pub use crate::output::WriteOptions;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// The market operator the raw inputs come from.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Rto {
+    #[default]
+    Caiso,
+}
+
+impl Rto {
+    /// A representative location and the local clock the RTO's data uses.
+    pub fn site(self) -> Site {
+        match self {
+            // Central California, on Pacific time.
+            Rto::Caiso => Site {
+                latitude: 36.78,
+                longitude: -119.42,
+                standard_offset: -8.,
+                us_dst: true,
+            },
+        }
+    }
+
+    /// How the RTO is named on charts.
+    pub fn name(self) -> &'static str {
+        match self {
+            Rto::Caiso => "CAISO",
+        }
+    }
+
+    /// The balancing authority's code in EIA's datasets.
+    pub fn eia_respondent(self) -> &'static str {
+        match self {
+            Rto::Caiso => "CISO",
+        }
+    }
+}
+
+/// Display names and units for charts, loaded from a TOML file so charts
+/// can be relabeled or translated without touching the source keys that
+/// csvs and commands use:
+///
+/// ```toml
+/// [sources]
+/// Batteries = "Storage"
+///
+/// [units]
+/// price = "USD/MWh"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Labels {
+    /// Display names keyed by source, matched case-insensitively.
+    pub sources: HashMap<String, String>,
+    pub units: Units,
+}
+
+/// The unit text on chart axes.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Units {
+    pub price: String,
+    pub energy: String,
+    pub power: String,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Self {
+            price: "$/MWh".to_string(),
+            energy: "MWh".to_string(),
+            power: "MW".to_string(),
+        }
+    }
+}
+
+impl Labels {
+    pub fn load(path: &Path) -> error::Result<Self> {
+        let mut labels: Self = toml::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| err!(Invalid, "Invalid labels {path:?}: {e}"))?;
+        // Re-key by canonical source name so lookups don't depend on case.
+        labels.sources = labels
+            .sources
+            .into_iter()
+            .map(|(source, name)| {
+                let idx = EnergyGenCsvRow::source_idx(&source)?;
+                let key = EnergyGenCsvRow::source_keys().nth(idx).map(|(key, _)| key);
+                Ok((key.unwrap_or_default().to_string(), name))
+            })
+            .collect::<error::Result<_>>()?;
+        Ok(labels)
+    }
+
+    /// The display name of a source key from `EnergyGenCsvRow::source_keys`.
+    pub fn source<'a>(&'a self, key: &'a str) -> &'a str {
+        self.sources.get(key).map_or(key, String::as_str)
+    }
+}
+
+/// Project-wide defaults, read from `energy-analysis.toml` in the working
+/// directory or from the file passed with `--config`. Every field is
+/// optional, and a missing file is the same as an empty one.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Relative inputs missing from the working directory are looked up
+    /// here, and bare file names given to parse commands are written here.
+    pub data_dir: Option<PathBuf>,
+    /// Bare file names given as outputs of write and graph commands are
+    /// written here.
+    pub results_dir: Option<PathBuf>,
+    pub rto: Rto,
+    /// Five-minute intervals used by windowed analyses when no window is
+    /// passed on the command line.
+    pub window_intervals: Option<usize>,
+    pub theme: Theme,
+    /// Where sunrise and sunset are computed for, in place of the RTO's
+    /// default location.
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// A labels TOML for chart text. See `Labels`.
+    #[serde(rename = "labels")]
+    pub labels_file: Option<PathBuf>,
+    /// A key for EIA's open-data API. The EIA_API_KEY environment variable
+    /// takes precedence, which keeps the key out of a shared config.
+    pub eia_api_key: Option<String>,
+    /// Writes a footer on every chart naming the span of its data, the
+    /// version that drew it, and when.
+    pub footer: bool,
+    /// How the y axis of price charts is scaled, unless `--y-scale` says
+    /// otherwise.
+    pub y_scale: YScale,
+    /// The unit charts of generation draw output in, "MW" or "GW".
+    pub unit: PowerUnit,
+    /// Either end of every chart's y axis, fixed in the units the chart is
+    /// labeled in, unless `--y-min` and `--y-max` say otherwise.
+    pub y_min: Option<f64>,
+    pub y_max: Option<f64>,
+    /// Labeled horizontal lines drawn across every chart that supports
+    /// them, along with any from `--reference-line`.
+    pub reference_lines: Vec<ReferenceLine>,
+    /// Labeled times marked on the time-of-day charts, along with any from
+    /// `--time-mark`.
+    pub time_marks: Vec<TimeMark>,
+    /// Whether charts also write their values beside them, from
+    /// `--also-csv`.
+    #[serde(skip)]
+    pub also_csv: bool,
+    /// A title, axis labels and size for every chart, from `--title`,
+    /// `--x-label`, `--y-label` and `--size`.
+    #[serde(skip)]
+    pub title: Option<String>,
+    #[serde(skip)]
+    pub x_label: Option<String>,
+    #[serde(skip)]
+    pub y_label: Option<String>,
+    #[serde(skip)]
+    pub size: Option<(u32, u32)>,
+    #[serde(skip)]
+    pub labels: Labels,
+    /// The footer line of the chart being drawn, filled in for each run
+    /// from its provenance.
+    #[serde(skip)]
+    pub footer_line: Option<String>,
+    /// Which LMP price analyses read, from `--price-node`.
+    #[serde(skip)]
+    pub price_node: PriceNode,
+    /// Whether outputs may replace files already there and how many
+    /// decimals their numbers keep, from `--overwrite` and `--precision`.
+    #[serde(skip)]
+    pub write: WriteOptions,
+}
+
+/// A labeled time of day marked on the time-of-day charts, such as the
+/// start of the evening peak:
+///
+/// ```toml
+/// time_marks = [{ time = "16:00", label = "Peak start" }]
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TimeMark {
+    pub time: NaiveTime,
+    pub label: String,
+}
+
+impl TimeMark {
+    /// The mark at the five-minute slot its time falls in.
+    pub fn marker(&self) -> Marker {
+        Marker {
+            slot: Compute::time_to_idx_5min(self.time.hour(), self.time.minute()),
+            label: self.label.clone(),
+        }
+    }
+}
+
+impl Settings {
+    pub const FILE_NAME: &'static str = "energy-analysis.toml";
+
+    pub fn load(path: &Path) -> error::Result<Self> {
+        let mut settings: Self = toml::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| err!(Invalid, "Invalid config {path:?}: {e}"))?;
+        if settings.window_intervals == Some(0) {
+            bail!(Invalid, "window_intervals in {path:?} must be positive");
+        }
+        if let (Some(min), Some(max)) = (settings.y_min, settings.y_max) {
+            if min >= max {
+                bail!(Invalid, "y_min {min} in {path:?} must be below y_max {max}");
+            }
+        }
+        if let Some(labels) = &settings.labels_file {
+            settings.labels = Labels::load(labels)?;
+        }
+        Ok(settings)
+    }
+
+    /// Loads `path` if one was given, otherwise `energy-analysis.toml` from
+    /// the working directory if it exists.
+    pub fn discover(path: Option<&Path>) -> error::Result<Self> {
+        match path {
+            Some(path) => Self::load(path),
+            None if Path::new(Self::FILE_NAME).is_file() => Self::load(Path::new(Self::FILE_NAME)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Computes over `path`, reading prices at the `price_node`.
+    pub fn compute<'a>(&self, path: &'a Path) -> Compute<'a> {
+        Compute::new(path).with_price_node(self.price_node)
+    }
+
+    pub fn eia_api_key(&self) -> error::Result<String> {
+        std::env::var("EIA_API_KEY")
+            .ok()
+            .or_else(|| self.eia_api_key.clone())
+            .ok_or_else(|| {
+                err!(
+                    Invalid,
+                    "Set EIA_API_KEY or eia_api_key in the config to use EIA's API"
+                )
+            })
+    }
+
+    /// The RTO's site, moved to the configured coordinates if there are any.
+    pub fn site(&self) -> Site {
+        let site = self.rto.site();
+        Site {
+            latitude: self.latitude.unwrap_or(site.latitude),
+            longitude: self.longitude.unwrap_or(site.longitude),
+            ..site
+        }
+    }
+
+    /// Resolves an input path, falling back to `data_dir` for relative
+    /// paths that don't exist as given.
+    pub fn input(&self, path: &Path) -> PathBuf {
+        match &self.data_dir {
+            Some(dir) if path.is_relative() && !path.exists() && dir.join(path).exists() => {
+                dir.join(path)
+            }
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// Places a bare output file name in `results_dir`.
+    pub fn output(&self, path: &Path) -> PathBuf {
+        Self::place(self.results_dir.as_deref(), path)
+    }
+
+    /// Places a bare output file name in `data_dir`.
+    pub fn data_output(&self, path: &Path) -> PathBuf {
+        Self::place(self.data_dir.as_deref(), path)
+    }
+
+    fn place(dir: Option<&Path>, path: &Path) -> PathBuf {
+        let mut components = path.components();
+        match (dir, components.next(), components.next()) {
+            (Some(dir), Some(Component::Normal(_)), None) => dir.join(path),
+            _ => path.to_path_buf(),
+        }
+    }
+}
+
+/// Time-of-use periods, like a utility's 4-9pm peak, loaded from a TOML:
+///
+/// ```toml
+/// default = "Off-peak"
+///
+/// [[periods]]
+/// name = "Peak"
+/// start = "16:00"
+/// end = "21:00"
+/// days = "weekdays"   # optional: "all" (default), "weekdays" or "weekends"
+/// months = [6, 7, 8, 9] # optional, all months when left out
+/// ```
+///
+/// An interval belongs to the first period that contains it, or to the
+/// default period if none do. Several entries may share a name, e.g. a
+/// summer and a winter peak.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TouSchedule {
+    #[serde(default = "TouSchedule::default_period")]
+    pub default: String,
+    pub periods: Vec<TouPeriod>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TouPeriod {
+    pub name: String,
+    pub start: NaiveTime,
+    /// Exclusive. An end at or before the start wraps past midnight.
+    pub end: NaiveTime,
+    #[serde(default)]
+    pub days: TouDays,
+    #[serde(default)]
+    pub months: Vec<u32>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TouDays {
+    #[default]
+    All,
+    Weekdays,
+    Weekends,
+}
+
+impl TouPeriod {
+    pub fn validate(&self) -> error::Result<()> {
+        if self.start == self.end {
+            bail!(
+                Invalid,
+                "TOU period {:?} starts and ends at the same time",
+                self.name
+            );
+        }
+        if let Some(month) = self.months.iter().find(|m| !(1..=12).contains(*m)) {
+            bail!(
+                Invalid,
+                "TOU period {:?} has invalid month {month}",
+                self.name
+            );
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, time: NaiveDateTime) -> bool {
+        let weekend = time.weekday().number_from_monday() > 5;
+        let day_matches = match self.days {
+            TouDays::All => true,
+            TouDays::Weekdays => !weekend,
+            TouDays::Weekends => weekend,
+        };
+        let month_matches = self.months.is_empty() || self.months.contains(&time.month());
+        let clock = time.time();
+        let time_matches = if self.start < self.end {
+            self.start <= clock && clock < self.end
+        } else {
+            self.start <= clock || clock < self.end
+        };
+        day_matches && month_matches && time_matches
+    }
+}
+
+impl TouSchedule {
+    fn default_period() -> String {
+        "Off-peak".to_string()
+    }
+
+    pub fn load(path: &Path) -> error::Result<Self> {
+        let schedule: Self = toml::from_str(&fs::read_to_string(path)?)?;
+        for period in &schedule.periods {
+            period.validate()?;
+        }
+        Ok(schedule)
+    }
+
+    /// The name of the period `time` falls in.
+    pub fn period(&self, time: NaiveDateTime) -> &str {
+        self.periods
+            .iter()
+            .find(|period| period.contains(time))
+            .map_or(&self.default, |period| &period.name)
+    }
+
+    /// Each distinct period name in the order it's listed, then the default.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for name in self
+            .periods
+            .iter()
+            .map(|p| p.name.as_str())
+            .chain([self.default.as_str()])
+        {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+}
+
+/// Nameplate capacity in MW for each generation source, loaded from a
+/// TOML table of `Source = megawatts` entries. Sources left out of the
+/// file have no capacity and are skipped by capacity-based analyses.
+#[derive(Debug)]
+pub struct InstalledCapacity {
+    pub capacity_mw: [Option<f64>; 14],
+}
+
+impl InstalledCapacity {
+    pub fn load(path: &Path) -> error::Result<Self> {
+        let entries: HashMap<String, f64> = toml::from_str(&fs::read_to_string(path)?)?;
+        let mut capacity_mw = [None; 14];
+        for (source, mw) in entries {
+            if mw <= 0. {
+                bail!(Invalid, "Capacity for {source} must be positive, got {mw}");
+            }
+            capacity_mw[EnergyGenCsvRow::source_idx(&source)?] = Some(mw);
+        }
+        Ok(Self { capacity_mw })
+    }
+
+    /// Divides each source's average output by its capacity. Sources
+    /// without a capacity become NaN.
+    pub fn normalize(&self, gen: &[f64; 14]) -> [f64; 14] {
+        let mut factors = [f64::NAN; 14];
+        for ((factor, val), cap) in factors.iter_mut().zip(gen).zip(&self.capacity_mw) {
+            if let Some(cap) = cap {
+                *factor = val / cap;
+            }
+        }
+        factors
+    }
+}
+
+/// California's classification of each water year (October through
+/// September) by runoff, which drives how much hydro is available.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WaterYearType {
+    Wet,
+    AboveNormal,
+    BelowNormal,
+    Dry,
+    Critical,
+}
+
+impl WaterYearType {
+    pub fn name(self) -> &'static str {
+        match self {
+            WaterYearType::Wet => "Wet",
+            WaterYearType::AboveNormal => "Above normal",
+            WaterYearType::BelowNormal => "Below normal",
+            WaterYearType::Dry => "Dry",
+            WaterYearType::Critical => "Critical",
+        }
+    }
+}
+
+/// Water year types loaded from a TOML table of `year = "type"` entries,
+/// where the year is the one the water year ends in:
+///
+/// ```toml
+/// 2023 = "wet"
+/// 2024 = "above-normal"
+/// ```
+#[derive(Debug)]
+pub struct WaterYears {
+    pub types: HashMap<i32, WaterYearType>,
+}
+
+impl WaterYears {
+    pub fn load(path: &Path) -> error::Result<Self> {
+        let entries: HashMap<String, WaterYearType> = toml::from_str(&fs::read_to_string(path)?)?;
+        let mut types = HashMap::new();
+        for (year, kind) in entries {
+            let year = year
+                .parse()
+                .map_err(|_| err!(Invalid, "{year:?} in {path:?} is not a year"))?;
+            types.insert(year, kind);
+        }
+        Ok(Self { types })
+    }
+
+    /// The water year `date` falls in, which starts on October 1st of the
+    /// previous calendar year.
+    pub fn water_year(date: NaiveDate) -> i32 {
+        if date.month() >= 10 {
+            date.year() + 1
+        } else {
+            date.year()
+        }
+    }
+
+    /// e.g. "WY2024 (Above normal)", or just "WY2024" when it's unclassified.
+    pub fn label(&self, date: NaiveDate) -> String {
+        let year = Self::water_year(date);
+        match self.types.get(&year) {
+            Some(kind) => format!("WY{year} ({})", kind.name()),
+            None => format!("WY{year}"),
+        }
+    }
+}
@@ -1,74 +1,787 @@
 //! ### Convert
-//! Tools for converting raw CAISO csv datasets into
-//! more digestible csvs that compute functions operate
-//! against.
+//! Tools for converting raw CAISO csv datasets (or the
+//! xlsx workbooks EIA also publishes) into more digestible
+//! csvs that compute functions operate against.
 
-use anyhow::bail;
-use chrono::{NaiveDateTime, Timelike};
-use csv::StringRecord;
+use crate::ancillary::{StorageRevenue, ValueStack};
+use crate::chart::PlotPoint;
+use crate::compute::{
+    AreaPrices, BaInterchange, Complementarity, DailySpread, DatasetStats, DirectionalHour,
+    EventDeviation, Flow, GroupSummary, MarginBin, MonthPeak, NetLoadPeaks, PeakTiming,
+    PriceComponents, PriceExceedance, PriceNode, PriceSpike, Profile, ScenarioSlot,
+    SourcePriceCorrelation, TotalMismatch,
+};
+use crate::error::{self, bail, err, EnergyAnalysisError, Locate};
+use crate::forecast::ForecastError;
+use crate::frame::Frame;
+use crate::gas::SparkSpread;
+use crate::nodal::{NodeCongestion, NodeProfile};
+use crate::output::{self, WriteOptions};
+use crate::scenario::{ChargingPlan, StorageSweepRow};
+use crate::stats::MeanDifference;
+use crate::tariff::TariffCost;
+use crate::weather::WeatherDay;
+#[cfg(feature = "native")]
+use calamine::{Data, Reader};
+#[cfg(feature = "native")]
+use chrono::NaiveTime;
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+use csv::{ByteRecord, Position, StringRecord};
 use plotters::style::{full_palette, RGBColor};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::array;
 use std::fmt::Write;
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
 use std::path::Path;
 
-#[derive(Serialize, Deserialize)]
+/// The format of every timestamp in the caiso datasets.
+pub const TIMESTAMP_FMT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// A row of one of the parsed csvs, read back with `read_rows`.
+pub trait CsvRow: DeserializeOwned {
+    /// Columns holding `TIMESTAMP_FMT` timestamps, which `read_rows` checks
+    /// as it goes so a bad one is reported where it's read.
+    const TIMESTAMPS: &'static [&'static str] = &[];
+}
+
+/// Reads a parsed csv row by row. A row that doesn't deserialize, or whose
+/// timestamps don't parse, fails with a `Parse` error giving its line and,
+/// where it's down to one field, the column and its value.
+pub fn read_rows<T: CsvRow>(path: &Path) -> error::Result<impl Iterator<Item = error::Result<T>>> {
+    read_rows_from(path, std::fs::File::open(path)?)
+}
+
+/// `read_rows` over any reader, such as an upload held in memory. `path`
+/// only names it in errors.
+pub fn read_rows_from<T: CsvRow>(
+    path: &Path,
+    reader: impl Read,
+) -> error::Result<impl Iterator<Item = error::Result<T>>> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers = reader.headers()?.clone();
+    let timestamps: Vec<usize> = T::TIMESTAMPS
+        .iter()
+        .filter_map(|col| headers.iter().position(|header| header == *col))
+        .collect();
+    let path = path.to_path_buf();
+    Ok(reader.into_records().map(move |record| {
+        let record = record.map_err(|e| {
+            let line = e.position().map_or(0, Position::line);
+            EnergyAnalysisError::parse(&path, line, None, None, e)
+        })?;
+        let line = record.position().map_or(0, Position::line);
+        for &idx in &timestamps {
+            let value = record.get(idx).unwrap_or_default();
+            parse_timestamp(value).locate(&path, line, &headers[idx], value)?;
+        }
+        record
+            .deserialize(Some(&headers))
+            .map_err(|e| match e.kind() {
+                csv::ErrorKind::Deserialize { err, .. } => {
+                    let idx = err.field().map(|idx| idx as usize);
+                    EnergyAnalysisError::parse(
+                        &path,
+                        line,
+                        idx.and_then(|idx| headers.get(idx)),
+                        idx.and_then(|idx| record.get(idx)),
+                        err.kind(),
+                    )
+                }
+                _ => EnergyAnalysisError::parse(&path, line, None, None, e),
+            })
+    }))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct EnergyPriceCsvRow {
     pub timestamp: String,
     pub hour: u32,
     pub minute: u32,
     // locational marginal price
     pub lmp_avg: f64,
+    // Each area's own LMP, then its congestion and loss components of its LMP. Csvs parsed
+    // before these were kept, and sources that don't report them, leave
+    // them NaN.
+    #[serde(default = "missing_component")]
+    pub np15_lmp: f64,
+    #[serde(default = "missing_component")]
+    pub sp15_lmp: f64,
+    #[serde(default = "missing_component")]
+    pub zp26_lmp: f64,
+    #[serde(default = "missing_component")]
+    pub np15_congestion: f64,
+    #[serde(default = "missing_component")]
+    pub sp15_congestion: f64,
+    #[serde(default = "missing_component")]
+    pub zp26_congestion: f64,
+    #[serde(default = "missing_component")]
+    pub np15_loss: f64,
+    #[serde(default = "missing_component")]
+    pub sp15_loss: f64,
+    #[serde(default = "missing_component")]
+    pub zp26_loss: f64,
 }
 
-pub fn convert_energy_price_csv(inputs: &[impl AsRef<Path>], output: &Path) -> anyhow::Result<()> {
-    let mut out_csv = csv::Writer::from_path(output)?;
-    for input in inputs {
-        let mut reader = csv::ReaderBuilder::new().flexible(true).from_path(input)?;
+fn missing_component() -> f64 {
+    f64::NAN
+}
+
+impl CsvRow for EnergyPriceCsvRow {
+    const TIMESTAMPS: &'static [&'static str] = &["timestamp"];
+}
+
+/// Parses a `TIMESTAMP_FMT` timestamp. Every caiso file uses the same
+/// fixed-width layout, so digits are read by position, which is much faster
+/// than chrono's format parser. Anything else falls back to chrono.
+pub fn parse_timestamp(timestamp: &str) -> error::Result<NaiveDateTime> {
+    let bytes = timestamp.as_bytes();
+    let fixed_width = || {
+        if bytes.len() != 19
+            || bytes[4] != b'-'
+            || bytes[7] != b'-'
+            || bytes[10] != b' '
+            || bytes[13] != b':'
+            || bytes[16] != b':'
+        {
+            return None;
+        }
+        let num = |range: Range<usize>| {
+            bytes[range].iter().try_fold(0, |acc: u32, &digit| {
+                digit
+                    .is_ascii_digit()
+                    .then(|| acc * 10 + u32::from(digit - b'0'))
+            })
+        };
+        NaiveDate::from_ymd_opt(num(0..4)? as i32, num(5..7)?, num(8..10)?)?.and_hms_opt(
+            num(11..13)?,
+            num(14..16)?,
+            num(17..19)?,
+        )
+    };
+    match fixed_width() {
+        Some(parsed) => Ok(parsed),
+        None => Ok(NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FMT)?),
+    }
+}
+
+/// What `convert_energy_gen_csv` does with rows whose Total is more than
+/// `TOTAL_TOLERANCE_MW` off from the sum of their sources. Every share-of-
+/// total analysis divides by that column.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TotalCheck {
+    /// Counts them and keeps the reported Total.
+    #[default]
+    Report,
+    /// Counts them and replaces Total with the sum of sources.
+    Recompute,
+}
+
+/// EIA rounds each source to the megawatt, so a correct Total can drift
+/// from the sum by a little.
+pub const TOTAL_TOLERANCE_MW: f64 = 1.;
+
+/// What the parse converters do with a raw row that won't parse. Either
+/// way a file's rows are read, the ones left out are counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OnError {
+    /// Leaves the row out, and reports the first one left out of each file
+    /// once it's read.
+    #[default]
+    Skip,
+    /// Stops the conversion at the row.
+    Abort,
+    /// Leaves the row out like `Skip`, printing each one as it's met.
+    Log,
+}
+
+/// The raw rows a parse converter read, and the ones it left out.
+#[derive(Debug, Default)]
+pub struct ConvertSummary {
+    pub rows: usize,
+    pub skipped: usize,
+    pub first_skipped: Option<EnergyAnalysisError>,
+}
+
+impl ConvertSummary {
+    /// How many rows made it into the output.
+    pub fn written(&self) -> usize {
+        self.rows - self.skipped
+    }
+}
+
+/// How a raw export separates its fields and writes its numbers. EIA's own
+/// files use the default, but re-exported datasets sometimes come out of
+/// spreadsheets set to other locales.
+#[derive(Clone, Copy, Debug)]
+pub struct CsvFormat {
+    pub delimiter: u8,
+    /// Numbers are written `1.234,5` instead of `1,234.5`.
+    pub decimal_comma: bool,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            decimal_comma: false,
+        }
+    }
+}
+
+impl CsvFormat {
+    fn reader(&self, input: &Path) -> error::Result<csv::Reader<std::fs::File>> {
+        Ok(csv::ReaderBuilder::new()
+            .flexible(true)
+            .has_headers(false)
+            .delimiter(self.delimiter)
+            .from_path(input)?)
+    }
+
+    /// Parses a number, tolerating surrounding whitespace and quotes and
+    /// thousands separators. Plain numbers skip the cleanup entirely.
+    pub fn parse_number(&self, field: &str) -> error::Result<f64> {
+        let field = field.trim();
+        if !self.decimal_comma {
+            if let Some(num) = parse_plain_decimal(field.as_bytes()) {
+                return Ok(num);
+            }
+            if let Ok(num) = field.parse() {
+                return Ok(num);
+            }
+        }
+        let (thousands, decimal) = if self.decimal_comma {
+            ('.', ',')
+        } else {
+            (',', '.')
+        };
+        let cleaned: String = field
+            .trim_matches(|c| c == '"' || c == '\'')
+            .chars()
+            .filter(|&c| c != thousands && c != ' ' && c != '\u{a0}' && c != '\'')
+            .map(|c| if c == decimal { '.' } else { c })
+            .collect();
+        Ok(cleaned.parse()?)
+    }
+}
+
+/// Reads a plain decimal like `-12.345` of at most fifteen digits, which
+/// is nearly every number in the raw files, without the general parser.
+/// Its digits make an integer a float holds exactly and its decimals a
+/// power of ten one does too, so dividing the two rounds just as parsing
+/// the text would.
+fn parse_plain_decimal(text: &[u8]) -> Option<f64> {
+    const POWERS: [f64; 16] = [
+        1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15,
+    ];
+    let (negative, digits) = match text.split_first() {
+        Some((b'-', rest)) => (true, rest),
+        _ => (false, text),
+    };
+    let (mut mantissa, mut count, mut decimals, mut dot) = (0u64, 0, 0, false);
+    for &byte in digits {
+        match byte {
+            b'0'..=b'9' => {
+                mantissa = mantissa * 10 + u64::from(byte - b'0');
+                count += 1;
+                decimals += usize::from(dot);
+            }
+            b'.' if !dot => dot = true,
+            _ => return None,
+        }
+    }
+    if count == 0 || count > 15 || digits.first() == Some(&b'.') {
+        return None;
+    }
+    let value = mantissa as f64 / POWERS[decimals];
+    Some(if negative { -value } else { value })
+}
 
-        for line in reader.records().skip(3) {
-            let line = line?;
-            if line.len() != 17 {
-                bail!("Unexpected csv row format: {line:?}");
+/// A known layout of a raw EIA export, listing the exact header of every
+/// column a converter reads. EIA changes header text and adds columns
+/// between releases, so each layout gets its own entry and files are
+/// matched against them by header rather than by position.
+pub struct Schema<const N: usize> {
+    pub name: &'static str,
+    pub columns: [&'static str; N],
+}
+
+impl<const N: usize> Schema<N> {
+    /// Returns the first schema whose columns all appear in `header`, along
+    /// with the position of each of its columns in the file.
+    pub(crate) fn detect<'s>(
+        schemas: &'s [Schema<N>],
+        header: &StringRecord,
+        file: &Path,
+    ) -> error::Result<(&'s Schema<N>, [usize; N])> {
+        for schema in schemas {
+            if let Some(indices) = schema.indices(header) {
+                return Ok((schema, indices));
             }
+        }
 
-            let lmp_sum = line
+        let unknown: Vec<&str> = header
+            .iter()
+            .filter(|col| !schemas.iter().any(|schema| schema.columns.contains(col)))
+            .collect();
+        let mut reason = format!("Unrecognized EIA file layout. Unknown headers: {unknown:?}");
+        for schema in schemas {
+            let missing: Vec<&str> = schema
+                .columns
                 .iter()
-                .skip(5)
-                .take(3)
-                .map(|entry| entry.parse::<f64>())
-                .try_fold(0., |acc, el| el.map(|num| num + acc))?;
-            let timestamp_string = line[1].to_string();
-            let timestamp = NaiveDateTime::parse_from_str(&timestamp_string, "%Y-%m-%d %H:%M:%S")?;
-            out_csv.serialize(&EnergyPriceCsvRow {
-                timestamp: timestamp_string,
-                hour: timestamp.hour(),
-                minute: timestamp.minute(),
-                // lmp_sum adds the three different zones. This averages them.
-                lmp_avg: lmp_sum / 3.,
-            })?;
+                .copied()
+                .filter(|col| !header.iter().any(|found| found == *col))
+                .collect();
+            write!(reason, "\n  '{}' is missing {missing:?}", schema.name)?;
         }
+        Err(EnergyAnalysisError::SchemaMismatch {
+            file: file.to_path_buf(),
+            reason,
+        })
+    }
+
+    fn indices(&self, header: &StringRecord) -> Option<[usize; N]> {
+        let mut indices = [0; N];
+        for (idx, col) in indices.iter_mut().zip(self.columns) {
+            *idx = header.iter().position(|found| found == col)?;
+        }
+        Some(indices)
+    }
+}
+
+/// Layouts of the zone and trading hub price exports. Columns are the
+/// interval start timestamp followed by the three LMPs, then their
+/// congestion and loss components, each in `EnergyPriceCsvRow::ZONES` order.
+/// Each trading hub is kept in the slot of the zone it's named after.
+/// Only the 2023Q4 through 2024Q3 exports have been checked against these
+/// headers. Exports from before 2023Q4, 2022's included, aren't known to
+/// match them, and one that doesn't gets its own entry with its exact
+/// header text.
+pub const PRICE_SCHEMAS: [Schema<10>; 2] = [
+    Schema {
+        name: "EIA CAISO zone LMP (2023Q4-2024Q3)",
+        columns: [
+            "Local Timestamp Pacific Time (Interval Beginning)",
+            "NP-15 LMP",
+            "SP-15 LMP",
+            "ZP-26 LMP",
+            "NP-15 (Congestion)",
+            "SP-15 (Congestion)",
+            "ZP-26 (Congestion)",
+            "NP-15 (Loss)",
+            "SP-15 (Loss)",
+            "ZP-26 (Loss)",
+        ],
+    },
+    Schema {
+        name: "EIA CAISO trading hub LMP (2023Q4-2024Q3)",
+        columns: [
+            "Local Timestamp Pacific Time (Interval Beginning)",
+            "TH_NP15_GEN-APND LMP",
+            "TH_SP15_GEN-APND LMP",
+            "TH_ZP26_GEN-APND LMP",
+            "TH_NP15_GEN-APND (Congestion)",
+            "TH_SP15_GEN-APND (Congestion)",
+            "TH_ZP26_GEN-APND (Congestion)",
+            "TH_NP15_GEN-APND (Loss)",
+            "TH_SP15_GEN-APND (Loss)",
+            "TH_ZP26_GEN-APND (Loss)",
+        ],
+    },
+];
+
+/// Layouts of the fuel mix export, newest first. Columns are in
+/// `EnergyGenCsvRow::HEADER_KEYWORDS` order. As with prices, only the
+/// 2023Q4 through 2024Q3 exports have been checked, and they share one
+/// layout.
+pub const GEN_SCHEMAS: [Schema<19>; 1] = [Schema {
+    name: "EIA CAISO fuel mix (2023Q4-2024Q3)",
+    columns: [
+        "UTC Timestamp (Interval Ending)",
+        "Local Timestamp Pacific Time (Interval Beginning)",
+        "Local Timestamp Pacific Time (Interval Ending)",
+        "Local Date",
+        "Hour Number",
+        "Total Generation (MW)",
+        "Batteries Generation (MW)",
+        "Biogas Generation (MW)",
+        "Biomass Generation (MW)",
+        "Coal Generation (MW)",
+        "Geothermal Generation (MW)",
+        "Imports Generation (MW)",
+        "Large Hydro Generation (MW)",
+        "Natural Gas Generation (MW)",
+        "Nuclear Generation (MW)",
+        "Other Generation (MW)",
+        "Small Hydro Generation (MW)",
+        "Solar Generation (MW)",
+        "Wind Generation (MW)",
+    ],
+}];
+
+/// Reads the header row that follows the three-line EIA preamble.
+fn read_eia_header(reader: &mut csv::Reader<std::fs::File>) -> error::Result<StringRecord> {
+    reader
+        .records()
+        .nth(3)
+        .ok_or_else(|| err!(NoData, "Empty CSV"))?
+        .map_err(EnergyAnalysisError::from)
+}
+
+/// A raw EIA export read row by row, either a csv or the sheet of an xlsx
+/// workbook that holds the data.
+enum RawReader {
+    Csv(csv::Reader<std::fs::File>),
+    #[cfg(feature = "native")]
+    Xlsx(std::vec::IntoIter<ByteRecord>),
+}
+
+impl RawReader {
+    /// How many rows of a sheet are searched for its header.
+    #[cfg(feature = "native")]
+    const XLSX_HEADER_ROWS: usize = 10;
+
+    /// Opens `input` and reads through its header row. Workbooks are
+    /// searched for the first sheet with a header matching one of `schemas`.
+    fn open<const N: usize>(
+        input: &Path,
+        format: CsvFormat,
+        schemas: &[Schema<N>],
+    ) -> error::Result<(Self, StringRecord)> {
+        let is_xlsx = input
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("xlsx"));
+        if !is_xlsx {
+            let mut reader = format.reader(input)?;
+            let header = read_eia_header(&mut reader)?;
+            return Ok((Self::Csv(reader), header));
+        }
+        Self::open_workbook(input, schemas)
+    }
+
+    /// Reads the first sheet of the workbook `input` with a header matching
+    /// one of `schemas`.
+    #[cfg(feature = "native")]
+    fn open_workbook<const N: usize>(
+        input: &Path,
+        schemas: &[Schema<N>],
+    ) -> error::Result<(Self, StringRecord)> {
+        let mut workbook = calamine::open_workbook_auto(input)?;
+        let sheets = workbook.sheet_names();
+        for sheet in &sheets {
+            let range = workbook.worksheet_range(sheet)?;
+            // Records get the sheet's row numbers, so errors point at them.
+            let first_row = range.start().map_or(0, |(row, _)| u64::from(row));
+            let mut rows = range.rows().zip(first_row + 1..);
+            let found = rows
+                .by_ref()
+                .take(Self::XLSX_HEADER_ROWS)
+                .map(|(row, _)| {
+                    row.iter()
+                        .map(|cell| cell.to_string())
+                        .collect::<StringRecord>()
+                })
+                .find(|header| {
+                    schemas
+                        .iter()
+                        .any(|schema| schema.indices(header).is_some())
+                });
+            let Some(header) = found else {
+                continue;
+            };
+            println!("{input:?} has its data in sheet {sheet:?}");
+            let records: Vec<ByteRecord> = rows
+                .map(|(row, line)| {
+                    let mut record: ByteRecord = row
+                        .iter()
+                        .zip(header.iter())
+                        .map(|(cell, column)| xlsx_field(cell, column))
+                        .collect();
+                    let mut position = Position::new();
+                    position.set_line(line);
+                    record.set_position(Some(position));
+                    record
+                })
+                .collect();
+            return Ok((Self::Xlsx(records.into_iter()), header));
+        }
+        let names: Vec<_> = schemas.iter().map(|schema| schema.name).collect();
+        Err(EnergyAnalysisError::SchemaMismatch {
+            file: input.to_path_buf(),
+            reason: format!("No sheet has a header matching {names:?}. Sheets: {sheets:?}"),
+        })
+    }
+
+    #[cfg(not(feature = "native"))]
+    fn open_workbook<const N: usize>(
+        input: &Path,
+        _schemas: &[Schema<N>],
+    ) -> error::Result<(Self, StringRecord)> {
+        bail!(
+            Invalid,
+            "{input:?} is a workbook, and reading workbooks needs the native feature"
+        )
+    }
+
+    /// Opens `input` as one of `schemas`, returning the reader positioned
+    /// at its first row along with the matching schema and the position of
+    /// each of its columns.
+    fn open_as<'s, const N: usize>(
+        input: &Path,
+        format: CsvFormat,
+        schemas: &'s [Schema<N>],
+    ) -> error::Result<(Self, StringRecord, &'s Schema<N>, [usize; N])> {
+        let (reader, header) = Self::open(input, format, schemas)?;
+        let (schema, columns) = Schema::detect(schemas, &header, input)?;
+        Ok((reader, header, schema, columns))
+    }
+
+    /// How many rows are left to read, when that's known without reading
+    /// them.
+    fn rows_left(&self) -> Option<usize> {
+        match self {
+            Self::Csv(_) => None,
+            #[cfg(feature = "native")]
+            Self::Xlsx(records) => Some(records.len()),
+        }
+    }
+
+    /// The last row of the file. Csvs are read from the end rather than
+    /// through.
+    fn last_record(&self, input: &Path, format: CsvFormat) -> error::Result<Option<ByteRecord>> {
+        const TAIL: u64 = 1 << 16;
+        match self {
+            Self::Csv(_) => {
+                let mut file = std::fs::File::open(input)?;
+                let len = file.metadata()?.len();
+                file.seek(SeekFrom::Start(len.saturating_sub(TAIL)))?;
+                let mut tail = Vec::new();
+                file.read_to_end(&mut tail)?;
+                let Some(line) = tail
+                    .split(|&byte| byte == b'\n')
+                    .rfind(|line| !line.trim_ascii().is_empty())
+                else {
+                    return Ok(None);
+                };
+                let mut record = ByteRecord::new();
+                let found = csv::ReaderBuilder::new()
+                    .has_headers(false)
+                    .flexible(true)
+                    .delimiter(format.delimiter)
+                    .from_reader(line)
+                    .read_byte_record(&mut record)?;
+                Ok(found.then_some(record))
+            }
+            #[cfg(feature = "native")]
+            Self::Xlsx(records) => Ok(records.as_slice().last().cloned()),
+        }
+    }
+
+    fn read(&mut self, record: &mut ByteRecord) -> error::Result<bool> {
+        match self {
+            Self::Csv(reader) => Ok(reader.read_byte_record(record)?),
+            #[cfg(feature = "native")]
+            Self::Xlsx(records) => match records.next() {
+                Some(next) => {
+                    *record = next;
+                    Ok(true)
+                }
+                None => Ok(false),
+            },
+        }
+    }
+
+    /// Workbook cells hold numbers rather than text, so they're always
+    /// written back out in the default format.
+    fn number_format(&self, format: CsvFormat) -> CsvFormat {
+        match self {
+            Self::Csv(_) => format,
+            #[cfg(feature = "native")]
+            Self::Xlsx(_) => CsvFormat::default(),
+        }
+    }
+}
+
+/// Writes a workbook cell the way the csv export writes the same field.
+/// Excel stores dates and timestamps alike as date-times, so the column
+/// name decides which one a midnight value was.
+#[cfg(feature = "native")]
+fn xlsx_field(cell: &Data, column: &str) -> String {
+    match cell {
+        Data::DateTime(time) => match time.as_datetime() {
+            Some(time) if !column.contains("Timestamp") && time.time() == NaiveTime::MIN => {
+                time.format("%Y-%m-%d").to_string()
+            }
+            Some(time) => time.format(TIMESTAMP_FMT).to_string(),
+            None => time.to_string(),
+        },
+        Data::DateTimeIso(time) => time.replacen('T', " ", 1),
+        cell => cell.to_string(),
+    }
+}
+
+/// The line a raw record was read from, for error messages.
+fn record_line(record: &ByteRecord) -> u64 {
+    record.position().map_or(0, Position::line)
+}
+
+/// Reads every record of `input` into `convert`, whose inner error fails
+/// just that row and whose outer error fails the conversion. Failed rows
+/// are handled as `on_error` says and tallied into `summary`.
+fn convert_rows(
+    reader: &mut RawReader,
+    input: &Path,
+    on_error: OnError,
+    summary: &mut ConvertSummary,
+    mut convert: impl FnMut(&ByteRecord) -> error::Result<error::Result<()>>,
+) -> error::Result<()> {
+    let mut record = ByteRecord::new();
+    let mut skipped = 0;
+    let mut first_skipped = None;
+    while reader.read(&mut record)? {
+        summary.rows += 1;
+        let Err(e) = convert(&record)? else {
+            continue;
+        };
+        match on_error {
+            OnError::Abort => return Err(e),
+            OnError::Log => println!("Skipped {e}"),
+            OnError::Skip => {}
+        }
+        skipped += 1;
+        first_skipped.get_or_insert(e);
+    }
+    summary.skipped += skipped;
+    if let Some(e) = first_skipped {
+        println!("{input:?} had {skipped} rows that didn't parse");
+        if on_error == OnError::Skip {
+            println!("  The first: {e}");
+        }
+        summary.first_skipped.get_or_insert(e);
     }
     Ok(())
 }
 
-pub fn write_energy_price_averages(output: &Path, prices: &[f64]) -> anyhow::Result<()> {
-    let mut csv = csv::Writer::from_path(output)?;
+pub fn convert_energy_price_csv(
+    inputs: &[impl AsRef<Path>],
+    output: &Path,
+    format: CsvFormat,
+    on_error: OnError,
+    options: WriteOptions,
+) -> error::Result<ConvertSummary> {
+    let mut out_csv = options.csv(output)?;
+    let mut summary = ConvertSummary::default();
+    let mut first: Option<&Schema<10>> = None;
+    // As with gen, rows are written field by field rather than serialized.
+    let (mut out_record, mut number) = (ByteRecord::new(), String::new());
+    let mut wrote_header = false;
+    for input in inputs {
+        let (mut reader, _, schema, columns) =
+            RawReader::open_as(input.as_ref(), format, &PRICE_SCHEMAS)?;
+        let format = reader.number_format(format);
+        // Numbers read with a decimal comma don't read back as their text.
+        let copy_numbers = !format.decimal_comma;
+        println!("{:?} matches {}", input.as_ref(), schema.name);
+        // Zone and hub prices share columns, so one csv can't hold both.
+        match first {
+            Some(first) if first.name != schema.name => bail!(
+                Invalid,
+                "{:?} matches {}, but the files before it match {}. Parse them separately",
+                input.as_ref(),
+                schema.name,
+                first.name
+            ),
+            _ => first = Some(schema),
+        }
 
-    let mut buf = String::new();
-    csv.write_record(["prices".as_bytes()])?;
+        // One row is reused for the whole file to avoid per-row allocations.
+        let mut row = EnergyPriceCsvRow::new(String::new(), 0, 0, 0.);
+        let at = Located {
+            file: input.as_ref(),
+            schema,
+            columns: &columns,
+        };
+        convert_rows(
+            &mut reader,
+            input.as_ref(),
+            on_error,
+            &mut summary,
+            |record| {
+                if let Err(e) = row.fill_from_record(record, at, format) {
+                    return Ok(Err(e));
+                }
+                if !wrote_header {
+                    out_csv.write_record(EnergyPriceCsvRow::FIELDS)?;
+                    wrote_header = true;
+                }
+                Ok(Ok(row.write(
+                    &mut out_csv,
+                    copy_numbers.then_some(record),
+                    at,
+                    &mut out_record,
+                    &mut number,
+                )?))
+            },
+        )?;
+    }
+    Ok(summary)
+}
 
-    for price in prices.iter() {
-        write!(&mut buf, "{price}")?;
-        csv.write_record([&buf])?;
-        buf.clear();
+/// Writes rows in the form the parse commands write them.
+pub fn write_parsed<T: CsvRow + Serialize>(
+    output: &Path,
+    rows: &[T],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    for row in rows {
+        csv.serialize(row)?;
     }
     Ok(())
 }
 
-// repr(c) because field order matters a lot for csv parsing
-#[repr(C)]
-#[derive(Serialize, Deserialize, Debug, Default)]
+/// Writes a frame as a `timestamp` column followed by each of its columns.
+/// NaN values are left empty.
+pub fn write_frame(output: &Path, frame: &Frame, options: WriteOptions) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    let mut header = vec!["timestamp".to_string()];
+    header.extend(frame.names().iter().cloned());
+    csv.write_record(&header)?;
+    let columns = frame
+        .names()
+        .iter()
+        .map(|name| frame.column(name))
+        .collect::<error::Result<Vec<_>>>()?;
+    for (row, time) in frame.times().iter().enumerate() {
+        let mut record = vec![time.format(TIMESTAMP_FMT).to_string()];
+        record.extend(columns.iter().map(|column| match column[row] {
+            value if value.is_nan() => String::new(),
+            value => options.float(value),
+        }));
+        csv.write_record(&record)?;
+    }
+    Ok(())
+}
+
+/// Writes each slot's average price and how many intervals it averages.
+pub fn write_energy_price_averages(
+    output: &Path,
+    prices: &[f64],
+    samples: &[usize],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record(["prices", "samples"])?;
+    for (price, count) in prices.iter().zip(samples) {
+        csv.write_record([options.float(*price), count.to_string()])?;
+    }
+    Ok(())
+}
+
+// Raw EIA files are mapped onto this by header name (see `GEN_SCHEMAS`),
+// while the intermediate csv is (de)serialized by field name.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct EnergyGenCsvRow {
     pub utc_timestamp: String,
     pub local_timestamp_start: String,
@@ -95,43 +808,560 @@ pub struct EnergyGenCsvRow {
     pub minute: u32,
 }
 
-pub fn convert_energy_gen_csv(inputs: &[impl AsRef<Path>], output: &Path) -> anyhow::Result<()> {
-    let mut out_csv = csv::Writer::from_path(output)?;
+impl CsvRow for EnergyGenCsvRow {
+    const TIMESTAMPS: &'static [&'static str] = &[
+        "utc_timestamp",
+        "local_timestamp_start",
+        "local_timestamp_end",
+    ];
+}
+
+/// One hour of flow between CAISO and a neighboring balancing authority, as
+/// written by fetch-eia.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct InterchangeCsvRow {
+    /// The local start of the hour.
+    pub local_timestamp: String,
+    pub ba: String,
+    /// MW flowing into CAISO. Negative when exporting.
+    pub imports_mw: f64,
+}
+
+impl CsvRow for InterchangeCsvRow {
+    const TIMESTAMPS: &'static [&'static str] = &["local_timestamp"];
+}
+
+/// One hour of CAISO's demand, as written by fetch-eia.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DemandCsvRow {
+    /// The local start of the hour.
+    pub local_timestamp: String,
+    pub demand_mw: f64,
+}
+
+impl CsvRow for DemandCsvRow {
+    const TIMESTAMPS: &'static [&'static str] = &["local_timestamp"];
+}
+
+pub fn convert_energy_gen_csv(
+    inputs: &[impl AsRef<Path>],
+    output: &Path,
+    format: CsvFormat,
+    totals: TotalCheck,
+    on_error: OnError,
+    options: WriteOptions,
+) -> error::Result<ConvertSummary> {
+    let mut out_csv = options.csv(output)?;
+    let mut summary = ConvertSummary::default();
+    // Rows are written field by field rather than serialized, which is
+    // most of what converting a large export costs.
+    let (mut out_record, mut number) = (ByteRecord::new(), String::new());
+    let mut wrote_header = false;
     for input in inputs {
-        let mut reader = csv::ReaderBuilder::new()
-            .flexible(true)
-            .has_headers(false)
-            .from_path(input)?;
-        EnergyGenCsvRow::validate(
-            &reader
-                .records()
-                .nth(3)
-                .ok_or_else(|| anyhow::anyhow!("Empty CSV"))??,
+        let (mut reader, _, schema, columns) =
+            RawReader::open_as(input.as_ref(), format, &GEN_SCHEMAS)?;
+        let format = reader.number_format(format);
+        let copy_numbers = !format.decimal_comma;
+        println!("{:?} matches {}", input.as_ref(), schema.name);
+
+        let mut mismatched_totals = 0;
+        let mut line = EnergyGenCsvRow::default();
+        let at = Located {
+            file: input.as_ref(),
+            schema,
+            columns: &columns,
+        };
+        convert_rows(
+            &mut reader,
+            input.as_ref(),
+            on_error,
+            &mut summary,
+            |record| {
+                let filled = line.fill_from_record(record, at, format);
+                if let Err(e) = filled.and_then(|()| line.set_time(record, at)) {
+                    return Ok(Err(e));
+                }
+                let mut recomputed = false;
+                if line.total_discrepancy().abs() > TOTAL_TOLERANCE_MW {
+                    mismatched_totals += 1;
+                    if totals == TotalCheck::Recompute {
+                        line.total = line.sum_of_sources();
+                        recomputed = true;
+                    }
+                }
+                if !wrote_header {
+                    out_csv.write_record(EnergyGenCsvRow::FIELDS)?;
+                    wrote_header = true;
+                }
+                Ok(Ok(line.write(
+                    &mut out_csv,
+                    copy_numbers.then_some(record),
+                    at,
+                    recomputed,
+                    &mut out_record,
+                    &mut number,
+                )?))
+            },
         )?;
+        if mismatched_totals > 0 {
+            let action = match totals {
+                TotalCheck::Report => "kept",
+                TotalCheck::Recompute => "recomputed",
+            };
+            println!(
+                "{:?} had {mismatched_totals} rows whose Total doesn't match the sum of sources ({action})",
+                input.as_ref()
+            );
+        }
+    }
 
-        let mut failed_lines = 0;
-        for line in reader.deserialize::<EnergyGenCsvRow>() {
-            let Ok(mut line) = line else {
-                // println!("Skipping failed line: {line:?}");
-                failed_lines += 1;
-                continue;
+    Ok(summary)
+}
+
+/// Which raw EIA export a file is, and so which kind of parsed csv it
+/// becomes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RawKind {
+    Price,
+    Gen,
+}
+
+/// What `inspect_raw` learned about a raw export from its header, its
+/// first rows and, for csvs, its last line.
+#[derive(Debug)]
+pub struct Inspection {
+    pub kind: RawKind,
+    pub schema: &'static str,
+    /// The columns the parse step reads, in `Schema` order.
+    pub columns: Vec<&'static str>,
+    /// Header columns the parse step doesn't read.
+    pub ignored: Vec<String>,
+    pub sampled: usize,
+    /// Sampled rows that don't parse, and the first one's error. The parse
+    /// step skips them, or stops at the first under `OnError::Abort`.
+    pub skipped: usize,
+    pub first_skipped: Option<EnergyAnalysisError>,
+    /// The error the parse step would stop at whatever its `OnError`, if
+    /// reading the sample hit one.
+    pub fatal: Option<EnergyAnalysisError>,
+    /// Sampled gen rows whose Total is off from the sum of their sources.
+    pub mismatched_totals: usize,
+    /// Exact for workbooks and csvs that were read to the end. Otherwise
+    /// estimated from the bytes per sampled row.
+    pub rows: usize,
+    pub rows_exact: bool,
+    /// The timestamps of the first and last rows.
+    pub first: Option<NaiveDateTime>,
+    pub last: Option<NaiveDateTime>,
+}
+
+/// Reads the header and up to `sample` rows of a raw price or gen export
+/// and reports what parsing it would do, without writing anything. Rows
+/// past the sample are counted from the file's size, and the last one is
+/// read from the end of the file.
+pub fn inspect_raw(input: &Path, format: CsvFormat, sample: usize) -> error::Result<Inspection> {
+    let price = match RawReader::open_as(input, format, &PRICE_SCHEMAS) {
+        Ok((reader, header, schema, columns)) => {
+            let at = Located {
+                file: input,
+                schema,
+                columns: &columns,
             };
+            let number_format = reader.number_format(format);
+            let mut row = EnergyPriceCsvRow::new(String::new(), 0, 0, 0.);
+            let sampled = Inspection::sample(reader, at, 0, format, sample, |record| {
+                let filled = row.fill_from_record(record, at, number_format);
+                Ok(filled.and_then(|()| at.timestamp(record, 0)))
+            })?;
+            return Ok(Inspection::new(RawKind::Price, &header, at, sampled));
+        }
+        Err(EnergyAnalysisError::SchemaMismatch { reason, .. }) => reason,
+        Err(e) => return Err(e),
+    };
+    match RawReader::open_as(input, format, &GEN_SCHEMAS) {
+        Ok((reader, header, schema, columns)) => {
+            let at = Located {
+                file: input,
+                schema,
+                columns: &columns,
+            };
+            let number_format = reader.number_format(format);
+            let mut row = EnergyGenCsvRow::default();
+            let mut mismatched_totals = 0;
+            let sampled = Inspection::sample(reader, at, 1, format, sample, |record| {
+                if let Err(e) = row.fill_from_record(record, at, number_format) {
+                    return Ok(Err(e));
+                }
+                let time = match at.timestamp(record, 1) {
+                    Ok(time) => time,
+                    Err(e) => return Ok(Err(e)),
+                };
+                if row.total_discrepancy().abs() > TOTAL_TOLERANCE_MW {
+                    mismatched_totals += 1;
+                }
+                Ok(Ok(time))
+            })?;
+            Ok(Inspection {
+                mismatched_totals,
+                ..Inspection::new(RawKind::Gen, &header, at, sampled)
+            })
+        }
+        Err(EnergyAnalysisError::SchemaMismatch { reason, .. }) => {
+            Err(EnergyAnalysisError::SchemaMismatch {
+                file: input.to_path_buf(),
+                reason: format!("Neither a price nor a gen export.\n{price}\n{reason}"),
+            })
+        }
+        Err(e) => Err(e),
+    }
+}
 
-            // Compute timestamp manually for consistency with other conversions.
-            let timestamp =
-                NaiveDateTime::parse_from_str(&line.local_timestamp_start, "%Y-%m-%d %H:%M:%S")?;
-            line.hour = timestamp.hour();
-            line.minute = timestamp.minute();
+/// What `Inspection::sample` read of a file's rows.
+#[derive(Default)]
+struct Sampled {
+    rows: usize,
+    skipped: usize,
+    first_skipped: Option<EnergyAnalysisError>,
+    fatal: Option<EnergyAnalysisError>,
+    total_rows: usize,
+    total_exact: bool,
+    first: Option<NaiveDateTime>,
+    last: Option<NaiveDateTime>,
+}
 
-            out_csv.serialize(line)?;
+impl Inspection {
+    fn new<const N: usize>(
+        kind: RawKind,
+        header: &StringRecord,
+        at: Located<N>,
+        sampled: Sampled,
+    ) -> Self {
+        Self {
+            kind,
+            schema: at.schema.name,
+            columns: at.schema.columns.to_vec(),
+            ignored: header
+                .iter()
+                .filter(|col| !col.is_empty() && !at.schema.columns.contains(col))
+                .map(str::to_string)
+                .collect(),
+            sampled: sampled.rows,
+            skipped: sampled.skipped,
+            first_skipped: sampled.first_skipped,
+            fatal: sampled.fatal,
+            mismatched_totals: 0,
+            rows: sampled.total_rows,
+            rows_exact: sampled.total_exact,
+            first: sampled.first,
+            last: sampled.last,
         }
-        println!("{:?} had {failed_lines} failed lines", input.as_ref());
     }
 
-    Ok(())
+    /// Reads up to `sample` rows with `read_row`, which returns the row's
+    /// timestamp, an inner error for a row the parse step would skip, or
+    /// an outer one for a row it would stop at. `time_col` is the schema
+    /// column of the timestamp.
+    fn sample<const N: usize>(
+        mut reader: RawReader,
+        at: Located<N>,
+        time_col: usize,
+        format: CsvFormat,
+        sample: usize,
+        mut read_row: impl FnMut(&ByteRecord) -> error::Result<error::Result<NaiveDateTime>>,
+    ) -> error::Result<Sampled> {
+        let mut sampled = Sampled::default();
+        let mut record = ByteRecord::new();
+        let mut first_byte = None;
+        // Every record read, which goes one past the sample to see whether
+        // the file does.
+        let mut read = 0;
+        let mut ended = false;
+        while sampled.fatal.is_none() {
+            if !reader.read(&mut record)? {
+                ended = true;
+                break;
+            }
+            read += 1;
+            first_byte = first_byte.or(record.position().map(Position::byte));
+            if sampled.rows == sample {
+                break;
+            }
+            sampled.rows += 1;
+            match read_row(&record) {
+                Ok(Ok(time)) => {
+                    sampled.first.get_or_insert(time);
+                    sampled.last = Some(time);
+                }
+                Ok(Err(e)) => {
+                    sampled.skipped += 1;
+                    sampled.first_skipped.get_or_insert(e);
+                }
+                Err(e) => sampled.fatal = Some(e),
+            }
+        }
+
+        (sampled.total_rows, sampled.total_exact) = match reader.rows_left() {
+            _ if ended => (read, true),
+            Some(left) => (read + left, true),
+            None => {
+                // Rows past the last one read are assumed to average the
+                // length of the ones before it.
+                let start = first_byte.unwrap_or_default();
+                let last = record.position().map_or(start, Position::byte);
+                let per_row = (last - start) as f64 / (read - 1).max(1) as f64;
+                let rest = std::fs::metadata(at.file)?.len().saturating_sub(last);
+                let rest = (rest as f64 / per_row.max(1.)).round() as usize;
+                (read - 1 + rest, false)
+            }
+        };
+        if !ended {
+            if let Some(last) = reader.last_record(at.file, format)? {
+                sampled.last = at.timestamp(&last, time_col).ok().or(sampled.last);
+            }
+        }
+        Ok(sampled)
+    }
+}
+
+/// Where a raw record's columns are, and the file it came from, so its
+/// fields can be read with errors that point back at them.
+#[derive(Clone, Copy)]
+struct Located<'a, const N: usize> {
+    file: &'a Path,
+    schema: &'a Schema<N>,
+    columns: &'a [usize; N],
+}
+
+impl<const N: usize> Located<'_, N> {
+    /// The field of `record` in the schema's `col`th column.
+    fn field<'r>(&self, record: &'r ByteRecord, col: usize) -> error::Result<&'r str> {
+        let (line, column) = (record_line(record), self.schema.columns[col]);
+        let bytes = record.get(self.columns[col]).ok_or_else(|| {
+            EnergyAnalysisError::parse(self.file, line, Some(column), None, "missing")
+        })?;
+        std::str::from_utf8(bytes).map_err(|e| {
+            let value = String::from_utf8_lossy(bytes);
+            EnergyAnalysisError::parse(self.file, line, Some(column), Some(&value), e)
+        })
+    }
+
+    /// The raw text of the field in the schema's `col`th column.
+    fn raw<'r>(&self, record: &'r ByteRecord, col: usize) -> &'r [u8] {
+        record.get(self.columns[col]).unwrap_or_default()
+    }
+
+    fn number(&self, record: &ByteRecord, col: usize, format: CsvFormat) -> error::Result<f64> {
+        let value = self.field(record, col)?;
+        format.parse_number(value).locate(
+            self.file,
+            record_line(record),
+            self.schema.columns[col],
+            value,
+        )
+    }
+
+    fn timestamp(&self, record: &ByteRecord, col: usize) -> error::Result<NaiveDateTime> {
+        let value = self.field(record, col)?;
+        parse_timestamp(value).locate(
+            self.file,
+            record_line(record),
+            self.schema.columns[col],
+            value,
+        )
+    }
+}
+
+impl EnergyPriceCsvRow {
+    /// The zones the EIA files price, in the order their columns come in.
+    pub const ZONES: [&'static str; 3] = ["NP-15", "SP-15", "ZP-26"];
+
+    /// A row without per-area LMPs or their components.
+    pub fn new(timestamp: String, hour: u32, minute: u32, lmp_avg: f64) -> Self {
+        Self {
+            timestamp,
+            hour,
+            minute,
+            lmp_avg,
+            np15_lmp: f64::NAN,
+            sp15_lmp: f64::NAN,
+            zp26_lmp: f64::NAN,
+            np15_congestion: f64::NAN,
+            sp15_congestion: f64::NAN,
+            zp26_congestion: f64::NAN,
+            np15_loss: f64::NAN,
+            sp15_loss: f64::NAN,
+            zp26_loss: f64::NAN,
+        }
+    }
+
+    /// Each area's own LMP, in `ZONES` order.
+    pub fn lmps(&self) -> [f64; 3] {
+        [self.np15_lmp, self.sp15_lmp, self.zp26_lmp]
+    }
+
+    /// The LMP at `node`, NaN if this row doesn't have it.
+    pub fn lmp(&self, node: PriceNode) -> f64 {
+        match node {
+            PriceNode::Average => self.lmp_avg,
+            PriceNode::Np15 => self.np15_lmp,
+            PriceNode::Sp15 => self.sp15_lmp,
+            PriceNode::Zp26 => self.zp26_lmp,
+        }
+    }
+
+    /// Each zone's congestion component, in `ZONES` order.
+    pub fn congestion(&self) -> [f64; 3] {
+        [
+            self.np15_congestion,
+            self.sp15_congestion,
+            self.zp26_congestion,
+        ]
+    }
+
+    /// Each zone's loss component, in `ZONES` order.
+    pub fn loss(&self) -> [f64; 3] {
+        [self.np15_loss, self.sp15_loss, self.zp26_loss]
+    }
+
+    /// The average LMP followed by each area's LMP, congestion then loss
+    /// component.
+    pub fn values(&self) -> [f64; 10] {
+        let [p0, p1, p2] = self.lmps();
+        let ([c0, c1, c2], [l0, l1, l2]) = (self.congestion(), self.loss());
+        [self.lmp_avg, p0, p1, p2, c0, c1, c2, l0, l1, l2]
+    }
+
+    /// Overwrites the values `values` returns.
+    pub(crate) fn set_values(&mut self, values: [f64; 10]) {
+        [
+            self.lmp_avg,
+            self.np15_lmp,
+            self.sp15_lmp,
+            self.zp26_lmp,
+            self.np15_congestion,
+            self.sp15_congestion,
+            self.zp26_congestion,
+            self.np15_loss,
+            self.sp15_loss,
+            self.zp26_loss,
+        ] = values;
+    }
+
+    /// Overwrites this row with a raw EIA record, averaging its areas'
+    /// LMPs and keeping each area's LMP and components. Reuses the timestamp
+    /// buffer so the converter doesn't allocate per line.
+    fn fill_from_record(
+        &mut self,
+        record: &ByteRecord,
+        at: Located<10>,
+        format: CsvFormat,
+    ) -> error::Result<()> {
+        let timestamp = at.timestamp(record, 0)?;
+        let mut values = [0.; 10];
+        for (col, value) in (1..at.columns.len()).zip(&mut values[1..]) {
+            *value = at.number(record, col, format)?;
+        }
+        // This averages the three different areas' LMPs.
+        values[0] = values[1..=Self::ZONES.len()].iter().sum::<f64>() / Self::ZONES.len() as f64;
+        self.timestamp.clear();
+        self.timestamp.push_str(at.field(record, 0)?);
+        self.hour = timestamp.hour();
+        self.minute = timestamp.minute();
+        self.set_values(values);
+        Ok(())
+    }
+
+    /// The parsed csv's header, which serializing a row would write.
+    const FIELDS: [&'static str; 13] = [
+        "timestamp",
+        "hour",
+        "minute",
+        "lmp_avg",
+        "np15_lmp",
+        "sp15_lmp",
+        "zp26_lmp",
+        "np15_congestion",
+        "sp15_congestion",
+        "zp26_congestion",
+        "np15_loss",
+        "sp15_loss",
+        "zp26_loss",
+    ];
+
+    /// Writes this row, filled from the raw record `raw`, as
+    /// `CsvWriter::serialize` would. See `EnergyGenCsvRow::write`.
+    fn write(
+        &self,
+        out: &mut output::CsvWriter<std::fs::File>,
+        raw: Option<&ByteRecord>,
+        at: Located<10>,
+        record: &mut ByteRecord,
+        number: &mut String,
+    ) -> error::Result<()> {
+        record.clear();
+        record.push_field(self.timestamp.as_bytes());
+        for time in [self.hour, self.minute] {
+            number.clear();
+            write!(number, "{time}")?;
+            record.push_field(number.as_bytes());
+        }
+        // The average is the only value not read straight from a column.
+        for (col, value) in self.values().into_iter().enumerate() {
+            let raw = match raw {
+                Some(raw) if col > 0 => at.raw(raw, col),
+                _ => &[],
+            };
+            push_number(record, number, raw, value, out.options());
+        }
+        out.write_byte_record(record)
+    }
+}
+
+/// Pushes `value` onto `record` as `options.float` writes it. `raw` is the
+/// text it was read from, copied when it already reads that way, or empty
+/// for a value worked out from others.
+fn push_number(
+    record: &mut ByteRecord,
+    number: &mut String,
+    raw: &[u8],
+    value: f64,
+    options: WriteOptions,
+) {
+    if let Some(text) = options.float_text(raw) {
+        record.push_field(text);
+        return;
+    }
+    number.clear();
+    options.push_float(number, value);
+    record.push_field(number.as_bytes());
 }
 
 impl EnergyGenCsvRow {
+    /// The parsed csv's header, which serializing a row would write.
+    const FIELDS: [&'static str; 20] = [
+        "utc_timestamp",
+        "local_timestamp_start",
+        "local_timestamp_end",
+        "local_date",
+        "hour",
+        "total",
+        "battery",
+        "biogas",
+        "biomass",
+        "coal",
+        "geothermal",
+        "imports",
+        "large_hydro",
+        "natural_gas",
+        "nuclear",
+        "other",
+        "small_hydro",
+        "solar",
+        "wind",
+        "minute",
+    ];
+
     const HEADER_KEYWORDS: [(&'static str, RGBColor); 19] = [
         ("Timestamp", full_palette::BLACK),
         ("Beginning", full_palette::BLACK),
@@ -154,18 +1384,146 @@ impl EnergyGenCsvRow {
         ("Wind", full_palette::BLUE_900),
     ];
 
-    fn validate(header: &StringRecord) -> anyhow::Result<()> {
-        for ((keyword, _), col_name) in Self::HEADER_KEYWORDS.iter().zip(header.iter()) {
-            if !col_name.contains(keyword) {
-                bail!("Expected column '{col_name}' to have keyword {keyword}");
-            }
+    /// Overwrites this row with a raw EIA record using column indices from its
+    /// `Schema`. Reuses the row's string buffers so the converter doesn't
+    /// allocate per line.
+    fn fill_from_record(
+        &mut self,
+        record: &ByteRecord,
+        at: Located<19>,
+        format: CsvFormat,
+    ) -> error::Result<()> {
+        let field = |idx: usize| at.field(record, idx);
+        let num = |idx: usize| at.number(record, idx, format);
+        for (buf, idx) in [
+            (&mut self.utc_timestamp, 0),
+            (&mut self.local_timestamp_start, 1),
+            (&mut self.local_timestamp_end, 2),
+            (&mut self.local_date, 3),
+        ] {
+            buf.clear();
+            buf.push_str(field(idx)?);
         }
+        let hour = field(4)?;
+        self.hour =
+            hour.trim()
+                .parse()
+                .locate(at.file, record_line(record), at.schema.columns[4], hour)?;
+        self.total = num(5)?;
+        self.battery = num(6)?;
+        self.biogas = num(7)?;
+        self.biomass = num(8)?;
+        self.coal = num(9)?;
+        self.geothermal = num(10)?;
+        self.imports = num(11)?;
+        self.large_hydro = num(12)?;
+        self.natural_gas = num(13)?;
+        self.nuclear = num(14)?;
+        self.other = num(15)?;
+        self.small_hydro = num(16)?;
+        self.solar = num(17)?;
+        self.wind = num(18)?;
+        self.minute = 0;
         Ok(())
     }
+
+    /// Sets the hour and minute from the interval's local start, computed
+    /// from the timestamp for consistency with other conversions.
+    fn set_time(&mut self, record: &ByteRecord, at: Located<19>) -> error::Result<()> {
+        let timestamp = at.timestamp(record, 1)?;
+        self.hour = timestamp.hour();
+        self.minute = timestamp.minute();
+        Ok(())
+    }
+
     pub fn source_keys() -> impl ExactSizeIterator<Item = (&'static str, RGBColor)> {
         Self::HEADER_KEYWORDS.iter().copied().skip(5)
     }
 
+    /// Returns the index of a source (case-insensitive) in the `sources` array.
+    pub fn source_idx(source: &str) -> error::Result<usize> {
+        Self::source_keys()
+            .position(|(key, _)| key.eq_ignore_ascii_case(source))
+            .ok_or_else(|| {
+                let keys: Vec<_> = Self::source_keys().map(|(key, _)| key).collect();
+                err!(
+                    Invalid,
+                    "Unknown source '{source}', expected one of {keys:?}"
+                )
+            })
+    }
+
+    pub(crate) fn sum_of_sources(&self) -> f64 {
+        self.sources().iter().skip(1).sum()
+    }
+
+    /// How far the reported Total is above the sum of the sources.
+    pub(crate) fn total_discrepancy(&self) -> f64 {
+        self.total - self.sum_of_sources()
+    }
+
+    /// Sets every source, Total included, in `sources` order.
+    pub(crate) fn set_sources(&mut self, sources: [f64; 14]) {
+        [
+            self.total,
+            self.battery,
+            self.biogas,
+            self.biomass,
+            self.coal,
+            self.geothermal,
+            self.imports,
+            self.large_hydro,
+            self.natural_gas,
+            self.nuclear,
+            self.other,
+            self.small_hydro,
+            self.solar,
+            self.wind,
+        ] = sources;
+    }
+
+    /// Writes this row, filled from the raw record `raw`, as
+    /// `CsvWriter::serialize` would, building the record in `record` and
+    /// each number in `number` so no field allocates. Numbers whose raw
+    /// text already reads as `out`'s options write them are copied rather
+    /// than formatted, which is most of what converting a large export
+    /// costs. Without `raw`, or for a recomputed Total, they're formatted.
+    fn write(
+        &self,
+        out: &mut output::CsvWriter<std::fs::File>,
+        raw: Option<&ByteRecord>,
+        at: Located<19>,
+        recomputed: bool,
+        record: &mut ByteRecord,
+        number: &mut String,
+    ) -> error::Result<()> {
+        record.clear();
+        for field in [
+            &self.utc_timestamp,
+            &self.local_timestamp_start,
+            &self.local_timestamp_end,
+            &self.local_date,
+        ] {
+            record.push_field(field.as_bytes());
+        }
+        number.clear();
+        write!(number, "{}", self.hour)?;
+        record.push_field(number.as_bytes());
+        // Sources come from the schema's columns from Total on.
+        for (idx, mw) in self.sources().into_iter().enumerate() {
+            let raw = match raw {
+                Some(_) if idx == 0 && recomputed => &[],
+                Some(raw) => at.raw(raw, idx + 5),
+                None => &[],
+            };
+            push_number(record, number, raw, mw, out.options());
+        }
+        number.clear();
+        write!(number, "{}", self.minute)?;
+        record.push_field(number.as_bytes());
+        out.write_byte_record(record)
+    }
+
     pub fn sources(&self) -> [f64; 14] {
         [
             self.total,
@@ -186,20 +1544,30 @@ impl EnergyGenCsvRow {
     }
 }
 
-pub fn write_energy_gen_averages(output: &Path, gen: &[[f64; 14]]) -> anyhow::Result<()> {
-    let mut csv = csv::Writer::from_path(output)?;
-    let mut bufs: [String; 14] = array::from_fn(|_| String::new());
+/// Writes each slot's average output by source and how many intervals it
+/// averages.
+pub fn write_energy_gen_averages(
+    output: &Path,
+    gen: &[[f64; 14]],
+    samples: &[usize],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    let mut bufs: [String; 15] = array::from_fn(|_| String::new());
 
     for (key, buf) in EnergyGenCsvRow::source_keys().zip(&mut bufs) {
         write!(buf, "{}", key.0)?;
     }
+    write!(bufs[14], "samples")?;
     csv.write_record(&bufs)?;
 
-    for dist in gen.iter() {
+    for (dist, count) in gen.iter().zip(samples) {
         for (val, buf) in dist.iter().copied().zip(&mut bufs) {
             buf.clear();
-            write!(buf, "{val}")?;
+            buf.push_str(&options.float(val));
         }
+        bufs[14].clear();
+        write!(bufs[14], "{count}")?;
         csv.write_record(&bufs)?;
     }
 
@@ -210,8 +1578,9 @@ pub fn write_energy_value_averages(
     output: &Path,
     averages: &[f64; 14],
     qtys: &[f64; 14],
-) -> anyhow::Result<()> {
-    let mut csv = csv::Writer::from_path(output)?;
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
     let mut bufs = [
         "source".to_string(),
         "avg_price".to_string(),
@@ -227,10 +1596,774 @@ pub fn write_energy_value_averages(
             buf.clear();
         }
         write!(&mut bufs[0], "{label}")?;
-        write!(&mut bufs[1], "{avg_price:.2}")?;
-        write!(&mut bufs[2], "{qty}")?;
+        bufs[1].push_str(&options.float(avg_price));
+        bufs[2].push_str(&options.float(qty));
         csv.write_record(&bufs)?;
     }
 
     Ok(())
 }
+
+/// Writes each source's average value under every scenario, a column per
+/// scenario.
+pub fn write_value_scenarios(
+    output: &Path,
+    scenarios: &[(String, [f64; 14])],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    let mut header = vec!["source".to_string()];
+    header.extend(scenarios.iter().map(|(label, _)| label.clone()));
+    csv.write_record(&header)?;
+    for (idx, (source, _)) in EnergyGenCsvRow::source_keys().enumerate() {
+        let mut record = vec![source.to_string()];
+        record.extend(
+            scenarios
+                .iter()
+                .map(|(_, values)| options.float(values[idx])),
+        );
+        csv.write_record(&record)?;
+    }
+    Ok(())
+}
+
+/// Writes the average price and demand in each band of reserve margin.
+pub fn write_margin_bins(
+    output: &Path,
+    bins: &[MarginBin],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record(["margin", "intervals", "avg_price", "avg_demand_mw"])?;
+    for bin in bins {
+        csv.write_record([
+            options.float(bin.margin),
+            bin.intervals.to_string(),
+            options.float(bin.avg_price),
+            options.float(bin.avg_demand_mw),
+        ])?;
+    }
+    Ok(())
+}
+
+pub fn write_storage_sweep(
+    output: &Path,
+    sweep: &[StorageSweepRow],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    for row in sweep {
+        csv.serialize(row)?;
+    }
+    Ok(())
+}
+
+pub fn write_complementarity(
+    output: &Path,
+    slots: &[Complementarity],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    for slot in slots {
+        csv.serialize(slot)?;
+    }
+    Ok(())
+}
+
+pub fn write_weather_days(
+    output: &Path,
+    days: &[WeatherDay],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    for day in days {
+        csv.serialize(day)?;
+    }
+    Ok(())
+}
+
+/// Writes the values a chart draws, one row per point.
+pub fn write_plotted(
+    output: &Path,
+    points: &[PlotPoint],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    for point in points {
+        csv.serialize(point)?;
+    }
+    Ok(())
+}
+
+/// Writes how many whole days landed in each slot of `profile`.
+pub fn write_day_coverage(
+    output: &Path,
+    full_days: &[usize],
+    profile: Profile,
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record(["slot", "full_days"])?;
+    for (idx, days) in full_days.iter().enumerate() {
+        csv.write_record([profile.label(idx), days.to_string()])?;
+    }
+    Ok(())
+}
+
+/// Writes a dataset's summary as one `stat,value` row per figure, with
+/// each source's energy as `<source>_mwh` and `<source>_share`.
+pub fn write_dataset_stats(
+    output: &Path,
+    stats: &DatasetStats,
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record(["stat", "value"])?;
+    let counts = [
+        ("rows", stats.rows),
+        ("days", stats.days),
+        ("partial_days", stats.partial_days),
+    ];
+    csv.write_record(["first", &stats.first])?;
+    csv.write_record(["last", &stats.last])?;
+    for (stat, count) in counts {
+        csv.write_record([stat, &count.to_string()])?;
+    }
+    if let Some(price) = &stats.price {
+        let figures = [
+            ("price_mean", price.mean),
+            ("price_median", price.median),
+            ("price_min", price.min),
+            ("price_max", price.max),
+            ("negative_price_share", price.negative_share),
+        ];
+        for (stat, value) in figures {
+            csv.write_record([stat, &options.float(value)])?;
+        }
+    }
+    for source in &stats.sources {
+        csv.write_record([format!("{}_mwh", source.source), options.float(source.mwh)])?;
+        csv.write_record([
+            format!("{}_share", source.source),
+            options.float(source.share),
+        ])?;
+    }
+    Ok(())
+}
+
+/// Writes the chance price exceeds each threshold in every slot of
+/// `profile`, a column per threshold, with how many intervals each slot
+/// summarizes.
+pub fn write_price_exceedance(
+    output: &Path,
+    exceedance: &PriceExceedance,
+    profile: Profile,
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    let mut header = vec!["slot".to_string()];
+    for threshold in &exceedance.thresholds {
+        header.push(format!("above_{}", options.float(*threshold)));
+    }
+    header.push("samples".to_string());
+    csv.write_record(&header)?;
+    for (idx, samples) in exceedance.samples.iter().enumerate() {
+        let mut record = vec![profile.label(idx)];
+        record.extend(
+            exceedance.probabilities[idx]
+                .iter()
+                .map(|p| options.float(*p)),
+        );
+        record.push(samples.to_string());
+        csv.write_record(&record)?;
+    }
+    Ok(())
+}
+
+/// Writes each slot's mean in two periods and the change between them,
+/// with its 95% confidence interval and p-value.
+pub fn write_period_difference(
+    output: &Path,
+    diffs: &[MeanDifference],
+    profile: Profile,
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record([
+        "slot", "before", "after", "change", "ci_low", "ci_high", "p_value",
+    ])?;
+    for (idx, diff) in diffs.iter().enumerate() {
+        let (low, high) = diff.interval_95();
+        let mut record = vec![profile.label(idx)];
+        record.extend(
+            [
+                diff.before,
+                diff.after,
+                diff.change,
+                low,
+                high,
+                diff.p_value,
+            ]
+            .map(|value| options.float(value)),
+        );
+        csv.write_record(&record)?;
+    }
+    Ok(())
+}
+
+/// Writes each month's median price and net load peak times, as `HH:MM`.
+pub fn write_month_peaks(
+    output: &Path,
+    months: &[MonthPeak],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record(["month", "days", "price_peak", "net_load_peak"])?;
+    for month in months {
+        csv.write_record([
+            month.month.clone(),
+            month.days.to_string(),
+            PeakTiming::clock(month.price_peak),
+            PeakTiming::clock(month.net_load_peak),
+        ])?;
+    }
+    Ok(())
+}
+
+/// Writes each zone's congestion and loss components in every slot of
+/// `profile`, with how many intervals each slot summarizes.
+pub fn write_price_components(
+    output: &Path,
+    components: &PriceComponents,
+    profile: Profile,
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    let mut header = vec!["slot".to_string()];
+    for component in ["congestion", "loss"] {
+        for zone in EnergyPriceCsvRow::ZONES {
+            header.push(format!(
+                "{}_{component}",
+                zone.replace('-', "").to_lowercase()
+            ));
+        }
+    }
+    header.push("samples".to_string());
+    csv.write_record(&header)?;
+    for (idx, samples) in components.samples.iter().enumerate() {
+        let mut record = vec![profile.label(idx)];
+        record.extend(components.congestion[idx].map(|value| options.float(value)));
+        record.extend(components.loss[idx].map(|value| options.float(value)));
+        record.push(samples.to_string());
+        csv.write_record(&record)?;
+    }
+    Ok(())
+}
+
+/// Writes each area's zone and trading hub LMP in each slot of `profile`,
+/// side by side.
+pub fn write_hub_zone(
+    output: &Path,
+    zone: &AreaPrices,
+    hub: &AreaPrices,
+    profile: Profile,
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    let mut header = vec!["slot".to_string()];
+    for zone in EnergyPriceCsvRow::ZONES {
+        let area = zone.replace('-', "").to_lowercase();
+        header.push(format!("{area}_zone"));
+        header.push(format!("{area}_hub"));
+    }
+    header.extend(["zone_samples".to_string(), "hub_samples".to_string()]);
+    csv.write_record(&header)?;
+    for (idx, (zone_samples, hub_samples)) in zone.samples.iter().zip(&hub.samples).enumerate() {
+        let mut record = vec![profile.label(idx)];
+        for (zone, hub) in zone.prices[idx].iter().zip(&hub.prices[idx]) {
+            record.push(options.float(*zone));
+            record.push(options.float(*hub));
+        }
+        record.push(zone_samples.to_string());
+        record.push(hub_samples.to_string());
+        csv.write_record(&record)?;
+    }
+    Ok(())
+}
+
+/// Writes the most congested nodes, most congested first.
+pub fn write_congested_nodes(
+    output: &Path,
+    nodes: &[NodeCongestion],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record([
+        "rank",
+        "node",
+        "avg_congestion",
+        "max_congestion",
+        "avg_lmp",
+        "intervals",
+    ])?;
+    for (rank, node) in nodes.iter().enumerate() {
+        csv.write_record([
+            (rank + 1).to_string(),
+            node.node.clone(),
+            options.float(node.avg_congestion),
+            options.float(node.max_congestion),
+            options.float(node.avg_lmp),
+            node.intervals.to_string(),
+        ])?;
+    }
+    Ok(())
+}
+
+/// Writes each node's average LMP and congestion component in each slot of
+/// `profile`.
+pub fn write_node_profiles(
+    output: &Path,
+    profiles: &[NodeProfile],
+    profile: Profile,
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    let mut header = vec!["slot".to_string()];
+    for node in profiles {
+        header.push(format!("{}_lmp", node.node));
+        header.push(format!("{}_congestion", node.node));
+    }
+    csv.write_record(&header)?;
+    for idx in 0..profile.slots() {
+        let mut record = vec![profile.label(idx)];
+        for node in profiles {
+            record.push(options.float(node.prices[idx]));
+            record.push(options.float(node.congestion[idx]));
+        }
+        csv.write_record(&record)?;
+    }
+    Ok(())
+}
+
+/// Writes the average price, fuel cost, and spark spread in each slot of
+/// `profile`.
+pub fn write_spark_spread(
+    output: &Path,
+    spread: &SparkSpread,
+    profile: Profile,
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record(["slot", "price", "fuel_cost", "spark_spread", "samples"])?;
+    for idx in 0..spread.spreads.len() {
+        csv.write_record([
+            profile.label(idx),
+            options.float(spread.prices[idx]),
+            options.float(spread.fuel_costs[idx]),
+            options.float(spread.spreads[idx]),
+            spread.samples[idx].to_string(),
+        ])?;
+    }
+    Ok(())
+}
+
+pub fn write_storage_revenue(
+    output: &Path,
+    revenue: &StorageRevenue,
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record([
+        "dispatch",
+        "arbitrage",
+        "reg_up",
+        "reg_down",
+        "spin",
+        "total",
+        "per_kw_year",
+    ])?;
+    for (dispatch, dollars) in &revenue.revenues {
+        let mut record = vec![dispatch.name().to_string()];
+        record.extend(dollars.by_source().map(|value| options.float(value)));
+        record.push(options.float(dollars.total()));
+        record.push(options.float(revenue.per_kw_year(dollars.total())));
+        csv.write_record(record)?;
+    }
+    Ok(())
+}
+
+pub fn write_value_stack(
+    output: &Path,
+    stack: &ValueStack,
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record([
+        "month",
+        "days",
+        "arbitrage",
+        "reg_up",
+        "reg_down",
+        "spin",
+        "total",
+        "per_kw",
+    ])?;
+    for month in &stack.months {
+        let mut record = vec![month.month.clone(), month.days.to_string()];
+        record.extend(month.revenue.by_source().map(|value| options.float(value)));
+        record.push(options.float(month.revenue.total()));
+        record.push(options.float(stack.per_kw(month.revenue.total())));
+        csv.write_record(record)?;
+    }
+    Ok(())
+}
+
+pub fn write_price_forecast(
+    output: &Path,
+    forecast: &[(NaiveDateTime, f64)],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record(["timestamp", "forecast_price"])?;
+    for (time, price) in forecast {
+        csv.write_record([
+            time.format(TIMESTAMP_FMT).to_string(),
+            options.float(*price),
+        ])?;
+    }
+    Ok(())
+}
+
+pub fn write_forecast_errors(
+    output: &Path,
+    errors: &[ForecastError],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    for error in errors {
+        csv.serialize(error)?;
+    }
+    Ok(())
+}
+
+pub fn write_total_mismatches(
+    output: &Path,
+    mismatches: &[TotalMismatch],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    if mismatches.is_empty() {
+        csv.write_record(["timestamp", "total", "sum_of_sources", "difference"])?;
+    }
+    for mismatch in mismatches {
+        csv.serialize(mismatch)?;
+    }
+    Ok(())
+}
+
+pub fn write_charging_plans(
+    output: &Path,
+    plans: &[ChargingPlan],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    for plan in plans {
+        csv.serialize(plan)?;
+    }
+    Ok(())
+}
+
+/// Writes the slots each plan charges in, one row per slot.
+pub fn write_charging_schedule(
+    output: &Path,
+    plans: &[ChargingPlan],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    for step in plans.iter().flat_map(|plan| &plan.schedule) {
+        csv.serialize(step)?;
+    }
+    Ok(())
+}
+
+pub fn write_tariff_costs(
+    output: &Path,
+    costs: &[TariffCost],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    for cost in costs {
+        csv.serialize(cost)?;
+    }
+    Ok(())
+}
+
+pub fn write_price_impact(
+    output: &Path,
+    prices: &[(f64, f64)],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    let mut bufs = ["base_price".to_string(), "scenario_price".to_string()];
+    csv.write_record(&bufs)?;
+
+    for (base, scenario) in prices.iter() {
+        for buf in bufs.iter_mut() {
+            buf.clear();
+        }
+        bufs[0].push_str(&options.float(*base));
+        bufs[1].push_str(&options.float(*scenario));
+        csv.write_record(&bufs)?;
+    }
+    Ok(())
+}
+
+/// Writes the historical and scenario net load and price in each
+/// five-minute window of the day.
+pub fn write_scenario_slots(
+    output: &Path,
+    slots: &[ScenarioSlot],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record([
+        "time",
+        "net_load",
+        "scenario_net_load",
+        "price",
+        "scenario_price",
+    ])?;
+    for (idx, slot) in slots.iter().enumerate() {
+        csv.write_record([
+            Profile::Day.label(idx),
+            options.float(slot.net_load),
+            options.float(slot.scenario_net_load),
+            options.float(slot.price),
+            options.float(slot.scenario_price),
+        ])?;
+    }
+    Ok(())
+}
+
+pub fn write_price_spikes(
+    output: &Path,
+    spikes: &[PriceSpike],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    for spike in spikes {
+        csv.serialize(spike)?;
+    }
+    Ok(())
+}
+
+/// Writes how far each event day's price and sources strayed from their
+/// baselines.
+pub fn write_event_deviations(
+    output: &Path,
+    deviations: &[EventDeviation],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    for deviation in deviations {
+        csv.serialize(deviation)?;
+    }
+    Ok(())
+}
+
+pub fn write_daily_spreads(
+    output: &Path,
+    spreads: &[DailySpread],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    for spread in spreads {
+        csv.serialize(spread)?;
+    }
+    Ok(())
+}
+
+pub fn write_source_price_correlation(
+    output: &Path,
+    corr: &SourcePriceCorrelation,
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    let mut bufs: [String; 15] = array::from_fn(|_| String::new());
+
+    write!(&mut bufs[0], "hour")?;
+    for (key, buf) in EnergyGenCsvRow::source_keys().zip(bufs.iter_mut().skip(1)) {
+        write!(buf, "{}", key.0)?;
+    }
+    csv.write_record(&bufs)?;
+
+    let rows = std::iter::once(("all".to_string(), &corr.overall)).chain(
+        corr.hourly
+            .iter()
+            .enumerate()
+            .map(|(hour, row)| (format!("{hour:02}"), row)),
+    );
+    for (label, row) in rows {
+        for buf in bufs.iter_mut() {
+            buf.clear();
+        }
+        write!(&mut bufs[0], "{label}")?;
+        for (val, buf) in row.iter().zip(bufs.iter_mut().skip(1)) {
+            buf.push_str(&options.float(*val));
+        }
+        csv.write_record(&bufs)?;
+    }
+    Ok(())
+}
+
+/// Writes one row of per-source values for each labeled period or time slot.
+pub fn write_labeled_source_values(
+    output: &Path,
+    label_name: &str,
+    rows: &[(String, [f64; 14])],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    let mut bufs: [String; 15] = array::from_fn(|_| String::new());
+
+    write!(&mut bufs[0], "{label_name}")?;
+    for (key, buf) in EnergyGenCsvRow::source_keys().zip(bufs.iter_mut().skip(1)) {
+        write!(buf, "{}", key.0)?;
+    }
+    csv.write_record(&bufs)?;
+
+    for (label, row) in rows {
+        for buf in bufs.iter_mut() {
+            buf.clear();
+        }
+        write!(&mut bufs[0], "{label}")?;
+        for (val, buf) in row.iter().zip(bufs.iter_mut().skip(1)) {
+            buf.push_str(&options.float(*val));
+        }
+        csv.write_record(&bufs)?;
+    }
+    Ok(())
+}
+
+/// Writes each group's average price followed by the share and value of every source.
+pub fn write_group_summaries(
+    output: &Path,
+    summaries: &[GroupSummary],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record(group_summary_header(&[]))?;
+    for summary in summaries {
+        csv.write_record(group_summary_record(summary, &[], options))?;
+    }
+    Ok(())
+}
+
+/// Writes net-load peak hours as group summaries with each group's
+/// average net load after its label.
+pub fn write_net_load_peaks(
+    output: &Path,
+    peaks: &NetLoadPeaks,
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    csv.write_record(group_summary_header(&["net_load_mw"]))?;
+    for (summary, net_load) in peaks.hours.iter().chain([&peaks.peak, &peaks.rest]) {
+        csv.write_record(group_summary_record(
+            summary,
+            &[options.float(*net_load)],
+            options,
+        ))?;
+    }
+    Ok(())
+}
+
+fn group_summary_header(extra: &[&str]) -> Vec<String> {
+    let mut header = vec!["group".to_string()];
+    header.extend(extra.iter().map(|col| col.to_string()));
+    header.push("avg_price".to_string());
+    for (key, _) in EnergyGenCsvRow::source_keys() {
+        header.push(format!("{key} share"));
+        header.push(format!("{key} value"));
+    }
+    header
+}
+
+fn group_summary_record(
+    summary: &GroupSummary,
+    extra: &[String],
+    options: WriteOptions,
+) -> Vec<String> {
+    let mut record = vec![summary.label.clone()];
+    record.extend_from_slice(extra);
+    record.push(options.float(summary.avg_price));
+    for (share, value) in summary.shares.iter().zip(summary.values.iter()) {
+        record.push(options.float(*share));
+        record.push(options.float(*value));
+    }
+    record
+}
+
+/// Writes the energy, average price, and interval count of each direction,
+/// overall and by hour. `names` label the positive and negative directions,
+/// e.g. import/export.
+pub fn write_directions(
+    output: &Path,
+    hours: &[DirectionalHour],
+    names: [&str; 2],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    let mut header = vec!["hour".to_string()];
+    for name in names {
+        header.push(format!("{name}_mwh"));
+        header.push(format!("{name}_avg_price"));
+        header.push(format!("{name}_intervals"));
+    }
+    header.push("net_mwh".to_string());
+    csv.write_record(&header)?;
+
+    let (mut positive, mut negative) = (Flow::default(), Flow::default());
+    for hour in hours {
+        positive.merge(&hour.positive);
+        negative.merge(&hour.negative);
+    }
+    let rows = std::iter::once(("all".to_string(), &positive, &negative)).chain(
+        hours
+            .iter()
+            .map(|hour| (format!("{:02}", hour.hour), &hour.positive, &hour.negative)),
+    );
+    for (label, positive, negative) in rows {
+        let mut record = vec![label];
+        for flow in [positive, negative] {
+            record.push(options.float(flow.mwh));
+            record.push(options.float(flow.avg_price()));
+            record.push(flow.intervals.to_string());
+        }
+        record.push(options.float(positive.mwh - negative.mwh));
+        csv.write_record(&record)?;
+    }
+    Ok(())
+}
+
+/// Writes the average MW flowing in from each neighbor by hour of day, one
+/// column per balancing authority plus their net.
+pub fn write_ba_interchange(
+    output: &Path,
+    bas: &[BaInterchange],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut csv = options.csv(output)?;
+    let mut header = vec!["hour".to_string()];
+    header.extend(bas.iter().map(|ba| format!("{}_mw", ba.ba)));
+    header.push("net_mw".to_string());
+    csv.write_record(&header)?;
+    for hour in 0..24 {
+        let mut record = vec![format!("{hour:02}")];
+        record.extend(bas.iter().map(|ba| options.float(ba.hours[hour])));
+        let net: f64 = bas.iter().map(|ba| ba.hours[hour]).sum();
+        record.push(options.float(net));
+        csv.write_record(&record)?;
+    }
+    Ok(())
+}
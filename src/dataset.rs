@@ -0,0 +1,147 @@
+//! ### Dataset
+//! A parsed price or gen dataset read into memory once, so a run of
+//! several analyses doesn't re-read the file for each. While a `Dataset`
+//! is handed to a `Compute`, analyses read its rows in place wherever they
+//! would read its path, and since it derefs to that path it can be handed
+//! straight to any compute function that takes one, e.g.
+//! `Compute::from_dataset(&prices).with_dataset(&gen).average_value_5min(&gen)`.
+//! `Dataset::from_reader` holds rows that never touched disk, such as an
+//! upload in a browser, under a name that needn't exist as a file.
+
+use crate::compute::Compute;
+use crate::convert::{self, EnergyGenCsvRow, EnergyPriceCsvRow, RawKind};
+use crate::error::{self, bail};
+use crate::manifest::Manifest;
+use std::borrow::Cow;
+use std::io::Read;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Clone, Debug)]
+enum Held {
+    Prices(Arc<Vec<EnergyPriceCsvRow>>),
+    Gen(Arc<Vec<EnergyGenCsvRow>>),
+}
+
+/// A parsed price or gen csv, or a `Store`, held in memory. Cloning shares
+/// the rows. The rows are held as stored, so a `Compute` still reads its
+/// own price node from them.
+#[derive(Clone, Debug)]
+pub struct Dataset {
+    path: PathBuf,
+    held: Held,
+}
+
+impl Dataset {
+    /// Reads every row of `path` into memory. Manifests are left on disk,
+    /// since analyses over them already work a month at a time.
+    pub fn load(path: &Path) -> error::Result<Self> {
+        if Manifest::is_manifest(path) {
+            bail!(
+                Invalid,
+                "{path:?} is a manifest. Load its monthly files one at a time"
+            );
+        }
+        let compute = Compute::new(path);
+        let held = match compute.kind()? {
+            RawKind::Price => Held::Prices(Arc::new(
+                compute
+                    .stored_price_rows()?
+                    .map(|row| row.map(Cow::into_owned))
+                    .collect::<error::Result<_>>()?,
+            )),
+            RawKind::Gen => Held::Gen(Arc::new(
+                compute
+                    .gen_rows()?
+                    .map(|row| row.map(Cow::into_owned))
+                    .collect::<error::Result<_>>()?,
+            )),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            held,
+        })
+    }
+
+    /// Reads a csv of the form output by parse-price-csv or parse-gen-csv
+    /// from `reader` and holds it under `name`, without touching the
+    /// filesystem. A `Compute` handed the dataset then reads these rows for
+    /// `name`, so bytes from anywhere run through the same analyses as a
+    /// file.
+    pub fn from_reader(name: impl Into<PathBuf>, mut reader: impl Read) -> error::Result<Self> {
+        let name = name.into();
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let header = bytes
+            .split(|byte| *byte == b'\n')
+            .next()
+            .unwrap_or_default();
+        let holds_prices = String::from_utf8_lossy(header)
+            .trim_end()
+            .split(',')
+            .any(|column| column == "lmp_avg");
+        let held = match holds_prices {
+            true => Held::Prices(Arc::new(
+                convert::read_rows_from(&name, bytes.as_slice())?.collect::<error::Result<_>>()?,
+            )),
+            false => Held::Gen(Arc::new(
+                convert::read_rows_from(&name, bytes.as_slice())?.collect::<error::Result<_>>()?,
+            )),
+        };
+        Ok(Self { path: name, held })
+    }
+
+    pub fn kind(&self) -> RawKind {
+        match self.held {
+            Held::Prices(_) => RawKind::Price,
+            Held::Gen(_) => RawKind::Gen,
+        }
+    }
+
+    /// How many rows the dataset holds.
+    pub fn len(&self) -> usize {
+        match &self.held {
+            Held::Prices(rows) => rows.len(),
+            Held::Gen(rows) => rows.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The price rows held, if the dataset holds prices.
+    pub(crate) fn price_rows(&self) -> Option<&[EnergyPriceCsvRow]> {
+        match &self.held {
+            Held::Prices(rows) => Some(rows),
+            Held::Gen(_) => None,
+        }
+    }
+
+    /// The gen rows held, if the dataset holds generation.
+    pub(crate) fn gen_rows(&self) -> Option<&[EnergyGenCsvRow]> {
+        match &self.held {
+            Held::Gen(rows) => Some(rows),
+            Held::Prices(_) => None,
+        }
+    }
+}
+
+impl Deref for Dataset {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for Dataset {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
@@ -0,0 +1,173 @@
+//! ### Error
+//! The errors the library returns. Problems with the data itself (a bad
+//! row, a missing day, files that don't line up) get their own variants so
+//! callers can tell them apart from bad arguments and failing I/O.
+
+use plotters::drawing::DrawingAreaErrorKind;
+use quick_xml::encoding::EncodingError;
+use std::fmt::Display;
+use std::num::{ParseFloatError, ParseIntError};
+use std::path::{Path, PathBuf};
+use std::str::Utf8Error;
+use std::string::FromUtf8Error;
+use std::{error, fmt, io};
+
+pub type Result<T, E = EnergyAnalysisError> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EnergyAnalysisError {
+    /// A row or value in a data file that couldn't be read.
+    #[error("{}, line {line}{}: {message}", file.display(), at_field(field, value))]
+    Parse {
+        file: PathBuf,
+        /// 1-based, counting the header.
+        line: u64,
+        /// The column's header, when the problem is with one field.
+        field: Option<String>,
+        value: Option<String>,
+        message: String,
+    },
+    /// A file whose columns aren't what the command expects.
+    #[error("{}: {reason}", file.display())]
+    SchemaMismatch { file: PathBuf, reason: String },
+    /// Intervals missing from a dataset, or too unevenly covered to average.
+    #[error("{0}")]
+    TimeGap(String),
+    /// Price and generation datasets with no intervals in common.
+    #[error("No overlapping data between {} and {}", price.display(), gen.display())]
+    JoinMismatch { price: PathBuf, gen: PathBuf },
+    /// Nothing to work with, such as an empty file or date range.
+    #[error("{0}")]
+    NoData(String),
+    /// An argument or setting that can't be used.
+    #[error("{0}")]
+    Invalid(String),
+    #[error("{0:?} already exists, pass --overwrite to replace it")]
+    OutputExists(PathBuf),
+    /// A failure reported by a remote API or an alert hook.
+    #[error("{0}")]
+    Remote(String),
+    #[error("Failed to draw a chart: {0}")]
+    Render(String),
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Time(#[from] chrono::ParseError),
+    #[error(transparent)]
+    Float(#[from] ParseFloatError),
+    #[error(transparent)]
+    Int(#[from] ParseIntError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    #[error(transparent)]
+    TomlWrite(#[from] toml::ser::Error),
+    #[cfg(feature = "native")]
+    #[error(transparent)]
+    Workbook(#[from] calamine::Error),
+    #[error(transparent)]
+    WorkbookWrite(#[from] rust_xlsxwriter::XlsxError),
+    #[cfg(feature = "native")]
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+    #[error(transparent)]
+    Xml(#[from] quick_xml::Error),
+    #[error(transparent)]
+    Utf8(#[from] Utf8Error),
+    #[error(transparent)]
+    Fmt(#[from] fmt::Error),
+}
+
+impl EnergyAnalysisError {
+    /// A `Parse` error at `line` of `file`.
+    pub fn parse(
+        file: &Path,
+        line: u64,
+        field: Option<&str>,
+        value: Option<&str>,
+        message: impl Display,
+    ) -> Self {
+        Self::Parse {
+            file: file.to_path_buf(),
+            line,
+            field: field.map(str::to_string),
+            value: value.map(str::to_string),
+            message: message.to_string(),
+        }
+    }
+
+    /// Whether the error is about the data itself rather than arguments,
+    /// settings or the environment. Another file may well work.
+    pub fn is_data_error(&self) -> bool {
+        matches!(
+            self,
+            Self::Parse { .. }
+                | Self::SchemaMismatch { .. }
+                | Self::TimeGap(_)
+                | Self::JoinMismatch { .. }
+                | Self::NoData(_)
+                | Self::Csv(_)
+                | Self::Time(_)
+                | Self::Float(_)
+                | Self::Int(_)
+        )
+    }
+}
+
+fn at_field(field: &Option<String>, value: &Option<String>) -> String {
+    match (field, value) {
+        (Some(field), Some(value)) => format!(", {field} = {value:?}"),
+        (Some(field), None) => format!(", {field}"),
+        _ => String::new(),
+    }
+}
+
+/// Places an error about one field of a data file, so it says which file,
+/// line and value to look at.
+pub trait Locate<T> {
+    fn locate(self, file: &Path, line: u64, field: &str, value: &str) -> Result<T>;
+}
+
+impl<T, E: Display> Locate<T> for Result<T, E> {
+    fn locate(self, file: &Path, line: u64, field: &str, value: &str) -> Result<T> {
+        self.map_err(|e| EnergyAnalysisError::parse(file, line, Some(field), Some(value), e))
+    }
+}
+
+impl<E: error::Error + Send + Sync> From<DrawingAreaErrorKind<E>> for EnergyAnalysisError {
+    fn from(e: DrawingAreaErrorKind<E>) -> Self {
+        Self::Render(e.to_string())
+    }
+}
+
+impl From<FromUtf8Error> for EnergyAnalysisError {
+    fn from(e: FromUtf8Error) -> Self {
+        Self::Utf8(e.utf8_error())
+    }
+}
+
+impl From<EncodingError> for EnergyAnalysisError {
+    fn from(e: EncodingError) -> Self {
+        Self::Xml(e.into())
+    }
+}
+
+/// Builds a message-carrying variant, e.g. `err!(NoData, "No rows in {path:?}")`.
+macro_rules! err {
+    ($kind:ident, $($arg:tt)*) => {
+        $crate::error::EnergyAnalysisError::$kind(format!($($arg)*))
+    };
+}
+
+/// Returns early with `err!`.
+macro_rules! bail {
+    ($kind:ident, $($arg:tt)*) => {
+        return Err($crate::error::err!($kind, $($arg)*))
+    };
+}
+
+pub(crate) use {bail, err};
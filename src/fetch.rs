@@ -0,0 +1,204 @@
+//! ### Fetch
+//! Pulls data straight from market and government APIs into the same
+//! intermediate csvs the parse commands write, as an alternative to the
+//! quarterly EIA downloads.
+//!
+//! Requests go through the system's `curl`, which handles TLS and proxies
+//! the way the rest of the machine is set up to.
+//!
+//! With `--append` a fetch adds only the intervals after the latest one its
+//! output already holds, so it can be rerun on a schedule to keep the csvs
+//! that `serve` and `monitor` watch up to date.
+//!
+//! Only the Pacific time conversions and `resume_after` are built without
+//! the `native` feature.
+
+#[cfg(feature = "native")]
+pub mod eia;
+#[cfg(feature = "native")]
+pub mod oasis;
+
+use crate::compute::Compute;
+use crate::convert::RawKind;
+use crate::error::{self, bail};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeDelta, Weekday};
+use std::fs;
+use std::path::Path;
+
+#[cfg(feature = "native")]
+use crate::output::{CsvWriter, WriteOptions};
+#[cfg(feature = "native")]
+use std::{
+    fs::File,
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Spaces requests at least `gap` apart and retries the ones an API turns
+/// away for coming too fast.
+#[cfg(feature = "native")]
+#[derive(Debug)]
+pub struct Client {
+    gap: Duration,
+    retries: u32,
+    timeout: Option<Duration>,
+    last: Option<Instant>,
+}
+
+#[cfg(feature = "native")]
+impl Client {
+    pub fn new(gap: Duration) -> Self {
+        Self {
+            gap,
+            retries: 3,
+            timeout: None,
+            last: None,
+        }
+    }
+
+    /// Gives up on a request, retries included, that takes longer than
+    /// `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The body of a GET of `url`. Rate-limited and unavailable responses
+    /// (429 and 503) are retried after a growing wait.
+    ///
+    /// The url goes to curl on stdin rather than on its command line, where
+    /// any user of the machine could read an API key in it, and errors name
+    /// it without its query for the same reason.
+    pub fn get(&mut self, url: &str) -> error::Result<Vec<u8>> {
+        self.request(url, "")
+    }
+
+    /// The body of a POST of `json` to `url`, retried like [`Client::get`].
+    pub fn post_json(&mut self, url: &str, json: &str) -> error::Result<Vec<u8>> {
+        let config = format!(
+            "header = \"Content-Type: application/json\"\ndata-binary = {}\n",
+            curl_quote(json)
+        );
+        self.request(url, &config)
+    }
+
+    /// Runs curl on `url` with `extra` added to its config.
+    fn request(&mut self, url: &str, extra: &str) -> error::Result<Vec<u8>> {
+        let shown = url.split('?').next().unwrap_or(url);
+        let started = Instant::now();
+        let mut wait = self.gap;
+        let mut attempt = 0;
+        loop {
+            if let Some(elapsed) = self.last.map(|last| last.elapsed()) {
+                thread::sleep(wait.saturating_sub(elapsed));
+            }
+            self.last = Some(Instant::now());
+            // The body comes back on stdout and the status as the last line
+            // of stderr, so nothing touches the disk.
+            let mut config = format!(
+                "url = {}\nwrite-out = \"%{{stderr}}\\n%{{http_code}}\"\n{extra}",
+                curl_quote(url)
+            );
+            if let Some(timeout) = self.timeout {
+                let left = timeout.saturating_sub(started.elapsed());
+                if left.is_zero() {
+                    bail!(Remote, "{shown} didn't answer within {timeout:?}");
+                }
+                config.push_str(&format!("max-time = {:.3}\n", left.as_secs_f64()));
+            }
+            let mut curl = Command::new("curl")
+                .args(["--silent", "--show-error", "--location", "--config", "-"])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()?;
+            curl.stdin
+                .take()
+                .expect("stdin is piped")
+                .write_all(config.as_bytes())?;
+            let output = curl.wait_with_output()?;
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let (message, code) = stderr.trim_end().rsplit_once('\n').unwrap_or(("", &stderr));
+            if !output.status.success() {
+                bail!(Remote, "curl failed on {shown}: {}", message.trim());
+            }
+            match code.trim() {
+                "429" | "503" if attempt < self.retries => {
+                    attempt += 1;
+                    wait *= 2;
+                }
+                code if code.starts_with('2') => return Ok(output.stdout),
+                code => bail!(Remote, "{shown} answered HTTP {code}"),
+            }
+        }
+    }
+}
+
+/// The local start of the latest interval in a csv a fetch appends to, or
+/// None when there's no csv yet. Fails if the csv holds the other kind of
+/// data, so prices are never appended to generation or the reverse.
+pub fn resume_after(path: &Path, kind: RawKind) -> error::Result<Option<NaiveDateTime>> {
+    if fs::metadata(path).map_or(true, |meta| meta.len() == 0) {
+        return Ok(None);
+    }
+    let compute = Compute::new(path);
+    let held = compute.kind()?;
+    if held != kind {
+        bail!(
+            Invalid,
+            "{path:?} holds {held:?} data, so {kind:?} data can't be appended to it"
+        );
+    }
+    Ok(match kind {
+        RawKind::Price => compute.latest_prices(1)?.last().map(|(time, _)| *time),
+        RawKind::Gen => compute.latest_gen()?.map(|(time, _)| time),
+    })
+}
+
+/// Opens a fetch's output csv, added to when `append` and otherwise
+/// replaced under the overwrite rule.
+#[cfg(feature = "native")]
+fn open(path: &Path, append: bool, options: WriteOptions) -> error::Result<CsvWriter<File>> {
+    match append {
+        true => options.append_csv(path),
+        false => options.csv(path),
+    }
+}
+
+/// A string quoted for a curl config file.
+#[cfg(feature = "native")]
+fn curl_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Converts a UTC time to Pacific local time under the US daylight saving
+/// rules in force since 2007. The hour that repeats each November appears
+/// twice in the result.
+pub fn pacific_from_utc(utc: NaiveDateTime) -> NaiveDateTime {
+    let year = utc.year();
+    let dst_start = nth_sunday(year, 3, 2)
+        .and_hms_opt(10, 0, 0)
+        .expect("valid time");
+    let dst_end = nth_sunday(year, 11, 1)
+        .and_hms_opt(9, 0, 0)
+        .expect("valid time");
+    let offset = if (dst_start..dst_end).contains(&utc) {
+        7
+    } else {
+        8
+    };
+    utc - TimeDelta::hours(offset)
+}
+
+/// The UTC time of midnight at the start of a Pacific local date.
+pub fn utc_of_pacific_midnight(date: NaiveDate) -> NaiveDateTime {
+    let dst = date > nth_sunday(date.year(), 3, 2) && date <= nth_sunday(date.year(), 11, 1);
+    let offset = if dst { 7 } else { 8 };
+    date.and_hms_opt(0, 0, 0).expect("valid time") + TimeDelta::hours(offset)
+}
+
+fn nth_sunday(year: i32, month: u32, nth: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, nth).expect("valid date")
+}
@@ -0,0 +1,287 @@
+//! EIA's open-data API (v2). Its hourly demand, net generation by fuel and
+//! interchange by neighboring balancing authority run days behind real
+//! time, where the downloadable five-minute files lag by a quarter. Needs
+//! a free API key from https://www.eia.gov/opendata/register.php.
+
+use super::{open, pacific_from_utc, utc_of_pacific_midnight, Client};
+use crate::convert::{DemandCsvRow, EnergyGenCsvRow, InterchangeCsvRow, TIMESTAMP_FMT};
+use crate::error::{self, bail, err};
+use crate::output::WriteOptions;
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta, Timelike};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+const BASE_URL: &str = "https://api.eia.gov/v2/electricity/rto";
+
+/// The most rows the API returns per request.
+const PAGE: usize = 5000;
+
+pub const REQUEST_GAP: Duration = Duration::from_secs(1);
+
+/// A row of any of the API's datasets.
+pub type Row = Map<String, Value>;
+
+pub struct EiaApi {
+    client: Client,
+    key: String,
+    /// The balancing authority, e.g. CISO for CAISO.
+    respondent: String,
+}
+
+impl EiaApi {
+    pub fn new(key: String, respondent: String) -> Self {
+        Self {
+            client: Client::new(REQUEST_GAP),
+            key,
+            respondent,
+        }
+    }
+
+    /// Fetches every dataset for the Pacific dates `start` through `end`.
+    pub fn hourly(&mut self, start: NaiveDate, end: NaiveDate) -> error::Result<Hourly> {
+        if end < start {
+            bail!(Invalid, "{end} is before {start}");
+        }
+        // Hourly periods are named by the UTC hour they end.
+        let first = utc_of_pacific_midnight(start) + TimeDelta::hours(1);
+        let last = utc_of_pacific_midnight(end + TimeDelta::days(1));
+        let respondent = self.respondent.clone();
+        let fuel = self.rows("fuel-type-data", ("respondent", &respondent), first, last)?;
+        let region = self.rows("region-data", ("respondent", &respondent), first, last)?;
+        let interchange = self.rows("interchange-data", ("fromba", &respondent), first, last)?;
+        Hourly::from_rows(&fuel, &region, &interchange)
+    }
+
+    /// Every row of one dataset between two UTC hours, a page at a time.
+    fn rows(
+        &mut self,
+        dataset: &str,
+        (facet, ba): (&str, &str),
+        first: NaiveDateTime,
+        last: NaiveDateTime,
+    ) -> error::Result<Vec<Row>> {
+        let mut rows = Vec::new();
+        loop {
+            println!("Fetching EIA {dataset} from row {}", rows.len());
+            // Brackets are escaped so curl doesn't read them as a glob.
+            let url = format!(
+                "{BASE_URL}/{dataset}/data/?api_key={}&frequency=hourly&data%5B0%5D=value\
+                 &facets%5B{facet}%5D%5B%5D={ba}&start={}&end={}\
+                 &sort%5B0%5D%5Bcolumn%5D=period&sort%5B0%5D%5Bdirection%5D=asc\
+                 &offset={}&length={PAGE}",
+                self.key,
+                first.format("%Y-%m-%dT%H"),
+                last.format("%Y-%m-%dT%H"),
+                rows.len(),
+            );
+            let body: Value = serde_json::from_slice(&self.client.get(&url)?)?;
+            if let Some(error) = body.get("error") {
+                bail!(Remote, "EIA error: {error}");
+            }
+            let response = &body["response"];
+            let total = number(&response["total"])
+                .ok_or_else(|| err!(Remote, "EIA response has no total"))?
+                as usize;
+            let Some(page) = response["data"].as_array() else {
+                bail!(Remote, "EIA response has no data");
+            };
+            rows.extend(page.iter().filter_map(|row| row.as_object().cloned()));
+            if page.is_empty() || rows.len() >= total {
+                return Ok(rows);
+            }
+        }
+    }
+}
+
+/// The API reports numbers as strings or numbers depending on the dataset.
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(num) => num.as_f64(),
+        Value::String(num) => num.parse().ok(),
+        _ => None,
+    }
+}
+
+/// The source each EIA fuel type code is counted under. EIA doesn't split
+/// hydro by size or separate biogas and biomass from other fuels.
+fn source_of(fuel_type: &str) -> &'static str {
+    match fuel_type {
+        "BAT" => "Batteries",
+        "COL" => "Coal",
+        "GEO" => "Geothermal",
+        "NG" => "Natural Gas",
+        "NUC" => "Nuclear",
+        "SUN" => "Solar",
+        "WAT" => "Large Hydro",
+        "WND" => "Wind",
+        _ => "Other",
+    }
+}
+
+/// Hourly data for one balancing authority, keyed by the UTC hour each
+/// value ends.
+#[derive(Debug, Default)]
+pub struct Hourly {
+    /// Net generation by source, with net imports (the negative of total
+    /// interchange) as Imports and Total recomputed from the sources.
+    pub gen: BTreeMap<NaiveDateTime, [f64; 14]>,
+    pub demand: BTreeMap<NaiveDateTime, f64>,
+    /// MW flowing in from each neighbor. Negative when exporting to it.
+    pub interchange: BTreeMap<(NaiveDateTime, String), f64>,
+}
+
+impl Hourly {
+    /// Combines rows of the fuel-type, region and interchange datasets.
+    pub fn from_rows(fuel: &[Row], region: &[Row], interchange: &[Row]) -> error::Result<Self> {
+        let mut hourly = Self::default();
+        let imports_idx = EnergyGenCsvRow::source_idx("Imports")?;
+        for row in fuel {
+            let Some((period, mw)) = period_value(row)? else {
+                continue;
+            };
+            let fuel_type = row
+                .get("fueltype")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let idx = EnergyGenCsvRow::source_idx(source_of(fuel_type))?;
+            hourly.gen.entry(period).or_insert([0.; 14])[idx] += mw;
+        }
+        for row in region {
+            let Some((period, mw)) = period_value(row)? else {
+                continue;
+            };
+            match row.get("type").and_then(Value::as_str) {
+                Some("D") => {
+                    hourly.demand.insert(period, mw);
+                }
+                // Only hours with generation are kept, so imports don't
+                // make up rows of their own.
+                Some("TI") => {
+                    if let Some(sources) = hourly.gen.get_mut(&period) {
+                        sources[imports_idx] = -mw;
+                    }
+                }
+                _ => (),
+            }
+        }
+        for sources in hourly.gen.values_mut() {
+            sources[0] = sources[1..].iter().sum();
+        }
+        for row in interchange {
+            let Some((period, mw)) = period_value(row)? else {
+                continue;
+            };
+            let Some(neighbor) = row.get("toba").and_then(Value::as_str) else {
+                bail!(Remote, "An interchange row has no toba: {row:?}");
+            };
+            *hourly
+                .interchange
+                .entry((period, neighbor.to_string()))
+                .or_default() -= mw;
+        }
+        Ok(hourly)
+    }
+
+    /// Drops every hour that starts at or before the local time `after`,
+    /// the latest interval of a csv being appended to (see
+    /// `resume_after`), so no interval is written twice.
+    pub fn retain_after(&mut self, after: NaiveDateTime) {
+        let later =
+            |hour_end: &NaiveDateTime| pacific_from_utc(*hour_end - TimeDelta::hours(1)) > after;
+        self.gen.retain(|hour_end, _| later(hour_end));
+        self.demand.retain(|hour_end, _| later(hour_end));
+        self.interchange.retain(|(hour_end, _), _| later(hour_end));
+    }
+
+    /// Writes generation as a parsed gen csv. Each hour is repeated over
+    /// its twelve five-minute intervals, so every command that reads one
+    /// works on it, with profiles stepping once an hour. With `append` the
+    /// rows are added to the end of the csv instead of replacing it, as
+    /// they are by the other writers.
+    pub fn write_gen(
+        &self,
+        output: &Path,
+        append: bool,
+        options: WriteOptions,
+    ) -> error::Result<()> {
+        let mut csv = open(output, append, options)?;
+        let mut row = EnergyGenCsvRow::default();
+        for (hour_end, sources) in &self.gen {
+            row.set_sources(*sources);
+            for step in 0..12 {
+                let utc_start = *hour_end - TimeDelta::minutes(60 - 5 * step);
+                let local_start = pacific_from_utc(utc_start);
+                let local_end = local_start + TimeDelta::minutes(5);
+                row.utc_timestamp = (utc_start + TimeDelta::minutes(5))
+                    .format(TIMESTAMP_FMT)
+                    .to_string();
+                row.local_timestamp_start = local_start.format(TIMESTAMP_FMT).to_string();
+                row.local_timestamp_end = local_end.format(TIMESTAMP_FMT).to_string();
+                row.local_date = local_start.date().to_string();
+                row.hour = local_start.hour();
+                row.minute = local_start.minute();
+                csv.serialize(&row)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes `local_timestamp,demand_mw` rows (see `DemandCsvRow`),
+    /// stamped with the local start of each hour.
+    pub fn write_demand(
+        &self,
+        output: &Path,
+        append: bool,
+        options: WriteOptions,
+    ) -> error::Result<()> {
+        let mut csv = open(output, append, options)?;
+        for (hour_end, mw) in &self.demand {
+            csv.serialize(DemandCsvRow {
+                local_timestamp: local_start(*hour_end),
+                demand_mw: *mw,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Writes `local_timestamp,ba,imports_mw` rows, one per neighbor per
+    /// hour.
+    pub fn write_interchange(
+        &self,
+        output: &Path,
+        append: bool,
+        options: WriteOptions,
+    ) -> error::Result<()> {
+        let mut csv = open(output, append, options)?;
+        for ((hour_end, ba), mw) in &self.interchange {
+            csv.serialize(InterchangeCsvRow {
+                local_timestamp: local_start(*hour_end),
+                ba: ba.clone(),
+                imports_mw: *mw,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn local_start(hour_end: NaiveDateTime) -> String {
+    pacific_from_utc(hour_end - TimeDelta::hours(1))
+        .format(TIMESTAMP_FMT)
+        .to_string()
+}
+
+/// A row's hour and value, or None when its value is missing, which the
+/// API reports as null.
+fn period_value(row: &Row) -> error::Result<Option<(NaiveDateTime, f64)>> {
+    let period = row
+        .get("period")
+        .and_then(Value::as_str)
+        .ok_or_else(|| err!(Remote, "An EIA row has no period: {row:?}"))?;
+    let period = NaiveDateTime::parse_from_str(&format!("{period}:00"), "%Y-%m-%dT%H:%M")?;
+    Ok(row
+        .get("value")
+        .and_then(number)
+        .map(|value| (period, value)))
+}
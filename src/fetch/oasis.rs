@@ -0,0 +1,320 @@
+//! CAISO's OASIS API, which publishes five-minute LMPs, solar and wind
+//! output and load days before they reach the EIA files. Responses are
+//! zips of XML reports.
+
+use super::{open, pacific_from_utc, utc_of_pacific_midnight, Client};
+use crate::convert::{EnergyGenCsvRow, EnergyPriceCsvRow, TIMESTAMP_FMT};
+use crate::error::{self, bail, err};
+use crate::output::WriteOptions;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeDelta, Timelike};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::time::Duration;
+
+const BASE_URL: &str = "https://oasis.caiso.com/oasisapi/SingleZip";
+
+/// The trading hubs averaged into the EIA files' price, in the same zones.
+pub const HUBS: [&str; 3] = ["TH_NP15_GEN-APND", "TH_SP15_GEN-APND", "TH_ZP26_GEN-APND"];
+
+/// OASIS turns away clients that ask more than about once every five seconds.
+pub const REQUEST_GAP: Duration = Duration::from_secs(5);
+
+/// What a system-wide item of an OASIS report measures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MixSource {
+    Solar,
+    Wind,
+    Load,
+}
+
+/// One system-wide value over one five-minute interval. Solar and wind
+/// come a trading hub at a time and are summed into the interval.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MixItem {
+    pub source: MixSource,
+    pub start: NaiveDateTime,
+    pub mw: f64,
+}
+
+/// One node's real-time LMP over one five-minute interval.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lmp {
+    pub node: String,
+    pub start: NaiveDateTime,
+    pub price: f64,
+}
+
+/// The URL of one report's data over one Pacific local day.
+fn report_url(query: &str, date: NaiveDate) -> String {
+    let fmt = "%Y%m%dT%H:%M-0000";
+    format!(
+        "{BASE_URL}?queryname={query}&startdatetime={}&enddatetime={}",
+        utc_of_pacific_midnight(date).format(fmt),
+        utc_of_pacific_midnight(date + TimeDelta::days(1)).format(fmt),
+    )
+}
+
+/// Downloads the LMPs at `nodes` for each day from `start` through `end`.
+/// OASIS caps how much interval data one request may cover, so each day is
+/// its own request.
+pub fn fetch_lmps(
+    client: &mut Client,
+    start: NaiveDate,
+    end: NaiveDate,
+    nodes: &[String],
+) -> error::Result<Vec<Lmp>> {
+    if end < start {
+        bail!(Invalid, "{end} is before {start}");
+    }
+    let mut lmps = Vec::new();
+    for date in start.iter_days().take_while(|date| *date <= end) {
+        println!("Fetching OASIS prices for {date}");
+        let url = format!(
+            "{}&version=3&market_run_id=RTM&node={}",
+            report_url("PRC_INTVL_LMP", date),
+            nodes.join(",")
+        );
+        let zip = client.get(&url)?;
+        lmps.extend(parse_zip(&zip).map_err(|e| err!(Remote, "{date}: {e:#}"))?);
+    }
+    Ok(lmps)
+}
+
+/// Downloads the real-time solar and wind output and the five-minute load
+/// forecast for each day from `start` through `end`, a day per request
+/// like `fetch_lmps`.
+pub fn fetch_mix(
+    client: &mut Client,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> error::Result<Vec<MixItem>> {
+    if end < start {
+        bail!(Invalid, "{end} is before {start}");
+    }
+    let mut mix = Vec::new();
+    for date in start.iter_days().take_while(|date| *date <= end) {
+        println!("Fetching OASIS solar, wind and load for {date}");
+        for url in [
+            format!(
+                "{}&version=1&market_run_id=RTD",
+                report_url("SLD_REN_FCST", date)
+            ),
+            format!(
+                "{}&version=1&market_run_id=RTM",
+                report_url("SLD_FCST", date)
+            ),
+        ] {
+            let zip = client.get(&url)?;
+            mix.extend(parse_mix_zip(&zip).map_err(|e| err!(Remote, "{date}: {e:#}"))?);
+        }
+    }
+    Ok(mix)
+}
+
+/// The XML reports in an OASIS zip.
+fn zip_reports(zip: &[u8]) -> error::Result<Vec<String>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(zip))?;
+    let mut reports = Vec::new();
+    for idx in 0..archive.len() {
+        let mut file = archive.by_index(idx)?;
+        if !file.name().to_ascii_lowercase().ends_with(".xml") {
+            continue;
+        }
+        let mut xml = String::new();
+        file.read_to_string(&mut xml)?;
+        reports.push(xml);
+    }
+    Ok(reports)
+}
+
+/// Reads the total LMP of every interval out of an OASIS zip.
+pub fn parse_zip(zip: &[u8]) -> error::Result<Vec<Lmp>> {
+    let mut lmps = Vec::new();
+    for xml in zip_reports(zip)? {
+        lmps.extend(parse_xml(&xml)?);
+    }
+    Ok(lmps)
+}
+
+/// Reads the total LMP (`LMP_PRC`) items of one OASIS report, leaving out
+/// the energy, congestion, loss and GHG components. Fails with OASIS's
+/// message when the report is an error.
+pub fn parse_xml(xml: &str) -> error::Result<Vec<Lmp>> {
+    report_data(xml)?
+        .iter()
+        .filter(|item| item.get("DATA_ITEM").map(String::as_str) == Some("LMP_PRC"))
+        .map(lmp_of)
+        .collect()
+}
+
+/// Reads the solar, wind and load items out of an OASIS zip.
+pub fn parse_mix_zip(zip: &[u8]) -> error::Result<Vec<MixItem>> {
+    let mut mix = Vec::new();
+    for xml in zip_reports(zip)? {
+        mix.extend(parse_mix_xml(&xml)?);
+    }
+    Ok(mix)
+}
+
+/// Reads the solar and wind items (those with a `RENEWABLE_TYPE`) and the
+/// system-wide five-minute load forecast (`SYS_FCST_5MIN_MW` for
+/// `CA ISO-TAC`, which the other TACs are parts of) out of one OASIS
+/// report. Fails with OASIS's message when the report is an error.
+pub fn parse_mix_xml(xml: &str) -> error::Result<Vec<MixItem>> {
+    let mut mix = Vec::new();
+    for item in report_data(xml)? {
+        let field = |name: &str| item.get(name).map(String::as_str);
+        let source = match (field("RENEWABLE_TYPE"), field("DATA_ITEM")) {
+            (Some("Solar"), _) => MixSource::Solar,
+            (Some("Wind"), _) => MixSource::Wind,
+            (None, Some("SYS_FCST_5MIN_MW")) if field("RESOURCE_NAME") == Some("CA ISO-TAC") => {
+                MixSource::Load
+            }
+            _ => continue,
+        };
+        mix.push(MixItem {
+            source,
+            start: interval_start(&item)?,
+            mw: value(&item)?,
+        });
+    }
+    Ok(mix)
+}
+
+/// The fields of every `REPORT_DATA` item of one OASIS report. Fails with
+/// OASIS's message when the report is an error.
+fn report_data(xml: &str) -> error::Result<Vec<BTreeMap<String, String>>> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut items = Vec::new();
+    let mut item: BTreeMap<String, String> = BTreeMap::new();
+    let mut tag = String::new();
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => {
+                tag = String::from_utf8_lossy(start.local_name().as_ref()).to_string();
+                if tag == "REPORT_DATA" {
+                    item.clear();
+                }
+            }
+            Event::Text(text) => {
+                item.insert(tag.clone(), text.decode()?.to_string());
+            }
+            Event::End(end) => match end.local_name().as_ref() {
+                b"REPORT_DATA" => items.push(item.clone()),
+                b"ERROR" => {
+                    let code = item.get("ERR_CODE").map_or("?", String::as_str);
+                    let desc = item.get("ERR_DESC").map_or("", String::as_str);
+                    bail!(Remote, "OASIS error {code}: {desc}");
+                }
+                _ => (),
+            },
+            Event::Eof => return Ok(items),
+            _ => (),
+        }
+    }
+}
+
+fn field<'a>(item: &'a BTreeMap<String, String>, name: &str) -> error::Result<&'a String> {
+    item.get(name)
+        .ok_or_else(|| err!(Remote, "An OASIS report item has no {name}"))
+}
+
+fn interval_start(item: &BTreeMap<String, String>) -> error::Result<NaiveDateTime> {
+    Ok(DateTime::parse_from_rfc3339(field(item, "INTERVAL_START_GMT")?)?.naive_utc())
+}
+
+fn value(item: &BTreeMap<String, String>) -> error::Result<f64> {
+    Ok(field(item, "VALUE")?.parse()?)
+}
+
+fn lmp_of(item: &BTreeMap<String, String>) -> error::Result<Lmp> {
+    Ok(Lmp {
+        node: field(item, "RESOURCE_NAME")?.clone(),
+        start: interval_start(item)?,
+        price: value(item)?,
+    })
+}
+
+/// Writes LMPs as a parsed price csv, averaging the nodes of each interval
+/// the way `convert_energy_price_csv` averages the EIA files' zones. With
+/// `append` they're added to the end of the csv instead, which should
+/// already be cut to intervals after its last (see `resume_after`).
+/// Returns how many intervals were written.
+pub fn write_prices(
+    output: &Path,
+    lmps: &[Lmp],
+    append: bool,
+    options: WriteOptions,
+) -> error::Result<usize> {
+    let mut intervals: BTreeMap<NaiveDateTime, (f64, usize)> = BTreeMap::new();
+    for lmp in lmps {
+        let (sum, count) = intervals.entry(lmp.start).or_default();
+        *sum += lmp.price;
+        *count += 1;
+    }
+    let mut csv = open(output, append, options)?;
+    let written = intervals.len();
+    for (utc, (sum, count)) in intervals {
+        let local = pacific_from_utc(utc);
+        csv.serialize(EnergyPriceCsvRow::new(
+            local.format(TIMESTAMP_FMT).to_string(),
+            local.hour(),
+            local.minute(),
+            sum / count as f64,
+        ))?;
+    }
+    Ok(written)
+}
+
+/// Writes solar, wind and load as a parsed gen csv. Load is the Total, and
+/// everything else serving it, imports included, is counted as Other, so
+/// shares of the Total are shares of load. Intervals without a load are
+/// left out. With `append` the rows are added to the end of the csv like
+/// `write_prices`. Returns how many intervals were written.
+pub fn write_gen(
+    output: &Path,
+    mix: &[MixItem],
+    append: bool,
+    options: WriteOptions,
+) -> error::Result<usize> {
+    let mut intervals: BTreeMap<NaiveDateTime, [Option<f64>; 3]> = BTreeMap::new();
+    for item in mix {
+        let mw = &mut intervals.entry(item.start).or_default()[item.source as usize];
+        *mw = Some(mw.unwrap_or(0.) + item.mw);
+    }
+    let idx = EnergyGenCsvRow::source_idx;
+    let (solar_idx, wind_idx, other_idx) = (idx("Solar")?, idx("Wind")?, idx("Other")?);
+    let mut csv = open(output, append, options)?;
+    let mut row = EnergyGenCsvRow::default();
+    let mut written = 0;
+    for (utc_start, [solar, wind, load]) in intervals {
+        let Some(load) = load else {
+            continue;
+        };
+        written += 1;
+        let (solar, wind) = (solar.unwrap_or(0.), wind.unwrap_or(0.));
+        let mut sources = [0.; 14];
+        sources[0] = load;
+        sources[solar_idx] = solar;
+        sources[wind_idx] = wind;
+        sources[other_idx] = load - solar - wind;
+        row.set_sources(sources);
+        let local_start = pacific_from_utc(utc_start);
+        row.utc_timestamp = (utc_start + TimeDelta::minutes(5))
+            .format(TIMESTAMP_FMT)
+            .to_string();
+        row.local_timestamp_start = local_start.format(TIMESTAMP_FMT).to_string();
+        row.local_timestamp_end = (local_start + TimeDelta::minutes(5))
+            .format(TIMESTAMP_FMT)
+            .to_string();
+        row.local_date = local_start.date().to_string();
+        row.hour = local_start.hour();
+        row.minute = local_start.minute();
+        csv.serialize(&row)?;
+    }
+    Ok(written)
+}
@@ -0,0 +1,160 @@
+//! ### Forecast
+//! Naive day-ahead price forecasts. They aren't meant to be good, only to
+//! set the bar any real forecasting model has to clear.
+
+use crate::error::{self, bail};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::compute::Compute;
+
+/// How a baseline predicts the price of one five-minute interval.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Baseline {
+    /// The price in the same slot the day before.
+    Yesterday,
+    /// The price in the same slot a week before.
+    LastWeek,
+    /// The average price in the same slot over the previous four weeks.
+    SeasonalAverage,
+}
+
+/// One interval's forecast next to what actually happened.
+#[derive(Clone, Copy, Debug)]
+pub struct Forecast {
+    pub time: NaiveDateTime,
+    pub actual: f64,
+    pub forecast: f64,
+}
+
+/// How far a baseline's forecasts landed from actual prices over a dataset.
+#[derive(Serialize, Debug)]
+pub struct ForecastError {
+    pub baseline: String,
+    pub intervals: usize,
+    /// Mean absolute error in $/MWh.
+    pub mae: f64,
+    /// Mean absolute percentage error, leaving out intervals whose actual
+    /// price is within $1 of zero, where percentages blow up. `None` when
+    /// every interval was left out.
+    pub mape: Option<f64>,
+}
+
+impl Baseline {
+    const SEASONAL_DAYS: i64 = 28;
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Baseline::Yesterday => "yesterday",
+            Baseline::LastWeek => "last-week",
+            Baseline::SeasonalAverage => "seasonal-average",
+        }
+    }
+
+    /// Predicts the price at `time` from `history`, or None when the
+    /// intervals it looks back at are missing.
+    pub fn predict(
+        self,
+        history: &HashMap<NaiveDateTime, f64>,
+        time: NaiveDateTime,
+    ) -> Option<f64> {
+        let days_ago = |days: i64| history.get(&(time - TimeDelta::days(days))).copied();
+        match self {
+            Baseline::Yesterday => days_ago(1),
+            Baseline::LastWeek => days_ago(7),
+            Baseline::SeasonalAverage => {
+                let past: Vec<f64> = (1..=Self::SEASONAL_DAYS).filter_map(days_ago).collect();
+                (!past.is_empty()).then(|| past.iter().sum::<f64>() / past.len() as f64)
+            }
+        }
+    }
+
+    /// Forecasts every interval of `series` that the baseline can see back
+    /// from, as if each day had been forecast the day before.
+    pub fn backtest(self, series: &[(NaiveDateTime, f64)]) -> Vec<Forecast> {
+        let history: HashMap<_, _> = series.iter().copied().collect();
+        series
+            .iter()
+            .filter_map(|&(time, actual)| {
+                let forecast = self.predict(&history, time)?;
+                Some(Forecast {
+                    time,
+                    actual,
+                    forecast,
+                })
+            })
+            .collect()
+    }
+
+    /// Forecasts each five-minute interval of the day after `series` ends.
+    pub fn next_day(
+        self,
+        series: &[(NaiveDateTime, f64)],
+    ) -> error::Result<Vec<(NaiveDateTime, f64)>> {
+        let Some(last) = series.iter().map(|(time, _)| time.date()).max() else {
+            bail!(NoData, "No prices to forecast from");
+        };
+        let history: HashMap<_, _> = series.iter().copied().collect();
+        let start = (last + TimeDelta::days(1)).and_time(NaiveTime::MIN);
+        let mut forecast = Vec::new();
+        for slot in 0..Compute::slots_per_day() {
+            let time = start + TimeDelta::minutes((slot * Compute::MINS_INCR) as i64);
+            let Some(price) = self.predict(&history, time) else {
+                bail!(
+                    TimeGap,
+                    "The {} baseline has no history for {time}",
+                    self.name()
+                );
+            };
+            forecast.push((time, price));
+        }
+        Ok(forecast)
+    }
+
+    /// MAE and MAPE of a backtest.
+    pub fn errors(self, forecasts: &[Forecast]) -> error::Result<ForecastError> {
+        if forecasts.is_empty() {
+            bail!(
+                NoData,
+                "Not enough history for the {} baseline",
+                self.name()
+            );
+        }
+        let mae = forecasts
+            .iter()
+            .map(|f| (f.actual - f.forecast).abs())
+            .sum::<f64>()
+            / forecasts.len() as f64;
+        let pcts: Vec<f64> = forecasts
+            .iter()
+            .filter(|f| f.actual.abs() >= 1.)
+            .map(|f| ((f.actual - f.forecast) / f.actual).abs())
+            .collect();
+        Ok(ForecastError {
+            baseline: self.name().to_string(),
+            intervals: forecasts.len(),
+            mae,
+            mape: (!pcts.is_empty()).then(|| 100. * pcts.iter().sum::<f64>() / pcts.len() as f64),
+        })
+    }
+}
+
+/// The forecasts on `date`, one per five-minute slot, or an error unless
+/// every slot was forecast.
+pub fn day_of(forecasts: &[Forecast], date: NaiveDate) -> error::Result<Vec<Forecast>> {
+    let day: Vec<Forecast> = forecasts
+        .iter()
+        .filter(|f| f.time.date() == date)
+        .copied()
+        .collect();
+    if day.len() != Compute::slots_per_day() {
+        bail!(
+            TimeGap,
+            "Only {} of {} intervals on {date} have a forecast",
+            day.len(),
+            Compute::slots_per_day()
+        );
+    }
+    Ok(day)
+}
@@ -0,0 +1,369 @@
+//! ### Frame
+//! A small columnar table: a time index plus any number of named f64
+//! columns. Price and gen datasets both load into one (see
+//! `Compute::frame`), so profiles and daily averages are written once and
+//! work on whichever columns are selected.
+
+use crate::compute::{Compute, Profile, ProfileAgg, SampleCheck};
+use crate::error::{self, bail, err};
+use crate::stats::{Clip, ClipBounds};
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta, Timelike};
+use std::ops::RangeInclusive;
+
+#[derive(Clone, Debug, Default)]
+pub struct Frame {
+    times: Vec<NaiveDateTime>,
+    names: Vec<String>,
+    columns: Vec<Vec<f64>>,
+}
+
+impl Frame {
+    /// An empty frame over `times`, which should be in order.
+    pub fn new(times: Vec<NaiveDateTime>) -> Self {
+        Self {
+            times,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a column, which must have a value at every time.
+    pub fn with_column(mut self, name: &str, values: Vec<f64>) -> error::Result<Self> {
+        if values.len() != self.times.len() {
+            bail!(
+                Invalid,
+                "Column {name} has {} values for {} times",
+                values.len(),
+                self.times.len()
+            );
+        }
+        self.names.push(name.to_string());
+        self.columns.push(values);
+        Ok(self)
+    }
+
+    /// This frame followed by `other`, which must have the same columns
+    /// and start after this one ends.
+    pub fn append(mut self, other: Frame) -> error::Result<Self> {
+        if other.names != self.names {
+            bail!(
+                Invalid,
+                "Can't append columns {:?} to {:?}",
+                other.names,
+                self.names
+            );
+        }
+        if let (Some(last), Some(first)) = (self.times.last(), other.times.first()) {
+            if first <= last {
+                bail!(Invalid, "Appended rows start at {first}, not after {last}");
+            }
+        }
+        self.times.extend(other.times);
+        for (column, more) in self.columns.iter_mut().zip(other.columns) {
+            column.extend(more);
+        }
+        Ok(self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    pub fn times(&self) -> &[NaiveDateTime] {
+        &self.times
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The values of a column, looked up case-insensitively.
+    pub fn column(&self, name: &str) -> error::Result<&[f64]> {
+        Ok(&self.columns[self.position(name)?])
+    }
+
+    /// A frame of only the named columns, in the order given.
+    pub fn select(&self, names: &[&str]) -> error::Result<Self> {
+        let mut frame = Self::new(self.times.clone());
+        for name in names {
+            let idx = self.position(name)?;
+            frame = frame.with_column(&self.names[idx], self.columns[idx].clone())?;
+        }
+        Ok(frame)
+    }
+
+    /// The rows that fall on `dates`.
+    pub fn between(&self, dates: RangeInclusive<NaiveDate>) -> Self {
+        let keep: Vec<usize> = (0..self.len())
+            .filter(|idx| dates.contains(&self.times[*idx].date()))
+            .collect();
+        self.rows(&keep)
+    }
+
+    /// The rows that don't fall on `dates`.
+    pub fn without(&self, dates: &[NaiveDate]) -> Self {
+        let keep: Vec<usize> = (0..self.len())
+            .filter(|idx| !dates.contains(&self.times[*idx].date()))
+            .collect();
+        self.rows(&keep)
+    }
+
+    /// The rows split into consecutive groups by `key` of their date, in
+    /// the order each key first appears.
+    pub fn split_by(&self, key: impl Fn(NaiveDate) -> String) -> Vec<(String, Self)> {
+        let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+        for (idx, time) in self.times.iter().enumerate() {
+            let label = key(time.date());
+            match groups.iter_mut().find(|(seen, _)| *seen == label) {
+                Some((_, rows)) => rows.push(idx),
+                None => groups.push((label, vec![idx])),
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(label, rows)| (label, self.rows(&rows)))
+            .collect()
+    }
+
+    /// The first and last days when they have fewer than a day's worth of
+    /// five-minute intervals, with how many they have. Datasets cut at
+    /// midday give the slots those days cover one more sample than the rest.
+    pub fn partial_days(&self) -> Vec<(NaiveDate, usize)> {
+        let (Some(first), Some(last)) = (self.times.first(), self.times.last()) else {
+            return Vec::new();
+        };
+        let mut edges = vec![first.date()];
+        if last.date() != first.date() {
+            edges.push(last.date());
+        }
+        edges
+            .into_iter()
+            .map(|date| {
+                let count = self.times.iter().filter(|time| time.date() == date).count();
+                (date, count)
+            })
+            .filter(|(_, count)| *count < Compute::slots_per_day())
+            .collect()
+    }
+
+    /// How many days other than `partial_days` have data in each slot of
+    /// `profile`.
+    pub fn full_days_per_slot(&self, profile: Profile) -> Vec<usize> {
+        let partial: Vec<NaiveDate> = self
+            .partial_days()
+            .into_iter()
+            .map(|(date, _)| date)
+            .collect();
+        let mut last_seen: Vec<Option<NaiveDate>> = vec![None; profile.slots()];
+        let mut days = vec![0; profile.slots()];
+        for time in &self.times {
+            let date = time.date();
+            if partial.contains(&date) {
+                continue;
+            }
+            let slot = profile.slot_of(*time);
+            if last_seen[slot] != Some(date) {
+                last_seen[slot] = Some(date);
+                days[slot] += 1;
+            }
+        }
+        days
+    }
+
+    /// The frame with one column winsorized by `clip`, along with the
+    /// bounds it was held to.
+    pub fn winsorize(&self, name: &str, clip: Clip) -> error::Result<(Self, ClipBounds)> {
+        let idx = self.position(name)?;
+        let bounds = clip.bounds(&self.columns[idx]);
+        let mut frame = self.clone();
+        for value in &mut frame.columns[idx] {
+            *value = bounds.apply(*value);
+        }
+        Ok((frame, bounds))
+    }
+
+    /// Every column linearly interpolated at `times`, which should be in
+    /// order. NaN values count as missing. A time is NaN in a column unless
+    /// it lands on one of the column's values or between two that are at
+    /// most `max_gap` apart, and times NaN in every column are left out.
+    pub fn interpolate(&self, times: &[NaiveDateTime], max_gap: Option<TimeDelta>) -> Self {
+        let columns: Vec<Vec<f64>> = self
+            .columns
+            .iter()
+            .map(|column| {
+                let known: Vec<(NaiveDateTime, f64)> = self
+                    .times
+                    .iter()
+                    .zip(column)
+                    .filter(|(_, value)| !value.is_nan())
+                    .map(|(time, value)| (*time, *value))
+                    .collect();
+                let mut idx = 0;
+                times
+                    .iter()
+                    .map(|&time| {
+                        while known.get(idx + 1).is_some_and(|(next, _)| *next <= time) {
+                            idx += 1;
+                        }
+                        match (known.get(idx), known.get(idx + 1)) {
+                            (Some(&(at, value)), _) if at == time => value,
+                            (Some(&(before, lo)), Some(&(after, hi)))
+                                if before < time
+                                    && max_gap.is_none_or(|gap| after - before <= gap) =>
+                            {
+                                let span = (after - before).num_seconds() as f64;
+                                let into = (time - before).num_seconds() as f64;
+                                lo + (hi - lo) * into / span
+                            }
+                            _ => f64::NAN,
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let keep: Vec<usize> = (0..times.len())
+            .filter(|idx| columns.iter().any(|column| !column[*idx].is_nan()))
+            .collect();
+        Self {
+            times: times.to_vec(),
+            names: self.names.clone(),
+            columns,
+        }
+        .rows(&keep)
+    }
+
+    /// Every column summarized by `agg` in each slot of `profile`, with how
+    /// many intervals landed in each, once the counts pass `check`.
+    pub fn profile(
+        &self,
+        profile: Profile,
+        check: SampleCheck,
+        agg: ProfileAgg,
+    ) -> error::Result<(Vec<Vec<f64>>, Vec<usize>)> {
+        let slot = |time| profile.slot_of(time);
+        let (values, counts) = match agg {
+            ProfileAgg::Mean => self.means_by(profile.slots(), slot),
+            _ => self.sorted_by(profile.slots(), slot, agg),
+        };
+        check.check(&counts, profile)?;
+        Ok((values, counts))
+    }
+
+    /// The average of every column in each `window_mins`-minute window of
+    /// the day. Every window needs data.
+    pub fn windowed(&self, window_mins: usize) -> error::Result<Vec<Vec<f64>>> {
+        let per_window = Compute::intervals_per_window(window_mins)?;
+        let (means, counts) = self.means_by(Compute::slots_per_day() / per_window, |time| {
+            Compute::time_to_idx_5min(time.hour(), time.minute()) / per_window
+        });
+        if counts.contains(&0) {
+            bail!(TimeGap, "No data for some of the day");
+        }
+        Ok(means)
+    }
+
+    /// The average of every column on each calendar day, in order.
+    pub fn daily(&self) -> Vec<(NaiveDate, Vec<f64>)> {
+        let mut days: Vec<(NaiveDate, Vec<f64>, usize)> = Vec::new();
+        for (row, time) in self.times.iter().enumerate() {
+            let date = time.date();
+            if days.last().is_none_or(|(last, _, _)| *last != date) {
+                days.push((date, vec![0.; self.columns.len()], 0));
+            }
+            let (_, totals, count) = days.last_mut().expect("just pushed");
+            for (total, column) in totals.iter_mut().zip(&self.columns) {
+                *total += column[row];
+            }
+            *count += 1;
+        }
+        days.into_iter()
+            .map(|(date, totals, count)| {
+                (date, totals.into_iter().map(|t| t / count as f64).collect())
+            })
+            .collect()
+    }
+
+    /// Averages every column into `buckets` buckets, returning the means of
+    /// each bucket and how many rows landed in it. Empty buckets are NaN.
+    fn means_by(
+        &self,
+        buckets: usize,
+        bucket: impl Fn(NaiveDateTime) -> usize,
+    ) -> (Vec<Vec<f64>>, Vec<usize>) {
+        let mut totals = vec![vec![0.; self.columns.len()]; buckets];
+        let mut counts = vec![0; buckets];
+        for (row, time) in self.times.iter().enumerate() {
+            let idx = bucket(*time);
+            for (total, column) in totals[idx].iter_mut().zip(&self.columns) {
+                *total += column[row];
+            }
+            counts[idx] += 1;
+        }
+        let means = totals
+            .into_iter()
+            .zip(&counts)
+            .map(|(totals, count)| totals.into_iter().map(|t| t / *count as f64).collect())
+            .collect();
+        (means, counts)
+    }
+
+    /// Like `means_by`, for order statistics. The first pass sends each row
+    /// to its bucket, and the second sorts one bucket's column at a time,
+    /// so only a bucket's worth of values is copied at once.
+    fn sorted_by(
+        &self,
+        buckets: usize,
+        bucket: impl Fn(NaiveDateTime) -> usize,
+        agg: ProfileAgg,
+    ) -> (Vec<Vec<f64>>, Vec<usize>) {
+        let mut rows: Vec<Vec<usize>> = vec![Vec::new(); buckets];
+        for (row, time) in self.times.iter().enumerate() {
+            rows[bucket(*time)].push(row);
+        }
+        let mut sorted = Vec::new();
+        let values = rows
+            .iter()
+            .map(|rows| {
+                self.columns
+                    .iter()
+                    .map(|column| {
+                        sorted.clear();
+                        sorted.extend(rows.iter().map(|row| column[*row]));
+                        sorted.sort_unstable_by(f64::total_cmp);
+                        agg.of_sorted(&sorted)
+                    })
+                    .collect()
+            })
+            .collect();
+        (values, rows.iter().map(Vec::len).collect())
+    }
+
+    /// A frame of only the rows at `keep`.
+    fn rows(&self, keep: &[usize]) -> Self {
+        Self {
+            times: keep.iter().map(|idx| self.times[*idx]).collect(),
+            names: self.names.clone(),
+            columns: self
+                .columns
+                .iter()
+                .map(|column| keep.iter().map(|idx| column[*idx]).collect())
+                .collect(),
+        }
+    }
+
+    fn position(&self, name: &str) -> error::Result<usize> {
+        self.names
+            .iter()
+            .position(|col| col.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                err!(
+                    Invalid,
+                    "No column {name:?}, expected one of {:?}",
+                    self.names
+                )
+            })
+    }
+}
@@ -0,0 +1,118 @@
+//! ### Gas
+//! The spark spread: what a gas plant clears after fuel, taken as the
+//! five-minute LMP less the plant's heat rate times that day's natural gas
+//! hub price. Hub prices are daily, read from a user-supplied csv (see
+//! `series`), and joined onto every interval of the day they cover.
+
+use crate::compute::{Compute, Profile, ProfileAgg, SampleCheck};
+use crate::error::{self, bail};
+use crate::frame::Frame;
+use crate::series::{self, SeriesLayout};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// How many days a hub price stands in for the days after it. Hubs don't
+/// trade over weekends and holidays, so gas for those days is bought on
+/// the last trading day before them.
+pub const MAX_CARRY_DAYS: i64 = 4;
+
+/// A typical combined-cycle plant's heat rate, in MMBtu per MWh.
+pub const DEFAULT_HEAT_RATE: f64 = 7.;
+
+/// Daily natural gas hub prices, in $/MMBtu.
+#[derive(Clone, Debug, Default)]
+pub struct GasPrices {
+    by_date: BTreeMap<NaiveDate, f64>,
+}
+
+impl GasPrices {
+    /// Reads the `column` of a daily price csv. Missing prices are left
+    /// out, and a date listed twice keeps its later price.
+    pub fn load(path: &Path, layout: &SeriesLayout, column: &str) -> error::Result<Self> {
+        let frame = series::load(path, layout, &[column.to_string()])?;
+        let by_date: BTreeMap<NaiveDate, f64> = frame
+            .times()
+            .iter()
+            .zip(frame.column(column)?)
+            .filter(|(_, price)| !price.is_nan())
+            .map(|(time, price)| (time.date(), *price))
+            .collect();
+        if by_date.is_empty() {
+            bail!(NoData, "No gas prices in {path:?}");
+        }
+        Ok(Self { by_date })
+    }
+
+    pub fn new(by_date: BTreeMap<NaiveDate, f64>) -> Self {
+        Self { by_date }
+    }
+
+    /// The price gas was bought at for `date`: that day's, or else the
+    /// latest within `MAX_CARRY_DAYS` before it.
+    pub fn on(&self, date: NaiveDate) -> Option<f64> {
+        let (last, price) = self.by_date.range(..=date).next_back()?;
+        ((date - *last).num_days() <= MAX_CARRY_DAYS).then_some(*price)
+    }
+}
+
+/// The average spark spread in each slot of a profile, beside the prices
+/// and fuel costs it comes from, all in $/MWh.
+#[derive(Clone, Debug)]
+pub struct SparkSpread {
+    pub prices: Vec<f64>,
+    pub fuel_costs: Vec<f64>,
+    pub spreads: Vec<f64>,
+    pub samples: Vec<usize>,
+    /// Days with prices but no gas price to join them to.
+    pub skipped_days: usize,
+}
+
+/// Every interval's LMP, fuel cost (`heat_rate` times the day's gas price)
+/// and spark spread, for the days of a price frame that have a gas price.
+pub fn spread_frame(prices: &Frame, gas: &GasPrices, heat_rate: f64) -> error::Result<Frame> {
+    if heat_rate <= 0. {
+        bail!(Invalid, "The heat rate must be positive, not {heat_rate}");
+    }
+    let (mut times, mut lmps, mut costs) = (Vec::new(), Vec::new(), Vec::new());
+    for (time, lmp) in prices.times().iter().zip(prices.column("Price")?) {
+        let Some(gas_price) = gas.on(time.date()) else {
+            continue;
+        };
+        times.push(*time);
+        lmps.push(*lmp);
+        costs.push(heat_rate * gas_price);
+    }
+    let spreads = lmps
+        .iter()
+        .zip(&costs)
+        .map(|(lmp, cost)| lmp - cost)
+        .collect();
+    Frame::new(times)
+        .with_column("Price", lmps)?
+        .with_column("Fuel cost", costs)?
+        .with_column("Spread", spreads)
+}
+
+/// The spark spread averaged over each slot of `profile`.
+pub fn spark_spread(
+    prices: &Compute,
+    gas: &GasPrices,
+    heat_rate: f64,
+    profile: Profile,
+) -> error::Result<SparkSpread> {
+    let prices = prices.select(&["Price"])?;
+    let frame = spread_frame(&prices, gas, heat_rate)?;
+    if frame.is_empty() {
+        bail!(NoData, "No day has both prices and a gas price");
+    }
+    let (slots, samples) = frame.profile(profile, SampleCheck::default(), ProfileAgg::Mean)?;
+    let column = |idx: usize| slots.iter().map(|slot| slot[idx]).collect();
+    Ok(SparkSpread {
+        prices: column(0),
+        fuel_costs: column(1),
+        spreads: column(2),
+        samples,
+        skipped_days: prices.daily().len() - frame.daily().len(),
+    })
+}
@@ -1,135 +1,4257 @@
 //! ### Graph
 //! Displays results from the `compute` module in shareable format.
 
-use anyhow::anyhow;
-use plotters::backend::BitMapBackend;
+use crate::error::{self, bail, err};
+use chrono::{NaiveDate, NaiveDateTime};
+use image::codecs::png::PngEncoder;
+use image::{ColorType, ImageEncoder};
+use plotters::backend::{BitMapBackend, SVGBackend};
 use plotters::chart::ChartBuilder;
+use plotters::chart::ChartContext;
 use plotters::chart::SeriesLabelPosition;
+use plotters::coord::ranged1d::Ranged;
+use plotters::coord::types::{RangedCoordf64, RangedCoordusize};
+use plotters::coord::Shift;
+use plotters::drawing::DrawingArea;
 use plotters::drawing::IntoDrawingArea;
+use plotters::element::DashedPathElement;
+use plotters::prelude::Cartesian2d;
+use plotters::prelude::Circle;
 use plotters::prelude::IntoSegmentedCoord;
+use plotters::prelude::PathElement;
+use plotters::prelude::Polygon;
+use plotters::prelude::RangedDate;
+use plotters::prelude::RangedDateTime;
 use plotters::prelude::Rectangle;
 use plotters::prelude::SegmentValue;
+use plotters::prelude::Text;
+use plotters::series::DashedLineSeries;
 use plotters::series::Histogram;
 use plotters::series::LineSeries;
+use plotters::series::PointSeries;
 use plotters::style::full_palette::BLUE_600;
+use plotters::style::full_palette::GREEN_600;
+use plotters::style::full_palette::GREY_300;
+use plotters::style::full_palette::GREY_400;
+use plotters::style::full_palette::ORANGE_600;
+use plotters::style::full_palette::PURPLE_600;
 use plotters::style::Color;
+use plotters::style::HSLColor;
+use plotters::style::IntoFont;
+use plotters::style::RGBAColor;
 use plotters::style::RGBColor;
+use plotters::style::ShapeStyle;
 use plotters::style::BLACK;
 use plotters::style::RED;
 use plotters::style::WHITE;
+use std::cell::Cell;
 use std::cmp::Ordering;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
-use crate::compute::Compute;
-use crate::convert::EnergyGenCsvRow;
+mod backend;
 
+use crate::ancillary::{StorageRevenue, ValueStack};
+use crate::compute::{
+    AreaPrices, BaInterchange, Band, Bin, Compute, DailyPeak, DailySpread, DayProfile,
+    DirectionalHour, EventDay, MarginBin, PeakTiming, PriceComponents, PriceExceedance, PricePoint,
+    PriceSpike, Profile, ScenarioSlot, SourcePriceCorrelation,
+};
+use crate::config::Labels;
+use crate::convert::{self, EnergyGenCsvRow, EnergyPriceCsvRow, TIMESTAMP_FMT};
+use crate::gas::SparkSpread;
+use crate::nodal::{NodeCongestion, NodeProfile};
+use crate::output::WriteOptions;
+use crate::scenario::{Revenue, StorageSweepRow};
+use crate::stats::{LinearFit, MeanDifference};
+use crate::tariff::TariffCost;
+use crate::weather::{WeatherDay, WeatherFits};
+use backend::Backend;
+
+pub use crate::chart::{
+    LegendPosition, Marker, PlotPoint, PowerUnit, ReferenceLine, SourceOrder, Theme, YScale,
+};
+
+impl Theme {
+    fn group_colors(self) -> [RGBColor; 5] {
+        match self {
+            Theme::Default => [BLUE_600, ORANGE_600, GREEN_600, PURPLE_600, RED],
+            Theme::Colorblind => [
+                RGBColor(0, 114, 178),
+                RGBColor(230, 159, 0),
+                RGBColor(0, 158, 115),
+                RGBColor(204, 121, 167),
+                RGBColor(213, 94, 0),
+            ],
+        }
+    }
+}
+
+impl YScale {
+    /// The value at a tick on the axis. Ticks are spaced evenly along the
+    /// axis, so off a linear one they're rounded to two significant figures
+    /// to read as the round numbers they nearly are.
+    fn tick(self, pos: f64) -> f64 {
+        let value = self.inverse(pos);
+        if self == YScale::Linear || value == 0. {
+            return value;
+        }
+        let scale = 10f64.powi(value.abs().log10().floor() as i32 - 1);
+        (value / scale).round() * scale
+    }
+
+    /// The axis range covering `low` to `high`.
+    fn range(self, low: f64, high: f64) -> Range<f64> {
+        self.forward(low)..self.forward(high)
+    }
+}
+
+impl PowerUnit {
+    /// A unit label given in MW or MWh, like the labels config's, in this
+    /// unit.
+    fn desc(self, mw_desc: &str) -> String {
+        match self {
+            PowerUnit::MW => mw_desc.to_string(),
+            PowerUnit::GW => mw_desc.replacen('M', "G", 1),
+        }
+    }
+}
+
+impl PlotPoint {
+    fn new(series: impl Into<String>, x: impl ToString, y: f64) -> Self {
+        Self {
+            series: series.into(),
+            x: x.to_string(),
+            y,
+        }
+    }
+}
+
+/// Percentile bands to shade behind one source's line on `daily_gen`.
+pub struct SourceBand<'b> {
+    pub source: usize,
+    pub bands: &'b [[Band; 14]],
+}
+
+/// The value range and label of a chart's y axis.
+struct YAxis<'d> {
+    range: Range<f64>,
+    desc: &'d str,
+}
+
+/// A region of the image a chart draws into.
+type Area<'a> = DrawingArea<Backend<'a>, Shift>;
+
+/// Pixels of a chart drawn in memory, three RGB bytes per pixel, row by
+/// row from the top left.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Bitmap {
+    /// The RGB color at `(x, y)`.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let at = (y as usize * self.width as usize + x as usize) * 3;
+        [self.pixels[at], self.pixels[at + 1], self.pixels[at + 2]]
+    }
+
+    /// The fraction of pixels whose color differs from `other`'s by more
+    /// than `tolerance` in any channel. Bitmaps of different sizes differ
+    /// everywhere.
+    pub fn difference(&self, other: &Bitmap, tolerance: u8) -> f64 {
+        if (self.width, self.height) != (other.width, other.height) {
+            return 1.;
+        }
+        let pixels = (self.width as usize * self.height as usize).max(1);
+        let differing = self
+            .pixels
+            .chunks_exact(3)
+            .zip(other.pixels.chunks_exact(3))
+            .filter(|(a, b)| {
+                a.iter()
+                    .zip(b.iter())
+                    .any(|(a, b)| a.abs_diff(*b) > tolerance)
+            })
+            .count();
+        differing as f64 / pixels as f64
+    }
+
+    /// The bitmap encoded as a png.
+    pub fn to_png(&self) -> error::Result<Vec<u8>> {
+        let mut png = Vec::new();
+        PngEncoder::new(&mut png)
+            .write_image(&self.pixels, self.width, self.height, ColorType::Rgb8)
+            .map_err(|e| err!(Render, "{e}"))?;
+        Ok(png)
+    }
+}
+
+/// How the `_to_buffer` methods encode a chart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    Svg,
+}
+
+impl ImageFormat {
+    /// Svg for `svg` and png for anything else, the way chart paths are
+    /// read.
+    pub fn from_extension(ext: &str) -> Self {
+        match ext.eq_ignore_ascii_case("svg") {
+            true => ImageFormat::Svg,
+            false => ImageFormat::Png,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Svg => "image/svg+xml",
+        }
+    }
+}
+
+/// What a chart is titled and labeled, how big it's drawn and in which
+/// palette. Each chart method picks its own title, axis labels and size,
+/// and any set here replace them. On charts of several panels, the axis
+/// labels replace every panel's.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChartOptions {
+    pub title: Option<String>,
+    pub x_label: Option<String>,
+    pub y_label: Option<String>,
+    /// Width and height in pixels.
+    pub size: Option<(u32, u32)>,
+    pub theme: Theme,
+}
+
+/// Where a chart is drawn.
+enum Target<'a> {
+    File(&'a Path),
+    /// Taken by the first chart drawn, since a bitmap holds one.
+    Bitmap(Cell<Option<&'a mut Bitmap>>),
+    /// Taken by the first chart drawn, like a bitmap.
+    Svg(Cell<Option<&'a mut String>>),
+    /// Only drawn to through `to_buffer`, which picks a target per chart.
+    Buffer,
+}
+
+/// Draws charts of computed results, to a png or svg file named by its
+/// extension, into a `Bitmap`, or into encoded bytes.
+///
+/// ```
+/// use energy_analysis::prelude::*;
+///
+/// # fn main() -> Result<(), EnergyAnalysisError> {
+/// let prices: Vec<f64> = (0..Compute::slots_per_day())
+///     .map(|slot| 40. + (slot % 144) as f64 / 4.)
+///     .collect();
+///
+/// let mut bitmap = Bitmap::default();
+/// Graphing::to_bitmap(&mut bitmap).daily_price(&prices, Profile::Day)?;
+/// assert!(bitmap.width > 0 && bitmap.height > 0);
+///
+/// let png = Graphing::in_memory()
+///     .with_theme(Theme::Colorblind)
+///     .daily_price_to_buffer(&prices, Profile::Day, ImageFormat::Png)?;
+/// assert!(png.starts_with(b"\x89PNG"));
+/// # Ok(())
+/// # }
+/// ```
 pub struct Graphing<'a> {
-    path: &'a Path,
+    target: Target<'a>,
+    options: ChartOptions,
+    markers: Vec<Marker>,
+    references: Vec<ReferenceLine>,
+    source_order: SourceOrder,
+    legend: LegendPosition,
+    highlight: Option<usize>,
+    labels: Labels,
+    footer: Option<String>,
+    y_scale: YScale,
+    y_min: Option<f64>,
+    y_max: Option<f64>,
+    unit: PowerUnit,
+    also_csv: bool,
+    write: WriteOptions,
 }
 
-impl<'a> Graphing<'a> {
-    const CHART_COLOR: RGBColor = WHITE;
+impl<'a> Graphing<'a> {
+    const CHART_COLOR: RGBColor = WHITE;
+
+    pub fn new(path: &'a Path) -> Self {
+        Self::drawing_to(Target::File(path))
+    }
+
+    /// Draws the next chart into `bitmap` instead of a file, such as to
+    /// compare it with a snapshot. Nothing is written to disk, so
+    /// `with_also_csv` has no effect.
+    pub fn to_bitmap(bitmap: &'a mut Bitmap) -> Self {
+        Self::drawing_to(Target::Bitmap(Cell::new(Some(bitmap))))
+    }
+
+    /// Draws nowhere on its own. Charts are returned as encoded bytes by
+    /// `to_buffer` and the `_to_buffer` methods, such as for the server or
+    /// to embed in a report without writing temp files.
+    pub fn in_memory() -> Self {
+        Self::drawing_to(Target::Buffer)
+    }
+
+    fn drawing_to(target: Target<'a>) -> Self {
+        Graphing {
+            target,
+            options: ChartOptions::default(),
+            markers: Vec::new(),
+            references: Vec::new(),
+            source_order: SourceOrder::default(),
+            legend: LegendPosition::default(),
+            highlight: None,
+            labels: Labels::default(),
+            footer: None,
+            y_scale: YScale::default(),
+            y_min: None,
+            y_max: None,
+            unit: PowerUnit::default(),
+            also_csv: false,
+            write: WriteOptions::default(),
+        }
+    }
+
+    /// Titles, labels, sizes and colors every chart drawn by this
+    /// `Graphing`, in place of the theme given to `with_theme`.
+    pub fn with_options(mut self, options: ChartOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.options.theme = theme;
+        self
+    }
+
+    pub fn with_labels(mut self, labels: Labels) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Writes a line of small print along the bottom of every chart, such
+    /// as where its data came from (see `Provenance::footer`).
+    pub fn with_footer(mut self, footer: Option<String>) -> Self {
+        self.footer = footer;
+        self
+    }
+
+    /// Maps the y axis of the charts that support it (see `YScale`).
+    pub fn with_y_scale(mut self, y_scale: YScale) -> Self {
+        self.y_scale = y_scale;
+        self
+    }
+
+    /// Fixes either end of the y axis instead of fitting it to the data, so
+    /// charts drawn from different data can be compared side by side. The
+    /// ends are in the units the chart is labeled in.
+    pub fn with_y_limits(mut self, y_min: Option<f64>, y_max: Option<f64>) -> Self {
+        self.y_min = y_min;
+        self.y_max = y_max;
+        self
+    }
+
+    /// Draws generation in `unit` instead of MW.
+    pub fn with_unit(mut self, unit: PowerUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Also writes the values each chart draws to a csv beside it (see
+    /// `Graphing::csv_path`), so the numbers behind a figure can be checked.
+    pub fn with_also_csv(mut self, also_csv: bool) -> Self {
+        self.also_csv = also_csv;
+        self
+    }
+
+    /// Whether charts and their csvs may replace files already there, and
+    /// how many decimals those csvs keep.
+    pub fn with_write_options(mut self, write: WriteOptions) -> Self {
+        self.write = write;
+        self
+    }
+
+    /// Where `with_also_csv` writes a chart's values. Empty for a chart
+    /// drawn in memory.
+    pub fn csv_path(&self) -> PathBuf {
+        match &self.target {
+            Target::File(path) => path.with_extension("csv"),
+            _ => PathBuf::new(),
+        }
+    }
+
+    /// Draws one chart with `draw` and returns it encoded as `format`,
+    /// styled like this `Graphing` but without touching disk.
+    pub fn to_buffer(
+        &self,
+        format: ImageFormat,
+        draw: impl FnOnce(&Graphing) -> error::Result<()>,
+    ) -> error::Result<Vec<u8>> {
+        match format {
+            ImageFormat::Png => {
+                let mut bitmap = Bitmap::default();
+                draw(&self.redirected(Target::Bitmap(Cell::new(Some(&mut bitmap)))))?;
+                bitmap.to_png()
+            }
+            ImageFormat::Svg => {
+                let mut svg = String::new();
+                // The svg is only finished once its backend is dropped.
+                draw(&self.redirected(Target::Svg(Cell::new(Some(&mut svg)))))?;
+                Ok(svg.into_bytes())
+            }
+        }
+    }
+
+    /// `daily_price`, encoded as `format`.
+    pub fn daily_price_to_buffer(
+        &self,
+        prices: &[f64],
+        profile: Profile,
+        format: ImageFormat,
+    ) -> error::Result<Vec<u8>> {
+        self.to_buffer(format, |graphing| graphing.daily_price(prices, profile))
+    }
+
+    /// `daily_gen`, encoded as `format`.
+    pub fn daily_gen_to_buffer(
+        &self,
+        gen: &[[f64; 14]],
+        profile: Profile,
+        title: &str,
+        band: Option<SourceBand>,
+        format: ImageFormat,
+    ) -> error::Result<Vec<u8>> {
+        self.to_buffer(format, |graphing| {
+            graphing.daily_gen(gen, profile, title, band)
+        })
+    }
+
+    /// `avg_value`, encoded as `format`.
+    pub fn avg_value_to_buffer(
+        &self,
+        values: &[f64; 14],
+        title: &str,
+        format: ImageFormat,
+    ) -> error::Result<Vec<u8>> {
+        self.to_buffer(format, |graphing| graphing.avg_value(values, title))
+    }
+
+    /// This `Graphing`'s style, drawing to `target` instead.
+    fn redirected<'b>(&self, target: Target<'b>) -> Graphing<'b> {
+        Graphing {
+            target,
+            options: self.options.clone(),
+            markers: self.markers.clone(),
+            references: self.references.clone(),
+            source_order: self.source_order.clone(),
+            legend: self.legend,
+            highlight: self.highlight,
+            labels: self.labels.clone(),
+            footer: self.footer.clone(),
+            y_scale: self.y_scale,
+            y_min: self.y_min,
+            y_max: self.y_max,
+            unit: self.unit,
+            also_csv: false,
+            write: self.write,
+        }
+    }
+
+    /// Writes the `points` a chart draws when `with_also_csv` is on. They're
+    /// only gathered then.
+    fn also_csv(&self, points: impl FnOnce() -> Vec<PlotPoint>) -> error::Result<()> {
+        match (&self.target, self.also_csv) {
+            (Target::File(_), true) => {
+                convert::write_plotted(&self.csv_path(), &points(), self.write)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// A point per source, leaving out the total, in every slot of `gen`.
+    fn source_points(&self, gen: &[[f64; 14]], x: impl Fn(usize) -> String) -> Vec<PlotPoint> {
+        self.source_keys()
+            .enumerate()
+            .skip(1)
+            .flat_map(|(src_idx, (source, _))| {
+                let x = &x;
+                gen.iter()
+                    .enumerate()
+                    .map(move |(idx, slot)| PlotPoint::new(source, x(idx), slot[src_idx]))
+            })
+            .collect()
+    }
+
+    /// The y axis for data spanning `range`, less any end fixed by
+    /// `with_y_limits`.
+    fn y_range(&self, range: Range<f64>) -> Range<f64> {
+        self.y_min.unwrap_or(range.start)..self.y_max.unwrap_or(range.end)
+    }
+
+    /// The y axis of a price chart for prices spanning `low` to `high`.
+    fn price_range(&self, low: f64, high: f64) -> Range<f64> {
+        let range = self.y_range(low..high);
+        self.y_scale.range(range.start, range.end)
+    }
+
+    /// Generation in the chart's unit.
+    fn in_unit(&self, gen: &[[f64; 14]]) -> Vec<[f64; 14]> {
+        gen.iter()
+            .map(|slot| slot.map(|mw| self.unit.of(mw)))
+            .collect()
+    }
+
+    /// Sources with their display names from the labels config.
+    fn source_keys(&self) -> impl ExactSizeIterator<Item = (&str, RGBColor)> + '_ {
+        let labels = &self.labels;
+        EnergyGenCsvRow::source_keys().map(move |(key, color)| (labels.source(key), color))
+    }
+
+    /// Marks times on the charts that support them: `daily_price`, `daily_gen`,
+    /// `daily_value` and `gen_share`. Adds to any marked already.
+    pub fn with_markers(mut self, markers: Vec<Marker>) -> Self {
+        self.markers.extend(markers);
+        self
+    }
+
+    /// Orders the sources of `daily_gen`, `gen_animation` and `daily_value`.
+    pub fn with_source_order(mut self, source_order: SourceOrder) -> Self {
+        self.source_order = source_order;
+        self
+    }
+
+    /// Places the legend of `daily_gen`, `gen_animation` and `daily_value`.
+    pub fn with_legend(mut self, legend: LegendPosition) -> Self {
+        self.legend = legend;
+        self
+    }
+
+    /// Draws one source of `daily_gen`, `gen_animation` and `daily_value`
+    /// in its color over the rest in grey, by its index in
+    /// `EnergyGenCsvRow::source_keys`.
+    pub fn with_highlight(mut self, highlight: Option<usize>) -> Self {
+        self.highlight = highlight;
+        self
+    }
+
+    /// How the line of a source is drawn, dimmed when another is
+    /// highlighted.
+    fn source_style(&self, src_idx: usize, color: RGBColor) -> ShapeStyle {
+        match self.highlight {
+            Some(highlight) if highlight != src_idx => GREY_400.mix(0.6).stroke_width(2),
+            Some(_) => color.stroke_width(4),
+            None => color.stroke_width(3),
+        }
+    }
+
+    /// The indices of the sources in `gen`, leaving out the total, in the
+    /// order `with_source_order` asks for.
+    fn ordered_sources(&self, gen: &[[f64; 14]]) -> Vec<usize> {
+        let mut sources: Vec<usize> = (1..EnergyGenCsvRow::source_keys().len()).collect();
+        match &self.source_order {
+            SourceOrder::Schema => {}
+            SourceOrder::Magnitude => {
+                let mean = |src_idx: usize| {
+                    gen.iter().map(|slot| slot[src_idx]).sum::<f64>() / gen.len() as f64
+                };
+                sources.sort_by(|a, b| mean(*b).total_cmp(&mean(*a)));
+            }
+            SourceOrder::Given(given) => {
+                let rank = |src_idx: &usize| {
+                    given
+                        .iter()
+                        .position(|idx| idx == src_idx)
+                        .unwrap_or(given.len())
+                };
+                // Stable, so sources left out keep their schema order.
+                sources.sort_by_key(rank);
+            }
+        }
+        sources
+    }
+
+    /// Draws `references` across the charts with a single plot over a y axis
+    /// of values, which is all but the faceted, paneled, histogram, heat
+    /// map and horizontal bar charts.
+    pub fn with_references(mut self, references: Vec<ReferenceLine>) -> Self {
+        self.references = references;
+        self
+    }
+
+    /// Draws each reference line within the y axis across the chart's plot,
+    /// over its series, with its label above the line's left end. Values
+    /// are placed on the y axis by `scale`.
+    fn draw_references<X>(
+        &self,
+        chart: &ChartContext<Backend<'a>, Cartesian2d<X, RangedCoordf64>>,
+        scale: YScale,
+    ) -> error::Result<()>
+    where
+        X: Ranged,
+        X::ValueType: Clone,
+    {
+        let (x, y_range) = (chart.x_range().start, chart.y_range());
+        let plot = chart.plotting_area();
+        let ((_, top), (width, _)) = (plot.get_base_pixel(), plot.dim_in_pixel());
+        let pixels = plot.strip_coord_spec();
+        for reference in &self.references {
+            let y = scale.forward(reference.value);
+            if !(y_range.start..=y_range.end).contains(&y) {
+                continue;
+            }
+            let (_, row) = chart.backend_coord(&(x.clone(), y));
+            let row = row - top;
+            pixels.draw(&DashedPathElement::new(
+                [(0, row), (width as i32, row)],
+                10,
+                6,
+                BLACK.mix(0.5).stroke_width(2),
+            ))?;
+            pixels.draw_text(
+                &reference.label,
+                &("sans-serif", 16).into_font().color(&BLACK.mix(0.8)),
+                (8, row - 22),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn draw_markers(
+        &self,
+        chart: &mut ChartContext<Backend, Cartesian2d<RangedCoordusize, RangedCoordf64>>,
+        y_range: Range<f64>,
+    ) -> error::Result<()> {
+        for marker in &self.markers {
+            chart.draw_series(DashedLineSeries::new(
+                [(marker.slot, y_range.start), (marker.slot, y_range.end)],
+                8,
+                6,
+                BLACK.mix(0.6).stroke_width(2),
+            ))?;
+            chart.draw_series([Text::new(
+                marker.label.clone(),
+                (marker.slot + 2, y_range.end),
+                ("sans-serif", 16).into_font().color(&BLACK.mix(0.8)),
+            )])?;
+        }
+        Ok(())
+    }
+
+    /// The chart's title, unless `ChartOptions` gives another.
+    fn title(&self, default: impl Into<String>) -> String {
+        self.options.title.clone().unwrap_or_else(|| default.into())
+    }
+
+    fn x_desc(&self, default: impl Into<String>) -> String {
+        self.options
+            .x_label
+            .clone()
+            .unwrap_or_else(|| default.into())
+    }
+
+    fn y_desc(&self, default: impl Into<String>) -> String {
+        self.options
+            .y_label
+            .clone()
+            .unwrap_or_else(|| default.into())
+    }
+
+    /// The chart's size, unless `ChartOptions` gives another.
+    fn size(&self, default: (u32, u32)) -> (u32, u32) {
+        self.options.size.unwrap_or(default)
+    }
+
+    /// Lists `entries` down `area`, level with the top of a plot beside it,
+    /// with a swatch for filled styles and a line for the rest.
+    fn side_legend(area: &Area<'a>, entries: &[(String, ShapeStyle)]) -> error::Result<()> {
+        const TOP: i32 = 80;
+        const ROW: i32 = 22;
+        let font = ("sans-serif", 16).into_font().color(&BLACK);
+        for (row, (label, style)) in entries.iter().enumerate() {
+            let y = TOP + ROW * row as i32;
+            match style.filled {
+                true => area.draw(&Rectangle::new([(4, y - 6), (16, y + 6)], *style))?,
+                false => area.draw(&PathElement::new([(4, y), (16, y)], *style))?,
+            }
+            area.draw_text(label, &font, (24, y - 8))?;
+        }
+        Ok(())
+    }
+
+    /// Weekly profiles get a wider chart so each day stays readable.
+    fn profile_size(profile: Profile) -> (u32, u32) {
+        match profile {
+            Profile::Day => (1080, 720),
+            Profile::Week => (2160, 720),
+        }
+    }
+
+    fn profile_desc(profile: Profile) -> &'static str {
+        match profile {
+            Profile::Day => "Time of day",
+            Profile::Week => "Hour of week",
+        }
+    }
+
+    /// A label every hour of the day. Weekly charts get a tick every hour,
+    /// which plotters can't space at six-hour steps on its own, so all but
+    /// every sixth is left blank in `profile_tick`.
+    fn profile_labels(profile: Profile) -> usize {
+        match profile {
+            Profile::Day => 24,
+            Profile::Week => profile.slots(),
+        }
+    }
+
+    fn profile_tick(profile: Profile, idx: usize) -> String {
+        match profile {
+            Profile::Week if !idx.is_multiple_of(6) => String::new(),
+            _ => profile.label(idx),
+        }
+    }
+
+    /// Fills in the chart's background and writes any footer along the
+    /// bottom, returning the area above the footer for the chart itself.
+    fn canvas(
+        &self,
+        root: &DrawingArea<Backend<'a>, Shift>,
+    ) -> error::Result<DrawingArea<Backend<'a>, Shift>> {
+        const FOOTER_HEIGHT: u32 = 28;
+        root.fill(&Self::CHART_COLOR)?;
+        let Some(footer) = &self.footer else {
+            return Ok(root.clone());
+        };
+        let (_, height) = root.dim_in_pixel();
+        let (chart, strip) = root.split_vertically(height.saturating_sub(FOOTER_HEIGHT));
+        strip.draw_text(
+            footer,
+            &("sans-serif", 16).into_font().color(&BLACK.mix(0.6)),
+            (12, 4),
+        )?;
+        Ok(chart)
+    }
+
+    /// Draws to an svg when the output path ends in `.svg`, otherwise to a
+    /// bitmap in the format of its extension, or into the bitmap given to
+    /// `to_bitmap`.
+    fn backend(&self, size: (u32, u32)) -> error::Result<Backend<'a>> {
+        let size = self.size(size);
+        let bitmap = match &self.target {
+            Target::File(path) => {
+                self.write.check(path)?;
+                return Backend::new(path, size);
+            }
+            Target::Bitmap(bitmap) => bitmap.take(),
+            Target::Svg(svg) => match svg.take() {
+                Some(svg) => return Ok(Backend::Svg(SVGBackend::with_string(svg, size))),
+                None => bail!(Invalid, "An svg holds one chart, and it's drawn"),
+            },
+            Target::Buffer => bail!(
+                Invalid,
+                "An in-memory Graphing draws through to_buffer or the _to_buffer methods"
+            ),
+        };
+        let Some(Bitmap {
+            width,
+            height,
+            pixels,
+        }) = bitmap
+        else {
+            return Err(err!(Invalid, "A bitmap holds one chart, and it's drawn"));
+        };
+        (*width, *height) = size;
+        pixels.clear();
+        pixels.resize(size.0 as usize * size.1 as usize * 3, 0);
+        Ok(Backend::Bitmap(BitMapBackend::with_buffer(pixels, size)))
+    }
+
+    /// Maps a correlation in [-1, 1] onto a blue-white-red diverging scale.
+    fn diverging_color(val: f64) -> RGBColor {
+        if val.is_nan() {
+            return GREY_300;
+        }
+        let val = val.clamp(-1., 1.);
+        let fade = |strength: f64| (255. * (1. - strength.abs())) as u8;
+        if val < 0. {
+            RGBColor(fade(val), fade(val), 255)
+        } else {
+            RGBColor(255, fade(val), fade(val))
+        }
+    }
+
+    /// Draws average price over a day, or a week on a wider chart.
+    pub fn daily_price(&self, prices: &[f64], profile: Profile) -> error::Result<()> {
+        let root = self
+            .backend(Self::profile_size(profile))?
+            .into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let max_price = prices.iter().fold(prices[0], |acc, el| el.max(acc));
+        let y_range = self.price_range(0., max_price);
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(72)
+            .margin(20)
+            .caption(
+                self.title(match profile {
+                    Profile::Day => "Daily average price/MWh",
+                    Profile::Week => "Weekly average price/MWh",
+                }),
+                ("sans-serif", 40.),
+            )
+            .build_cartesian_2d(0..(prices.len()), y_range.clone())?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .bold_line_style(WHITE.mix(0.3))
+            .y_desc(self.y_desc(&self.labels.units.price))
+            .x_desc(self.x_desc(Self::profile_desc(profile)))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|&idx| Self::profile_tick(profile, idx))
+            .y_label_formatter(&|price| format!("${:02}", self.y_scale.tick(*price)))
+            .x_labels(Self::profile_labels(profile))
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        chart.draw_series(
+            Histogram::vertical(&chart)
+                .style(RED.mix(0.5).filled())
+                .data(
+                    prices
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, &val)| (idx, self.y_scale.forward(val))),
+                ),
+        )?;
+        self.draw_references(&chart, self.y_scale)?;
+
+        self.draw_markers(&mut chart, y_range)?;
+
+        self.also_csv(|| {
+            prices
+                .iter()
+                .enumerate()
+                .map(|(idx, price)| PlotPoint::new("Price", profile.label(idx), *price))
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws how often prices landed in each of `bins`, with bins below zero
+    /// in a second color. With `log_scale`, bar heights are the log of
+    /// their counts so rare spikes stay visible beside the common prices.
+    pub fn price_histogram(&self, bins: &[Bin], width: f64, log_scale: bool) -> error::Result<()> {
+        let (Some(first), Some(last)) = (bins.first(), bins.last()) else {
+            return Err(err!(NoData, "No bins to graph"));
+        };
+        let root = self.backend((1080, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+        let [positive, negative, ..] = self.options.theme.group_colors();
+
+        // Empty bins have no height on a log scale, so bars rise from half a
+        // count to keep bins with a single interval visible.
+        let floor = if log_scale { 0.5f64.log10() } else { 0. };
+        let height = |count: usize| match log_scale {
+            true => (count as f64).log10(),
+            false => count as f64,
+        };
+        let max_height = bins
+            .iter()
+            .filter(|bin| bin.count > 0)
+            .map(|bin| height(bin.count))
+            .fold(0., f64::max);
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(96)
+            .margin(20)
+            .caption(
+                self.title("Distribution of five-minute prices"),
+                ("sans-serif", 40.),
+            )
+            .build_cartesian_2d(
+                first.low..(last.low + width),
+                floor..(max_height * 1.05).max(1.),
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(match log_scale {
+                true => "Intervals (log scale)",
+                false => "Intervals",
+            }))
+            .x_desc(self.x_desc(&self.labels.units.price))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|price| format!("${price:.0}"))
+            .y_label_formatter(&|height| match log_scale {
+                true => format!("{:.0}", 10f64.powf(*height)),
+                false => format!("{height:.0}"),
+            })
+            .x_labels(20)
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+
+        chart.draw_series(bins.iter().filter(|bin| bin.count > 0).map(|bin| {
+            let color = if bin.low < 0. { negative } else { positive };
+            Rectangle::new(
+                [(bin.low, floor), (bin.low + width, height(bin.count))],
+                color.mix(0.8).filled(),
+            )
+        }))?;
+
+        self.also_csv(|| {
+            bins.iter()
+                .map(|bin| PlotPoint::new("Intervals", bin.low, bin.count as f64))
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws average generation by source over a day, or a week on a wider
+    /// chart, optionally shading one source's p10-p90 band behind its line.
+    pub fn daily_gen(
+        &self,
+        gen: &[[f64; 14]],
+        profile: Profile,
+        title: &str,
+        band: Option<SourceBand>,
+    ) -> error::Result<()> {
+        let root = self
+            .backend(Self::profile_size(profile))?
+            .into_drawing_area();
+
+        let gen = self.in_unit(gen);
+        let bands: Option<Vec<[Band; 14]>> = band.as_ref().map(|band| {
+            band.bands
+                .iter()
+                .map(|slot| {
+                    slot.map(|band| Band {
+                        p10: self.unit.of(band.p10),
+                        p50: self.unit.of(band.p50),
+                        p90: self.unit.of(band.p90),
+                    })
+                })
+                .collect()
+        });
+        let band = band.zip(bands.as_deref()).map(|(band, bands)| SourceBand {
+            source: band.source,
+            bands,
+        });
+
+        let (gen_min, gen_max) = Self::gen_range(&gen)?;
+        let (gen_min, gen_max) = band.as_ref().map_or((gen_min, gen_max), |band| {
+            band.bands.iter().fold((gen_min, gen_max), |acc, slot| {
+                let slot = slot[band.source];
+                (acc.0.min(slot.p10), acc.1.max(slot.p90))
+            })
+        });
+        let pad = self.unit.of(250.);
+        self.also_csv(|| {
+            let mut rows = self.source_points(&gen, |idx| profile.label(idx));
+            if let Some(band) = &band {
+                let source = self
+                    .source_keys()
+                    .nth(band.source)
+                    .map_or("", |(key, _)| key);
+                for (idx, slot) in band.bands.iter().enumerate() {
+                    let slot = slot[band.source];
+                    for (stat, value) in [("p10", slot.p10), ("p50", slot.p50), ("p90", slot.p90)] {
+                        let series = format!("{source} {stat}");
+                        rows.push(PlotPoint::new(series, profile.label(idx), value));
+                    }
+                }
+            }
+            rows
+        })?;
+
+        self.draw_sources(
+            &root,
+            &gen,
+            profile,
+            &self.title(title),
+            band,
+            YAxis {
+                range: self.y_range((gen_min - pad)..(gen_max + pad)),
+                desc: &self.unit.desc(&self.labels.units.energy),
+            },
+        )
+    }
+
+    /// Animates the average day of generation through each labeled frame,
+    /// e.g. one per month, holding the y axis fixed across frames so the
+    /// curves' changes through the year are visible.
+    pub fn gen_animation(
+        &self,
+        frames: &[(String, Vec<[f64; 14]>)],
+        title: &str,
+        frame_ms: u32,
+    ) -> error::Result<()> {
+        if frames.is_empty() {
+            return Err(err!(NoData, "No frames to animate"));
+        }
+        let frames: Vec<_> = frames
+            .iter()
+            .map(|(label, gen)| (label, self.in_unit(gen)))
+            .collect();
+        let mut range = (f64::INFINITY, f64::NEG_INFINITY);
+        for (_, gen) in &frames {
+            let (min, max) = Self::gen_range(gen)?;
+            range = (range.0.min(min), range.1.max(max));
+        }
+        let pad = self.unit.of(250.);
+        let desc = self.unit.desc(&self.labels.units.energy);
+        self.also_csv(|| {
+            frames
+                .iter()
+                .flat_map(|(label, gen)| {
+                    self.source_points(gen, |idx| Profile::Day.label(idx))
+                        .into_iter()
+                        .map(move |point| PlotPoint {
+                            series: format!("{label}: {}", point.series),
+                            ..point
+                        })
+                })
+                .collect()
+        })?;
+
+        let path = match self.target {
+            Target::File(path) if !Backend::is_svg(path) => path,
+            _ => return Err(err!(Invalid, "Animations can only be written as gifs")),
+        };
+        self.write.check(path)?;
+        let size = self.size(Self::profile_size(Profile::Day));
+        let root = Backend::gif(path, size, frame_ms)?.into_drawing_area();
+        for (label, gen) in &frames {
+            let title = format!("{}, {label}", self.title(title));
+            self.draw_sources(
+                &root,
+                gen,
+                Profile::Day,
+                &title,
+                None,
+                YAxis {
+                    range: self.y_range((range.0 - pad)..(range.1 + pad)),
+                    desc: &desc,
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Draws the average day of prices in each labeled period, e.g. one per
+    /// quarter, as a grid of small panels sharing a y axis.
+    pub fn facet_price(&self, facets: &[(String, Vec<f64>)], title: &str) -> error::Result<()> {
+        let prices = facets.iter().flat_map(|(_, prices)| prices.iter().copied());
+        let (low, high) = prices
+            .filter(|price| !price.is_nan())
+            .fold((0f64, f64::NEG_INFINITY), |(low, high), price| {
+                (low.min(price), high.max(price))
+            });
+        if high == f64::NEG_INFINITY {
+            return Err(err!(NoData, "No prices to graph"));
+        }
+        let y_range = self.price_range(low, high);
+        let (root, panels) = self.facet_panels(facets.len(), title)?;
+
+        for (panel, (label, prices)) in panels.iter().zip(facets) {
+            let mut chart = self.facet_chart(panel, label, prices.len(), y_range.clone())?;
+            chart
+                .configure_mesh()
+                .disable_x_mesh()
+                .disable_y_mesh()
+                .bold_line_style(WHITE.mix(0.3))
+                .y_desc(self.y_desc(&self.labels.units.price))
+                .axis_desc_style(("sans-serif", 16))
+                .x_label_formatter(&|&idx| Profile::Day.label(idx))
+                .y_label_formatter(&|price| format!("${:02}", self.y_scale.tick(*price)))
+                .x_labels(6)
+                .y_labels(6)
+                .x_label_style(("sans-serif", 14))
+                .y_label_style(("sans-serif", 14))
+                .draw()?;
+            chart.draw_series(
+                Histogram::vertical(&chart)
+                    .style(RED.mix(0.5).filled())
+                    .data(
+                        prices
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, &val)| (idx, self.y_scale.forward(val))),
+                    ),
+            )?;
+        }
+
+        self.also_csv(|| {
+            facets
+                .iter()
+                .flat_map(|(label, prices)| {
+                    prices.iter().enumerate().map(|(idx, price)| {
+                        PlotPoint::new(label.as_str(), Profile::Day.label(idx), *price)
+                    })
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws the average day of generation by source in each labeled period
+    /// as a grid of small panels sharing a y axis. Only the first panel
+    /// carries the legend.
+    pub fn facet_gen(&self, facets: &[(String, Vec<[f64; 14]>)], title: &str) -> error::Result<()> {
+        if facets.is_empty() {
+            return Err(err!(NoData, "No periods to graph"));
+        }
+        let facets: Vec<_> = facets
+            .iter()
+            .map(|(label, gen)| (label, self.in_unit(gen)))
+            .collect();
+        let mut range = (f64::INFINITY, f64::NEG_INFINITY);
+        for (_, gen) in &facets {
+            let (min, max) = Self::gen_range(gen)?;
+            range = (range.0.min(min), range.1.max(max));
+        }
+        let pad = self.unit.of(250.);
+        let y_range = self.y_range((range.0 - pad)..(range.1 + pad));
+        let desc = self.unit.desc(&self.labels.units.energy);
+        let (root, panels) = self.facet_panels(facets.len(), title)?;
+
+        for (idx, (panel, (label, gen))) in panels.iter().zip(&facets).enumerate() {
+            let mut chart = self.facet_chart(panel, label, gen.len(), y_range.clone())?;
+            chart
+                .configure_mesh()
+                .disable_x_mesh()
+                .disable_y_mesh()
+                .bold_line_style(WHITE.mix(0.3))
+                .y_desc(self.y_desc(&desc))
+                .axis_desc_style(("sans-serif", 16))
+                .x_label_formatter(&|&idx| Profile::Day.label(idx))
+                .x_labels(6)
+                .y_labels(6)
+                .x_label_style(("sans-serif", 14))
+                .y_label_style(("sans-serif", 14))
+                .draw()?;
+            for (src_idx, (source, color)) in self.source_keys().enumerate().skip(1) {
+                chart
+                    .draw_series(LineSeries::new(
+                        gen.iter()
+                            .enumerate()
+                            .map(|(timeslice, arr)| (timeslice, arr[src_idx])),
+                        color.stroke_width(2),
+                    ))?
+                    .label(source)
+                    .legend(move |(x, y)| {
+                        Rectangle::new([(x, y - 4), (x + 8, y + 4)], color.filled())
+                    });
+            }
+            if idx == 0 {
+                chart
+                    .configure_series_labels()
+                    .border_style(BLACK)
+                    .position(SeriesLabelPosition::UpperRight)
+                    .label_font(("Calibri", 11))
+                    .draw()?;
+            }
+        }
+
+        self.also_csv(|| {
+            facets
+                .iter()
+                .flat_map(|(label, gen)| {
+                    self.source_points(gen, |idx| Profile::Day.label(idx))
+                        .into_iter()
+                        .map(move |point| PlotPoint {
+                            series: format!("{label}: {}", point.series),
+                            ..point
+                        })
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// The chart under `title` along with a panel per facet, laid out in a
+    /// grid as close to square as fits them. Extra cells stay blank.
+    fn facet_panels(&self, facets: usize, title: &str) -> error::Result<(Area<'a>, Vec<Area<'a>>)> {
+        const PANEL_SIZE: (u32, u32) = (540, 360);
+        if facets == 0 {
+            return Err(err!(NoData, "No periods to graph"));
+        }
+        let cols = (facets as f64).sqrt().ceil() as usize;
+        let rows = facets.div_ceil(cols);
+        let root = self
+            .backend((
+                (PANEL_SIZE.0 * cols as u32).max(1080),
+                PANEL_SIZE.1 * rows as u32 + 60,
+            ))?
+            .into_drawing_area();
+        let chart = self.canvas(&root)?;
+        let panels = chart
+            .titled(&self.title(title), ("sans-serif", 40.))?
+            .split_evenly((rows, cols));
+        Ok((root, panels))
+    }
+
+    /// One panel's chart over the slots of a day.
+    fn facet_chart<'b>(
+        &self,
+        panel: &'b Area<'a>,
+        label: &str,
+        slots: usize,
+        y_range: Range<f64>,
+    ) -> error::Result<ChartContext<'b, Backend<'a>, Cartesian2d<RangedCoordusize, RangedCoordf64>>>
+    {
+        Ok(ChartBuilder::on(panel)
+            .x_label_area_size(32)
+            .y_label_area_size(64)
+            .margin(12)
+            .caption(label, ("sans-serif", 24.))
+            .build_cartesian_2d(0..slots, y_range)?)
+    }
+
+    /// Stacks each source's percent share of generation in every slot of a
+    /// profile, bottom to top in source order. Takes `Compute::gen_shares`.
+    pub fn gen_share(
+        &self,
+        shares: &[[f64; 14]],
+        profile: Profile,
+        title: &str,
+    ) -> error::Result<()> {
+        let root = self
+            .backend(Self::profile_size(profile))?
+            .into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(0..(shares.len() - 1), self.y_range(0f64..100.))?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .bold_line_style(WHITE.mix(0.3))
+            .y_desc(self.y_desc("% of generation"))
+            .x_desc(self.x_desc(Self::profile_desc(profile)))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|&idx| Self::profile_tick(profile, idx))
+            .y_label_formatter(&|pct| format!("{pct:.0}%"))
+            .x_labels(Self::profile_labels(profile))
+            .y_labels(11)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        let mut floor = vec![0.; shares.len()];
+        for (src_idx, (label, color)) in self.source_keys().enumerate().skip(1) {
+            let ceiling: Vec<f64> = floor
+                .iter()
+                .zip(shares)
+                .map(|(floor, slot)| floor + slot[src_idx])
+                .collect();
+            let outline: Vec<_> = ceiling
+                .iter()
+                .copied()
+                .enumerate()
+                .chain(floor.iter().copied().enumerate().rev())
+                .collect();
+            chart
+                .draw_series([Polygon::new(outline, color.filled())])?
+                .label(label)
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                });
+            floor = ceiling;
+        }
+        self.draw_references(&chart, YScale::Linear)?;
+
+        self.draw_markers(&mut chart, 0f64..100.)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE.mix(0.8))
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 14))
+            .draw()?;
+
+        self.also_csv(|| self.source_points(shares, |idx| profile.label(idx)))?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Stacks each source's average output over a profile, with the average
+    /// price drawn over it against a second axis on the right. Negative
+    /// output, like batteries charging, is left out of the stack.
+    pub fn price_gen_overlay(
+        &self,
+        prices: &[f64],
+        gen: &[[f64; 14]],
+        profile: Profile,
+        title: &str,
+    ) -> error::Result<()> {
+        if prices.is_empty() || prices.len() != gen.len() {
+            return Err(err!(NoData, "No joined price and generation to overlay"));
+        }
+        let root = self
+            .backend(Self::profile_size(profile))?
+            .into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let gen = &self.in_unit(gen);
+        let gen_max = gen
+            .iter()
+            .map(|slot| slot.iter().skip(1).map(|qty| qty.max(0.)).sum::<f64>())
+            .fold(0., f64::max);
+        let gen_range = self.y_range(0f64..(gen_max * 1.05));
+        let price_min = prices.iter().copied().fold(0., f64::min);
+        let price_max = prices.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let price_range = price_min..(price_max * 1.1).max(price_min + 1.);
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(96)
+            .right_y_label_area_size(96)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(0..(gen.len() - 1), gen_range.clone())?
+            .set_secondary_coord(0..(gen.len() - 1), price_range.clone());
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .bold_line_style(WHITE.mix(0.3))
+            .y_desc(self.y_desc(self.unit.desc(&self.labels.units.energy)))
+            .x_desc(self.x_desc(Self::profile_desc(profile)))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|&idx| Self::profile_tick(profile, idx))
+            .x_labels(Self::profile_labels(profile))
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        chart
+            .configure_secondary_axes()
+            .y_desc(&self.labels.units.price)
+            .axis_desc_style(("sans-serif", 30))
+            .label_style(("sans-serif", 16))
+            .draw()?;
+
+        let mut floor = vec![0.; gen.len()];
+        for (src_idx, (label, color)) in self.source_keys().enumerate().skip(1) {
+            let ceiling: Vec<f64> = floor
+                .iter()
+                .zip(gen)
+                .map(|(floor, slot)| floor + slot[src_idx].max(0.))
+                .collect();
+            let outline: Vec<_> = ceiling
+                .iter()
+                .copied()
+                .enumerate()
+                .chain(floor.iter().copied().enumerate().rev())
+                .collect();
+            chart
+                .draw_series([Polygon::new(outline, color.mix(0.8).filled())])?
+                .label(label)
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                });
+            floor = ceiling;
+        }
+        self.draw_markers(&mut chart, gen_range)?;
+
+        chart
+            .draw_secondary_series(LineSeries::new(
+                prices.iter().copied().enumerate(),
+                BLACK.stroke_width(3),
+            ))?
+            .label("Price")
+            .legend(|(x, y)| PathElement::new([(x, y), (x + 10, y)], BLACK.stroke_width(3)));
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE.mix(0.8))
+            .position(SeriesLabelPosition::UpperLeft)
+            .label_font(("Calibri", 14))
+            .draw()?;
+
+        self.also_csv(|| {
+            let mut rows = self.source_points(gen, |idx| profile.label(idx));
+            rows.extend(
+                prices
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, price)| PlotPoint::new("Price", profile.label(idx), *price)),
+            );
+            rows
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws each source's realized price in each slot of a profile.
+    pub fn daily_value(
+        &self,
+        values: &[[f64; 14]],
+        profile: Profile,
+        title: &str,
+    ) -> error::Result<()> {
+        let root = self
+            .backend(Self::profile_size(profile))?
+            .into_drawing_area();
+        let (min, max) = Self::gen_range(values)?;
+        let pad = (max - min) * 0.05;
+        self.also_csv(|| self.source_points(values, |idx| profile.label(idx)))?;
+        self.draw_sources(
+            &root,
+            values,
+            profile,
+            &self.title(title),
+            None,
+            YAxis {
+                range: self.y_range((min - pad)..(max + pad)),
+                desc: &self.labels.units.price,
+            },
+        )
+    }
+
+    /// The lowest and highest value of any source, leaving out the total.
+    fn gen_range(gen: &[[f64; 14]]) -> error::Result<(f64, f64)> {
+        let gen_min = gen
+            .iter()
+            .flat_map(|arr| arr.iter().skip(1))
+            .min_by(|x, y| x.partial_cmp(y).unwrap_or(Ordering::Equal))
+            .ok_or_else(|| err!(NoData, "Failed to compute chart min"))?;
+        let gen_max = gen
+            .iter()
+            .flat_map(|arr| arr.iter().skip(1))
+            .max_by(|x, y| x.partial_cmp(y).unwrap_or(Ordering::Equal))
+            .ok_or_else(|| err!(NoData, "Failed to compute chart max"))?;
+        Ok((*gen_min, *gen_max))
+    }
+
+    /// Draws a line per source (leaving out the total) over the slots of a
+    /// profile onto `root` and presents it, which adds a frame when the
+    /// backend is a gif.
+    fn draw_sources(
+        &self,
+        root: &DrawingArea<Backend<'a>, Shift>,
+        gen: &[[f64; 14]],
+        profile: Profile,
+        title: &str,
+        band: Option<SourceBand>,
+        y_axis: YAxis,
+    ) -> error::Result<()> {
+        let root = &self.canvas(root)?;
+        let YAxis {
+            range: y_range,
+            desc: y_desc,
+        } = y_axis;
+
+        const LEGEND_WIDTH: u32 = 180;
+        let (plot, side) = match self.legend {
+            LegendPosition::Inside => (root.clone(), None),
+            LegendPosition::Outside => {
+                let (width, _) = root.dim_in_pixel();
+                let (plot, side) = root.split_horizontally(width.saturating_sub(LEGEND_WIDTH));
+                (plot, Some(side))
+            }
+        };
+        // What the legend lists, for one drawn beside the plot.
+        let mut entries: Vec<(String, ShapeStyle)> = Vec::new();
+        let mut chart = ChartBuilder::on(&plot)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .caption(title, ("sans-serif", 40.))
+            .build_cartesian_2d(0..(gen.len()), y_range.clone())?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .bold_line_style(WHITE.mix(0.3))
+            .y_desc(self.y_desc(y_desc))
+            .x_desc(self.x_desc(Self::profile_desc(profile)))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|&idx| Self::profile_tick(profile, idx))
+            .x_labels(Self::profile_labels(profile))
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        if let Some(band) = &band {
+            let (label, color) = self
+                .source_keys()
+                .nth(band.source)
+                .ok_or_else(|| err!(Invalid, "No source at index {}", band.source))?;
+            let slots = band.bands.iter().map(|slot| slot[band.source]);
+            let outline: Vec<_> = slots
+                .clone()
+                .enumerate()
+                .map(|(idx, slot)| (idx, slot.p90))
+                .chain(
+                    slots
+                        .clone()
+                        .enumerate()
+                        .rev()
+                        .map(|(idx, slot)| (idx, slot.p10)),
+                )
+                .collect();
+            chart
+                .draw_series([Polygon::new(outline, color.mix(0.2).filled())])?
+                .label(format!("{label} p10-p90"))
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.mix(0.2).filled())
+                });
+            entries.push((format!("{label} p10-p90"), color.mix(0.2).filled()));
+            chart
+                .draw_series(LineSeries::new(
+                    slots.enumerate().map(|(idx, slot)| (idx, slot.p50)),
+                    color.mix(0.6).stroke_width(1),
+                ))?
+                .label(format!("{label} median"))
+                .legend(move |(x, y)| {
+                    PathElement::new([(x, y), (x + 10, y)], color.mix(0.6).stroke_width(1))
+                });
+            entries.push((format!("{label} median"), color.mix(0.6).stroke_width(1)));
+        }
+
+        let sources: Vec<_> = self.source_keys().collect();
+        let line = |src_idx: usize| {
+            gen.iter()
+                .enumerate()
+                .map(move |(timeslice, arr)| (timeslice, arr[src_idx]))
+        };
+        for src_idx in self.ordered_sources(gen) {
+            let (label, color) = sources[src_idx];
+            let style = self.source_style(src_idx, color);
+            let swatch = ShapeStyle {
+                filled: true,
+                ..style
+            };
+            // A highlighted line is drawn over the rest below, keeping its
+            // place in the legend here.
+            let points: Vec<_> = match self.highlight == Some(src_idx) {
+                true => Vec::new(),
+                false => line(src_idx).collect(),
+            };
+            chart
+                .draw_series(LineSeries::new(points, style))?
+                .label(label)
+                .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], swatch));
+            entries.push((label.to_string(), swatch));
+        }
+        if let Some(highlight) = self.highlight {
+            let (_, color) = sources
+                .get(highlight)
+                .ok_or_else(|| err!(Invalid, "No source at index {highlight}"))?;
+            chart.draw_series(LineSeries::new(
+                line(highlight),
+                self.source_style(highlight, *color),
+            ))?;
+        }
+        self.draw_references(&chart, YScale::Linear)?;
+
+        self.draw_markers(&mut chart, y_range)?;
+
+        match side {
+            None => chart
+                .configure_series_labels()
+                .border_style(BLACK)
+                .position(SeriesLabelPosition::UpperRight)
+                .label_font(("Calibri", 14))
+                .draw()?,
+            Some(side) => Self::side_legend(&side, &entries)?,
+        }
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    pub fn avg_value(&self, values: &[f64; 14], title: &str) -> error::Result<()> {
+        let values: Vec<_> = values
+            .iter()
+            .copied()
+            .zip(self.source_keys())
+            .skip(1)
+            .filter(|(val, _)| *val > 0.)
+            .collect();
+
+        let root = self.backend((1080, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let max_price = values.iter().fold(values[0].0, |acc, el| el.0.max(acc));
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(72)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(
+                (0..(values.len() - 1)).into_segmented(),
+                self.y_range(0f64..(max_price * 1.1)),
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .y_desc(self.y_desc(&self.labels.units.price))
+            .x_desc(self.x_desc("Electricity source"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|seg| match seg {
+                SegmentValue::Last | SegmentValue::Exact(_) => "".to_string(),
+                SegmentValue::CenterOf(idx) => values[*idx].1 .0.to_string(),
+            })
+            .y_label_formatter(&|price| format!("${price:.2}"))
+            .x_labels(20)
+            .y_labels(20)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        chart.draw_series(
+            Histogram::vertical(&chart)
+                .style(BLUE_600.filled())
+                .data(values.iter().enumerate().map(|(idx, val)| (idx, val.0))),
+        )?;
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        self.also_csv(|| {
+            values
+                .iter()
+                .map(|(val, (source, _))| PlotPoint::new("Value", source, *val))
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws each source's average value under two or more scenarios side
+    /// by side on one axis, so what a scenario adds shows up against the
+    /// base it's compared with. `avg_value` draws a single scenario.
+    pub fn value_scenarios(
+        &self,
+        scenarios: &[(String, [f64; 14])],
+        title: &str,
+    ) -> error::Result<()> {
+        if scenarios.len() < 2 {
+            bail!(Invalid, "Comparing values needs at least two scenarios");
+        }
+        self.grouped_values(scenarios, title)
+    }
+
+    pub fn storage_sweep(&self, sweep: &[StorageSweepRow], title: &str) -> error::Result<()> {
+        let root = self.backend((1080, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let max_gwh = sweep
+            .last()
+            .ok_or_else(|| err!(NoData, "Cannot graph an empty sweep"))?
+            .capacity_gwh;
+        let (min_value, max_value) = sweep.iter().fold((f64::MAX, f64::MIN), |acc, row| {
+            (acc.0.min(row.avg_value), acc.1.max(row.avg_value))
+        });
+        let padding = ((max_value - min_value) * 0.1).max(1.);
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(72)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(
+                0f64..max_gwh.max(1.),
+                self.y_range((min_value - padding)..(max_value + padding)),
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .bold_line_style(WHITE.mix(0.3))
+            .y_desc(self.y_desc(&self.labels.units.price))
+            .x_desc(self.x_desc("Added storage (GWh)"))
+            .axis_desc_style(("sans-serif", 30))
+            .y_label_formatter(&|price| format!("${price:.2}"))
+            .x_labels(20)
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        let points = || sweep.iter().map(|row| (row.capacity_gwh, row.avg_value));
+        chart.draw_series(LineSeries::new(points(), BLUE_600.stroke_width(3)))?;
+        chart.draw_series(PointSeries::of_element(
+            points(),
+            5,
+            BLUE_600.filled(),
+            &|coord, size, style| Circle::new(coord, size, style),
+        ))?;
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        self.also_csv(|| {
+            sweep
+                .iter()
+                .map(|row| PlotPoint::new("Average value", row.capacity_gwh, row.avg_value))
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws the average price in each band of implied reserve margin,
+    /// with a point per band.
+    pub fn reserve_margin(&self, bins: &[MarginBin], title: &str) -> error::Result<()> {
+        let (Some(first), Some(last)) = (bins.first(), bins.last()) else {
+            return Err(err!(NoData, "Cannot graph an empty reserve margin"));
+        };
+        let root = self.backend((1080, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let (min_price, max_price) = bins.iter().fold((0f64, 0f64), |acc, bin| {
+            (acc.0.min(bin.avg_price), acc.1.max(bin.avg_price))
+        });
+        // Empty bands are left out, so the closest two give the width.
+        let width = bins
+            .windows(2)
+            .map(|pair| pair[1].margin - pair[0].margin)
+            .reduce(f64::min)
+            .unwrap_or(0.05);
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(72)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(
+                first.margin * 100.0..(last.margin + width) * 100.,
+                self.price_range(min_price, max_price * 1.1),
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(&self.labels.units.price))
+            .x_desc(self.x_desc("Implied reserve margin"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|pct| format!("{pct:.0}%"))
+            .y_label_formatter(&|price| format!("${:.0}", self.y_scale.tick(*price)))
+            .x_labels(12)
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        // Each band is drawn at its middle.
+        let points = || {
+            bins.iter().map(|bin| {
+                (
+                    (bin.margin + width / 2.) * 100.,
+                    self.y_scale.forward(bin.avg_price),
+                )
+            })
+        };
+        chart.draw_series(LineSeries::new(points(), RED.stroke_width(3)))?;
+        chart.draw_series(PointSeries::of_element(
+            points(),
+            5,
+            RED.filled(),
+            &|coord, size, style| Circle::new(coord, size, style),
+        ))?;
+
+        self.draw_references(&chart, self.y_scale)?;
+
+        self.also_csv(|| {
+            bins.iter()
+                .map(|bin| {
+                    let band = format!("{:.0}%", bin.margin * 100.);
+                    PlotPoint::new("Average price", band, bin.avg_price)
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws historical and scenario price profiles over the day on one chart.
+    pub fn price_impact(&self, prices: &[(f64, f64)], title: &str) -> error::Result<()> {
+        let root = self.backend((1080, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let (min_price, max_price) = prices
+            .iter()
+            .flat_map(|(base, scenario)| [*base, *scenario])
+            .fold((0f64, f64::MIN), |acc, el| (acc.0.min(el), acc.1.max(el)));
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(72)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(
+                0..(prices.len()),
+                self.y_range(min_price..(max_price * 1.1)),
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .bold_line_style(WHITE.mix(0.3))
+            .y_desc(self.y_desc(&self.labels.units.price))
+            .x_desc(self.x_desc("Time of day"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|&idx| {
+                let (hour, minute) = Compute::idx_5min_to_time(idx);
+                format!("{hour:02}:{minute:02}")
+            })
+            .y_label_formatter(&|price| format!("${price:.2}"))
+            .x_labels(24)
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        for (label, color, scenario) in [("Historical", RED, false), ("Scenario", BLUE_600, true)] {
+            chart
+                .draw_series(LineSeries::new(
+                    prices
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, (base, scen))| (idx, if scenario { *scen } else { *base })),
+                    color.stroke_width(3),
+                ))?
+                .label(label)
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                });
+        }
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 14))
+            .draw()?;
+
+        self.also_csv(|| {
+            prices
+                .iter()
+                .enumerate()
+                .flat_map(|(idx, (base, scenario))| {
+                    let (hour, minute) = Compute::idx_5min_to_time(idx);
+                    let time = format!("{hour:02}:{minute:02}");
+                    [
+                        PlotPoint::new("Historical", &time, *base),
+                        PlotPoint::new("Scenario", &time, *scenario),
+                    ]
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws historical and scenario net load over the day above the
+    /// historical and scenario price.
+    pub fn scenario_impact(&self, slots: &[ScenarioSlot], title: &str) -> error::Result<()> {
+        let root = self.backend((1080, 1080))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+        let root = root.titled(&self.title(title), ("sans-serif", 40.))?;
+        let (_, height) = root.dim_in_pixel();
+        let (upper, lower) = root.split_vertically(height / 2);
+
+        let net_load: Vec<(f64, f64)> = slots
+            .iter()
+            .map(|slot| {
+                (
+                    self.unit.of(slot.net_load),
+                    self.unit.of(slot.scenario_net_load),
+                )
+            })
+            .collect();
+        let prices: Vec<(f64, f64)> = slots
+            .iter()
+            .map(|slot| (slot.price, slot.scenario_price))
+            .collect();
+        let power = self.unit.desc(&self.labels.units.power);
+        for (name, desc, values, area) in [
+            ("Net load", power.as_str(), &net_load, &upper),
+            ("Price", self.labels.units.price.as_str(), &prices, &lower),
+        ] {
+            let (low, high) = values
+                .iter()
+                .flat_map(|(base, scenario)| [*base, *scenario])
+                .filter(|value| !value.is_nan())
+                .fold((0f64, f64::MIN), |(low, high), value| {
+                    (low.min(value), high.max(value))
+                });
+            if low > high {
+                bail!(NoData, "No {} to graph", name.to_lowercase());
+            }
+            let mut chart = ChartBuilder::on(area)
+                .x_label_area_size(60)
+                .y_label_area_size(84)
+                .margin(20)
+                .caption(name, ("sans-serif", 28.))
+                .build_cartesian_2d(0..values.len(), self.y_range(low..high * 1.1))?;
+            chart
+                .configure_mesh()
+                .disable_x_mesh()
+                .disable_y_mesh()
+                .bold_line_style(WHITE.mix(0.3))
+                .y_desc(self.y_desc(desc))
+                .x_desc(self.x_desc("Time of day"))
+                .axis_desc_style(("sans-serif", 24))
+                .x_label_formatter(&|&idx| Profile::Day.label(idx))
+                .y_label_formatter(&|value| match name {
+                    "Price" => format!("${value:.2}"),
+                    _ => format!("{value:.0}"),
+                })
+                .x_labels(24)
+                .y_labels(8)
+                .x_label_style(("sans-serif", 16))
+                .y_label_style(("sans-serif", 16))
+                .draw()?;
+            for (label, color, scenario) in
+                [("Historical", RED, false), ("Scenario", BLUE_600, true)]
+            {
+                chart
+                    .draw_series(LineSeries::new(
+                        values
+                            .iter()
+                            .map(|(base, scen)| if scenario { *scen } else { *base })
+                            .enumerate(),
+                        color.stroke_width(3),
+                    ))?
+                    .label(label)
+                    .legend(move |(x, y)| {
+                        Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                    });
+            }
+            chart
+                .configure_series_labels()
+                .border_style(BLACK)
+                .background_style(WHITE.mix(0.8))
+                .position(SeriesLabelPosition::UpperLeft)
+                .label_font(("Calibri", 14))
+                .draw()?;
+        }
+
+        self.also_csv(|| {
+            slots
+                .iter()
+                .enumerate()
+                .flat_map(|(idx, slot)| {
+                    let time = Profile::Day.label(idx);
+                    [
+                        PlotPoint::new("Historical net load", &time, slot.net_load),
+                        PlotPoint::new("Scenario net load", &time, slot.scenario_net_load),
+                        PlotPoint::new("Historical price", &time, slot.price),
+                        PlotPoint::new("Scenario price", &time, slot.scenario_price),
+                    ]
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// A change in price, like +$1.50 or -$0.25.
+    fn signed_price(price: f64) -> String {
+        let sign = if price < 0. { "-" } else { "+" };
+        format!("{sign}${:.2}", price.abs())
+    }
+
+    /// The y range of a delta chart, which always includes zero.
+    fn delta_range(deltas: impl Iterator<Item = f64>) -> error::Result<Range<f64>> {
+        let (low, high) = deltas
+            .filter(|delta| !delta.is_nan())
+            .fold((f64::MAX, f64::MIN), |(low, high), delta| {
+                (low.min(delta), high.max(delta))
+            });
+        if low > high {
+            return Err(err!(NoData, "No differences to graph"));
+        }
+        let pad = (high - low).max(1.) * 0.1;
+        Ok((low.min(0.) - pad)..(high.max(0.) + pad))
+    }
+
+    /// Draws how far a price profile moved from a base scenario in each
+    /// slot, with rises and falls in different colors about a zero line.
+    pub fn profile_delta(&self, delta: &[f64], profile: Profile, title: &str) -> error::Result<()> {
+        let root = self
+            .backend(Self::profile_size(profile))?
+            .into_drawing_area();
+        let root = self.canvas(&root)?;
+        let [rise, fall, ..] = self.options.theme.group_colors();
+
+        let y_range = self.y_range(Self::delta_range(delta.iter().copied())?);
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(72)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(0..delta.len(), y_range)?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(format!("Change in {}", self.labels.units.price)))
+            .x_desc(self.x_desc(Self::profile_desc(profile)))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|&idx| Self::profile_tick(profile, idx))
+            .y_label_formatter(&|price| Self::signed_price(*price))
+            .x_labels(Self::profile_labels(profile))
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        chart.draw_series(
+            delta
+                .iter()
+                .enumerate()
+                .filter(|(_, val)| !val.is_nan())
+                .map(|(idx, &val)| {
+                    let color = if val < 0. { fall } else { rise };
+                    Rectangle::new([(idx, 0.), (idx + 1, val)], color.mix(0.8).filled())
+                }),
+        )?;
+        chart.draw_series(LineSeries::new(
+            [(0, 0.), (delta.len(), 0.)],
+            BLACK.stroke_width(2),
+        ))?;
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        self.also_csv(|| {
+            delta
+                .iter()
+                .enumerate()
+                .map(|(idx, val)| PlotPoint::new("Change", profile.label(idx), *val))
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws how far each slot's mean moved between two periods, shaded
+    /// with its 95% confidence interval about a zero line. `unit` is the
+    /// unit of the values compared.
+    pub fn period_difference(
+        &self,
+        diffs: &[MeanDifference],
+        profile: Profile,
+        unit: &str,
+        title: &str,
+    ) -> error::Result<()> {
+        let root = self
+            .backend(Self::profile_size(profile))?
+            .into_drawing_area();
+        let root = self.canvas(&root)?;
+        let [color, ..] = self.options.theme.group_colors();
+        let intervals: Vec<(f64, f64)> = diffs.iter().map(MeanDifference::interval_95).collect();
+
+        let y_range = self.y_range(Self::delta_range(
+            intervals.iter().flat_map(|(low, high)| [*low, *high]),
+        )?);
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(0..diffs.len(), y_range)?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(format!("Change in {unit}")))
+            .x_desc(self.x_desc(Self::profile_desc(profile)))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|&idx| Self::profile_tick(profile, idx))
+            .y_label_formatter(&|change| format!("{change:+.1}"))
+            .x_labels(Self::profile_labels(profile))
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        let outline: Vec<_> = intervals
+            .iter()
+            .enumerate()
+            .map(|(idx, (_, high))| (idx, *high))
+            .chain(
+                intervals
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(idx, (low, _))| (idx, *low)),
+            )
+            .filter(|(_, value)| !value.is_nan())
+            .collect();
+        chart
+            .draw_series([Polygon::new(outline, color.mix(0.2).filled())])?
+            .label("95% confidence")
+            .legend(move |(x, y)| {
+                Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.mix(0.2).filled())
+            });
+        chart.draw_series(LineSeries::new(
+            [(0, 0.), (diffs.len(), 0.)],
+            BLACK.stroke_width(2),
+        ))?;
+        chart
+            .draw_series(LineSeries::new(
+                diffs
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, diff)| !diff.change.is_nan())
+                    .map(|(idx, diff)| (idx, diff.change)),
+                color.stroke_width(3),
+            ))?
+            .label("Change")
+            .legend(move |(x, y)| PathElement::new([(x, y), (x + 10, y)], color.stroke_width(3)));
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE.mix(0.8))
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 16))
+            .draw()?;
+
+        self.also_csv(|| {
+            diffs
+                .iter()
+                .zip(&intervals)
+                .enumerate()
+                .flat_map(|(idx, (diff, (low, high)))| {
+                    let slot = profile.label(idx);
+                    [
+                        PlotPoint::new("Change", &slot, diff.change),
+                        PlotPoint::new("Low", &slot, *low),
+                        PlotPoint::new("High", &slot, *high),
+                    ]
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws each zone's congestion component of its LMP above its loss
+    /// component, a line per zone, over the slots of a profile.
+    pub fn price_components(
+        &self,
+        components: &PriceComponents,
+        profile: Profile,
+    ) -> error::Result<()> {
+        let (width, _) = Self::profile_size(profile);
+        let root = self.backend((width, 1080))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+        let title = match profile {
+            Profile::Day => "Daily average congestion and loss by zone",
+            Profile::Week => "Weekly average congestion and loss by zone",
+        };
+        let root = root.titled(&self.title(title), ("sans-serif", 40.))?;
+        let (_, height) = root.dim_in_pixel();
+        let (upper, lower) = root.split_vertically(height / 2);
+        let colors = self.options.theme.group_colors();
+
+        for (area, name, values) in [
+            (&upper, "Congestion", &components.congestion),
+            (&lower, "Loss", &components.loss),
+        ] {
+            let y_range = self.y_range(Self::delta_range(
+                values.iter().flat_map(|zones| zones.iter().copied()),
+            )?);
+            let mut chart = ChartBuilder::on(area)
+                .x_label_area_size(60)
+                .y_label_area_size(72)
+                .margin(20)
+                .caption(name, ("sans-serif", 28.))
+                .build_cartesian_2d(0..values.len(), y_range)?;
+            chart
+                .configure_mesh()
+                .disable_x_mesh()
+                .disable_y_mesh()
+                .bold_line_style(WHITE.mix(0.3))
+                .y_desc(self.y_desc(&self.labels.units.price))
+                .x_desc(self.x_desc(Self::profile_desc(profile)))
+                .axis_desc_style(("sans-serif", 24))
+                .x_label_formatter(&|&idx| Self::profile_tick(profile, idx))
+                .y_label_formatter(&|price| Self::signed_price(*price))
+                .x_labels(Self::profile_labels(profile))
+                .y_labels(8)
+                .x_label_style(("sans-serif", 16))
+                .y_label_style(("sans-serif", 16))
+                .draw()?;
+            chart.draw_series(LineSeries::new(
+                [(0, 0.), (values.len(), 0.)],
+                BLACK.mix(0.5).stroke_width(1),
+            ))?;
+            for (zone_idx, (zone, color)) in EnergyPriceCsvRow::ZONES.iter().zip(colors).enumerate()
+            {
+                chart
+                    .draw_series(LineSeries::new(
+                        values.iter().map(|zones| zones[zone_idx]).enumerate(),
+                        color.stroke_width(3),
+                    ))?
+                    .label(*zone)
+                    .legend(move |(x, y)| {
+                        PathElement::new([(x, y), (x + 10, y)], color.stroke_width(3))
+                    });
+            }
+            chart
+                .configure_series_labels()
+                .border_style(BLACK)
+                .background_style(WHITE.mix(0.8))
+                .position(SeriesLabelPosition::UpperRight)
+                .label_font(("Calibri", 16))
+                .draw()?;
+        }
+
+        self.also_csv(|| {
+            [
+                ("congestion", &components.congestion),
+                ("loss", &components.loss),
+            ]
+            .into_iter()
+            .flat_map(|(name, values)| {
+                EnergyPriceCsvRow::ZONES
+                    .iter()
+                    .enumerate()
+                    .flat_map(move |(zone_idx, zone)| {
+                        values.iter().enumerate().map(move |(idx, zones)| {
+                            PlotPoint::new(
+                                format!("{zone} {name}"),
+                                profile.label(idx),
+                                zones[zone_idx],
+                            )
+                        })
+                    })
+            })
+            .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws a panel per area comparing its zone LMP with its trading
+    /// hub's over the slots of a profile.
+    pub fn hub_zone(
+        &self,
+        zone: &AreaPrices,
+        hub: &AreaPrices,
+        profile: Profile,
+    ) -> error::Result<()> {
+        let (width, _) = Self::profile_size(profile);
+        let root = self.backend((width, 1440))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+        let title = match profile {
+            Profile::Day => "Daily average LMP, trading hubs vs zones",
+            Profile::Week => "Weekly average LMP, trading hubs vs zones",
+        };
+        let root = root.titled(&self.title(title), ("sans-serif", 40.))?;
+        let panels = root.split_evenly((EnergyPriceCsvRow::ZONES.len(), 1));
+        let colors = self.options.theme.group_colors();
+        let slots = zone.prices.len().min(hub.prices.len());
+
+        for ((area_idx, area), panel) in EnergyPriceCsvRow::ZONES.iter().enumerate().zip(&panels) {
+            let lines = [("Zone", zone, colors[0]), ("Hub", hub, colors[1])];
+            let y_range = self.y_range(Self::delta_range(lines.iter().flat_map(
+                |(_, prices, _)| {
+                    prices.prices[..slots]
+                        .iter()
+                        .map(move |areas| areas[area_idx])
+                },
+            ))?);
+            let mut chart = ChartBuilder::on(panel)
+                .x_label_area_size(60)
+                .y_label_area_size(72)
+                .margin(20)
+                .caption(*area, ("sans-serif", 28.))
+                .build_cartesian_2d(0..slots, y_range)?;
+            chart
+                .configure_mesh()
+                .disable_x_mesh()
+                .disable_y_mesh()
+                .bold_line_style(WHITE.mix(0.3))
+                .y_desc(self.y_desc(&self.labels.units.price))
+                .x_desc(self.x_desc(Self::profile_desc(profile)))
+                .axis_desc_style(("sans-serif", 24))
+                .x_label_formatter(&|&idx| Self::profile_tick(profile, idx))
+                .y_label_formatter(&|price| Self::signed_price(*price))
+                .x_labels(Self::profile_labels(profile))
+                .y_labels(8)
+                .x_label_style(("sans-serif", 16))
+                .y_label_style(("sans-serif", 16))
+                .draw()?;
+            for (name, prices, color) in lines {
+                chart
+                    .draw_series(LineSeries::new(
+                        prices.prices[..slots]
+                            .iter()
+                            .map(|areas| areas[area_idx])
+                            .enumerate(),
+                        color.stroke_width(3),
+                    ))?
+                    .label(name)
+                    .legend(move |(x, y)| {
+                        PathElement::new([(x, y), (x + 10, y)], color.stroke_width(3))
+                    });
+            }
+            chart
+                .configure_series_labels()
+                .border_style(BLACK)
+                .background_style(WHITE.mix(0.8))
+                .position(SeriesLabelPosition::UpperRight)
+                .label_font(("Calibri", 16))
+                .draw()?;
+        }
+
+        self.also_csv(|| {
+            EnergyPriceCsvRow::ZONES
+                .iter()
+                .enumerate()
+                .flat_map(|(area_idx, area)| {
+                    [("zone", zone), ("hub", hub)]
+                        .into_iter()
+                        .flat_map(move |(name, prices)| {
+                            prices.prices[..slots]
+                                .iter()
+                                .enumerate()
+                                .map(move |(idx, areas)| {
+                                    PlotPoint::new(
+                                        format!("{area} {name}"),
+                                        profile.label(idx),
+                                        areas[area_idx],
+                                    )
+                                })
+                        })
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws the average spark spread in each slot of a profile as bars,
+    /// colored by whether gas plants clear their fuel cost, under lines of
+    /// the price and fuel cost it's the difference of.
+    pub fn spark_spread(
+        &self,
+        spread: &SparkSpread,
+        profile: Profile,
+        title: &str,
+    ) -> error::Result<()> {
+        let root = self
+            .backend(Self::profile_size(profile))?
+            .into_drawing_area();
+        let root = self.canvas(&root)?;
+        let [price_color, cost_color, rise, _, fall] = self.options.theme.group_colors();
+
+        let values = [&spread.prices, &spread.fuel_costs, &spread.spreads];
+        let y_range = self.y_range(Self::delta_range(
+            values.iter().flat_map(|series| series.iter().copied()),
+        )?);
+        let slots = spread.spreads.len();
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(72)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(0..slots, y_range)?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(&self.labels.units.price))
+            .x_desc(self.x_desc(Self::profile_desc(profile)))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|&idx| Self::profile_tick(profile, idx))
+            .y_label_formatter(&|price| Self::signed_price(*price))
+            .x_labels(Self::profile_labels(profile))
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        chart
+            .draw_series(
+                spread
+                    .spreads
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, val)| !val.is_nan())
+                    .map(|(idx, &val)| {
+                        let color = if val < 0. { fall } else { rise };
+                        Rectangle::new([(idx, 0.), (idx + 1, val)], color.mix(0.6).filled())
+                    }),
+            )?
+            .label("Spark spread")
+            .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], rise.filled()));
+        chart.draw_series(LineSeries::new(
+            [(0, 0.), (slots, 0.)],
+            BLACK.stroke_width(2),
+        ))?;
+        for (label, series, color) in [
+            ("Price", &spread.prices, price_color),
+            ("Fuel cost", &spread.fuel_costs, cost_color),
+        ] {
+            chart
+                .draw_series(LineSeries::new(
+                    series.iter().copied().enumerate(),
+                    color.stroke_width(3),
+                ))?
+                .label(label)
+                .legend(move |(x, y)| {
+                    PathElement::new([(x, y), (x + 10, y)], color.stroke_width(3))
+                });
+        }
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE.mix(0.8))
+            .position(SeriesLabelPosition::UpperLeft)
+            .label_font(("Calibri", 16))
+            .draw()?;
+
+        self.also_csv(|| {
+            [
+                ("Price", &spread.prices),
+                ("Fuel cost", &spread.fuel_costs),
+                ("Spark spread", &spread.spreads),
+            ]
+            .into_iter()
+            .flat_map(|(series, values)| {
+                values
+                    .iter()
+                    .enumerate()
+                    .map(move |(idx, val)| PlotPoint::new(series, profile.label(idx), *val))
+            })
+            .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws how far each source's average value moved from a base
+    /// scenario. Sources without a delta are left off.
+    pub fn value_delta(&self, delta: &[f64; 14], title: &str) -> error::Result<()> {
+        let deltas: Vec<_> = delta
+            .iter()
+            .copied()
+            .zip(self.source_keys())
+            .skip(1)
+            .filter(|(val, _)| !val.is_nan())
+            .collect();
+        let root = self.backend((1080, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+        let [rise, fall, ..] = self.options.theme.group_colors();
+
+        let y_range = self.y_range(Self::delta_range(deltas.iter().map(|(val, _)| *val))?);
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(72)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d((0..deltas.len() - 1).into_segmented(), y_range)?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .y_desc(self.y_desc(format!("Change in {}", self.labels.units.price)))
+            .x_desc(self.x_desc("Electricity source"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|seg| match seg {
+                SegmentValue::Last | SegmentValue::Exact(_) => "".to_string(),
+                SegmentValue::CenterOf(idx) => deltas
+                    .get(*idx)
+                    .map(|(_, (source, _))| source.to_string())
+                    .unwrap_or_default(),
+            })
+            .y_label_formatter(&|price| Self::signed_price(*price))
+            .x_labels(20)
+            .y_labels(20)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        chart.draw_series(
+            Histogram::vertical(&chart)
+                .margin(10)
+                .style_func(|_, val| match *val < 0. {
+                    true => fall.filled(),
+                    false => rise.filled(),
+                })
+                .data(deltas.iter().enumerate().map(|(idx, (val, _))| (idx, *val))),
+        )?;
+        chart.draw_series(LineSeries::new(
+            [(SegmentValue::Exact(0), 0.), (SegmentValue::Last, 0.)],
+            BLACK.stroke_width(2),
+        ))?;
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        self.also_csv(|| {
+            deltas
+                .iter()
+                .map(|(val, (source, _))| PlotPoint::new("Change", source, *val))
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws a bar per way of dispatching a battery, stacking its revenue
+    /// from each source in $/kW-year.
+    pub fn storage_revenue(&self, revenue: &StorageRevenue, title: &str) -> error::Result<()> {
+        let bars: Vec<(String, [f64; 4])> = revenue
+            .revenues
+            .iter()
+            .map(|(dispatch, dollars)| {
+                let parts = dollars.by_source().map(|part| revenue.per_kw_year(part));
+                (dispatch.name().to_string(), parts)
+            })
+            .collect();
+        self.revenue_bars(&bars, "Revenue, $/kW-year", title)
+    }
+
+    /// Draws a bar per month, stacking a battery's revenue from each
+    /// source in $/kW.
+    pub fn value_stack(&self, stack: &ValueStack, title: &str) -> error::Result<()> {
+        let bars: Vec<(String, [f64; 4])> = stack
+            .months
+            .iter()
+            .map(|month| {
+                let parts = month.revenue.by_source().map(|part| stack.per_kw(part));
+                (month.month.clone(), parts)
+            })
+            .collect();
+        self.revenue_bars(&bars, "Revenue, $/kW", title)
+    }
+
+    /// Stacked bars of revenue by source, in `Revenue::SOURCES` order.
+    fn revenue_bars(
+        &self,
+        bars: &[(String, [f64; 4])],
+        y_desc: &str,
+        title: &str,
+    ) -> error::Result<()> {
+        let highest = bars
+            .iter()
+            .map(|(_, parts)| parts.iter().sum::<f64>())
+            .fold(f64::NAN, f64::max);
+        if bars.is_empty() || highest.is_nan() {
+            return Err(err!(NoData, "No revenue to graph"));
+        }
+        let width = (bars.len() as u32 * 90 + 240).max(1080);
+        let root = self.backend((width, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+        let colors = self.options.theme.group_colors();
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(
+                (0..bars.len() - 1).into_segmented(),
+                0.0..highest.max(1.) * 1.35,
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .bold_line_style(WHITE.mix(0.3))
+            .y_desc(self.y_desc(y_desc))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|seg| match seg {
+                SegmentValue::CenterOf(idx) if *idx < bars.len() => bars[*idx].0.clone(),
+                _ => "".to_string(),
+            })
+            .y_label_formatter(&|dollars| format!("${dollars:.0}"))
+            .x_labels(bars.len())
+            .y_labels(10)
+            .x_label_style(("sans-serif", 18))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        // Narrow bars in a crowded chart, wide ones when there are few.
+        let gap = (360 / bars.len()).clamp(8, 60) as u32;
+        for (part, label) in Revenue::SOURCES.into_iter().enumerate() {
+            let color = colors[part % colors.len()];
+            chart
+                .draw_series(bars.iter().enumerate().map(|(idx, (_, parts))| {
+                    let base: f64 = parts[..part].iter().sum();
+                    let mut bar = Rectangle::new(
+                        [
+                            (SegmentValue::Exact(idx), base),
+                            (SegmentValue::Exact(idx + 1), base + parts[part]),
+                        ],
+                        color.filled(),
+                    );
+                    bar.set_margin(0, 0, gap, gap);
+                    bar
+                }))?
+                .label(label)
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                });
+        }
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE.mix(0.8))
+            .position(SeriesLabelPosition::UpperLeft)
+            .label_font(("Calibri", 16))
+            .draw()?;
+
+        self.also_csv(|| {
+            bars.iter()
+                .flat_map(|(bar, parts)| {
+                    Revenue::SOURCES
+                        .into_iter()
+                        .zip(parts)
+                        .map(move |(series, val)| PlotPoint::new(series, bar, *val))
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws each node's average congestion component as a bar, most
+    /// congested at the top, labeled with its average LMP.
+    pub fn congested_nodes(&self, nodes: &[NodeCongestion], title: &str) -> error::Result<()> {
+        if nodes.is_empty() {
+            return Err(err!(NoData, "No nodes to graph"));
+        }
+        let height = (nodes.len() as u32 * 36 + 240).max(720);
+        let root = self.backend((1440, height))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+        let [rise, fall, ..] = self.options.theme.group_colors();
+        // The first node goes on top, in the last row.
+        let row = |rank: usize| nodes.len() - 1 - rank;
+
+        let x_range = self.y_range(Self::delta_range(
+            nodes.iter().map(|node| node.avg_congestion),
+        )?);
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(240)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(x_range, (0..nodes.len() - 1).into_segmented())?;
+
+        chart
+            .configure_mesh()
+            .disable_y_mesh()
+            .bold_line_style(WHITE.mix(0.3))
+            .x_desc(self.x_desc(format!("Average congestion, {}", self.labels.units.price)))
+            .axis_desc_style(("sans-serif", 30))
+            .y_label_formatter(&|seg| match seg {
+                SegmentValue::CenterOf(idx) if *idx < nodes.len() => {
+                    let node = &nodes[row(*idx)];
+                    format!("{} (avg ${:.2})", node.node, node.avg_lmp)
+                }
+                _ => "".to_string(),
+            })
+            .x_label_formatter(&|price| Self::signed_price(*price))
+            .y_labels(nodes.len())
+            .x_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+
+        chart.draw_series(
+            Histogram::horizontal(&chart)
+                .margin(6)
+                .style_func(|_, val| match *val < 0. {
+                    true => fall.filled(),
+                    false => rise.filled(),
+                })
+                .data(
+                    nodes
+                        .iter()
+                        .enumerate()
+                        .map(|(rank, node)| (row(rank), node.avg_congestion)),
+                ),
+        )?;
+
+        self.also_csv(|| {
+            nodes
+                .iter()
+                .map(|node| PlotPoint::new("Average congestion", &node.node, node.avg_congestion))
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws what each tariff costs per kWh of the household's load as a
+    /// bar, in the order the tariffs were defined, labeled with the total.
+    pub fn tariff_costs(&self, costs: &[TariffCost], title: &str) -> error::Result<()> {
+        if costs.is_empty() {
+            return Err(err!(NoData, "No tariffs to graph"));
+        }
+        let height = (costs.len() as u32 * 36 + 240).max(720);
+        let root = self.backend((1440, height))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+        let [color, ..] = self.options.theme.group_colors();
+        // The first tariff goes on top, in the last row.
+        let row = |rank: usize| costs.len() - 1 - rank;
+
+        let x_range = self.y_range(Self::delta_range(
+            costs.iter().map(|cost| cost.cents_per_kwh),
+        )?);
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(240)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(x_range, (0..costs.len() - 1).into_segmented())?;
+
+        chart
+            .configure_mesh()
+            .disable_y_mesh()
+            .bold_line_style(WHITE.mix(0.3))
+            .x_desc(self.x_desc("Cost, ¢/kWh"))
+            .axis_desc_style(("sans-serif", 30))
+            .y_label_formatter(&|seg| match seg {
+                SegmentValue::CenterOf(idx) if *idx < costs.len() => {
+                    let cost = &costs[row(*idx)];
+                    format!("{} (${:.2})", cost.tariff, cost.total_cost)
+                }
+                _ => "".to_string(),
+            })
+            .x_label_formatter(&|cents| format!("{cents:.1}¢"))
+            .y_labels(costs.len())
+            .x_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+
+        chart.draw_series(
+            Histogram::horizontal(&chart)
+                .margin(6)
+                .style(color.filled())
+                .data(
+                    costs
+                        .iter()
+                        .enumerate()
+                        .map(|(rank, cost)| (row(rank), cost.cents_per_kwh)),
+                ),
+        )?;
+
+        self.also_csv(|| {
+            costs
+                .iter()
+                .map(|cost| PlotPoint::new("Cents per kWh", &cost.tariff, cost.cents_per_kwh))
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws each node's average LMP over the slots of a profile, a line
+    /// per node.
+    pub fn node_profiles(&self, profiles: &[NodeProfile], profile: Profile) -> error::Result<()> {
+        let root = self
+            .backend(Self::profile_size(profile))?
+            .into_drawing_area();
+        let root = self.canvas(&root)?;
+        let title = match profile {
+            Profile::Day => "Daily average LMP by node",
+            Profile::Week => "Weekly average LMP by node",
+        };
+
+        let y_range = self.y_range(Self::delta_range(
+            profiles.iter().flat_map(|node| node.prices.iter().copied()),
+        )?);
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(0..profile.slots(), y_range)?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .bold_line_style(WHITE.mix(0.3))
+            .y_desc(self.y_desc(&self.labels.units.price))
+            .x_desc(self.x_desc(Self::profile_desc(profile)))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|&idx| Self::profile_tick(profile, idx))
+            .y_label_formatter(&|price| Self::signed_price(*price))
+            .x_labels(Self::profile_labels(profile))
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        let colors = self.options.theme.group_colors();
+        for (idx, node) in profiles.iter().enumerate() {
+            let color = colors[idx % colors.len()];
+            chart
+                .draw_series(LineSeries::new(
+                    node.prices.iter().copied().enumerate(),
+                    color.stroke_width(3),
+                ))?
+                .label(node.node.as_str())
+                .legend(move |(x, y)| {
+                    PathElement::new([(x, y), (x + 10, y)], color.stroke_width(3))
+                });
+        }
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE.mix(0.8))
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 16))
+            .draw()?;
+
+        self.also_csv(|| {
+            profiles
+                .iter()
+                .flat_map(|node| {
+                    node.prices.iter().enumerate().map(move |(idx, price)| {
+                        PlotPoint::new(&node.node, profile.label(idx), *price)
+                    })
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws the chance price exceeds each threshold, a line per threshold,
+    /// over the slots of a profile.
+    pub fn price_exceedance(
+        &self,
+        exceedance: &PriceExceedance,
+        profile: Profile,
+    ) -> error::Result<()> {
+        let root = self
+            .backend(Self::profile_size(profile))?
+            .into_drawing_area();
+        let root = self.canvas(&root)?;
+        let title = match profile {
+            Profile::Day => "Chance of exceeding each price by time of day",
+            Profile::Week => "Chance of exceeding each price by hour of the week",
+        };
+
+        let max_probability = exceedance
+            .probabilities
+            .iter()
+            .flatten()
+            .filter(|p| !p.is_nan())
+            .fold(0f64, |acc, p| acc.max(*p));
+        let y_max = (max_probability * 1.1).clamp(0.01, 1.);
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(0..profile.slots(), self.y_range(0f64..y_max))?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .bold_line_style(WHITE.mix(0.3))
+            .y_desc(self.y_desc("Share of intervals"))
+            .x_desc(self.x_desc(Self::profile_desc(profile)))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|&idx| Self::profile_tick(profile, idx))
+            .y_label_formatter(&|p| format!("{:.1}%", p * 100.))
+            .x_labels(Self::profile_labels(profile))
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        let colors = self.options.theme.group_colors();
+        for (idx, threshold) in exceedance.thresholds.iter().enumerate() {
+            let color = colors[idx % colors.len()];
+            chart
+                .draw_series(LineSeries::new(
+                    exceedance
+                        .probabilities
+                        .iter()
+                        .map(|slot| slot[idx])
+                        .enumerate(),
+                    color.stroke_width(3),
+                ))?
+                .label(format!("Above ${threshold}/MWh"))
+                .legend(move |(x, y)| {
+                    PathElement::new([(x, y), (x + 10, y)], color.stroke_width(3))
+                });
+        }
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE.mix(0.8))
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 16))
+            .draw()?;
+
+        self.also_csv(|| {
+            exceedance
+                .thresholds
+                .iter()
+                .enumerate()
+                .flat_map(|(idx, threshold)| {
+                    let series = format!("Above ${threshold}");
+                    exceedance
+                        .probabilities
+                        .iter()
+                        .enumerate()
+                        .map(move |(slot, probabilities)| {
+                            PlotPoint::new(&series, profile.label(slot), probabilities[idx])
+                        })
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws the full price time series with the spike threshold and each
+    /// spike's peak marked.
+    pub fn price_spikes(
+        &self,
+        series: &[(NaiveDateTime, f64)],
+        spike_price: f64,
+        spikes: &[PriceSpike],
+    ) -> error::Result<()> {
+        let (Some(first), Some(last)) = (series.first(), series.last()) else {
+            return Err(err!(NoData, "Cannot graph an empty price series"));
+        };
+        let root = self.backend((1440, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let (min_price, max_price) = series.iter().fold((0f64, spike_price), |acc, (_, el)| {
+            (acc.0.min(*el), acc.1.max(*el))
+        });
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .caption(
+                self.title(format!("Price spikes above ${spike_price:.2}/MWh")),
+                ("sans-serif", 40.),
+            )
+            .build_cartesian_2d(
+                RangedDateTime::from(first.0..last.0),
+                self.price_range(min_price, max_price * 1.05),
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(&self.labels.units.price))
+            .x_desc(self.x_desc("Date"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|time| time.format("%Y-%m-%d").to_string())
+            .y_label_formatter(&|price| format!("${:.0}", self.y_scale.tick(*price)))
+            .x_labels(12)
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        let y = |price| self.y_scale.forward(price);
+        chart.draw_series(LineSeries::new(
+            series.iter().map(|&(time, price)| (time, y(price))),
+            BLUE_600.mix(0.6).stroke_width(1),
+        ))?;
+        chart.draw_series(LineSeries::new(
+            [(first.0, y(spike_price)), (last.0, y(spike_price))],
+            BLACK.stroke_width(2),
+        ))?;
+        chart.draw_series(
+            spikes
+                .iter()
+                .filter_map(|spike| {
+                    let time = NaiveDateTime::parse_from_str(&spike.peak_time, TIMESTAMP_FMT);
+                    time.ok().map(|time| (time, y(spike.peak_price)))
+                })
+                .map(|coord| Circle::new(coord, 5, RED.filled())),
+        )?;
+
+        self.draw_references(&chart, self.y_scale)?;
+
+        self.also_csv(|| {
+            let mut rows: Vec<_> = series
+                .iter()
+                .map(|(time, price)| PlotPoint::new("Price", time, *price))
+                .collect();
+            rows.extend(
+                [first.0, last.0].map(|time| PlotPoint::new("Spike threshold", time, spike_price)),
+            );
+            rows.extend(
+                spikes
+                    .iter()
+                    .map(|spike| PlotPoint::new("Spike peak", &spike.peak_time, spike.peak_price)),
+            );
+            rows
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws each day's arbitrage spread across the dataset, with the cheap
+    /// and expensive averages it's taken between shown faintly behind it.
+    pub fn daily_spreads(&self, spreads: &[DailySpread], title: &str) -> error::Result<()> {
+        let (Some(first), Some(last)) = (spreads.first(), spreads.last()) else {
+            return Err(err!(NoData, "Cannot graph an empty spread series"));
+        };
+        let root = self.backend((1440, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let (min_price, max_price) = spreads.iter().fold((0f64, 0f64), |acc, day| {
+            (
+                acc.0.min(day.cheap_avg),
+                acc.1.max(day.expensive_avg.max(day.spread)),
+            )
+        });
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(
+                RangedDate::from(first.date..last.date),
+                self.price_range(min_price, max_price * 1.05),
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(&self.labels.units.price))
+            .x_desc(self.x_desc("Date"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|date| date.format("%Y-%m-%d").to_string())
+            .y_label_formatter(&|price| format!("${:.0}", self.y_scale.tick(*price)))
+            .x_labels(12)
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        type Line = (&'static str, RGBColor, f64, fn(&DailySpread) -> f64);
+        let lines: [Line; 3] = [
+            ("Expensive average", RED, 0.35, |day| day.expensive_avg),
+            ("Cheap average", BLUE_600, 0.35, |day| day.cheap_avg),
+            ("Spread", BLACK, 1., |day| day.spread),
+        ];
+        for (label, color, alpha, value) in lines {
+            let style = color.mix(alpha).stroke_width(2);
+            chart
+                .draw_series(LineSeries::new(
+                    spreads
+                        .iter()
+                        .map(|day| (day.date, self.y_scale.forward(value(day)))),
+                    style,
+                ))?
+                .label(label)
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.mix(alpha).filled())
+                });
+        }
+
+        self.draw_references(&chart, self.y_scale)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 14))
+            .draw()?;
+
+        self.also_csv(|| {
+            lines
+                .iter()
+                .flat_map(|(label, _, _, value)| {
+                    spreads
+                        .iter()
+                        .map(move |day| PlotPoint::new(*label, day.date, value(day)))
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws the time of each day's price and net load peak across the
+    /// dataset, faintly, under the average peak time between the shifts
+    /// found in each, which are marked with dashed lines.
+    pub fn peak_timing(&self, timing: &PeakTiming, title: &str) -> error::Result<()> {
+        let (Some(first), Some(last)) = (timing.days.first(), timing.days.last()) else {
+            return Err(err!(NoData, "Cannot graph an empty peak series"));
+        };
+        let root = self.backend((1440, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+        let [price_color, net_load_color, ..] = self.options.theme.group_colors();
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(RangedDate::from(first.date..last.date), 0f64..24.)?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc("Time of daily peak"))
+            .x_desc(self.x_desc("Date"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|date| date.format("%Y-%m-%d").to_string())
+            .y_label_formatter(&|hours| PeakTiming::clock(hours * 60.))
+            .x_labels(12)
+            .y_labels(12)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+
+        type Series = (&'static str, &'static str, RGBColor, fn(&DailyPeak) -> f64);
+        let series: [Series; 2] = [
+            ("price", "Price peak", price_color, |day| day.price_peak),
+            ("net_load", "Net load peak", net_load_color, |day| {
+                day.net_load_peak
+            }),
+        ];
+        let mut levels = Vec::new();
+        for (name, label, color, value) in series {
+            chart.draw_series(timing.days.iter().map(|day| {
+                Circle::new((day.date, value(day) / 60.), 3, color.mix(0.3).filled())
+            }))?;
+            let mut starts: Vec<usize> = timing
+                .shifts
+                .iter()
+                .filter(|shift| shift.series == name)
+                .filter_map(|shift| timing.days.iter().position(|day| day.date == shift.date))
+                .collect();
+            starts.insert(0, 0);
+            let mut steps = Vec::new();
+            for (idx, &start) in starts.iter().enumerate() {
+                let end = starts.get(idx + 1).copied().unwrap_or(timing.days.len());
+                let days = &timing.days[start..end];
+                let level = days.iter().map(value).sum::<f64>() / days.len() as f64;
+                let until = timing.days.get(end).map_or(last.date, |day| day.date);
+                steps.extend([(days[0].date, level / 60.), (until, level / 60.)]);
+                levels.push(PlotPoint::new(
+                    format!("{label} level"),
+                    days[0].date,
+                    level,
+                ));
+            }
+            let style = color.stroke_width(3);
+            chart
+                .draw_series(LineSeries::new(steps, style))?
+                .label(label)
+                .legend(move |(x, y)| PathElement::new([(x, y), (x + 10, y)], style));
+            for shift in timing.shifts.iter().filter(|shift| shift.series == name) {
+                chart.draw_series(DashedLineSeries::new(
+                    [(shift.date, 0.), (shift.date, 24.)],
+                    8,
+                    6,
+                    color.mix(0.6).stroke_width(2),
+                ))?;
+            }
+        }
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE.mix(0.8))
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 16))
+            .draw()?;
+
+        self.also_csv(|| {
+            series
+                .iter()
+                .flat_map(|(_, label, _, value)| {
+                    timing
+                        .days
+                        .iter()
+                        .map(move |day| PlotPoint::new(*label, day.date, value(day)))
+                })
+                .chain(levels)
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Splits a daily series wherever days are missing, so lines break over
+    /// gaps in the data instead of bridging them.
+    fn runs_of_days<T>(series: &[(NaiveDate, T)]) -> impl Iterator<Item = &[(NaiveDate, T)]> {
+        series.chunk_by(|a, b| (b.0 - a.0).num_days() == 1)
+    }
+
+    /// Draws daily average price across the dataset, faintly, under its
+    /// rolling average.
+    pub fn price_timeseries(
+        &self,
+        daily: &[(NaiveDate, f64)],
+        rolling: &[(NaiveDate, f64)],
+        title: &str,
+    ) -> error::Result<()> {
+        let (Some(first), Some(last)) = (daily.first(), daily.last()) else {
+            return Err(err!(NoData, "Cannot graph an empty price series"));
+        };
+        let root = self.backend((1440, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let (min_price, max_price) = daily.iter().fold((0f64, 0f64), |acc, (_, price)| {
+            (acc.0.min(*price), acc.1.max(*price))
+        });
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(
+                RangedDate::from(first.0..last.0),
+                self.price_range(min_price, max_price * 1.05),
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(&self.labels.units.price))
+            .x_desc(self.x_desc("Date"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|date| date.format("%Y-%m-%d").to_string())
+            .y_label_formatter(&|price| format!("${:.0}", self.y_scale.tick(*price)))
+            .x_labels(12)
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        for (label, series, alpha) in [
+            ("Daily average", daily, 0.3),
+            ("Rolling average", rolling, 1.),
+        ] {
+            let style = RED.mix(alpha).stroke_width(2);
+            chart
+                .draw_series(Self::runs_of_days(series).map(|run| {
+                    PathElement::new(
+                        run.iter()
+                            .map(|&(date, price)| (date, self.y_scale.forward(price)))
+                            .collect::<Vec<_>>(),
+                        style,
+                    )
+                }))?
+                .label(label)
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], RED.mix(alpha).filled())
+                });
+        }
+
+        self.draw_references(&chart, self.y_scale)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 14))
+            .draw()?;
+
+        self.also_csv(|| {
+            [("Daily average", daily), ("Rolling average", rolling)]
+                .into_iter()
+                .flat_map(|(label, series)| {
+                    series
+                        .iter()
+                        .map(move |(date, price)| PlotPoint::new(label, date, *price))
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws each source's (rolling) daily average output across the dataset.
+    pub fn gen_timeseries(&self, gen: &[(NaiveDate, [f64; 14])], title: &str) -> error::Result<()> {
+        let gen: Vec<_> = gen
+            .iter()
+            .map(|(date, sources)| (*date, sources.map(|mw| self.unit.of(mw))))
+            .collect();
+        self.source_timeseries(
+            &gen,
+            self.unit.of(250.),
+            &self.unit.desc(&self.labels.units.power),
+            None,
+            title,
+        )
+    }
+
+    /// Draws each source's (rolling) revenue per day across the dataset.
+    pub fn revenue_timeseries(
+        &self,
+        revenue: &[(NaiveDate, [f64; 14])],
+        title: &str,
+    ) -> error::Result<()> {
+        let largest = revenue
+            .iter()
+            .flat_map(|(_, sources)| sources.iter().skip(1))
+            .fold(0f64, |acc, dollars| acc.max(dollars.abs()));
+        self.source_timeseries(
+            revenue,
+            largest * 0.05,
+            "Revenue per day",
+            Some(&|dollars| match dollars.abs() >= 1e6 {
+                true => format!("${:.1}M", dollars / 1e6),
+                false => format!("${:.0}k", dollars / 1e3),
+            }),
+            title,
+        )
+    }
+
+    /// Draws a line per source, leaving out the total, over a daily series
+    /// padded by `pad` above and below.
+    fn source_timeseries(
+        &self,
+        series: &[(NaiveDate, [f64; 14])],
+        pad: f64,
+        y_desc: &str,
+        y_fmt: Option<&dyn Fn(&f64) -> String>,
+        title: &str,
+    ) -> error::Result<()> {
+        let (Some(first), Some(last)) = (series.first(), series.last()) else {
+            return Err(err!(NoData, "Cannot graph an empty daily series"));
+        };
+        let root = self.backend((1440, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let days: Vec<_> = series.iter().map(|(_, sources)| *sources).collect();
+        let (min, max) = Self::gen_range(&days)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(
+                RangedDate::from(first.0..last.0),
+                self.y_range((min - pad)..(max + pad)),
+            )?;
+
+        let mut mesh = chart.configure_mesh();
+        mesh.disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(y_desc))
+            .x_desc(self.x_desc("Date"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|date| date.format("%Y-%m-%d").to_string())
+            .x_labels(12)
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16));
+        if let Some(y_fmt) = y_fmt {
+            mesh.y_label_formatter(y_fmt);
+        }
+        mesh.draw()?;
+        for (src_idx, (label, color)) in self.source_keys().enumerate().skip(1) {
+            chart
+                .draw_series(Self::runs_of_days(series).map(|run| {
+                    PathElement::new(
+                        run.iter()
+                            .map(|(date, sources)| (*date, sources[src_idx]))
+                            .collect::<Vec<_>>(),
+                        color.stroke_width(2),
+                    )
+                }))?
+                .label(label)
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                });
+        }
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 14))
+            .draw()?;
+
+        self.also_csv(|| self.source_points(&days, |idx| series[idx].0.to_string()))?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws one day's raw prices above its raw generation by source.
+    pub fn day(&self, day: &DayProfile) -> error::Result<()> {
+        let (_, height) = self.size((1080, 1080));
+        let root = self.backend((1080, 1080))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+        let root = root.titled(
+            &self.title(day.date.format("%A %B %-d, %Y").to_string()),
+            ("sans-serif", 40.),
+        )?;
+        let (upper, lower) = root.split_vertically(400 * height / 1080);
+        let slots = Compute::slots_per_day();
+        let time_label = |idx: &usize| {
+            let (hour, minute) = Compute::idx_5min_to_time(*idx);
+            format!("{hour:02}:{minute:02}")
+        };
+
+        let (min_price, max_price) = day.prices.iter().fold((0f64, 0f64), |acc, (_, el)| {
+            (acc.0.min(*el), acc.1.max(*el))
+        });
+        let mut price_chart = ChartBuilder::on(&upper)
+            .x_label_area_size(48)
+            .y_label_area_size(84)
+            .margin(20)
+            .build_cartesian_2d(
+                0..slots,
+                self.price_range(min_price, (max_price * 1.1).max(1.)),
+            )?;
+        price_chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(&self.labels.units.price))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&time_label)
+            .y_label_formatter(&|price| format!("${:.0}", self.y_scale.tick(*price)))
+            .x_labels(24)
+            .y_labels(8)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        price_chart.draw_series(LineSeries::new(
+            day.prices
+                .iter()
+                .map(|&(idx, price)| (idx, self.y_scale.forward(price))),
+            RED.stroke_width(3),
+        ))?;
+
+        let gen: Vec<_> = day
+            .gen
+            .iter()
+            .map(|(slot, arr)| (*slot, arr.map(|mw| self.unit.of(mw))))
+            .collect();
+        let (gen_min, gen_max) = gen
+            .iter()
+            .flat_map(|(_, arr)| arr.iter().skip(1))
+            .fold((0f64, 0f64), |acc, el| (acc.0.min(*el), acc.1.max(*el)));
+        let pad = self.unit.of(250.);
+        let mut gen_chart = ChartBuilder::on(&lower)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .build_cartesian_2d(0..slots, (gen_min - pad)..(gen_max + pad))?;
+        gen_chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(self.unit.desc(&self.labels.units.power)))
+            .x_desc(self.x_desc("Time of day"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&time_label)
+            .x_labels(24)
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+
+        for (src_idx, (label, color)) in self.source_keys().enumerate().skip(1) {
+            gen_chart
+                .draw_series(LineSeries::new(
+                    gen.iter().map(|(slot, arr)| (*slot, arr[src_idx])),
+                    color.stroke_width(3),
+                ))?
+                .label(label)
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                });
+        }
+
+        gen_chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 14))
+            .draw()?;
+
+        self.also_csv(|| {
+            let mut rows: Vec<_> = day
+                .prices
+                .iter()
+                .map(|(idx, price)| PlotPoint::new("Price", time_label(idx), *price))
+                .collect();
+            let slots: Vec<_> = gen.iter().map(|(_, arr)| *arr).collect();
+            rows.extend(self.source_points(&slots, |idx| time_label(&gen[idx].0)));
+            rows
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws an event day's raw prices over its baseline's, above how far
+    /// each source's output strayed from its baseline.
+    pub fn event_day(&self, event: &EventDay) -> error::Result<()> {
+        let day = &event.day;
+        let (_, height) = self.size((1080, 1080));
+        let root = self.backend((1080, 1080))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+        let root = root.titled(
+            &self.title(format!(
+                "{} against the {} days around it",
+                day.date.format("%B %-d, %Y"),
+                event.baseline_days
+            )),
+            ("sans-serif", 40.),
+        )?;
+        let (upper, lower) = root.split_vertically(height / 2);
+        let slots = Compute::slots_per_day();
+        let time_label = |idx: &usize| Profile::Day.label(*idx);
+
+        let (min_price, max_price) = day
+            .prices
+            .iter()
+            .map(|(_, price)| *price)
+            .chain(event.baseline_prices.iter().copied())
+            .fold((0f64, 0f64), |acc, el| (acc.0.min(el), acc.1.max(el)));
+        let mut price_chart = ChartBuilder::on(&upper)
+            .x_label_area_size(48)
+            .y_label_area_size(84)
+            .margin(20)
+            .build_cartesian_2d(
+                0..slots,
+                self.price_range(min_price, (max_price * 1.1).max(1.)),
+            )?;
+        price_chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(&self.labels.units.price))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&time_label)
+            .y_label_formatter(&|price| format!("${:.0}", self.y_scale.tick(*price)))
+            .x_labels(24)
+            .y_labels(8)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        let baseline_style = BLACK.mix(0.5).stroke_width(2);
+        price_chart
+            .draw_series(DashedLineSeries::new(
+                event
+                    .baseline_prices
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, price)| (idx, self.y_scale.forward(*price))),
+                8,
+                6,
+                baseline_style,
+            ))?
+            .label("Baseline")
+            .legend(move |(x, y)| PathElement::new([(x, y), (x + 10, y)], baseline_style));
+        price_chart
+            .draw_series(LineSeries::new(
+                day.prices
+                    .iter()
+                    .map(|&(idx, price)| (idx, self.y_scale.forward(price))),
+                RED.stroke_width(3),
+            ))?
+            .label("Price")
+            .legend(|(x, y)| PathElement::new([(x, y), (x + 10, y)], RED.stroke_width(3)));
+        self.draw_references(&price_chart, self.y_scale)?;
+        price_chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE.mix(0.8))
+            .position(SeriesLabelPosition::UpperLeft)
+            .label_font(("Calibri", 14))
+            .draw()?;
+
+        let changes: Vec<(usize, [f64; 14])> = day
+            .gen
+            .iter()
+            .map(|(idx, sources)| {
+                let baseline = event.baseline_gen[*idx];
+                (
+                    *idx,
+                    std::array::from_fn(|src| self.unit.of(sources[src] - baseline[src])),
+                )
+            })
+            .collect();
+        let y_range = Self::delta_range(
+            changes
+                .iter()
+                .flat_map(|(_, sources)| sources.iter().skip(1).copied()),
+        )?;
+        let mut gen_chart = ChartBuilder::on(&lower)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .build_cartesian_2d(0..slots, self.y_range(y_range))?;
+        gen_chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(format!(
+                "Change from baseline ({})",
+                self.unit.desc(&self.labels.units.power)
+            )))
+            .x_desc(self.x_desc("Time of day"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&time_label)
+            .y_label_formatter(&|change| format!("{change:+.0}"))
+            .x_labels(24)
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        gen_chart.draw_series(LineSeries::new(
+            [(0, 0.), (slots, 0.)],
+            BLACK.mix(0.5).stroke_width(1),
+        ))?;
+        for (src_idx, (label, color)) in self.source_keys().enumerate().skip(1) {
+            gen_chart
+                .draw_series(LineSeries::new(
+                    changes.iter().map(|(slot, arr)| (*slot, arr[src_idx])),
+                    color.stroke_width(3),
+                ))?
+                .label(label)
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                });
+        }
+        gen_chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE.mix(0.8))
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 14))
+            .draw()?;
+
+        self.also_csv(|| {
+            let mut rows: Vec<_> = day
+                .prices
+                .iter()
+                .map(|(idx, price)| PlotPoint::new("Price", time_label(idx), *price))
+                .collect();
+            rows.extend(
+                event
+                    .baseline_prices
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, price)| PlotPoint::new("Baseline price", time_label(&idx), *price)),
+            );
+            let slots: Vec<_> = changes.iter().map(|(_, arr)| *arr).collect();
+            rows.extend(self.source_points(&slots, |idx| time_label(&changes[idx].0)));
+            rows
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws a heatmap of source/price correlations with one row per hour of
+    /// the day plus a row for the whole dataset.
+    pub fn correlation_matrix(&self, corr: &SourcePriceCorrelation) -> error::Result<()> {
+        let labels: Vec<_> = self.source_keys().map(|(key, _)| key).collect();
+        let rows: Vec<(String, &[f64; 14])> = corr
+            .hourly
+            .iter()
+            .enumerate()
+            .map(|(hour, row)| (format!("{hour:02}:00"), row))
+            .chain(std::iter::once(("All".to_string(), &corr.overall)))
+            .collect();
+
+        let root = self.backend((1440, 1080))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .caption(
+                self.title("Correlation of generation with price"),
+                ("sans-serif", 40.),
+            )
+            .build_cartesian_2d(
+                (0..(labels.len() - 1)).into_segmented(),
+                (0..(rows.len() - 1)).into_segmented(),
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .x_desc(self.x_desc("Electricity source"))
+            .y_desc(self.y_desc("Hour of day"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|seg| match seg {
+                SegmentValue::CenterOf(idx) if *idx < labels.len() => labels[*idx].to_string(),
+                _ => "".to_string(),
+            })
+            .y_label_formatter(&|seg| match seg {
+                SegmentValue::CenterOf(idx) if *idx < rows.len() => rows[*idx].0.clone(),
+                _ => "".to_string(),
+            })
+            .x_labels(labels.len())
+            .y_labels(rows.len())
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+
+        let cells = || {
+            rows.iter().enumerate().flat_map(|(row_idx, (_, row))| {
+                row.iter()
+                    .copied()
+                    .enumerate()
+                    .map(move |(col_idx, val)| (col_idx, row_idx, val))
+            })
+        };
+        chart.draw_series(cells().map(|(col, row, val)| {
+            Rectangle::new(
+                [
+                    (SegmentValue::Exact(col), SegmentValue::Exact(row)),
+                    (SegmentValue::Exact(col + 1), SegmentValue::Exact(row + 1)),
+                ],
+                Self::diverging_color(val).filled(),
+            )
+        }))?;
+        chart.draw_series(
+            cells()
+                .filter(|(_, _, val)| !val.is_nan())
+                .map(|(col, row, val)| {
+                    Text::new(
+                        format!("{val:.2}"),
+                        (SegmentValue::CenterOf(col), SegmentValue::CenterOf(row)),
+                        ("sans-serif", 14),
+                    )
+                }),
+        )?;
 
-    pub fn new(path: &'a Path) -> Self {
-        Graphing { path }
+        self.also_csv(|| {
+            rows.iter()
+                .flat_map(|(row, vals)| {
+                    labels
+                        .iter()
+                        .zip(vals.iter())
+                        .map(move |(col, val)| PlotPoint::new(row, col, *val))
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
     }
 
-    pub fn daily_price(&self, prices: &[f64]) -> anyhow::Result<()> {
-        let root = BitMapBackend::new(self.path, (1080, 720)).into_drawing_area();
-        root.fill(&Self::CHART_COLOR)?;
+    /// Draws each source's capacity factor across labeled slots (times of
+    /// day or months). Sources without a known capacity are skipped.
+    pub fn capacity_factors(
+        &self,
+        factors: &[(String, [f64; 14])],
+        title: &str,
+        x_desc: &str,
+    ) -> error::Result<()> {
+        let root = self.backend((1080, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let max_factor = factors
+            .iter()
+            .flat_map(|(_, row)| row.iter().skip(1))
+            .filter(|val| !val.is_nan())
+            .fold(1f64, |acc, el| acc.max(*el));
 
-        let max_price = prices.iter().fold(prices[0], |acc, el| el.max(acc));
         let mut chart = ChartBuilder::on(&root)
             .x_label_area_size(72)
             .y_label_area_size(72)
             .margin(20)
-            .caption("Daily average price/MWh", ("sans-serif", 40.))
-            .build_cartesian_2d(0..(prices.len()), 0f64..max_price)?;
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(
+                0..factors.len().saturating_sub(1),
+                self.y_range(0f64..max_factor),
+            )?;
 
         chart
             .configure_mesh()
             .disable_x_mesh()
             .disable_y_mesh()
-            .bold_line_style(WHITE.mix(0.3))
-            .y_desc("$/MWh")
-            .x_desc("Time of day")
+            .y_desc(self.y_desc("Capacity factor"))
+            .x_desc(self.x_desc(x_desc))
             .axis_desc_style(("sans-serif", 30))
-            .x_label_formatter(&|&idx| {
-                let (hour, minute) = Compute::idx_5min_to_time(idx);
-                format!("{hour:02}:{minute:02}")
-            })
-            .y_label_formatter(&|price| format!("${:02}", price))
-            .x_labels(24)
+            .x_label_formatter(&|idx| factors.get(*idx).map_or(String::new(), |f| f.0.clone()))
+            .y_label_formatter(&|factor| format!("{:.0}%", factor * 100.))
+            .x_labels(factors.len().min(24))
             .y_labels(10)
             .x_label_style(("sans-serif", 16))
             .y_label_style(("sans-serif", 16))
             .draw()?;
+        for (src_idx, (label, color)) in self.source_keys().enumerate().skip(1) {
+            if factors.iter().all(|(_, row)| row[src_idx].is_nan()) {
+                continue;
+            }
+            chart
+                .draw_series(LineSeries::new(
+                    factors
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, (_, row))| (idx, row[src_idx])),
+                    color.stroke_width(3),
+                ))?
+                .label(label)
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                });
+        }
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 14))
+            .draw()?;
 
-        chart.draw_series(
-            Histogram::vertical(&chart)
-                .style(RED.mix(0.5).filled())
-                .data(prices.iter().enumerate().map(|(idx, &val)| (idx, val))),
-        )?;
+        self.also_csv(|| {
+            self.source_keys()
+                .enumerate()
+                .skip(1)
+                .filter(|(src_idx, _)| factors.iter().any(|(_, row)| !row[*src_idx].is_nan()))
+                .flat_map(|(src_idx, (source, _))| {
+                    factors
+                        .iter()
+                        .map(move |(label, row)| PlotPoint::new(source, label, row[src_idx]))
+                })
+                .collect()
+        })?;
 
         root.present()?;
 
         Ok(())
     }
 
-    pub fn daily_gen(&self, gen: &[[f64; 14]], title: &str) -> anyhow::Result<()> {
-        let root = BitMapBackend::new(self.path, (1080, 720)).into_drawing_area();
-        root.fill(&Self::CHART_COLOR)?;
+    /// Draws one cluster of bars per source with a bar for each labeled group,
+    /// e.g. value by source during solar vs non-solar hours.
+    pub fn grouped_values(&self, groups: &[(String, [f64; 14])], title: &str) -> error::Result<()> {
+        self.grouped_bars(groups, title, &self.labels.units.price, &|price| {
+            format!("${price:.2}")
+        })
+    }
 
-        let gen_min = gen
+    /// Like `grouped_values`, but for each source's share of generation.
+    pub fn grouped_shares(&self, groups: &[(String, [f64; 14])], title: &str) -> error::Result<()> {
+        let percents: Vec<_> = groups
             .iter()
-            .flat_map(|arr| arr.iter().skip(1))
-            .min_by(|x, y| x.partial_cmp(y).unwrap_or(Ordering::Equal))
-            .ok_or_else(|| anyhow!("Failed to compute chart min"))?;
-        let gen_max = gen
+            .map(|(label, shares)| (label.clone(), shares.map(|share| share * 100.)))
+            .collect();
+        self.grouped_bars(&percents, title, "Share of generation", &|pct| {
+            format!("{pct:.0}%")
+        })
+    }
+
+    fn grouped_bars(
+        &self,
+        groups: &[(String, [f64; 14])],
+        title: &str,
+        y_desc: &str,
+        y_fmt: &dyn Fn(&f64) -> String,
+    ) -> error::Result<()> {
+        // Skip Total and any source with no value in every group.
+        let sources: Vec<(usize, &str)> = self
+            .source_keys()
+            .enumerate()
+            .skip(1)
+            .filter(|(idx, _)| groups.iter().any(|(_, vals)| vals[*idx] != 0.))
+            .map(|(idx, (label, _))| (idx, label))
+            .collect();
+        if sources.is_empty() || groups.is_empty() {
+            return Err(err!(NoData, "No values to graph"));
+        }
+        // Each source gets one slot per group plus one empty slot as a gap.
+        let stride = groups.len() + 1;
+        let slots = sources.len() * stride;
+
+        let (min_val, max_val) = groups
             .iter()
-            .flat_map(|arr| arr.iter().skip(1))
-            .max_by(|x, y| x.partial_cmp(y).unwrap_or(Ordering::Equal))
-            .ok_or_else(|| anyhow!("Failed to compute chart max"))?;
+            .flat_map(|(_, vals)| sources.iter().map(|(idx, _)| vals[*idx]))
+            .fold((0f64, 0f64), |acc, el| (acc.0.min(el), acc.1.max(el)));
+
+        let root = self.backend((1080, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(72)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(
+                (0..(slots - 1)).into_segmented(),
+                self.y_range((min_val * 1.1)..(max_val * 1.1)),
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .y_desc(self.y_desc(y_desc))
+            .x_desc(self.x_desc("Electricity source"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|seg| match seg {
+                SegmentValue::CenterOf(slot) if slot % stride == (groups.len() - 1) / 2 => sources
+                    .get(slot / stride)
+                    .map_or("", |src| src.1)
+                    .to_string(),
+                _ => "".to_string(),
+            })
+            .y_label_formatter(y_fmt)
+            .x_labels(slots)
+            .y_labels(20)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        let colors = self.options.theme.group_colors();
+        for (group_idx, (label, vals)) in groups.iter().enumerate() {
+            let color = colors[group_idx % colors.len()];
+            chart
+                .draw_series(sources.iter().enumerate().map(|(pos, (src_idx, _))| {
+                    let slot = pos * stride + group_idx;
+                    Rectangle::new(
+                        [
+                            (SegmentValue::Exact(slot), 0.),
+                            (SegmentValue::Exact(slot + 1), vals[*src_idx]),
+                        ],
+                        color.filled(),
+                    )
+                }))?
+                .label(label.as_str())
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                });
+        }
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE)
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 14))
+            .draw()?;
+
+        self.also_csv(|| {
+            groups
+                .iter()
+                .flat_map(|(group, vals)| {
+                    sources
+                        .iter()
+                        .map(move |(idx, source)| PlotPoint::new(group, source, vals[*idx]))
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws one line per group over the five-minute slots of the day, e.g.
+    /// hydro output in each water year.
+    pub fn grouped_profiles(
+        &self,
+        groups: &[(String, Vec<f64>)],
+        title: &str,
+        y_desc: &str,
+    ) -> error::Result<()> {
+        let (min_val, max_val) = groups
+            .iter()
+            .flat_map(|(_, vals)| vals.iter().copied())
+            .fold((f64::MAX, f64::MIN), |acc, el| {
+                (acc.0.min(el), acc.1.max(el))
+            });
+        if groups.is_empty() || min_val > max_val {
+            return Err(err!(NoData, "No profiles to graph"));
+        }
+        let pad = ((max_val - min_val) * 0.1).max(1.);
+        let slots = groups.iter().map(|(_, vals)| vals.len()).max().unwrap_or(0);
+
+        let root = self.backend((1080, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
 
         let mut chart = ChartBuilder::on(&root)
             .x_label_area_size(72)
             .y_label_area_size(84)
             .margin(20)
-            .caption(title, ("sans-serif", 40.))
-            .build_cartesian_2d(0..(gen.len()), (*gen_min - 250.)..(*gen_max + 250.))?;
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(0..slots, self.y_range((min_val - pad)..(max_val + pad)))?;
 
         chart
             .configure_mesh()
             .disable_x_mesh()
             .disable_y_mesh()
             .bold_line_style(WHITE.mix(0.3))
-            .y_desc("MWh")
-            .x_desc("Time of day")
+            .y_desc(self.y_desc(y_desc))
+            .x_desc(self.x_desc("Time of day"))
             .axis_desc_style(("sans-serif", 30))
             .x_label_formatter(&|&idx| {
                 let (hour, minute) = Compute::idx_5min_to_time(idx);
                 format!("{hour:02}:{minute:02}")
             })
+            .y_label_formatter(&|val| format!("{val:.0}"))
             .x_labels(24)
             .y_labels(10)
             .x_label_style(("sans-serif", 16))
             .y_label_style(("sans-serif", 16))
             .draw()?;
-
-        for (src_idx, (label, color)) in EnergyGenCsvRow::source_keys().enumerate().skip(1) {
+        let colors = self.options.theme.group_colors();
+        for (group_idx, (label, vals)) in groups.iter().enumerate() {
+            let color = colors[group_idx % colors.len()];
             chart
                 .draw_series(LineSeries::new(
-                    gen.iter()
-                        .enumerate()
-                        .map(|(timeslice, arr)| (timeslice, arr[src_idx])),
+                    vals.iter().copied().enumerate(),
                     color.stroke_width(3),
                 ))?
+                .label(label.as_str())
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                });
+        }
+
+        self.draw_references(&chart, YScale::Linear)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE)
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 14))
+            .draw()?;
+
+        self.also_csv(|| {
+            groups
+                .iter()
+                .flat_map(|(label, vals)| {
+                    vals.iter().enumerate().map(move |(idx, val)| {
+                        let (hour, minute) = Compute::idx_5min_to_time(idx);
+                        PlotPoint::new(label, format!("{hour:02}:{minute:02}"), *val)
+                    })
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Draws the positive direction above and the negative direction below
+    /// the axis for each hour of day, labeling each bar with the average price
+    /// energy moved at. `labels` name the two directions.
+    pub fn directions(
+        &self,
+        hours: &[DirectionalHour],
+        labels: [&str; 2],
+        title: &str,
+    ) -> error::Result<()> {
+        if hours.is_empty() {
+            return Err(err!(NoData, "No data to graph"));
+        }
+        let gwh = |mwh: f64| mwh / 1000.;
+        let max_positive = hours
+            .iter()
+            .fold(0f64, |acc, h| acc.max(gwh(h.positive.mwh)));
+        let max_negative = hours
+            .iter()
+            .fold(0f64, |acc, h| acc.max(gwh(h.negative.mwh)));
+
+        let root = self.backend((1080, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .caption(self.title(title), ("sans-serif", 40.))
+            .build_cartesian_2d(
+                (0..(hours.len() - 1)).into_segmented(),
+                self.y_range((-max_negative * 1.15)..(max_positive * 1.15)),
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .y_desc(self.y_desc(format!("GWh ({} below zero)", labels[1].to_lowercase())))
+            .x_desc(self.x_desc("Hour of day"))
+            .axis_desc_style(("sans-serif", 30))
+            .x_label_formatter(&|seg| match seg {
+                SegmentValue::CenterOf(idx) => format!("{:02}", hours[*idx].hour),
+                _ => "".to_string(),
+            })
+            .x_labels(hours.len())
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+        let [positive_color, negative_color, ..] = self.options.theme.group_colors();
+        let directions = [
+            (
+                labels[0],
+                positive_color,
+                1.,
+                hours.iter().map(|h| h.positive).collect::<Vec<_>>(),
+            ),
+            (
+                labels[1],
+                negative_color,
+                -1.,
+                hours.iter().map(|h| h.negative).collect(),
+            ),
+        ];
+        for (label, color, sign, flows) in directions {
+            chart
+                .draw_series(flows.iter().enumerate().map(|(idx, flow)| {
+                    let mut bar = Rectangle::new(
+                        [
+                            (SegmentValue::Exact(idx), 0.),
+                            (SegmentValue::Exact(idx + 1), sign * gwh(flow.mwh)),
+                        ],
+                        color.filled(),
+                    );
+                    bar.set_margin(0, 0, 3, 3);
+                    bar
+                }))?
                 .label(label)
                 .legend(move |(x, y)| {
                     Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
                 });
+            chart.draw_series(
+                flows
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, flow)| flow.intervals > 0)
+                    .map(|(idx, flow)| {
+                        // Nudge labels just past the end of the bar.
+                        let offset = 0.04 * max_positive.max(max_negative);
+                        let price = flow.avg_price().round();
+                        let sign_str = if price < 0. { "-" } else { "" };
+                        Text::new(
+                            format!("{sign_str}${:.0}", price.abs()),
+                            (SegmentValue::Exact(idx), sign * (gwh(flow.mwh) + offset)),
+                            ("sans-serif", 12),
+                        )
+                    }),
+            )?;
         }
 
+        self.draw_references(&chart, YScale::Linear)?;
+
         chart
             .configure_series_labels()
             .border_style(BLACK)
@@ -137,60 +4259,365 @@ impl<'a> Graphing<'a> {
             .label_font(("Calibri", 14))
             .draw()?;
 
+        self.also_csv(|| {
+            hours
+                .iter()
+                .flat_map(|h| {
+                    let hour = format!("{:02}:00", h.hour);
+                    [
+                        PlotPoint::new(labels[0], &hour, gwh(h.positive.mwh)),
+                        PlotPoint::new(labels[1], &hour, -gwh(h.negative.mwh)),
+                    ]
+                })
+                .collect()
+        })?;
+
         root.present()?;
 
         Ok(())
     }
 
-    pub fn avg_value(&self, values: &[f64; 14], title: &str) -> anyhow::Result<()> {
-        let values: Vec<_> = values
+    /// Stacks each neighbor's average flow into CAISO by hour of day, with
+    /// imports above zero and exports below. Neighbors past the theme's
+    /// palette are merged into "Other".
+    pub fn ba_interchange(&self, bas: &[BaInterchange], title: &str) -> error::Result<()> {
+        if bas.is_empty() {
+            return Err(err!(NoData, "No data to graph"));
+        }
+        let colors = self.options.theme.group_colors();
+        let shown = match bas.len() <= colors.len() {
+            true => bas.len(),
+            false => colors.len() - 1,
+        };
+        let mut stacks: Vec<(&str, [f64; 24])> = bas[..shown]
             .iter()
-            .copied()
-            .zip(EnergyGenCsvRow::source_keys())
-            .skip(1)
-            .filter(|(val, _)| *val > 0.)
+            .map(|ba| (ba.ba.as_str(), ba.hours.map(|mw| self.unit.of(mw))))
             .collect();
+        if shown < bas.len() {
+            let mut other = [0.; 24];
+            for ba in &bas[shown..] {
+                for (sum, mw) in other.iter_mut().zip(ba.hours) {
+                    *sum += self.unit.of(mw);
+                }
+            }
+            stacks.push(("Other", other));
+        }
 
-        let root = BitMapBackend::new(self.path, (1080, 720)).into_drawing_area();
-        root.fill(&Self::CHART_COLOR)?;
+        // Imports and exports stack away from zero separately.
+        let (mut max_in, mut max_out) = (0f64, 0f64);
+        for hour in 0..24 {
+            let flows = stacks.iter().map(|(_, hours)| hours[hour]);
+            max_in = max_in.max(flows.clone().filter(|mw| *mw > 0.).sum());
+            max_out = max_out.max(-flows.filter(|mw| *mw < 0.).sum::<f64>());
+        }
 
-        let max_price = values.iter().fold(values[0].0, |acc, el| el.0.max(acc));
+        let root = self.backend((1080, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
 
         let mut chart = ChartBuilder::on(&root)
             .x_label_area_size(72)
-            .y_label_area_size(72)
+            .y_label_area_size(84)
             .margin(20)
-            .caption(title, ("sans-serif", 40.))
+            .caption(self.title(title), ("sans-serif", 40.))
             .build_cartesian_2d(
-                (0..(values.len() - 1)).into_segmented(),
-                0f64..(max_price * 1.1),
+                (0..23usize).into_segmented(),
+                self.y_range((-max_out * 1.15)..(max_in * 1.15)),
             )?;
 
         chart
             .configure_mesh()
             .disable_x_mesh()
-            .y_desc("$/MWh")
-            .x_desc("Electricity source")
+            .y_desc(self.y_desc(format!(
+                "Average {} (exports below zero)",
+                self.unit.desc("MW")
+            )))
+            .x_desc(self.x_desc("Hour of day"))
             .axis_desc_style(("sans-serif", 30))
             .x_label_formatter(&|seg| match seg {
-                SegmentValue::Last | SegmentValue::Exact(_) => "".to_string(),
-                SegmentValue::CenterOf(idx) => values[*idx].1 .0.to_string(),
+                SegmentValue::CenterOf(hour) => format!("{hour:02}"),
+                _ => "".to_string(),
             })
-            .y_label_formatter(&|price| format!("${price:.2}"))
-            .x_labels(20)
-            .y_labels(20)
+            .x_labels(24)
+            .y_labels(10)
             .x_label_style(("sans-serif", 16))
             .y_label_style(("sans-serif", 16))
             .draw()?;
+        let (mut tops, mut bottoms) = ([0f64; 24], [0f64; 24]);
+        for (idx, (label, hours)) in stacks.iter().enumerate() {
+            let color = colors[idx % colors.len()];
+            let bars: Vec<_> = (0..24)
+                .map(|hour| {
+                    let mw = hours[hour];
+                    let base = if mw >= 0. { &mut tops } else { &mut bottoms };
+                    let from = base[hour];
+                    base[hour] += mw;
+                    let mut bar = Rectangle::new(
+                        [
+                            (SegmentValue::Exact(hour), from),
+                            (SegmentValue::Exact(hour + 1), from + mw),
+                        ],
+                        color.filled(),
+                    );
+                    bar.set_margin(0, 0, 3, 3);
+                    bar
+                })
+                .collect();
+            chart
+                .draw_series(bars)?
+                .label(*label)
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                });
+        }
 
-        chart.draw_series(
-            Histogram::vertical(&chart)
-                .style(BLUE_600.filled())
-                .data(values.iter().enumerate().map(|(idx, val)| (idx, val.0))),
+        self.draw_references(&chart, YScale::Linear)?;
+
+        chart
+            .configure_series_labels()
+            .border_style(BLACK)
+            .background_style(WHITE)
+            .position(SeriesLabelPosition::UpperRight)
+            .label_font(("Calibri", 14))
+            .draw()?;
+
+        self.also_csv(|| {
+            stacks
+                .iter()
+                .flat_map(|(label, hours)| {
+                    hours
+                        .iter()
+                        .enumerate()
+                        .map(move |(hour, mw)| PlotPoint::new(*label, format!("{hour:02}:00"), *mw))
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Plots each day's peak price and evening ramp against its high
+    /// temperature, side by side, each with its fitted line.
+    pub fn weather_scatter(
+        &self,
+        days: &[WeatherDay],
+        fits: &WeatherFits,
+        temp_desc: &str,
+    ) -> error::Result<()> {
+        if days.is_empty() {
+            return Err(err!(NoData, "No days to graph"));
+        }
+        let root = self.backend((1800, 720))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+        let root = root.titled(
+            &self.title("Daily peaks by high temperature"),
+            ("sans-serif", 40.),
+        )?;
+        let (width, _) = root.dim_in_pixel();
+        let (left, right) = root.split_horizontally(width / 2);
+        let [price_color, ramp_color, ..] = self.options.theme.group_colors();
+
+        let prices: Vec<_> = days
+            .iter()
+            .map(|d| (d.max_temp, d.peak_price, price_color.mix(0.6)))
+            .collect();
+        let price_desc = format!("Peak price ({})", self.labels.units.price);
+        self.scatter(
+            &left,
+            &prices,
+            &fits.peak_price,
+            temp_desc,
+            &price_desc,
+            &[],
+        )?;
+        let ramps: Vec<_> = days
+            .iter()
+            .map(|d| (d.max_temp, d.evening_ramp_mw, ramp_color.mix(0.6)))
+            .collect();
+        let ramp_desc = format!("Evening ramp ({})", self.labels.units.power);
+        self.scatter(
+            &right,
+            &ramps,
+            &fits.evening_ramp,
+            temp_desc,
+            &ramp_desc,
+            &[],
         )?;
 
+        self.also_csv(|| {
+            days.iter()
+                .flat_map(|d| {
+                    [
+                        PlotPoint::new("Peak price", d.max_temp, d.peak_price),
+                        PlotPoint::new("Evening ramp", d.max_temp, d.evening_ramp_mw),
+                    ]
+                })
+                .collect()
+        })?;
+
+        root.present()?;
+
+        Ok(())
+    }
+
+    /// Plots each five-minute interval's output from `source` against its
+    /// price, with `fit` as a line across them. With `by_hour`, points are
+    /// colored by their hour of the day around the color wheel.
+    pub fn price_scatter(
+        &self,
+        points: &[PricePoint],
+        fit: &LinearFit,
+        source: &str,
+        by_hour: bool,
+    ) -> error::Result<()> {
+        if points.is_empty() {
+            return Err(err!(NoData, "No points to graph"));
+        }
+        let root = self.backend((1200, 900))?.into_drawing_area();
+        let root = self.canvas(&root)?;
+        let root = root.titled(
+            &self.title(format!("Price by {source} output")),
+            ("sans-serif", 40.),
+        )?;
+        let [color, ..] = self.options.theme.group_colors();
+        let hour_color = |hour: u32| HSLColor(hour as f64 / 24., 0.7, 0.45).mix(0.5);
+
+        let coords: Vec<_> = points
+            .iter()
+            .map(|p| {
+                let color = if by_hour {
+                    hour_color(p.hour)
+                } else {
+                    color.mix(0.3)
+                };
+                (p.mw, p.lmp, color)
+            })
+            .collect();
+        let keys: Vec<_> = match by_hour {
+            true => (0..24)
+                .step_by(3)
+                .map(|hour| (format!("{hour:02}:00"), hour_color(hour)))
+                .collect(),
+            false => Vec::new(),
+        };
+        let x_desc = format!("{source} ({})", self.labels.units.power);
+        self.scatter(
+            &root,
+            &coords,
+            fit,
+            &x_desc,
+            &self.labels.units.price,
+            &keys,
+        )?;
+
+        self.also_csv(|| {
+            let mut rows: Vec<_> = points
+                .iter()
+                .map(|p| PlotPoint::new(format!("{:02}:00", p.hour), p.mw, p.lmp))
+                .collect();
+            let (low, high) = points.iter().fold((f64::MAX, f64::MIN), |acc, p| {
+                (acc.0.min(p.mw), acc.1.max(p.mw))
+            });
+            rows.extend([low, high].map(|mw| PlotPoint::new("Fit", mw, fit.at(mw))));
+            rows
+        })?;
+
         root.present()?;
 
         Ok(())
     }
+
+    /// Draws `points` in their colors onto `area` with `fit` as a line
+    /// across them, its equation in the legend after any `keys`.
+    fn scatter(
+        &self,
+        area: &DrawingArea<Backend<'a>, Shift>,
+        points: &[(f64, f64, RGBAColor)],
+        fit: &LinearFit,
+        x_desc: &str,
+        y_desc: &str,
+        keys: &[(String, RGBAColor)],
+    ) -> error::Result<()> {
+        let bounds = |values: &mut dyn Iterator<Item = f64>| {
+            let (min, max) = values
+                .filter(|val| !val.is_nan())
+                .fold((f64::MAX, f64::MIN), |acc, el| {
+                    (acc.0.min(el), acc.1.max(el))
+                });
+            let padding = ((max - min) * 0.05).max(1.);
+            (min - padding)..(max + padding)
+        };
+        let x_range = bounds(&mut points.iter().map(|p| p.0));
+        // Room for the fitted line where it leaves the points behind.
+        let fit_ends = [x_range.start, x_range.end].map(|x| fit.at(x));
+        let y_range = bounds(&mut points.iter().map(|p| p.1).chain(fit_ends));
+        let y = |value| self.y_scale.forward(value);
+
+        let mut chart = ChartBuilder::on(area)
+            .x_label_area_size(72)
+            .y_label_area_size(84)
+            .margin(20)
+            .build_cartesian_2d(
+                x_range.clone(),
+                self.price_range(y_range.start, y_range.end),
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .y_desc(self.y_desc(y_desc))
+            .x_desc(self.x_desc(x_desc))
+            .axis_desc_style(("sans-serif", 30))
+            .y_label_formatter(&|pos| format!("{:.0}", self.y_scale.tick(*pos)))
+            .x_labels(10)
+            .y_labels(10)
+            .x_label_style(("sans-serif", 16))
+            .y_label_style(("sans-serif", 16))
+            .draw()?;
+
+        // Thousands of intervals would bury each other at the size that
+        // suits a few dozen days.
+        let radius = if points.len() > 1000 { 2 } else { 4 };
+        chart.draw_series(
+            points
+                .iter()
+                .map(|&(px, py, color)| Circle::new((px, y(py)), radius, color.filled())),
+        )?;
+        for (label, color) in keys {
+            let color = *color;
+            chart
+                .draw_series(std::iter::empty::<Circle<(f64, f64), u32>>())?
+                .label(label)
+                .legend(move |(x, y)| Circle::new((x + 5, y), 4, color.filled()));
+        }
+        if !fit.slope.is_nan() {
+            chart
+                // Straight only on a linear axis, so traced in steps.
+                .draw_series(LineSeries::new(
+                    (0..=64).map(|step| {
+                        let x = x_range.start + (x_range.end - x_range.start) * step as f64 / 64.;
+                        (x, y(fit.at(x)))
+                    }),
+                    BLACK.stroke_width(2),
+                ))?
+                .label(format!(
+                    "y = {:.2}x + {:.2}, R² = {:.2}, n = {}",
+                    fit.slope, fit.intercept, fit.r_squared, fit.count
+                ))
+                .legend(|(x, y)| PathElement::new([(x, y), (x + 10, y)], BLACK.stroke_width(2)));
+        }
+        if !keys.is_empty() || !fit.slope.is_nan() {
+            chart
+                .configure_series_labels()
+                .border_style(BLACK)
+                .background_style(WHITE)
+                .position(SeriesLabelPosition::UpperLeft)
+                .label_font(("Calibri", 14))
+                .draw()?;
+        }
+
+        Ok(())
+    }
 }
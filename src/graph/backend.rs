@@ -0,0 +1,188 @@
+//! Lets every chart be drawn as either a bitmap or an svg, picked from the
+//! output file's extension. Plotters only writes bitmap files outside the
+//! browser, so a wasm32 build draws bitmaps into buffers alone.
+
+use crate::error::{self, err};
+use plotters::backend::{BitMapBackend, DrawingBackend, SVGBackend};
+use plotters_backend::{
+    BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingErrorKind,
+};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+type BitmapError = <BitMapBackend<'static> as DrawingBackend>::ErrorType;
+
+pub enum Backend<'a> {
+    Bitmap(BitMapBackend<'a>),
+    Svg(SVGBackend<'a>),
+}
+
+impl<'a> Backend<'a> {
+    /// An svg backend for `.svg` paths and a bitmap one for anything else.
+    pub fn new(path: &'a Path, size: (u32, u32)) -> error::Result<Self> {
+        if Self::is_svg(path) {
+            return Ok(Backend::Svg(SVGBackend::new(path, size)));
+        }
+        #[cfg(target_arch = "wasm32")]
+        return Err(err!(
+            Render,
+            "{path:?} isn't an svg, and bitmaps can only be drawn to a buffer in the browser"
+        ));
+        #[cfg(not(target_arch = "wasm32"))]
+        Ok(Backend::Bitmap(BitMapBackend::new(path, size)))
+    }
+
+    /// A gif at `path` that takes a frame per `present`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn gif(path: &'a Path, size: (u32, u32), frame_ms: u32) -> error::Result<Self> {
+        Ok(Backend::Bitmap(
+            BitMapBackend::gif(path, size, frame_ms).map_err(|e| err!(Render, "{e}"))?,
+        ))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn gif(path: &'a Path, _size: (u32, u32), _frame_ms: u32) -> error::Result<Self> {
+        Err(err!(Render, "{path:?} can't be animated from the browser"))
+    }
+
+    pub fn is_svg(path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+    }
+}
+
+#[derive(Debug)]
+pub enum BackendError {
+    Bitmap(BitmapError),
+    Svg(std::io::Error),
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BackendError::Bitmap(e) => write!(f, "{e}"),
+            BackendError::Svg(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for BackendError {}
+
+fn lift<E: Error + Send + Sync>(
+    kind: DrawingErrorKind<E>,
+    wrap: fn(E) -> BackendError,
+) -> DrawingErrorKind<BackendError> {
+    match kind {
+        DrawingErrorKind::DrawingError(e) => DrawingErrorKind::DrawingError(wrap(e)),
+        DrawingErrorKind::FontError(e) => DrawingErrorKind::FontError(e),
+    }
+}
+
+/// Calls the same method on whichever backend is inside.
+macro_rules! delegate {
+    ($self:ident, $backend:ident => $call:expr) => {
+        match $self {
+            Backend::Bitmap($backend) => $call.map_err(|e| lift(e, BackendError::Bitmap)),
+            Backend::Svg($backend) => $call.map_err(|e| lift(e, BackendError::Svg)),
+        }
+    };
+}
+
+impl DrawingBackend for Backend<'_> {
+    type ErrorType = BackendError;
+
+    fn get_size(&self) -> (u32, u32) {
+        match self {
+            Backend::Bitmap(backend) => backend.get_size(),
+            Backend::Svg(backend) => backend.get_size(),
+        }
+    }
+
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<BackendError>> {
+        delegate!(self, backend => backend.ensure_prepared())
+    }
+
+    fn present(&mut self) -> Result<(), DrawingErrorKind<BackendError>> {
+        delegate!(self, backend => backend.present())
+    }
+
+    fn draw_pixel(
+        &mut self,
+        point: BackendCoord,
+        color: BackendColor,
+    ) -> Result<(), DrawingErrorKind<BackendError>> {
+        delegate!(self, backend => backend.draw_pixel(point, color))
+    }
+
+    fn draw_line<S: BackendStyle>(
+        &mut self,
+        from: BackendCoord,
+        to: BackendCoord,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<BackendError>> {
+        delegate!(self, backend => backend.draw_line(from, to, style))
+    }
+
+    fn draw_rect<S: BackendStyle>(
+        &mut self,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<BackendError>> {
+        delegate!(self, backend => backend.draw_rect(upper_left, bottom_right, style, fill))
+    }
+
+    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        path: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<BackendError>> {
+        delegate!(self, backend => backend.draw_path(path, style))
+    }
+
+    fn draw_circle<S: BackendStyle>(
+        &mut self,
+        center: BackendCoord,
+        radius: u32,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<BackendError>> {
+        delegate!(self, backend => backend.draw_circle(center, radius, style, fill))
+    }
+
+    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        vert: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<BackendError>> {
+        delegate!(self, backend => backend.fill_polygon(vert, style))
+    }
+
+    fn draw_text<S: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &S,
+        pos: BackendCoord,
+    ) -> Result<(), DrawingErrorKind<BackendError>> {
+        delegate!(self, backend => backend.draw_text(text, style, pos))
+    }
+
+    fn estimate_text_size<S: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &S,
+    ) -> Result<(u32, u32), DrawingErrorKind<BackendError>> {
+        delegate!(self, backend => backend.estimate_text_size(text, style))
+    }
+
+    fn blit_bitmap(
+        &mut self,
+        pos: BackendCoord,
+        size: (u32, u32),
+        src: &[u8],
+    ) -> Result<(), DrawingErrorKind<BackendError>> {
+        delegate!(self, backend => backend.blit_bitmap(pos, size, src))
+    }
+}
@@ -0,0 +1,264 @@
+//! ### Join
+//! Merge joins of two time-ordered streams of rows, such as the price and
+//! generation csvs, matched on exact timestamps, on the nearest timestamp
+//! within a tolerance, or on the interval a coarser row covers (an hourly
+//! price or demand reading joined onto five-minute data). Both streams are
+//! read once, in order, and rows that find no partner are counted.
+
+use crate::error::{self, EnergyAnalysisError};
+use chrono::{NaiveDateTime, TimeDelta};
+use std::cell::Cell;
+use std::iter::{self, Peekable};
+use std::rc::Rc;
+
+/// How a left row finds its right row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinMode {
+    /// The right row stamped with the same time. Each right row joins at
+    /// most once.
+    Exact,
+    /// The right row nearest in time, if it's within the tolerance, taking
+    /// the earlier on a tie. A right row can join many left rows.
+    Nearest(TimeDelta),
+    /// The right row whose interval, from its timestamp for the given
+    /// width, holds the left row's time, as an hour holds its five-minute
+    /// intervals. A right row can join many left rows.
+    Interval(TimeDelta),
+}
+
+/// How many rows of each stream a join has read that found no partner.
+/// Rows past where the join stopped aren't read, so aren't counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JoinStats {
+    pub matched: usize,
+    pub left_unmatched: usize,
+    pub right_unmatched: usize,
+}
+
+type Timed<'a, T> = Box<dyn Iterator<Item = error::Result<(NaiveDateTime, T)>> + 'a>;
+
+/// Pairs each row of `left` with a row of `right`, as `mode` says. Both
+/// streams must be in time order. A read error ends the join and is
+/// returned by `finish`, or as the last item of `results`.
+pub struct TimeAlignedJoin<'a, L, R> {
+    left: Timed<'a, L>,
+    right: Peekable<Timed<'a, R>>,
+    mode: JoinMode,
+    /// The latest right row at or before the left row being joined, and
+    /// whether it has joined anything.
+    current: Option<(NaiveDateTime, R, bool)>,
+    stats: JoinStats,
+    /// The stats of the join this one was built on by `then`, as of the
+    /// last pair it handed over.
+    inner: Option<Rc<Cell<JoinStats>>>,
+    error: Option<EnergyAnalysisError>,
+    done: bool,
+}
+
+impl<'a, L, R: Clone> TimeAlignedJoin<'a, L, R> {
+    pub fn new(
+        left: impl Iterator<Item = error::Result<(NaiveDateTime, L)>> + 'a,
+        right: impl Iterator<Item = error::Result<(NaiveDateTime, R)>> + 'a,
+        mode: JoinMode,
+    ) -> Self {
+        let right: Timed<R> = Box::new(right);
+        Self {
+            left: Box::new(left),
+            right: right.peekable(),
+            mode,
+            current: None,
+            stats: JoinStats::default(),
+            inner: None,
+            error: None,
+            done: false,
+        }
+    }
+
+    /// The rows matched and left unmatched so far.
+    pub fn stats(&self) -> JoinStats {
+        self.stats
+    }
+
+    /// The rows the join this one was built on by `then` has matched and
+    /// left unmatched so far.
+    pub fn inner_stats(&self) -> Option<JoinStats> {
+        self.inner.as_ref().map(|stats| stats.get())
+    }
+
+    /// Reads the rest of the join and returns its stats, or the error that
+    /// cut it short.
+    pub fn finish(mut self) -> error::Result<JoinStats> {
+        for _ in self.by_ref() {}
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.stats),
+        }
+    }
+
+    /// The joined pairs, followed by the error that cut the join short, if
+    /// any, for callers that collect into a `Result`.
+    pub fn results(self) -> Results<'a, L, R> {
+        Results(self)
+    }
+
+    fn fail(&mut self, e: EnergyAnalysisError) -> Option<(L, R)> {
+        self.error = Some(e);
+        self.end()
+    }
+
+    /// Stops the join, counting the right row it stopped on if that never
+    /// joined. A join built on by `then` reads on to its end, so its stats
+    /// and errors cover all its rows.
+    fn end(&mut self) -> Option<(L, R)> {
+        if let Some((_, _, false)) = self.current.take() {
+            self.stats.right_unmatched += 1;
+        }
+        if self.inner.is_some() {
+            if let Some(Err(e)) = self.left.find(Result::is_err) {
+                self.error = Some(e);
+            }
+        }
+        self.done = true;
+        None
+    }
+
+    /// Moves `current` up to the last right row at or before `time`.
+    fn advance(&mut self, time: NaiveDateTime) -> error::Result<()> {
+        while let Some(next) = self.right.next_if(|next| match next {
+            Ok((next_time, _)) => *next_time <= time,
+            Err(_) => true,
+        }) {
+            let (next_time, row) = next?;
+            if let Some((_, _, false)) = self.current {
+                self.stats.right_unmatched += 1;
+            }
+            self.current = Some((next_time, row, false));
+        }
+        Ok(())
+    }
+
+    /// Whether no later left row could join a right row.
+    fn exhausted(&mut self, time: NaiveDateTime) -> bool {
+        if self.right.peek().is_some() {
+            return false;
+        }
+        match (&self.current, self.mode) {
+            // An exact partner would have joined already.
+            (None, _) | (Some(_), JoinMode::Exact) => true,
+            (Some((start, _, _)), JoinMode::Interval(width)) => time >= *start + width,
+            (Some((start, _, _)), JoinMode::Nearest(tolerance)) => time - *start > tolerance,
+        }
+    }
+
+    /// The right row `time` joins, if any.
+    fn partner(&mut self, time: NaiveDateTime) -> Option<R> {
+        let mode = self.mode;
+        if let JoinMode::Nearest(tolerance) = mode {
+            let before = self
+                .current
+                .as_ref()
+                .map(|(start, _, _)| (time - *start).abs())
+                .filter(|gap| *gap <= tolerance);
+            let next_is_nearer = matches!(
+                self.right.peek(),
+                Some(Ok((next_time, _))) if *next_time - time <= tolerance
+                    && before.is_none_or(|before| *next_time - time < before)
+            );
+            // Later left rows are nearer still to the next row than to
+            // this one, so this one can go.
+            if next_is_nearer {
+                if let Some(Ok((next_time, row))) = self.right.next() {
+                    if let Some((_, _, false)) = self.current {
+                        self.stats.right_unmatched += 1;
+                    }
+                    self.current = Some((next_time, row, false));
+                }
+            }
+        }
+        let (start, row, used) = self.current.as_mut()?;
+        let joins = match mode {
+            JoinMode::Exact => *start == time && !*used,
+            JoinMode::Interval(width) => time < *start + width,
+            JoinMode::Nearest(tolerance) => (time - *start).abs() <= tolerance,
+        };
+        if !joins {
+            return None;
+        }
+        *used = true;
+        match mode {
+            JoinMode::Exact => self.current.take().map(|(_, row, _)| row),
+            _ => Some(row.clone()),
+        }
+    }
+}
+
+impl<'a, L: 'a, R: Clone + 'a> TimeAlignedJoin<'a, L, R> {
+    /// Joins a third stream onto this join's pairs, timed by `time`, so
+    /// three streams align in one pass. A read error in this join ends the
+    /// new one with that error, and this join's stats stay readable through
+    /// `inner_stats`.
+    pub fn then<T: Clone>(
+        self,
+        time: impl Fn(&L, &R) -> error::Result<NaiveDateTime> + 'a,
+        third: impl Iterator<Item = error::Result<(NaiveDateTime, T)>> + 'a,
+        mode: JoinMode,
+    ) -> TimeAlignedJoin<'a, (L, R), T> {
+        let stats = Rc::new(Cell::new(JoinStats::default()));
+        let inner = Rc::clone(&stats);
+        let mut results = self.results();
+        let pairs = iter::from_fn(move || {
+            let next = results.next();
+            inner.set(results.0.stats);
+            next
+        })
+        .map(move |pair| {
+            let (left, right) = pair?;
+            Ok((time(&left, &right)?, (left, right)))
+        });
+        let mut join = TimeAlignedJoin::new(pairs, third, mode);
+        join.inner = Some(stats);
+        join
+    }
+}
+
+/// A join read as results. See `TimeAlignedJoin::results`.
+pub struct Results<'a, L, R>(TimeAlignedJoin<'a, L, R>);
+
+impl<L, R: Clone> Iterator for Results<'_, L, R> {
+    type Item = error::Result<(L, R)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.0.next() {
+            Some(pair) => Some(Ok(pair)),
+            None => self.0.error.take().map(Err),
+        }
+    }
+}
+
+impl<L, R: Clone> Iterator for TimeAlignedJoin<'_, L, R> {
+    type Item = (L, R);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            let (time, left) = match self.left.next() {
+                Some(Ok(row)) => row,
+                Some(Err(e)) => return self.fail(e),
+                None => return self.end(),
+            };
+            if let Err(e) = self.advance(time) {
+                return self.fail(e);
+            }
+            if let Some(right) = self.partner(time) {
+                self.stats.matched += 1;
+                return Some((left, right));
+            }
+            self.stats.left_unmatched += 1;
+            if self.exhausted(time) {
+                return self.end();
+            }
+        }
+    }
+}
@@ -1,3 +1,50 @@
+//! Parses CAISO price and generation data, computes averages, scenarios and
+//! statistics over it, and charts the results. Most programs start from
+//! `prelude`, which gathers the types they reach for first.
+//!
+//! Built for wasm32-unknown-unknown with `--no-default-features --features
+//! wasm`, it runs in a browser over bytes read into a `Dataset`, without
+//! the CLI, fetching or threads.
+
+pub mod ancillary;
+pub mod astro;
+pub(crate) mod cache;
+pub mod chart;
+#[cfg(feature = "native")]
+#[doc(hidden)]
+pub mod cli;
 pub mod compute;
+pub mod config;
 pub mod convert;
+pub mod dataset;
+pub mod error;
+pub mod fetch;
+pub mod forecast;
+pub mod frame;
+pub mod gas;
+#[cfg(feature = "graph")]
 pub mod graph;
+pub mod join;
+pub mod manifest;
+#[cfg(feature = "native")]
+pub mod monitor;
+pub mod nodal;
+pub(crate) mod output;
+pub mod pipeline;
+pub mod prelude;
+pub mod provenance;
+#[cfg(feature = "graph")]
+pub mod report;
+pub mod scenario;
+pub mod series;
+#[cfg(feature = "native")]
+pub mod serve;
+pub mod stats;
+pub mod store;
+pub mod tariff;
+#[cfg(feature = "testdata")]
+pub mod testdata;
+#[cfg(feature = "native")]
+pub(crate) mod watch;
+pub mod weather;
+pub mod xlsx;
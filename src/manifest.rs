@@ -0,0 +1,155 @@
+//! ### Manifest
+//! A parsed dataset split into one csv per calendar month, listed in a
+//! small toml file. Every command that takes a parsed csv accepts the
+//! manifest instead. Analyses of a single month read only its file, and
+//! analyses that don't cross month boundaries run the months in parallel.
+//!
+//! ```toml
+//! kind = "price"
+//!
+//! [[months]]
+//! month = "2024-01"
+//! file = "prices-2024-01.csv"
+//! rows = 8928
+//! ```
+
+use crate::convert::{parse_timestamp, RawKind};
+use crate::error::{self, err, Locate};
+use crate::output::{CsvWriter, WriteOptions};
+use chrono::{Datelike, NaiveDate};
+#[cfg(feature = "native")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub kind: RawKind,
+    pub months: Vec<Month>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Month {
+    /// `YYYY-MM`.
+    pub month: String,
+    /// Relative to the manifest's directory until `Manifest::open`
+    /// resolves it.
+    pub file: PathBuf,
+    pub rows: usize,
+}
+
+impl Manifest {
+    /// Whether `path` names a manifest rather than a csv, store or toml
+    /// config. A manifest is a toml file with top-level `kind` and `months`
+    /// keys, so a config like capacity.toml is read as plain bytes.
+    pub fn is_manifest(path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "toml")
+            && fs::read_to_string(path)
+                .ok()
+                .and_then(|text| text.parse::<toml::Table>().ok())
+                .is_some_and(|table| table.contains_key("kind") && table.contains_key("months"))
+    }
+
+    /// Reads a manifest, resolving its files against its directory.
+    pub fn open(path: &Path) -> error::Result<Self> {
+        let mut manifest: Self = toml::from_str(&fs::read_to_string(path)?)?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for month in &mut manifest.months {
+            month.file = dir.join(&month.file);
+        }
+        Ok(manifest)
+    }
+
+    /// The files of every month, in order.
+    pub fn files(&self) -> impl DoubleEndedIterator<Item = &Path> {
+        self.months.iter().map(|month| month.file.as_path())
+    }
+
+    /// The file holding `date`, if any does.
+    pub(crate) fn file_of(&self, date: NaiveDate) -> Option<&Path> {
+        let key = month_key(date);
+        self.months
+            .iter()
+            .find(|month| month.month == key)
+            .map(|month| month.file.as_path())
+    }
+
+    /// Runs `f` on each month's file in parallel, or in turn without the
+    /// `native` feature, returning the results in month order.
+    pub(crate) fn par_map<T: Send>(
+        &self,
+        f: impl Fn(&Path) -> error::Result<T> + Sync,
+    ) -> error::Result<Vec<T>> {
+        #[cfg(feature = "native")]
+        let months = self.months.par_iter();
+        #[cfg(not(feature = "native"))]
+        let months = self.months.iter();
+        months.map(|month| f(&month.file)).collect()
+    }
+
+    /// Splits a parsed csv of `kind` into one csv per month beside
+    /// `manifest`, named after it (`prices.toml` lists
+    /// `prices-2024-01.csv`, ...), then writes the manifest.
+    pub fn split(
+        csv: &Path,
+        kind: RawKind,
+        manifest: &Path,
+        options: WriteOptions,
+    ) -> error::Result<Self> {
+        let time_col = match kind {
+            RawKind::Price => "timestamp",
+            RawKind::Gen => "local_timestamp_start",
+        };
+        let dir = manifest.parent().unwrap_or(Path::new(""));
+        let stem = manifest
+            .file_stem()
+            .ok_or_else(|| err!(Invalid, "{manifest:?} has no file name"))?
+            .to_string_lossy();
+
+        let mut reader = csv::Reader::from_path(csv)?;
+        let header = reader.headers()?.clone();
+        let idx = header
+            .iter()
+            .position(|col| col == time_col)
+            .ok_or_else(|| err!(Invalid, "{csv:?} has no {time_col} column"))?;
+        let mut writers: BTreeMap<String, (PathBuf, CsvWriter<File>, usize)> = BTreeMap::new();
+        for record in reader.records() {
+            let record = record?;
+            let line = record.position().map_or(0, csv::Position::line);
+            let timestamp =
+                parse_timestamp(&record[idx]).locate(csv, line, time_col, &record[idx])?;
+            let key = month_key(timestamp.date());
+            if !writers.contains_key(&key) {
+                let file = PathBuf::from(format!("{stem}-{key}.csv"));
+                let mut writer = options.csv(&dir.join(&file))?;
+                writer.write_record(&header)?;
+                writers.insert(key.clone(), (file, writer, 0));
+            }
+            let (_, writer, rows) = writers.get_mut(&key).expect("just inserted");
+            writer.write_record(&record)?;
+            *rows += 1;
+        }
+
+        let mut months = Vec::new();
+        for (month, (file, mut writer, rows)) in writers {
+            writer.flush()?;
+            months.push(Month { month, file, rows });
+        }
+        let written = Self { kind, months };
+        options
+            .create(manifest)?
+            .write_all(toml::to_string(&written)?.as_bytes())?;
+        println!(
+            "Split {csv:?} into {} months listed in {manifest:?}",
+            written.months.len()
+        );
+        Ok(written)
+    }
+}
+
+fn month_key(date: NaiveDate) -> String {
+    format!("{}-{:02}", date.year(), date.month())
+}
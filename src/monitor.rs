@@ -0,0 +1,171 @@
+//! ### Monitor
+//! Watches the latest data for prices and solar shares crossing thresholds,
+//! and tells a shell command or webhook when they do, for experiments in
+//! shifting demand toward cheap or clean hours. The data is kept current by
+//! rerunning `fetch-oasis --append` and `fetch-eia --append` on a schedule.
+
+use crate::compute::Compute;
+use crate::error::{self, bail};
+use crate::fetch::Client;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+/// Conditions worth an alert. Unset thresholds are never checked.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Thresholds {
+    /// $/MWh.
+    pub price_above: Option<f64>,
+    /// $/MWh.
+    pub price_below: Option<f64>,
+    /// Percent of total generation.
+    pub solar_share_above: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AlertKind {
+    PriceAbove,
+    PriceBelow,
+    SolarShareAbove,
+}
+
+/// A threshold the data has just crossed.
+#[derive(Clone, Debug, Serialize)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub time: NaiveDateTime,
+    pub value: f64,
+    pub threshold: f64,
+}
+
+impl Alert {
+    pub fn message(&self) -> String {
+        match self.kind {
+            AlertKind::PriceAbove => format!(
+                "Price rose to ${:.2}/MWh at {}, above ${:.2}",
+                self.value, self.time, self.threshold
+            ),
+            AlertKind::PriceBelow => format!(
+                "Price fell to ${:.2}/MWh at {}, below ${:.2}",
+                self.value, self.time, self.threshold
+            ),
+            AlertKind::SolarShareAbove => format!(
+                "Solar reached {:.1}% of generation at {}, above {:.1}%",
+                self.value, self.time, self.threshold
+            ),
+        }
+    }
+}
+
+/// Remembers which thresholds are already crossed so each crossing alerts
+/// once, not on every check while the condition lasts.
+#[derive(Debug)]
+pub struct Monitor {
+    thresholds: Thresholds,
+    crossed: Vec<AlertKind>,
+}
+
+impl Monitor {
+    pub fn new(thresholds: Thresholds) -> Self {
+        Self {
+            thresholds,
+            crossed: Vec::new(),
+        }
+    }
+
+    /// Alerts for thresholds the latest price and generation newly cross.
+    pub fn check(
+        &mut self,
+        price: Option<(NaiveDateTime, f64)>,
+        gen: Option<(NaiveDateTime, [f64; 14])>,
+    ) -> Vec<Alert> {
+        let solar = gen.map(|(time, sources)| {
+            let share = 100. * sources[Compute::solar_idx()] / sources[0];
+            (time, share)
+        });
+        let Thresholds {
+            price_above,
+            price_below,
+            solar_share_above,
+        } = self.thresholds;
+        let checks = [
+            (AlertKind::PriceAbove, price, price_above, 1.),
+            (AlertKind::PriceBelow, price, price_below, -1.),
+            (AlertKind::SolarShareAbove, solar, solar_share_above, 1.),
+        ];
+
+        let mut alerts = Vec::new();
+        for (kind, latest, threshold, sign) in checks {
+            let (Some((time, value)), Some(threshold)) = (latest, threshold) else {
+                continue;
+            };
+            let crossed = sign * value > sign * threshold;
+            let was_crossed = self.crossed.contains(&kind);
+            if crossed && !was_crossed {
+                self.crossed.push(kind);
+                alerts.push(Alert {
+                    kind,
+                    time,
+                    value,
+                    threshold,
+                });
+            } else if !crossed && was_crossed {
+                self.crossed.retain(|k| *k != kind);
+            }
+        }
+        alerts
+    }
+
+    /// Checks the end of a price and a generation dataset.
+    pub fn check_files(&mut self, prices: &Compute, gen_csv: &Path) -> error::Result<Vec<Alert>> {
+        let price = prices.latest_prices(1)?.pop();
+        let gen = Compute::new(gen_csv).latest_gen()?;
+        Ok(self.check(price, gen))
+    }
+}
+
+/// Where alerts are sent.
+#[derive(Clone, Debug)]
+pub enum Hook {
+    /// Run through `sh -c` with the alert in `ENERGY_ALERT_*` variables.
+    Command(String),
+    /// A URL that's POSTed the alert as JSON.
+    Webhook(String),
+}
+
+impl Hook {
+    /// How long a webhook has to answer before the alert is given up on, so
+    /// an endpoint that hangs can't stall the monitor.
+    pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn fire(&self, alert: &Alert) -> error::Result<()> {
+        match self {
+            Hook::Command(command) => {
+                let kind = serde_json::to_value(alert.kind)?;
+                let status = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .env("ENERGY_ALERT_KIND", kind.as_str().unwrap_or_default())
+                    .env("ENERGY_ALERT_TIME", alert.time.to_string())
+                    .env("ENERGY_ALERT_VALUE", alert.value.to_string())
+                    .env("ENERGY_ALERT_THRESHOLD", alert.threshold.to_string())
+                    .env("ENERGY_ALERT_MESSAGE", alert.message())
+                    .status()?;
+                if !status.success() {
+                    bail!(Remote, "Alert command {command:?} failed with {status}");
+                }
+            }
+            Hook::Webhook(url) => {
+                let mut body = serde_json::to_value(alert)?;
+                body["message"] = alert.message().into();
+                Client::new(Duration::ZERO)
+                    .with_timeout(Self::WEBHOOK_TIMEOUT)
+                    .post_json(url, &body.to_string())?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,426 @@
+//! ### Nodal
+//! Prices at individual pricing nodes, from CAISO's OASIS nodal LMP
+//! extracts. An extract lists every node's every interval once per price
+//! component, so a quarter of one runs to gigabytes. Nothing here holds a
+//! whole file: parsing streams an extract into a narrow csv, summaries keep
+//! one running total per node, and a node index written beside the parsed
+//! csv lets one node's rows be read without scanning the others.
+
+use crate::compute::{Profile, ProfileAgg, SampleCheck};
+use crate::convert::{parse_timestamp, CsvRow, Schema, TIMESTAMP_FMT};
+use crate::error::{self, bail, err, EnergyAnalysisError, Locate};
+use crate::fetch::pacific_from_utc;
+use crate::frame::Frame;
+use crate::output::{CsvWriter, WriteOptions};
+use chrono::{DateTime, NaiveDateTime};
+use csv::{ByteRecord, Position};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Layouts of the OASIS nodal LMP extracts. Columns are the interval start
+/// in UTC, the node, the price component and its value.
+pub const NODAL_SCHEMAS: [Schema<4>; 2] = [
+    Schema {
+        name: "OASIS interval LMP (PRC_INTVL_LMP)",
+        columns: ["INTERVALSTARTTIME_GMT", "NODE", "LMP_TYPE", "VALUE"],
+    },
+    Schema {
+        name: "OASIS LMP (PRC_LMP)",
+        columns: ["INTERVALSTARTTIME_GMT", "NODE", "LMP_TYPE", "MW"],
+    },
+];
+
+/// The `LMP_TYPE` of a node's total price.
+pub const LMP: &str = "LMP";
+/// The `LMP_TYPE` of a node's congestion component. Positive where
+/// congestion raises the node's price.
+pub const CONGESTION: &str = "MCC";
+
+/// One price component of one node over one interval, as parse-nodal-csv
+/// writes it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodalCsvRow {
+    /// Pacific local interval start.
+    pub timestamp: String,
+    pub node: String,
+    pub lmp_type: String,
+    pub price: f64,
+}
+
+impl CsvRow for NodalCsvRow {
+    const TIMESTAMPS: &'static [&'static str] = &["timestamp"];
+}
+
+/// A stretch of consecutive rows of one node in a parsed nodal csv.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct Run {
+    start: u64,
+    end: u64,
+    rows: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexRow {
+    node: String,
+    start: u64,
+    end: u64,
+    rows: usize,
+}
+
+/// Where each node's rows sit in a parsed nodal csv. Extracts come grouped
+/// by node, so most nodes are one run per input file.
+#[derive(Debug, Default)]
+pub struct NodeIndex {
+    runs: BTreeMap<String, Vec<Run>>,
+}
+
+impl NodeIndex {
+    /// The index of a parsed nodal csv, beside it: data/nodal.csv is
+    /// indexed by data/nodal.nodes.csv.
+    pub fn path(nodal_csv: &Path) -> PathBuf {
+        nodal_csv.with_extension("nodes.csv")
+    }
+
+    /// Reads the index parse-nodal-csv wrote beside `nodal_csv`.
+    pub fn open(nodal_csv: &Path) -> error::Result<Self> {
+        let path = Self::path(nodal_csv);
+        if !path.exists() {
+            bail!(
+                NoData,
+                "{nodal_csv:?} has no node index at {path:?}. Parse it again with parse-nodal-csv"
+            );
+        }
+        let len = std::fs::metadata(nodal_csv)?.len();
+        let mut index = Self::default();
+        for row in csv::Reader::from_path(&path)?.into_deserialize() {
+            let IndexRow {
+                node,
+                start,
+                end,
+                rows,
+            } = row.map_err(|e: csv::Error| {
+                let line = e.position().map_or(0, Position::line);
+                EnergyAnalysisError::parse(&path, line, None, None, e)
+            })?;
+            if end > len {
+                bail!(
+                    Invalid,
+                    "{path:?} doesn't match {nodal_csv:?}. Parse it again with parse-nodal-csv"
+                );
+            }
+            index.push(&node, Run { start, end, rows });
+        }
+        Ok(index)
+    }
+
+    fn write(&self, path: &Path, options: WriteOptions) -> error::Result<()> {
+        let mut csv = options.csv(path)?;
+        for (node, runs) in &self.runs {
+            for run in runs {
+                csv.serialize(IndexRow {
+                    node: node.clone(),
+                    start: run.start,
+                    end: run.end,
+                    rows: run.rows,
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, node: &str, run: Run) {
+        match self.runs.get_mut(node) {
+            Some(runs) => runs.push(run),
+            None => {
+                self.runs.insert(node.to_string(), vec![run]);
+            }
+        }
+    }
+
+    /// Every indexed node, in name order.
+    pub fn nodes(&self) -> impl Iterator<Item = &str> {
+        self.runs.keys().map(String::as_str)
+    }
+
+    /// How many rows, over every price component, `node` has.
+    pub fn rows(&self, node: &str) -> usize {
+        self.runs
+            .get(node)
+            .map_or(0, |runs| runs.iter().map(|run| run.rows).sum())
+    }
+
+    /// Every row of `node`, reading only its runs of `nodal_csv`.
+    pub fn read(&self, nodal_csv: &Path, node: &str) -> error::Result<Vec<NodalCsvRow>> {
+        let Some(runs) = self.runs.get(node) else {
+            let known: Vec<&str> = self.nodes().take(5).collect();
+            bail!(
+                NoData,
+                "No node {node} in {nodal_csv:?}. It has {} nodes, such as {known:?}",
+                self.runs.len()
+            );
+        };
+        let mut file = File::open(nodal_csv)?;
+        let mut rows = Vec::with_capacity(self.rows(node));
+        for run in runs {
+            file.seek(SeekFrom::Start(run.start))?;
+            let reader = BufReader::new((&mut file).take(run.end - run.start));
+            let mut csv = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .from_reader(reader);
+            for row in csv.deserialize() {
+                let row: NodalCsvRow = row.map_err(|e| {
+                    let line = e.position().map_or(0, Position::line);
+                    EnergyAnalysisError::parse(nodal_csv, line, None, None, e)
+                })?;
+                rows.push(row);
+            }
+        }
+        Ok(rows)
+    }
+}
+
+/// Streams OASIS nodal extracts into one parsed csv and writes its node
+/// index beside it. Rows are passed straight through, so memory doesn't
+/// grow with the extract, only with how many runs the index has.
+pub fn convert_nodal_csv(
+    inputs: &[impl AsRef<Path>],
+    output: &Path,
+    options: WriteOptions,
+) -> error::Result<NodeIndex> {
+    let counted = Counted {
+        inner: BufWriter::new(options.create(output)?),
+        written: 0,
+    };
+    let mut csv = CsvWriter::with_options(counted, options);
+    csv.write_record(["timestamp", "node", "lmp_type", "price"])?;
+    // The bytes written so far, which is where the next row starts.
+    let offset = |csv: &mut CsvWriter<Counted>| -> error::Result<u64> {
+        csv.flush()?;
+        Ok(csv.get_ref().written)
+    };
+    let mut start = offset(&mut csv)?;
+
+    let mut index = NodeIndex::default();
+    let (mut node, mut rows) = (String::new(), 0);
+    let mut timestamp = String::new();
+    let mut record = ByteRecord::new();
+    for input in inputs {
+        let input = input.as_ref();
+        let mut reader = csv::Reader::from_path(input)?;
+        let header = reader.headers()?.clone();
+        let (schema, columns) = Schema::detect(&NODAL_SCHEMAS, &header, input)?;
+        println!("{input:?} matches {}", schema.name);
+        while reader.read_byte_record(&mut record)? {
+            let line = record.position().map_or(0, Position::line);
+            let field = |col: usize| {
+                let bytes = record.get(columns[col]).unwrap_or_default();
+                std::str::from_utf8(bytes).locate(
+                    input,
+                    line,
+                    schema.columns[col],
+                    &String::from_utf8_lossy(bytes),
+                )
+            };
+            let utc = field(0)?;
+            let start_utc = DateTime::parse_from_rfc3339(utc)
+                .locate(input, line, schema.columns[0], utc)?
+                .naive_utc();
+            let price = field(3)?;
+            let price: f64 = price
+                .trim()
+                .parse()
+                .locate(input, line, schema.columns[3], price)?;
+            timestamp.clear();
+            write!(
+                timestamp,
+                "{}",
+                pacific_from_utc(start_utc).format(TIMESTAMP_FMT)
+            )?;
+
+            let row_node = field(1)?;
+            if row_node != node {
+                let end = offset(&mut csv)?;
+                if rows > 0 {
+                    index.push(&node, Run { start, end, rows });
+                }
+                (start, rows) = (end, 0);
+                node.clear();
+                node.push_str(row_node);
+            }
+            csv.write_record([
+                timestamp.as_str(),
+                row_node,
+                field(2)?,
+                &options.float(price),
+            ])?;
+            rows += 1;
+        }
+        // A file's last run ends with it, even if the next starts on the
+        // same node.
+        let end = offset(&mut csv)?;
+        if rows > 0 {
+            index.push(&node, Run { start, end, rows });
+        }
+        (start, rows) = (end, 0);
+        node.clear();
+    }
+    csv.into_inner()?.inner.flush()?;
+    index.write(&NodeIndex::path(output), options)?;
+    Ok(index)
+}
+
+/// Counts the bytes passed to a file. Flushing only empties the csv
+/// writer's buffer into the `BufWriter`, so counting every row's offset
+/// doesn't cost a write to disk.
+struct Counted {
+    inner: BufWriter<File>,
+    written: u64,
+}
+
+impl Write for Counted {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// How congested one node was over a nodal csv, in $/MWh.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeCongestion {
+    pub node: String,
+    pub avg_congestion: f64,
+    pub max_congestion: f64,
+    pub avg_lmp: f64,
+    /// Intervals with a congestion component.
+    pub intervals: usize,
+}
+
+#[derive(Default)]
+struct Totals {
+    congestion: f64,
+    max_congestion: f64,
+    congested: usize,
+    lmp: f64,
+    priced: usize,
+}
+
+/// The `count` nodes with the highest average congestion component, most
+/// congested first. One pass over the file, holding a running total per
+/// node rather than any rows.
+pub fn top_congested(nodal_csv: &Path, count: usize) -> error::Result<Vec<NodeCongestion>> {
+    if count == 0 {
+        bail!(Invalid, "Ask for at least one node");
+    }
+    let mut reader = csv::Reader::from_path(nodal_csv)?;
+    let mut totals: HashMap<String, Totals> = HashMap::new();
+    let mut record = ByteRecord::new();
+    while reader.read_byte_record(&mut record)? {
+        let line = record.position().map_or(0, Position::line);
+        let field = |idx: usize| {
+            let bytes = record.get(idx).unwrap_or_default();
+            std::str::from_utf8(bytes).locate(
+                nodal_csv,
+                line,
+                ["timestamp", "node", "lmp_type", "price"][idx],
+                &String::from_utf8_lossy(bytes),
+            )
+        };
+        let lmp_type = field(2)?;
+        if lmp_type != LMP && lmp_type != CONGESTION {
+            continue;
+        }
+        let (node, price) = (field(1)?, field(3)?);
+        let price: f64 = price.parse().locate(nodal_csv, line, "price", price)?;
+        let node_totals = match totals.get_mut(node) {
+            Some(node_totals) => node_totals,
+            None => totals.entry(node.to_string()).or_insert(Totals {
+                max_congestion: f64::MIN,
+                ..Totals::default()
+            }),
+        };
+        if lmp_type == LMP {
+            node_totals.lmp += price;
+            node_totals.priced += 1;
+        } else {
+            node_totals.congestion += price;
+            node_totals.max_congestion = node_totals.max_congestion.max(price);
+            node_totals.congested += 1;
+        }
+    }
+
+    let mut nodes: Vec<NodeCongestion> = totals
+        .into_iter()
+        .filter(|(_, totals)| totals.congested > 0)
+        .map(|(node, totals)| NodeCongestion {
+            node,
+            avg_congestion: totals.congestion / totals.congested as f64,
+            max_congestion: totals.max_congestion,
+            avg_lmp: totals.lmp / totals.priced.max(1) as f64,
+            intervals: totals.congested,
+        })
+        .collect();
+    if nodes.is_empty() {
+        bail!(NoData, "No congestion components in {nodal_csv:?}");
+    }
+    nodes.sort_by(|a, b| {
+        b.avg_congestion
+            .total_cmp(&a.avg_congestion)
+            .then_with(|| a.node.cmp(&b.node))
+    });
+    nodes.truncate(count);
+    Ok(nodes)
+}
+
+/// One node's LMP and congestion component averaged in each slot of a
+/// profile.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeProfile {
+    pub node: String,
+    pub prices: Vec<f64>,
+    pub congestion: Vec<f64>,
+    /// How many intervals each slot averages.
+    pub samples: Vec<usize>,
+}
+
+/// `node`'s average LMP and congestion over `profile`, read through the
+/// node index.
+pub fn node_profile(
+    nodal_csv: &Path,
+    index: &NodeIndex,
+    node: &str,
+    profile: Profile,
+) -> error::Result<NodeProfile> {
+    let mut intervals: BTreeMap<NaiveDateTime, [f64; 2]> = BTreeMap::new();
+    for row in index.read(nodal_csv, node)? {
+        let column = match row.lmp_type.as_str() {
+            LMP => 0,
+            CONGESTION => 1,
+            _ => continue,
+        };
+        let time = parse_timestamp(&row.timestamp)?;
+        intervals.entry(time).or_insert([f64::NAN; 2])[column] = row.price;
+    }
+    if intervals.is_empty() {
+        return Err(err!(NoData, "No prices for node {node} in {nodal_csv:?}"));
+    }
+    let (times, values): (Vec<_>, Vec<_>) = intervals.into_iter().unzip();
+    let frame = Frame::new(times)
+        .with_column("Price", values.iter().map(|[lmp, _]| *lmp).collect())?
+        .with_column("Congestion", values.iter().map(|[_, mcc]| *mcc).collect())?;
+    let (slots, samples) = frame.profile(profile, SampleCheck::default(), ProfileAgg::Mean)?;
+    Ok(NodeProfile {
+        node: node.to_string(),
+        prices: slots.iter().map(|slot| slot[0]).collect(),
+        congestion: slots.iter().map(|slot| slot[1]).collect(),
+        samples,
+    })
+}
@@ -0,0 +1,603 @@
+//! ### Output
+//! The one place result files are opened, so every writer follows the same
+//! rules about replacing files that already exist.
+//!
+//! Those rules are a `WriteOptions` handed to each writer. Library callers
+//! overwrite by default. The CLI refuses to unless it's given
+//! `--overwrite`. Csvs opened by `append_csv` are only added to, so
+//! they're never refused. The CLI also has every file written noted, so it
+//! can record where each came from (see `provenance`).
+//!
+//! Every number in a result csv is written by `WriteOptions::float`, to a
+//! fixed number of decimals with the trailing zeros dropped. The same value
+//! always reads the same, and parsing and rewriting a csv doesn't add
+//! digits of float noise like `23.450000000000003`.
+
+use crate::error::{self, EnergyAnalysisError};
+#[cfg(feature = "native")]
+use chrono::Local;
+use serde::ser::{self, Serialize, Serializer};
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Decimals kept unless `WriteOptions::precision` says otherwise. Enough
+/// for every price and quantity in the raw files, which have at most five.
+pub const DEFAULT_PRECISION: usize = 6;
+
+static WRITTEN: Mutex<Option<Vec<PathBuf>>> = Mutex::new(None);
+
+static APPENDED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// How result files are written: whether ones already there may be
+/// replaced, and how many decimals their numbers keep. Every writer is
+/// handed one, so two callers in one process can write differently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteOptions {
+    pub overwrite: bool,
+    pub precision: usize,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            overwrite: true,
+            precision: DEFAULT_PRECISION,
+        }
+    }
+}
+
+impl WriteOptions {
+    /// Writes `value` rounded to `precision` decimals, without trailing
+    /// zeros or a negative zero. NaN and infinities are written as Rust
+    /// does.
+    pub fn float(self, value: f64) -> String {
+        let mut text = String::new();
+        self.push_float(&mut text, value);
+        text
+    }
+
+    /// Appends `float(value)` to `text`, for writers that build their
+    /// records themselves rather than allocating a string per number.
+    pub fn push_float(self, text: &mut String, value: f64) {
+        let precision = self.precision;
+        // Scaled to an integer count of the last decimal kept, a value below
+        // 1e12 is within 1e-4 of its exact product, so unless that's near a
+        // tie it rounds the way formatting the exact value would.
+        if precision <= 6 {
+            let scale = 10u64.pow(precision as u32);
+            let scaled = value * scale as f64;
+            if scaled.abs() < 1e12 && (scaled.abs().fract() - 0.5).abs() > 1e-3 {
+                let units = scaled.round().abs() as u64;
+                if units == 0 {
+                    text.push('0');
+                    return;
+                }
+                if scaled < 0. {
+                    text.push('-');
+                }
+                let _ = write!(text, "{}", units / scale);
+                let mut decimals = units % scale;
+                if decimals > 0 {
+                    let mut width = precision;
+                    while decimals.is_multiple_of(10) {
+                        decimals /= 10;
+                        width -= 1;
+                    }
+                    let _ = write!(text, ".{decimals:0width$}");
+                }
+                return;
+            }
+        }
+        // The shortest text that reads back as `value` is already rounded
+        // when it has no more decimals than we keep. Below 1e9 a float is
+        // within 1e-7 of that text, so rounding to at most six decimals
+        // lands on it.
+        if precision <= 6 && value.abs() < 1e9 {
+            let mut buffer = ryu::Buffer::new();
+            let shortest = buffer.format(value);
+            if let Some((whole, decimals)) = shortest.split_once('.') {
+                let decimals = decimals.trim_end_matches('0');
+                if !shortest.contains('e') && decimals.len() <= precision {
+                    text.push_str(if whole == "-0" && decimals.is_empty() {
+                        "0"
+                    } else {
+                        whole
+                    });
+                    if !decimals.is_empty() {
+                        text.push('.');
+                        text.push_str(decimals);
+                    }
+                    return;
+                }
+            }
+        }
+
+        let start = text.len();
+        if !value.is_finite() {
+            let _ = write!(text, "{value}");
+            return;
+        }
+        let _ = write!(text, "{value:.precision$}");
+        if text[start..].contains('.') {
+            let len = text.trim_end_matches('0').trim_end_matches('.').len();
+            text.truncate(len);
+        }
+        if &text[start..] == "-0" {
+            text.remove(start);
+        }
+    }
+
+    /// What `float` writes for the number `text` reads as, when that's
+    /// `text` itself less any trailing decimal zeros, so a converter can
+    /// copy a raw field rather than format it again. Plain decimals of at
+    /// most fifteen significant places, nine of them whole and no more
+    /// decimals than the precision, qualify: each reads as the float
+    /// nearest it, which writes back as the same text.
+    pub fn float_text(self, text: &[u8]) -> Option<&[u8]> {
+        let digits = text.strip_prefix(b"-").unwrap_or(text);
+        let (whole, decimals) = match digits.iter().position(|&byte| byte == b'.') {
+            Some(dot) => (&digits[..dot], &digits[dot + 1..]),
+            None => (digits, &[][..]),
+        };
+        let kept = decimals.len() - decimals.iter().rev().take_while(|&&b| b == b'0').count();
+        let plain = |part: &[u8]| part.iter().all(u8::is_ascii_digit);
+        let canonical = !whole.is_empty()
+            && whole.len() <= 9
+            && (whole.len() == 1 || whole[0] != b'0')
+            && plain(whole)
+            && plain(decimals)
+            && kept <= self.precision
+            && whole.len() + kept <= 15;
+        let len = text.len() - digits.len() + whole.len() + if kept > 0 { kept + 1 } else { 0 };
+        let text = &text[..len];
+        (canonical && text != b"-0").then_some(text)
+    }
+
+    #[cfg(any(feature = "native", feature = "graph"))]
+    /// Fails if `path` exists and overwriting isn't allowed. For writers
+    /// that open the file themselves, like plotters.
+    pub fn check(self, path: &Path) -> error::Result<()> {
+        if !self.overwrite && path.exists() {
+            return Err(EnergyAnalysisError::OutputExists(path.to_path_buf()));
+        }
+        note_written(path);
+        Ok(())
+    }
+
+    /// Opens `path` for writing under the overwrite rule.
+    pub fn create(self, path: &Path) -> error::Result<File> {
+        note_written(path);
+        if self.overwrite {
+            return Ok(File::create(path)?);
+        }
+        match OpenOptions::new().write(true).create_new(true).open(path) {
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                Err(EnergyAnalysisError::OutputExists(path.to_path_buf()))
+            }
+            file => Ok(file?),
+        }
+    }
+
+    /// Opens `sidecar`, a file describing `output` such as its provenance.
+    /// When `output` was appended to, the sidecar has to describe all of
+    /// it again, so it's replaced whatever the overwrite rule says.
+    pub fn create_beside(self, output: &Path, sidecar: &Path) -> error::Result<File> {
+        let appended = APPENDED
+            .lock()
+            .is_ok_and(|appended| appended.iter().any(|path| path == output));
+        match appended {
+            true => Ok(File::create(sidecar)?),
+            false => self.create(sidecar),
+        }
+    }
+
+    /// Opens `path` as a csv whose numbers are all written by `float`.
+    pub fn csv(self, path: &Path) -> error::Result<CsvWriter<File>> {
+        Ok(CsvWriter::with_options(self.create(path)?, self))
+    }
+
+    #[cfg(feature = "native")]
+    /// Opens `path` as a csv to add rows to the end of, creating it if it
+    /// doesn't exist. Nothing already there is replaced, so this isn't held
+    /// to the overwrite rule. A header is only written when the file is new
+    /// or empty.
+    pub fn append_csv(self, path: &Path) -> error::Result<CsvWriter<File>> {
+        note_written(path);
+        if let Ok(mut appended) = APPENDED.lock() {
+            appended.push(path.to_path_buf());
+        }
+        let file = OpenOptions::new().append(true).create(true).open(path)?;
+        let fresh = file.metadata()?.len() == 0;
+        Ok(CsvWriter {
+            inner: csv::WriterBuilder::new()
+                .has_headers(fresh)
+                .from_writer(file),
+            options: self,
+        })
+    }
+}
+
+#[cfg(feature = "native")]
+/// Notes every file written from now on, for `take_written`.
+pub fn track_written() {
+    if let Ok(mut guard) = WRITTEN.lock() {
+        guard.get_or_insert_with(Vec::new);
+    }
+}
+
+#[cfg(feature = "native")]
+/// The files written since tracking started or this was last called, in
+/// the order they were first written. Empty when nothing is tracking.
+pub fn take_written() -> Vec<PathBuf> {
+    WRITTEN
+        .lock()
+        .ok()
+        .and_then(|mut guard| guard.as_mut().map(std::mem::take))
+        .unwrap_or_default()
+}
+
+fn note_written(path: &Path) {
+    if let Ok(mut guard) = WRITTEN.lock() {
+        if let Some(written) = guard.as_mut() {
+            if !written.iter().any(|seen| seen == path) {
+                written.push(path.to_path_buf());
+            }
+        }
+    }
+}
+
+/// A csv writer that writes every float it serializes with its options'
+/// `float`. Records written whole are taken as they are, so their numbers
+/// should be formatted with `float` too.
+#[derive(Debug)]
+pub struct CsvWriter<W: Write> {
+    inner: csv::Writer<W>,
+    options: WriteOptions,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self::with_options(out, WriteOptions::default())
+    }
+
+    pub fn with_options(out: W, options: WriteOptions) -> Self {
+        Self {
+            inner: csv::Writer::from_writer(out),
+            options,
+        }
+    }
+
+    /// The options numbers are written with.
+    pub fn options(&self) -> WriteOptions {
+        self.options
+    }
+
+    pub fn write_record<I, T>(&mut self, record: I) -> error::Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        Ok(self.inner.write_record(record)?)
+    }
+
+    /// Writes a record built field by field, skipping the per-field
+    /// conversion `write_record` does.
+    pub fn write_byte_record(&mut self, record: &csv::ByteRecord) -> error::Result<()> {
+        Ok(self.inner.write_byte_record(record)?)
+    }
+
+    pub fn serialize<S: Serialize>(&mut self, row: S) -> error::Result<()> {
+        let options = self.options;
+        Ok(self.inner.serialize(Rounded(&row, options))?)
+    }
+
+    pub fn flush(&mut self) -> error::Result<()> {
+        Ok(self.inner.flush()?)
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.get_ref()
+    }
+
+    /// Flushes the csv and hands back what it was writing to.
+    pub fn into_inner(self) -> error::Result<W> {
+        Ok(self.inner.into_inner().map_err(|e| e.into_error())?)
+    }
+}
+
+/// Serializes the value it wraps with every float replaced by `float`'s
+/// text, at any depth.
+struct Rounded<'a, T: ?Sized>(&'a T, WriteOptions);
+
+impl<T: Serialize + ?Sized> Serialize for Rounded<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(RoundingSerializer(serializer, self.1))
+    }
+}
+
+struct RoundingSerializer<S>(S, WriteOptions);
+
+/// Forwards serializer calls that carry no nested values unchanged.
+macro_rules! forward {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(fn $method(self, $($arg: $ty),*) -> Result<S::Ok, S::Error> {
+            self.0.$method($($arg),*)
+        })*
+    };
+}
+
+impl<S: Serializer> Serializer for RoundingSerializer<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Rounding<S::SerializeSeq>;
+    type SerializeTuple = Rounding<S::SerializeTuple>;
+    type SerializeTupleStruct = Rounding<S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Rounding<S::SerializeTupleVariant>;
+    type SerializeMap = Rounding<S::SerializeMap>;
+    type SerializeStruct = Rounding<S::SerializeStruct>;
+    type SerializeStructVariant = Rounding<S::SerializeStructVariant>;
+
+    forward! {
+        serialize_bool(v: bool);
+        serialize_i8(v: i8);
+        serialize_i16(v: i16);
+        serialize_i32(v: i32);
+        serialize_i64(v: i64);
+        serialize_u8(v: u8);
+        serialize_u16(v: u16);
+        serialize_u32(v: u32);
+        serialize_u64(v: u64);
+        serialize_char(v: char);
+        serialize_str(v: &str);
+        serialize_bytes(v: &[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(name: &'static str);
+        serialize_unit_variant(name: &'static str, index: u32, variant: &'static str);
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<S::Ok, S::Error> {
+        self.0.serialize_str(&self.1.float(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<S::Ok, S::Error> {
+        self.0.serialize_str(&self.1.float(v))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.0.serialize_some(&Rounded(value, self.1))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.0
+            .serialize_newtype_struct(name, &Rounded(value, self.1))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.0
+            .serialize_newtype_variant(name, index, variant, &Rounded(value, self.1))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        self.0
+            .serialize_seq(len)
+            .map(|compound| Rounding(compound, self.1))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        self.0
+            .serialize_tuple(len)
+            .map(|compound| Rounding(compound, self.1))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        self.0
+            .serialize_tuple_struct(name, len)
+            .map(|compound| Rounding(compound, self.1))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        self.0
+            .serialize_tuple_variant(name, index, variant, len)
+            .map(|compound| Rounding(compound, self.1))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        self.0
+            .serialize_map(len)
+            .map(|compound| Rounding(compound, self.1))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        self.0
+            .serialize_struct(name, len)
+            .map(|compound| Rounding(compound, self.1))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        self.0
+            .serialize_struct_variant(name, index, variant, len)
+            .map(|compound| Rounding(compound, self.1))
+    }
+}
+
+/// A compound value being serialized, whose elements are each `Rounded`.
+struct Rounding<C>(C, WriteOptions);
+
+impl<C: ser::SerializeSeq> ser::SerializeSeq for Rounding<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.0.serialize_element(&Rounded(value, self.1))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.0.end()
+    }
+}
+
+impl<C: ser::SerializeTuple> ser::SerializeTuple for Rounding<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.0.serialize_element(&Rounded(value, self.1))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.0.end()
+    }
+}
+
+impl<C: ser::SerializeTupleStruct> ser::SerializeTupleStruct for Rounding<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.0.serialize_field(&Rounded(value, self.1))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.0.end()
+    }
+}
+
+impl<C: ser::SerializeTupleVariant> ser::SerializeTupleVariant for Rounding<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.0.serialize_field(&Rounded(value, self.1))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.0.end()
+    }
+}
+
+impl<C: ser::SerializeMap> ser::SerializeMap for Rounding<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), C::Error> {
+        self.0.serialize_key(&Rounded(key, self.1))
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.0.serialize_value(&Rounded(value, self.1))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.0.end()
+    }
+}
+
+impl<C: ser::SerializeStruct> ser::SerializeStruct for Rounding<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        self.0.serialize_field(key, &Rounded(value, self.1))
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.0.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.0.end()
+    }
+}
+
+impl<C: ser::SerializeStructVariant> ser::SerializeStructVariant for Rounding<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        self.0.serialize_field(key, &Rounded(value, self.1))
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.0.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.0.end()
+    }
+}
+
+#[cfg(feature = "native")]
+/// Places result files in one directory with the time of the run added to
+/// each name, so repeated runs sit side by side instead of replacing each
+/// other. Every file from one run shares a timestamp.
+#[derive(Debug)]
+pub struct StampedDir {
+    dir: PathBuf,
+    stamp: String,
+}
+
+#[cfg(feature = "native")]
+impl StampedDir {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            stamp: Local::now().format("%Y%m%dT%H%M%S").to_string(),
+        }
+    }
+
+    /// `results/prices.png` becomes `<dir>/prices-<stamp>.png`.
+    pub fn place(&self, path: &Path) -> PathBuf {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match path.extension() {
+            Some(ext) => format!("{stem}-{}.{}", self.stamp, ext.to_string_lossy()),
+            None => format!("{stem}-{}", self.stamp),
+        };
+        self.dir.join(name)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
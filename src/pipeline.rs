@@ -0,0 +1,89 @@
+//! ### Pipeline
+//! A TOML description of a whole analysis: a table of named artifacts and
+//! the ordered subcommand invocations that produce and consume them.
+//!
+//! ```toml
+//! [artifacts]
+//! prices = "data/prices.csv"
+//! price_avg = "results/prices_avg.csv"
+//!
+//! [[steps]]
+//! run = "analyze"
+//! args = ["price-minutes", "{prices}", "--out-csv", "{price_avg}"]
+//! ```
+//!
+//! Any argument may reference an artifact as `{name}`. Paths are relative
+//! to the working directory, as they are on the command line.
+
+use crate::error::{self, bail, err};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Pipeline {
+    #[serde(default)]
+    pub artifacts: BTreeMap<String, PathBuf>,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    /// The subcommand to run, e.g. "analyze"
+    pub run: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl Pipeline {
+    pub fn load(path: &Path) -> error::Result<Self> {
+        let pipeline: Self = toml::from_str(&fs::read_to_string(path)?)?;
+        if pipeline.steps.is_empty() {
+            bail!(Invalid, "{path:?} has no steps");
+        }
+        Ok(pipeline)
+    }
+
+    /// Each step as a command line (subcommand first) with its artifact
+    /// references resolved. Fails on the first unknown artifact so that
+    /// nothing runs from a pipeline with a typo in it.
+    pub fn command_lines(&self) -> error::Result<Vec<Vec<String>>> {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(idx, step)| {
+                let mut line = vec![step.run.clone()];
+                for arg in &step.args {
+                    line.push(
+                        self.resolve(arg)
+                            .map_err(|e| err!(Invalid, "Step {} ({}): {e}", idx + 1, step.run))?,
+                    );
+                }
+                Ok(line)
+            })
+            .collect()
+    }
+
+    fn resolve(&self, arg: &str) -> error::Result<String> {
+        let mut out = String::with_capacity(arg.len());
+        let mut rest = arg;
+        while let Some(open) = rest.find('{') {
+            out.push_str(&rest[..open]);
+            let close = rest[open..]
+                .find('}')
+                .ok_or_else(|| err!(Invalid, "Unclosed artifact reference in {arg:?}"))?;
+            let name = &rest[open + 1..open + close];
+            let path = self
+                .artifacts
+                .get(name)
+                .ok_or_else(|| err!(Invalid, "Unknown artifact {name:?}"))?;
+            out.push_str(&path.to_string_lossy());
+            rest = &rest[open + close + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
@@ -0,0 +1,80 @@
+//! ### Prelude
+//! The types a program built on this crate reaches for first, for a single
+//! glob import. Parsing raw CAISO files, averaging them and pricing a
+//! scenario looks like:
+//!
+//! ```
+//! use energy_analysis::convert;
+//! use energy_analysis::prelude::*;
+//! use energy_analysis::testdata::SyntheticData;
+//!
+//! # fn main() -> Result<(), EnergyAnalysisError> {
+//! let dir = std::env::temp_dir().join(format!("energy_analysis_prelude_{}", std::process::id()));
+//! std::fs::create_dir_all(&dir)?;
+//! let data = SyntheticData {
+//!     days: 3,
+//!     ..SyntheticData::default()
+//! };
+//! data.write_price_csv(&dir.join("price_raw.csv"))?;
+//! data.write_gen_csv(&dir.join("gen_raw.csv"))?;
+//!
+//! // Raw files are parsed once into the csvs every analysis reads.
+//! let (price_csv, gen_csv) = (dir.join("prices.csv"), dir.join("gen.csv"));
+//! let write = WriteOptions::default();
+//! convert::convert_energy_price_csv(
+//!     &[dir.join("price_raw.csv")],
+//!     &price_csv,
+//!     CsvFormat::default(),
+//!     OnError::Abort,
+//!     write,
+//! )?;
+//! convert::convert_energy_gen_csv(
+//!     &[dir.join("gen_raw.csv")],
+//!     &gen_csv,
+//!     CsvFormat::default(),
+//!     TotalCheck::Report,
+//!     OnError::Abort,
+//!     write,
+//! )?;
+//!
+//! // Held in memory, the csvs are read once however many analyses follow.
+//! let prices = Dataset::load(&price_csv)?;
+//! let gen = Dataset::load(&gen_csv)?;
+//! let profile = Compute::from_dataset(&prices).average_price_5min()?;
+//! assert_eq!(profile.len(), Compute::slots_per_day());
+//!
+//! // Doubling solar pushes down the price it sells at.
+//! let solar = EnergyGenCsvRow::source_idx("Solar")?;
+//! let joined = Compute::from_dataset(&prices).with_dataset(&gen);
+//! let (values, _) = joined.average_value_5min(&gen)?;
+//! let doubled = SourceScaling {
+//!     source_idx: solar,
+//!     factor: 2.,
+//!     elasticity: 1.,
+//!     window: None,
+//! };
+//! let (scaled, _) = joined.average_value_scaled(&gen, &doubled)?;
+//! assert!(scaled[solar] < values[solar]);
+//! # std::fs::remove_dir_all(&dir)?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub use crate::chart::{
+    LegendPosition, Marker, PowerUnit, ReferenceLine, SourceOrder, Theme, YScale,
+};
+pub use crate::compute::{
+    Agg, Compute, DayPolicy, FewSamples, Period, PriceNode, Profile, ProfileAgg, SampleCheck,
+};
+pub use crate::config::{Labels, Settings, WriteOptions};
+pub use crate::convert::{
+    CsvFormat, DemandCsvRow, EnergyGenCsvRow, EnergyPriceCsvRow, OnError, RawKind, TotalCheck,
+};
+pub use crate::dataset::Dataset;
+pub use crate::error::EnergyAnalysisError;
+#[cfg(feature = "graph")]
+pub use crate::graph::{Bitmap, ChartOptions, Graphing, ImageFormat};
+pub use crate::join::{JoinMode, TimeAlignedJoin};
+pub use crate::scenario::{
+    BatteryScenario, ChargingObjective, ChargingScenario, Dispatch, ScenarioWindow, SourceScaling,
+};
@@ -0,0 +1,143 @@
+//! ### Provenance
+//! Records where each result came from in a `.meta.json` beside it: the
+//! inputs and a hash of their content, the command and options that made
+//! it, the crate version, and when it ran. A figure in the paper can then
+//! be traced back to exactly the data and settings behind it, and remade.
+//!
+//! Commands can add facts that only come out of the run, like the bounds
+//! prices were clipped to, with `note`. Charts can carry a one-line summary
+//! of the same record as a footer.
+
+use crate::cache;
+use crate::compute::{Compute, Period};
+use crate::config::Rto;
+use crate::error;
+use crate::output::WriteOptions;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+static NOTES: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// Records `value` under `key` in the provenance of this run's results.
+pub fn note(key: &str, value: impl Into<String>) {
+    if let Ok(mut notes) = NOTES.lock() {
+        notes.insert(key.to_string(), value.into());
+    }
+}
+
+/// Takes the notes made since the last call.
+pub fn take_notes() -> BTreeMap<String, String> {
+    NOTES
+        .lock()
+        .map(|mut notes| std::mem::take(&mut *notes))
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Provenance {
+    /// The command line as it was typed.
+    pub command: Vec<String>,
+    /// Every option the command ran with, defaults and config included.
+    pub options: String,
+    pub inputs: Vec<InputFile>,
+    pub version: String,
+    pub generated_at: DateTime<Local>,
+    pub notes: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct InputFile {
+    pub path: PathBuf,
+    /// The SHA-256 of the content in hex, as `sha256sum` prints it. For a
+    /// manifest, it's taken over every month it lists and then the
+    /// manifest, as `cat <months...> <manifest> | sha256sum` gives.
+    pub hash: String,
+    /// The first and last dates of a parsed dataset. None for other
+    /// inputs, like a scenario or weather csv.
+    pub dates: Option<(NaiveDate, NaiveDate)>,
+}
+
+impl Provenance {
+    /// The provenance of a run over `inputs`, hashing those that exist and
+    /// taking the notes made so far.
+    pub fn new(command: Vec<String>, options: String, inputs: &[PathBuf]) -> error::Result<Self> {
+        let mut files = Vec::new();
+        for path in inputs.iter().filter(|path| path.is_file()) {
+            let mut hasher = Sha256::new();
+            cache::hash_file(path, &mut hasher)?;
+            files.push(InputFile {
+                path: path.clone(),
+                hash: format!("{:x}", hasher.finalize()),
+                dates: Compute::new(path).date_span().ok().flatten(),
+            });
+        }
+        Ok(Self {
+            command,
+            options,
+            inputs: files,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: Local::now(),
+            notes: take_notes(),
+        })
+    }
+
+    /// A line naming the quarters the inputs span, the version, and the
+    /// date, e.g. "CAISO 2023Q4–2024Q3, generated by energy-analysis v0.1.0
+    /// on 2024-11-02".
+    pub fn footer(&self, rto: Rto) -> String {
+        let first = self
+            .inputs
+            .iter()
+            .filter_map(|input| input.dates)
+            .map(|d| d.0)
+            .min();
+        let last = self
+            .inputs
+            .iter()
+            .filter_map(|input| input.dates)
+            .map(|d| d.1)
+            .max();
+        let span = match first.zip(last) {
+            Some((first, last)) => {
+                let (first, last) = (Period::Quarter.label(first), Period::Quarter.label(last));
+                match first == last {
+                    true => format!("{} {first}", rto.name()),
+                    false => format!("{} {first}–{last}", rto.name()),
+                }
+            }
+            None => format!("{} data", rto.name()),
+        };
+        format!(
+            "{span}, generated by energy-analysis v{} on {}",
+            self.version,
+            self.generated_at.format("%Y-%m-%d")
+        )
+    }
+
+    /// Where the provenance of `output` is kept: `results/prices.png` has
+    /// `results/prices.png.meta.json`.
+    pub fn sidecar(output: &Path) -> PathBuf {
+        let mut name = output.as_os_str().to_owned();
+        name.push(".meta.json");
+        PathBuf::from(name)
+    }
+
+    /// Writes this as the provenance of `output`, returning where.
+    pub fn write(&self, output: &Path, options: WriteOptions) -> error::Result<PathBuf> {
+        let sidecar = Self::sidecar(output);
+        let json = serde_json::to_string_pretty(self)?;
+        writeln!(options.create_beside(output, &sidecar)?, "{json}")?;
+        Ok(sidecar)
+    }
+
+    /// The provenance recorded for `output`.
+    pub fn read(output: &Path) -> error::Result<Self> {
+        let json = std::fs::read_to_string(Self::sidecar(output))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
@@ -0,0 +1,219 @@
+//! ### Report
+//! Runs the standard analyses and assembles their charts and summary
+//! tables into a single document that can be shared as-is.
+
+use crate::compute::{Compute, GroupSummary, Period, Profile};
+use crate::convert::EnergyGenCsvRow;
+use crate::dataset::Dataset;
+use crate::error;
+use crate::graph::{Graphing, ImageFormat};
+use crate::manifest::Manifest;
+use crate::output::WriteOptions;
+use base64::Engine;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// Charts are written next to the report and linked by relative path.
+    Markdown,
+    /// Charts are embedded inline and never written, so the file stands
+    /// alone.
+    Html,
+}
+
+pub struct Report {
+    format: ReportFormat,
+    body: String,
+    options: WriteOptions,
+}
+
+impl Report {
+    /// Renders charts and tables for the given datasets into `out_dir` and
+    /// returns the path of the finished report.
+    pub fn generate(
+        prices: &Compute,
+        gen_csv: &Path,
+        out_dir: &Path,
+        format: ReportFormat,
+        options: WriteOptions,
+    ) -> error::Result<PathBuf> {
+        fs::create_dir_all(out_dir)?;
+        // Each dataset feeds several sections, so it's parsed once up front.
+        let price_csv = prices.path();
+        let held = [price_csv, gen_csv]
+            .into_iter()
+            .filter(|path| !Manifest::is_manifest(path))
+            .map(Dataset::load)
+            .collect::<error::Result<Vec<_>>>()?;
+        let prices = held.iter().fold(prices.clone(), Compute::with_dataset);
+        let mut report = Report {
+            format,
+            body: String::new(),
+            options,
+        };
+        report.heading(1, "CAISO energy price and generation report")?;
+        report.paragraph(&format!(
+            "Generated from `{}` and `{}`.",
+            price_csv.display(),
+            gen_csv.display()
+        ))?;
+
+        report.heading(2, "Daily profiles")?;
+        let graphing = Graphing::in_memory();
+        let averages = prices.average_price_5min()?;
+        let png = graphing.daily_price_to_buffer(&averages, Profile::Day, ImageFormat::Png)?;
+        report.image(out_dir, "prices.png", &png, "Daily average price")?;
+
+        let gen = held
+            .iter()
+            .fold(Compute::new(gen_csv), Compute::with_dataset)
+            .average_gen_5min()?;
+        let png = graphing.daily_gen_to_buffer(
+            &gen,
+            Profile::Day,
+            "Daily average generation by source",
+            None,
+            ImageFormat::Png,
+        )?;
+        report.image(
+            out_dir,
+            "gen.png",
+            &png,
+            "Daily average generation by source",
+        )?;
+
+        let (values, _qtys) = prices.average_value_5min(gen_csv)?;
+        let png =
+            graphing.avg_value_to_buffer(&values, "Average value by source", ImageFormat::Png)?;
+        report.image(out_dir, "values.png", &png, "Average value by source")?;
+
+        for (period, name) in [(Period::Month, "Monthly"), (Period::Quarter, "Quarterly")] {
+            let summaries = prices.period_summaries(gen_csv, period)?;
+            report.heading(2, &format!("{name} summary"))?;
+            report.summary_tables(&summaries)?;
+        }
+
+        let path = out_dir.join(match format {
+            ReportFormat::Markdown => "report.md",
+            ReportFormat::Html => "report.html",
+        });
+        std::io::Write::write_all(&mut options.create(&path)?, report.finish().as_bytes())?;
+        Ok(path)
+    }
+
+    fn summary_tables(&mut self, summaries: &[GroupSummary]) -> error::Result<()> {
+        let mut header = vec!["Period".to_string()];
+        header.extend(
+            EnergyGenCsvRow::source_keys()
+                .skip(1)
+                .map(|(key, _)| key.to_string()),
+        );
+
+        self.heading(3, "Average price")?;
+        let rows: Vec<Vec<String>> = summaries
+            .iter()
+            .map(|summary| vec![summary.label.clone(), format!("${:.2}", summary.avg_price)])
+            .collect();
+        self.table(&["Period".to_string(), "$/MWh".to_string()], &rows)?;
+
+        self.heading(3, "Share of generation")?;
+        let rows: Vec<Vec<String>> = summaries
+            .iter()
+            .map(|summary| {
+                let shares = summary.shares.iter().skip(1);
+                std::iter::once(summary.label.clone())
+                    .chain(shares.map(|share| format!("{:.1}%", share * 100.)))
+                    .collect()
+            })
+            .collect();
+        self.table(&header, &rows)?;
+
+        self.heading(3, "Value by source ($/MWh)")?;
+        let rows: Vec<Vec<String>> = summaries
+            .iter()
+            .map(|summary| {
+                let values = summary.values.iter().skip(1);
+                std::iter::once(summary.label.clone())
+                    .chain(values.map(|value| format!("{value:.2}")))
+                    .collect()
+            })
+            .collect();
+        self.table(&header, &rows)?;
+        Ok(())
+    }
+
+    fn heading(&mut self, level: usize, text: &str) -> error::Result<()> {
+        match self.format {
+            ReportFormat::Markdown => writeln!(self.body, "{} {text}\n", "#".repeat(level))?,
+            ReportFormat::Html => writeln!(self.body, "<h{level}>{text}</h{level}>")?,
+        }
+        Ok(())
+    }
+
+    fn paragraph(&mut self, text: &str) -> error::Result<()> {
+        match self.format {
+            ReportFormat::Markdown => writeln!(self.body, "{text}\n")?,
+            ReportFormat::Html => writeln!(self.body, "<p>{}</p>", text.replace('`', ""))?,
+        }
+        Ok(())
+    }
+
+    /// Links `png` from the report as `name` in `out_dir`, or embeds it.
+    fn image(&mut self, out_dir: &Path, name: &str, png: &[u8], alt: &str) -> error::Result<()> {
+        match self.format {
+            ReportFormat::Markdown => {
+                let mut file = self.options.create(&out_dir.join(name))?;
+                std::io::Write::write_all(&mut file, png)?;
+                writeln!(self.body, "![{alt}]({name})\n")?;
+            }
+            ReportFormat::Html => {
+                let encoded = base64::engine::general_purpose::STANDARD.encode(png);
+                writeln!(
+                    self.body,
+                    "<img alt=\"{alt}\" src=\"data:image/png;base64,{encoded}\">"
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn table(&mut self, header: &[String], rows: &[Vec<String>]) -> error::Result<()> {
+        match self.format {
+            ReportFormat::Markdown => {
+                writeln!(self.body, "| {} |", header.join(" | "))?;
+                writeln!(self.body, "|{}", " --- |".repeat(header.len()))?;
+                for row in rows {
+                    writeln!(self.body, "| {} |", row.join(" | "))?;
+                }
+                writeln!(self.body)?;
+            }
+            ReportFormat::Html => {
+                writeln!(self.body, "<table>")?;
+                writeln!(self.body, "<tr><th>{}</th></tr>", header.join("</th><th>"))?;
+                for row in rows {
+                    writeln!(self.body, "<tr><td>{}</td></tr>", row.join("</td><td>"))?;
+                }
+                writeln!(self.body, "</table>")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> String {
+        match self.format {
+            ReportFormat::Markdown => self.body,
+            ReportFormat::Html => format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+                 <title>CAISO energy report</title>\n<style>\n\
+                 body {{ font-family: sans-serif; max-width: 1200px; margin: auto; }}\n\
+                 img {{ max-width: 100%; }}\n\
+                 table {{ border-collapse: collapse; margin-bottom: 1em; }}\n\
+                 th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: right; }}\n\
+                 </style>\n</head>\n<body>\n{}</body>\n</html>\n",
+                self.body
+            ),
+        }
+    }
+}
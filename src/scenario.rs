@@ -0,0 +1,575 @@
+//! ### Scenario
+//! Hypothetical changes to the historical caiso data, used to answer
+//! "what if the grid looked different" questions.
+
+use crate::compute::Compute;
+use crate::convert::EnergyGenCsvRow;
+use crate::error::{self, err};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// A hypothetical battery that only charges from solar output and
+/// discharges whenever the grid pays the most for it.
+#[derive(Clone, Copy, Debug)]
+pub struct BatteryScenario {
+    pub capacity_mwh: f64,
+    /// Hours needed to fully charge or discharge at rated power.
+    pub duration_hours: f64,
+    pub round_trip_efficiency: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StorageSweepRow {
+    pub capacity_gwh: f64,
+    /// Average $/MWh of solar + battery output including the new storage.
+    pub avg_value: f64,
+    /// Total arbitrage profit earned by the new storage over the dataset.
+    pub arbitrage_revenue: f64,
+}
+
+impl BatteryScenario {
+    // Roughly what's being built in CAISO today.
+    const DEFAULT_DURATION_HOURS: f64 = 4.;
+    const DEFAULT_EFFICIENCY: f64 = 0.85;
+
+    pub fn with_capacity(capacity_mwh: f64) -> Self {
+        Self {
+            capacity_mwh,
+            duration_hours: Self::DEFAULT_DURATION_HOURS,
+            round_trip_efficiency: Self::DEFAULT_EFFICIENCY,
+        }
+    }
+
+    pub fn power_mw(&self) -> f64 {
+        self.capacity_mwh / self.duration_hours
+    }
+
+    /// Returns the profit from one charge/discharge cycle over a single day of
+    /// `(price, solar_mw)` five-minute intervals.
+    ///
+    /// This assumes perfect foresight. Every interval the battery charges in
+    /// comes before every one it discharges in, so it never sells energy it
+    /// hasn't stored yet. The split between them is whichever pays best.
+    pub fn daily_arbitrage(&self, day: &[(f64, f64)]) -> f64 {
+        // Intervals by index, cheapest solar first to charge and dearest
+        // first to discharge, sorted once for every split.
+        let mut charge: Vec<usize> = (0..day.len()).filter(|&i| day[i].1 > 0.).collect();
+        charge.sort_by(|&a, &b| day[a].0.total_cmp(&day[b].0));
+        let mut discharge: Vec<usize> = (0..day.len()).collect();
+        discharge.sort_by(|&a, &b| day[b].0.total_cmp(&day[a].0));
+
+        // Moving the split past an interval without solar only adds to what
+        // can be discharged, so only splits just after solar are tried.
+        (1..day.len())
+            .filter(|&split| day[split - 1].1 > 0.)
+            .map(|split| {
+                self.cycle(
+                    day,
+                    charge.iter().copied().filter(|&i| i < split),
+                    discharge.iter().copied().filter(|&i| i >= split),
+                )
+            })
+            .fold(0., f64::max)
+    }
+
+    /// The profit from charging on the `charge` intervals of `day` and
+    /// discharging on the `discharge` intervals, each in the order given.
+    fn cycle(
+        &self,
+        day: &[(f64, f64)],
+        charge: impl Iterator<Item = usize>,
+        discharge: impl Iterator<Item = usize>,
+    ) -> f64 {
+        const EPSILON: f64 = 1e-9;
+        let hours_per_incr = Compute::MINS_INCR as f64 / 60.;
+        let max_step = self.power_mw() * hours_per_incr;
+
+        // (price, MWh still available) in order of how attractive the
+        // interval is.
+        let mut charge = charge
+            .map(|i| (day[i].0, (day[i].1 * hours_per_incr).min(max_step)))
+            .peekable();
+        let mut discharge = discharge.map(|i| (day[i].0, max_step)).peekable();
+
+        let mut stored = 0.;
+        let mut revenue = 0.;
+        while self.capacity_mwh - stored > EPSILON {
+            let (Some((ch_price, ch_avail)), Some((dis_price, dis_avail))) =
+                (charge.peek_mut(), discharge.peek_mut())
+            else {
+                break;
+            };
+            if self.round_trip_efficiency * *dis_price <= *ch_price {
+                break;
+            }
+            let qty = ch_avail
+                .min(*dis_avail / self.round_trip_efficiency)
+                .min(self.capacity_mwh - stored);
+            revenue += qty * (self.round_trip_efficiency * *dis_price - *ch_price);
+            stored += qty;
+            *ch_avail -= qty;
+            *dis_avail -= qty * self.round_trip_efficiency;
+            if *ch_avail <= EPSILON {
+                charge.next();
+            }
+            if *dis_avail <= EPSILON {
+                discharge.next();
+            }
+        }
+        revenue
+    }
+
+    /// Returns the revenue of a grid-charged battery over one day of
+    /// hourly prices, sold as `dispatch` says.
+    ///
+    /// This assumes perfect foresight. As in `daily_arbitrage`, every hour
+    /// the battery charges in comes before every one it discharges in, and
+    /// the split between them is whichever earns the most in all. Within
+    /// each side, the cheapest hours to charge are paired with the dearest
+    /// to discharge for one cycle. Regulation is offered at full power both
+    /// ways with the charge held mid-way, and the energy it moves is taken
+    /// to net out over the hour. Co-optimized, the upward capacity goes to
+    /// spinning reserve in hours it pays more than regulation up.
+    pub fn daily_revenue(&self, hours: &[HourPrices], dispatch: Dispatch) -> Revenue {
+        let splits = match dispatch {
+            Dispatch::Regulation => 0..0,
+            _ => 1..hours.len(),
+        };
+        // No split at all is the day spent without cycling.
+        std::iter::once(None)
+            .chain(splits.map(Some))
+            .map(|split| self.split_revenue(hours, dispatch, split))
+            .fold(Revenue::default(), |best, revenue| {
+                match revenue.total() > best.total() {
+                    true => revenue,
+                    false => best,
+                }
+            })
+    }
+
+    /// The revenue of a day where the battery charges only in hours before
+    /// `split` and discharges only from it on, or never cycles if None.
+    fn split_revenue(
+        &self,
+        hours: &[HourPrices],
+        dispatch: Dispatch,
+        split: Option<usize>,
+    ) -> Revenue {
+        let power = self.power_mw();
+        let upward = |hour: &HourPrices| match dispatch {
+            Dispatch::CoOptimized => hour.reg_up.max(hour.spin),
+            _ => hour.reg_up,
+        };
+        let regulation = |hour: &HourPrices| power * (upward(hour) + hour.reg_down);
+        let mut revenue = Revenue::default();
+        let mut cycling = vec![false; hours.len()];
+
+        if let Some(split) = split {
+            let mut charge: Vec<usize> = (0..split).collect();
+            charge.sort_by(|a, b| hours[*a].energy.total_cmp(&hours[*b].energy));
+            let mut discharge: Vec<usize> = (split..hours.len()).collect();
+            discharge.sort_by(|a, b| hours[*b].energy.total_cmp(&hours[*a].energy));
+            // Each charging hour fills `power` MWh of the battery.
+            let cycles = self.duration_hours.floor() as usize;
+            for (&charge, &discharge) in charge.iter().zip(&discharge).take(cycles) {
+                let profit = power
+                    * (self.round_trip_efficiency * hours[discharge].energy - hours[charge].energy);
+                let forgone = match dispatch {
+                    Dispatch::CoOptimized => {
+                        regulation(&hours[charge]) + regulation(&hours[discharge])
+                    }
+                    _ => 0.,
+                };
+                if profit <= forgone.max(0.) {
+                    break;
+                }
+                revenue.arbitrage += profit;
+                cycling[charge] = true;
+                cycling[discharge] = true;
+            }
+        }
+
+        if dispatch != Dispatch::Arbitrage {
+            for (hour, _) in hours.iter().zip(&cycling).filter(|(_, cycling)| !**cycling) {
+                match upward(hour) > hour.reg_up {
+                    true => revenue.spin += power * hour.spin,
+                    false => revenue.reg_up += power * hour.reg_up,
+                }
+                revenue.reg_down += power * hour.reg_down;
+            }
+        }
+        revenue
+    }
+}
+
+/// What a battery's hours are sold for. Regulation pays for capacity held
+/// ready each hour, so any hour the battery isn't cycling can earn it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Dispatch {
+    /// Only buys and sells energy.
+    Arbitrage,
+    /// Only sells regulation up and down.
+    Regulation,
+    /// Cycles in the hours where arbitrage beats the reserves it gives up,
+    /// and sells regulation or spinning reserve in the rest.
+    CoOptimized,
+}
+
+impl Dispatch {
+    pub const ALL: [Dispatch; 3] = [
+        Dispatch::Arbitrage,
+        Dispatch::Regulation,
+        Dispatch::CoOptimized,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Dispatch::Arbitrage => "Arbitrage only",
+            Dispatch::Regulation => "Regulation only",
+            Dispatch::CoOptimized => "Co-optimized",
+        }
+    }
+}
+
+/// One hour's energy price, in $/MWh, and regulation and spinning
+/// reserve capacity prices, in $/MW per hour.
+#[derive(Clone, Copy, Debug)]
+pub struct HourPrices {
+    pub energy: f64,
+    pub reg_up: f64,
+    pub reg_down: f64,
+    pub spin: f64,
+}
+
+/// Battery revenue by where it came from, in dollars.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Revenue {
+    pub arbitrage: f64,
+    pub reg_up: f64,
+    pub reg_down: f64,
+    pub spin: f64,
+}
+
+impl Revenue {
+    /// What each source earned, in `Revenue::SOURCES` order.
+    pub const SOURCES: [&'static str; 4] = [
+        "Energy arbitrage",
+        "Regulation up",
+        "Regulation down",
+        "Spinning reserve",
+    ];
+
+    pub fn by_source(&self) -> [f64; 4] {
+        [self.arbitrage, self.reg_up, self.reg_down, self.spin]
+    }
+
+    pub fn total(&self) -> f64 {
+        self.by_source().iter().sum()
+    }
+}
+
+impl std::ops::AddAssign for Revenue {
+    fn add_assign(&mut self, other: Self) {
+        self.arbitrage += other.arbitrage;
+        self.reg_up += other.reg_up;
+        self.reg_down += other.reg_down;
+        self.spin += other.spin;
+    }
+}
+
+/// An electric vehicle that needs `kwh` some time between plugging in at
+/// `start` and leaving at `end`, which wraps past midnight when it's at or
+/// before `start`.
+#[derive(Clone, Copy, Debug)]
+pub struct ChargingScenario {
+    pub kwh: f64,
+    pub power_kw: f64,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub objective: ChargingObjective,
+}
+
+/// What a charging plan fills its slots to minimize.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ChargingObjective {
+    #[default]
+    Cost,
+    /// The grid's carbon intensity, from `carbon_intensity`.
+    Emissions,
+}
+
+/// Kilograms of CO2 per MWh of each source in `EnergyGenCsvRow::sources`
+/// order. Gas is a typical combined-cycle plant, and imports take CARB's
+/// default for unspecified power. Biogas and biomass burn biogenic carbon
+/// and count as zero, as in CARB's inventory.
+pub const EMISSION_FACTORS: [f64; 14] = [
+    0., 0., 0., 0., 1000., 0., 428., 0., 400., 0., 0., 0., 0., 0.,
+];
+
+/// The kg of CO2 per MWh of the grid's output in one interval. Batteries
+/// only move energy in time, so they're left out along with Total.
+pub fn carbon_intensity(sources: &[f64; 14]) -> f64 {
+    let (mut kg, mut mwh) = (0., 0.);
+    for (src, (output, factor)) in sources.iter().zip(EMISSION_FACTORS).enumerate() {
+        if src > 1 && *output > 0. {
+            kg += output * factor;
+            mwh += output;
+        }
+    }
+    if mwh > 0. {
+        kg / mwh
+    } else {
+        0.
+    }
+}
+
+/// The cost of one charging session charged naively, at full power from
+/// plug-in, and in the best slots of the window for its objective. The
+/// emissions columns are empty when the plan had no generation to go on.
+#[derive(Serialize, Debug)]
+pub struct ChargingPlan {
+    pub label: String,
+    pub naive_cost: f64,
+    pub optimized_cost: f64,
+    pub savings: f64,
+    /// Average $/MWh paid for the session's energy.
+    pub naive_price: f64,
+    pub optimized_price: f64,
+    /// Kilograms of CO2 behind the session's energy.
+    pub naive_kg: Option<f64>,
+    pub optimized_kg: Option<f64>,
+    pub kg_saved: Option<f64>,
+    /// Time of day of the cheapest, or cleanest, slot used.
+    pub best_slot: String,
+    /// The optimized session's slots, in time order.
+    #[serde(skip)]
+    pub schedule: Vec<ChargingStep>,
+}
+
+/// One five-minute slot of an optimized charging session.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ChargingStep {
+    pub label: String,
+    pub time: String,
+    pub kwh: f64,
+    pub price: f64,
+    pub kg_per_mwh: Option<f64>,
+}
+
+impl ChargingScenario {
+    /// The five-minute slots of the day inside the window, in time order.
+    pub fn slots(&self) -> Vec<usize> {
+        let slots = Compute::slots_per_day();
+        let start = Compute::time_to_idx_5min(self.start.hour(), self.start.minute());
+        let end = Compute::time_to_idx_5min(self.end.hour(), self.end.minute());
+        let len = (end + slots - start - 1) % slots + 1;
+        (start..start + len).map(|idx| idx % slots).collect()
+    }
+
+    /// Plans a session over `prices`, the $/MWh price of each slot in the
+    /// window in time order, and `intensity`, the kg/MWh of each slot if
+    /// known. The emissions objective needs `intensity`.
+    pub fn plan(
+        &self,
+        label: String,
+        prices: &[f64],
+        intensity: Option<&[f64]>,
+    ) -> error::Result<ChargingPlan> {
+        let max_step = self.power_kw * Compute::MINS_INCR as f64 / 60.;
+        if (max_step * prices.len() as f64) < self.kwh {
+            return Err(err!(
+                Invalid,
+                "{} kWh can't be delivered at {} kW in a {}-minute window",
+                self.kwh,
+                self.power_kw,
+                prices.len() * Compute::MINS_INCR
+            ));
+        }
+        // kWh drawn in each slot when they're filled in `order`.
+        let fill = |order: &[usize]| {
+            let mut kwh = vec![0.; prices.len()];
+            let mut remaining = self.kwh;
+            for &idx in order {
+                if remaining <= 0. {
+                    break;
+                }
+                kwh[idx] = max_step.min(remaining);
+                remaining -= kwh[idx];
+            }
+            kwh
+        };
+        let total = |kwh: &[f64], per_mwh: &[f64]| -> f64 {
+            kwh.iter()
+                .zip(per_mwh)
+                .map(|(kwh, x)| kwh * x / 1000.)
+                .sum()
+        };
+
+        let key = match (self.objective, intensity) {
+            (ChargingObjective::Cost, _) => prices,
+            (ChargingObjective::Emissions, Some(intensity)) => intensity,
+            (ChargingObjective::Emissions, None) => {
+                return Err(err!(
+                    Invalid,
+                    "Planning for emissions needs generation to weigh slots by"
+                ))
+            }
+        };
+        let naive: Vec<usize> = (0..prices.len()).collect();
+        let mut best = naive.clone();
+        best.sort_by(|a, b| key[*a].total_cmp(&key[*b]));
+        let (naive, optimized) = (fill(&naive), fill(&best));
+        let (naive_cost, optimized_cost) = (total(&naive, prices), total(&optimized, prices));
+        let kg =
+            intensity.map(|intensity| (total(&naive, intensity), total(&optimized, intensity)));
+
+        let slots = self.slots();
+        let time = |idx: usize| {
+            let (hour, minute) = Compute::idx_5min_to_time(slots[idx]);
+            format!("{hour:02}:{minute:02}")
+        };
+        let schedule = optimized
+            .iter()
+            .enumerate()
+            .filter(|(_, kwh)| **kwh > 0.)
+            .map(|(idx, kwh)| ChargingStep {
+                label: label.clone(),
+                time: time(idx),
+                kwh: *kwh,
+                price: prices[idx],
+                kg_per_mwh: intensity.map(|intensity| intensity[idx]),
+            })
+            .collect();
+        Ok(ChargingPlan {
+            naive_cost,
+            optimized_cost,
+            savings: naive_cost - optimized_cost,
+            naive_price: 1000. * naive_cost / self.kwh,
+            optimized_price: 1000. * optimized_cost / self.kwh,
+            naive_kg: kg.map(|(naive, _)| naive),
+            optimized_kg: kg.map(|(_, optimized)| optimized),
+            kg_saved: kg.map(|(naive, optimized)| naive - optimized),
+            best_slot: time(best[0]),
+            schedule,
+            label,
+        })
+    }
+}
+
+/// Scales one source's output by a constant factor, as if CAISO had that
+/// much more (or less) of it installed. With a window, only intervals
+/// inside it are scaled, as on a smoky day or during an eclipse.
+///
+/// Prices respond through a simple merit-order model: the extra output
+/// displaces whatever else was serving demand, so Total is unchanged, and
+/// price scales with the remaining net load as `price * (net_load_after / net_load_before) ^ elasticity`.
+#[derive(Clone, Debug)]
+pub struct SourceScaling {
+    pub source_idx: usize,
+    pub factor: f64,
+    pub elasticity: f64,
+    pub window: Option<ScenarioWindow>,
+}
+
+/// The times a scenario applies: a span of each day, on only `dates` if
+/// given.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioWindow {
+    pub start: NaiveTime,
+    /// Exclusive. An end at or before the start wraps past midnight.
+    pub end: NaiveTime,
+    pub dates: Option<RangeInclusive<NaiveDate>>,
+}
+
+impl ScenarioWindow {
+    /// Whether `date` is one of the window's days.
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        self.dates
+            .as_ref()
+            .is_none_or(|dates| dates.contains(&date))
+    }
+
+    pub fn contains(&self, time: NaiveDateTime) -> bool {
+        let clock = time.time();
+        let clock_matches = if self.start < self.end {
+            self.start <= clock && clock < self.end
+        } else {
+            self.start <= clock || clock < self.end
+        };
+        clock_matches && self.covers(time.date())
+    }
+}
+
+impl SourceScaling {
+    pub const DEFAULT_ELASTICITY: f64 = 1.;
+
+    /// `source` is a generation source label such as "Solar" or "Wind".
+    pub fn new(source: &str, factor: f64, elasticity: f64) -> error::Result<Self> {
+        let source_idx = EnergyGenCsvRow::source_idx(source)?;
+        if source_idx == 0 {
+            return Err(err!(Invalid, "Cannot scale the Total column"));
+        }
+        Ok(Self {
+            source_idx,
+            factor,
+            elasticity,
+            window: None,
+        })
+    }
+
+    /// Scales the source only inside `window`.
+    pub fn with_window(mut self, window: ScenarioWindow) -> Self {
+        self.window = Some(window);
+        self
+    }
+
+    pub fn source_name(&self) -> &'static str {
+        EnergyGenCsvRow::source_keys()
+            .nth(self.source_idx)
+            .map_or("", |(key, _)| key)
+    }
+
+    /// Like `apply` for the interval at `time`, leaving intervals outside
+    /// the window as they were.
+    pub fn apply_at(&self, time: NaiveDateTime, sources: &mut [f64; 14], price: f64) -> f64 {
+        match &self.window {
+            Some(window) if !window.contains(time) => price,
+            _ => self.apply(sources, price),
+        }
+    }
+
+    /// Scales the source in `sources` and returns the suppressed price.
+    /// Total stays at demand: the rest of the fleet gives up (or makes up)
+    /// the difference in proportion to its output, and output beyond what it
+    /// can give up is curtailed. Ignores the window.
+    pub fn apply(&self, sources: &mut [f64; 14], price: f64) -> f64 {
+        let idx = self.source_idx;
+        let total = sources[0];
+        let before = sources[idx];
+        let others: f64 = sources
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|&(i, mw)| i != idx && *mw > 0.)
+            .map(|(_, mw)| mw)
+            .sum();
+        let after = (before * self.factor).min(before + others);
+        sources[idx] = after;
+        if others > 0. {
+            let keep = (others - (after - before)) / others;
+            for (i, mw) in sources.iter_mut().enumerate().skip(1) {
+                if i != idx && *mw > 0. {
+                    *mw *= keep;
+                }
+            }
+        }
+
+        let net_before = total - before;
+        if net_before <= 0. {
+            return price;
+        }
+        let ratio = ((total - after) / net_before).max(0.);
+        price * ratio.powf(self.elasticity)
+    }
+}
@@ -0,0 +1,156 @@
+//! ### Series
+//! Third-party time series, like temperature readings, read from csvs laid
+//! out however their source writes them and aligned onto the five-minute
+//! grid the caiso datasets use, so they can be joined into an analysis.
+//! Timestamps are taken to be in the same local time as the caiso files.
+
+use crate::compute::Compute;
+use crate::convert::{CsvFormat, TIMESTAMP_FMT};
+use crate::error::{self, bail, EnergyAnalysisError, Locate};
+use crate::frame::Frame;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike};
+use csv::Position;
+use std::path::Path;
+
+/// Where an external csv keeps its timestamps and how it writes them.
+/// The defaults read the csvs `align-series` writes.
+#[derive(Clone, Debug)]
+pub struct SeriesLayout {
+    pub timestamp_column: String,
+    /// A chrono format, e.g. `%m/%d/%Y %H:%M`. Dates without a time are
+    /// read as midnight.
+    pub timestamp_format: String,
+    pub csv: CsvFormat,
+}
+
+impl Default for SeriesLayout {
+    fn default() -> Self {
+        Self {
+            timestamp_column: "timestamp".to_string(),
+            timestamp_format: TIMESTAMP_FMT.to_string(),
+            csv: CsvFormat::default(),
+        }
+    }
+}
+
+impl SeriesLayout {
+    fn parse_time(&self, value: &str) -> Result<NaiveDateTime, chrono::ParseError> {
+        let value = value.trim();
+        NaiveDateTime::parse_from_str(value, &self.timestamp_format).or_else(|e| {
+            NaiveDate::parse_from_str(value, &self.timestamp_format)
+                .map(|date| date.and_time(NaiveTime::MIN))
+                .map_err(|_| e)
+        })
+    }
+}
+
+/// Reads the named `columns` of an external csv, or every column but the
+/// timestamp when none are named, sorted by time. Empty fields are missing
+/// values and load as NaN.
+pub fn load(path: &Path, layout: &SeriesLayout, columns: &[String]) -> error::Result<Frame> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(layout.csv.delimiter)
+        .from_path(path)?;
+    let headers = reader.headers()?.clone();
+    let position = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.trim() == name)
+            .ok_or_else(|| EnergyAnalysisError::SchemaMismatch {
+                file: path.to_path_buf(),
+                reason: format!(
+                    "No column {name:?} among {:?}",
+                    headers.iter().collect::<Vec<_>>()
+                ),
+            })
+    };
+    let time_idx = position(&layout.timestamp_column)?;
+    let value_idxs: Vec<usize> = match columns.is_empty() {
+        true => (0..headers.len()).filter(|idx| *idx != time_idx).collect(),
+        false => columns
+            .iter()
+            .map(|name| position(name))
+            .collect::<error::Result<_>>()?,
+    };
+    if value_idxs.is_empty() {
+        bail!(Invalid, "{path:?} has no columns besides its timestamps");
+    }
+
+    let mut rows: Vec<(NaiveDateTime, Vec<f64>)> = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, Position::line);
+        let field = |idx: usize| record.get(idx).unwrap_or_default();
+        let time = layout.parse_time(field(time_idx)).locate(
+            path,
+            line,
+            &headers[time_idx],
+            field(time_idx),
+        )?;
+        let values = value_idxs
+            .iter()
+            .map(|&idx| match field(idx).trim() {
+                "" => Ok(f64::NAN),
+                value => layout
+                    .csv
+                    .parse_number(value)
+                    .locate(path, line, &headers[idx], value),
+            })
+            .collect::<error::Result<_>>()?;
+        rows.push((time, values));
+    }
+    if rows.is_empty() {
+        bail!(NoData, "No rows in {path:?}");
+    }
+    rows.sort_by_key(|(time, _)| *time);
+
+    let mut frame = Frame::new(rows.iter().map(|(time, _)| *time).collect());
+    for (col, idx) in value_idxs.iter().enumerate() {
+        let values = rows.iter().map(|(_, values)| values[col]).collect();
+        frame = frame.with_column(headers[*idx].trim(), values)?;
+    }
+    Ok(frame)
+}
+
+/// Every five-minute interval start from the first one at or after `start`
+/// through `end`.
+fn five_minute_grid(start: NaiveDateTime, end: NaiveDateTime) -> Vec<NaiveDateTime> {
+    let step = TimeDelta::minutes(Compute::MINS_INCR as i64);
+    let into_interval = TimeDelta::seconds(
+        (start.minute() as usize % Compute::MINS_INCR * 60 + start.second() as usize) as i64,
+    );
+    let mut time = match into_interval.is_zero() {
+        true => start,
+        false => start - into_interval + step,
+    };
+    let mut grid = Vec::new();
+    while time <= end {
+        grid.push(time);
+        time += step;
+    }
+    grid
+}
+
+/// Interpolates `series` onto `grid`, or onto every five-minute interval
+/// it spans without one. Times outside the series, or in gaps between its
+/// values longer than `max_gap`, are left out.
+pub fn align(
+    series: &Frame,
+    grid: Option<&[NaiveDateTime]>,
+    max_gap: Option<TimeDelta>,
+) -> error::Result<Frame> {
+    let (Some(first), Some(last)) = (series.times().first(), series.times().last()) else {
+        bail!(NoData, "No values to align");
+    };
+    let aligned = match grid {
+        Some(grid) => series.interpolate(grid, max_gap),
+        None => series.interpolate(&five_minute_grid(*first, *last), max_gap),
+    };
+    if aligned.is_empty() {
+        bail!(
+            NoData,
+            "The series doesn't overlap any interval of the grid"
+        );
+    }
+    Ok(aligned)
+}
@@ -0,0 +1,469 @@
+//! ### Serve
+//! Answers compute queries over HTTP so a dashboard can ask for profiles and
+//! charts without running the CLI for each one.
+//!
+//! ```text
+//! GET /price-profile?start=2024-06-01&end=2024-06-30&window=15m
+//! GET /gen-profile?window=1h
+//! GET /charts/price.png?start=2024-06-01
+//! GET /charts/gen.svg
+//! GET /metrics
+//! ```
+//!
+//! `start` and `end` are inclusive dates and default to the whole dataset.
+//! `window` is a number of minutes with an optional `m` or `h` suffix, and
+//! defaults to five minutes. Charts are always drawn at five minutes.
+//! Responses are cached until an input file changes, keeping only the most
+//! recently used few hundred.
+//!
+//! Charts are only served by builds with the `graph` feature.
+//!
+//! `/metrics` reports the latest price and generation mix as Prometheus
+//! gauges. Only the end of each file is read, so a scraper can poll it while
+//! another process, like `fetch-oasis --append`, adds to the csvs.
+
+use crate::chart::Theme;
+#[cfg(feature = "graph")]
+use crate::compute::Profile;
+use crate::compute::{Compute, PriceNode};
+use crate::config::Labels;
+use crate::convert::EnergyGenCsvRow;
+use crate::error::{self, bail, err};
+#[cfg(feature = "graph")]
+use crate::graph::{Graphing, ImageFormat};
+use crate::watch::Watcher;
+use chrono::NaiveDate;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Clone, Debug)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    fn json(value: &Value) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: value.to_string().into_bytes(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: json!({ "error": message }).to_string().into_bytes(),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Answers requests against one price and one generation dataset.
+pub struct Service {
+    price_csv: PathBuf,
+    gen_csv: PathBuf,
+    price_node: PriceNode,
+    #[cfg_attr(not(feature = "graph"), allow(dead_code))]
+    theme: Theme,
+    #[cfg_attr(not(feature = "graph"), allow(dead_code))]
+    labels: Labels,
+    cache: Mutex<ResponseCache>,
+    inputs: Mutex<Watcher>,
+}
+
+impl Service {
+    pub fn new(price_csv: PathBuf, gen_csv: PathBuf) -> Self {
+        let inputs = Watcher::new(vec![price_csv.clone(), gen_csv.clone()]);
+        Self {
+            price_csv,
+            gen_csv,
+            price_node: PriceNode::default(),
+            theme: Theme::default(),
+            labels: Labels::default(),
+            cache: Mutex::new(ResponseCache::new(ResponseCache::DEFAULT_ENTRIES)),
+            inputs: Mutex::new(inputs),
+        }
+    }
+
+    pub fn with_style(mut self, theme: Theme, labels: Labels) -> Self {
+        self.theme = theme;
+        self.labels = labels;
+        self
+    }
+
+    /// Caches at most `entries` responses, dropping the least recently used
+    /// first.
+    pub fn with_cache_entries(mut self, entries: usize) -> Self {
+        self.cache = Mutex::new(ResponseCache::new(entries));
+        self
+    }
+
+    /// How many responses are cached.
+    pub fn cached_responses(&self) -> usize {
+        self.cache.lock().map_or(0, |cache| cache.entries.len())
+    }
+
+    /// Answers with `node`'s LMP as the price.
+    pub fn with_price_node(mut self, node: PriceNode) -> Self {
+        self.price_node = node;
+        self
+    }
+
+    fn prices(&self) -> Compute<'_> {
+        Compute::new(&self.price_csv).with_price_node(self.price_node)
+    }
+
+    /// Answers a GET of `target`, a path with an optional query string.
+    pub fn get(&self, target: &str) -> Response {
+        if self.inputs.lock().is_ok_and(|mut inputs| inputs.changed()) {
+            if let Ok(mut cache) = self.cache.lock() {
+                cache.clear();
+            }
+        }
+        if let Some(hit) = self.cache.lock().ok().and_then(|mut c| c.get(target)) {
+            return hit;
+        }
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let response = match Query::parse(query) {
+            Ok(query) => self.route(path, &query),
+            Err(e) => Response::error(400, &format!("{e:#}")),
+        };
+        if response.status == 200 {
+            if let Ok(mut cache) = self.cache.lock() {
+                cache.insert(target, response.clone());
+            }
+        }
+        response
+    }
+
+    fn route(&self, path: &str, query: &Query) -> Response {
+        let result = match path {
+            "/price-profile" => self.price_profile(query),
+            "/gen-profile" => self.gen_profile(query),
+            "/metrics" => self.metrics(),
+            #[cfg(feature = "graph")]
+            "/charts/price.png" | "/charts/price.svg" | "/charts/gen.png" | "/charts/gen.svg" => {
+                self.chart(path, query)
+            }
+            _ => return Response::error(404, &format!("No endpoint at {path}")),
+        };
+        result.unwrap_or_else(|e| Response::error(500, &format!("{e:#}")))
+    }
+
+    fn price_profile(&self, query: &Query) -> error::Result<Response> {
+        let prices = self
+            .prices()
+            .windowed_price_profile(query.dates(), query.window)?;
+        let slots: Vec<Value> = prices
+            .iter()
+            .enumerate()
+            .map(|(idx, price)| json!({ "time": query.slot_label(idx), "price": price }))
+            .collect();
+        Ok(Response::json(&query.wrap(slots)))
+    }
+
+    fn gen_profile(&self, query: &Query) -> error::Result<Response> {
+        let gen = Compute::new(&self.gen_csv).windowed_gen_profile(query.dates(), query.window)?;
+        let slots: Vec<Value> = gen
+            .iter()
+            .enumerate()
+            .map(|(idx, sources)| {
+                let sources: Map<String, Value> = EnergyGenCsvRow::source_keys()
+                    .zip(sources)
+                    .map(|((key, _), mw)| (key.to_string(), json!(mw)))
+                    .collect();
+                json!({ "time": query.slot_label(idx), "sources": sources })
+            })
+            .collect();
+        Ok(Response::json(&query.wrap(slots)))
+    }
+
+    fn metrics(&self) -> error::Result<Response> {
+        let hour = 60 / Compute::MINS_INCR;
+        let prices = self.prices().latest_prices(hour)?;
+        let Some(&(price_time, price)) = prices.last() else {
+            bail!(NoData, "No prices in {:?}", self.price_csv);
+        };
+        let recent = prices.iter().map(|(_, price)| *price);
+        let mut metrics = Metrics::default();
+        metrics.gauge("energy_price_lmp", "Latest LMP in $/MWh", &[("", price)]);
+        metrics.gauge(
+            "energy_price_lmp_avg_1h",
+            "Average LMP over the last hour in $/MWh",
+            &[("", recent.clone().sum::<f64>() / prices.len() as f64)],
+        );
+        metrics.gauge(
+            "energy_price_lmp_max_1h",
+            "Highest LMP over the last hour in $/MWh",
+            &[("", recent.fold(f64::NEG_INFINITY, f64::max))],
+        );
+        metrics.gauge(
+            "energy_price_timestamp_seconds",
+            "Start of the latest priced interval, local time read as UTC",
+            &[("", price_time.and_utc().timestamp() as f64)],
+        );
+
+        if let Some((gen_time, sources)) = Compute::new(&self.gen_csv).latest_gen()? {
+            let by_source: Vec<(&str, f64)> = EnergyGenCsvRow::source_keys()
+                .zip(sources)
+                .skip(1)
+                .map(|((key, _), mw)| (key, mw))
+                .collect();
+            let shares: Vec<(&str, f64)> = by_source
+                .iter()
+                .map(|&(key, mw)| (key, mw / sources[0]))
+                .collect();
+            metrics.gauge("energy_gen_mw", "Latest output by source in MW", &by_source);
+            metrics.gauge(
+                "energy_gen_share",
+                "Latest share of total output by source",
+                &shares,
+            );
+            metrics.gauge(
+                "energy_gen_total_mw",
+                "Latest total output in MW",
+                &[("", sources[0])],
+            );
+            metrics.gauge(
+                "energy_gen_timestamp_seconds",
+                "Start of the latest generation interval, local time read as UTC",
+                &[("", gen_time.and_utc().timestamp() as f64)],
+            );
+        }
+        Ok(Response {
+            status: 200,
+            content_type: "text/plain; version=0.0.4",
+            body: metrics.text.into_bytes(),
+        })
+    }
+
+    #[cfg(feature = "graph")]
+    fn chart(&self, path: &str, query: &Query) -> error::Result<Response> {
+        let (name, ext) = path
+            .trim_start_matches("/charts/")
+            .split_once('.')
+            .ok_or_else(|| err!(Invalid, "No chart at {path}"))?;
+        let format = ImageFormat::from_extension(ext);
+        let graphing = Graphing::in_memory()
+            .with_theme(self.theme)
+            .with_labels(self.labels.clone());
+        let body = match name {
+            "price" => self
+                .prices()
+                .windowed_price_profile(query.dates(), Compute::MINS_INCR)
+                .and_then(|prices| graphing.daily_price_to_buffer(&prices, Profile::Day, format)),
+            _ => Compute::new(&self.gen_csv)
+                .windowed_gen_profile(query.dates(), Compute::MINS_INCR)
+                .and_then(|gen| {
+                    graphing.daily_gen_to_buffer(
+                        &gen,
+                        Profile::Day,
+                        "Average generation by source",
+                        None,
+                        format,
+                    )
+                }),
+        };
+        Ok(Response {
+            status: 200,
+            content_type: format.content_type(),
+            body: body?,
+        })
+    }
+
+    /// Serves requests on `addr` until the process is stopped, one thread
+    /// per connection.
+    pub fn serve(self, addr: &str) -> error::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        println!("Serving on http://{}", listener.local_addr()?);
+        let service = Arc::new(self);
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let service = Arc::clone(&service);
+            thread::spawn(move || {
+                if let Err(e) = service.respond(stream) {
+                    eprintln!("{e:#}");
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn respond(&self, mut stream: TcpStream) -> error::Result<()> {
+        let mut reader = BufReader::new(&stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // Headers aren't used, but have to be read before responding.
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+        let response = match request_line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["GET", target, _] => self.get(target),
+            [_, _, _] => Response::error(405, "Only GET is supported"),
+            _ => Response::error(400, "Malformed request line"),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            response.status,
+            response.reason(),
+            response.content_type,
+            response.body.len()
+        )?;
+        stream.write_all(&response.body)?;
+        Ok(())
+    }
+}
+
+/// Responses by request target, dropping the least recently used once
+/// `limit` are held so varied query strings can't grow it without bound.
+struct ResponseCache {
+    limit: usize,
+    /// Each response with the tick it was last used.
+    entries: HashMap<String, (Response, u64)>,
+    tick: u64,
+}
+
+impl ResponseCache {
+    const DEFAULT_ENTRIES: usize = 256;
+
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            entries: HashMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn get(&mut self, target: &str) -> Option<Response> {
+        self.tick += 1;
+        let (response, used) = self.entries.get_mut(target)?;
+        *used = self.tick;
+        Some(response.clone())
+    }
+
+    fn insert(&mut self, target: &str, response: Response) {
+        if self.limit == 0 {
+            return;
+        }
+        if self.entries.len() >= self.limit && !self.entries.contains_key(target) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(target, _)| target.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.entries
+            .insert(target.to_string(), (response, self.tick));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Prometheus' text exposition format.
+#[derive(Default)]
+struct Metrics {
+    text: String,
+}
+
+impl Metrics {
+    /// Adds a gauge with one sample per source, or a single unlabeled sample
+    /// when the source is empty.
+    fn gauge(&mut self, name: &str, help: &str, samples: &[(&str, f64)]) {
+        use std::fmt::Write;
+        let _ = writeln!(self.text, "# HELP {name} {help}\n# TYPE {name} gauge");
+        for (source, val) in samples {
+            let _ = match *source {
+                "" => writeln!(self.text, "{name} {val}"),
+                source => writeln!(self.text, "{name}{{source=\"{source}\"}} {val}"),
+            };
+        }
+    }
+}
+
+/// The parameters shared by every endpoint.
+#[derive(Debug)]
+struct Query {
+    start: Option<NaiveDate>,
+    end: Option<NaiveDate>,
+    window: usize,
+}
+
+impl Query {
+    fn parse(query: &str) -> error::Result<Self> {
+        let mut parsed = Self {
+            start: None,
+            end: None,
+            window: Compute::MINS_INCR,
+        };
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, val) = pair.split_once('=').unwrap_or((pair, ""));
+            match key {
+                "start" => parsed.start = Some(NaiveDate::parse_from_str(val, "%Y-%m-%d")?),
+                "end" => parsed.end = Some(NaiveDate::parse_from_str(val, "%Y-%m-%d")?),
+                "window" => parsed.window = Self::parse_window(val)?,
+                _ => bail!(Invalid, "Unknown parameter {key:?}"),
+            }
+        }
+        Compute::intervals_per_window(parsed.window)?;
+        Ok(parsed)
+    }
+
+    /// Minutes in a window given as `15`, `15m`, or `1h`.
+    fn parse_window(window: &str) -> error::Result<usize> {
+        let (num, scale) = match window.strip_suffix('h') {
+            Some(hours) => (hours, 60),
+            None => (window.strip_suffix('m').unwrap_or(window), 1),
+        };
+        num.parse::<usize>()
+            .ok()
+            .and_then(|num| num.checked_mul(scale))
+            .ok_or_else(|| err!(Invalid, "Invalid window {window:?}"))
+    }
+
+    fn dates(&self) -> RangeInclusive<NaiveDate> {
+        self.start.unwrap_or(NaiveDate::MIN)..=self.end.unwrap_or(NaiveDate::MAX)
+    }
+
+    fn slot_label(&self, idx: usize) -> String {
+        let mins = idx * self.window;
+        format!("{:02}:{:02}", mins / 60, mins % 60)
+    }
+
+    fn wrap(&self, slots: Vec<Value>) -> Value {
+        json!({
+            "start": self.start,
+            "end": self.end,
+            "window_mins": self.window,
+            "slots": slots,
+        })
+    }
+}
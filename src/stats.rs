@@ -0,0 +1,273 @@
+//! ### Stats
+//! Small streaming statistics helpers used by the compute functions.
+
+use serde::{Deserialize, Serialize};
+
+/// Accumulates the Pearson correlation between two series one pair at
+/// a time, using Welford-style updates so large MW values stay accurate.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Correlation {
+    count: usize,
+    mean_x: f64,
+    mean_y: f64,
+    m2_x: f64,
+    m2_y: f64,
+    co_moment: f64,
+}
+
+impl Correlation {
+    pub fn push(&mut self, x: f64, y: f64) {
+        self.count += 1;
+        let n = self.count as f64;
+        let dx = x - self.mean_x;
+        self.mean_x += dx / n;
+        let dy = y - self.mean_y;
+        self.mean_y += dy / n;
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+        self.co_moment += dx * (y - self.mean_y);
+    }
+
+    /// Combines two accumulators as if every pair had been pushed into one
+    /// (Chan et al.), so partitions can be accumulated in parallel.
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let (n_a, n_b) = (self.count as f64, other.count as f64);
+        let n = n_a + n_b;
+        let dx = other.mean_x - self.mean_x;
+        let dy = other.mean_y - self.mean_y;
+        self.m2_x += other.m2_x + dx * dx * n_a * n_b / n;
+        self.m2_y += other.m2_y + dy * dy * n_a * n_b / n;
+        self.co_moment += other.co_moment + dx * dy * n_a * n_b / n;
+        self.mean_x += dx * n_b / n;
+        self.mean_y += dy * n_b / n;
+        self.count += other.count;
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn means(&self) -> (f64, f64) {
+        (self.mean_x, self.mean_y)
+    }
+
+    /// Sample variances of each series and their sample covariance, all NaN
+    /// with fewer than two pairs.
+    pub fn variances(&self) -> (f64, f64, f64) {
+        if self.count < 2 {
+            return (f64::NAN, f64::NAN, f64::NAN);
+        }
+        let n = (self.count - 1) as f64;
+        (self.m2_x / n, self.m2_y / n, self.co_moment / n)
+    }
+
+    /// The least-squares line predicting y from x. Every field but `count`
+    /// is NaN with fewer than two pairs or a constant x.
+    pub fn fit(&self) -> LinearFit {
+        let (slope, intercept) = if self.count < 2 || self.m2_x == 0. {
+            (f64::NAN, f64::NAN)
+        } else {
+            let slope = self.co_moment / self.m2_x;
+            (slope, self.mean_y - slope * self.mean_x)
+        };
+        LinearFit {
+            slope,
+            intercept,
+            r_squared: self.pearson().powi(2),
+            count: self.count,
+        }
+    }
+
+    /// Returns NaN when either series is constant (e.g. a source that never ran).
+    pub fn pearson(&self) -> f64 {
+        let denom = (self.m2_x * self.m2_y).sqrt();
+        if self.count < 2 || denom == 0. {
+            return f64::NAN;
+        }
+        self.co_moment / denom
+    }
+}
+
+/// A line `y = slope * x + intercept` fit to `count` pairs, with the share
+/// of y's variance it explains.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    pub r_squared: f64,
+    pub count: usize,
+}
+
+impl LinearFit {
+    pub fn at(&self, x: f64) -> f64 {
+        self.slope * x + self.intercept
+    }
+}
+
+/// The value below which fraction `p` of `sorted` falls, interpolating
+/// linearly between neighbors. NaN for an empty slice.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let Some(last) = sorted.len().checked_sub(1) else {
+        return f64::NAN;
+    };
+    let rank = p.clamp(0., 1.) * last as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+}
+
+/// Percentiles, from 0 to 100, to winsorize a series at: values below the
+/// `low`th percentile are raised to it and values above the `high`th are
+/// lowered to it, so a few extreme intervals can't swamp an average.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Clip {
+    pub low: f64,
+    pub high: f64,
+}
+
+/// The values a `Clip` worked out to for one series.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClipBounds {
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Clip {
+    /// The bounds of the clip over `values`, skipping NaN.
+    pub fn bounds(self, values: &[f64]) -> ClipBounds {
+        let mut sorted: Vec<f64> = values.iter().copied().filter(|v| !v.is_nan()).collect();
+        sorted.sort_unstable_by(f64::total_cmp);
+        ClipBounds {
+            low: percentile(&sorted, self.low / 100.),
+            high: percentile(&sorted, self.high / 100.),
+        }
+    }
+}
+
+impl ClipBounds {
+    pub fn apply(self, value: f64) -> f64 {
+        value.clamp(self.low, self.high)
+    }
+}
+
+/// How far the mean of one sample moved from another's, with Welch's
+/// standard error for samples of unequal size and spread. The p-value and
+/// confidence interval take the difference as normally distributed, which
+/// holds for the dozens of days a period usually spans.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct MeanDifference {
+    pub before: f64,
+    pub after: f64,
+    pub change: f64,
+    pub std_error: f64,
+    /// The chance of a change at least this large if the means were equal.
+    pub p_value: f64,
+}
+
+impl MeanDifference {
+    /// The z score of a two-sided 95% confidence interval.
+    pub const Z_95: f64 = 1.959964;
+
+    /// Compares `after` with `before`, skipping NaN. Every field is NaN
+    /// unless each sample has at least two values.
+    pub fn of(before: &[f64], after: &[f64]) -> Self {
+        let moments = |sample: &[f64]| {
+            let values: Vec<f64> = sample.iter().copied().filter(|v| !v.is_nan()).collect();
+            let n = values.len() as f64;
+            let mean = values.iter().sum::<f64>() / n;
+            let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.);
+            (mean, variance / n, values.len())
+        };
+        let (before_mean, before_var, before_n) = moments(before);
+        let (after_mean, after_var, after_n) = moments(after);
+        if before_n < 2 || after_n < 2 {
+            return Self {
+                before: f64::NAN,
+                after: f64::NAN,
+                change: f64::NAN,
+                std_error: f64::NAN,
+                p_value: f64::NAN,
+            };
+        }
+        let change = after_mean - before_mean;
+        let std_error = (before_var + after_var).sqrt();
+        let p_value = match std_error {
+            0. if change == 0. => 1.,
+            0. => 0.,
+            _ => erfc((change / std_error).abs() / std::f64::consts::SQRT_2),
+        };
+        Self {
+            before: before_mean,
+            after: after_mean,
+            change,
+            std_error,
+            p_value,
+        }
+    }
+
+    /// The 95% confidence interval of the change.
+    pub fn interval_95(&self) -> (f64, f64) {
+        let half = Self::Z_95 * self.std_error;
+        (self.change - half, self.change + half)
+    }
+}
+
+/// The complementary error function, to within about 1e-7 (Abramowitz and
+/// Stegun 7.1.26).
+fn erfc(x: f64) -> f64 {
+    let t = 1. / (1. + 0.3275911 * x.abs());
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erfc = poly * (-x * x).exp();
+    if x < 0. {
+        2. - erfc
+    } else {
+        erfc
+    }
+}
+
+/// Where the mean of `values` shifts, by binary segmentation: the split
+/// that leaves the least squared error about each side's mean is kept when
+/// both sides have at least `min_len` values and their means differ at
+/// significance `alpha`, then each side is searched the same way. Returns
+/// the index each new level starts at, in order.
+pub fn changepoints(values: &[f64], min_len: usize, alpha: f64) -> Vec<usize> {
+    let min_len = min_len.max(2);
+    if values.len() < 2 * min_len {
+        return Vec::new();
+    }
+    let (mut sum, mut sum_sq) = (vec![0.], vec![0.]);
+    for value in values {
+        sum.push(sum[sum.len() - 1] + value);
+        sum_sq.push(sum_sq[sum_sq.len() - 1] + value * value);
+    }
+    let error = |from: usize, to: usize| {
+        let (total, squares) = (sum[to] - sum[from], sum_sq[to] - sum_sq[from]);
+        squares - total * total / (to - from) as f64
+    };
+    let Some(split) = (min_len..=values.len() - min_len).min_by(|a, b| {
+        (error(0, *a) + error(*a, values.len()))
+            .total_cmp(&(error(0, *b) + error(*b, values.len())))
+    }) else {
+        return Vec::new();
+    };
+    let (before, after) = values.split_at(split);
+    if MeanDifference::of(before, after).p_value >= alpha {
+        return Vec::new();
+    }
+    let mut found = changepoints(before, min_len, alpha);
+    found.push(split);
+    found.extend(
+        changepoints(after, min_len, alpha)
+            .into_iter()
+            .map(|idx| split + idx),
+    );
+    found
+}
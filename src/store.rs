@@ -0,0 +1,318 @@
+//! ### Store
+//! A binary, day-indexed copy of the intermediate csvs. Loading one skips
+//! csv and float parsing entirely, and the day index lets a date range be
+//! read without decoding the rest of the file.
+//!
+//! Layout (all integers little-endian):
+//! - 8 byte magic, 1 byte kind, 7 bytes padding
+//! - row count (u64), day count (u64)
+//! - one (date as days since CE: i64, first row: u64) entry per day
+//! - fixed-width rows, in time order
+
+use crate::convert::{parse_timestamp, read_rows, EnergyGenCsvRow, EnergyPriceCsvRow};
+use crate::error::{self, bail, err, EnergyAnalysisError};
+use crate::output::WriteOptions;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike};
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Write};
+use std::ops::Range;
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"EASTORE1";
+const HEADER_LEN: usize = 32;
+const DAY_ENTRY_LEN: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Price = 0,
+    Gen = 1,
+}
+
+impl Kind {
+    /// Bytes per row: timestamps as i64 seconds followed by f64 values.
+    fn row_len(self) -> usize {
+        match self {
+            // local start, lmp_avg
+            Kind::Price => 8 + 8,
+            // utc end, local start, local end, 14 sources
+            Kind::Gen => 3 * 8 + 14 * 8,
+        }
+    }
+}
+
+pub struct Store {
+    kind: Kind,
+    data: Vec<u8>,
+    days: Vec<(NaiveDate, usize)>,
+    row_count: usize,
+    rows_offset: usize,
+}
+
+impl Store {
+    /// Returns whether `path` starts with the store magic rather than csv text.
+    pub fn is_store(path: &Path) -> error::Result<bool> {
+        let mut magic = [0; MAGIC.len()];
+        let read = File::open(path)?.read(&mut magic)?;
+        Ok(read == MAGIC.len() && &magic == MAGIC)
+    }
+
+    pub fn open(path: &Path) -> error::Result<Self> {
+        let data = fs::read(path)?;
+        let corrupt = |reason: &str| EnergyAnalysisError::SchemaMismatch {
+            file: path.to_path_buf(),
+            reason: reason.to_string(),
+        };
+        if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+            return Err(corrupt("Not a binary store"));
+        }
+        let kind = match data[MAGIC.len()] {
+            0 => Kind::Price,
+            1 => Kind::Gen,
+            other => return Err(corrupt(&format!("Unknown store kind {other}"))),
+        };
+        let row_count = read_u64(&data, 16) as usize;
+        let day_count = read_u64(&data, 24) as usize;
+        let rows_offset = HEADER_LEN + day_count * DAY_ENTRY_LEN;
+        if data.len() != rows_offset + row_count * kind.row_len() {
+            return Err(corrupt("Truncated or corrupt"));
+        }
+
+        let days = (0..day_count)
+            .map(|idx| {
+                let offset = HEADER_LEN + idx * DAY_ENTRY_LEN;
+                let days_from_ce = read_u64(&data, offset) as i64;
+                let date = i32::try_from(days_from_ce)
+                    .ok()
+                    .and_then(NaiveDate::from_num_days_from_ce_opt)
+                    .ok_or_else(|| corrupt("Invalid day index"))?;
+                Ok((date, read_u64(&data, offset + 8) as usize))
+            })
+            .collect::<error::Result<_>>()?;
+        Ok(Self {
+            kind,
+            data,
+            days,
+            row_count,
+            rows_offset,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.row_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.row_count == 0
+    }
+
+    /// Row indices of every interval on dates in `[start, end)`.
+    pub fn rows_between(&self, start: NaiveDate, end: NaiveDate) -> Range<usize> {
+        let first_row = |date: NaiveDate| {
+            let day = self.days.partition_point(|(day, _)| *day < date);
+            self.days.get(day).map_or(self.row_count, |(_, row)| *row)
+        };
+        first_row(start)..first_row(end).max(first_row(start))
+    }
+
+    /// Decodes the given rows of a price store. Stores keep only the average
+    /// LMP, so congestion and loss components come back NaN.
+    pub(crate) fn into_price_rows(
+        self,
+        rows: Range<usize>,
+    ) -> error::Result<impl Iterator<Item = error::Result<EnergyPriceCsvRow>>> {
+        self.expect_kind(Kind::Price)?;
+        Ok(rows.map(move |idx| {
+            let row = self.row(idx);
+            let timestamp = read_time(row, 0)?;
+            Ok(EnergyPriceCsvRow::new(
+                format_timestamp(timestamp),
+                timestamp.hour(),
+                timestamp.minute(),
+                read_f64(row, 8),
+            ))
+        }))
+    }
+
+    /// Decodes the given rows of a generation store.
+    pub(crate) fn into_gen_rows(
+        self,
+        rows: Range<usize>,
+    ) -> error::Result<impl Iterator<Item = error::Result<EnergyGenCsvRow>>> {
+        self.expect_kind(Kind::Gen)?;
+        Ok(rows.map(move |idx| {
+            let row = self.row(idx);
+            let start = read_time(row, 8)?;
+            let local_timestamp_start = format_timestamp(start);
+            let sources: [f64; 14] = std::array::from_fn(|src| read_f64(row, 24 + src * 8));
+            Ok(EnergyGenCsvRow {
+                utc_timestamp: format_timestamp(read_time(row, 0)?),
+                local_date: local_timestamp_start[..10].to_string(),
+                local_timestamp_start,
+                local_timestamp_end: format_timestamp(read_time(row, 16)?),
+                hour: start.hour(),
+                minute: start.minute(),
+                total: sources[0],
+                battery: sources[1],
+                biogas: sources[2],
+                biomass: sources[3],
+                coal: sources[4],
+                geothermal: sources[5],
+                imports: sources[6],
+                large_hydro: sources[7],
+                natural_gas: sources[8],
+                nuclear: sources[9],
+                other: sources[10],
+                small_hydro: sources[11],
+                solar: sources[12],
+                wind: sources[13],
+            })
+        }))
+    }
+
+    /// Writes the rows of a parsed price csv to a store at `output`.
+    pub fn write_prices(
+        price_csv: &Path,
+        output: &Path,
+        options: WriteOptions,
+    ) -> error::Result<()> {
+        let mut writer = StoreWriter::new(Kind::Price);
+        for row in read_rows(price_csv)? {
+            let row: EnergyPriceCsvRow = row?;
+            let timestamp = parse_timestamp(&row.timestamp)?;
+            writer.push(timestamp.date(), |buf| {
+                write_time(buf, timestamp);
+                buf.extend(row.lmp_avg.to_le_bytes());
+            })?;
+        }
+        writer.finish(output, options)
+    }
+
+    /// Writes the rows of a parsed gen csv to a store at `output`.
+    pub fn write_gen(gen_csv: &Path, output: &Path, options: WriteOptions) -> error::Result<()> {
+        let mut writer = StoreWriter::new(Kind::Gen);
+        for row in read_rows(gen_csv)? {
+            let row: EnergyGenCsvRow = row?;
+            let start = parse_timestamp(&row.local_timestamp_start)?;
+            let utc_end = parse_timestamp(&row.utc_timestamp)?;
+            let local_end = parse_timestamp(&row.local_timestamp_end)?;
+            writer.push(start.date(), |buf| {
+                write_time(buf, utc_end);
+                write_time(buf, start);
+                write_time(buf, local_end);
+                for source in row.sources() {
+                    buf.extend(source.to_le_bytes());
+                }
+            })?;
+        }
+        writer.finish(output, options)
+    }
+
+    /// Whether the store holds prices rather than generation.
+    pub(crate) fn holds_prices(&self) -> bool {
+        self.kind == Kind::Price
+    }
+
+    fn expect_kind(&self, kind: Kind) -> error::Result<()> {
+        if self.kind != kind {
+            bail!(
+                Invalid,
+                "Expected a {kind:?} store but found a {:?} store",
+                self.kind
+            );
+        }
+        Ok(())
+    }
+
+    fn row(&self, idx: usize) -> &[u8] {
+        let start = self.rows_offset + idx * self.kind.row_len();
+        &self.data[start..start + self.kind.row_len()]
+    }
+}
+
+/// Accumulates rows and the day index in memory, since the index precedes
+/// the rows in the file.
+struct StoreWriter {
+    kind: Kind,
+    rows: Vec<u8>,
+    row_count: usize,
+    days: Vec<(NaiveDate, usize)>,
+}
+
+impl StoreWriter {
+    fn new(kind: Kind) -> Self {
+        Self {
+            kind,
+            rows: Vec::new(),
+            row_count: 0,
+            days: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, date: NaiveDate, encode: impl FnOnce(&mut Vec<u8>)) -> error::Result<()> {
+        match self.days.last() {
+            Some((last, _)) if *last > date => bail!(TimeGap, "Rows are out of order at {date}"),
+            Some((last, _)) if *last == date => (),
+            _ => self.days.push((date, self.row_count)),
+        }
+        let before = self.rows.len();
+        encode(&mut self.rows);
+        debug_assert_eq!(self.rows.len() - before, self.kind.row_len());
+        self.row_count += 1;
+        Ok(())
+    }
+
+    fn finish(self, output: &Path, options: WriteOptions) -> error::Result<()> {
+        let mut out = BufWriter::new(options.create(output)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&[self.kind as u8, 0, 0, 0, 0, 0, 0, 0])?;
+        out.write_all(&(self.row_count as u64).to_le_bytes())?;
+        out.write_all(&(self.days.len() as u64).to_le_bytes())?;
+        for (date, first_row) in &self.days {
+            out.write_all(&i64::from(date.num_days_from_ce()).to_le_bytes())?;
+            out.write_all(&(*first_row as u64).to_le_bytes())?;
+        }
+        out.write_all(&self.rows)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().expect("8 bytes"))
+}
+
+fn read_f64(data: &[u8], offset: usize) -> f64 {
+    f64::from_le_bytes(data[offset..offset + 8].try_into().expect("8 bytes"))
+}
+
+fn read_time(data: &[u8], offset: usize) -> error::Result<NaiveDateTime> {
+    let secs = read_u64(data, offset) as i64;
+    DateTime::from_timestamp(secs, 0)
+        .map(|time| time.naive_utc())
+        .ok_or_else(|| err!(Invalid, "Invalid timestamp {secs} in store"))
+}
+
+fn write_time(buf: &mut Vec<u8>, time: NaiveDateTime) {
+    buf.extend(time.and_utc().timestamp().to_le_bytes());
+}
+
+/// Formats `TIMESTAMP_FMT` by hand; chrono's formatter would dominate load time.
+fn format_timestamp(time: NaiveDateTime) -> String {
+    let mut out = [0u8; 19];
+    let mut put = |start: usize, width: usize, mut val: u32| {
+        for pos in (start..start + width).rev() {
+            out[pos] = b'0' + (val % 10) as u8;
+            val /= 10;
+        }
+    };
+    put(0, 4, time.year() as u32);
+    put(5, 2, time.month());
+    put(8, 2, time.day());
+    put(11, 2, time.hour());
+    put(14, 2, time.minute());
+    put(17, 2, time.second());
+    for (pos, sep) in [(4, b'-'), (7, b'-'), (10, b' '), (13, b':'), (16, b':')] {
+        out[pos] = sep;
+    }
+    String::from_utf8(out.to_vec()).expect("ascii digits")
+}
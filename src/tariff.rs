@@ -0,0 +1,221 @@
+//! ### Tariff
+//! What a household would pay for electricity under example retail rate
+//! plans, given its typical daily load. Real-time plans pass the wholesale
+//! price through to the customer, while flat and time-of-use plans show
+//! what the same load costs on the rates most households actually have.
+
+use crate::compute::Compute;
+use crate::config::TouPeriod;
+use crate::convert::{read_rows, CsvRow};
+use crate::error::{self, bail};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// A retail rate plan. Loaded from a TOML list of plans:
+///
+/// ```toml
+/// [[tariffs]]
+/// name = "TOU 4-9pm"
+/// rate = 0.36              # $/kWh outside every period
+/// monthly_charge = 12.0    # optional
+/// rates = { Peak = 0.49 }  # $/kWh in each named period
+///
+/// [[tariffs.periods]]      # the same fields as a TOU schedule's periods
+/// name = "Peak"
+/// start = "16:00"
+/// end = "21:00"
+///
+/// [[tariffs]]
+/// name = "Real-time"
+/// rate = 0.20
+/// wholesale = true         # adds the wholesale price to every rate
+/// ```
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Tariff {
+    pub name: String,
+    pub rate: f64,
+    #[serde(default)]
+    pub rates: HashMap<String, f64>,
+    #[serde(default)]
+    pub periods: Vec<TouPeriod>,
+    #[serde(default)]
+    pub monthly_charge: f64,
+    #[serde(default)]
+    pub wholesale: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TariffFile {
+    tariffs: Vec<Tariff>,
+}
+
+impl Tariff {
+    pub fn load_all(path: &Path) -> error::Result<Vec<Self>> {
+        let file: TariffFile = toml::from_str(&fs::read_to_string(path)?)?;
+        if file.tariffs.is_empty() {
+            bail!(Invalid, "{path:?} defines no tariffs");
+        }
+        for tariff in &file.tariffs {
+            for period in &tariff.periods {
+                period.validate()?;
+                if !tariff.rates.contains_key(&period.name) {
+                    bail!(
+                        Invalid,
+                        "Tariff {:?} has no rate for {:?}",
+                        tariff.name,
+                        period.name
+                    );
+                }
+            }
+            if let Some(name) = tariff
+                .rates
+                .keys()
+                .find(|name| !tariff.periods.iter().any(|p| &p.name == *name))
+            {
+                bail!(
+                    Invalid,
+                    "Tariff {:?} has a rate for unknown period {name:?}",
+                    tariff.name
+                );
+            }
+        }
+        Ok(file.tariffs)
+    }
+
+    /// $/kWh charged at `time` when the wholesale price is `lmp` $/MWh.
+    pub fn rate(&self, time: NaiveDateTime, lmp: f64) -> f64 {
+        let retail = self
+            .periods
+            .iter()
+            .find(|period| period.contains(time))
+            .map_or(self.rate, |period| self.rates[&period.name]);
+        if self.wholesale {
+            retail + lmp / 1000.
+        } else {
+            retail
+        }
+    }
+}
+
+/// A household's average demand in kW through each five-minute window of
+/// the day, repeated every day. Read from a csv of `time,kw` rows, where
+/// each row's demand holds until the next row's time:
+///
+/// ```csv
+/// time,kw
+/// 00:00,0.4
+/// 07:00,0.9
+/// 17:00,1.6
+/// 22:00,0.6
+/// ```
+#[derive(Debug)]
+pub struct LoadProfile {
+    kw: Vec<f64>,
+}
+
+#[derive(Deserialize)]
+struct LoadRow {
+    time: NaiveTime,
+    kw: f64,
+}
+
+impl CsvRow for LoadRow {}
+
+impl LoadProfile {
+    pub fn load(path: &Path) -> error::Result<Self> {
+        let rows: Vec<LoadRow> = read_rows(path)?.collect::<error::Result<_>>()?;
+        if rows.first().map(|row| row.time) != Some(NaiveTime::MIN) {
+            bail!(Invalid, "{path:?} must start at 00:00");
+        }
+        if rows.windows(2).any(|pair| pair[0].time >= pair[1].time) {
+            bail!(Invalid, "{path:?} must list times in increasing order");
+        }
+        if let Some(row) = rows.iter().find(|row| row.kw < 0.) {
+            bail!(Invalid, "{path:?} has negative demand at {}", row.time);
+        }
+        let kw = (0..Compute::slots_per_day())
+            .map(|idx| {
+                let (hour, minute) = Compute::idx_5min_to_time(idx);
+                let start = NaiveTime::from_hms_opt(hour, minute, 0).expect("valid time");
+                rows.iter()
+                    .rev()
+                    .find(|row| row.time <= start)
+                    .map_or(0., |row| row.kw)
+            })
+            .collect();
+        Ok(Self { kw })
+    }
+
+    pub fn from_kw(kw: Vec<f64>) -> error::Result<Self> {
+        if kw.len() != Compute::slots_per_day() {
+            bail!(
+                Invalid,
+                "A load profile needs {} five-minute values, got {}",
+                Compute::slots_per_day(),
+                kw.len()
+            );
+        }
+        Ok(Self { kw })
+    }
+
+    /// kWh used in the five-minute window starting at `time`.
+    pub fn kwh(&self, time: NaiveDateTime) -> f64 {
+        let idx = Compute::time_to_idx_5min(time.hour(), time.minute());
+        self.kw[idx] * Compute::MINS_INCR as f64 / 60.
+    }
+
+    pub fn daily_kwh(&self) -> f64 {
+        self.kw.iter().sum::<f64>() * Compute::MINS_INCR as f64 / 60.
+    }
+}
+
+/// What the household pays under one tariff over the whole dataset.
+#[derive(Serialize, Debug)]
+pub struct TariffCost {
+    pub tariff: String,
+    pub kwh: f64,
+    pub energy_cost: f64,
+    /// Monthly charges prorated over the days with prices.
+    pub fixed_cost: f64,
+    pub total_cost: f64,
+    pub cents_per_kwh: f64,
+}
+
+/// Bills the household for every interval in `prices` under each tariff.
+pub fn household_costs(
+    prices: &[(NaiveDateTime, f64)],
+    load: &LoadProfile,
+    tariffs: &[Tariff],
+) -> error::Result<Vec<TariffCost>> {
+    let mut days: Vec<NaiveDate> = prices.iter().map(|(time, _)| time.date()).collect();
+    days.dedup();
+    if days.is_empty() {
+        bail!(NoData, "No prices to bill against");
+    }
+    let kwh: f64 = prices.iter().map(|(time, _)| load.kwh(*time)).sum();
+
+    Ok(tariffs
+        .iter()
+        .map(|tariff| {
+            let energy_cost: f64 = prices
+                .iter()
+                .map(|&(time, lmp)| load.kwh(time) * tariff.rate(time, lmp))
+                .sum();
+            let fixed_cost = tariff.monthly_charge * 12. / 365. * days.len() as f64;
+            let total_cost = energy_cost + fixed_cost;
+            TariffCost {
+                tariff: tariff.name.clone(),
+                kwh,
+                energy_cost,
+                fixed_cost,
+                total_cost,
+                cents_per_kwh: 100. * total_cost / kwh,
+            }
+        })
+        .collect())
+}
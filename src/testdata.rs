@@ -0,0 +1,345 @@
+//! ### Testdata
+//! Synthesizes raw CAISO-format price, generation and nodal CSVs with
+//! realistic daily shapes, controlled gaps, and seeded noise. Used by the
+//! end-to-end tests and the hidden `gen-testdata` command for benchmarking.
+
+use crate::compute::Compute;
+use crate::convert::{GEN_SCHEMAS, TIMESTAMP_FMT};
+use crate::error;
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta, Timelike};
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Parameters for a synthetic dataset. The same parameters always produce
+/// the same files.
+#[derive(Clone, Debug)]
+pub struct SyntheticData {
+    pub start: NaiveDate,
+    pub days: usize,
+    pub seed: u64,
+    /// Standard deviation of the noise added to every value, as a fraction
+    /// of that value.
+    pub noise: f64,
+    /// Drops every nth price interval to simulate missing data.
+    pub price_gap_every: Option<usize>,
+    /// Drops every nth generation interval to simulate missing data.
+    pub gen_gap_every: Option<usize>,
+}
+
+/// A small splitmix64 generator, so synthetic data doesn't need a rand dependency.
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Approximately standard normal (sum of uniforms).
+    fn next_normal(&mut self) -> f64 {
+        (0..12).map(|_| self.next_f64()).sum::<f64>() - 6.
+    }
+}
+
+/// One synthetic five-minute interval.
+struct Interval {
+    start: NaiveDateTime,
+    zone_lmps: [f64; 3],
+    /// Sources in `EnergyGenCsvRow::sources` order, excluding Total.
+    sources: [f64; 13],
+}
+
+impl Default for SyntheticData {
+    fn default() -> Self {
+        Self {
+            start: NaiveDate::from_ymd_opt(2024, 1, 1).expect("valid date"),
+            days: 7,
+            seed: 0,
+            noise: 0.05,
+            price_gap_every: None,
+            gen_gap_every: None,
+        }
+    }
+}
+
+impl SyntheticData {
+    const NODAL_HEADER: [&'static str; 16] = [
+        "INTERVALSTARTTIME_GMT",
+        "INTERVALENDTIME_GMT",
+        "OPR_DT",
+        "OPR_HR",
+        "OPR_INTERVAL",
+        "NODE_ID_XML",
+        "NODE_ID",
+        "NODE",
+        "MARKET_RUN_ID",
+        "LMP_TYPE",
+        "XML_DATA_ITEM",
+        "PNODE_RESMRID",
+        "GRP_TYPE",
+        "POS",
+        "VALUE",
+        "GROUP",
+    ];
+
+    const ANCILLARY_HEADER: [&'static str; 11] = [
+        "INTERVALSTARTTIME_GMT",
+        "INTERVALENDTIME_GMT",
+        "OPR_DT",
+        "OPR_HR",
+        "OPR_INTERVAL",
+        "MARKET_RUN_ID",
+        "ANC_TYPE",
+        "ANC_REGION",
+        "XML_DATA_ITEM",
+        "MW",
+        "GROUP",
+    ];
+
+    const PRICE_HEADER: [&'static str; 17] = [
+        "UTC Timestamp (Interval Ending)",
+        "Local Timestamp Pacific Time (Interval Beginning)",
+        "Local Timestamp Pacific Time (Interval Ending)",
+        "Local Date",
+        "Hour Number",
+        "NP-15 LMP",
+        "SP-15 LMP",
+        "ZP-26 LMP",
+        "NP-15 (Congestion)",
+        "SP-15 (Congestion)",
+        "ZP-26 (Congestion)",
+        "NP-15 (Energy)",
+        "SP-15 (Energy)",
+        "ZP-26 (Energy)",
+        "NP-15 (Loss)",
+        "SP-15 (Loss)",
+        "ZP-26 (Loss)",
+    ];
+
+    /// Writes a raw zone price file in the format parse-price-csv expects.
+    pub fn write_price_csv(&self, path: &Path) -> error::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(
+            out,
+            "CAISO 5-Minute Real-Time Locational Marginal Prices ($/megawatt) for Zones"
+        )?;
+        writeln!(
+            out,
+            "5-minute real-time locational marginal prices for CAISO"
+        )?;
+        writeln!(out, "Source: synthetic test data")?;
+        writeln!(out, "{}", Self::PRICE_HEADER.join(","))?;
+
+        for (idx, interval) in self.intervals().enumerate() {
+            if self
+                .price_gap_every
+                .is_some_and(|n| n > 0 && idx % n == n - 1)
+            {
+                continue;
+            }
+            write!(out, "{}", Self::timestamps(interval.start))?;
+            for lmp in interval.zone_lmps {
+                write!(out, ",{lmp:.5}")?;
+            }
+            // Congestion, energy, and loss components.
+            for lmp in interval.zone_lmps {
+                write!(out, ",{:.5}", lmp * 0.01)?;
+            }
+            for lmp in interval.zone_lmps {
+                write!(out, ",{:.5}", lmp * 0.98)?;
+            }
+            for lmp in interval.zone_lmps {
+                write!(out, ",{:.5}", lmp * 0.01)?;
+            }
+            writeln!(out)?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Writes a raw fuel mix file in the format parse-gen-csv expects.
+    pub fn write_gen_csv(&self, path: &Path) -> error::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "CAISO 5-Minute Fuel Mix Generation (megawatt)")?;
+        writeln!(out, "5-minute generation data by fuel for CAISO")?;
+        writeln!(out, "Source: synthetic test data")?;
+        writeln!(out, "{}", GEN_SCHEMAS[0].columns.join(","))?;
+
+        for (idx, interval) in self.intervals().enumerate() {
+            if self
+                .gen_gap_every
+                .is_some_and(|n| n > 0 && idx % n == n - 1)
+            {
+                continue;
+            }
+            let total: f64 = interval.sources.iter().sum();
+            write!(out, "{},{total:.1}", Self::timestamps(interval.start))?;
+            for val in interval.sources {
+                write!(out, ",{val:.1}")?;
+            }
+            writeln!(out)?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Writes a raw OASIS nodal LMP extract in the format parse-nodal-csv
+    /// expects, grouped by node then price component the way OASIS sorts
+    /// them. `NODE_000` is uncongested and each later node's congestion
+    /// component is a dollar higher than the one before it.
+    pub fn write_nodal_csv(&self, path: &Path, nodes: usize) -> error::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{}", Self::NODAL_HEADER.join(","))?;
+        let intervals: Vec<Interval> = self.intervals().collect();
+        for node_idx in 0..nodes {
+            let node = format!("NODE_{node_idx:03}");
+            let congestion = node_idx as f64;
+            for (lmp_type, item) in [
+                ("LMP", "LMP_PRC"),
+                ("MCE", "LMP_ENE_PRC"),
+                ("MCC", "LMP_CONG_PRC"),
+                ("MCL", "LMP_LOSS_PRC"),
+            ] {
+                for interval in &intervals {
+                    let energy = interval.zone_lmps[0];
+                    let loss = energy * 0.01;
+                    let price = match lmp_type {
+                        "LMP" => energy + congestion + loss,
+                        "MCE" => energy,
+                        "MCC" => congestion,
+                        _ => loss,
+                    };
+                    // Pacific Standard Time, ignoring daylight saving.
+                    let utc = interval.start + TimeDelta::hours(8);
+                    let step = TimeDelta::minutes(Compute::MINS_INCR as i64);
+                    writeln!(
+                        out,
+                        "{},{},{},{},{},{node},{node},{node},RTM,{lmp_type},{item},\
+                         {node},ALL,1,{price:.5},1",
+                        utc.format("%Y-%m-%dT%H:%M:%S-00:00"),
+                        (utc + step).format("%Y-%m-%dT%H:%M:%S-00:00"),
+                        interval.start.format("%Y-%m-%d"),
+                        interval.start.hour() + 1,
+                        interval.start.minute() / 5 + 1,
+                    )?;
+                }
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Writes a raw OASIS day-ahead ancillary service price extract in the
+    /// format parse-ancillary-csv expects. In `AS_CAISO_EXP`, regulation up costs
+    /// $10/MW plus a quarter dollar per hour of the day and regulation down
+    /// a flat $6/MW. Spinning reserve is $4/MW, but $16/MW over the evening
+    /// peak from 6 to 10 PM. `AS_SP26_EXP` prices everything double, as a
+    /// decoy.
+    pub fn write_ancillary_csv(&self, path: &Path) -> error::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{}", Self::ANCILLARY_HEADER.join(","))?;
+        let start = self.start.and_hms_opt(0, 0, 0).expect("valid time");
+        for hour in 0..self.days * 24 {
+            let local = start + TimeDelta::hours(hour as i64);
+            // Pacific Standard Time, ignoring daylight saving.
+            let utc = local + TimeDelta::hours(8);
+            for (region, scale) in [("AS_CAISO_EXP", 1.), ("AS_SP26_EXP", 2.)] {
+                for (service, price) in [
+                    ("RU", 10. + local.hour() as f64 / 4.),
+                    ("RD", 6.),
+                    (
+                        "SR",
+                        if (18..22).contains(&local.hour()) {
+                            16.
+                        } else {
+                            4.
+                        },
+                    ),
+                    ("NR", 1.),
+                ] {
+                    writeln!(
+                        out,
+                        "{},{},{},{},1,DAM,{service},{region},{service}_CLR_PRC,{:.5},1",
+                        utc.format("%Y-%m-%dT%H:%M:%S-00:00"),
+                        (utc + TimeDelta::hours(1)).format("%Y-%m-%dT%H:%M:%S-00:00"),
+                        local.format("%Y-%m-%d"),
+                        local.hour() + 1,
+                        price * scale,
+                    )?;
+                }
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// The five timestamp/date columns shared by both raw formats.
+    fn timestamps(start: NaiveDateTime) -> String {
+        let step = TimeDelta::minutes(Compute::MINS_INCR as i64);
+        let end = start + step;
+        // Pacific Standard Time, ignoring daylight saving.
+        let utc_end = end + TimeDelta::hours(8);
+        format!(
+            "{},{},{},{},{}",
+            utc_end.format(TIMESTAMP_FMT),
+            start.format(TIMESTAMP_FMT),
+            end.format(TIMESTAMP_FMT),
+            start.format("%Y-%m-%d"),
+            start.hour() + 1,
+        )
+    }
+
+    fn intervals(&self) -> impl Iterator<Item = Interval> + '_ {
+        let mut rng = SplitMix(self.seed);
+        let start = self.start.and_hms_opt(0, 0, 0).expect("valid time");
+        let step = TimeDelta::minutes(Compute::MINS_INCR as i64);
+        let count = self.days * Compute::slots_per_day();
+
+        (0..count).map(move |idx| {
+            let start = start + step * idx as i32;
+            let hour = start.hour() as f64 + start.minute() as f64 / 60.;
+            let mut noisy = |val: f64| val * (1. + self.noise * rng.next_normal());
+
+            // Solar follows a sine from 6am to 6pm; batteries charge while it
+            // shines and discharge into the evening peak.
+            let sun = (PI * (hour - 6.) / 12.).sin().max(0.);
+            let evening = (-(hour - 19.).powi(2) / 4.).exp();
+            let solar = noisy(15000. * sun);
+            let battery = noisy(4000. * evening) - 3000. * sun;
+            let gas = noisy(8000. - 5000. * sun + 4000. * evening);
+            let sources = [
+                battery,
+                noisy(200.),
+                noisy(300.),
+                0.,
+                noisy(800.),
+                noisy(5000. - 2000. * sun),
+                noisy(3000. + 1000. * evening),
+                gas,
+                noisy(2200.),
+                0.,
+                noisy(250.),
+                solar,
+                noisy(3000. - 1000. * sun),
+            ];
+
+            let base = 40. - 35. * sun + 60. * evening;
+            let zone_lmps = [noisy(base), noisy(base * 1.05), noisy(base * 0.95)];
+            Interval {
+                start,
+                zone_lmps,
+                sources,
+            }
+        })
+    }
+}
@@ -0,0 +1,57 @@
+//! ### Watch
+//! Notices when any of a set of files changes, so a chart can be re-rendered
+//! while its data or config is being edited.
+
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often watched files are checked for changes.
+pub const POLL: Duration = Duration::from_millis(500);
+
+/// Tracks the modification times of a set of files.
+#[derive(Debug)]
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    seen: Vec<Option<SystemTime>>,
+}
+
+impl Watcher {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let seen = Self::modified(&paths);
+        Self { paths, seen }
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Whether any file was modified, created, or removed since the last
+    /// check.
+    pub fn changed(&mut self) -> bool {
+        let now = Self::modified(&self.paths);
+        let changed = now != self.seen;
+        self.seen = now;
+        changed
+    }
+
+    /// Blocks until a file changes. Editors often save in several writes, so
+    /// this waits for the files to settle for one poll before returning.
+    pub fn wait(&mut self) {
+        while !self.changed() {
+            thread::sleep(POLL);
+        }
+        thread::sleep(POLL);
+        while self.changed() {
+            thread::sleep(POLL);
+        }
+    }
+
+    fn modified(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+        paths
+            .iter()
+            .map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+            .collect()
+    }
+}
@@ -0,0 +1,133 @@
+//! ### Weather
+//! How the grid responds to heat: each day's peak price and evening ramp
+//! against its high temperature, read from a user-supplied weather series
+//! (see `series`). Air conditioning load peaks in the late afternoon just
+//! as solar fades, so hot days should make for steeper ramps and pricier
+//! evenings.
+
+use crate::compute::Compute;
+use crate::error::{self, bail};
+use crate::frame::Frame;
+use crate::stats::{Correlation, LinearFit};
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+
+/// The hours a day's net load is at its solar-driven low.
+pub const MIDDAY_HOURS: Range<u32> = 10..16;
+/// The hours net load climbs to its peak as solar sets.
+pub const EVENING_HOURS: Range<u32> = 16..22;
+
+/// One day's high temperature alongside how the grid fared.
+#[derive(Serialize, Debug)]
+pub struct WeatherDay {
+    pub date: NaiveDate,
+    pub max_temp: f64,
+    pub peak_price: f64,
+    /// How far net load (Total less wind and solar) rose from its lowest
+    /// in `MIDDAY_HOURS` to its highest in `EVENING_HOURS`, in MW.
+    pub evening_ramp_mw: f64,
+}
+
+/// Regressions of each day's peak price and evening ramp on its high
+/// temperature.
+#[derive(Clone, Copy, Debug)]
+pub struct WeatherFits {
+    pub peak_price: LinearFit,
+    pub evening_ramp: LinearFit,
+}
+
+impl WeatherFits {
+    pub fn new(days: &[WeatherDay]) -> Self {
+        let (mut price, mut ramp) = (Correlation::default(), Correlation::default());
+        for day in days {
+            price.push(day.max_temp, day.peak_price);
+            ramp.push(day.max_temp, day.evening_ramp_mw);
+        }
+        Self {
+            peak_price: price.fit(),
+            evening_ramp: ramp.fit(),
+        }
+    }
+}
+
+/// The days with a high temperature in `weather`'s `temp_column`, prices,
+/// and generation through both the midday and evening hours, in order.
+pub fn weather_days(
+    prices: &Compute,
+    gen_csv: &Path,
+    weather: &Frame,
+    temp_column: &str,
+) -> error::Result<Vec<WeatherDay>> {
+    let temps = daily_max(weather.times(), weather.column(temp_column)?);
+    let prices = prices.select(&["Price"])?;
+    let peak_prices: HashMap<NaiveDate, f64> = daily_max(prices.times(), prices.column("Price")?)
+        .into_iter()
+        .collect();
+    let ramps: HashMap<NaiveDate, f64> = evening_ramps(gen_csv)?.into_iter().collect();
+
+    let days: Vec<WeatherDay> = temps
+        .into_iter()
+        .filter_map(|(date, max_temp)| {
+            Some(WeatherDay {
+                date,
+                max_temp,
+                peak_price: *peak_prices.get(&date)?,
+                evening_ramp_mw: *ramps.get(&date)?,
+            })
+        })
+        .collect();
+    if days.is_empty() {
+        bail!(
+            NoData,
+            "No day has a high temperature, prices, and generation through the evening"
+        );
+    }
+    Ok(days)
+}
+
+/// Each day's rise in net load from its midday low to its evening high.
+/// Days missing either window are left out.
+fn evening_ramps(gen_csv: &Path) -> error::Result<Vec<(NaiveDate, f64)>> {
+    let gen = Compute::new(gen_csv).select(&["Total", "Wind", "Solar"])?;
+    let (total, wind, solar) = (
+        gen.column("Total")?,
+        gen.column("Wind")?,
+        gen.column("Solar")?,
+    );
+    let mut days: Vec<(NaiveDate, f64, f64)> = Vec::new();
+    for (row, time) in gen.times().iter().enumerate() {
+        if days.last().is_none_or(|(date, _, _)| *date != time.date()) {
+            days.push((time.date(), f64::INFINITY, f64::NEG_INFINITY));
+        }
+        let (_, low, high) = days.last_mut().expect("just pushed");
+        let net_load = total[row] - wind[row] - solar[row];
+        if MIDDAY_HOURS.contains(&time.hour()) {
+            *low = low.min(net_load);
+        } else if EVENING_HOURS.contains(&time.hour()) {
+            *high = high.max(net_load);
+        }
+    }
+    Ok(days
+        .into_iter()
+        .filter(|(_, low, high)| low.is_finite() && high.is_finite())
+        .map(|(date, low, high)| (date, high - low))
+        .collect())
+}
+
+/// Each day's largest value, skipping NaN. `times` should be in order.
+fn daily_max(times: &[NaiveDateTime], values: &[f64]) -> Vec<(NaiveDate, f64)> {
+    let mut days: Vec<(NaiveDate, f64)> = Vec::new();
+    for (time, &value) in times.iter().zip(values) {
+        if value.is_nan() {
+            continue;
+        }
+        match days.last_mut() {
+            Some((date, max)) if *date == time.date() => *max = max.max(value),
+            _ => days.push((time.date(), value)),
+        }
+    }
+    days
+}
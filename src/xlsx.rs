@@ -0,0 +1,98 @@
+//! ### Xlsx
+//! Copies result csvs into an Excel workbook, one sheet per table, each
+//! with a native chart of its numeric columns. Plenty of the people reading
+//! these results only ever open them in Excel.
+
+use crate::error::{self, bail};
+use rust_xlsxwriter::{Chart, ChartType, Workbook};
+use std::path::Path;
+
+use crate::output::WriteOptions;
+
+/// Tables longer than this are charted as lines instead of columns.
+const MAX_COLUMN_CHART_ROWS: usize = 31;
+
+/// Writes each `(name, csv)` table to its own sheet of the workbook at
+/// `output`. Fields that parse as numbers are stored as numbers.
+pub fn write_tables(
+    output: &Path,
+    tables: &[(&str, &Path)],
+    options: WriteOptions,
+) -> error::Result<()> {
+    let mut workbook = Workbook::new();
+    for (name, csv) in tables {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_path(csv)?;
+        let rows: Vec<csv::StringRecord> = reader.records().collect::<Result<_, _>>()?;
+        let Some((header, body)) = rows.split_first() else {
+            bail!(NoData, "{csv:?} has no header");
+        };
+
+        let sheet_name = sheet_name(name);
+        let sheet = workbook.add_worksheet().set_name(&sheet_name)?;
+        for (col, field) in header.iter().enumerate() {
+            sheet.write(0, col as u16, field)?;
+        }
+        for (row, record) in body.iter().enumerate() {
+            for (col, field) in record.iter().enumerate() {
+                let (row, col) = (row as u32 + 1, col as u16);
+                match field.parse::<f64>() {
+                    Ok(num) => sheet.write(row, col, num)?,
+                    Err(_) => sheet.write(row, col, field)?,
+                };
+            }
+        }
+        sheet.set_freeze_panes(1, 0)?;
+        sheet.autofit();
+
+        if let Some(chart) = chart(&sheet_name, header, body) {
+            sheet.insert_chart(1, header.len() as u16 + 1, &chart)?;
+        }
+    }
+    workbook.save_to_writer(options.create(output)?)?;
+    Ok(())
+}
+
+/// Charts every numeric column as a series. A leading text column, like
+/// a source name or date, labels the x axis.
+fn chart(sheet: &str, header: &csv::StringRecord, body: &[csv::StringRecord]) -> Option<Chart> {
+    let numeric = |col: usize| {
+        body.iter()
+            .all(|record| record.get(col).is_some_and(|f| f.parse::<f64>().is_ok()))
+    };
+    let labeled = !numeric(0);
+    let series: Vec<usize> = (usize::from(labeled)..header.len())
+        .filter(|&col| numeric(col))
+        .collect();
+    if body.is_empty() || series.is_empty() {
+        return None;
+    }
+
+    let mut chart = Chart::new(if body.len() > MAX_COLUMN_CHART_ROWS {
+        ChartType::Line
+    } else {
+        ChartType::Column
+    });
+    chart.title().set_name(sheet);
+    let last_row = body.len() as u32;
+    for col in series {
+        let col = col as u16;
+        let added = chart
+            .add_series()
+            .set_name((sheet, 0, col))
+            .set_values((sheet, 1, col, last_row, col));
+        if labeled {
+            added.set_categories((sheet, 1, 0, last_row, 0));
+        }
+    }
+    Some(chart)
+}
+
+/// Excel sheet names are at most 31 characters and can't contain `[]:*?/\`.
+fn sheet_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+        .take(31)
+        .collect()
+}
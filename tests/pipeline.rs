@@ -0,0 +1,5135 @@
+//! End-to-end tests that run synthetic raw CAISO files through
+//! parse → compute → write.
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike};
+use energy_analysis::{
+    ancillary::{self, AncillaryPrices},
+    astro::Site,
+    compute::{
+        self, group_by, group_by_index, histogram, par_group_by_index, rolling_mean, Agg, Aggs,
+        Compute, CorrelationReducer, DayPolicy, FewSamples, Mean, PeakTiming, Period, PriceNode,
+        Profile, ProfileAgg, Reducer, SampleCheck, SpikeThreshold,
+    },
+    config::{
+        InstalledCapacity, Labels, Rto, Settings, TimeMark, TouSchedule, WaterYears, WriteOptions,
+    },
+    convert::{self, CsvFormat, EnergyGenCsvRow, EnergyPriceCsvRow, OnError, RawKind, TotalCheck},
+    dataset::Dataset,
+    error::EnergyAnalysisError,
+    fetch::{self, eia::Hourly, oasis},
+    forecast::{Baseline, Forecast, ForecastError},
+    frame::Frame,
+    gas::{self, GasPrices},
+    graph::{
+        Bitmap, ChartOptions, Graphing, ImageFormat, LegendPosition, Marker, PowerUnit,
+        ReferenceLine, SourceOrder, Theme, YScale,
+    },
+    join::{JoinMode, JoinStats, TimeAlignedJoin},
+    manifest::Manifest,
+    monitor::{Alert, AlertKind, Hook, Monitor, Thresholds},
+    nodal::{self, NodeIndex},
+    pipeline::Pipeline,
+    provenance::{self, Provenance},
+    report::{Report, ReportFormat},
+    scenario::{
+        carbon_intensity, BatteryScenario, ChargingObjective, ChargingScenario, Dispatch,
+        HourPrices, ScenarioWindow, SourceScaling,
+    },
+    series::{self, SeriesLayout},
+    serve::Service,
+    stats::{changepoints, Clip, ClipBounds, Correlation, MeanDifference},
+    store::Store,
+    tariff::{self, LoadProfile, Tariff},
+    testdata::SyntheticData,
+    weather::{self, WeatherFits},
+    xlsx,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// A scratch directory holding one synthetic dataset, parsed and ready for compute.
+struct Fixture {
+    dir: PathBuf,
+}
+
+impl Fixture {
+    fn new(name: &str, data: &SyntheticData) -> Self {
+        let dir =
+            std::env::temp_dir().join(format!("energy_analysis_{name}_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let fixture = Self { dir };
+        data.write_price_csv(&fixture.path("price_raw.csv"))
+            .unwrap();
+        data.write_gen_csv(&fixture.path("gen_raw.csv")).unwrap();
+        convert::convert_energy_price_csv(
+            &[fixture.path("price_raw.csv")],
+            &fixture.prices(),
+            CsvFormat::default(),
+            OnError::Abort,
+            WriteOptions::default(),
+        )
+        .unwrap();
+        convert::convert_energy_gen_csv(
+            &[fixture.path("gen_raw.csv")],
+            &fixture.gen(),
+            CsvFormat::default(),
+            TotalCheck::Report,
+            OnError::Abort,
+            WriteOptions::default(),
+        )
+        .unwrap();
+        fixture
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.dir.join(file)
+    }
+
+    fn prices(&self) -> PathBuf {
+        self.path("prices.csv")
+    }
+
+    fn gen(&self) -> PathBuf {
+        self.path("gen.csv")
+    }
+
+    /// Runs the command line in the fixture's directory, so relative paths
+    /// like prices.csv resolve inside it, and fails the test if it fails.
+    /// Returns what it printed.
+    fn cli(&self, args: &[&str]) -> String {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_energy_analysis"))
+            .current_dir(&self.dir)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    /// Runs the command line like `cli`, but expects it to fail, and
+    /// returns what it printed to stderr.
+    fn cli_err(&self, args: &[&str]) -> String {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_energy_analysis"))
+            .current_dir(&self.dir)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(!output.status.success(), "{args:?} succeeded");
+        String::from_utf8(output.stderr).unwrap()
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn line_count(path: &Path) -> usize {
+    fs::read_to_string(path).unwrap().lines().count()
+}
+
+/// Compares a chart with its png snapshot in tests/snapshots, allowing for
+/// the small differences in how text renders from one machine to another.
+/// A missing snapshot is written instead, as is every one when
+/// UPDATE_SNAPSHOTS is set.
+fn assert_snapshot(name: &str, bitmap: &Bitmap) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.png"));
+    let save = |path: &Path| {
+        image::RgbImage::from_raw(bitmap.width, bitmap.height, bitmap.pixels.clone())
+            .unwrap()
+            .save(path)
+            .unwrap()
+    };
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() || !path.exists() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        save(&path);
+        return;
+    }
+    let expected = image::open(&path).unwrap().to_rgb8();
+    let expected = Bitmap {
+        width: expected.width(),
+        height: expected.height(),
+        pixels: expected.into_raw(),
+    };
+    let difference = bitmap.difference(&expected, 16);
+    if difference > 0.005 {
+        let actual = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.png"));
+        save(&actual);
+        panic!(
+            "{name} differs from its snapshot in {:.2}% of pixels. It was drawn to {actual:?}. \
+             Rerun with UPDATE_SNAPSHOTS=1 if the change is intended",
+            difference * 100.
+        );
+    }
+}
+
+#[test]
+fn parse_keeps_every_interval() {
+    let data = SyntheticData::default();
+    let fixture = Fixture::new("parse", &data);
+    let intervals = data.days * Compute::slots_per_day();
+    assert_eq!(line_count(&fixture.prices()), intervals + 1);
+    assert_eq!(line_count(&fixture.gen()), intervals + 1);
+}
+
+#[test]
+fn parse_keeps_congestion_and_loss() {
+    let fixture = Fixture::new("components", &SyntheticData::default());
+    let price_csv = fixture.prices();
+    let compute = Compute::new(&price_csv);
+    let prices = compute.average_price_5min().unwrap();
+    let components = compute.price_components(Profile::Day).unwrap();
+    assert_eq!(components.congestion.len(), Compute::slots_per_day());
+    // The synthetic zones' components are each 1% of their LMP.
+    for (slot, price) in prices.iter().enumerate() {
+        for parts in [components.congestion[slot], components.loss[slot]] {
+            let mean = parts.iter().sum::<f64>() / 3.;
+            assert!((mean - 0.01 * price).abs() < 1e-3, "{mean} vs {price}");
+        }
+    }
+
+    let hourly = compute.downsample_prices(60, &Aggs::default()).unwrap();
+    assert!((hourly[0].np15_loss - 0.01 * hourly[0].lmp_avg).abs() < 0.1);
+
+    let store = fixture.path("prices.store");
+    Store::write_prices(&price_csv, &store, WriteOptions::default()).unwrap();
+    assert!(Compute::new(&store).price_components(Profile::Day).is_err());
+}
+
+#[test]
+fn parses_trading_hubs_beside_zones() {
+    let fixture = Fixture::new("hubs", &SyntheticData::default());
+    let raw = fs::read_to_string(fixture.path("price_raw.csv")).unwrap();
+    let hubs = ["NP15", "SP15", "ZP26"]
+        .iter()
+        .zip(EnergyPriceCsvRow::ZONES)
+        .fold(raw, |raw, (hub, zone)| {
+            raw.replace(&format!("{zone} "), &format!("TH_{hub}_GEN-APND "))
+        });
+    fs::write(fixture.path("hub_raw.csv"), hubs).unwrap();
+    let hub_csv = fixture.path("hubs.csv");
+    convert::convert_energy_price_csv(
+        &[fixture.path("hub_raw.csv")],
+        &hub_csv,
+        CsvFormat::default(),
+        OnError::Abort,
+        WriteOptions::default(),
+    )
+    .unwrap();
+
+    let price_csv = fixture.prices();
+    let zone = Compute::new(&price_csv).area_prices(Profile::Day).unwrap();
+    let hub = Compute::new(&hub_csv).area_prices(Profile::Day).unwrap();
+    assert_eq!(zone.prices, hub.prices);
+    let average = Compute::new(&hub_csv).average_price_5min().unwrap();
+    for (areas, price) in hub.prices.iter().zip(&average) {
+        assert!((areas.iter().sum::<f64>() / 3. - price).abs() < 1e-6);
+    }
+    let row: EnergyPriceCsvRow = convert::read_rows(&hub_csv)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(row.lmp(PriceNode::Sp15), row.sp15_lmp);
+    assert!(!row.lmp(PriceNode::Zp26).is_nan());
+
+    // Each Compute reads its own node, so two can read one file side by side.
+    let (sp15, average) = std::thread::scope(|scope| {
+        let sp15 = scope.spawn(|| {
+            Compute::new(&hub_csv)
+                .with_price_node(PriceNode::Sp15)
+                .price_series()
+                .unwrap()
+        });
+        let average = scope.spawn(|| Compute::new(&hub_csv).price_series().unwrap());
+        (sp15.join().unwrap(), average.join().unwrap())
+    });
+    assert_eq!(sp15[0].1, row.sp15_lmp);
+    assert_eq!(average[0].1, row.lmp_avg);
+    assert_ne!(sp15, average);
+
+    let out = fixture.path("hub_zone.csv");
+    convert::write_hub_zone(&out, &zone, &hub, Profile::Day, WriteOptions::default()).unwrap();
+    assert_eq!(line_count(&out), Compute::slots_per_day() + 1);
+
+    // Zone and hub prices can't share a csv.
+    let mixed = convert::convert_energy_price_csv(
+        &[fixture.path("price_raw.csv"), fixture.path("hub_raw.csv")],
+        &fixture.path("mixed.csv"),
+        CsvFormat::default(),
+        OnError::Abort,
+        WriteOptions::default(),
+    );
+    assert!(mixed.is_err());
+}
+
+#[test]
+fn nodal_extracts_rank_congestion_and_index_nodes() {
+    let data = SyntheticData {
+        days: 2,
+        ..SyntheticData::default()
+    };
+    let fixture = Fixture::new("nodal", &data);
+    let raw = fixture.path("nodal_raw.csv");
+    data.write_nodal_csv(&raw, 12).unwrap();
+    let nodal_csv = fixture.path("nodal.csv");
+    let index = nodal::convert_nodal_csv(&[&raw], &nodal_csv, WriteOptions::default()).unwrap();
+    assert_eq!(index.nodes().count(), 12);
+    // Two days of four components.
+    assert_eq!(index.rows("NODE_003"), 4 * 2 * Compute::slots_per_day());
+
+    let top = nodal::top_congested(&nodal_csv, 3).unwrap();
+    let names: Vec<&str> = top.iter().map(|node| node.node.as_str()).collect();
+    assert_eq!(names, ["NODE_011", "NODE_010", "NODE_009"]);
+    assert!((top[0].avg_congestion - 11.).abs() < 1e-9);
+    assert!(nodal::top_congested(&nodal_csv, 0).is_err());
+
+    let index = NodeIndex::open(&nodal_csv).unwrap();
+    let rows = index.read(&nodal_csv, "NODE_007").unwrap();
+    assert!(rows.iter().all(|row| row.node == "NODE_007"));
+    let quiet = nodal::node_profile(&nodal_csv, &index, "NODE_000", Profile::Day).unwrap();
+    let busy = nodal::node_profile(&nodal_csv, &index, "NODE_007", Profile::Day).unwrap();
+    assert_eq!(busy.prices.len(), Compute::slots_per_day());
+    for (quiet, busy) in quiet.prices.iter().zip(&busy.prices) {
+        assert!((busy - quiet - 7.).abs() < 1e-6, "{busy} vs {quiet}");
+    }
+    assert!(nodal::node_profile(&nodal_csv, &index, "NODE_999", Profile::Day).is_err());
+}
+
+#[test]
+fn ancillary_prices_feed_co_optimized_storage_revenue() {
+    let data = SyntheticData::default();
+    let fixture = Fixture::new("ancillary", &data);
+    let raw = fixture.path("ancillary_raw.csv");
+    data.write_ancillary_csv(&raw).unwrap();
+    let ancillary_csv = fixture.path("ancillary.csv");
+    let hours = ancillary::convert_ancillary_csv(
+        &[&raw],
+        &ancillary_csv,
+        ancillary::DEFAULT_REGION,
+        WriteOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(hours, data.days * 24);
+    assert!(ancillary::convert_ancillary_csv(
+        &[&raw],
+        &ancillary_csv,
+        "NOPE",
+        WriteOptions::default()
+    )
+    .is_err());
+
+    let prices = AncillaryPrices::load(&ancillary_csv).unwrap();
+    let time = NaiveDate::from_ymd_opt(2024, 1, 1)
+        .unwrap()
+        .and_hms_opt(5, 30, 0)
+        .unwrap();
+    assert_eq!(prices.at(time), Some([11.25, 6., 4., 1.]));
+
+    let battery = BatteryScenario::with_capacity(400.);
+    let revenue =
+        ancillary::storage_revenue(&Compute::new(&fixture.prices()), &prices, battery).unwrap();
+    assert_eq!((revenue.days, revenue.skipped_hours), (data.days, 0));
+    let [(_, arbitrage), (_, regulation), (_, both)] = revenue.revenues.as_slice() else {
+        panic!(
+            "expected a revenue per dispatch, got {:?}",
+            revenue.revenues
+        );
+    };
+    assert_eq!(revenue.revenues[2].0, Dispatch::CoOptimized);
+    assert_eq!(arbitrage.reg_up + arbitrage.reg_down, 0.);
+    assert_eq!(regulation.arbitrage, 0.);
+    // Every hour of every day holds 100 MW of regulation down at $6.
+    assert!((regulation.reg_down - 100. * 6. * 24. * data.days as f64).abs() < 1e-6);
+    assert!(both.total() >= arbitrage.total().max(regulation.total()));
+}
+
+#[test]
+fn solar_batteries_charge_before_they_discharge() {
+    let battery = BatteryScenario::with_capacity(100.);
+    let half = Compute::slots_per_day() / 2;
+    let day = |first: (f64, f64), second: (f64, f64)| {
+        let mut day = vec![first; half];
+        day.extend(vec![second; half]);
+        day
+    };
+    // Cheap solar at midday, then an expensive evening: one full cycle.
+    let evening_peak = day((10., 1000.), (200., 0.));
+    let full_cycle = 100. * (0.85 * 200. - 10.);
+    assert!((battery.daily_arbitrage(&evening_peak) - full_cycle).abs() < 1e-6);
+    // An expensive morning comes before there's anything stored to sell.
+    let morning_peak = day((200., 0.), (10., 1000.));
+    assert_eq!(battery.daily_arbitrage(&morning_peak), 0.);
+}
+
+#[test]
+fn grid_batteries_charge_before_they_discharge() {
+    // 100 MW for four hours.
+    let battery = BatteryScenario::with_capacity(400.);
+    let hour = |energy| HourPrices {
+        energy,
+        reg_up: 0.,
+        reg_down: 0.,
+        spin: 0.,
+    };
+    // Dear mornings and cheap evenings can't be paired: what's sold in the
+    // morning hasn't been bought yet.
+    let mut day: Vec<HourPrices> = (0..24)
+        .map(|h| hour(if h < 12 { 200. } else { 10. }))
+        .collect();
+    assert_eq!(
+        battery.daily_revenue(&day, Dispatch::Arbitrage).arbitrage,
+        0.
+    );
+
+    // A cheap hour at 13:00 and a dear one at 18:00 make one profitable
+    // hour of cycling, and the rest of the evening is too flat to use.
+    day[13] = hour(5.);
+    day[18] = hour(100.);
+    let revenue = battery.daily_revenue(&day, Dispatch::Arbitrage);
+    assert!((revenue.arbitrage - 100. * (0.85 * 100. - 5.)).abs() < 1e-6);
+
+    // Held back for regulation at $50 an hour both ways, every hour earns
+    // more than that cycle, so co-optimized doesn't cycle.
+    let held: Vec<HourPrices> = day
+        .iter()
+        .map(|h| HourPrices {
+            reg_up: 50.,
+            reg_down: 50.,
+            ..*h
+        })
+        .collect();
+    let both = battery.daily_revenue(&held, Dispatch::CoOptimized);
+    assert_eq!(both.arbitrage, 0.);
+    assert!((both.total() - 24. * 100. * 100.).abs() < 1e-6);
+}
+
+#[test]
+fn value_stack_splits_revenue_by_month() {
+    let data = SyntheticData {
+        days: 40,
+        ..SyntheticData::default()
+    };
+    let fixture = Fixture::new("value_stack", &data);
+    let raw = fixture.path("ancillary_raw.csv");
+    data.write_ancillary_csv(&raw).unwrap();
+    let ancillary_csv = fixture.path("ancillary.csv");
+    ancillary::convert_ancillary_csv(
+        &[&raw],
+        &ancillary_csv,
+        ancillary::DEFAULT_REGION,
+        WriteOptions::default(),
+    )
+    .unwrap();
+    let prices = AncillaryPrices::load(&ancillary_csv).unwrap();
+    let battery = BatteryScenario::with_capacity(400.);
+
+    let stack = ancillary::value_stack(
+        &Compute::new(&fixture.prices()),
+        &prices,
+        battery,
+        Dispatch::CoOptimized,
+    )
+    .unwrap();
+    let months: Vec<(&str, usize)> = stack
+        .months
+        .iter()
+        .map(|month| (month.month.as_str(), month.days))
+        .collect();
+    assert_eq!(months, [("2024-01", 31), ("2024-02", 9)]);
+    // Spinning reserve outbids regulation up over the evening peak.
+    assert!(stack.months.iter().all(|month| month.revenue.spin > 0.));
+
+    let revenue =
+        ancillary::storage_revenue(&Compute::new(&fixture.prices()), &prices, battery).unwrap();
+    let stacked: f64 = stack.months.iter().map(|month| month.revenue.total()).sum();
+    assert!((stacked - revenue.revenues[2].1.total()).abs() < 1e-6);
+    let arbitrage_only = ancillary::value_stack(
+        &Compute::new(&fixture.prices()),
+        &prices,
+        battery,
+        Dispatch::Arbitrage,
+    )
+    .unwrap();
+    assert!(arbitrage_only
+        .months
+        .iter()
+        .all(|month| month.revenue.total() == month.revenue.arbitrage));
+}
+
+#[test]
+fn averages_follow_the_daily_shape() {
+    let fixture = Fixture::new("averages", &SyntheticData::default());
+
+    let prices = Compute::new(&fixture.prices())
+        .average_price_5min()
+        .unwrap();
+    assert_eq!(prices.len(), Compute::slots_per_day());
+    let slot = |hour: usize| hour * 60 / Compute::MINS_INCR;
+    assert!(prices[slot(12)] < prices[slot(3)]);
+    assert!(prices[slot(19)] > prices[slot(3)]);
+
+    let gen = Compute::new(&fixture.gen()).average_gen_5min().unwrap();
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+    assert!(gen[slot(12)][solar] > 10000.);
+    assert_eq!(gen[slot(0)][solar], 0.);
+    for sources in &gen {
+        let sum: f64 = sources[1..].iter().sum();
+        assert!(
+            (sources[0] - sum).abs() < 1.,
+            "total {} != {sum}",
+            sources[0]
+        );
+    }
+
+    let profiled = Compute::new(&fixture.prices())
+        .sampled_price_profile(Profile::Day)
+        .unwrap();
+    assert!(profiled.samples.iter().all(|count| *count == 7));
+    let out = fixture.path("price_averages.csv");
+    convert::write_energy_price_averages(&out, &prices, &profiled.samples, WriteOptions::default())
+        .unwrap();
+    assert_eq!(line_count(&out), Compute::slots_per_day() + 1);
+}
+
+#[test]
+fn frames_select_columns_from_either_dataset() {
+    let fixture = Fixture::new("columns", &SyntheticData::default());
+    let (prices, gen) = (fixture.prices(), fixture.gen());
+
+    let price_frame = Compute::new(&prices).frame().unwrap();
+    assert_eq!(price_frame.names(), ["Price"]);
+    assert_eq!(price_frame.len(), 7 * Compute::slots_per_day());
+
+    let picked = Compute::new(&gen).select(&["wind", "Solar"]).unwrap();
+    assert_eq!(picked.names(), ["Wind", "Solar"]);
+    assert!(Compute::new(&gen).select(&["Tidal"]).is_err());
+
+    // A selection averages the same as those columns of the full profile.
+    let hourly = picked.windowed(60).unwrap();
+    let full = Compute::new(&gen).average_gen_5min().unwrap();
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+    let noon: f64 = full[144..156].iter().map(|slot| slot[solar]).sum::<f64>() / 12.;
+    assert!((hourly[12][1] - noon).abs() < 1e-6);
+
+    let days = picked.daily();
+    assert_eq!(days.len(), 7);
+    assert_eq!(days[0].0, NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+}
+
+#[test]
+fn weekly_profile_buckets_by_hour_of_week() {
+    let fixture = Fixture::new("weekly", &SyntheticData::default());
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+
+    let week = Compute::new(&fixture.gen())
+        .average_gen_profile(Profile::Week)
+        .unwrap();
+    assert_eq!(week.len(), 168);
+    assert_eq!(Profile::Week.label(24 * 4 + 13), "Fri 13:00");
+    // Synthetic data repeats the same day, so every weekday's noon matches.
+    for day in 0..7 {
+        assert!(week[day * 24 + 12][solar] > 10000.);
+        assert_eq!(week[day * 24][solar], 0.);
+    }
+}
+
+#[test]
+fn partial_edge_days_are_found_and_trimmed() {
+    // Noon on Feb 28 through the morning of Mar 1, across a leap day.
+    let start = NaiveDate::from_ymd_opt(2024, 2, 28)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap();
+    let times: Vec<NaiveDateTime> = (0..2 * Compute::slots_per_day())
+        .map(|idx| start + TimeDelta::minutes(5 * idx as i64))
+        .collect();
+    let frame = Frame::new(times)
+        .with_column("Price", vec![1.; 2 * Compute::slots_per_day()])
+        .unwrap();
+
+    let half = Compute::slots_per_day() / 2;
+    assert_eq!(
+        frame.partial_days(),
+        vec![
+            (NaiveDate::from_ymd_opt(2024, 2, 28).unwrap(), half),
+            (NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(), half),
+        ]
+    );
+    // Only Feb 29 is whole, so it's the one day behind every slot.
+    assert!(frame
+        .full_days_per_slot(Profile::Day)
+        .iter()
+        .all(|days| *days == 1));
+
+    let trim = DayPolicy {
+        trim_partial: true,
+        ..Default::default()
+    };
+    assert_eq!(trim.apply(frame.clone()).len(), Compute::slots_per_day());
+    let leap = DayPolicy {
+        skip_leap_days: true,
+        ..Default::default()
+    };
+    assert_eq!(leap.apply(frame.clone()).len(), 2 * half);
+    assert!(DayPolicy {
+        skip_leap_days: true,
+        ..trim
+    }
+    .apply(frame)
+    .is_empty());
+}
+
+#[test]
+fn robust_aggs_and_clipping_ignore_a_spike() {
+    let start = NaiveDate::from_ymd_opt(2024, 1, 1)
+        .unwrap()
+        .and_time(NaiveTime::MIN);
+    let times: Vec<NaiveDateTime> = (0..3 * Compute::slots_per_day())
+        .map(|idx| start + TimeDelta::minutes(5 * idx as i64))
+        .collect();
+    let mut prices = vec![10.; times.len()];
+    prices[2 * Compute::slots_per_day()] = 1000.;
+    let frame = Frame::new(times).with_column("Price", prices).unwrap();
+
+    let first_slot = |agg| {
+        frame
+            .profile(Profile::Day, SampleCheck::default(), agg)
+            .unwrap()
+            .0[0][0]
+    };
+    assert_eq!(first_slot(ProfileAgg::Mean), 340.);
+    assert_eq!(first_slot(ProfileAgg::Median), 10.);
+    assert_eq!(first_slot(ProfileAgg::TrimmedMean(0.34)), 10.);
+    assert_eq!(first_slot(ProfileAgg::TrimmedMean(0.)), 340.);
+    assert_eq!(ProfileAgg::TrimmedMean(0.49).of_sorted(&[1., 2.]), 1.5);
+
+    // Winsorizing holds the spike to the rest of the prices instead.
+    let clip = Clip { low: 1., high: 99. };
+    let (clipped, bounds) = frame.winsorize("Price", clip).unwrap();
+    assert_eq!(
+        bounds,
+        ClipBounds {
+            low: 10.,
+            high: 10.
+        }
+    );
+    let (slots, _) = clipped
+        .profile(Profile::Day, SampleCheck::default(), ProfileAgg::Mean)
+        .unwrap();
+    assert_eq!(slots[0][0], 10.);
+}
+
+#[test]
+fn clip_percentile_winsorizes_written_profiles() {
+    let fixture = Fixture::new("clip_profile", &SyntheticData::default());
+    let csv = fs::read_to_string(fixture.prices()).unwrap();
+    let mut lines: Vec<String> = csv.lines().map(str::to_string).collect();
+    // A $5000 interval at 18:00 on the third day.
+    let spike = 1 + 2 * Compute::slots_per_day() + Compute::time_to_idx_5min(18, 0);
+    let mut fields: Vec<&str> = lines[spike].split(',').collect();
+    fields[3] = "5000";
+    lines[spike] = fields.join(",");
+    fs::write(fixture.path("spiked.csv"), lines.join("\n") + "\n").unwrap();
+
+    // The bounds and slot means by hand, interpolating between ranks.
+    let rows: Vec<EnergyPriceCsvRow> = convert::read_rows(&fixture.path("spiked.csv"))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let mut sorted: Vec<f64> = rows.iter().map(|row| row.lmp_avg).collect();
+    sorted.sort_by(f64::total_cmp);
+    let percentile = |p: f64| {
+        let rank = p * (sorted.len() - 1) as f64;
+        let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+        sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+    };
+    let (low, high) = (percentile(0.01), percentile(0.99));
+    assert!(high < 5000.);
+    let mut by_slot = vec![(0., 0.); Compute::slots_per_day()];
+    for row in &rows {
+        let slot = &mut by_slot[Compute::time_to_idx_5min(row.hour, row.minute)];
+        slot.0 += row.lmp_avg.clamp(low, high);
+        slot.1 += 1.;
+    }
+
+    let printed = fixture.cli(&[
+        "write-price-minutes",
+        "spiked.csv",
+        "clipped.csv",
+        "--clip-percentile",
+        "1,99",
+    ]);
+    let bounds = format!("{low:.2} to {high:.2} ");
+    assert!(
+        printed.contains(&format!("Clipped prices to {bounds}")),
+        "{printed}"
+    );
+    let meta = Provenance::read(&fixture.path("clipped.csv")).unwrap();
+    assert!(
+        meta.notes["clipped_prices"].starts_with(&bounds),
+        "{meta:?}"
+    );
+    fixture.cli(&["write-price-minutes", "spiked.csv", "unclipped.csv"]);
+
+    let slots = |name: &str| -> Vec<f64> {
+        fs::read_to_string(fixture.path(name))
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap().parse().unwrap())
+            .collect()
+    };
+    let clipped = slots("clipped.csv");
+    for (idx, (price, (sum, count))) in clipped.iter().zip(&by_slot).enumerate() {
+        let mean = sum / count;
+        assert!((price - mean).abs() < 1e-6, "slot {idx}: {price} vs {mean}");
+    }
+    let evening = Compute::time_to_idx_5min(18, 0);
+    assert!(slots("unclipped.csv")[evening] > clipped[evening] + 500.);
+}
+
+#[test]
+fn price_spikes_group_into_events() {
+    let start = NaiveDate::from_ymd_opt(2024, 1, 1)
+        .unwrap()
+        .and_time(NaiveTime::MIN);
+    let mut series: Vec<(NaiveDateTime, f64)> = (0..Compute::slots_per_day())
+        .map(|idx| (start + TimeDelta::minutes(5 * idx as i64), 20.))
+        .collect();
+    for (idx, price) in [(100, 300.), (101, 900.), (102, 400.), (200, 500.)] {
+        series[idx].1 = price;
+    }
+
+    let spikes = Compute::find_price_spikes(
+        &series,
+        Compute::spike_price(&series, SpikeThreshold::Absolute(250.)),
+    );
+    assert_eq!(spikes.len(), 2);
+    assert_eq!(spikes[0].start, "2024-01-01 08:20:00");
+    assert_eq!(spikes[0].end, "2024-01-01 08:35:00");
+    assert_eq!(spikes[0].duration_mins, 15);
+    assert_eq!(spikes[0].peak_time, "2024-01-01 08:25:00");
+    assert_eq!(spikes[0].peak_price, 900.);
+    assert_eq!(spikes[1].duration_mins, 5);
+    assert_eq!(spikes[1].peak_price, 500.);
+
+    // Four spikes in 288 intervals sit well over three deviations above
+    // the mean, while the flat prices around them never do.
+    let z = Compute::spike_price(&series, SpikeThreshold::ZScore(3.));
+    assert!(z > 20. && z < 300.);
+    assert_eq!(Compute::find_price_spikes(&series, z).len(), 2);
+}
+
+#[test]
+fn animation_frames_follow_periods() {
+    let data = SyntheticData {
+        start: chrono::NaiveDate::from_ymd_opt(2024, 1, 29).unwrap(),
+        ..SyntheticData::default()
+    };
+    let fixture = Fixture::new("frames", &data);
+    let gen = fixture.gen();
+    let compute = Compute::new(&gen);
+
+    let months = compute.average_gen_5min_by_period(Period::Month).unwrap();
+    let labels: Vec<_> = months.iter().map(|(label, _)| label.as_str()).collect();
+    assert_eq!(labels, ["2024-01", "2024-02"]);
+    assert!(months
+        .iter()
+        .all(|(_, gen)| gen.len() == Compute::slots_per_day()));
+
+    let weeks = compute.average_gen_5min_by_period(Period::Week).unwrap();
+    let labels: Vec<_> = weeks.iter().map(|(label, _)| label.as_str()).collect();
+    assert_eq!(labels, ["2024-W05"]);
+}
+
+#[test]
+fn facets_chart_a_panel_per_period() {
+    let data = SyntheticData {
+        start: chrono::NaiveDate::from_ymd_opt(2024, 3, 29).unwrap(),
+        ..SyntheticData::default()
+    };
+    let fixture = Fixture::new("facets", &data);
+    let prices = fixture.prices();
+    let compute = Compute::new(&prices);
+
+    let quarters = compute
+        .average_price_5min_by_period(Period::Quarter)
+        .unwrap();
+    let labels: Vec<_> = quarters.iter().map(|(label, _)| label.as_str()).collect();
+    assert_eq!(labels, ["2024Q1", "2024Q2"]);
+    let months = compute.average_price_5min_by_period(Period::Month).unwrap();
+    assert_eq!(months[0].1, quarters[0].1);
+
+    let chart = fixture.path("facets.svg");
+    Graphing::new(&chart)
+        .facet_price(&quarters, "Prices by quarter")
+        .unwrap();
+    let svg = fs::read_to_string(&chart).unwrap();
+    assert!(svg.contains("2024Q1") && svg.contains("2024Q2"));
+
+    let gen = Compute::new(&fixture.gen())
+        .average_gen_5min_by_period(Period::Quarter)
+        .unwrap();
+    let chart = fixture.path("gen_facets.svg");
+    Graphing::new(&chart)
+        .facet_gen(&gen, "Generation by quarter")
+        .unwrap();
+    assert!(fs::read_to_string(&chart).unwrap().contains("2024Q2"));
+}
+
+#[test]
+fn gen_shares_stack_to_100() {
+    let fixture = Fixture::new("shares", &SyntheticData::default());
+    let gen = Compute::new(&fixture.gen())
+        .average_gen_profile(Profile::Day)
+        .unwrap();
+    let shares = Compute::gen_shares(&gen);
+    assert_eq!(shares.len(), gen.len());
+    for slot in &shares {
+        assert_eq!(slot[0], 100.);
+        assert!((slot.iter().skip(1).sum::<f64>() - 100.).abs() < 1e-9);
+        assert!(slot.iter().all(|share| *share >= 0.));
+    }
+}
+
+#[test]
+fn dataset_stats_summarize_prices_and_energy() {
+    let fixture = Fixture::new("dataset_stats", &SyntheticData::default());
+    let prices = Compute::new(&fixture.prices()).dataset_stats().unwrap();
+    assert_eq!(prices.kind, RawKind::Price);
+    assert_eq!(prices.rows, prices.days * Compute::slots_per_day());
+    assert_eq!(prices.partial_days, 0);
+    assert!(prices.sources.is_empty());
+    let price = prices.price.unwrap();
+    assert!(price.min <= price.median && price.median <= price.max);
+    assert!(price.min <= price.mean && price.mean <= price.max);
+    assert!((0. ..=1.).contains(&price.negative_share));
+
+    let gen = Compute::new(&fixture.gen()).dataset_stats().unwrap();
+    assert_eq!(gen.kind, RawKind::Gen);
+    assert!(gen.price.is_none());
+    assert_eq!(gen.sources[0].source, "Total");
+    assert_eq!(gen.sources[0].share, 1.);
+    let shares: f64 = gen.sources[1..].iter().map(|source| source.share).sum();
+    // The sources only roughly add up to the Total the export reports.
+    assert!((shares - 1.).abs() < 0.05);
+
+    let out = fixture.path("gen_stats.csv");
+    convert::write_dataset_stats(&out, &gen, WriteOptions::default()).unwrap();
+    let csv = fs::read_to_string(&out).unwrap();
+    assert!(csv.starts_with("stat,value\nfirst,"));
+    assert!(csv.contains("\nSolar_share,"));
+}
+
+#[test]
+fn arbitrage_spreads_average_each_days_extremes() {
+    let dir = std::env::temp_dir().join(format!("energy_analysis_spread_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let start = NaiveDate::from_ymd_opt(2024, 5, 1)
+        .unwrap()
+        .and_time(NaiveTime::MIN);
+    // A full day priced at its slot index, then a day with only 20 intervals.
+    let rows: Vec<EnergyPriceCsvRow> = (0..Compute::slots_per_day() + 20)
+        .map(|idx| {
+            let time = start + TimeDelta::minutes(5 * idx as i64);
+            let price = (idx % Compute::slots_per_day()) as f64;
+            EnergyPriceCsvRow::new(
+                time.format(convert::TIMESTAMP_FMT).to_string(),
+                time.hour(),
+                time.minute(),
+                price,
+            )
+        })
+        .collect();
+    let prices = dir.join("prices.csv");
+    convert::write_parsed(&prices, &rows, WriteOptions::default()).unwrap();
+
+    let spreads = Compute::new(&prices).daily_arbitrage_spread(12).unwrap();
+    assert_eq!(spreads.len(), 1);
+    assert_eq!(spreads[0].date, start.date());
+    assert_eq!(spreads[0].cheap_avg, 5.5);
+    assert_eq!(spreads[0].expensive_avg, 281.5);
+    assert_eq!(spreads[0].spread, 276.);
+
+    // Ten a side fits in the short day, whose prices run 0 through 19.
+    let spreads = Compute::new(&prices).daily_arbitrage_spread(10).unwrap();
+    assert_eq!(spreads.len(), 2);
+    assert_eq!(spreads[1].spread, 14.5 - 4.5);
+    assert!(Compute::new(&prices).daily_arbitrage_spread(0).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn price_exceedance_falls_as_thresholds_rise() {
+    let fixture = Fixture::new("exceedance", &SyntheticData::default());
+    let exceedance = Compute::new(&fixture.prices())
+        .price_exceedance(Profile::Day, &[80., 0., 40.])
+        .unwrap();
+    assert_eq!(exceedance.thresholds, [0., 40., 80.]);
+    assert_eq!(exceedance.probabilities.len(), Compute::slots_per_day());
+    for slot in &exceedance.probabilities {
+        assert!(slot.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert!(slot.iter().all(|p| (0. ..=1.).contains(p)));
+    }
+    assert!(exceedance.probabilities.iter().any(|slot| slot[2] > 0.));
+
+    let out = fixture.path("exceedance.csv");
+    convert::write_price_exceedance(&out, &exceedance, Profile::Day, WriteOptions::default())
+        .unwrap();
+    let csv = fs::read_to_string(&out).unwrap();
+    assert!(csv.starts_with("slot,above_0,above_40,above_80,samples\n"));
+    assert_eq!(line_count(&out), Compute::slots_per_day() + 1);
+}
+
+#[test]
+fn source_scaling_keeps_total_at_demand() {
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+    let wind = EnergyGenCsvRow::source_idx("Wind").unwrap();
+    let gas = EnergyGenCsvRow::source_idx("Natural Gas").unwrap();
+    let fleet = || {
+        let mut sources = [0.; 14];
+        sources[0] = 100.;
+        sources[solar] = 40.;
+        sources[wind] = 20.;
+        sources[gas] = 40.;
+        sources
+    };
+    let sum = |sources: &[f64; 14]| sources.iter().skip(1).sum::<f64>();
+
+    // The extra 20 MW of solar displaces wind and gas by their shares.
+    let mut sources = fleet();
+    let price = SourceScaling::new("Solar", 1.5, 1.)
+        .unwrap()
+        .apply(&mut sources, 60.);
+    assert_eq!(sources[0], 100.);
+    assert!((sum(&sources) - 100.).abs() < 1e-9);
+    assert!((sources[solar] - 60.).abs() < 1e-9);
+    assert!((sources[gas] - 80. / 3.).abs() < 1e-9);
+    assert!((sources[wind] - 40. / 3.).abs() < 1e-9);
+    assert!((price - 60. * 40. / 60.).abs() < 1e-9);
+
+    // Less solar is made up by the rest of the fleet.
+    let mut sources = fleet();
+    SourceScaling::new("Solar", 0.5, 1.)
+        .unwrap()
+        .apply(&mut sources, 60.);
+    assert!((sum(&sources) - 100.).abs() < 1e-9);
+    assert!((sources[gas] - 160. / 3.).abs() < 1e-9);
+
+    // Beyond demand, the source is curtailed and prices at zero.
+    let mut sources = fleet();
+    let price = SourceScaling::new("Solar", 4., 1.)
+        .unwrap()
+        .apply(&mut sources, 60.);
+    assert_eq!(sources[solar], 100.);
+    assert_eq!(sum(&sources), 100.);
+    assert_eq!(price, 0.);
+}
+
+#[test]
+fn solar_loss_only_touches_its_window() {
+    let fixture = Fixture::new("solar_loss", &SyntheticData::default());
+    let window = ScenarioWindow {
+        start: NaiveTime::from_hms_opt(10, 0, 0).unwrap(),
+        end: NaiveTime::from_hms_opt(14, 0, 0).unwrap(),
+        dates: None,
+    };
+    let scaling = SourceScaling::new("Solar", 0.5, 1.)
+        .unwrap()
+        .with_window(window);
+    let slots = Compute::new(&fixture.prices())
+        .net_load_impact_5min(&fixture.gen(), &scaling)
+        .unwrap();
+    assert_eq!(slots.len(), Compute::slots_per_day());
+    for (idx, slot) in slots.iter().enumerate() {
+        let (hour, _) = Compute::idx_5min_to_time(idx);
+        if (10..14).contains(&hour) {
+            assert!(slot.scenario_net_load > slot.net_load);
+            assert!(slot.scenario_price >= slot.price);
+        } else {
+            assert_eq!(slot.scenario_net_load, slot.net_load);
+            assert_eq!(slot.scenario_price, slot.price);
+        }
+    }
+
+    // A window past midnight wraps, and dates limit it to those days.
+    let overnight = ScenarioWindow {
+        start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+        end: NaiveTime::from_hms_opt(2, 0, 0).unwrap(),
+        dates: Some(
+            NaiveDate::from_ymd_opt(2024, 4, 8).unwrap()
+                ..=NaiveDate::from_ymd_opt(2024, 4, 8).unwrap(),
+        ),
+    };
+    let at = |date: u32, hour: u32| {
+        NaiveDate::from_ymd_opt(2024, 4, date)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    };
+    assert!(overnight.contains(at(8, 23)));
+    assert!(overnight.contains(at(8, 1)));
+    assert!(!overnight.contains(at(8, 12)));
+    assert!(!overnight.contains(at(9, 1)));
+}
+
+#[test]
+fn extreme_days_are_found_from_the_raw_days() {
+    let data = SyntheticData::default();
+    let fixture = Fixture::new("extremes", &data);
+    let price_csv = fixture.prices();
+    let prices = Compute::new(&price_csv);
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+
+    // Average each day from its own raw intervals.
+    let days: Vec<(NaiveDate, f64, f64)> = (0..data.days)
+        .map(|offset| {
+            let date = data.start + TimeDelta::days(offset as i64);
+            let day = prices.day_profile(&fixture.gen(), date).unwrap();
+            assert_eq!(day.prices.len(), Compute::slots_per_day());
+            assert_eq!(day.gen.len(), Compute::slots_per_day());
+            let price =
+                day.prices.iter().map(|(_, price)| price).sum::<f64>() / day.prices.len() as f64;
+            let sun = day
+                .gen
+                .iter()
+                .map(|(_, sources)| sources[solar])
+                .sum::<f64>()
+                / day.gen.len() as f64;
+            (date, price, sun)
+        })
+        .collect();
+    let extreme = |pick: fn(&(NaiveDate, f64, f64)) -> f64, max: bool| {
+        let mut sorted = days.clone();
+        sorted.sort_by(|a, b| pick(a).total_cmp(&pick(b)));
+        if max {
+            sorted.reverse();
+        }
+        (sorted[0].0, pick(&sorted[0]))
+    };
+
+    let found = prices.find_extreme_days(&fixture.gen()).unwrap();
+    let expected = [
+        extreme(|day| day.1, true),
+        extreme(|day| day.1, false),
+        extreme(|day| day.2, true),
+    ];
+    assert_eq!(found.len(), expected.len());
+    for (day, (date, value)) in found.iter().zip(expected) {
+        assert_eq!(day.date, date, "{}", day.label);
+        assert!((day.value - value).abs() < 1e-6, "{}", day.label);
+    }
+    assert!(prices
+        .day_profile(&fixture.gen(), data.start - TimeDelta::days(1))
+        .is_err());
+}
+
+#[test]
+fn event_days_compare_against_the_days_around_them() {
+    let fixture = Fixture::new("event_days", &SyntheticData::default());
+    let first = Compute::new(&fixture.prices())
+        .dataset_stats()
+        .unwrap()
+        .first;
+    let first = NaiveDateTime::parse_from_str(&first, convert::TIMESTAMP_FMT)
+        .unwrap()
+        .date();
+    let date = first + TimeDelta::days(3);
+    let events = Compute::new(&fixture.prices())
+        .event_days(&fixture.gen(), &[date], 2)
+        .unwrap();
+    let [event] = events.as_slice() else {
+        panic!("expected one event day");
+    };
+    assert_eq!(event.day.date, date);
+    // Two days either side, leaving out the event day itself.
+    assert_eq!(event.baseline_days, 4);
+    assert_eq!(event.baseline_prices.len(), Compute::slots_per_day());
+
+    let deviations = event.deviations();
+    assert_eq!(deviations[0].series, "Price");
+    assert_eq!(deviations.len(), 15);
+    for deviation in &deviations {
+        assert!((deviation.change - (deviation.actual - deviation.baseline)).abs() < 1e-6);
+    }
+    // The average price can't stray further than its widest interval.
+    assert!(deviations[0].change.abs() <= deviations[0].largest_gap.abs());
+
+    let out = fixture.path("case_study.csv");
+    convert::write_event_deviations(&out, &deviations, WriteOptions::default()).unwrap();
+    assert_eq!(line_count(&out), deviations.len() + 1);
+}
+
+#[test]
+fn period_differences_test_each_slot() {
+    let same = MeanDifference::of(&[1., 2., 3.], &[1., 2., 3.]);
+    assert_eq!(same.change, 0.);
+    assert!((same.p_value - 1.).abs() < 1e-6);
+    // Means of 2 and 12 with standard errors of 1/sqrt(3) apiece.
+    let moved = MeanDifference::of(&[1., 2., 3.], &[11., 12., 13.]);
+    assert_eq!(moved.change, 10.);
+    assert!((moved.std_error - (2f64 / 3.).sqrt()).abs() < 1e-12);
+    assert!(moved.p_value < 1e-6);
+    let (low, high) = moved.interval_95();
+    assert!(low < 10. && 10. < high);
+    assert!(MeanDifference::of(&[1.], &[2., 3.]).change.is_nan());
+
+    let fixture = Fixture::new("period_diff", &SyntheticData::default());
+    let first = Compute::new(&fixture.prices())
+        .dataset_stats()
+        .unwrap()
+        .first;
+    let first = NaiveDateTime::parse_from_str(&first, convert::TIMESTAMP_FMT)
+        .unwrap()
+        .date();
+    let before = first..=first + TimeDelta::days(2);
+    let after = first + TimeDelta::days(3)..=first + TimeDelta::days(5);
+    let diffs = Compute::new(&fixture.gen())
+        .period_difference("Solar", Profile::Day, before, after)
+        .unwrap();
+    assert_eq!(diffs.len(), Compute::slots_per_day());
+    // No sun at midnight, in either period.
+    assert_eq!(diffs[0].change, 0.);
+    assert!(diffs.iter().all(|diff| (0. ..=1.).contains(&diff.p_value)));
+
+    let out = fixture.path("period_diff.csv");
+    convert::write_period_difference(&out, &diffs, Profile::Day, WriteOptions::default()).unwrap();
+    assert!(fs::read_to_string(&out)
+        .unwrap()
+        .starts_with("slot,before,after,change,ci_low,ci_high,p_value\n00:00,"));
+}
+
+#[test]
+fn peak_timing_finds_shifted_peaks() {
+    let mut values = vec![1080.; 20];
+    values.extend([1140.; 20]);
+    for (idx, value) in values.iter_mut().enumerate() {
+        *value += (idx % 3) as f64 * 5.;
+    }
+    assert_eq!(changepoints(&values, 5, 0.01), vec![20]);
+    assert!(changepoints(&values[..20], 5, 0.01).is_empty());
+    assert!(changepoints(&values, 25, 0.01).is_empty());
+
+    let fixture = Fixture::new("peak_timing", &SyntheticData::default());
+    let timing = Compute::new(&fixture.prices())
+        .peak_timing(&fixture.gen(), 2)
+        .unwrap();
+    assert!(!timing.days.is_empty());
+    assert!(timing.days.iter().all(|day| {
+        (0. ..1440.).contains(&day.price_peak) && (0. ..1440.).contains(&day.net_load_peak)
+    }));
+    let days: usize = timing.months.iter().map(|month| month.days).sum();
+    assert_eq!(days, timing.days.len());
+    assert_eq!(PeakTiming::clock(1145.), "19:05");
+
+    let out = fixture.path("peak_timing.csv");
+    convert::write_month_peaks(&out, &timing.months, WriteOptions::default()).unwrap();
+    assert!(fs::read_to_string(&out)
+        .unwrap()
+        .starts_with("month,days,price_peak,net_load_peak\n"));
+}
+
+#[test]
+fn value_scenarios_share_one_chart() {
+    let fixture = Fixture::new("value_scenarios", &SyntheticData::default());
+    let (base, _) = Compute::new(&fixture.prices())
+        .average_value_5min(&fixture.gen())
+        .unwrap();
+    let (paired, _) = Compute::new(&fixture.prices())
+        .average_value_solar_battery(&fixture.gen())
+        .unwrap();
+    let scenarios = [
+        ("Base".to_string(), base),
+        ("Solar + Battery".to_string(), paired),
+    ];
+
+    let chart = fixture.path("value_scenarios.svg");
+    Graphing::new(&chart)
+        .value_scenarios(&scenarios, "Base vs Solar + Battery")
+        .unwrap();
+    let svg = fs::read_to_string(&chart).unwrap();
+    assert!(svg.contains("Base") && svg.contains("Solar + Battery"));
+    assert!(Graphing::new(&chart)
+        .value_scenarios(&scenarios[..1], "Base alone")
+        .is_err());
+
+    let out = fixture.path("value_scenarios.csv");
+    convert::write_value_scenarios(&out, &scenarios, WriteOptions::default()).unwrap();
+    let csv = fs::read_to_string(&out).unwrap();
+    assert!(csv.starts_with("source,Base,Solar + Battery\nTotal,"));
+    assert_eq!(csv.lines().count(), 15);
+    // Pairing moves the batteries' output into solar's column.
+    let batteries = csv
+        .lines()
+        .find(|line| line.starts_with("Batteries,"))
+        .unwrap();
+    assert!(batteries.ends_with(",0"));
+}
+
+#[test]
+fn daily_revenue_accumulates_each_day() {
+    let fixture = Fixture::new("daily_revenue", &SyntheticData::default());
+    let daily = Compute::new(&fixture.prices())
+        .daily_revenue(&fixture.gen())
+        .unwrap();
+    assert!(daily.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    // Summed over the days, revenue over energy is the value-minutes average.
+    let (values, qtys) = Compute::new(&fixture.prices())
+        .average_value_5min(&fixture.gen())
+        .unwrap();
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+    let revenue: f64 = daily.iter().map(|(_, sources)| sources[solar]).sum();
+    let mwh = qtys[solar] * Compute::MINS_INCR as f64 / 60.;
+    assert!((revenue / mwh - values[solar]).abs() < 1e-6);
+
+    let chart = fixture.path("daily_revenue.svg");
+    Graphing::new(&chart)
+        .revenue_timeseries(&rolling_mean(&daily, 7), "Revenue by source")
+        .unwrap();
+    assert!(fs::read_to_string(&chart)
+        .unwrap()
+        .contains("Revenue per day"));
+}
+
+#[test]
+fn time_aligned_joins_match_exact_nearest_and_interval() {
+    let at = |hour, minute| {
+        NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    };
+    let stream = |times: Vec<NaiveDateTime>| times.into_iter().map(|time| Ok((time, time)));
+    let join = |left: Vec<NaiveDateTime>, right: Vec<NaiveDateTime>, mode| {
+        let mut join = TimeAlignedJoin::new(stream(left), stream(right), mode);
+        let pairs: Vec<_> = join.by_ref().collect();
+        (pairs, join.stats())
+    };
+
+    let (pairs, stats) = join(
+        vec![at(0, 0), at(0, 5), at(0, 10), at(0, 20)],
+        vec![at(0, 5), at(0, 10), at(0, 15), at(0, 20)],
+        JoinMode::Exact,
+    );
+    assert_eq!(pairs.len(), 3);
+    assert!(pairs.iter().all(|(left, right)| left == right));
+    assert_eq!(
+        stats,
+        JoinStats {
+            matched: 3,
+            left_unmatched: 1,
+            right_unmatched: 1,
+        }
+    );
+
+    // Five-minute rows onto hourly ones: each joins the hour it falls in.
+    let (pairs, stats) = join(
+        vec![at(0, 55), at(1, 0), at(1, 55), at(3, 5)],
+        vec![at(1, 0), at(2, 0)],
+        JoinMode::Interval(TimeDelta::hours(1)),
+    );
+    let hours: Vec<_> = pairs.iter().map(|(_, right)| *right).collect();
+    assert_eq!(hours, [at(1, 0), at(1, 0)]);
+    assert_eq!((stats.left_unmatched, stats.right_unmatched), (2, 1));
+
+    let (pairs, stats) = join(
+        vec![at(0, 58), at(1, 29), at(1, 31), at(2, 20)],
+        vec![at(1, 0), at(2, 0)],
+        JoinMode::Nearest(TimeDelta::minutes(15)),
+    );
+    let nearest: Vec<_> = pairs.iter().map(|(left, right)| (*left, *right)).collect();
+    assert_eq!(nearest, [(at(0, 58), at(1, 0))]);
+    assert_eq!((stats.left_unmatched, stats.right_unmatched), (3, 1));
+    let (pairs, _) = join(
+        vec![at(0, 58), at(1, 29), at(1, 31)],
+        vec![at(1, 0), at(2, 0)],
+        JoinMode::Nearest(TimeDelta::minutes(30)),
+    );
+    let nearest: Vec<_> = pairs.iter().map(|(_, right)| *right).collect();
+    assert_eq!(nearest, [at(1, 0), at(1, 0), at(2, 0)]);
+
+    // The price and generation fixtures line up interval for interval.
+    let fixture = Fixture::new("join", &SyntheticData::default());
+    let rows = |path: PathBuf, column: &'static str| {
+        let mut reader = csv::Reader::from_path(path).unwrap();
+        let idx = reader
+            .headers()
+            .unwrap()
+            .iter()
+            .position(|header| header == column)
+            .unwrap();
+        let times: Vec<_> = reader
+            .records()
+            .map(|record| {
+                let record = record.unwrap();
+                NaiveDateTime::parse_from_str(&record[idx], convert::TIMESTAMP_FMT).unwrap()
+            })
+            .collect();
+        stream(times)
+    };
+    let stats = TimeAlignedJoin::new(
+        rows(fixture.prices(), "timestamp"),
+        rows(fixture.gen(), "local_timestamp_start"),
+        JoinMode::Exact,
+    )
+    .finish()
+    .unwrap();
+    assert!(stats.matched > 0);
+    assert_eq!(stats.left_unmatched, 0);
+}
+
+#[test]
+fn joined_analyses_fail_on_a_corrupt_row() {
+    let fixture = Fixture::new("corrupt_join", &SyntheticData::default());
+    let prices = fs::read_to_string(fixture.prices()).unwrap();
+    let mut lines: Vec<String> = prices.lines().map(str::to_string).collect();
+    let idx = lines[0]
+        .split(',')
+        .position(|header| header == "timestamp")
+        .unwrap();
+    let mut fields: Vec<&str> = lines[100].split(',').collect();
+    fields[idx] = "not a time";
+    lines[100] = fields.join(",");
+    fs::write(fixture.prices(), lines.join("\n") + "\n").unwrap();
+
+    let stderr = fixture.cli_err(&[
+        "analyze",
+        "value-minutes",
+        "prices.csv",
+        "gen.csv",
+        "--out-csv",
+        "value.csv",
+        "--no-cache",
+    ]);
+    assert!(stderr.contains("not a time"), "{stderr}");
+    assert_eq!(stderr.matches("not a time").count(), 1, "{stderr}");
+    assert!(Compute::new(&fixture.prices())
+        .period_summaries(&fixture.gen(), Period::Month)
+        .is_err());
+}
+
+#[test]
+fn reserve_margin_joins_price_gen_and_demand() {
+    let fixture = Fixture::new("reserve_margin", &SyntheticData::default());
+    let first = Compute::new(&fixture.prices())
+        .dataset_stats()
+        .unwrap()
+        .first;
+    let first = NaiveDateTime::parse_from_str(&first, convert::TIMESTAMP_FMT).unwrap();
+    // A day of hourly demand, leaving the rest of the dataset without.
+    let mut demand = "local_timestamp,demand_mw\n".to_string();
+    for hour in 0..24 {
+        let time = first + TimeDelta::hours(hour);
+        demand += &format!("{},20000\n", time.format(convert::TIMESTAMP_FMT));
+    }
+    let demand_csv = fixture.path("demand.csv");
+    fs::write(&demand_csv, demand).unwrap();
+
+    let (price_csv, gen_csv) = (fixture.prices(), fixture.gen());
+    let mut joined = Compute::new(&price_csv)
+        .iter_price_gen_demand(&gen_csv, &demand_csv)
+        .unwrap();
+    for ((price, gen), demand) in joined.by_ref() {
+        assert_eq!(price.timestamp, gen.local_timestamp_start);
+        let time = NaiveDateTime::parse_from_str(&price.timestamp, convert::TIMESTAMP_FMT).unwrap();
+        let hour =
+            NaiveDateTime::parse_from_str(&demand.local_timestamp, convert::TIMESTAMP_FMT).unwrap();
+        assert!(hour <= time && time < hour + TimeDelta::hours(1));
+    }
+    // The price and generation join reads on past the day of demand.
+    let price_gen = joined.inner_stats().unwrap();
+    assert_eq!(price_gen.matched, line_count(&fixture.prices()) - 1);
+    assert_eq!(
+        (price_gen.left_unmatched, price_gen.right_unmatched),
+        (0, 0)
+    );
+    let stats = joined.finish().unwrap();
+    assert_eq!(stats.matched, Compute::slots_per_day());
+    assert_eq!((stats.left_unmatched, stats.right_unmatched), (1, 0));
+
+    let mut capacity_mw = [None; 14];
+    capacity_mw[EnergyGenCsvRow::source_idx("Natural Gas").unwrap()] = Some(20000.);
+    let margin = Compute::new(&fixture.prices())
+        .reserve_margin_prices(
+            &fixture.gen(),
+            &demand_csv,
+            &InstalledCapacity { capacity_mw },
+            0.1,
+        )
+        .unwrap();
+    let intervals: usize = margin.bins.iter().map(|bin| bin.intervals).sum();
+    assert_eq!(intervals, stats.matched);
+    assert_eq!(margin.price_gen, price_gen);
+    assert!(margin.bins.iter().all(|bin| bin.avg_demand_mw == 20000.));
+
+    let out = fixture.path("reserve_margin.csv");
+    convert::write_margin_bins(&out, &margin.bins, WriteOptions::default()).unwrap();
+    assert!(fs::read_to_string(&out)
+        .unwrap()
+        .starts_with("margin,intervals,avg_price,avg_demand_mw\n"));
+
+    // A corrupt generation row after the day of demand still fails it.
+    let gen = fs::read_to_string(fixture.gen()).unwrap();
+    let mut lines: Vec<&str> = gen.lines().collect();
+    let last = lines.len() - 1;
+    lines[last] = "not,a,row";
+    fs::write(fixture.gen(), lines.join("\n") + "\n").unwrap();
+    assert!(Compute::new(&fixture.prices())
+        .reserve_margin_prices(
+            &fixture.gen(),
+            &demand_csv,
+            &InstalledCapacity {
+                capacity_mw: [None; 14]
+            },
+            0.1,
+        )
+        .is_err());
+}
+
+#[test]
+fn capacity_factors_divide_output_by_capacity() {
+    let data = SyntheticData {
+        start: NaiveDate::from_ymd_opt(2024, 1, 29).unwrap(),
+        ..SyntheticData::default()
+    };
+    let fixture = Fixture::new("capacity_factors", &data);
+    let toml = fixture.path("capacity.toml");
+    fs::write(&toml, "Solar = 20000\n\"natural gas\" = 40000.5\n").unwrap();
+    let capacity = InstalledCapacity::load(&toml).unwrap();
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+    let gas = EnergyGenCsvRow::source_idx("Natural Gas").unwrap();
+    let wind = EnergyGenCsvRow::source_idx("Wind").unwrap();
+
+    let gen_csv = fixture.gen();
+    let gen = Compute::new(&gen_csv);
+    let averages = gen.average_gen_5min().unwrap();
+    let factors = gen.capacity_factors_5min(&capacity).unwrap();
+    assert_eq!(factors.len(), Compute::slots_per_day());
+    assert_eq!(factors[144].0, "12:00");
+    for ((_, factor), average) in factors.iter().zip(&averages) {
+        assert_eq!(factor[solar], average[solar] / 20000.);
+        assert_eq!(factor[gas], average[gas] / 40000.5);
+        assert!(factor[wind].is_nan());
+    }
+
+    // Each month divides the mean of its own intervals.
+    let monthly = gen.capacity_factors_monthly(&capacity).unwrap();
+    let labels: Vec<&str> = monthly.iter().map(|(label, _)| label.as_str()).collect();
+    assert_eq!(labels, ["2024-01", "2024-02"]);
+    let price_csv = fixture.prices();
+    let prices = Compute::new(&price_csv);
+    let (mut sum, mut count) = (0., 0);
+    for offset in 0..3 {
+        let day = prices
+            .day_profile(&gen_csv, data.start + TimeDelta::days(offset))
+            .unwrap();
+        sum += day
+            .gen
+            .iter()
+            .map(|(_, sources)| sources[solar])
+            .sum::<f64>();
+        count += day.gen.len();
+    }
+    assert!((monthly[0].1[solar] - sum / count as f64 / 20000.).abs() < 1e-9);
+
+    fs::write(&toml, "Solar = -5\n").unwrap();
+    assert!(InstalledCapacity::load(&toml).is_err());
+    fs::write(&toml, "Sunshine = 5\n").unwrap();
+    assert!(InstalledCapacity::load(&toml).is_err());
+}
+
+#[test]
+fn dataset_serves_rows_from_memory() {
+    let fixture = Fixture::new("dataset_memory", &SyntheticData::default());
+    let on_disk = Compute::new(&fixture.prices())
+        .average_value_5min(&fixture.gen())
+        .unwrap();
+
+    let prices = Dataset::load(&fixture.prices()).unwrap();
+    let gen = Dataset::load(&fixture.gen()).unwrap();
+    assert_eq!(prices.kind(), RawKind::Price);
+    assert_eq!(gen.kind(), RawKind::Gen);
+    assert_eq!(
+        gen.len(),
+        SyntheticData::default().days * Compute::slots_per_day()
+    );
+
+    // Overwriting the files doesn't reach analyses handed the datasets,
+    // but every other analysis reads the files as they are now.
+    fs::write(fixture.prices(), "").unwrap();
+    fs::write(fixture.gen(), "").unwrap();
+    assert_eq!(
+        Compute::from_dataset(&prices)
+            .with_dataset(&gen)
+            .average_value_5min(&gen)
+            .unwrap(),
+        on_disk
+    );
+    assert_ne!(
+        Compute::from_dataset(&prices).average_value_5min(&gen).ok(),
+        Some(on_disk)
+    );
+    let held = Compute::from_dataset(&prices).average_price_5min().unwrap();
+    assert_ne!(
+        Compute::new(&fixture.prices()).average_price_5min().ok(),
+        Some(held.clone())
+    );
+    assert_ne!(Compute::new(&prices).average_price_5min().ok(), Some(held));
+}
+
+#[test]
+fn datasets_read_from_bytes_never_touch_disk() {
+    let fixture = Fixture::new("dataset_bytes", &SyntheticData::default());
+    let on_disk = Compute::new(&fixture.prices())
+        .average_value_5min(&fixture.gen())
+        .unwrap();
+    let price_bytes = fs::read(fixture.prices()).unwrap();
+    let gen_bytes = fs::read(fixture.gen()).unwrap();
+
+    // Names that aren't files, as an upload in a browser would have.
+    let prices = Dataset::from_reader("upload/prices.csv", price_bytes.as_slice()).unwrap();
+    let gen = Dataset::from_reader("upload/gen.csv", gen_bytes.as_slice()).unwrap();
+    assert!(!prices.exists() && !gen.exists());
+    assert_eq!(prices.kind(), RawKind::Price);
+    assert_eq!(gen.kind(), RawKind::Gen);
+    assert_eq!(
+        Compute::from_dataset(&prices).kind().unwrap(),
+        RawKind::Price
+    );
+    let joined = Compute::from_dataset(&prices).with_dataset(&gen);
+    assert_eq!(joined.average_value_5min(&gen).unwrap(), on_disk);
+    let day = SyntheticData::default().start;
+    assert_eq!(
+        joined.day_profile(&gen, day).unwrap().gen.len(),
+        Compute::slots_per_day()
+    );
+    assert_eq!(
+        Compute::from_dataset(&prices).latest_prices(3).unwrap(),
+        Compute::new(&fixture.prices()).latest_prices(3).unwrap()
+    );
+    assert_eq!(
+        Compute::from_dataset(&gen).latest_gen().unwrap(),
+        Compute::new(&fixture.gen()).latest_gen().unwrap()
+    );
+    assert_eq!(
+        Compute::from_dataset(&gen).date_span().unwrap(),
+        Compute::new(&fixture.gen()).date_span().unwrap()
+    );
+
+    let averages = Compute::from_dataset(&prices).average_price_5min().unwrap();
+    let svg = Graphing::in_memory()
+        .daily_price_to_buffer(&averages, Profile::Day, ImageFormat::Svg)
+        .unwrap();
+    assert!(String::from_utf8(svg).unwrap().starts_with("<svg"));
+
+    // Bad bytes fail like a bad file, named as given.
+    let err = Dataset::from_reader(
+        "upload/bad.csv",
+        &b"timestamp,hour,minute,lmp_avg\nx,1,2,3\n"[..],
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("upload/bad.csv"));
+
+    // A name is only read from memory by analyses handed its dataset, so
+    // bytes held under a real file's name don't shadow the file.
+    assert!(Compute::new(Path::new("upload/prices.csv"))
+        .average_price_5min()
+        .is_err());
+    let shadow = Dataset::from_reader(fixture.prices(), gen_bytes.as_slice()).unwrap();
+    assert_eq!(shadow.kind(), RawKind::Gen);
+    assert_eq!(
+        Compute::new(&fixture.prices()).kind().unwrap(),
+        RawKind::Price
+    );
+}
+
+#[test]
+fn on_error_policy_applies_to_both_converters() {
+    let fixture = Fixture::new("on_error", &SyntheticData::default());
+    for raw in ["price_raw.csv", "gen_raw.csv"] {
+        let text = fs::read_to_string(fixture.path(raw)).unwrap();
+        let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+        lines[6] = lines[6].replace("2024-01-01", "2024-13-01");
+        fs::write(fixture.path(&format!("bad_{raw}")), lines.join("\n")).unwrap();
+    }
+    let convert = |raw: &str, on_error| {
+        let input = [fixture.path(&format!("bad_{raw}"))];
+        let out = fixture.path(&format!("out_{raw}"));
+        match raw {
+            "price_raw.csv" => convert::convert_energy_price_csv(
+                &input,
+                &out,
+                CsvFormat::default(),
+                on_error,
+                WriteOptions::default(),
+            ),
+            _ => convert::convert_energy_gen_csv(
+                &input,
+                &out,
+                CsvFormat::default(),
+                TotalCheck::Report,
+                on_error,
+                WriteOptions::default(),
+            ),
+        }
+    };
+
+    let rows = SyntheticData::default().days * Compute::slots_per_day();
+    for raw in ["price_raw.csv", "gen_raw.csv"] {
+        for on_error in [OnError::Skip, OnError::Log] {
+            let summary = convert(raw, on_error).unwrap();
+            assert_eq!((summary.rows, summary.skipped), (rows, 1), "{raw}");
+            assert!(summary
+                .first_skipped
+                .unwrap()
+                .to_string()
+                .contains("2024-13-01"));
+            // The header takes the skipped row's line.
+            assert_eq!(line_count(&fixture.path(&format!("out_{raw}"))), rows);
+        }
+        let err = convert(raw, OnError::Abort).unwrap_err();
+        assert!(err.to_string().contains("2024-13-01"), "{err}");
+    }
+}
+
+#[test]
+fn rolling_mean_spans_calendar_days() {
+    let day = |d| chrono::NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+    let series = [(day(1), 1.), (day(2), 2.), (day(3), 3.), (day(10), 10.)];
+    let rolled = rolling_mean(&series, 2);
+    let values: Vec<f64> = rolled.iter().map(|(_, val)| *val).collect();
+    assert_eq!(values, [1., 1.5, 2.5, 10.]);
+    assert_eq!(rolled[3].0, day(10));
+
+    let weekly = rolling_mean(&series, 7);
+    assert_eq!(weekly[2].1, 2.);
+    // The week ending the 10th starts on the 4th, after the gap.
+    assert_eq!(weekly[3].1, 10.);
+}
+
+#[test]
+fn value_join_survives_gaps() {
+    let data = SyntheticData {
+        price_gap_every: Some(7),
+        gen_gap_every: Some(11),
+        ..Default::default()
+    };
+    let fixture = Fixture::new("gaps", &data);
+    let (values, qtys) = Compute::new(&fixture.prices())
+        .average_value_5min(&fixture.gen())
+        .unwrap();
+    assert!(values.iter().all(|val| val.is_finite()));
+
+    let gapless = Fixture::new("gapless", &SyntheticData::default());
+    let (_, full_qtys) = Compute::new(&gapless.prices())
+        .average_value_5min(&gapless.gen())
+        .unwrap();
+    assert!(qtys[0] < full_qtys[0]);
+
+    // Solar only runs in the cheap midday hours, so it should be worth less
+    // than the average MWh.
+    assert!(values[EnergyGenCsvRow::source_idx("Solar").unwrap()] < values[0]);
+
+    let out = fixture.path("values.csv");
+    convert::write_energy_value_averages(&out, &values, &qtys, WriteOptions::default()).unwrap();
+    assert_eq!(line_count(&out), EnergyGenCsvRow::source_keys().len() + 1);
+}
+
+#[test]
+fn value_profile_resolves_the_day() {
+    let fixture = Fixture::new("value_profile", &SyntheticData::default());
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+    let day = Compute::new(&fixture.prices())
+        .value_profile(&fixture.gen(), Profile::Day)
+        .unwrap();
+    assert_eq!(day.len(), Compute::slots_per_day());
+
+    // Every source sees the same prices within a slot, so its value there
+    // stays close to that slot's average price, weighted a little by output.
+    let prices = Compute::new(&fixture.prices())
+        .average_price_profile(Profile::Day)
+        .unwrap();
+    let noon = Compute::time_to_idx_5min(12, 0);
+    for src in [0, solar] {
+        assert!((day[noon][src] - prices[noon]).abs() < 0.05 * prices[noon].abs());
+    }
+
+    let week = Compute::new(&fixture.prices())
+        .value_profile(&fixture.gen(), Profile::Week)
+        .unwrap();
+    assert_eq!(week.len(), Profile::Week.slots());
+}
+
+#[test]
+fn price_gen_overlay_shares_one_profile() {
+    let fixture = Fixture::new("price_gen_overlay", &SyntheticData::default());
+    let (prices, gen) = Compute::new(&fixture.prices())
+        .price_gen_profile(&fixture.gen(), Profile::Day)
+        .unwrap();
+    assert_eq!(prices.len(), Compute::slots_per_day());
+    assert_eq!(gen.len(), prices.len());
+
+    let alone = Compute::new(&fixture.prices())
+        .average_price_profile(Profile::Day)
+        .unwrap();
+    let noon = Compute::time_to_idx_5min(12, 0);
+    assert!((prices[noon] - alone[noon]).abs() < 1e-6 * alone[noon].abs().max(1.));
+
+    let chart = fixture.path("overlay.svg");
+    Graphing::new(&chart)
+        .price_gen_overlay(&prices, &gen, Profile::Day, "Overlay")
+        .unwrap();
+    assert!(fs::read_to_string(&chart).unwrap().starts_with("<svg"));
+}
+
+#[test]
+fn price_points_fit_a_scatter() {
+    let fixture = Fixture::new("price_points", &SyntheticData::default());
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+    let (points, fit) = Compute::new(&fixture.prices())
+        .price_points(&fixture.gen(), solar)
+        .unwrap();
+    assert_eq!(fit.count, points.len());
+    assert!(points.iter().all(|point| point.hour < 24));
+
+    let chart = fixture.path("scatter.svg");
+    Graphing::new(&chart)
+        .price_scatter(&points, &fit, "Solar", true)
+        .unwrap();
+    assert!(fs::read_to_string(&chart).unwrap().contains("12:00"));
+}
+
+#[test]
+fn histograms_align_bins_to_zero() {
+    let bins = histogram([-12., -0.5, 0., 4.9, 5., 23., f64::NAN], 5.).unwrap();
+    let lows: Vec<f64> = bins.iter().map(|bin| bin.low).collect();
+    assert_eq!(lows, [-15., -10., -5., 0., 5., 10., 15., 20.]);
+    let counts: Vec<usize> = bins.iter().map(|bin| bin.count).collect();
+    assert_eq!(counts, [1, 0, 1, 2, 1, 0, 0, 1]);
+    assert!(histogram([1.], 0.).is_err());
+    assert!(histogram([0., 1e9], 1.).is_err());
+
+    let fixture = Fixture::new("histogram", &SyntheticData::default());
+    let series = Compute::new(&fixture.prices()).price_series().unwrap();
+    let bins = histogram(series.iter().map(|(_, price)| *price), 10.).unwrap();
+    assert_eq!(
+        bins.iter().map(|bin| bin.count).sum::<usize>(),
+        series.len()
+    );
+    let chart = fixture.path("histogram.svg");
+    Graphing::new(&chart)
+        .price_histogram(&bins, 10., true)
+        .unwrap();
+    assert!(fs::read_to_string(&chart).unwrap().starts_with("<svg"));
+}
+
+#[test]
+fn tou_periods_wrap_and_filter() {
+    let dir = std::env::temp_dir().join(format!("ea-tou-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("tou.toml");
+    fs::write(
+        &path,
+        "[[periods]]\nname = \"Peak\"\nstart = \"16:00\"\nend = \"21:00\"\ndays = \"weekdays\"\n\
+         [[periods]]\nname = \"Overnight\"\nstart = \"22:00\"\nend = \"06:00\"\n",
+    )
+    .unwrap();
+    let schedule = TouSchedule::load(&path).unwrap();
+    let at = |date: &str| NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M").unwrap();
+    // 2024-01-03 is a Wednesday, 2024-01-06 a Saturday.
+    assert_eq!(schedule.period(at("2024-01-03 16:00")), "Peak");
+    assert_eq!(schedule.period(at("2024-01-03 21:00")), "Off-peak");
+    assert_eq!(schedule.period(at("2024-01-06 17:00")), "Off-peak");
+    assert_eq!(schedule.period(at("2024-01-06 03:00")), "Overnight");
+    assert_eq!(schedule.names(), ["Peak", "Overnight", "Off-peak"]);
+
+    let fixture = Fixture::new("tou", &SyntheticData::default());
+    let summaries = Compute::new(&fixture.prices())
+        .tou_summaries(&fixture.gen(), &schedule)
+        .unwrap();
+    let labels: Vec<_> = summaries.iter().map(|s| s.label.as_str()).collect();
+    assert_eq!(labels, schedule.names());
+
+    fs::write(
+        &path,
+        "[[periods]]\nname = \"x\"\nstart = \"10:00\"\nend = \"11:00\"\nmonths = [13]\n",
+    )
+    .unwrap();
+    assert!(TouSchedule::load(&path).is_err());
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn tariffs_bill_the_load_profile() {
+    let dir = std::env::temp_dir().join(format!("ea-tariff-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let load_csv = dir.join("load.csv");
+    fs::write(&load_csv, "time,kw\n00:00,1.0\n16:00,2.0\n21:00,1.0\n").unwrap();
+    let load = LoadProfile::load(&load_csv).unwrap();
+    assert!((load.daily_kwh() - 29.).abs() < 1e-9);
+
+    let tariffs_toml = dir.join("tariffs.toml");
+    fs::write(
+        &tariffs_toml,
+        "[[tariffs]]\nname = \"Flat\"\nrate = 0.3\nmonthly_charge = 36.5\n\
+         [[tariffs]]\nname = \"TOU\"\nrate = 0.2\nrates = { Peak = 0.5 }\n\
+         [[tariffs.periods]]\nname = \"Peak\"\nstart = \"16:00\"\nend = \"21:00\"\n\
+         [[tariffs]]\nname = \"Real-time\"\nrate = 0.0\nwholesale = true\n",
+    )
+    .unwrap();
+    let tariffs = Tariff::load_all(&tariffs_toml).unwrap();
+
+    let fixture = Fixture::new("tariff", &SyntheticData::default());
+    let prices = Compute::new(&fixture.prices()).price_series().unwrap();
+    let days = prices.len() as f64 / Compute::slots_per_day() as f64;
+    let costs = tariff::household_costs(&prices, &load, &tariffs).unwrap();
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-6 * b.abs().max(1.);
+
+    assert!(close(costs[0].kwh, 29. * days));
+    assert!(close(costs[0].fixed_cost, 1.2 * days));
+    assert!(close(costs[0].energy_cost, 0.3 * 29. * days));
+    // 10 peak kWh at $0.50 and 19 off-peak at $0.20.
+    assert!(close(costs[1].energy_cost, 8.8 * days));
+    let wholesale: f64 = prices
+        .iter()
+        .map(|(t, lmp)| load.kwh(*t) * lmp / 1000.)
+        .sum();
+    assert!(close(costs[2].energy_cost, wholesale));
+
+    fs::write(
+        &tariffs_toml,
+        "[[tariffs]]\nname = \"x\"\nrate = 0.2\nrates = { Peak = 0.5 }\n",
+    )
+    .unwrap();
+    assert!(Tariff::load_all(&tariffs_toml).is_err());
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn charging_fills_the_cheapest_slots() {
+    let time = |s: &str| NaiveTime::parse_from_str(s, "%H:%M").unwrap();
+    let scenario = ChargingScenario {
+        kwh: 1.,
+        power_kw: 6.,
+        start: time("23:50"),
+        end: time("00:10"),
+        objective: ChargingObjective::Cost,
+    };
+    let slots = scenario.slots();
+    assert_eq!(slots.len(), 4);
+    assert_eq!(slots[0], Compute::time_to_idx_5min(23, 50));
+    assert_eq!(slots[2], 0);
+
+    // Each slot delivers 0.5 kWh, so the session needs two of them.
+    let prices = [100., 80., 20., 40.];
+    let intensity = [300., 100., 500., 400.];
+    let plan = scenario
+        .plan("day".to_string(), &prices, Some(&intensity))
+        .unwrap();
+    assert!((plan.naive_cost - 0.09).abs() < 1e-9);
+    assert!((plan.optimized_cost - 0.03).abs() < 1e-9);
+    assert!((plan.optimized_price - 30.).abs() < 1e-9);
+    assert_eq!(plan.best_slot, "00:00");
+    // 0.5 kWh at 300 and 100 kg/MWh naively, at 500 and 400 when cheapest.
+    assert!((plan.naive_kg.unwrap() - 0.2).abs() < 1e-9);
+    assert!((plan.optimized_kg.unwrap() - 0.45).abs() < 1e-9);
+    let steps: Vec<_> = plan
+        .schedule
+        .iter()
+        .map(|step| (step.time.as_str(), step.kwh, step.price, step.kg_per_mwh))
+        .collect();
+    assert_eq!(
+        steps,
+        [
+            ("00:00", 0.5, 20., Some(500.)),
+            ("00:05", 0.5, 40., Some(400.))
+        ]
+    );
+
+    // For emissions the cleanest slots go first, at a higher price.
+    let clean = ChargingScenario {
+        objective: ChargingObjective::Emissions,
+        ..scenario
+    };
+    let plan = clean
+        .plan("day".to_string(), &prices, Some(&intensity))
+        .unwrap();
+    assert_eq!(plan.best_slot, "23:55");
+    assert!((plan.optimized_kg.unwrap() - 0.2).abs() < 1e-9);
+    assert!((plan.optimized_cost - 0.09).abs() < 1e-9);
+    assert!(clean.plan("day".to_string(), &prices, None).is_err());
+    let plan = scenario.plan("day".to_string(), &prices, None).unwrap();
+    assert_eq!(plan.naive_kg, None);
+
+    let needy = ChargingScenario {
+        kwh: 3.,
+        ..scenario
+    };
+    assert!(needy.plan("day".to_string(), &[1.; 4], None).is_err());
+
+    let mut sources = [0.; 14];
+    sources[EnergyGenCsvRow::source_idx("Natural Gas").unwrap()] = 300.;
+    sources[EnergyGenCsvRow::source_idx("Solar").unwrap()] = 100.;
+    sources[EnergyGenCsvRow::source_idx("Batteries").unwrap()] = 500.;
+    assert_eq!(carbon_intensity(&sources), 300.);
+
+    let fixture = Fixture::new("charging", &SyntheticData::default());
+    let price_csv = fixture.prices();
+    let gen_csv = fixture.gen();
+    let prices = Compute::new(&price_csv);
+    let plans = prices.charging_plans(&scenario, true, None).unwrap();
+    assert!(plans.iter().all(|plan| plan.savings >= 0.));
+    let overnight = ChargingScenario {
+        kwh: 20.,
+        start: time("18:00"),
+        end: time("07:00"),
+        ..clean
+    };
+    let plans = prices
+        .charging_plans(&overnight, true, Some(&gen_csv))
+        .unwrap();
+    for plan in &plans {
+        assert!(plan.kg_saved.unwrap() >= 0.);
+        assert!((plan.schedule.iter().map(|step| step.kwh).sum::<f64>() - 20.).abs() < 1e-9);
+    }
+
+    fixture.cli(&[
+        "optimize-charging",
+        "prices.csv",
+        "charging.csv",
+        "--per-day",
+        "--gen-csv",
+        "gen.csv",
+        "--objective",
+        "emissions",
+        "--kwh",
+        "20",
+        "--power-kw",
+        "6",
+        "--schedule-csv",
+        "schedule.csv",
+    ]);
+    let schedule = fs::read_to_string(fixture.path("schedule.csv")).unwrap();
+    assert!(schedule.starts_with("label,time,kwh,price,kg_per_mwh\n"));
+    let steps: usize = plans.iter().map(|plan| plan.schedule.len()).sum();
+    assert_eq!(schedule.lines().count(), steps + 1);
+    let summary = fs::read_to_string(fixture.path("charging.csv")).unwrap();
+    assert!(summary.starts_with(
+        "label,naive_cost,optimized_cost,savings,naive_price,optimized_price,naive_kg,optimized_kg,kg_saved,best_slot\n"
+    ));
+}
+
+#[test]
+fn water_years_split_at_october() {
+    let dir = std::env::temp_dir().join(format!("ea-water-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("water_years.toml");
+    fs::write(&path, "2023 = \"wet\"\n2024 = \"above-normal\"\n").unwrap();
+    let years = WaterYears::load(&path).unwrap();
+    fs::remove_dir_all(&dir).ok();
+
+    let data = SyntheticData {
+        start: NaiveDate::from_ymd_opt(2023, 9, 28).unwrap(),
+        days: 6,
+        ..Default::default()
+    };
+    let fixture = Fixture::new("water_years", &data);
+    let summaries = Compute::new(&fixture.prices())
+        .water_year_summaries(&fixture.gen(), &[], &years)
+        .unwrap();
+    let labels: Vec<_> = summaries.iter().map(|s| s.label.as_str()).collect();
+    assert_eq!(labels, ["WY2023 (Wet)", "WY2024 (Above normal)"]);
+
+    let profiles = Compute::new(&fixture.gen())
+        .average_gen_5min_by_water_year(&[], &years)
+        .unwrap();
+    assert_eq!(profiles.len(), 2);
+    assert!(profiles
+        .iter()
+        .all(|(_, gen)| gen.len() == Compute::slots_per_day()));
+
+    // Two datasets of one water year pool into one summary whose average is
+    // that of their equally many intervals together.
+    let first = Fixture::new(
+        "water_years_first",
+        &SyntheticData {
+            start: NaiveDate::from_ymd_opt(2023, 10, 2).unwrap(),
+            days: 3,
+            ..Default::default()
+        },
+    );
+    let second = Fixture::new(
+        "water_years_second",
+        &SyntheticData {
+            start: NaiveDate::from_ymd_opt(2023, 10, 5).unwrap(),
+            days: 3,
+            seed: 7,
+            ..Default::default()
+        },
+    );
+    let alone = |fixture: &Fixture| {
+        let summaries = Compute::new(&fixture.prices())
+            .water_year_summaries(&fixture.gen(), &[], &years)
+            .unwrap();
+        let profiles = Compute::new(&fixture.gen())
+            .average_gen_5min_by_water_year(&[], &years)
+            .unwrap();
+        (summaries[0].avg_price, profiles[0].1.clone())
+    };
+    let ((first_price, first_gen), (second_price, second_gen)) = (alone(&first), alone(&second));
+    assert_ne!(first_price, second_price);
+
+    let others = [(second.prices(), second.gen())];
+    let pooled = Compute::new(&first.prices())
+        .water_year_summaries(&first.gen(), &others, &years)
+        .unwrap();
+    assert_eq!(pooled.len(), 1);
+    assert_eq!(pooled[0].label, "WY2024 (Above normal)");
+    assert!((pooled[0].avg_price - (first_price + second_price) / 2.).abs() < 1e-9);
+
+    let pooled = Compute::new(&first.gen())
+        .average_gen_5min_by_water_year(&[second.gen()], &years)
+        .unwrap();
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+    let noon = Compute::time_to_idx_5min(12, 0);
+    let expected = (first_gen[noon][solar] + second_gen[noon][solar]) / 2.;
+    assert!((pooled[0].1[noon][solar] - expected).abs() < 1e-6);
+    // Datasets out of time order are refused rather than interleaved.
+    assert!(Compute::new(&second.gen())
+        .average_gen_5min_by_water_year(&[first.gen()], &years)
+        .is_err());
+
+    fs::write(first.path("water_years.toml"), "2024 = \"above-normal\"\n").unwrap();
+    let second_prices = second.prices();
+    let second_gen = second.gen();
+    first.cli(&[
+        "analyze",
+        "hydro-years",
+        "prices.csv",
+        "gen.csv",
+        "water_years.toml",
+        "--dataset",
+        second_prices.to_str().unwrap(),
+        second_gen.to_str().unwrap(),
+        "--out-csv",
+        "hydro_years.csv",
+    ]);
+    let csv = fs::read_to_string(first.path("hydro_years.csv")).unwrap();
+    assert_eq!(csv.lines().count(), 2);
+    assert!(csv
+        .lines()
+        .nth(1)
+        .unwrap()
+        .starts_with("WY2024 (Above normal),"));
+}
+
+#[test]
+fn source_price_correlation_matches_a_two_pass_pearson() {
+    let data = SyntheticData::default();
+    let fixture = Fixture::new("correlation", &data);
+    let price_csv = fixture.prices();
+    let prices = Compute::new(&price_csv);
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+
+    // (hour, solar, price) for every interval, joined by time of day.
+    let mut rows: Vec<(usize, f64, f64)> = Vec::new();
+    for offset in 0..data.days {
+        let date = data.start + TimeDelta::days(offset as i64);
+        let day = prices.day_profile(&fixture.gen(), date).unwrap();
+        for ((slot, price), (gen_slot, sources)) in day.prices.iter().zip(&day.gen) {
+            assert_eq!(slot, gen_slot);
+            let (hour, _) = Compute::idx_5min_to_time(*slot);
+            rows.push((hour as usize, sources[solar], *price));
+        }
+    }
+    let pearson = |pairs: Vec<(f64, f64)>| {
+        let n = pairs.len() as f64;
+        let mean_x = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+        let cov: f64 = pairs.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+        let var_x: f64 = pairs.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+        let var_y: f64 = pairs.iter().map(|p| (p.1 - mean_y).powi(2)).sum();
+        cov / (var_x * var_y).sqrt()
+    };
+
+    let corr = prices.source_price_correlation(&fixture.gen()).unwrap();
+    let overall = pearson(rows.iter().map(|r| (r.1, r.2)).collect());
+    assert!((corr.overall[solar] - overall).abs() < 1e-9);
+    // Synthetic prices sag as solar rises through the middle of the day.
+    assert!(overall < 0.);
+    let noon = pearson(
+        rows.iter()
+            .filter(|r| r.0 == 12)
+            .map(|r| (r.1, r.2))
+            .collect(),
+    );
+    assert!((corr.hourly[12][solar] - noon).abs() < 1e-9);
+    // Solar never runs at midnight, so its correlation there is undefined.
+    assert!(corr.hourly[0][solar].is_nan());
+}
+
+#[test]
+fn solar_regimes_split_on_solar_output() {
+    let data = SyntheticData::default();
+    let fixture = Fixture::new("solar_regimes", &data);
+    let price_csv = fixture.prices();
+    let prices = Compute::new(&price_csv);
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+    let threshold = 1000.;
+
+    // [price sum, count, solar MW, total MW, solar value] in each regime.
+    let mut regimes = [[0.; 5]; 2];
+    for offset in 0..data.days {
+        let date = data.start + TimeDelta::days(offset as i64);
+        let day = prices.day_profile(&fixture.gen(), date).unwrap();
+        for ((_, price), (_, sources)) in day.prices.iter().zip(&day.gen) {
+            let regime = &mut regimes[usize::from(sources[solar] <= threshold)];
+            regime[0] += price;
+            regime[1] += 1.;
+            regime[2] += sources[solar];
+            regime[3] += sources[0];
+            regime[4] += sources[solar] * price;
+        }
+    }
+
+    let summaries = prices
+        .solar_regime_summaries(&fixture.gen(), threshold)
+        .unwrap();
+    let labels: Vec<&str> = summaries.iter().map(|s| s.label.as_str()).collect();
+    assert_eq!(labels, ["Solar hours", "Non-solar hours"]);
+    for (summary, [price, count, solar_mw, total_mw, value]) in summaries.iter().zip(regimes) {
+        assert!((summary.avg_price - price / count).abs() < 1e-9);
+        assert!((summary.shares[solar] - solar_mw / total_mw).abs() < 1e-9);
+        assert!((summary.values[solar] - value / solar_mw).abs() < 1e-9);
+    }
+    // Synthetic solar depresses midday prices.
+    assert!(summaries[0].avg_price < summaries[1].avg_price);
+}
+
+#[test]
+fn complementarity_combines_variances() {
+    let mut corr = Correlation::default();
+    for (x, y) in [(1., 4.), (2., 2.), (3., 3.), (6., 1.)] {
+        corr.push(x, y);
+    }
+    // x has mean 3 and sample variance 14/3, y mean 2.5 and 5/3, and
+    // their sums 5, 4, 6, 7 have sample variance 5/3.
+    let (var_x, var_y, cov) = corr.variances();
+    assert!((var_x - 14. / 3.).abs() < 1e-9);
+    assert!((var_y - 5. / 3.).abs() < 1e-9);
+    assert!((var_x + var_y + 2. * cov - 5. / 3.).abs() < 1e-9);
+
+    let fixture = Fixture::new("complementarity", &SyntheticData::default());
+    let slots = Compute::new(&fixture.gen())
+        .wind_solar_complementarity()
+        .unwrap();
+    assert_eq!(slots.len(), Compute::slots_per_day());
+    let noon = &slots[Compute::time_to_idx_5min(12, 0)];
+    assert!((noon.combined_mw - noon.wind_mw - noon.solar_mw).abs() < 1e-6);
+    assert!(noon.combined_cv.is_finite());
+    assert!(slots[0].solar_cv.is_nan());
+}
+
+#[test]
+fn net_load_peaks_rank_hours() {
+    let fixture = Fixture::new("net_load", &SyntheticData::default());
+    let peaks = Compute::new(&fixture.prices())
+        .net_load_peaks(&fixture.gen(), 10)
+        .unwrap();
+    assert_eq!(peaks.hours.len(), 10);
+    assert!(peaks.hours.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    let lowest_peak = peaks.hours.last().unwrap().1;
+    assert!(peaks.peak.1 >= lowest_peak);
+    assert!(peaks.rest.1 < peaks.peak.1);
+    assert!((peaks.peak.0.shares[0] - 1.).abs() < 1e-9);
+
+    let hours = SyntheticData::default().days * 24;
+    assert!(Compute::new(&fixture.prices())
+        .net_load_peaks(&fixture.gen(), hours)
+        .is_err());
+}
+
+#[test]
+fn forecast_baselines_look_back() {
+    let fixture = Fixture::new("forecast", &SyntheticData::default());
+    let series = Compute::new(&fixture.prices()).price_series().unwrap();
+    let slots = Compute::slots_per_day();
+
+    let yesterday = Baseline::Yesterday.backtest(&series);
+    assert_eq!(yesterday.len(), series.len() - slots);
+    assert_eq!(yesterday[0].time, series[slots].0);
+    assert_eq!(yesterday[0].forecast, series[0].1);
+    assert_eq!(
+        Baseline::LastWeek.backtest(&series).len(),
+        series.len() - 7 * slots
+    );
+
+    let next = Baseline::SeasonalAverage.next_day(&series).unwrap();
+    assert_eq!(next.len(), slots);
+    let last_day = &series[series.len() - slots..];
+    assert!(next[0].0 > last_day[slots - 1].0);
+
+    let errors = Baseline::Yesterday.errors(&yesterday).unwrap();
+    assert!(errors.mae > 0. && errors.mape.unwrap() > 0.);
+    let perfect: Vec<_> = yesterday
+        .iter()
+        .map(|f| Forecast {
+            forecast: f.actual,
+            ..*f
+        })
+        .collect();
+    assert_eq!(Baseline::Yesterday.errors(&perfect).unwrap().mae, 0.);
+
+    // With every actual price within $1 of zero, there's no MAPE to give.
+    let near_zero: Vec<_> = yesterday
+        .iter()
+        .map(|f| Forecast {
+            actual: 0.5,
+            forecast: 2.,
+            ..*f
+        })
+        .collect();
+    let errors = Baseline::Yesterday.errors(&near_zero).unwrap();
+    assert_eq!(errors.mae, 1.5);
+    assert_eq!(errors.mape, None);
+}
+
+#[test]
+fn gaps_in_one_slot_are_rejected() {
+    // Dropping one interval per day always hits the same time slot.
+    let data = SyntheticData {
+        days: 14,
+        price_gap_every: Some(Compute::slots_per_day()),
+        ..Default::default()
+    };
+    let fixture = Fixture::new("uneven", &data);
+    assert!(Compute::new(&fixture.prices())
+        .average_price_5min()
+        .is_err());
+
+    // An explicit minimum can let the thin slot through, or only warn.
+    let prices = fixture.prices();
+    let lenient = |min_samples, on_few| {
+        Compute::new(&prices)
+            .with_samples(SampleCheck {
+                min_samples: Some(min_samples),
+                on_few,
+            })
+            .sampled_price_profile(Profile::Day)
+    };
+    let samples = lenient(0, FewSamples::Error).unwrap().samples;
+    assert_eq!(samples.iter().min(), Some(&0));
+    assert_eq!(samples.iter().max(), Some(&14));
+    assert!(lenient(1, FewSamples::Error).is_err());
+    assert!(lenient(1, FewSamples::Warn).is_ok());
+}
+
+#[test]
+fn written_price_minutes_count_each_slots_samples() {
+    // The last interval of every day is missing, so 23:55 has no samples.
+    let data = SyntheticData {
+        days: 14,
+        price_gap_every: Some(Compute::slots_per_day()),
+        ..Default::default()
+    };
+    let fixture = Fixture::new("slot_samples", &data);
+    let mut by_slot = vec![(0., 0); Compute::slots_per_day()];
+    for row in convert::read_rows::<EnergyPriceCsvRow>(&fixture.prices()).unwrap() {
+        let row = row.unwrap();
+        let slot = &mut by_slot[Compute::time_to_idx_5min(row.hour, row.minute)];
+        slot.0 += row.lmp_avg;
+        slot.1 += 1;
+    }
+    assert_eq!(by_slot[Compute::slots_per_day() - 1].1, 0);
+
+    // By default a slot may trail the fullest by twelve days.
+    let stderr = fixture.cli_err(&["write-price-minutes", "prices.csv", "minutes.csv"]);
+    assert!(
+        stderr.contains("1 of 288 slots have fewer than 2 samples, the first being 23:55 with 0"),
+        "{stderr}"
+    );
+    let stderr = fixture.cli_err(&[
+        "write-price-minutes",
+        "prices.csv",
+        "minutes.csv",
+        "--min-samples",
+        "15",
+        "--few-samples",
+        "error",
+    ]);
+    assert!(stderr.contains("288 of 288 slots"), "{stderr}");
+
+    fixture.cli(&[
+        "write-price-minutes",
+        "prices.csv",
+        "minutes.csv",
+        "--min-samples",
+        "1",
+        "--few-samples",
+        "warn",
+    ]);
+    let written = fs::read_to_string(fixture.path("minutes.csv")).unwrap();
+    let mut lines = written.lines();
+    assert_eq!(lines.next(), Some("prices,samples"));
+    let slots: Vec<(f64, usize)> = lines
+        .map(|line| {
+            let (price, samples) = line.split_once(',').unwrap();
+            (price.parse().unwrap(), samples.parse().unwrap())
+        })
+        .collect();
+    assert_eq!(slots.len(), by_slot.len());
+    for (idx, ((price, samples), (sum, count))) in slots.iter().zip(&by_slot).enumerate() {
+        assert_eq!(samples, count, "slot {idx}");
+        if *count > 0 {
+            let mean = sum / *count as f64;
+            assert!((price - mean).abs() < 1e-6, "slot {idx}: {price} vs {mean}");
+        }
+    }
+}
+
+#[test]
+fn generation_is_deterministic() {
+    let data = SyntheticData {
+        days: 1,
+        ..Default::default()
+    };
+    let first = Fixture::new("seed_a", &data);
+    let second = Fixture::new("seed_b", &data);
+    let reseeded = Fixture::new("seed_c", &SyntheticData { seed: 1, ..data });
+    let read = |fixture: &Fixture| fs::read(fixture.gen()).unwrap();
+    assert_eq!(read(&first), read(&second));
+    assert_ne!(read(&first), read(&reseeded));
+}
+
+#[test]
+fn gen_columns_are_found_by_header_name() {
+    let fixture = Fixture::new("shuffled", &SyntheticData::default());
+    let raw = fs::read_to_string(fixture.path("gen_raw.csv")).unwrap();
+    // Reverses every column after the preamble and puts an unknown column
+    // in the middle, which the mapping should skip.
+    let shuffled: Vec<String> = raw
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| {
+            if idx < 3 {
+                return line.to_string();
+            }
+            let mut fields: Vec<&str> = line.split(',').rev().collect();
+            fields.insert(
+                7,
+                if idx == 3 {
+                    "Pumped Storage (MW)"
+                } else {
+                    "-999.0"
+                },
+            );
+            fields.join(",")
+        })
+        .collect();
+    let shuffled_raw = fixture.path("shuffled_raw.csv");
+    fs::write(&shuffled_raw, shuffled.join("\n")).unwrap();
+
+    let out = fixture.path("shuffled.csv");
+    convert::convert_energy_gen_csv(
+        &[shuffled_raw],
+        &out,
+        CsvFormat::default(),
+        TotalCheck::Report,
+        OnError::Abort,
+        WriteOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(&out).unwrap(),
+        fs::read_to_string(fixture.gen()).unwrap()
+    );
+}
+
+#[test]
+fn unknown_header_is_rejected() {
+    let fixture = Fixture::new("header", &SyntheticData::default());
+    let raw = fs::read_to_string(fixture.path("gen_raw.csv")).unwrap();
+    let renamed = fixture.path("renamed_raw.csv");
+    fs::write(&renamed, raw.replace("Solar Generation (MW)", "Sun (MW)")).unwrap();
+    let err = convert::convert_energy_gen_csv(
+        &[renamed],
+        &fixture.path("out.csv"),
+        CsvFormat::default(),
+        TotalCheck::Report,
+        OnError::Abort,
+        WriteOptions::default(),
+    )
+    .unwrap_err();
+    let EnergyAnalysisError::SchemaMismatch { reason, .. } = err else {
+        panic!("expected a schema mismatch, got {err}");
+    };
+    // The error names what it didn't recognize and what each layout lacks.
+    assert!(
+        reason.contains(r#"Unknown headers: ["Sun (MW)"]"#),
+        "{reason}"
+    );
+    for schema in &convert::GEN_SCHEMAS {
+        assert!(
+            reason.contains(&format!(
+                r#"'{}' is missing ["Solar Generation (MW)"]"#,
+                schema.name
+            )),
+            "{reason}"
+        );
+    }
+}
+
+#[test]
+fn every_registered_layout_converts() {
+    let fixture = Fixture::new("layouts", &SyntheticData::default());
+    let preamble = "Title\nDescription\nSource\n";
+    let timestamp = "2024-03-10 01:55:00";
+
+    for (idx, schema) in convert::PRICE_SCHEMAS.iter().enumerate() {
+        let raw = fixture.path(&format!("price_layout_{idx}.csv"));
+        let values: Vec<String> = (1..schema.columns.len())
+            .map(|col| format!("{}.0", 10 * col))
+            .collect();
+        fs::write(
+            &raw,
+            format!(
+                "{preamble}{},Notes\n{timestamp},{},n/a\n",
+                schema.columns.join(","),
+                values.join(",")
+            ),
+        )
+        .unwrap();
+        let out = fixture.path(&format!("price_layout_{idx}_out.csv"));
+        convert::convert_energy_price_csv(
+            &[raw],
+            &out,
+            CsvFormat::default(),
+            OnError::Abort,
+            WriteOptions::default(),
+        )
+        .unwrap();
+        let rows: Vec<EnergyPriceCsvRow> = convert::read_rows(&out)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(rows.len(), 1, "{}", schema.name);
+        assert_eq!(rows[0].timestamp, timestamp);
+        assert_eq!((rows[0].hour, rows[0].minute), (1, 55));
+        assert_eq!(rows[0].lmp_avg, 20., "{}", schema.name);
+        assert_eq!(rows[0].lmps(), [10., 20., 30.], "{}", schema.name);
+    }
+
+    for (idx, schema) in convert::GEN_SCHEMAS.iter().enumerate() {
+        let raw = fixture.path(&format!("gen_layout_{idx}.csv"));
+        let sources: Vec<String> = (1..=13).map(|mw| format!("{mw}.0")).collect();
+        fs::write(
+            &raw,
+            format!(
+                "{preamble}{}\n2024-03-10 09:55:00,{timestamp},2024-03-10 02:00:00,2024-03-10,2,91.0,{}\n",
+                schema.columns.join(","),
+                sources.join(",")
+            ),
+        )
+        .unwrap();
+        let out = fixture.path(&format!("gen_layout_{idx}_out.csv"));
+        convert::convert_energy_gen_csv(
+            &[raw],
+            &out,
+            CsvFormat::default(),
+            TotalCheck::Report,
+            OnError::Abort,
+            WriteOptions::default(),
+        )
+        .unwrap();
+        let rows: Vec<EnergyGenCsvRow> = convert::read_rows(&out)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(rows.len(), 1, "{}", schema.name);
+        assert_eq!(rows[0].local_timestamp_start, timestamp);
+        assert_eq!((rows[0].hour, rows[0].minute), (1, 55));
+        assert_eq!(rows[0].total, 91.);
+        assert_eq!(rows[0].battery, 1.);
+        assert_eq!(rows[0].wind, 13.);
+    }
+}
+
+#[test]
+fn mismatched_totals_are_found_and_recomputed() {
+    let fixture = Fixture::new("totals", &SyntheticData::default());
+    let raw = fs::read_to_string(fixture.path("gen_raw.csv")).unwrap();
+    let mut lines: Vec<String> = raw.lines().map(str::to_string).collect();
+    // The first data row follows three title lines and the header.
+    let mut fields: Vec<String> = lines[4].split(',').map(str::to_string).collect();
+    let total: f64 = fields[5].parse().unwrap();
+    fields[5] = format!("{:.1}", total + 500.);
+    lines[4] = fields.join(",");
+    let bumped = fixture.path("bumped_raw.csv");
+    fs::write(&bumped, lines.join("\n")).unwrap();
+
+    let kept = fixture.path("kept.csv");
+    convert::convert_energy_gen_csv(
+        &[&bumped],
+        &kept,
+        CsvFormat::default(),
+        TotalCheck::Report,
+        OnError::Abort,
+        WriteOptions::default(),
+    )
+    .unwrap();
+    let mismatches = Compute::new(&kept).total_mismatches().unwrap();
+    assert_eq!(mismatches.len(), 1);
+    assert!((mismatches[0].difference - 500.).abs() < 1e-6);
+
+    let fixed = fixture.path("fixed.csv");
+    convert::convert_energy_gen_csv(
+        &[&bumped],
+        &fixed,
+        CsvFormat::default(),
+        TotalCheck::Recompute,
+        OnError::Abort,
+        WriteOptions::default(),
+    )
+    .unwrap();
+    assert!(Compute::new(&fixed).total_mismatches().unwrap().is_empty());
+}
+
+#[test]
+fn semicolon_decimal_comma_files_parse_the_same() {
+    let fixture = Fixture::new("locale", &SyntheticData::default());
+    let format = CsvFormat {
+        delimiter: b';',
+        decimal_comma: true,
+    };
+    let localize = |raw: &str| {
+        let localized: String = fs::read_to_string(fixture.path(raw))
+            .unwrap()
+            .lines()
+            .map(|line| line.replace(',', ";").replace('.', ",") + "\n")
+            .collect();
+        let path = fixture.path(&format!("localized_{raw}"));
+        fs::write(&path, localized).unwrap();
+        [path]
+    };
+    let out = fixture.path("localized.csv");
+
+    convert::convert_energy_price_csv(
+        &localize("price_raw.csv"),
+        &out,
+        format,
+        OnError::Abort,
+        WriteOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(fs::read(&out).unwrap(), fs::read(fixture.prices()).unwrap());
+    convert::convert_energy_gen_csv(
+        &localize("gen_raw.csv"),
+        &out,
+        format,
+        TotalCheck::Report,
+        OnError::Abort,
+        WriteOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(fs::read(&out).unwrap(), fs::read(fixture.gen()).unwrap());
+
+    let format = CsvFormat::default();
+    assert_eq!(format.parse_number(" \"1,234.5\" ").unwrap(), 1234.5);
+    assert_eq!(format.parse_number("-12").unwrap(), -12.);
+    assert!(format.parse_number("12a").is_err());
+    let format = CsvFormat {
+        decimal_comma: true,
+        ..format
+    };
+    assert_eq!(format.parse_number("1.234,5").unwrap(), 1234.5);
+}
+
+#[test]
+fn xlsx_workbooks_parse_like_their_csv() {
+    let fixture = Fixture::new("xlsx", &SyntheticData::default());
+    let raw = fs::read_to_string(fixture.path("gen_raw.csv")).unwrap();
+
+    // EIA's workbooks store timestamps and numbers as typed cells, sometimes
+    // behind a notes sheet.
+    let mut workbook = rust_xlsxwriter::Workbook::new();
+    workbook
+        .add_worksheet()
+        .set_name("Notes")
+        .unwrap()
+        .write(0, 0, "Synthetic test data")
+        .unwrap();
+    let sheet = workbook.add_worksheet().set_name("Fuel mix").unwrap();
+    let timestamp = rust_xlsxwriter::Format::new().set_num_format("yyyy-mm-dd hh:mm:ss");
+    let date = rust_xlsxwriter::Format::new().set_num_format("yyyy-mm-dd");
+    for (row, line) in raw.lines().enumerate() {
+        for (col, field) in line.split(',').enumerate() {
+            let (row, col) = (row as u32, col as u16);
+            if row < 4 {
+                sheet.write(row, col, field).unwrap();
+            } else if let Ok(time) = convert::parse_timestamp(field) {
+                sheet
+                    .write_with_format(row, col, &time, &timestamp)
+                    .unwrap();
+            } else if let Ok(day) = chrono::NaiveDate::parse_from_str(field, "%Y-%m-%d") {
+                sheet.write_with_format(row, col, &day, &date).unwrap();
+            } else {
+                sheet
+                    .write(row, col, field.parse::<f64>().unwrap())
+                    .unwrap();
+            }
+        }
+    }
+    let xlsx = fixture.path("gen_raw.xlsx");
+    workbook.save(&xlsx).unwrap();
+
+    let out = fixture.path("gen_xlsx.csv");
+    convert::convert_energy_gen_csv(
+        &[xlsx],
+        &out,
+        CsvFormat::default(),
+        TotalCheck::Report,
+        OnError::Abort,
+        WriteOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(&out).unwrap(),
+        fs::read_to_string(fixture.gen()).unwrap()
+    );
+}
+
+#[test]
+fn xlsx_tables_keep_numbers_as_numbers() {
+    use calamine::{Data, Reader};
+
+    let fixture = Fixture::new("xlsx_out", &SyntheticData::default());
+    let values = fixture.path("values.csv");
+    let (averages, qtys) = Compute::new(&fixture.prices())
+        .average_value_5min(&fixture.gen())
+        .unwrap();
+    convert::write_energy_value_averages(&values, &averages, &qtys, WriteOptions::default())
+        .unwrap();
+    let out = fixture.path("values.xlsx");
+    xlsx::write_tables(&out, &[("values: 5min", &values)], WriteOptions::default()).unwrap();
+
+    let mut workbook = calamine::open_workbook_auto(&out).unwrap();
+    assert_eq!(workbook.sheet_names(), ["values_ 5min"]);
+    let range = workbook.worksheet_range("values_ 5min").unwrap();
+    assert_eq!(range.height(), 15);
+    assert_eq!(range.get((1, 0)), Some(&Data::String("Total".to_string())));
+    assert!(matches!(range.get((1, 1)), Some(Data::Float(_))));
+}
+
+#[test]
+fn store_matches_csv() {
+    let data = SyntheticData {
+        gen_gap_every: Some(13),
+        ..Default::default()
+    };
+    let fixture = Fixture::new("store", &data);
+    let (price_store, gen_store) = (fixture.path("prices.bin"), fixture.path("gen.bin"));
+    Store::write_prices(&fixture.prices(), &price_store, WriteOptions::default()).unwrap();
+    Store::write_gen(&fixture.gen(), &gen_store, WriteOptions::default()).unwrap();
+
+    assert_eq!(
+        Compute::new(&fixture.prices())
+            .average_value_5min(&fixture.gen())
+            .unwrap(),
+        Compute::new(&price_store)
+            .average_value_5min(&gen_store)
+            .unwrap()
+    );
+    assert_eq!(
+        Compute::new(&fixture.gen()).average_gen_5min().unwrap(),
+        Compute::new(&gen_store).average_gen_5min().unwrap()
+    );
+
+    let store = Store::open(&price_store).unwrap();
+    let second_day = data.start.succ_opt().unwrap();
+    let rows = store.rows_between(second_day, second_day.succ_opt().unwrap());
+    assert_eq!(rows.len(), Compute::slots_per_day());
+    assert_eq!(rows.start, Compute::slots_per_day());
+}
+
+#[test]
+fn group_by_buckets_and_reduces_by_key() {
+    // Keys needn't be contiguous, and buckets keep their first appearance order.
+    let items = [("b", 1.), ("a", 2.), ("b", 3.), ("c", 4.), ("a", 6.)];
+    type Both = (Mean<f64>, Vec<f64>);
+    let groups: Vec<(&str, Both)> =
+        group_by(items.map(Ok), |(key, val)| Ok((key, (val, val)))).unwrap();
+    let groups: Vec<(&str, (f64, Vec<f64>))> = groups
+        .into_iter()
+        .map(|(key, reducer)| (key, reducer.finish()))
+        .collect();
+    assert_eq!(
+        groups,
+        [
+            ("b", (2., vec![1., 3.])),
+            ("a", (4., vec![2., 6.])),
+            ("c", (4., vec![4.])),
+        ]
+    );
+
+    let by_index: Vec<Mean<f64>> =
+        group_by_index([0, 1, 1, 3].map(Ok), 4, |idx| Ok((idx, idx as f64))).unwrap();
+    let counts: Vec<usize> = by_index.iter().map(Mean::count).collect();
+    assert_eq!(counts, [1, 2, 0, 1]);
+    assert!(by_index[2].finish().is_nan());
+    assert!(group_by_index::<_, Mean<f64>>([4].map(Ok), 4, |idx| Ok((idx, 0.))).is_err());
+
+    // The daily averages built on the engine match a hand-rolled average.
+    let fixture = Fixture::new("group_by", &SyntheticData::default());
+    let price_csv = fixture.prices();
+    let prices = Compute::new(&price_csv);
+    let mut slots = vec![(0., 0.); Compute::slots_per_day()];
+    for (time, price) in prices.price_series().unwrap() {
+        let slot = &mut slots[Compute::time_to_idx_5min(time.hour(), time.minute())];
+        slot.0 += price;
+        slot.1 += 1.;
+    }
+    for (average, (total, count)) in prices.average_price_5min().unwrap().iter().zip(slots) {
+        assert!((average - total / count).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn parallel_merge_matches_sequential() {
+    let fixture = Fixture::new("parallel", &SyntheticData::default());
+    let rows: Vec<(usize, [f64; 14])> = csv::Reader::from_path(fixture.gen())
+        .unwrap()
+        .deserialize()
+        .map(|row| {
+            let row: EnergyGenCsvRow = row.unwrap();
+            (row.hour as usize, row.sources())
+        })
+        .collect();
+    let days: Vec<Vec<_>> = rows
+        .chunks(Compute::slots_per_day())
+        .map(<[_]>::to_vec)
+        .collect();
+
+    let sequential: Vec<Mean<[f64; 14]>> =
+        group_by_index(rows.iter().copied().map(Ok), 24, Ok).unwrap();
+    let parallel: Vec<Mean<[f64; 14]>> = par_group_by_index(days.clone(), 24, Ok).unwrap();
+    for (seq, par) in sequential.into_iter().zip(parallel) {
+        assert_eq!(seq.count(), par.count());
+        for (a, b) in seq.finish().iter().zip(par.finish()) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    // Correlates every source with solar output, which stands in for price.
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+    let pair = |(hour, sources): (usize, [f64; 14])| Ok((hour, (sources, sources[solar])));
+    let sequential: Vec<CorrelationReducer> =
+        group_by_index(rows.iter().copied().map(Ok), 24, pair).unwrap();
+    let parallel: Vec<CorrelationReducer> = par_group_by_index(days, 24, pair).unwrap();
+    for (seq, par) in sequential.into_iter().zip(parallel) {
+        for (a, b) in seq.finish().iter().zip(par.finish()) {
+            assert!(
+                a.is_nan() && b.is_nan() || (a - b).abs() < 1e-9,
+                "{a} != {b}"
+            );
+        }
+    }
+}
+
+#[test]
+fn reports_summarize_each_month() {
+    let data = SyntheticData {
+        start: NaiveDate::from_ymd_opt(2024, 1, 29).unwrap(),
+        ..SyntheticData::default()
+    };
+    let fixture = Fixture::new("report", &data);
+    let price_csv = fixture.prices();
+    let prices = Compute::new(&price_csv);
+
+    let path = Report::generate(
+        &prices,
+        &fixture.gen(),
+        &fixture.path("md"),
+        ReportFormat::Markdown,
+        WriteOptions::default(),
+    )
+    .unwrap();
+    let markdown = fs::read_to_string(&path).unwrap();
+    for chart in ["prices.png", "gen.png", "values.png"] {
+        assert!(markdown.contains(&format!("]({chart})")));
+        assert!(fixture.path("md").join(chart).exists());
+    }
+    assert!(markdown.contains("## Monthly summary"));
+    assert!(markdown.contains("## Quarterly summary"));
+
+    // The week straddles January and February, and each month's row holds
+    // the mean of its own prices.
+    let series = prices.price_series().unwrap();
+    for month in ["2024-01", "2024-02"] {
+        let month_prices: Vec<f64> = series
+            .iter()
+            .filter(|(time, _)| Period::Month.label(time.date()) == month)
+            .map(|(_, price)| *price)
+            .collect();
+        let mean = month_prices.iter().sum::<f64>() / month_prices.len() as f64;
+        assert!(
+            markdown.contains(&format!("| {month} | ${mean:.2} |")),
+            "{month} should average ${mean:.2}"
+        );
+    }
+    assert!(markdown.contains("| 2024Q1 |"));
+
+    let path = Report::generate(
+        &prices,
+        &fixture.gen(),
+        &fixture.path("html"),
+        ReportFormat::Html,
+        WriteOptions::default(),
+    )
+    .unwrap();
+    let html = fs::read_to_string(&path).unwrap();
+    assert_eq!(html.matches("src=\"data:image/png;base64,").count(), 3);
+    assert!(!fixture.path("html").join("prices.png").exists());
+    assert!(html.contains("<td>2024-02</td>"));
+}
+
+#[test]
+fn pipeline_resolves_artifacts() {
+    let dir = std::env::temp_dir().join(format!("energy_analysis_toml_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("pipeline.toml");
+    let write = |steps: &str| {
+        let artifacts = "[artifacts]\nprices = \"data/prices.csv\"\n";
+        fs::write(&path, format!("{artifacts}{steps}")).unwrap();
+    };
+
+    write(
+        "[[steps]]\nrun = \"write-price-minutes\"\nargs = [\"{prices}\", \"out/{prices}.bak\"]\n",
+    );
+    let lines = Pipeline::load(&path).unwrap().command_lines().unwrap();
+    assert_eq!(
+        lines,
+        [[
+            "write-price-minutes",
+            "data/prices.csv",
+            "out/data/prices.csv.bak"
+        ]]
+    );
+
+    write("[[steps]]\nrun = \"write-gen-minutes\"\nargs = [\"{gen}\", \"out.csv\"]\n");
+    let err = Pipeline::load(&path).unwrap().command_lines().unwrap_err();
+    assert!(err.to_string().contains("\"gen\""), "{err}");
+
+    write("");
+    assert!(Pipeline::load(&path).is_err());
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn sun_times_match_published_tables() {
+    // San Francisco, 2024-06-20: sunrise 05:48, sunset 20:35 PDT.
+    let site = Site {
+        latitude: 37.77,
+        longitude: -122.42,
+        standard_offset: -8.,
+        us_dst: true,
+    };
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 6, 20).unwrap();
+    let times = site.sun_times(date).unwrap();
+    assert!((times.sunrise - (5. * 60. + 48.)).abs() < 3., "{times:?}");
+    assert!((times.sunset - (20. * 60. + 35.)).abs() < 3., "{times:?}");
+
+    let polar = Site {
+        latitude: 80.,
+        ..site
+    };
+    assert_eq!(polar.sun_times(date), None);
+}
+
+#[test]
+fn labels_match_source_names_loosely() {
+    let dir = std::env::temp_dir().join(format!("ea-labels-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("labels.toml");
+    fs::write(
+        &path,
+        "[sources]\n\"large hydro\" = \"Hidro grande\"\n[units]\nprice = \"€/MWh\"\n",
+    )
+    .unwrap();
+
+    let labels = Labels::load(&path).unwrap();
+    assert_eq!(labels.source("Large Hydro"), "Hidro grande");
+    assert_eq!(labels.source("Solar"), "Solar");
+    assert_eq!(labels.units.price, "€/MWh");
+    assert_eq!(labels.units.energy, "MWh");
+
+    fs::write(&path, "[sources]\nSolr = \"x\"\n").unwrap();
+    assert!(Labels::load(&path).is_err());
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn cache_skips_unchanged_inputs() {
+    let data = SyntheticData {
+        days: 2,
+        ..Default::default()
+    };
+    let fixture = Fixture::new("cache", &data);
+    let entries =
+        || fs::read_dir(fixture.path(".energy-analysis-cache")).map_or(0, |dir| dir.count());
+    let run = |out: &str, flags: &[&str]| {
+        let args = ["analyze", "price-minutes", "prices.csv", "--out-csv", out];
+        fixture.cli(&[&args[..], flags].concat());
+        fs::read_to_string(fixture.path(out)).unwrap()
+    };
+
+    let first = run("first.csv", &[]);
+    let cached = entries();
+    assert!(cached > 0);
+    // A hit is read back, so nothing new is cached.
+    assert_eq!(run("second.csv", &[]), first);
+    assert_eq!(entries(), cached);
+    assert_eq!(run("uncached.csv", &["--no-cache"]), first);
+    assert_eq!(entries(), cached);
+
+    let mut csv = fs::read_to_string(fixture.prices()).unwrap();
+    csv.push('\n');
+    fs::write(fixture.prices(), csv).unwrap();
+    run("third.csv", &[]);
+    assert!(entries() > cached);
+}
+
+#[test]
+fn provenance_sidecars_hash_the_inputs() {
+    let data = SyntheticData {
+        days: 2,
+        ..Default::default()
+    };
+    let fixture = Fixture::new("provenance", &data);
+    let prices = fixture.prices();
+    let out = fixture.path("price_averages.csv");
+    let command: Vec<String> = ["energy_analysis", "write-price-minutes"]
+        .map(String::from)
+        .to_vec();
+    let missing = fixture.path("missing.csv");
+    let record = || {
+        Provenance::new(
+            command.clone(),
+            "WritePriceMinutes".into(),
+            &[prices.clone(), missing.clone()],
+        )
+        .unwrap()
+    };
+
+    provenance::note("clipped_prices", "1 to 2");
+    let sidecar = record().write(&out, WriteOptions::default()).unwrap();
+    assert_eq!(sidecar, fixture.path("price_averages.csv.meta.json"));
+    let read = Provenance::read(&out).unwrap();
+    assert_eq!(read.command, command);
+    assert_eq!(read.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(read.notes["clipped_prices"], "1 to 2");
+    // Only files that exist are hashed, and the same content hashes the same.
+    assert_eq!(read.inputs.len(), 1);
+    assert_eq!(read.inputs, record().inputs);
+    assert!(record().notes.is_empty());
+
+    // The footer names the quarter the prices fall in.
+    let footer = read.footer(Rto::Caiso);
+    assert!(
+        footer.starts_with("CAISO 2024Q1, generated by energy-analysis v"),
+        "{footer}"
+    );
+    let chart = fixture.path("prices.svg");
+    let averages = Compute::new(&prices).average_price_5min().unwrap();
+    Graphing::new(&chart)
+        .with_footer(Some(footer))
+        .daily_price(&averages, Profile::Day)
+        .unwrap();
+    assert!(fs::read_to_string(&chart).unwrap().contains("CAISO 2024Q1"));
+
+    let mut csv = fs::read_to_string(&prices).unwrap();
+    csv.push('\n');
+    fs::write(&prices, csv).unwrap();
+    assert_ne!(record().inputs[0].hash, read.inputs[0].hash);
+}
+
+#[test]
+fn footers_name_the_span_version_and_date() {
+    // A week across the end of March spans two quarters.
+    let fixture = Fixture::new(
+        "footer",
+        &SyntheticData {
+            start: NaiveDate::from_ymd_opt(2024, 3, 28).unwrap(),
+            ..SyntheticData::default()
+        },
+    );
+    let chart = |flags: &[&str]| {
+        let args = [
+            "analyze",
+            "price-minutes",
+            "prices.csv",
+            "--out-png",
+            "prices.svg",
+            "--overwrite",
+        ];
+        fixture.cli(&[&args[..], flags].concat());
+        fs::read_to_string(fixture.path("prices.svg")).unwrap()
+    };
+    let footer = format!(
+        "CAISO 2024Q1–2024Q2, generated by energy-analysis v{} on {}",
+        env!("CARGO_PKG_VERSION"),
+        chrono::Local::now().format("%Y-%m-%d")
+    );
+    assert!(chart(&["--footer"]).contains(&footer), "{footer}");
+    assert!(!chart(&[]).contains("generated by"));
+
+    fs::write(fixture.path("config.toml"), "footer = true\n").unwrap();
+    assert!(chart(&["--config", "config.toml"]).contains(&footer));
+}
+
+#[test]
+fn provenance_hashes_toml_configs_as_bytes() {
+    let fixture = Fixture::new("provenance_config", &SyntheticData::default());
+    let capacity = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/capacity.toml");
+    let out = fixture.path("capacity_factors.csv");
+    fixture.cli(&[
+        "analyze",
+        "capacity-factors",
+        "gen.csv",
+        capacity.to_str().unwrap(),
+        "--out-csv",
+        "capacity_factors.csv",
+    ]);
+
+    let read = Provenance::read(&out).unwrap();
+    let paths: Vec<_> = read.inputs.iter().map(|input| &input.path).collect();
+    assert_eq!(paths, [Path::new("gen.csv"), &capacity]);
+    // Hashed as plain bytes, like the same content under any other name.
+    let copy = fixture.path("capacity.txt");
+    fs::copy(&capacity, &copy).unwrap();
+    let plain = Provenance::new(vec![], String::new(), &[copy]).unwrap();
+    assert_eq!(read.inputs[1].hash, plain.inputs[0].hash);
+    assert_eq!(read.inputs[1].dates, None);
+
+    // The hash is the SHA-256 that `sha256sum` prints.
+    let abc = fixture.path("abc.txt");
+    fs::write(&abc, "abc").unwrap();
+    assert_eq!(
+        Provenance::new(vec![], String::new(), &[abc])
+            .unwrap()
+            .inputs[0]
+            .hash,
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
+
+#[test]
+fn service_sees_edits_and_removals() {
+    let fixture = Fixture::new("serve_watch", &SyntheticData::default());
+    let other = Fixture::new(
+        "serve_watch_other",
+        &SyntheticData {
+            seed: 7,
+            ..SyntheticData::default()
+        },
+    );
+    let service = Service::new(fixture.prices(), fixture.gen());
+    let target = "/price-profile?window=1h";
+    let first = service.get(target);
+    assert_eq!(service.get(target).body, first.body);
+
+    // Other prices under the same name, with a different modification time.
+    fs::copy(other.prices(), fixture.prices()).unwrap();
+    fs::File::options()
+        .write(true)
+        .open(fixture.prices())
+        .unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH)
+        .unwrap();
+    let edited = service.get(target);
+    assert_eq!(edited.status, 200);
+    assert_ne!(edited.body, first.body);
+
+    fs::remove_file(fixture.prices()).unwrap();
+    assert_eq!(service.get(target).status, 500);
+}
+
+#[test]
+fn legacy_commands_match_analyze() {
+    let fixture = Fixture::new("legacy", &SyntheticData::default());
+    let capacity = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/capacity.toml");
+    let capacity = capacity.to_str().unwrap();
+    let data = |name: &str| {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join(name);
+        path.to_str().unwrap().to_string()
+    };
+    let (load, tariffs) = (data("household_load.csv"), data("tariffs.toml"));
+    // Each hidden write- or graph- command, and the analyze command it
+    // stands for, with the inputs and options they share.
+    let pairs: [(&str, &str, &[&str]); 12] = [
+        ("price-minutes", "csv", &["prices.csv"]),
+        ("gen-minutes", "csv", &["gen.csv"]),
+        ("value-minutes", "csv", &["prices.csv", "gen.csv"]),
+        ("correlation", "csv", &["prices.csv", "gen.csv"]),
+        ("price-spikes", "csv", &["prices.csv", "--z-score", "3"]),
+        ("capacity-factors", "csv", &["gen.csv", capacity]),
+        ("arbitrage-spread", "csv", &["prices.csv"]),
+        (
+            "price-forecast",
+            "csv",
+            &["prices.csv", "--baseline", "last-week"],
+        ),
+        ("tariff-costs", "csv", &["prices.csv", &load, &tariffs]),
+        ("price-minutes", "png", &["prices.csv"]),
+        ("value-minutes", "png", &["prices.csv", "gen.csv"]),
+        ("price-forecast", "png", &["prices.csv"]),
+    ];
+    for (analysis, ext, args) in pairs {
+        let legacy = format!(
+            "{}-{analysis}",
+            if ext == "csv" { "write" } else { "graph" }
+        );
+        let (old, new) = (format!("old.{ext}"), format!("new.{ext}"));
+        // Outputs go after the positional inputs, before any options.
+        let split = args.iter().position(|arg| arg.starts_with("--"));
+        let (inputs, options) = args.split_at(split.unwrap_or(args.len()));
+        fixture.cli(
+            &[
+                &[legacy.as_str()],
+                inputs,
+                &[old.as_str()],
+                options,
+                &["--overwrite"],
+            ]
+            .concat(),
+        );
+        let out = format!("--out-{ext}");
+        fixture.cli(
+            &[
+                &["analyze", analysis],
+                inputs,
+                &[out.as_str(), new.as_str()],
+                options,
+                &["--overwrite"],
+            ]
+            .concat(),
+        );
+        assert_eq!(
+            fs::read(fixture.path(&old)).unwrap(),
+            fs::read(fixture.path(&new)).unwrap(),
+            "{legacy} differs from analyze {analysis}"
+        );
+    }
+
+    // Every hidden alias still parses, even where the fixture has no input
+    // for it to run on.
+    for alias in [
+        "write-gen-solar-battery",
+        "write-value-solar-battery",
+        "graph-gen-minutes",
+        "graph-gen-solar-battery",
+        "graph-value-solar-battery",
+        "write-value-profile",
+        "graph-value-profile",
+        "write-storage-sweep",
+        "graph-storage-sweep",
+        "write-price-impact",
+        "graph-price-impact",
+        "graph-price-spikes",
+        "graph-correlation",
+        "graph-capacity-factors",
+        "write-solar-split",
+        "graph-solar-split",
+        "write-tou-summary",
+        "graph-tou-summary",
+        "write-hydro-years",
+        "graph-hydro-years",
+        "write-complementarity",
+        "graph-complementarity",
+        "write-weather-correlation",
+        "graph-weather-correlation",
+        "write-net-load-peaks",
+        "graph-net-load-peaks",
+        "write-interchange",
+        "graph-interchange",
+        "write-ba-interchange",
+        "graph-ba-interchange",
+        "write-battery-split",
+        "graph-battery-split",
+        "graph-arbitrage-spread",
+        "write-forecast-errors",
+    ] {
+        fixture.cli(&[alias, "--help"]);
+    }
+}
+
+#[test]
+fn forecasts_and_tariffs_are_analyses() {
+    // Enough days for the seasonal baseline's four weeks of history.
+    let fixture = Fixture::new(
+        "analyze_forecast",
+        &SyntheticData {
+            days: 35,
+            ..SyntheticData::default()
+        },
+    );
+    fixture.cli(&[
+        "analyze",
+        "forecast-errors",
+        "prices.csv",
+        "--out-csv",
+        "errors.csv",
+        "--out-png",
+        "errors.svg",
+    ]);
+    let errors = fs::read_to_string(fixture.path("errors.csv")).unwrap();
+    let baselines: Vec<&str> = errors
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').next().unwrap())
+        .collect();
+    assert_eq!(baselines, ["yesterday", "last-week", "seasonal-average"]);
+    let chart = fs::read_to_string(fixture.path("errors.svg")).unwrap();
+    assert!(
+        chart.contains("Baseline forecasts for 2024-02-04"),
+        "{chart}"
+    );
+    assert!(chart.contains("seasonal-average, MAE $"));
+
+    let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("data");
+    fixture.cli(&[
+        "analyze",
+        "tariff-costs",
+        "prices.csv",
+        data.join("household_load.csv").to_str().unwrap(),
+        data.join("tariffs.toml").to_str().unwrap(),
+        "--out-csv",
+        "tariffs.csv",
+        "--out-png",
+        "tariffs.svg",
+    ]);
+    let chart = fs::read_to_string(fixture.path("tariffs.svg")).unwrap();
+    for tariff in Tariff::load_all(&data.join("tariffs.toml")).unwrap() {
+        let name = tariff.name.replace('&', "&amp;");
+        assert!(chart.contains(&name), "{name} isn't charted");
+    }
+
+    let help = fixture.cli(&["--help"]);
+    for old in [
+        "write-price-forecast",
+        "write-forecast-errors",
+        "graph-price-forecast",
+        "write-tariff-costs",
+    ] {
+        assert!(!help.contains(old), "{old} is still listed");
+    }
+}
+
+#[test]
+fn config_axis_bounds_survive_runs_without_the_flags() {
+    let fixture = Fixture::new("config_axis", &SyntheticData::default());
+    let config = fixture.path("config.toml");
+    fs::write(&config, "y_min = -1000\ny_max = 1000\n").unwrap();
+    let chart = |flags: &[&str]| {
+        let args = [
+            "--config",
+            "config.toml",
+            "analyze",
+            "price-minutes",
+            "prices.csv",
+            "--out-png",
+            "prices.svg",
+            "--overwrite",
+            "--no-meta",
+            "--no-cache",
+        ];
+        fixture.cli(&[&args[..], flags].concat());
+        fs::read_to_string(fixture.path("prices.svg")).unwrap()
+    };
+    assert!(chart(&[]).contains("$-1000"));
+    // A flag still takes precedence over the config.
+    assert!(chart(&["--y-min", "-2000"]).contains("$-2000"));
+}
+
+#[test]
+fn charts_render_as_svg() {
+    let fixture = Fixture::new("svg", &SyntheticData::default());
+    let prices = Compute::new(&fixture.prices())
+        .average_price_profile(Profile::Day)
+        .unwrap();
+    let chart = fixture.path("prices.svg");
+    Graphing::new(&chart)
+        .daily_price(&prices, Profile::Day)
+        .unwrap();
+    assert!(fs::read_to_string(&chart).unwrap().starts_with("<svg"));
+
+    let frames = Compute::new(&fixture.gen())
+        .average_gen_5min_by_period(Period::Month)
+        .unwrap();
+    let animation = fixture.path("gen.svg");
+    assert!(Graphing::new(&animation)
+        .gen_animation(&frames, "Generation", 100)
+        .is_err());
+}
+
+#[test]
+fn y_scales_invert_and_render() {
+    for scale in [YScale::Linear, YScale::Log, YScale::Symlog] {
+        for value in [1., 35., 1000.] {
+            let back = scale.inverse(scale.forward(value));
+            assert!((back - value).abs() < 1e-9 * value, "{scale:?} {value}");
+        }
+    }
+    assert_eq!(YScale::Symlog.forward(-50.), -YScale::Symlog.forward(50.));
+    assert_eq!(YScale::Log.forward(-50.), YScale::Log.forward(0.5));
+
+    let fixture = Fixture::new("y_scale", &SyntheticData::default());
+    let series = Compute::new(&fixture.prices()).price_series().unwrap();
+    let chart = fixture.path("spikes.svg");
+    Graphing::new(&chart)
+        .with_y_scale(YScale::Symlog)
+        .price_spikes(&series, 100., &[])
+        .unwrap();
+    assert!(fs::read_to_string(&chart).unwrap().starts_with("<svg"));
+}
+
+#[test]
+fn fixed_axes_render_in_gigawatts() {
+    assert_eq!(PowerUnit::GW.of(1500.), 1.5);
+    assert_eq!(PowerUnit::MW.of(1500.), 1500.);
+
+    let fixture = Fixture::new("fixed_axes", &SyntheticData::default());
+    let gen = Compute::new(&fixture.gen())
+        .average_gen_profile(Profile::Day)
+        .unwrap();
+    let chart = fixture.path("gen.svg");
+    Graphing::new(&chart)
+        .with_unit(PowerUnit::GW)
+        .with_y_limits(Some(0.), Some(40.))
+        .daily_gen(&gen, Profile::Day, "Generation", None)
+        .unwrap();
+    let svg = fs::read_to_string(&chart).unwrap();
+    assert!(svg.contains("GWh"));
+    assert!(svg.contains(">40<") || svg.contains("40.0"));
+}
+
+#[test]
+fn charts_match_their_snapshots() {
+    let fixture = Fixture::new("snapshots", &SyntheticData::default());
+    let prices = Compute::new(&fixture.prices())
+        .average_price_profile(Profile::Day)
+        .unwrap();
+    let gen = Compute::new(&fixture.gen()).average_gen_5min().unwrap();
+    let (values, _) = Compute::new(&fixture.prices())
+        .average_value_5min(&fixture.gen())
+        .unwrap();
+
+    let mut bitmap = Bitmap::default();
+    let graphing = Graphing::to_bitmap(&mut bitmap);
+    graphing.daily_price(&prices, Profile::Day).unwrap();
+    // A bitmap holds one chart.
+    assert!(graphing.daily_price(&prices, Profile::Day).is_err());
+    assert_eq!((bitmap.width, bitmap.height), (1080, 720));
+    assert_eq!(bitmap.pixel(0, 0), [255, 255, 255]);
+    assert_snapshot("daily_price", &bitmap);
+
+    let mut bitmap = Bitmap::default();
+    Graphing::to_bitmap(&mut bitmap)
+        .daily_gen(&gen, Profile::Day, "Daily average generation", None)
+        .unwrap();
+    assert_snapshot("daily_gen", &bitmap);
+
+    let mut bitmap = Bitmap::default();
+    Graphing::to_bitmap(&mut bitmap)
+        .avg_value(&values, "Average value by source")
+        .unwrap();
+    assert_snapshot("avg_value", &bitmap);
+
+    // The same chart drawn to a file matches the one drawn in memory.
+    let chart = fixture.path("avg_value.png");
+    Graphing::new(&chart)
+        .avg_value(&values, "Average value by source")
+        .unwrap();
+    let drawn = image::open(&chart).unwrap().to_rgb8();
+    assert_eq!(drawn.as_raw(), &bitmap.pixels);
+}
+
+#[test]
+fn chart_options_title_label_and_size_charts() {
+    let fixture = Fixture::new("chart_options", &SyntheticData::default());
+    let prices = Compute::new(&fixture.prices())
+        .average_price_profile(Profile::Day)
+        .unwrap();
+    let draw = |options: ChartOptions| {
+        let mut bitmap = Bitmap::default();
+        Graphing::to_bitmap(&mut bitmap)
+            .with_options(options)
+            .daily_price(&prices, Profile::Day)
+            .unwrap();
+        bitmap
+    };
+    let default = draw(ChartOptions::default());
+
+    let sized = draw(ChartOptions {
+        size: Some((640, 480)),
+        ..ChartOptions::default()
+    });
+    assert_eq!((sized.width, sized.height), (640, 480));
+
+    // Only the caption strip changes with the title, and only the axis
+    // description with the label.
+    let changed_rows = |bitmap: &Bitmap| -> Vec<u32> {
+        (0..bitmap.height)
+            .filter(|&y| (0..bitmap.width).any(|x| bitmap.pixel(x, y) != default.pixel(x, y)))
+            .collect()
+    };
+    let titled = draw(ChartOptions {
+        title: Some("Prices".into()),
+        ..ChartOptions::default()
+    });
+    let changed = changed_rows(&titled);
+    assert!(!changed.is_empty() && changed.iter().all(|&y| y < 80));
+    let labeled = draw(ChartOptions {
+        x_label: Some("Hour".into()),
+        ..ChartOptions::default()
+    });
+    let changed = changed_rows(&labeled);
+    assert!(!changed.is_empty() && changed.iter().all(|&y| y > 640));
+}
+
+#[test]
+fn gen_legends_follow_the_source_order() {
+    let fixture = Fixture::new("legend", &SyntheticData::default());
+    let gen = Compute::new(&fixture.gen()).average_gen_5min().unwrap();
+    let colors: Vec<[u8; 3]> = EnergyGenCsvRow::source_keys()
+        .map(|(_, color)| [color.0, color.1, color.2])
+        .collect();
+    // The swatch of each row of a legend drawn beside the plot.
+    let legend = |order: SourceOrder| {
+        let mut bitmap = Bitmap::default();
+        Graphing::to_bitmap(&mut bitmap)
+            .with_source_order(order)
+            .with_legend(LegendPosition::Outside)
+            .daily_gen(&gen, Profile::Day, "Daily average generation", None)
+            .unwrap();
+        (0..colors.len() - 1)
+            .map(|row| bitmap.pixel(bitmap.width - 170, 80 + 22 * row as u32))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(legend(SourceOrder::Schema), colors[1..]);
+
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+    let wind = EnergyGenCsvRow::source_idx("Wind").unwrap();
+    let given = legend(SourceOrder::Given(vec![solar, wind]));
+    assert_eq!(given[..2], [colors[solar], colors[wind]]);
+    let rest: Vec<_> = (1..colors.len())
+        .filter(|idx| ![solar, wind].contains(idx))
+        .map(|idx| colors[idx])
+        .collect();
+    assert_eq!(given[2..], rest);
+
+    let mean = |idx: usize| gen.iter().map(|slot| slot[idx]).sum::<f64>();
+    let largest = (1..colors.len())
+        .max_by(|a, b| mean(*a).total_cmp(&mean(*b)))
+        .unwrap();
+    assert_eq!(legend(SourceOrder::Magnitude)[0], colors[largest]);
+}
+
+#[test]
+fn highlighting_a_source_greys_the_rest() {
+    let fixture = Fixture::new("highlight", &SyntheticData::default());
+    let gen = Compute::new(&fixture.gen()).average_gen_5min().unwrap();
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+    let (_, color) = EnergyGenCsvRow::source_keys().nth(solar).unwrap();
+    let color = [color.0, color.1, color.2];
+
+    let mut bitmap = Bitmap::default();
+    Graphing::to_bitmap(&mut bitmap)
+        .with_highlight(Some(solar))
+        .with_legend(LegendPosition::Outside)
+        .daily_gen(&gen, Profile::Day, "Daily average generation", None)
+        .unwrap();
+    let swatches: Vec<_> = (1..EnergyGenCsvRow::source_keys().len())
+        .map(|row| bitmap.pixel(bitmap.width - 170, 80 + 22 * (row as u32 - 1)))
+        .collect();
+    assert_eq!(swatches[solar - 1], color);
+    let grey = swatches[0];
+    assert_ne!(grey, color);
+    assert!(swatches
+        .iter()
+        .enumerate()
+        .all(|(row, swatch)| row == solar - 1 || *swatch == grey));
+    // Solar's line keeps its full color.
+    let colored = bitmap.pixels.chunks(3).filter(|px| *px == color).count();
+    assert!(colored > 1000);
+}
+
+#[test]
+fn reference_lines_and_time_marks_come_from_the_config() {
+    let fixture = Fixture::new("references", &SyntheticData::default());
+    let config = fixture.path("energy-analysis.toml");
+    fs::write(
+        &config,
+        "reference_lines = [{ value = 50, label = \"Peaker cost\" }, { value = 1e6, label = \"Off the chart\" }]\n\
+         time_marks = [{ time = \"16:00\", label = \"Peak start\" }]\n",
+    )
+    .unwrap();
+    let settings = Settings::load(&config).unwrap();
+    assert_eq!(settings.reference_lines.len(), 2);
+    let marks: Vec<_> = settings.time_marks.iter().map(TimeMark::marker).collect();
+    assert_eq!(marks[0].slot, Compute::time_to_idx_5min(16, 0));
+
+    let prices = Compute::new(&fixture.prices())
+        .average_price_profile(Profile::Day)
+        .unwrap();
+    let draw = |references: Vec<ReferenceLine>, marks: Vec<Marker>| {
+        let mut bitmap = Bitmap::default();
+        Graphing::to_bitmap(&mut bitmap)
+            .with_references(references)
+            .with_markers(marks)
+            .daily_price(&prices, Profile::Day)
+            .unwrap();
+        bitmap
+    };
+    let plain = draw(Vec::new(), Vec::new());
+    let marked = draw(settings.reference_lines.clone(), marks);
+    assert!(marked.difference(&plain, 16) > 0.);
+    // Lines beyond the y axis are left off rather than drawn over the axes.
+    let off = draw(settings.reference_lines[1..].to_vec(), Vec::new());
+    assert_eq!(off, plain);
+}
+
+#[test]
+fn config_directories_and_window_apply_to_runs() {
+    let fixture = Fixture::new("config_dirs", &SyntheticData::default());
+    fs::create_dir_all(fixture.path("data")).unwrap();
+    fs::create_dir_all(fixture.path("out")).unwrap();
+    fs::rename(fixture.prices(), fixture.path("data/prices.csv")).unwrap();
+    let config = fixture.path(Settings::FILE_NAME);
+    fs::write(
+        &config,
+        "data_dir = \"data\"\nresults_dir = \"out\"\nwindow_intervals = 12\ntheme = \"colorblind\"\n",
+    )
+    .unwrap();
+    let settings = Settings::load(&config).unwrap();
+    assert_eq!(settings.window_intervals, Some(12));
+    assert_eq!(settings.theme, Theme::Colorblind);
+    assert_eq!(settings.output(Path::new("a.csv")), Path::new("out/a.csv"));
+    assert_eq!(settings.output(Path::new("x/a.csv")), Path::new("x/a.csv"));
+
+    // The bare input is found in data_dir, the bare output lands in
+    // results_dir, and the window comes from the config.
+    fixture.cli(&[
+        "analyze",
+        "arbitrage-spread",
+        "prices.csv",
+        "--out-csv",
+        "spread.csv",
+    ]);
+    fixture.cli(&[
+        "analyze",
+        "arbitrage-spread",
+        "data/prices.csv",
+        "--intervals",
+        "12",
+        "--out-csv",
+        "spread_12.csv",
+    ]);
+    fixture.cli(&[
+        "analyze",
+        "arbitrage-spread",
+        "data/prices.csv",
+        "--intervals",
+        "48",
+        "--out-csv",
+        "spread_48.csv",
+    ]);
+    assert!(!fixture.path("spread.csv").exists());
+    let configured = fs::read_to_string(fixture.path("out/spread.csv")).unwrap();
+    assert_eq!(
+        configured,
+        fs::read_to_string(fixture.path("out/spread_12.csv")).unwrap()
+    );
+    assert_ne!(
+        configured,
+        fs::read_to_string(fixture.path("out/spread_48.csv")).unwrap()
+    );
+
+    fs::write(&config, "window_intervals = 0\n").unwrap();
+    assert!(Settings::load(&config).is_err());
+    fs::write(&config, "colour = \"colorblind\"\n").unwrap();
+    assert!(Settings::load(&config).is_err());
+}
+
+#[test]
+fn completions_and_manpages_cover_the_visible_commands() {
+    let fixture = Fixture::new("completions", &SyntheticData::default());
+    let bash = fixture.cli(&["completions", "bash"]);
+    assert!(bash.contains("_energy_analysis()"));
+    assert!(bash.contains("parse-price-csv"));
+    assert!(bash.contains("--overwrite"));
+    let fish = fixture.cli(&["completions", "fish"]);
+    assert!(fish.contains("complete -c energy_analysis"));
+
+    fixture.cli(&["man", "man"]);
+    let main = fs::read_to_string(fixture.path("man/energy_analysis.1")).unwrap();
+    assert!(main.starts_with(".ie \\n(.g .ds Aq"));
+    assert!(main.contains("parse\\-price\\-csv"));
+    let page = fs::read_to_string(fixture.path("man/energy_analysis-parse-price-csv.1")).unwrap();
+    assert!(page.contains("energy_analysis\\-parse\\-price\\-csv"));
+    // Hidden commands get no page of their own.
+    assert!(!fixture.path("man/energy_analysis-gen-testdata.1").exists());
+    assert!(!fixture
+        .path("man/energy_analysis-write-arbitrage-spread.1")
+        .exists());
+}
+
+#[test]
+fn write_options_belong_to_each_writer() {
+    let fixture = Fixture::new("write_options", &SyntheticData::default());
+    let time = NaiveDate::from_ymd_opt(2024, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let forecast = [(time, 23.456789)];
+    let (rounded, kept) = (fixture.path("rounded.csv"), fixture.path("kept.csv"));
+    fs::write(&kept, "keep me\n").unwrap();
+    let strict = WriteOptions {
+        overwrite: false,
+        precision: 2,
+    };
+
+    // Two writers in one process, side by side, each keep their own options.
+    let (strict_result, default_result) = std::thread::scope(|scope| {
+        let strict_result = scope.spawn(|| {
+            let kept = convert::write_price_forecast(&kept, &forecast, strict);
+            convert::write_price_forecast(&rounded, &forecast, strict).and(Ok(kept))
+        });
+        let default_result = scope.spawn(|| {
+            convert::write_price_forecast(
+                &fixture.path("default.csv"),
+                &forecast,
+                WriteOptions::default(),
+            )
+        });
+        (
+            strict_result.join().unwrap(),
+            default_result.join().unwrap(),
+        )
+    });
+    assert!(matches!(
+        strict_result.unwrap(),
+        Err(EnergyAnalysisError::OutputExists(_))
+    ));
+    default_result.unwrap();
+    assert_eq!(fs::read_to_string(&kept).unwrap(), "keep me\n");
+    assert!(fs::read_to_string(&rounded).unwrap().contains(",23.46"));
+    assert!(fs::read_to_string(fixture.path("default.csv"))
+        .unwrap()
+        .contains(",23.456789"));
+
+    assert_eq!(WriteOptions::default().float(1.0 / 3.0), "0.333333");
+    assert_eq!(strict.float(1.0 / 3.0), "0.33");
+}
+
+#[test]
+fn existing_outputs_are_kept_without_overwrite() {
+    let fixture = Fixture::new("overwrite", &SyntheticData::default());
+    let out = fixture.path("minutes.csv");
+    fs::write(&out, "keep me\n").unwrap();
+    let stderr = fixture.cli_err(&["write-price-minutes", "prices.csv", "minutes.csv"]);
+    assert!(stderr.contains("already exists, pass --overwrite to replace it"));
+    assert_eq!(fs::read_to_string(&out).unwrap(), "keep me\n");
+
+    fixture.cli(&[
+        "write-price-minutes",
+        "prices.csv",
+        "minutes.csv",
+        "--overwrite",
+    ]);
+    let written = fs::read_to_string(&out).unwrap();
+    assert!(written.starts_with("prices,samples\n"));
+    assert_eq!(written.lines().count(), Compute::slots_per_day() + 1);
+
+    // With --output-dir the same name lands in the directory with the time
+    // of the run added, leaving the original alone.
+    fixture.cli(&[
+        "write-price-minutes",
+        "prices.csv",
+        "results/minutes.csv",
+        "--output-dir",
+        "runs",
+    ]);
+    assert_eq!(fs::read_to_string(&out).unwrap(), written);
+    let stamped: Vec<_> = fs::read_dir(fixture.path("runs"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".csv"))
+        .collect();
+    assert_eq!(stamped.len(), 1);
+    let stamp = stamped[0]
+        .strip_prefix("minutes-")
+        .and_then(|name| name.strip_suffix(".csv"))
+        .unwrap();
+    assert!(NaiveDateTime::parse_from_str(stamp, "%Y%m%dT%H%M%S").is_ok());
+    assert_eq!(
+        fs::read_to_string(fixture.path("runs").join(&stamped[0])).unwrap(),
+        written
+    );
+}
+
+#[test]
+fn charts_render_to_buffers() {
+    let fixture = Fixture::new("buffers", &SyntheticData::default());
+    let (values, _) = Compute::new(&fixture.prices())
+        .average_value_5min(&fixture.gen())
+        .unwrap();
+    let graphing = Graphing::in_memory();
+
+    let png = graphing
+        .avg_value_to_buffer(&values, "Average value by source", ImageFormat::Png)
+        .unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+    let mut bitmap = Bitmap::default();
+    Graphing::to_bitmap(&mut bitmap)
+        .avg_value(&values, "Average value by source")
+        .unwrap();
+    let decoded = image::load_from_memory(&png).unwrap().to_rgb8();
+    assert_eq!(decoded.as_raw(), &bitmap.pixels);
+
+    let svg = graphing
+        .avg_value_to_buffer(&values, "Average value by source", ImageFormat::Svg)
+        .unwrap();
+    let svg = String::from_utf8(svg).unwrap();
+    assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+
+    // Each buffer is a fresh chart, and nothing is drawn outside of one.
+    assert_eq!(
+        graphing
+            .avg_value_to_buffer(&values, "Average value by source", ImageFormat::Png)
+            .unwrap(),
+        png
+    );
+    assert!(graphing
+        .avg_value(&values, "Average value by source")
+        .is_err());
+    assert_eq!(graphing.csv_path(), PathBuf::new());
+}
+
+#[test]
+fn charts_also_write_what_they_draw() {
+    let fixture = Fixture::new("also_csv", &SyntheticData::default());
+    let prices = Compute::new(&fixture.prices())
+        .average_price_profile(Profile::Day)
+        .unwrap();
+    let chart = fixture.path("prices.png");
+    let graphing = Graphing::new(&chart).with_also_csv(true);
+    graphing.daily_price(&prices, Profile::Day).unwrap();
+    let csv = graphing.csv_path();
+    assert_eq!(csv, fixture.path("prices.csv"));
+    assert_eq!(line_count(&csv), prices.len() + 1);
+    let noon = fs::read_to_string(&csv)
+        .unwrap()
+        .lines()
+        .find(|line| line.starts_with("Price,12:00,"))
+        .map(|line| line.rsplit(',').next().unwrap().parse::<f64>().unwrap())
+        .unwrap();
+    // Written to the default six decimals.
+    assert!((noon - prices[Compute::time_to_idx_5min(12, 0)]).abs() < 1e-6);
+}
+
+#[test]
+fn deltas_compare_scenarios() {
+    assert_eq!(
+        compute::profile_delta(&[1., 2.], &[4., 0.]).unwrap(),
+        vec![3., -2.]
+    );
+    assert!(compute::profile_delta(&[1.], &[1., 2.]).is_err());
+
+    let fixture = Fixture::new("deltas", &SyntheticData::default());
+    let base = Compute::new(&fixture.prices())
+        .average_value_5min(&fixture.gen())
+        .unwrap();
+    let merged = Compute::new(&fixture.prices())
+        .average_value_solar_battery(&fixture.gen())
+        .unwrap();
+    let delta = compute::value_delta(&base, &merged);
+    assert!(delta[EnergyGenCsvRow::source_idx("Batteries").unwrap()].is_nan());
+    assert_eq!(delta[EnergyGenCsvRow::source_idx("Wind").unwrap()], 0.);
+
+    let chart = fixture.path("delta.svg");
+    Graphing::new(&chart)
+        .value_delta(&delta, "Change in value")
+        .unwrap();
+    assert!(fs::read_to_string(&chart).unwrap().starts_with("<svg"));
+}
+
+#[test]
+fn service_answers_windowed_profiles() {
+    let fixture = Fixture::new("serve", &SyntheticData::default());
+    let service = Service::new(fixture.prices(), fixture.gen());
+
+    let response = service.get("/price-profile?start=2024-01-02&end=2024-01-03&window=1h");
+    assert_eq!(response.status, 200);
+    let body: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+    let slots = body["slots"].as_array().unwrap();
+    assert_eq!(slots.len(), 24);
+    assert_eq!(slots[1]["time"], "01:00");
+
+    let gen = service.get("/gen-profile?window=15m");
+    let body: serde_json::Value = serde_json::from_slice(&gen.body).unwrap();
+    assert_eq!(body["slots"].as_array().unwrap().len(), 96);
+    assert!(body["slots"][0]["sources"]["Solar"].is_number());
+
+    assert_eq!(service.get("/price-profile?window=7m").status, 400);
+    assert_eq!(service.get("/price-profile?start=2030-01-01").status, 500);
+    assert_eq!(service.get("/missing").status, 404);
+    assert!(service.get("/charts/price.svg").body.starts_with(b"<svg"));
+
+    // Too many minutes to count is a bad request, not an overflow.
+    let huge = format!("/price-profile?window={}h", usize::MAX / 2);
+    assert_eq!(service.get(&huge).status, 400);
+}
+
+#[test]
+fn service_cache_is_bounded() {
+    let fixture = Fixture::new("serve_cache", &SyntheticData::default());
+    let service = Service::new(fixture.prices(), fixture.gen()).with_cache_entries(2);
+
+    let first = "/price-profile?window=1h";
+    let first_body = service.get(first).body;
+    for day in 2..=6 {
+        let target = format!("/price-profile?start=2024-01-0{day}");
+        assert_eq!(service.get(&target).status, 200);
+        assert!(service.cached_responses() <= 2);
+    }
+    assert_eq!(service.cached_responses(), 2);
+    assert_eq!(service.get(first).body, first_body);
+}
+
+#[test]
+fn metrics_read_the_end_of_the_data() {
+    let fixture = Fixture::new("metrics", &SyntheticData::default());
+    let prices = fixture.prices();
+    let compute = Compute::new(&prices);
+    let series = compute.price_series().unwrap();
+    assert_eq!(
+        compute.latest_prices(12).unwrap(),
+        series[series.len() - 12..]
+    );
+    assert_eq!(compute.latest_prices(series.len() + 5).unwrap(), series);
+
+    let response = Service::new(fixture.prices(), fixture.gen()).get("/metrics");
+    assert_eq!(response.status, 200);
+    let text = String::from_utf8(response.body).unwrap();
+    let latest = series.last().unwrap().1;
+    assert!(text.contains(&format!("\nenergy_price_lmp {latest}\n")));
+    assert!(text.contains("energy_gen_share{source=\"Solar\"}"));
+}
+
+#[test]
+fn monitor_alerts_once_per_crossing() {
+    let mut monitor = Monitor::new(Thresholds {
+        price_above: Some(100.),
+        solar_share_above: Some(50.),
+        ..Default::default()
+    });
+    let time = NaiveDate::from_ymd_opt(2024, 6, 1)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap();
+    let mut gen = [0.; 14];
+    gen[0] = 100.;
+    gen[EnergyGenCsvRow::source_idx("Solar").unwrap()] = 60.;
+
+    let kinds = |alerts: Vec<Alert>| alerts.iter().map(|a| a.kind).collect::<Vec<_>>();
+    assert_eq!(
+        kinds(monitor.check(Some((time, 120.)), Some((time, gen)))),
+        [AlertKind::PriceAbove, AlertKind::SolarShareAbove]
+    );
+    assert!(monitor
+        .check(Some((time, 130.)), Some((time, gen)))
+        .is_empty());
+    assert!(monitor.check(Some((time, 80.)), None).is_empty());
+    assert_eq!(
+        kinds(monitor.check(Some((time, 101.)), None)),
+        [AlertKind::PriceAbove]
+    );
+}
+
+#[test]
+fn webhooks_post_the_alert_as_json() {
+    use std::io::{BufRead, BufReader, Read, Write};
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/alerts", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(&stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some(len) = header.to_ascii_lowercase().strip_prefix("content-length:") {
+                length = len.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        (&stream)
+            .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        (request_line, body)
+    });
+
+    let alert = Alert {
+        kind: AlertKind::PriceBelow,
+        time: NaiveDate::from_ymd_opt(2024, 6, 1)
+            .unwrap()
+            .and_hms_opt(12, 5, 0)
+            .unwrap(),
+        value: -3.5,
+        threshold: 0.,
+    };
+    Hook::Webhook(url).fire(&alert).unwrap();
+    let (request_line, body) = server.join().unwrap();
+    assert!(request_line.starts_with("POST /alerts "), "{request_line}");
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["kind"], "price-below");
+    assert_eq!(body["value"], -3.5);
+    assert_eq!(body["message"], alert.message());
+}
+
+#[test]
+fn requests_give_up_on_a_silent_server() {
+    // Accepts the connection but never answers.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hang", listener.local_addr().unwrap());
+    let started = std::time::Instant::now();
+    let message = fetch::Client::new(Duration::ZERO)
+        .with_timeout(Duration::from_millis(500))
+        .post_json(&url, "{}")
+        .unwrap_err()
+        .to_string();
+    assert!(message.contains("(28)"), "{message}");
+    assert!(started.elapsed() < Duration::from_secs(5));
+    drop(listener);
+}
+
+/// An OASIS report holding `items`, as the API sends it.
+fn oasis_report(items: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\"?><OASISReport xmlns=\"http://www.caiso.com/soa/OASISReport_v1.xsd\">\
+         <MessagePayload><RTO><REPORT_ITEM>{items}</REPORT_ITEM></RTO></MessagePayload></OASISReport>"
+    )
+}
+
+/// One LMP item of an OASIS report.
+fn oasis_item(item: &str, node: &str, start: &str, value: f64) -> String {
+    format!(
+        "<REPORT_DATA><DATA_ITEM>{item}</DATA_ITEM><RESOURCE_NAME>{node}</RESOURCE_NAME>\
+         <INTERVAL_START_GMT>{start}</INTERVAL_START_GMT><VALUE>{value}</VALUE></REPORT_DATA>"
+    )
+}
+
+/// A zip of one OASIS report, as the API sends it.
+fn oasis_zip(xml: &str) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file(
+        "PRC_INTVL_LMP.xml",
+        zip::write::SimpleFileOptions::default(),
+    )
+    .unwrap();
+    std::io::Write::write_all(&mut zip, xml.as_bytes()).unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+#[test]
+fn oasis_zips_parse_into_local_prices() {
+    let xml = oasis_report(
+        &[
+            oasis_item("LMP_PRC", "A", "2024-06-01T07:00:00-00:00", 10.),
+            oasis_item("LMP_PRC", "B", "2024-06-01T07:00:00-00:00", 20.),
+            oasis_item("LMP_CONG_PRC", "A", "2024-06-01T07:00:00-00:00", 99.),
+            oasis_item("LMP_PRC", "A", "2024-06-01T07:05:00-00:00", 30.),
+        ]
+        .concat(),
+    );
+    let lmps = oasis::parse_zip(&oasis_zip(&xml)).unwrap();
+    assert_eq!(lmps.len(), 3);
+
+    let dir = std::env::temp_dir().join(format!("ea-oasis-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let prices = dir.join("prices.csv");
+    oasis::write_prices(&prices, &lmps, false, WriteOptions::default()).unwrap();
+    let series = Compute::new(&prices).price_series().unwrap();
+    fs::remove_dir_all(&dir).ok();
+    let midnight = NaiveDate::from_ymd_opt(2024, 6, 1)
+        .unwrap()
+        .and_time(NaiveTime::MIN);
+    assert_eq!(
+        series,
+        [
+            (midnight, 15.),
+            (midnight + chrono::TimeDelta::minutes(5), 30.)
+        ]
+    );
+
+    let error = oasis_report(
+        "<ERROR><ERR_CODE>1015</ERR_CODE><ERR_DESC>Too many requests</ERR_DESC></ERROR>",
+    );
+    let message = oasis::parse_xml(&error).unwrap_err().to_string();
+    assert!(message.contains("1015"), "{message}");
+}
+
+/// A sample OASIS report from tests/oasis, laid out as the API sends them.
+fn oasis_sample(name: &str) -> String {
+    fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/oasis")
+            .join(name),
+    )
+    .unwrap()
+}
+
+#[test]
+fn oasis_samples_fetch_prices_and_gen() {
+    let fixture = Fixture::new("oasis_samples", &SyntheticData::default());
+    fs::write(
+        fixture.path("lmp.zip"),
+        oasis_zip(&oasis_sample("PRC_INTVL_LMP.xml")),
+    )
+    .unwrap();
+    fs::write(
+        fixture.path("renewables.zip"),
+        oasis_zip(&oasis_sample("SLD_REN_FCST.xml")),
+    )
+    .unwrap();
+    fs::write(
+        fixture.path("load.zip"),
+        oasis_zip(&oasis_sample("SLD_FCST.xml")),
+    )
+    .unwrap();
+    fs::write(
+        fixture.path("error.zip"),
+        oasis_zip(&oasis_sample("error.xml")),
+    )
+    .unwrap();
+
+    let printed = fixture.cli(&[
+        "fetch-oasis",
+        "--zip",
+        "lmp.zip",
+        "renewables.zip",
+        "load.zip",
+        "--output-csv",
+        "oasis_prices.csv",
+        "--gen-csv",
+        "oasis_gen.csv",
+    ]);
+    assert!(printed.contains("Wrote 2 prices"), "{printed}");
+    assert!(
+        printed.contains("Wrote 2 intervals of generation"),
+        "{printed}"
+    );
+
+    // 19:00 UTC is noon in Pacific daylight time.
+    let noon = NaiveDate::from_ymd_opt(2024, 6, 1)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap();
+    let five = chrono::TimeDelta::minutes(5);
+    // The hubs' total LMPs are averaged, and their congestion left out.
+    let series = Compute::new(&fixture.path("oasis_prices.csv"))
+        .price_series()
+        .unwrap();
+    assert_eq!(series.len(), 2);
+    assert_eq!(series[0].0, noon);
+    assert!((series[0].1 - -2.6).abs() < 1e-9, "{series:?}");
+    assert_eq!(series[1].0, noon + five);
+    assert!((series[1].1 - -2.25).abs() < 1e-9, "{series:?}");
+
+    // Solar sums across hubs, the load is CAISO's as a whole rather than
+    // one TAC's, and the rest of it is Other.
+    let idx = |source| EnergyGenCsvRow::source_idx(source).unwrap();
+    let gen_csv = fixture.path("oasis_gen.csv");
+    let rows: Vec<EnergyGenCsvRow> = convert::read_rows(&gen_csv)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let first = rows[0].sources();
+    assert_eq!(rows[0].local_timestamp_start, "2024-06-01 12:00:00");
+    assert_eq!(first[0], 25000.);
+    assert_eq!(first[idx("Solar")], 16000.);
+    assert_eq!(first[idx("Wind")], 1500.);
+    assert_eq!(first[idx("Other")], 7500.);
+    assert_eq!(
+        Compute::new(&gen_csv).latest_gen().unwrap(),
+        Some((noon + five, {
+            let mut sources = [0.; 14];
+            sources[0] = 25400.;
+            sources[idx("Solar")] = 16100.;
+            sources[idx("Other")] = 9300.;
+            sources
+        }))
+    );
+
+    // OASIS's errors come back as reports too, and name their code.
+    for message in [
+        oasis::parse_xml(&oasis_sample("error.xml"))
+            .unwrap_err()
+            .to_string(),
+        oasis::parse_mix_xml(&oasis_sample("error.xml"))
+            .unwrap_err()
+            .to_string(),
+        fixture.cli_err(&[
+            "fetch-oasis",
+            "--zip",
+            "error.zip",
+            "--output-csv",
+            "failed.csv",
+        ]),
+    ] {
+        assert!(message.contains("OASIS error 1015"), "{message}");
+    }
+}
+
+#[test]
+fn fetches_append_only_intervals_after_the_latest() {
+    let fixture = Fixture::new("fetch_append", &SyntheticData::default());
+    let zip = |name: &str, items: &[(&str, f64)]| {
+        let items: Vec<String> = items
+            .iter()
+            .map(|(start, value)| oasis_item("LMP_PRC", "A", start, *value))
+            .collect();
+        fs::write(
+            fixture.path(name),
+            oasis_zip(&oasis_report(&items.concat())),
+        )
+        .unwrap();
+    };
+    zip(
+        "first.zip",
+        &[
+            ("2024-06-01T07:00:00-00:00", 10.),
+            ("2024-06-01T07:05:00-00:00", 20.),
+        ],
+    );
+    // Repeats the latest interval with a revised price, which is left as
+    // it was written.
+    zip(
+        "second.zip",
+        &[
+            ("2024-06-01T07:05:00-00:00", 99.),
+            ("2024-06-01T07:10:00-00:00", 30.),
+        ],
+    );
+
+    let append = |zip: &str| {
+        fixture.cli(&[
+            "fetch-oasis",
+            "--zip",
+            zip,
+            "--output-csv",
+            "live.csv",
+            "--append",
+        ])
+    };
+    assert!(append("first.zip").contains("Wrote 2 prices"));
+    // No --overwrite, which appending doesn't need, provenance included.
+    let printed = append("second.zip");
+    assert!(
+        printed.contains("Appended 1 prices after 2024-06-01 00:05:00"),
+        "{printed}"
+    );
+    assert!(append("second.zip").contains("Appended 0 prices"));
+
+    let live = fixture.path("live.csv");
+    let csv = fs::read_to_string(&live).unwrap();
+    assert_eq!(csv.matches("timestamp").count(), 1, "{csv}");
+    let midnight = NaiveDate::from_ymd_opt(2024, 6, 1)
+        .unwrap()
+        .and_time(NaiveTime::MIN);
+    let minutes = |mins| midnight + chrono::TimeDelta::minutes(mins);
+    assert_eq!(
+        Compute::new(&live).price_series().unwrap(),
+        [(minutes(0), 10.), (minutes(5), 20.), (minutes(10), 30.)]
+    );
+    assert_eq!(
+        Compute::new(&live).latest_prices(1).unwrap(),
+        [(minutes(10), 30.)]
+    );
+
+    // Prices never land in a gen csv.
+    let error = fixture.cli_err(&[
+        "fetch-oasis",
+        "--zip",
+        "first.zip",
+        "--output-csv",
+        "gen.csv",
+        "--append",
+    ]);
+    assert!(error.contains("holds Gen data"), "{error}");
+}
+
+#[test]
+fn fetch_clients_retry_and_keep_their_own_bodies() {
+    use std::io::{BufRead, BufReader, Write};
+
+    // Answers each path with its own name, except that /busy is turned away
+    // once and /down always.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let mut busy = true;
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line).unwrap();
+            let path = line.split(' ').nth(1).unwrap_or("").split('?').next();
+            let (status, body) = match path.unwrap_or("") {
+                "/busy" if std::mem::take(&mut busy) => ("429 Too Many Requests", ""),
+                "/down" => ("503 Service Unavailable", ""),
+                path => ("200 OK", path.trim_start_matches('/')),
+            };
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+
+    let get = |path: &str| fetch::Client::new(Duration::ZERO).get(&format!("{base}{path}"));
+    let bodies: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = ["/a", "/b", "/c"]
+            .map(|path| scope.spawn(move || get(path).unwrap()))
+            .into_iter()
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    assert_eq!(bodies, [b"a", b"b", b"c"]);
+    assert_eq!(get("/busy").unwrap(), b"busy");
+
+    let message = get("/down?api_key=secret").unwrap_err().to_string();
+    assert!(message.contains("HTTP 503"), "{message}");
+    assert!(!message.contains("secret"), "{message}");
+
+    // Bodies come back through a pipe, so no file is left in the shared
+    // temp directory for another user to plant or read.
+    let leftovers = fs::read_dir(std::env::temp_dir())
+        .unwrap()
+        .filter(|entry| {
+            let name = entry.as_ref().unwrap().file_name();
+            name.to_string_lossy().starts_with("energy_analysis_fetch_")
+        })
+        .count();
+    assert_eq!(leftovers, 0);
+
+    let closed = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let message = fetch::Client::new(Duration::ZERO)
+        .get(&format!("http://{closed}/refused"))
+        .unwrap_err()
+        .to_string();
+    assert!(message.contains("curl failed on"), "{message}");
+    assert!(message.contains("(7)"), "{message}");
+}
+
+#[test]
+fn pacific_time_follows_daylight_saving() {
+    let utc = |y, m, d, h| {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, 0, 0)
+            .unwrap()
+    };
+    assert_eq!(
+        fetch::pacific_from_utc(utc(2024, 3, 10, 9)),
+        utc(2024, 3, 10, 1)
+    );
+    assert_eq!(
+        fetch::pacific_from_utc(utc(2024, 3, 10, 10)),
+        utc(2024, 3, 10, 3)
+    );
+    assert_eq!(
+        fetch::pacific_from_utc(utc(2024, 11, 3, 8)),
+        utc(2024, 11, 3, 1)
+    );
+    assert_eq!(
+        fetch::pacific_from_utc(utc(2024, 11, 3, 9)),
+        utc(2024, 11, 3, 1)
+    );
+    let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+    assert_eq!(
+        fetch::utc_of_pacific_midnight(date(3, 10)),
+        utc(2024, 3, 10, 8)
+    );
+    assert_eq!(
+        fetch::utc_of_pacific_midnight(date(3, 11)),
+        utc(2024, 3, 11, 7)
+    );
+    assert_eq!(
+        fetch::utc_of_pacific_midnight(date(11, 3)),
+        utc(2024, 11, 3, 7)
+    );
+    assert_eq!(
+        fetch::utc_of_pacific_midnight(date(11, 4)),
+        utc(2024, 11, 4, 8)
+    );
+}
+
+#[test]
+fn eia_hours_fill_the_gen_schema() {
+    let row = |json: serde_json::Value| json.as_object().unwrap().clone();
+    let day = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let mut fuel = Vec::new();
+    let mut region = Vec::new();
+    let mut interchange = Vec::new();
+    // Pacific midnight on June 1st is 07:00 UTC, and periods name the hour they end.
+    for hour in 0..24 {
+        let period = (day.and_hms_opt(8, 0, 0).unwrap() + chrono::TimeDelta::hours(hour))
+            .format("%Y-%m-%dT%H")
+            .to_string();
+        let solar = if (6..18).contains(&hour) { 1000. } else { 0. };
+        fuel.push(row(
+            serde_json::json!({ "period": period, "fueltype": "SUN", "value": solar.to_string() }),
+        ));
+        fuel.push(row(
+            serde_json::json!({ "period": period, "fueltype": "NG", "value": 500 }),
+        ));
+        fuel.push(row(
+            serde_json::json!({ "period": period, "fueltype": "OIL", "value": null }),
+        ));
+        region.push(row(
+            serde_json::json!({ "period": period, "type": "TI", "value": "-200" }),
+        ));
+        region.push(row(
+            serde_json::json!({ "period": period, "type": "D", "value": "1700" }),
+        ));
+        interchange.push(row(
+            serde_json::json!({ "period": period, "toba": "BPAT", "value": "-150" }),
+        ));
+    }
+    let hourly = Hourly::from_rows(&fuel, &region, &interchange).unwrap();
+    assert_eq!(hourly.gen.len(), 24);
+
+    let dir = std::env::temp_dir().join(format!("ea-eia-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let gen_csv = dir.join("gen.csv");
+    hourly
+        .write_gen(&gen_csv, false, WriteOptions::default())
+        .unwrap();
+    let interchange_csv = dir.join("interchange.csv");
+    hourly
+        .write_interchange(&interchange_csv, false, WriteOptions::default())
+        .unwrap();
+    let gen = Compute::new(&gen_csv)
+        .average_gen_profile(Profile::Day)
+        .unwrap();
+    let interchange = fs::read_to_string(&interchange_csv).unwrap();
+    fs::remove_dir_all(&dir).ok();
+
+    let idx = |source| EnergyGenCsvRow::source_idx(source).unwrap();
+    let noon = Compute::time_to_idx_5min(12, 30);
+    assert_eq!(gen[noon][idx("Solar")], 1000.);
+    assert_eq!(gen[noon][idx("Imports")], 200.);
+    assert_eq!(gen[noon][0], 1700.);
+    assert_eq!(gen[Compute::time_to_idx_5min(3, 0)][idx("Solar")], 0.);
+    assert!(
+        interchange.contains("2024-06-01 00:00:00,BPAT,150"),
+        "{interchange}"
+    );
+}
+
+#[test]
+fn imports_split_into_imports_and_exports() {
+    let fixture = Fixture::new("import_export", &SyntheticData::default());
+    // Exports 1 GW through the middle of the day.
+    let raw = fs::read_to_string(fixture.path("gen_raw.csv")).unwrap();
+    let exporting: Vec<String> = raw
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| {
+            let mut fields: Vec<&str> = line.split(',').collect();
+            let midday = idx > 3 && fields[1][11..13].parse::<u32>().unwrap() / 2 == 5;
+            if midday {
+                fields[11] = "-1000.0";
+            }
+            fields.join(",")
+        })
+        .collect();
+    let raw = fixture.path("exporting_raw.csv");
+    fs::write(&raw, exporting.join("\n")).unwrap();
+    let gen = fixture.path("exporting.csv");
+    convert::convert_energy_gen_csv(
+        &[raw],
+        &gen,
+        CsvFormat::default(),
+        TotalCheck::Report,
+        OnError::Abort,
+        WriteOptions::default(),
+    )
+    .unwrap();
+
+    let price_csv = fixture.prices();
+    let prices = Compute::new(&price_csv);
+    let hours = prices.interchange_by_hour(&gen).unwrap();
+    assert_eq!(hours.len(), 24);
+    let series = prices.price_series().unwrap();
+    let days = SyntheticData::default().days as f64;
+    for hour in &hours {
+        let (flow, idle) = if (10..12).contains(&hour.hour) {
+            (&hour.negative, &hour.positive)
+        } else {
+            (&hour.positive, &hour.negative)
+        };
+        assert_eq!(idle.intervals, 0, "hour {}", hour.hour);
+        assert_eq!(flow.intervals, 12 * days as usize, "hour {}", hour.hour);
+        assert!(idle.avg_price().is_nan());
+        if (10..12).contains(&hour.hour) {
+            // A constant flow weighs every interval's price the same.
+            assert!((flow.mwh - 1000. * days).abs() < 1e-6);
+            let hour_prices: Vec<f64> = series
+                .iter()
+                .filter(|(time, _)| time.hour() as usize == hour.hour)
+                .map(|(_, price)| *price)
+                .collect();
+            let mean = hour_prices.iter().sum::<f64>() / hour_prices.len() as f64;
+            assert!((flow.avg_price() - mean).abs() < 1e-9);
+        }
+    }
+}
+
+#[test]
+fn batteries_split_into_charging_and_discharging() {
+    let data = SyntheticData::default();
+    let fixture = Fixture::new("battery_split", &data);
+    let price_csv = fixture.prices();
+    let prices = Compute::new(&price_csv);
+    let battery = EnergyGenCsvRow::source_idx("Batteries").unwrap();
+
+    // [discharged MWh, discharge dollars, charged MWh, charge dollars] by hour.
+    let mut expected = [[0.; 4]; 24];
+    for offset in 0..data.days {
+        let day = prices
+            .day_profile(&fixture.gen(), data.start + TimeDelta::days(offset as i64))
+            .unwrap();
+        for ((slot, price), (_, sources)) in day.prices.iter().zip(&day.gen) {
+            let (hour, _) = Compute::idx_5min_to_time(*slot);
+            let mwh = sources[battery] / 12.;
+            let sums = &mut expected[hour as usize];
+            let side = if mwh >= 0. { 0 } else { 2 };
+            sums[side] += mwh.abs();
+            sums[side + 1] += mwh.abs() * price;
+        }
+    }
+
+    let hours = prices.battery_by_hour(&fixture.gen()).unwrap();
+    for (hour, [out, out_dollars, charged, charge_dollars]) in hours.iter().zip(expected) {
+        assert!((hour.positive.mwh - out).abs() < 1e-6);
+        assert!((hour.negative.mwh - charged).abs() < 1e-6);
+        if out > 0. {
+            assert!((hour.positive.avg_price() - out_dollars / out).abs() < 1e-9);
+        }
+        if charged > 0. {
+            assert!((hour.negative.avg_price() - charge_dollars / charged).abs() < 1e-9);
+        }
+    }
+    // Synthetic batteries charge on cheap midday solar and sell into the
+    // evening peak, so they buy low and sell high.
+    assert!(hours[12].positive.mwh == 0. && hours[12].negative.mwh > 0.);
+    assert!(hours[19].positive.mwh > 0. && hours[19].negative.mwh == 0.);
+    assert!(hours[19].positive.avg_price() > 2. * hours[12].negative.avg_price());
+}
+
+#[test]
+fn gen_bands_take_percentiles_across_days() {
+    let data = SyntheticData::default();
+    let fixture = Fixture::new("gen_bands", &data);
+    let price_csv = fixture.prices();
+    let prices = Compute::new(&price_csv);
+    let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+    let noon = Compute::time_to_idx_5min(12, 0);
+
+    let mut outputs: Vec<f64> = (0..data.days)
+        .map(|offset| {
+            let day = prices
+                .day_profile(&fixture.gen(), data.start + TimeDelta::days(offset as i64))
+                .unwrap();
+            day.gen.iter().find(|(slot, _)| *slot == noon).unwrap().1[solar]
+        })
+        .collect();
+    outputs.sort_by(f64::total_cmp);
+    // Seven days put the 10th percentile 0.6 of the way from the lowest to
+    // the next, the median on the fourth, and the 90th at 5.4.
+    let between = |lo: usize, frac: f64| outputs[lo] + (outputs[lo + 1] - outputs[lo]) * frac;
+
+    let gen_csv = fixture.gen();
+    let bands = Compute::new(&gen_csv)
+        .gen_percentile_bands(Profile::Day)
+        .unwrap();
+    assert_eq!(bands.len(), Compute::slots_per_day());
+    let band = bands[noon][solar];
+    assert!((band.p10 - between(0, 0.6)).abs() < 1e-9);
+    assert_eq!(band.p50, outputs[3]);
+    assert!((band.p90 - between(5, 0.4)).abs() < 1e-9);
+    assert!(band.p10 < band.p50 && band.p50 < band.p90);
+    // No sun at midnight, so solar's band collapses to zero.
+    let night = bands[0][solar];
+    assert_eq!((night.p10, night.p50, night.p90), (0., 0., 0.));
+}
+
+#[test]
+fn interchange_splits_by_neighbor() {
+    let dir = std::env::temp_dir().join(format!("ea-ba-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let interchange_csv = dir.join("interchange.csv");
+    let mut csv = String::from("local_timestamp,ba,imports_mw\n");
+    for day in 1..=2 {
+        for hour in 0..24 {
+            let time = format!("2024-06-0{day} {hour:02}:00:00");
+            // NEVP exports to CAISO at night and imports from it at midday.
+            let nevp = if (10..16).contains(&hour) {
+                -300
+            } else {
+                100 * day
+            };
+            csv.push_str(&format!(
+                "{time},BPAT,1000\n{time},NEVP,{nevp}\n{time},IID,5\n"
+            ));
+        }
+    }
+    fs::write(&interchange_csv, csv).unwrap();
+
+    let bas = Compute::new(&interchange_csv).interchange_by_ba().unwrap();
+    let names: Vec<_> = bas.iter().map(|ba| ba.ba.as_str()).collect();
+    assert_eq!(names, ["BPAT", "NEVP", "IID"]);
+    assert_eq!(bas[0].hours, [1000.; 24]);
+    assert_eq!(bas[1].hours[3], 150.);
+    assert_eq!(bas[1].hours[12], -300.);
+
+    let csv_out = dir.join("ba_interchange.csv");
+    convert::write_ba_interchange(&csv_out, &bas, WriteOptions::default()).unwrap();
+    let chart = dir.join("ba_interchange.svg");
+    Graphing::new(&chart)
+        .ba_interchange(&bas, "Imports by neighbor")
+        .unwrap();
+    let written = fs::read_to_string(&csv_out).unwrap();
+    let rendered = fs::read_to_string(&chart).unwrap();
+    fs::remove_dir_all(&dir).ok();
+
+    let mut lines = written.lines();
+    assert_eq!(lines.next(), Some("hour,BPAT_mw,NEVP_mw,IID_mw,net_mw"));
+    assert_eq!(lines.nth(12), Some("12,1000,-300,5,705"));
+    assert!(rendered.starts_with("<svg"));
+}
+
+#[test]
+fn data_problems_have_their_own_errors() {
+    let january = Fixture::new("err-jan", &SyntheticData::default());
+    let june = Fixture::new(
+        "err-jun",
+        &SyntheticData {
+            start: NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+            ..SyntheticData::default()
+        },
+    );
+    let Err(err) = Compute::new(&january.prices()).source_price_correlation(&june.gen()) else {
+        panic!("January prices and June generation don't overlap");
+    };
+    assert!(
+        matches!(err, EnergyAnalysisError::JoinMismatch { .. }),
+        "{err}"
+    );
+    assert!(err.is_data_error());
+
+    let err = Compute::intervals_per_window(7).unwrap_err();
+    assert!(matches!(err, EnergyAnalysisError::Invalid(_)), "{err}");
+    assert!(!err.is_data_error());
+}
+
+#[test]
+fn parse_errors_point_at_the_bad_value() {
+    let fixture = Fixture::new("parse-err", &SyntheticData::default());
+    let with_line = |path: &Path, line: usize, edit: &dyn Fn(&str) -> String| {
+        let text: Vec<String> = fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .enumerate()
+            .map(|(idx, text)| match idx + 1 == line {
+                true => edit(text),
+                false => text.to_string(),
+            })
+            .collect();
+        let out = fixture.path(&format!("bad_{line}.csv"));
+        fs::write(&out, text.join("\n") + "\n").unwrap();
+        out
+    };
+
+    let prices = with_line(&fixture.prices(), 5, &|text| {
+        let mut fields: Vec<&str> = text.split(',').collect();
+        fields[3] = "abc";
+        fields.join(",")
+    });
+    let Err(err) = Compute::new(&prices).frame() else {
+        panic!("lmp_avg isn't a number");
+    };
+    let EnergyAnalysisError::Parse {
+        file,
+        line,
+        field,
+        value,
+        ..
+    } = &err
+    else {
+        panic!("{err}");
+    };
+    assert_eq!(
+        (file, *line, field.as_deref(), value.as_deref()),
+        (&prices, 5, Some("lmp_avg"), Some("abc"))
+    );
+
+    // Reads from the end of a file still say which of its lines is bad.
+    let last = line_count(&fixture.prices());
+    let prices = with_line(&fixture.prices(), last - 1, &|text| {
+        text.replace(":00,", ":0x,")
+    });
+    let err = Compute::new(&prices).latest_prices(3).unwrap_err();
+    let EnergyAnalysisError::Parse {
+        file, line, field, ..
+    } = &err
+    else {
+        panic!("{err}");
+    };
+    assert_eq!(
+        (file, *line as usize, field.as_deref()),
+        (&prices, last - 1, Some("timestamp"))
+    );
+
+    // Raw files count their three-line preamble and header.
+    let raw = with_line(&fixture.path("price_raw.csv"), 7, &|text| {
+        text.replace("2024-01-01", "2024-13-01")
+    });
+    let out = fixture.path("bad_prices.csv");
+    let err = convert::convert_energy_price_csv(
+        &[&raw],
+        &out,
+        CsvFormat::default(),
+        OnError::Abort,
+        WriteOptions::default(),
+    )
+    .unwrap_err();
+    let message = err.to_string();
+    assert!(
+        matches!(err, EnergyAnalysisError::Parse { line: 7, .. }),
+        "{message}"
+    );
+    assert!(message.contains("\"2024-13-01 "), "{message}");
+}
+
+#[test]
+fn inspect_samples_without_parsing_everything() {
+    let fixture = Fixture::new("inspect", &SyntheticData::default());
+    let intervals = 7 * 288;
+    let day = |d: u32, h: u32, m: u32| {
+        NaiveDate::from_ymd_opt(2024, 1, d)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+    };
+
+    let prices =
+        convert::inspect_raw(&fixture.path("price_raw.csv"), CsvFormat::default(), 50).unwrap();
+    assert_eq!(prices.kind, RawKind::Price);
+    assert_eq!(prices.sampled, 50);
+    assert!(!prices.rows_exact);
+    assert!(
+        prices.rows.abs_diff(intervals) < intervals / 20,
+        "{}",
+        prices.rows
+    );
+    assert_eq!((prices.first, prices.last), (day(1, 0, 0), day(7, 23, 55)));
+    assert!(prices.ignored.iter().any(|col| col == "NP-15 (Energy)"));
+    assert!(prices.fatal.is_none());
+
+    // A bad source is skipped by the gen parse, so sampling goes on.
+    let raw = fs::read_to_string(fixture.path("gen_raw.csv")).unwrap();
+    let mut lines: Vec<String> = raw.lines().map(str::to_string).collect();
+    let (row, _) = lines[9].rsplit_once(',').unwrap();
+    lines[9] = format!("{row},lots");
+    let bad = fixture.path("bad_gen_raw.csv");
+    fs::write(&bad, lines.join("\n") + "\n").unwrap();
+    let gen = convert::inspect_raw(&bad, CsvFormat::default(), 10_000).unwrap();
+    assert_eq!(gen.kind, RawKind::Gen);
+    assert!(gen.rows_exact);
+    assert_eq!(
+        (gen.rows, gen.sampled, gen.skipped),
+        (intervals, intervals, 1)
+    );
+    assert!(matches!(
+        gen.first_skipped,
+        Some(EnergyAnalysisError::Parse { line: 10, .. })
+    ));
+}
+
+#[test]
+fn monthly_manifests_read_like_their_csv() {
+    let fixture = Fixture::new(
+        "months",
+        &SyntheticData {
+            start: NaiveDate::from_ymd_opt(2024, 1, 28).unwrap(),
+            ..SyntheticData::default()
+        },
+    );
+    let prices = fixture.path("prices.toml");
+    let manifest = Manifest::split(
+        &fixture.prices(),
+        RawKind::Price,
+        &prices,
+        WriteOptions::default(),
+    )
+    .unwrap();
+    let months: Vec<_> = manifest
+        .months
+        .iter()
+        .map(|month| (month.month.as_str(), month.rows))
+        .collect();
+    assert_eq!(months, [("2024-01", 4 * 288), ("2024-02", 3 * 288)]);
+    assert!(fixture.path("prices-2024-02.csv").exists());
+    // A toml config is not mistaken for a manifest by its extension.
+    assert!(Manifest::is_manifest(&prices));
+    let capacity = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/capacity.toml");
+    assert!(!Manifest::is_manifest(&capacity));
+
+    let price_csv = fixture.prices();
+    let (csv, split) = (Compute::new(&price_csv), Compute::new(&prices));
+    assert_eq!(
+        csv.daily_average_prices().unwrap(),
+        split.daily_average_prices().unwrap()
+    );
+    assert_eq!(
+        csv.average_price_profile(Profile::Day).unwrap(),
+        split.average_price_profile(Profile::Day).unwrap()
+    );
+    // The last 1000 rows span both months.
+    assert_eq!(
+        csv.latest_prices(1000).unwrap(),
+        split.latest_prices(1000).unwrap()
+    );
+
+    let gen = fixture.path("gen.toml");
+    Manifest::split(&fixture.gen(), RawKind::Gen, &gen, WriteOptions::default()).unwrap();
+    assert_eq!(
+        Compute::new(&fixture.gen()).daily_average_gen().unwrap(),
+        Compute::new(&gen).daily_average_gen().unwrap()
+    );
+    assert_eq!(
+        Compute::new(&fixture.gen()).latest_gen().unwrap(),
+        Compute::new(&gen).latest_gen().unwrap()
+    );
+}
+
+#[test]
+fn downsampling_keeps_the_schema() {
+    let fixture = Fixture::new("downsample", &SyntheticData::default());
+    let prices = Compute::new(&fixture.prices()).price_series().unwrap();
+    let hourly = Compute::new(&fixture.prices())
+        .downsample_prices(60, &Aggs::default())
+        .unwrap();
+    assert_eq!(hourly.len(), 7 * 24);
+    let first_hour = prices[..12].iter().map(|(_, price)| price).sum::<f64>() / 12.;
+    assert!((hourly[0].lmp_avg - first_hour).abs() < 1e-9);
+    assert_eq!(
+        (hourly[1].timestamp.as_str(), hourly[1].hour),
+        ("2024-01-01 01:00:00", 1)
+    );
+
+    // Written out, it reads back like any parsed csv.
+    let out = fixture.path("hourly.csv");
+    convert::write_parsed(&out, &hourly, WriteOptions::default()).unwrap();
+    assert_eq!(Compute::new(&out).price_series().unwrap().len(), 7 * 24);
+
+    let aggs = Aggs {
+        default: Agg::Mean,
+        columns: vec![("solar".to_string(), Agg::Sum)],
+    };
+    let gen_csv = fixture.gen();
+    let gen = Compute::new(&gen_csv);
+    let quarters = gen.downsample_gen(15, &aggs).unwrap();
+    let raw = gen.frame().unwrap();
+    let solar = raw.column("Solar").unwrap();
+    let wind = raw.column("Wind").unwrap();
+    assert_eq!(quarters.len(), 7 * 96);
+    assert!((quarters[5].solar - solar[15..18].iter().sum::<f64>()).abs() < 1e-9);
+    assert!((quarters[5].wind - wind[15..18].iter().sum::<f64>() / 3.).abs() < 1e-9);
+    assert_eq!(quarters[5].local_timestamp_start, "2024-01-01 01:15:00");
+    assert_eq!(quarters[5].local_timestamp_end, "2024-01-01 01:30:00");
+
+    let unknown = Aggs {
+        columns: vec![("Sun".to_string(), Agg::Sum)],
+        ..Aggs::default()
+    };
+    assert!(gen.downsample_gen(60, &unknown).is_err());
+    assert!(gen.downsample_gen(7, &Aggs::default()).is_err());
+}
+
+#[test]
+fn written_numbers_round_trip_without_noise() {
+    let fixture = Fixture::new("round_trip", &SyntheticData::default());
+    let cases = [
+        (0.1 + 0.2, "0.3"),
+        (23.450000000000003, "23.45"),
+        (1000., "1000"),
+        (-1e-9, "0"),
+        (f64::NAN, "NaN"),
+        (-0., "0"),
+        (-0.5, "-0.5"),
+        (1e-7, "0"),
+        (2.0000004, "2"),
+        (2.0000006, "2.000001"),
+        (1e12 + 0.25, "1000000000000.25"),
+        (f64::NEG_INFINITY, "-inf"),
+    ];
+    let errors: Vec<ForecastError> = cases
+        .iter()
+        .map(|&(mae, _)| ForecastError {
+            baseline: "yesterday".to_string(),
+            intervals: 1,
+            mae,
+            mape: None,
+        })
+        .collect();
+    let numbers = fixture.path("numbers.csv");
+    convert::write_forecast_errors(&numbers, &errors, WriteOptions::default()).unwrap();
+    let written = fs::read_to_string(&numbers).unwrap();
+    let maes: Vec<&str> = written
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').nth(2).unwrap())
+        .collect();
+    let expected: Vec<&str> = cases.iter().map(|&(_, text)| text).collect();
+    assert_eq!(maes, expected);
+
+    // Rewriting a written csv from what it reads back changes nothing.
+    let hourly = Compute::new(&fixture.prices())
+        .downsample_prices(60, &Aggs::default())
+        .unwrap();
+    let once = fixture.path("hourly.csv");
+    convert::write_parsed(&once, &hourly, WriteOptions::default()).unwrap();
+    let rows = convert::read_rows::<EnergyPriceCsvRow>(&once)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let twice = fixture.path("hourly_again.csv");
+    convert::write_parsed(&twice, &rows, WriteOptions::default()).unwrap();
+    let written = fs::read_to_string(&once).unwrap();
+    assert_eq!(written, fs::read_to_string(&twice).unwrap());
+
+    // Conversion writes its rows by hand, exactly as serializing them would,
+    // copying raw numbers only where they already read that way.
+    let raw = fs::read_to_string(fixture.path("price_raw.csv")).unwrap();
+    let mut lines: Vec<String> = raw.lines().map(str::to_string).collect();
+    let mut fields: Vec<&str> = lines[4].split(',').collect();
+    let awkward = [
+        "12.50",
+        "007",
+        "1e-05",
+        "-0.0",
+        "0.12345678",
+        "-3.",
+        "40",
+        "40",
+        "40",
+        "1234567890.5",
+        "-0",
+        "5.0000004",
+    ];
+    fields[5..].copy_from_slice(&awkward);
+    lines[4] = fields.join(",");
+    fs::write(fixture.path("price_raw.csv"), lines.join("\n") + "\n").unwrap();
+    convert::convert_energy_price_csv(
+        &[fixture.path("price_raw.csv")],
+        &fixture.prices(),
+        CsvFormat::default(),
+        OnError::Abort,
+        WriteOptions::default(),
+    )
+    .unwrap();
+    let converted = fs::read_to_string(fixture.prices()).unwrap();
+    assert!(
+        converted
+            .lines()
+            .nth(1)
+            .unwrap()
+            .ends_with(",6.500003,12.5,7,0.00001,0,0.123457,-3,1234567890.5,0,5"),
+        "{converted}"
+    );
+    let prices = convert::read_rows::<EnergyPriceCsvRow>(&fixture.prices())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let serialized = fixture.path("prices_serialized.csv");
+    convert::write_parsed(&serialized, &prices, WriteOptions::default()).unwrap();
+    assert_eq!(converted, fs::read_to_string(&serialized).unwrap());
+    let rows = convert::read_rows::<EnergyGenCsvRow>(&fixture.gen())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let serialized = fixture.path("gen_serialized.csv");
+    convert::write_parsed(&serialized, &rows, WriteOptions::default()).unwrap();
+    assert_eq!(
+        fs::read_to_string(fixture.gen()).unwrap(),
+        fs::read_to_string(&serialized).unwrap()
+    );
+    assert!(written.lines().nth(1).unwrap().split(',').all(|field| {
+        field
+            .split_once('.')
+            .is_none_or(|(_, decimals)| decimals.len() <= 6)
+    }));
+}
+
+#[test]
+fn external_series_interpolate_onto_the_grid() {
+    let fixture = Fixture::new("align_series", &SyntheticData::default());
+    let weather = fixture.path("weather.csv");
+    fs::write(
+        &weather,
+        "station;DATE;TMP\n\
+         KSFO;01/01/2024 01:00;12,5\n\
+         KSFO;01/01/2024 00:00;10,0\n\
+         KSFO;01/01/2024 02:00;\n\
+         KSFO;01/01/2024 05:00;20,0\n",
+    )
+    .unwrap();
+    let layout = SeriesLayout {
+        timestamp_column: "DATE".to_string(),
+        timestamp_format: "%m/%d/%Y %H:%M".to_string(),
+        csv: CsvFormat {
+            delimiter: b';',
+            decimal_comma: true,
+        },
+    };
+    let loaded = series::load(&weather, &layout, &["TMP".to_string()]).unwrap();
+    assert_eq!(loaded.names(), ["TMP"]);
+    let time = |hour, minute| {
+        NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    };
+    assert_eq!(loaded.times()[0], time(0, 0));
+
+    // Every five minutes from midnight to 5am, across the empty 2am value.
+    let aligned = series::align(&loaded, None, None).unwrap();
+    assert_eq!(aligned.len(), 5 * 12 + 1);
+    let tmp = aligned.column("TMP").unwrap();
+    assert!((tmp[6] - 11.25).abs() < 1e-9);
+    assert!((tmp[12 + 6] - (12.5 + 7.5 / 8.)).abs() < 1e-9);
+
+    // Onto the price intervals, which run on past the series.
+    let grid = Compute::new(&fixture.prices()).frame().unwrap();
+    let gapped = series::align(&loaded, Some(grid.times()), Some(TimeDelta::hours(1))).unwrap();
+    assert_eq!(gapped.len(), 12 + 1 + 1);
+    assert_eq!(*gapped.times().last().unwrap(), time(5, 0));
+
+    // The written csv reads back with the default layout.
+    let out = fixture.path("weather_5min.csv");
+    convert::write_frame(&out, &aligned, WriteOptions::default()).unwrap();
+    let reread = series::load(&out, &SeriesLayout::default(), &[]).unwrap();
+    assert_eq!(reread.times(), aligned.times());
+    for (reread, tmp) in reread.column("TMP").unwrap().iter().zip(tmp) {
+        assert!((reread - tmp).abs() < 1e-6, "{reread} vs {tmp}");
+    }
+
+    let missing = series::load(&weather, &layout, &["Temp".to_string()]);
+    assert!(matches!(
+        missing,
+        Err(EnergyAnalysisError::SchemaMismatch { .. })
+    ));
+}
+
+#[test]
+fn weather_days_join_highs_with_peaks_and_ramps() {
+    let fixture = Fixture::new("weather", &SyntheticData::default());
+    // Two readings a day, the afternoon one rising a degree a day.
+    let mut rows = "timestamp,temperature\n".to_string();
+    for day in 1..=7 {
+        rows += &format!(
+            "2024-01-0{day} 06:00:00,10\n2024-01-0{day} 15:00:00,{}\n",
+            20 + day
+        );
+    }
+    let weather_csv = fixture.path("weather.csv");
+    fs::write(&weather_csv, rows).unwrap();
+    let weather_frame = series::load(&weather_csv, &SeriesLayout::default(), &[]).unwrap();
+
+    let days = weather::weather_days(
+        &Compute::new(&fixture.prices()),
+        &fixture.gen(),
+        &weather_frame,
+        "temperature",
+    )
+    .unwrap();
+    assert_eq!(days.len(), 7);
+    assert_eq!(days[2].max_temp, 23.);
+    let jan_3 = NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+    let peak = Compute::new(&fixture.prices())
+        .price_series()
+        .unwrap()
+        .into_iter()
+        .filter(|(time, _)| time.date() == jan_3)
+        .map(|(_, price)| price)
+        .fold(f64::MIN, f64::max);
+    assert_eq!(days[2].peak_price, peak);
+    assert!(days.iter().all(|day| day.evening_ramp_mw > 0.));
+
+    let fits = WeatherFits::new(&days);
+    assert_eq!(fits.peak_price.count, 7);
+    let chart = fixture.path("weather.svg");
+    Graphing::new(&chart)
+        .weather_scatter(&days, &fits, "Daily high (°C)")
+        .unwrap();
+    assert!(fs::read_to_string(&chart).unwrap().starts_with("<svg"));
+
+    let mut line = Correlation::default();
+    for x in 0..5 {
+        line.push(x as f64, 2. * x as f64 + 1.);
+    }
+    let fit = line.fit();
+    assert!((fit.slope - 2.).abs() < 1e-9 && (fit.intercept - 1.).abs() < 1e-9);
+    assert!((fit.r_squared - 1.).abs() < 1e-9);
+}
+
+#[test]
+fn spark_spread_joins_gas_prices_by_day() {
+    let fixture = Fixture::new("spark", &SyntheticData::default());
+    let gas_csv = fixture.path("gas.csv");
+    fs::write(
+        &gas_csv,
+        "date,price\n2024-01-02,3\n2024-01-03,\n2024-01-04,2\n",
+    )
+    .unwrap();
+    let layout = SeriesLayout {
+        timestamp_column: "date".to_string(),
+        timestamp_format: "%Y-%m-%d".to_string(),
+        ..SeriesLayout::default()
+    };
+    let gas = GasPrices::load(&gas_csv, &layout, "price").unwrap();
+    let day = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+    assert_eq!(gas.on(day(1)), None);
+    assert_eq!(gas.on(day(3)), Some(3.));
+    assert_eq!(gas.on(day(8)), Some(2.));
+    assert_eq!(gas.on(day(9)), None);
+
+    let prices = Compute::new(&fixture.prices()).select(&["Price"]).unwrap();
+    let frame = gas::spread_frame(&prices, &gas, 7.).unwrap();
+    assert_eq!(frame.times()[0].date(), day(2));
+    let (price, spread) = (
+        frame.column("Price").unwrap(),
+        frame.column("Spread").unwrap(),
+    );
+    assert!((spread[0] - (price[0] - 21.)).abs() < 1e-9);
+    assert!(gas::spread_frame(&prices, &gas, 0.).is_err());
+
+    let spread =
+        gas::spark_spread(&Compute::new(&fixture.prices()), &gas, 7., Profile::Day).unwrap();
+    assert_eq!(spread.skipped_days, 1);
+    assert_eq!(spread.spreads.len(), Compute::slots_per_day());
+    for idx in 0..spread.spreads.len() {
+        let expected = spread.prices[idx] - spread.fuel_costs[idx];
+        assert!((spread.spreads[idx] - expected).abs() < 1e-9);
+    }
+}
+
+/// Only the prelude is imported here, so everything these use has to be
+/// exported by it.
+mod prelude_only {
+    use super::Fixture;
+    use energy_analysis::prelude::*;
+    use energy_analysis::testdata::SyntheticData;
+
+    #[test]
+    fn the_prelude_runs_an_analysis_on_its_own() {
+        let fixture = Fixture::new("prelude", &SyntheticData::default());
+        let prices = Dataset::load(&fixture.prices()).unwrap();
+        let gen = Dataset::load(&fixture.gen()).unwrap();
+
+        // Midnight's median by hand, over the week's seven midnights.
+        let series = Compute::from_dataset(&prices).price_series().unwrap();
+        let mut midnights: Vec<f64> = series
+            .iter()
+            .step_by(Compute::slots_per_day())
+            .map(|(_, price)| *price)
+            .collect();
+        midnights.sort_by(f64::total_cmp);
+        assert_eq!(midnights.len(), 7);
+        let profile = Compute::from_dataset(&prices)
+            .with_agg(ProfileAgg::Median)
+            .with_days(DayPolicy::default())
+            .with_samples(SampleCheck {
+                min_samples: Some(7),
+                on_few: FewSamples::Error,
+            })
+            .average_price_profile(Profile::Day)
+            .unwrap();
+        assert_eq!(profile[0], midnights[3]);
+
+        // Generation covers the same days, and joining prices to every
+        // other interval matches half of them.
+        assert_eq!(
+            Compute::from_dataset(&gen).date_span().unwrap(),
+            Compute::from_dataset(&prices).date_span().unwrap()
+        );
+        let every_other: Vec<_> = series.iter().copied().step_by(2).collect();
+        let stats = TimeAlignedJoin::new(
+            series.clone().into_iter().map(Ok),
+            every_other.into_iter().map(Ok),
+            JoinMode::Exact,
+        )
+        .finish()
+        .unwrap();
+        assert_eq!(stats.matched, series.len().div_ceil(2));
+        assert_eq!(stats.left_unmatched, series.len() / 2);
+
+        // Doubling a week's solar lowers what it earns per MWh.
+        let solar = EnergyGenCsvRow::source_idx("Solar").unwrap();
+        let joined = Compute::from_dataset(&prices).with_dataset(&gen);
+        let (values, _) = joined.average_value_5min(&gen).unwrap();
+        let doubled = SourceScaling {
+            source_idx: solar,
+            factor: 2.,
+            elasticity: 1.,
+            window: None,
+        };
+        let (scaled, _) = joined.average_value_scaled(&gen, &doubled).unwrap();
+        assert!(scaled[solar] < values[solar]);
+
+        let err = Compute::intervals_per_window(7).unwrap_err();
+        assert!(matches!(err, EnergyAnalysisError::Invalid(_)), "{err}");
+
+        #[cfg(feature = "graph")]
+        {
+            let svg = Graphing::in_memory()
+                .daily_price_to_buffer(&profile, Profile::Day, ImageFormat::Svg)
+                .unwrap();
+            assert!(String::from_utf8(svg).unwrap().starts_with("<svg"));
+        }
+    }
+}